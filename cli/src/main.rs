@@ -8,8 +8,10 @@ struct Parameters {
     validation_flags: naga::valid::ValidationFlags,
     index_bounds_check_policy: naga::back::IndexBoundsCheckPolicy,
     entry_point: Option<String>,
+    shader_stage: Option<naga::ShaderStage>,
     spv_adjust_coordinate_space: bool,
     spv_flow_dump_prefix: Option<String>,
+    spv_debug_source: bool,
     spv: naga::back::spv::Options,
     msl: naga::back::msl::Options,
     glsl: naga::back::glsl::Options,
@@ -90,7 +92,17 @@ fn main() {
                     };
                 }
                 "flow-dir" => params.spv_flow_dump_prefix = args.next(),
+                "debug-source" => params.spv_debug_source = true,
                 "entry-point" => params.entry_point = Some(args.next().unwrap()),
+                "stage" => {
+                    let value = args.next().unwrap();
+                    params.shader_stage = Some(match value.as_str() {
+                        "vert" => naga::ShaderStage::Vertex,
+                        "frag" => naga::ShaderStage::Fragment,
+                        "comp" => naga::ShaderStage::Compute,
+                        other => panic!("Unknown shader stage: {}", other),
+                    });
+                }
                 "profile" => {
                     use naga::back::glsl::Version;
                     let string = args.next().unwrap();
@@ -131,37 +143,71 @@ fn main() {
             return;
         }
     };
-    let module = match Path::new(input_path)
+    let (module, input_text) = match Path::new(input_path)
         .extension()
         .expect("Input has no extension?")
         .to_str()
         .unwrap()
     {
-        "spv" => {
+        ext @ "spv" | ext @ "spvasm" => {
             let options = naga::front::spv::Options {
                 adjust_coordinate_space: params.spv_adjust_coordinate_space,
                 strict_capabilities: false,
                 flow_graph_dump_prefix: params.spv_flow_dump_prefix.map(std::path::PathBuf::from),
             };
-            let input = fs::read(input_path).unwrap();
-            naga::front::spv::parse_u8_slice(&input, &options).unwrap()
+            let words = if ext == "spvasm" {
+                let input = fs::read_to_string(input_path).unwrap();
+                naga::front::spv::assemble(&input).unwrap_pretty()
+            } else {
+                let input = fs::read(input_path).unwrap();
+                if input.len() % 4 != 0 {
+                    print_err(naga::front::spv::Error::IncompleteData);
+                    std::process::exit(1);
+                }
+                input
+                    .chunks(4)
+                    .map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+                    .collect()
+            };
+            let parser = naga::front::spv::Parser::new(words.into_iter(), &options);
+            let (module, debug_info) = parser.parse_with_debug_info().unwrap_pretty();
+            // Spans point into the source embedded in the module, if any.
+            (module, debug_info.source.map(|source| source.source_code))
         }
         "wgsl" => {
             let input = fs::read_to_string(input_path).unwrap();
             let result = naga::front::wgsl::parse_str(&input);
             match result {
-                Ok(v) => v,
+                Ok(v) => (v, Some(input)),
                 Err(ref e) => {
                     e.emit_to_stderr(&input);
                     panic!("unable to parse WGSL");
                 }
             }
         }
+        "hlsl" => {
+            let input = fs::read_to_string(input_path).unwrap();
+            let mut options = naga::front::hlsl::Options::default();
+            if let Some(stage) = params.shader_stage {
+                let name = match params.entry_point {
+                    Some(ref name) => name.clone(),
+                    None => "main".to_string(),
+                };
+                options.entry_points.insert(name, stage);
+            }
+            match naga::front::hlsl::parse_str(&input, &options) {
+                Ok(v) => (v, Some(input)),
+                Err(ref e) => {
+                    e.emit_to_stderr(&input);
+                    panic!("unable to parse HLSL");
+                }
+            }
+        }
         "vert" => {
             let input = fs::read_to_string(input_path).unwrap();
             let mut entry_points = naga::FastHashMap::default();
             entry_points.insert("main".to_string(), naga::ShaderStage::Vertex);
-            naga::front::glsl::parse_str(
+            let module = naga::front::glsl::parse_str(
                 &input,
                 &naga::front::glsl::Options {
                     entry_points,
@@ -173,13 +219,14 @@ fn main() {
                 let filename = input_path.file_name().and_then(std::ffi::OsStr::to_str);
                 emit_glsl_parser_error(err, filename.unwrap_or("glsl"), &input);
                 std::process::exit(1);
-            })
+            });
+            (module, Some(input))
         }
         "frag" => {
             let input = fs::read_to_string(input_path).unwrap();
             let mut entry_points = naga::FastHashMap::default();
             entry_points.insert("main".to_string(), naga::ShaderStage::Fragment);
-            naga::front::glsl::parse_str(
+            let module = naga::front::glsl::parse_str(
                 &input,
                 &naga::front::glsl::Options {
                     entry_points,
@@ -191,13 +238,14 @@ fn main() {
                 let filename = input_path.file_name().and_then(std::ffi::OsStr::to_str);
                 emit_glsl_parser_error(err, filename.unwrap_or("glsl"), &input);
                 std::process::exit(1);
-            })
+            });
+            (module, Some(input))
         }
         "comp" => {
             let input = fs::read_to_string(input_path).unwrap();
             let mut entry_points = naga::FastHashMap::default();
             entry_points.insert("main".to_string(), naga::ShaderStage::Compute);
-            naga::front::glsl::parse_str(
+            let module = naga::front::glsl::parse_str(
                 &input,
                 &naga::front::glsl::Options {
                     entry_points,
@@ -209,7 +257,8 @@ fn main() {
                 let filename = input_path.file_name().and_then(std::ffi::OsStr::to_str);
                 emit_glsl_parser_error(err, filename.unwrap_or("glsl"), &input);
                 std::process::exit(1);
-            })
+            });
+            (module, Some(input))
         }
         other => panic!("Unknown input extension: {}", other),
     };
@@ -223,7 +272,20 @@ fn main() {
     {
         Ok(info) => Some(info),
         Err(error) => {
-            print_err(error);
+            // Spans of SPIR-V lines without embedded source lie past its end.
+            let in_text = |input: &String| {
+                error.spans().all(|&(span, _)| {
+                    span.to_range()
+                        .map_or(true, |range| range.end <= input.len())
+                })
+            };
+            match input_text {
+                Some(ref input) if in_text(input) => {
+                    let filename = input_path.file_name().and_then(std::ffi::OsStr::to_str);
+                    error.emit_to_stderr_with_path(input, filename.unwrap_or("input"));
+                }
+                _ => print_err(error),
+            }
             None
         }
     };
@@ -257,6 +319,8 @@ fn main() {
             "metal" => {
                 use naga::back::msl;
 
+                params.msl.index_bounds_check_policy = params.index_bounds_check_policy;
+
                 let pipeline_options = msl::PipelineOptions::default();
                 let (msl, _) = msl::write_string(
                     &module,
@@ -267,13 +331,23 @@ fn main() {
                 .unwrap_pretty();
                 fs::write(output_path, msl).unwrap();
             }
-            "spv" => {
+            ext @ "spv" | ext @ "spvasm" => {
                 use naga::back::spv;
 
                 params.spv.index_bounds_check_policy = params.index_bounds_check_policy;
+                if params.spv_debug_source {
+                    params.spv.debug_info = input_text.as_ref().map(|source_code| spv::DebugInfo {
+                        file_name: input_path.display().to_string(),
+                        source_code: source_code.clone(),
+                    });
+                }
 
                 let spv =
                     spv::write_vec(&module, info.as_ref().unwrap(), &params.spv).unwrap_pretty();
+                if ext == "spvasm" {
+                    fs::write(output_path, spv::disassemble(&spv)).unwrap();
+                    continue;
+                }
                 let bytes = spv
                     .iter()
                     .fold(Vec::with_capacity(spv.len() * 4), |mut v, w| {
@@ -286,6 +360,8 @@ fn main() {
             stage @ "vert" | stage @ "frag" | stage @ "comp" => {
                 use naga::back::glsl;
 
+                params.glsl.index_bounds_check_policy = params.index_bounds_check_policy;
+
                 let pipeline_options = glsl::PipelineOptions {
                     entry_point: match params.entry_point {
                         Some(ref name) => name.clone(),
@@ -319,6 +395,9 @@ fn main() {
             }
             "hlsl" => {
                 use naga::back::hlsl;
+
+                params.hlsl.index_bounds_check_policy = params.index_bounds_check_policy;
+
                 let mut buffer = String::new();
                 let mut writer = hlsl::Writer::new(&mut buffer, &params.hlsl);
                 writer
@@ -1,5 +1,7 @@
 use std::{cmp::Ordering, fmt, hash, marker::PhantomData, num::NonZeroU32, ops};
 
+use crate::{Span, SpanContext};
+
 /// An unique index in the arena array that a handle points to.
 /// The "non-zero" part ensures that an `Option<Handle<T>>` has
 /// the same size and representation as `Handle<T>`.
@@ -101,6 +103,16 @@ impl<T> fmt::Debug for Range<T> {
         write!(formatter, "[{}..{}]", self.inner.start + 1, self.inner.end)
     }
 }
+impl<T> Range<T> {
+    /// Creates a range covering the handles from `first` to `last`, inclusive.
+    pub(crate) fn new_from_bounds(first: Handle<T>, last: Handle<T>) -> Self {
+        Range {
+            inner: (first.index() as u32)..(last.index() as u32 + 1),
+            marker: Default::default(),
+        }
+    }
+}
+
 impl<T> Iterator for Range<T> {
     type Item = Handle<T>;
     fn next(&mut self) -> Option<Self::Item> {
@@ -132,6 +144,9 @@ impl<T> Iterator for Range<T> {
 pub struct Arena<T> {
     /// Values of this arena.
     data: Vec<T>,
+    #[cfg(feature = "span")]
+    #[cfg_attr(any(feature = "serialize", feature = "deserialize"), serde(skip))]
+    span_info: Vec<Span>,
 }
 
 impl<T> Default for Arena<T> {
@@ -148,7 +163,11 @@ impl<T: fmt::Debug> fmt::Debug for Arena<T> {
 impl<T> Arena<T> {
     /// Create a new arena with no initial capacity allocated.
     pub fn new() -> Self {
-        Arena { data: Vec::new() }
+        Arena {
+            data: Vec::new(),
+            #[cfg(feature = "span")]
+            span_info: Vec::new(),
+        }
     }
 
     /// Extracts the inner vector.
@@ -187,11 +206,16 @@ impl<T> Arena<T> {
     }
 
     /// Adds a new value to the arena, returning a typed handle.
-    pub fn append(&mut self, value: T) -> Handle<T> {
+    ///
+    /// The `span` is only kept if the "span" feature is enabled.
+    #[allow(unused_variables)]
+    pub fn append(&mut self, value: T, span: Span) -> Handle<T> {
         let position = self.data.len() + 1;
         let index =
             Index::new(position as u32).expect("Failed to append to Arena. Handle overflows");
         self.data.push(value);
+        #[cfg(feature = "span")]
+        self.span_info.push(span);
         Handle::new(index)
     }
 
@@ -207,21 +231,26 @@ impl<T> Arena<T> {
     /// returns a handle pointing to
     /// an existing element if the check succeeds, or adds a new
     /// element otherwise.
-    pub fn fetch_if_or_append<F: Fn(&T, &T) -> bool>(&mut self, value: T, fun: F) -> Handle<T> {
+    pub fn fetch_if_or_append<F: Fn(&T, &T) -> bool>(
+        &mut self,
+        value: T,
+        span: Span,
+        fun: F,
+    ) -> Handle<T> {
         if let Some(index) = self.data.iter().position(|d| fun(d, &value)) {
             let index = unsafe { Index::new_unchecked((index + 1) as u32) };
             Handle::new(index)
         } else {
-            self.append(value)
+            self.append(value, span)
         }
     }
 
     /// Adds a value with a check for uniqueness, where the check is plain comparison.
-    pub fn fetch_or_append(&mut self, value: T) -> Handle<T>
+    pub fn fetch_or_append(&mut self, value: T, span: Span) -> Handle<T>
     where
         T: PartialEq,
     {
-        self.fetch_if_or_append(value, T::eq)
+        self.fetch_if_or_append(value, span, T::eq)
     }
 
     pub fn try_get(&self, handle: Handle<T>) -> Option<&T> {
@@ -240,6 +269,28 @@ impl<T> Arena<T> {
             marker: PhantomData,
         }
     }
+
+    /// Get the source span of an element, if it was recorded.
+    #[allow(unused_variables)]
+    pub fn get_span(&self, handle: Handle<T>) -> Span {
+        #[cfg(feature = "span")]
+        return self
+            .span_info
+            .get(handle.index())
+            .cloned()
+            .unwrap_or_default();
+        #[cfg(not(feature = "span"))]
+        return Span::default();
+    }
+
+    /// Get the source span of an element, labelled with the handle.
+    pub(crate) fn get_span_context(&self, handle: Handle<T>) -> SpanContext {
+        let description = match std::any::type_name::<T>().rsplit("::").next() {
+            Some(type_name) => format!("{} {:?}", type_name, handle),
+            None => format!("{:?}", handle),
+        };
+        (self.get_span(handle), description)
+    }
 }
 
 impl<T> ops::Index<Handle<T>> for Arena<T> {
@@ -263,8 +314,8 @@ mod tests {
     #[test]
     fn append_non_unique() {
         let mut arena: Arena<u8> = Arena::new();
-        let t1 = arena.append(0);
-        let t2 = arena.append(0);
+        let t1 = arena.append(0, Default::default());
+        let t2 = arena.append(0, Default::default());
         assert!(t1 != t2);
         assert!(arena[t1] == arena[t2]);
     }
@@ -272,8 +323,8 @@ mod tests {
     #[test]
     fn append_unique() {
         let mut arena: Arena<u8> = Arena::new();
-        let t1 = arena.append(0);
-        let t2 = arena.append(1);
+        let t1 = arena.append(0, Default::default());
+        let t2 = arena.append(1, Default::default());
         assert!(t1 != t2);
         assert!(arena[t1] != arena[t2]);
     }
@@ -281,8 +332,8 @@ mod tests {
     #[test]
     fn fetch_or_append_non_unique() {
         let mut arena: Arena<u8> = Arena::new();
-        let t1 = arena.fetch_or_append(0);
-        let t2 = arena.fetch_or_append(0);
+        let t1 = arena.fetch_or_append(0, Default::default());
+        let t2 = arena.fetch_or_append(0, Default::default());
         assert!(t1 == t2);
         assert!(arena[t1] == arena[t2])
     }
@@ -290,8 +341,8 @@ mod tests {
     #[test]
     fn fetch_or_append_unique() {
         let mut arena: Arena<u8> = Arena::new();
-        let t1 = arena.fetch_or_append(0);
-        let t2 = arena.fetch_or_append(1);
+        let t1 = arena.fetch_or_append(0, Default::default());
+        let t2 = arena.fetch_or_append(1, Default::default());
         assert!(t1 != t2);
         assert!(arena[t1] != arena[t2]);
     }
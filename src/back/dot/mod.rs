@@ -120,6 +120,53 @@ impl StatementGraph {
                     self.calls.push((id, function));
                     "Call"
                 }
+                S::Atomic {
+                    pointer,
+                    ref fun,
+                    value,
+                    result,
+                } => {
+                    self.emits.push((id, result));
+                    self.dependencies.push((id, pointer, "pointer"));
+                    self.dependencies.push((id, value, "value"));
+                    if let crate::AtomicFunction::Exchange { compare: Some(cmp) } = *fun {
+                        self.dependencies.push((id, cmp, "cmp"));
+                    }
+                    "Atomic"
+                }
+                S::SubgroupBallot { result, predicate } => {
+                    self.emits.push((id, result));
+                    if let Some(predicate) = predicate {
+                        self.dependencies.push((id, predicate, "predicate"));
+                    }
+                    "SubgroupBallot"
+                }
+                S::SubgroupCollectiveOperation {
+                    argument, result, ..
+                } => {
+                    self.emits.push((id, result));
+                    self.dependencies.push((id, argument, "arg"));
+                    "SubgroupCollectiveOperation"
+                }
+                S::SubgroupGather {
+                    ref mode,
+                    argument,
+                    result,
+                } => {
+                    self.emits.push((id, result));
+                    self.dependencies.push((id, argument, "arg"));
+                    match *mode {
+                        crate::GatherMode::BroadcastFirst => {}
+                        crate::GatherMode::Broadcast(index)
+                        | crate::GatherMode::Shuffle(index)
+                        | crate::GatherMode::ShuffleDown(index)
+                        | crate::GatherMode::ShuffleUp(index)
+                        | crate::GatherMode::ShuffleXor(index) => {
+                            self.dependencies.push((id, index, "index"));
+                        }
+                    }
+                    "SubgroupGather"
+                }
             };
         }
         root
@@ -218,6 +265,7 @@ fn write_fun(
                 offset: _,
                 level,
                 depth_ref,
+                gather,
             } => {
                 edges.insert("image", image);
                 edges.insert("sampler", sampler);
@@ -242,7 +290,11 @@ fn write_fun(
                 if let Some(expr) = depth_ref {
                     edges.insert("depth_ref", expr);
                 }
-                ("ImageSample".into(), 5)
+                let string = match gather {
+                    Some(component) => format!("ImageGather{:?}", component).into(),
+                    None => "ImageSample".into(),
+                };
+                (string, 5)
             }
             E::ImageLoad {
                 image,
@@ -305,6 +357,7 @@ fn write_fun(
                 arg,
                 arg1,
                 arg2,
+                arg3,
             } => {
                 edges.insert("arg", arg);
                 if let Some(expr) = arg1 {
@@ -313,6 +366,9 @@ fn write_fun(
                 if let Some(expr) = arg2 {
                     edges.insert("arg2", expr);
                 }
+                if let Some(expr) = arg3 {
+                    edges.insert("arg3", expr);
+                }
                 (format!("{:?}", fun).into(), 7)
             }
             E::As {
@@ -332,6 +388,11 @@ fn write_fun(
                 edges.insert("", expr);
                 ("ArrayLength".into(), 7)
             }
+            E::AtomicResult { kind, width, .. } => {
+                (format!("AtomicResult<{:?},{}>", kind, width).into(), 4)
+            }
+            E::SubgroupBallotResult => ("SubgroupBallotResult".into(), 4),
+            E::SubgroupOperationResult { .. } => ("SubgroupOperationResult".into(), 4),
         };
 
         // give uniform expressions an outline
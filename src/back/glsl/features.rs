@@ -32,6 +32,12 @@ bitflags::bitflags! {
         const CULL_DISTANCE = 1 << 14;
         // Sample ID
         const SAMPLE_VARIABLES = 1 << 15;
+        /// Subgroup builtins and operations
+        const SUBGROUP_OPERATIONS = 1 << 16;
+        /// Runtime sized binding arrays and non-uniform indexing into them
+        const NON_UNIFORM_QUALIFIER = 1 << 17;
+        /// Texture gather functions, including the component and depth reference arguments
+        const TEXTURE_GATHER = 1 << 18;
     }
 }
 
@@ -98,6 +104,9 @@ impl FeaturesManager {
         check_feature!(CLIP_DISTANCE, 130, 300);
         check_feature!(CULL_DISTANCE, 450, 300);
         check_feature!(SAMPLE_VARIABLES, 400, 300);
+        check_feature!(SUBGROUP_OPERATIONS, 430, 310);
+        check_feature!(NON_UNIFORM_QUALIFIER, 450, 320);
+        check_feature!(TEXTURE_GATHER, 400, 310);
 
         // Return an error if there are missing features
         if missing.is_empty() {
@@ -191,6 +200,27 @@ impl FeaturesManager {
             writeln!(out, "#extension GL_OES_sample_variables : require")?;
         }
 
+        if self.0.contains(Features::SUBGROUP_OPERATIONS) {
+            // https://github.com/KhronosGroup/GLSL/blob/master/extensions/khr/GL_KHR_shader_subgroup.txt
+            writeln!(out, "#extension GL_KHR_shader_subgroup_basic : require")?;
+            writeln!(out, "#extension GL_KHR_shader_subgroup_vote : require")?;
+            writeln!(
+                out,
+                "#extension GL_KHR_shader_subgroup_arithmetic : require"
+            )?;
+            writeln!(out, "#extension GL_KHR_shader_subgroup_ballot : require")?;
+            writeln!(out, "#extension GL_KHR_shader_subgroup_shuffle : require")?;
+            writeln!(
+                out,
+                "#extension GL_KHR_shader_subgroup_shuffle_relative : require"
+            )?;
+        }
+
+        if self.0.contains(Features::NON_UNIFORM_QUALIFIER) {
+            // https://github.com/KhronosGroup/GLSL/blob/master/extensions/ext/GL_EXT_nonuniform_qualifier.txt
+            writeln!(out, "#extension GL_EXT_nonuniform_qualifier : require")?;
+        }
+
         Ok(())
     }
 }
@@ -233,6 +263,10 @@ impl<'a, W> Writer<'a, W> {
                         self.features.request(Features::ARRAY_OF_ARRAYS)
                     }
                 }
+                TypeInner::BindingArray {
+                    size: crate::ArraySize::Dynamic,
+                    ..
+                } => self.features.request(Features::NON_UNIFORM_QUALIFIER),
                 TypeInner::Image {
                     dim,
                     arrayed,
@@ -282,6 +316,66 @@ impl<'a, W> Writer<'a, W> {
             }
         }
 
+        let uses_subgroup_operations = self
+            .module
+            .functions
+            .iter()
+            .map(|(_, function)| function)
+            .chain(Some(&self.entry_point.function))
+            .flat_map(|function| function.expressions.iter())
+            .any(|(_, expression)| match *expression {
+                crate::Expression::SubgroupBallotResult
+                | crate::Expression::SubgroupOperationResult { .. } => true,
+                _ => false,
+            });
+        if uses_subgroup_operations {
+            self.features.request(Features::SUBGROUP_OPERATIONS);
+        }
+
+        let uses_texture_gather = self
+            .module
+            .functions
+            .iter()
+            .map(|(_, function)| function)
+            .chain(Some(&self.entry_point.function))
+            .flat_map(|function| function.expressions.iter())
+            .any(|(_, expression)| match *expression {
+                crate::Expression::ImageSample {
+                    gather: Some(_), ..
+                } => true,
+                _ => false,
+            });
+        if uses_texture_gather {
+            self.features.request(Features::TEXTURE_GATHER);
+        }
+
+        let ep_info = self.info.get_entry_point(self.entry_point_idx as usize);
+        let uses_non_uniform_indexing = self
+            .module
+            .functions
+            .iter()
+            .map(|(handle, function)| (function, &self.info[handle]))
+            .chain(Some((&self.entry_point.function, ep_info)))
+            .any(|(function, info)| {
+                function
+                    .expressions
+                    .iter()
+                    .any(|(_, expression)| match *expression {
+                        crate::Expression::Access { base, index } => {
+                            crate::proc::is_non_uniform_binding_array_access(
+                                base,
+                                index,
+                                self.module,
+                                info,
+                            )
+                        }
+                        _ => false,
+                    })
+            });
+        if uses_non_uniform_indexing {
+            self.features.request(Features::NON_UNIFORM_QUALIFIER);
+        }
+
         for (_, global) in self.module.global_variables.iter() {
             match global.class {
                 StorageClass::WorkGroup => self.features.request(Features::COMPUTE_SHADER),
@@ -321,6 +415,9 @@ impl<'a, W> Writer<'a, W> {
                             crate::BuiltIn::SampleIndex => {
                                 self.features.request(Features::SAMPLE_VARIABLES)
                             }
+                            crate::BuiltIn::SubgroupSize | crate::BuiltIn::SubgroupInvocationId => {
+                                self.features.request(Features::SUBGROUP_OPERATIONS)
+                            }
                             _ => {}
                         },
                         Binding::Location {
@@ -44,7 +44,7 @@
 pub use features::Features;
 
 use crate::{
-    back,
+    back::{self, ImageAddress, IndexBoundsCheckPolicy},
     proc::{self, NameKey},
     valid, Handle, ShaderStage, TypeInner,
 };
@@ -154,6 +154,10 @@ pub struct Options {
     pub writer_flags: WriterFlags,
     /// Map of resources association to binding locations.
     pub binding_map: BindingMap,
+    /// How should the generated code handle array, vector, matrix, or image
+    /// indices that are out of range?
+    #[cfg_attr(feature = "deserialize", serde(default))]
+    pub index_bounds_check_policy: IndexBoundsCheckPolicy,
 }
 
 impl Default for Options {
@@ -162,6 +166,7 @@ impl Default for Options {
             version: Version::Embedded(310),
             writer_flags: WriterFlags::ADJUST_COORDINATE_SPACE,
             binding_map: BindingMap::default(),
+            index_bounds_check_policy: IndexBoundsCheckPolicy::default(),
         }
     }
 }
@@ -454,11 +459,12 @@ impl<'a, W: Write> Writer<'a, W> {
         for (handle, ty) in self.module.types.iter() {
             if let TypeInner::Struct { ref members, .. } = ty.inner {
                 // No needed to write a struct that also should be written as a global variable
-                let is_global_struct = self
-                    .module
-                    .global_variables
-                    .iter()
-                    .any(|e| e.1.ty == handle);
+                let is_global_struct = self.module.global_variables.iter().any(|e| {
+                    match self.module.types[e.1.ty].inner {
+                        TypeInner::BindingArray { base, .. } => base == handle,
+                        _ => e.1.ty == handle,
+                    }
+                });
 
                 if !is_global_struct {
                     self.write_struct(false, handle, members)?
@@ -478,7 +484,13 @@ impl<'a, W: Write> Writer<'a, W> {
                 continue;
             }
 
-            match self.module.types[global.ty].inner {
+            // Binding arrays of images are written as arrays of samplers
+            let (ty, array_size) = match self.module.types[global.ty].inner {
+                TypeInner::BindingArray { base, size } => (base, Some(size)),
+                _ => (global.ty, None),
+            };
+
+            match self.module.types[ty].inner {
                 // We treat images separately because they might require
                 // writing the storage format
                 TypeInner::Image {
@@ -487,7 +499,7 @@ impl<'a, W: Write> Writer<'a, W> {
                     class,
                 } => {
                     // Gather the storage format if needed
-                    let layout_storage_format = match self.module.types[global.ty].inner {
+                    let layout_storage_format = match self.module.types[ty].inner {
                         TypeInner::Image {
                             class: crate::ImageClass::Storage(format),
                             ..
@@ -535,7 +547,11 @@ impl<'a, W: Write> Writer<'a, W> {
                     // Finally write the name and end the global with a `;`
                     // The leading space is important
                     let global_name = self.get_global_name(handle, global);
-                    writeln!(self.out, " {};", global_name)?;
+                    write!(self.out, " {}", global_name)?;
+                    if let Some(size) = array_size {
+                        self.write_array_size(size)?;
+                    }
+                    writeln!(self.out, ";")?;
                     writeln!(self.out)?;
 
                     self.reflection_names.insert(global.ty, global_name);
@@ -635,7 +651,9 @@ impl<'a, W: Write> Writer<'a, W> {
     fn write_value_type(&mut self, inner: &TypeInner) -> BackendResult {
         match *inner {
             // Scalars are simple we just get the full name from `glsl_scalar`
+            // Atomics are just scalars in memory, and only the operations on them are special
             TypeInner::Scalar { kind, width }
+            | TypeInner::Atomic { kind, width }
             | TypeInner::ValuePointer {
                 size: None,
                 kind,
@@ -674,7 +692,9 @@ impl<'a, W: Write> Writer<'a, W> {
             // GLSL arrays are written as `type name[size]`
             // Current code is written arrays only as `[size]`
             // Base `type` and `name` should be written outside
-            TypeInner::Array { size, .. } => self.write_array_size(size)?,
+            TypeInner::Array { size, .. } | TypeInner::BindingArray { size, .. } => {
+                self.write_array_size(size)?
+            }
             // Panic if either Image, Sampler, Pointer, or a Struct is being written
             //
             // Write all variants instead of `_` so that if new variants are added a
@@ -701,6 +721,8 @@ impl<'a, W: Write> Writer<'a, W> {
         match self.module.types[ty].inner {
             // glsl has no pointer types so just write types as normal and loads are skipped
             TypeInner::Pointer { base, .. } => self.write_type(base),
+            // binding arrays are written as arrays of their base type after the global name
+            TypeInner::BindingArray { base, .. } => self.write_type(base),
             TypeInner::Struct {
                 top_level: true,
                 ref members,
@@ -808,12 +830,18 @@ impl<'a, W: Write> Writer<'a, W> {
         // Leading space is important
         let global_name = self.get_global_name(handle, global);
         write!(self.out, " {}", global_name)?;
-        if let TypeInner::Array { size, .. } = self.module.types[global.ty].inner {
-            self.write_array_size(size)?;
+        match self.module.types[global.ty].inner {
+            TypeInner::Array { size, .. } | TypeInner::BindingArray { size, .. } => {
+                self.write_array_size(size)?
+            }
+            _ => {}
+        }
+        // Shared variables can't have initializers
+        if global.class != crate::StorageClass::WorkGroup {
+            if let Some(default_value) = zero_init_value_str(&self.module.types[global.ty].inner) {
+                write!(self.out, " = {}", default_value)?;
+            }
         }
-        if let Some(default_value) = zero_init_value_str(&self.module.types[global.ty].inner) {
-            write!(self.out, " = {}", default_value)?;
-        };
         writeln!(self.out, ";")?;
 
         Ok(())
@@ -1379,7 +1407,17 @@ impl<'a, W: Write> Writer<'a, W> {
                 writeln!(self.out, ") {{")?;
 
                 // Write all cases
-                for case in cases {
+                for (i, case) in cases.iter().enumerate() {
+                    // Labels falling through to an empty default do nothing,
+                    // and can't be left dangling at the end of the switch.
+                    if default.is_empty()
+                        && cases[i..]
+                            .iter()
+                            .all(|case| case.fall_through && case.body.is_empty())
+                    {
+                        break;
+                    }
+
                     writeln!(
                         self.out,
                         "{}case {}:",
@@ -1586,12 +1624,25 @@ impl<'a, W: Write> Writer<'a, W> {
                 }
             }
             // Stores in glsl are just variable assignments written as `pointer = value;`
+            //
+            // With the `ReadZeroSkipWrite` policy, the assignment is guarded by
+            // the bounds checks of the pointer's access chain.
             Statement::Store { pointer, value } => {
-                write!(self.out, "{}", INDENT.repeat(indent))?;
+                let checks = self.read_zero_skip_write_checks(pointer, ctx);
+                if !checks.is_empty() {
+                    write!(self.out, "{}if (", INDENT.repeat(indent))?;
+                    self.write_bounds_checks(&checks, ctx)?;
+                    writeln!(self.out, ") {{")?;
+                }
+                let store_indent = indent + !checks.is_empty() as usize;
+                write!(self.out, "{}", INDENT.repeat(store_indent))?;
                 self.write_expr(pointer, ctx)?;
                 write!(self.out, " = ")?;
                 self.write_expr(value, ctx)?;
-                writeln!(self.out, ";")?
+                writeln!(self.out, ";")?;
+                if !checks.is_empty() {
+                    writeln!(self.out, "{}}}", INDENT.repeat(indent))?;
+                }
             }
             // Stores a value into an image.
             Statement::ImageStore {
@@ -1600,20 +1651,42 @@ impl<'a, W: Write> Writer<'a, W> {
                 array_index,
                 value,
             } => {
-                write!(self.out, "{}", INDENT.repeat(indent))?;
                 // This will only panic if the module is invalid
-                let dim = match *ctx.info[image].ty.inner_with(&self.module.types) {
-                    TypeInner::Image { dim, .. } => dim,
+                let (dim, class) = match *ctx.info[image].ty.inner_with(&self.module.types) {
+                    TypeInner::Image { dim, class, .. } => (dim, class),
                     _ => unreachable!(),
                 };
+                let address = ImageAddress {
+                    image,
+                    class,
+                    dim,
+                    coordinate,
+                    array_index,
+                    level: None,
+                    sample: None,
+                };
+
+                let policy = self.options.index_bounds_check_policy;
+                let store_indent = if policy == IndexBoundsCheckPolicy::ReadZeroSkipWrite {
+                    write!(self.out, "{}if (", INDENT.repeat(indent))?;
+                    self.write_image_bounds_checks(&address, ctx)?;
+                    writeln!(self.out, ") {{")?;
+                    indent + 1
+                } else {
+                    indent
+                };
 
-                write!(self.out, "imageStore(")?;
+                write!(self.out, "{}imageStore(", INDENT.repeat(store_indent))?;
                 self.write_expr(image, ctx)?;
                 write!(self.out, ", ")?;
-                self.write_texture_coordinates(coordinate, array_index, dim, ctx)?;
+                self.write_image_address(&address, ctx)?;
                 write!(self.out, ", ")?;
                 self.write_expr(value, ctx)?;
                 writeln!(self.out, ");")?;
+
+                if policy == IndexBoundsCheckPolicy::ReadZeroSkipWrite {
+                    writeln!(self.out, "{}}}", INDENT.repeat(indent))?;
+                }
             }
             // A `Call` is written `name(arguments)` where `arguments` is a comma separated expressions list
             Statement::Call {
@@ -1644,6 +1717,141 @@ impl<'a, W: Write> Writer<'a, W> {
                 self.write_slice(&arguments, |this, _, arg| this.write_expr(*arg, ctx))?;
                 writeln!(self.out, ");")?
             }
+            // Atomic functions are written as `T name = atomicFun(pointer, value);`
+            // Compare-exchange only returns the old value in glsl, so we
+            // compose the result vector from it after the call
+            Statement::Atomic {
+                pointer,
+                ref fun,
+                value,
+                result,
+            } => {
+                write!(self.out, "{}", INDENT.repeat(indent))?;
+                let (kind, width) = match *ctx.info[result].ty.inner_with(&self.module.types) {
+                    TypeInner::Scalar { kind, width } | TypeInner::Vector { kind, width, .. } => {
+                        (kind, width)
+                    }
+                    _ => unreachable!(),
+                };
+                let scalar = glsl_scalar(kind, width)?;
+                let name = format!("_expr{}", result.index());
+                match *fun {
+                    crate::AtomicFunction::Exchange { compare: Some(cmp) } => {
+                        write!(self.out, "{} {}_old = atomicCompSwap(", scalar.full, name)?;
+                        self.write_expr(pointer, ctx)?;
+                        write!(self.out, ", ")?;
+                        self.write_expr(cmp, ctx)?;
+                        write!(self.out, ", ")?;
+                        self.write_expr(value, ctx)?;
+                        writeln!(self.out, ");")?;
+                        write!(
+                            self.out,
+                            "{}{}vec2 {} = {}vec2({}_old, {}({}_old == ",
+                            INDENT.repeat(indent),
+                            scalar.prefix,
+                            name,
+                            scalar.prefix,
+                            name,
+                            scalar.full,
+                            name,
+                        )?;
+                        self.write_expr(cmp, ctx)?;
+                        writeln!(self.out, "));")?;
+                    }
+                    _ => {
+                        write!(
+                            self.out,
+                            "{} {} = {}(",
+                            scalar.full,
+                            name,
+                            glsl_atomic_function(fun)
+                        )?;
+                        self.write_expr(pointer, ctx)?;
+                        write!(self.out, ", ")?;
+                        // glsl has no atomic subtraction, so we add the negation
+                        if let crate::AtomicFunction::Subtract = *fun {
+                            write!(self.out, "-")?;
+                        }
+                        self.write_expr(value, ctx)?;
+                        writeln!(self.out, ");")?;
+                    }
+                }
+                self.named_expressions.insert(result, name);
+            }
+            Statement::SubgroupBallot { result, predicate } => {
+                write!(self.out, "{}", INDENT.repeat(indent))?;
+                let name = format!("_expr{}", result.index());
+                write!(self.out, "uvec4 {} = subgroupBallot(", name)?;
+                match predicate {
+                    Some(predicate) => self.write_expr(predicate, ctx)?,
+                    None => write!(self.out, "true")?,
+                }
+                writeln!(self.out, ");")?;
+                self.named_expressions.insert(result, name);
+            }
+            Statement::SubgroupCollectiveOperation {
+                op,
+                collective_op,
+                argument,
+                result,
+            } => {
+                use crate::{CollectiveOperation as Co, SubgroupOperation as So};
+
+                write!(self.out, "{}", INDENT.repeat(indent))?;
+                let name = format!("_expr{}", result.index());
+                match ctx.info[result].ty {
+                    proc::TypeResolution::Handle(handle) => self.write_type(handle)?,
+                    proc::TypeResolution::Value(ref inner) => self.write_value_type(inner)?,
+                }
+                let prefix = match collective_op {
+                    Co::Reduce => "subgroup",
+                    Co::InclusiveScan => "subgroupInclusive",
+                    Co::ExclusiveScan => "subgroupExclusive",
+                };
+                let fun_name = match op {
+                    So::All => "All",
+                    So::Any => "Any",
+                    So::Add => "Add",
+                    So::Mul => "Mul",
+                    So::Min => "Min",
+                    So::Max => "Max",
+                    So::And => "And",
+                    So::Or => "Or",
+                    So::Xor => "Xor",
+                };
+                write!(self.out, " {} = {}{}(", name, prefix, fun_name)?;
+                self.write_expr(argument, ctx)?;
+                writeln!(self.out, ");")?;
+                self.named_expressions.insert(result, name);
+            }
+            Statement::SubgroupGather {
+                mode,
+                argument,
+                result,
+            } => {
+                write!(self.out, "{}", INDENT.repeat(indent))?;
+                let name = format!("_expr{}", result.index());
+                match ctx.info[result].ty {
+                    proc::TypeResolution::Handle(handle) => self.write_type(handle)?,
+                    proc::TypeResolution::Value(ref inner) => self.write_value_type(inner)?,
+                }
+                let (fun_name, index) = match mode {
+                    crate::GatherMode::BroadcastFirst => ("subgroupBroadcastFirst", None),
+                    crate::GatherMode::Broadcast(index) => ("subgroupBroadcast", Some(index)),
+                    crate::GatherMode::Shuffle(index) => ("subgroupShuffle", Some(index)),
+                    crate::GatherMode::ShuffleDown(index) => ("subgroupShuffleDown", Some(index)),
+                    crate::GatherMode::ShuffleUp(index) => ("subgroupShuffleUp", Some(index)),
+                    crate::GatherMode::ShuffleXor(index) => ("subgroupShuffleXor", Some(index)),
+                };
+                write!(self.out, " {} = {}(", name, fun_name)?;
+                self.write_expr(argument, ctx)?;
+                if let Some(index) = index {
+                    write!(self.out, ", ")?;
+                    self.write_expr(index, ctx)?;
+                }
+                writeln!(self.out, ");")?;
+                self.named_expressions.insert(result, name);
+            }
         }
 
         Ok(())
@@ -1667,11 +1875,43 @@ impl<'a, W: Write> Writer<'a, W> {
 
         match ctx.expressions[expr] {
             // `Access` is applied to arrays, vectors and matrices and is written as indexing
+            //
+            // With the `ReadZeroSkipWrite` policy, indexing a value (as opposed to a pointer,
+            // which is checked by `Load` and `Store`) yields zero when out of bounds.
             Expression::Access { base, index } => {
+                let index = proc::GuardedIndex::Expression(index);
+                let check = match self.options.index_bounds_check_policy {
+                    IndexBoundsCheckPolicy::ReadZeroSkipWrite if !self.is_pointer(base, ctx) => {
+                        proc::access_needs_check(
+                            base,
+                            index,
+                            self.module,
+                            ctx.expressions,
+                            ctx.info,
+                        )
+                        .map(|length| proc::BoundsCheck {
+                            base,
+                            index,
+                            length,
+                        })
+                    }
+                    _ => None,
+                };
+
+                if let Some(check) = check {
+                    write!(self.out, "(")?;
+                    self.write_bounds_checks(&[check], ctx)?;
+                    write!(self.out, " ? ")?;
+                }
                 self.write_expr(base, ctx)?;
                 write!(self.out, "[")?;
-                self.write_expr(index, ctx)?;
-                write!(self.out, "]")?
+                self.write_index(base, index, ctx)?;
+                write!(self.out, "]")?;
+                if check.is_some() {
+                    write!(self.out, " : ")?;
+                    self.write_zero_init_value(&ctx.info[expr].ty)?;
+                    write!(self.out, ")")?;
+                }
             }
             // `AccessIndex` is the same as `Access` except that the index is a constant and it can
             // be applied to structs, in this case we need to find the name of the field at that
@@ -1696,7 +1936,12 @@ impl<'a, W: Write> Writer<'a, W> {
                     }
                     TypeInner::Matrix { .. }
                     | TypeInner::Array { .. }
-                    | TypeInner::ValuePointer { .. } => write!(self.out, "[{}]", index)?,
+                    | TypeInner::BindingArray { .. }
+                    | TypeInner::ValuePointer { .. } => {
+                        write!(self.out, "[")?;
+                        self.write_index(base, proc::GuardedIndex::Known(index), ctx)?;
+                        write!(self.out, "]")?
+                    }
                     TypeInner::Struct { .. } => {
                         // This will never panic in case the type is a `Struct`, this is not true
                         // for other types so we can only check while inside this match arm
@@ -1759,7 +2004,23 @@ impl<'a, W: Write> Writer<'a, W> {
                 write!(self.out, "{}", self.names[&ctx.name_key(handle)])?
             }
             // glsl has no pointers so there's no load operation, just write the pointer expression
-            Expression::Load { pointer } => self.write_expr(pointer, ctx)?,
+            //
+            // With the `ReadZeroSkipWrite` policy, the load yields zero if any index
+            // of the pointer's access chain is out of bounds.
+            Expression::Load { pointer } => {
+                let checks = self.read_zero_skip_write_checks(pointer, ctx);
+                if checks.is_empty() {
+                    self.write_expr(pointer, ctx)?;
+                } else {
+                    write!(self.out, "(")?;
+                    self.write_bounds_checks(&checks, ctx)?;
+                    write!(self.out, " ? ")?;
+                    self.write_expr(pointer, ctx)?;
+                    write!(self.out, " : ")?;
+                    self.write_zero_init_value(&ctx.info[expr].ty)?;
+                    write!(self.out, ")")?;
+                }
+            }
             // `ImageSample` is a bit complicated compared to the rest of the IR.
             //
             // First there are three variations depending wether the sample level is explicitly set,
@@ -1769,6 +2030,58 @@ impl<'a, W: Write> Writer<'a, W> {
             // `textureLod(image, coordinate, level)` - Zero or Exact sample level
             //
             // Furthermore if `depth_ref` is some we need to append it to the coordinate vector
+            //
+            // Gathers are written separately, with `textureGather(image, coordinate[, offset],
+            // component)` where the depth reference replaces the component
+            Expression::ImageSample {
+                image,
+                sampler: _, //TODO?
+                coordinate,
+                array_index,
+                offset,
+                level: _,
+                depth_ref,
+                gather: Some(component),
+            } => {
+                let offset_name = match offset {
+                    Some(_) => "Offset",
+                    None => "",
+                };
+                write!(self.out, "textureGather{}(", offset_name)?;
+                self.write_expr(image, ctx)?;
+                write!(self.out, ", ")?;
+
+                match array_index {
+                    Some(expr) => {
+                        let size = match *ctx.info[coordinate].ty.inner_with(&self.module.types) {
+                            TypeInner::Vector { size, .. } => size as u8,
+                            _ => unreachable!(),
+                        };
+                        write!(self.out, "vec{}(", size + 1)?;
+                        self.write_expr(coordinate, ctx)?;
+                        write!(self.out, ", ")?;
+                        self.write_expr(expr, ctx)?;
+                        write!(self.out, ")")?;
+                    }
+                    None => self.write_expr(coordinate, ctx)?,
+                }
+
+                if let Some(expr) = depth_ref {
+                    write!(self.out, ", ")?;
+                    self.write_expr(expr, ctx)?;
+                }
+
+                if let Some(constant) = offset {
+                    write!(self.out, ", ")?;
+                    self.write_constant(&self.module.constants[constant])?;
+                }
+
+                if depth_ref.is_none() && component != crate::SwizzleComponent::X {
+                    write!(self.out, ", {}", component as u8)?;
+                }
+
+                write!(self.out, ")")?
+            }
             Expression::ImageSample {
                 image,
                 sampler: _, //TODO?
@@ -1777,6 +2090,7 @@ impl<'a, W: Write> Writer<'a, W> {
                 offset,
                 level,
                 depth_ref,
+                gather: None,
             } => {
                 let dim = match *ctx.info[image].ty.inner_with(&self.module.types) {
                     TypeInner::Image { dim, .. } => dim,
@@ -1917,16 +2231,47 @@ impl<'a, W: Write> Writer<'a, W> {
                     crate::ImageClass::Depth => todo!(),
                 };
 
+                let (level, sample) = match class {
+                    crate::ImageClass::Sampled { multi: true, .. } => (None, index),
+                    _ => (index, None),
+                };
+                let address = ImageAddress {
+                    image,
+                    class,
+                    dim,
+                    coordinate,
+                    array_index,
+                    level,
+                    sample,
+                };
+
+                // With the `ReadZeroSkipWrite` policy, out of bounds loads yield zero
+                let policy = self.options.index_bounds_check_policy;
+                if policy == IndexBoundsCheckPolicy::ReadZeroSkipWrite {
+                    write!(self.out, "(")?;
+                    self.write_image_bounds_checks(&address, ctx)?;
+                    write!(self.out, " ? ")?;
+                }
+
                 write!(self.out, "{}(", fun_name)?;
                 self.write_expr(image, ctx)?;
                 write!(self.out, ", ")?;
-                self.write_texture_coordinates(coordinate, array_index, dim, ctx)?;
-
-                if let Some(index_expr) = index {
+                self.write_image_address(&address, ctx)?;
+                if let Some(level) = level {
+                    write!(self.out, ", ")?;
+                    self.write_image_level(image, level, ctx)?;
+                }
+                if let Some(sample) = sample {
                     write!(self.out, ", ")?;
-                    self.write_expr(index_expr, ctx)?;
+                    self.write_image_sample(image, sample, ctx)?;
                 }
                 write!(self.out, ")")?;
+
+                if policy == IndexBoundsCheckPolicy::ReadZeroSkipWrite {
+                    write!(self.out, " : ")?;
+                    self.write_zero_init_value(&ctx.info[expr].ty)?;
+                    write!(self.out, ")")?;
+                }
             }
             // Query translates into one of the:
             // - textureSize/imageSize
@@ -2122,6 +2467,7 @@ impl<'a, W: Write> Writer<'a, W> {
                 arg,
                 arg1,
                 arg2,
+                arg3,
             } => {
                 use crate::MathFunction as Mf;
 
@@ -2183,19 +2529,84 @@ impl<'a, W: Write> Writer<'a, W> {
                     // bits
                     Mf::CountOneBits => "bitCount",
                     Mf::ReverseBits => "bitfieldReverse",
+                    Mf::ExtractBits => "bitfieldExtract",
+                    Mf::InsertBits => "bitfieldInsert",
+                    Mf::FirstTrailingBit => "findLSB",
+                    Mf::FirstLeadingBit => "findMSB",
+                    // data packing
+                    Mf::Pack4x8snorm => "packSnorm4x8",
+                    Mf::Pack4x8unorm => "packUnorm4x8",
+                    Mf::Pack2x16snorm => "packSnorm2x16",
+                    Mf::Pack2x16unorm => "packUnorm2x16",
+                    Mf::Pack2x16float => "packHalf2x16",
+                    // data unpacking
+                    Mf::Unpack4x8snorm => "unpackSnorm4x8",
+                    Mf::Unpack4x8unorm => "unpackUnorm4x8",
+                    Mf::Unpack2x16snorm => "unpackSnorm2x16",
+                    Mf::Unpack2x16unorm => "unpackUnorm2x16",
+                    Mf::Unpack2x16float => "unpackHalf2x16",
+                };
+
+                // `findLSB` and `findMSB` always return signed integers,
+                // so the result has to be converted back for unsigned arguments
+                let convert_result = match fun {
+                    Mf::FirstTrailingBit | Mf::FirstLeadingBit => {
+                        match *ctx.info[arg].ty.inner_with(&self.module.types) {
+                            TypeInner::Scalar {
+                                kind: crate::ScalarKind::Uint,
+                                ..
+                            } => {
+                                write!(self.out, "uint(")?;
+                                true
+                            }
+                            TypeInner::Vector {
+                                kind: crate::ScalarKind::Uint,
+                                size,
+                                ..
+                            } => {
+                                write!(self.out, "uvec{}(", size as u8)?;
+                                true
+                            }
+                            _ => false,
+                        }
+                    }
+                    _ => false,
                 };
 
                 write!(self.out, "{}(", fun_name)?;
                 self.write_expr(arg, ctx)?;
-                if let Some(arg) = arg1 {
-                    write!(self.out, ", ")?;
-                    self.write_expr(arg, ctx)?;
+                match fun {
+                    // the offset and the bit count of bitfield functions are signed in glsl
+                    Mf::ExtractBits | Mf::InsertBits => {
+                        let (insert, offset, count) = match fun {
+                            Mf::InsertBits => (arg1, arg2, arg3),
+                            _ => (None, arg1, arg2),
+                        };
+                        if let Some(insert) = insert {
+                            write!(self.out, ", ")?;
+                            self.write_expr(insert, ctx)?;
+                        }
+                        for &arg in [offset, count].iter() {
+                            write!(self.out, ", int(")?;
+                            self.write_expr(arg.unwrap(), ctx)?;
+                            write!(self.out, ")")?;
+                        }
+                    }
+                    _ => {
+                        if let Some(arg) = arg1 {
+                            write!(self.out, ", ")?;
+                            self.write_expr(arg, ctx)?;
+                        }
+                        if let Some(arg) = arg2 {
+                            write!(self.out, ", ")?;
+                            self.write_expr(arg, ctx)?;
+                        }
+                    }
                 }
-                if let Some(arg) = arg2 {
-                    write!(self.out, ", ")?;
-                    self.write_expr(arg, ctx)?;
+                write!(self.out, ")")?;
+                if convert_result {
+                    write!(self.out, ")")?;
                 }
-                write!(self.out, ")")?
             }
             // `As` is always a call.
             // If `convert` is true the function name is the type
@@ -2248,6 +2659,12 @@ impl<'a, W: Write> Writer<'a, W> {
                 write!(self.out, ")")?
             }
             Expression::Call(_function) => unreachable!(),
+            // `AtomicResult` is always named by the `Atomic` statement
+            Expression::AtomicResult { .. } => unreachable!(),
+            // Subgroup results are always named by the subgroup statements
+            Expression::SubgroupBallotResult | Expression::SubgroupOperationResult { .. } => {
+                unreachable!()
+            }
             // `ArrayLength` is written as `expr.length()` and we convert it to a uint
             Expression::ArrayLength(expr) => {
                 write!(self.out, "uint(")?;
@@ -2289,6 +2706,344 @@ impl<'a, W: Write> Writer<'a, W> {
         Ok(())
     }
 
+    /// Helper method to write the coordinates of an image access, per the bounds check policy
+    ///
+    /// With the `Restrict` policy, the coordinates are clamped to the size of the image.
+    fn write_image_address(
+        &mut self,
+        address: &ImageAddress,
+        ctx: &back::FunctionCtx,
+    ) -> BackendResult {
+        if self.options.index_bounds_check_policy != IndexBoundsCheckPolicy::Restrict {
+            return self.write_texture_coordinates(
+                address.coordinate,
+                address.array_index,
+                address.dim,
+                ctx,
+            );
+        }
+
+        // The image size includes the array layers, so we clamp everything at once
+        write!(self.out, "clamp(")?;
+        self.write_texture_coordinates(address.coordinate, address.array_index, address.dim, ctx)?;
+        match self.image_coordinate_components(address, ctx) {
+            1 => write!(self.out, ", 0, ")?,
+            components => write!(self.out, ", ivec{}(0), ", components)?,
+        }
+        self.write_image_size(address, ctx)?;
+        write!(self.out, " - 1)")?;
+        Ok(())
+    }
+
+    /// Helper method to write the level of detail of an image load, per the bounds check policy
+    fn write_image_level(
+        &mut self,
+        image: Handle<crate::Expression>,
+        level: Handle<crate::Expression>,
+        ctx: &back::FunctionCtx,
+    ) -> BackendResult {
+        if self.options.index_bounds_check_policy == IndexBoundsCheckPolicy::Restrict {
+            write!(self.out, "clamp(")?;
+            self.write_expr(level, ctx)?;
+            write!(self.out, ", 0, textureQueryLevels(")?;
+            self.write_expr(image, ctx)?;
+            write!(self.out, ") - 1)")?;
+        } else {
+            self.write_expr(level, ctx)?;
+        }
+        Ok(())
+    }
+
+    /// Helper method to write the sample index of an image load, per the bounds check policy
+    fn write_image_sample(
+        &mut self,
+        image: Handle<crate::Expression>,
+        sample: Handle<crate::Expression>,
+        ctx: &back::FunctionCtx,
+    ) -> BackendResult {
+        if self.options.index_bounds_check_policy == IndexBoundsCheckPolicy::Restrict {
+            write!(self.out, "clamp(")?;
+            self.write_expr(sample, ctx)?;
+            write!(self.out, ", 0, textureSamples(")?;
+            self.write_expr(image, ctx)?;
+            write!(self.out, ") - 1)")?;
+        } else {
+            self.write_expr(sample, ctx)?;
+        }
+        Ok(())
+    }
+
+    /// Helper method to write the size of the image accessed by `address`
+    ///
+    /// The size has as many components as the texel coordinates, including the array layer.
+    fn write_image_size(
+        &mut self,
+        address: &ImageAddress,
+        ctx: &back::FunctionCtx,
+    ) -> BackendResult {
+        match address.class {
+            crate::ImageClass::Storage(_) => {
+                write!(self.out, "imageSize(")?;
+                self.write_expr(address.image, ctx)?;
+            }
+            crate::ImageClass::Sampled { multi: true, .. } => {
+                write!(self.out, "textureSize(")?;
+                self.write_expr(address.image, ctx)?;
+            }
+            crate::ImageClass::Sampled { .. } | crate::ImageClass::Depth => {
+                write!(self.out, "textureSize(")?;
+                self.write_expr(address.image, ctx)?;
+                write!(self.out, ", ")?;
+                match address.level {
+                    Some(level) => self.write_image_level(address.image, level, ctx)?,
+                    None => write!(self.out, "0")?,
+                }
+            }
+        }
+        write!(self.out, ")")?;
+        Ok(())
+    }
+
+    /// Helper method to write the conditions under which an image access is in bounds
+    fn write_image_bounds_checks(
+        &mut self,
+        address: &ImageAddress,
+        ctx: &back::FunctionCtx,
+    ) -> BackendResult {
+        if let Some(level) = address.level {
+            write!(self.out, "uint(")?;
+            self.write_expr(level, ctx)?;
+            write!(self.out, ") < uint(textureQueryLevels(")?;
+            self.write_expr(address.image, ctx)?;
+            write!(self.out, ")) && ")?;
+        }
+        if let Some(sample) = address.sample {
+            write!(self.out, "uint(")?;
+            self.write_expr(sample, ctx)?;
+            write!(self.out, ") < uint(textureSamples(")?;
+            self.write_expr(address.image, ctx)?;
+            write!(self.out, ")) && ")?;
+        }
+
+        match self.image_coordinate_components(address, ctx) {
+            1 => {
+                write!(self.out, "uint(")?;
+                self.write_texture_coordinates(
+                    address.coordinate,
+                    address.array_index,
+                    address.dim,
+                    ctx,
+                )?;
+                write!(self.out, ") < uint(")?;
+                self.write_image_size(address, ctx)?;
+                write!(self.out, ")")?;
+            }
+            components => {
+                write!(self.out, "all(lessThan(uvec{}(", components)?;
+                self.write_texture_coordinates(
+                    address.coordinate,
+                    address.array_index,
+                    address.dim,
+                    ctx,
+                )?;
+                write!(self.out, "), uvec{}(", components)?;
+                self.write_image_size(address, ctx)?;
+                write!(self.out, ")))")?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the number of components of the texel coordinates, including the array layer
+    fn image_coordinate_components(&self, address: &ImageAddress, ctx: &back::FunctionCtx) -> u8 {
+        let components = match *ctx.info[address.coordinate]
+            .ty
+            .inner_with(&self.module.types)
+        {
+            TypeInner::Vector { size, .. } => size as u8,
+            _ => 1,
+        };
+        components + address.array_index.is_some() as u8
+    }
+
+    /// Returns `true` if the expression is a pointer
+    fn is_pointer(&self, expr: Handle<crate::Expression>, ctx: &back::FunctionCtx) -> bool {
+        match *ctx.info[expr].ty.inner_with(&self.module.types) {
+            TypeInner::Pointer { .. } | TypeInner::ValuePointer { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the indices of the access chain `pointer` that need a bounds check
+    ///
+    /// This is always empty unless the `ReadZeroSkipWrite` policy is used.
+    fn read_zero_skip_write_checks(
+        &self,
+        pointer: Handle<crate::Expression>,
+        ctx: &back::FunctionCtx,
+    ) -> Vec<proc::BoundsCheck> {
+        match self.options.index_bounds_check_policy {
+            IndexBoundsCheckPolicy::ReadZeroSkipWrite => {
+                proc::bounds_check_iter(pointer, self.module, ctx.expressions, ctx.info).collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Helper method to write an index, restricting it to be in bounds when needed
+    ///
+    /// With the `Restrict` policy, indices are written as `min(uint(index), length - 1u)`.
+    fn write_index(
+        &mut self,
+        base: Handle<crate::Expression>,
+        index: proc::GuardedIndex,
+        ctx: &back::FunctionCtx,
+    ) -> BackendResult {
+        if let proc::GuardedIndex::Expression(expr) = index {
+            if proc::is_non_uniform_binding_array_access(base, expr, self.module, ctx.info) {
+                write!(self.out, "nonuniformEXT(")?;
+                self.write_expr(expr, ctx)?;
+                write!(self.out, ")")?;
+                return Ok(());
+            }
+        }
+
+        let length = match self.options.index_bounds_check_policy {
+            IndexBoundsCheckPolicy::Restrict => {
+                proc::access_needs_check(base, index, self.module, ctx.expressions, ctx.info)
+            }
+            _ => None,
+        };
+
+        match (index, length) {
+            (proc::GuardedIndex::Known(value), None) => write!(self.out, "{}", value)?,
+            (proc::GuardedIndex::Expression(expr), None) => self.write_expr(expr, ctx)?,
+            (_, Some(length)) => {
+                write!(self.out, "min(")?;
+                self.write_guarded_index(index, ctx)?;
+                write!(self.out, ", ")?;
+                match length {
+                    proc::IndexableLength::Known(length) => write!(self.out, "{}u", length - 1)?,
+                    _ => {
+                        self.write_index_length(base, length, ctx)?;
+                        write!(self.out, " - 1u")?;
+                    }
+                }
+                write!(self.out, ")")?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Helper method to write the given index as a `uint`
+    fn write_guarded_index(
+        &mut self,
+        index: proc::GuardedIndex,
+        ctx: &back::FunctionCtx,
+    ) -> BackendResult {
+        match index {
+            proc::GuardedIndex::Known(value) => write!(self.out, "{}u", value)?,
+            proc::GuardedIndex::Expression(expr) => {
+                write!(self.out, "uint(")?;
+                self.write_expr(expr, ctx)?;
+                write!(self.out, ")")?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Helper method to write the length of the indexable `base` as a `uint`
+    fn write_index_length(
+        &mut self,
+        base: Handle<crate::Expression>,
+        length: proc::IndexableLength,
+        ctx: &back::FunctionCtx,
+    ) -> BackendResult {
+        match length {
+            proc::IndexableLength::Known(length) => write!(self.out, "{}u", length)?,
+            proc::IndexableLength::Specializable(constant) => {
+                write!(self.out, "uint(")?;
+                self.write_constant(&self.module.constants[constant])?;
+                write!(self.out, ")")?;
+            }
+            proc::IndexableLength::Dynamic => {
+                write!(self.out, "uint(")?;
+                self.write_expr(base, ctx)?;
+                write!(self.out, ".length())")?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Helper method to write the bounds checks of `checks`, joined with `&&`
+    fn write_bounds_checks(
+        &mut self,
+        checks: &[proc::BoundsCheck],
+        ctx: &back::FunctionCtx,
+    ) -> BackendResult {
+        for (i, check) in checks.iter().enumerate() {
+            if i != 0 {
+                write!(self.out, " && ")?;
+            }
+            self.write_guarded_index(check.index, ctx)?;
+            write!(self.out, " < ")?;
+            self.write_index_length(check.base, check.length, ctx)?;
+        }
+        Ok(())
+    }
+
+    /// Helper method to write the zero value of a type, e.g. `vec4(0)`
+    fn write_zero_init_value(&mut self, ty: &proc::TypeResolution) -> BackendResult {
+        match *ty {
+            proc::TypeResolution::Handle(handle) => self.write_zero_init_type(handle),
+            proc::TypeResolution::Value(ref inner) => {
+                self.write_value_type(inner)?;
+                write!(self.out, "(0)")?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Helper method to write the zero value of the type `ty`
+    fn write_zero_init_type(&mut self, ty: Handle<crate::Type>) -> BackendResult {
+        match self.module.types[ty].inner {
+            TypeInner::Struct { ref members, .. } => {
+                write!(self.out, "{}(", &self.names[&NameKey::Type(ty)])?;
+                for (i, member) in members.iter().enumerate() {
+                    if i != 0 {
+                        write!(self.out, ", ")?;
+                    }
+                    self.write_zero_init_type(member.ty)?;
+                }
+                write!(self.out, ")")?;
+            }
+            TypeInner::Array { base, size, .. } => {
+                let count = match size {
+                    crate::ArraySize::Constant(constant) => {
+                        self.module.constants[constant].to_array_length().unwrap()
+                    }
+                    // Runtime-sized arrays can't be loaded
+                    crate::ArraySize::Dynamic => unreachable!(),
+                };
+                self.write_type(base)?;
+                self.write_array_size(size)?;
+                write!(self.out, "(")?;
+                for i in 0..count {
+                    if i != 0 {
+                        write!(self.out, ", ")?;
+                    }
+                    self.write_zero_init_type(base)?;
+                }
+                write!(self.out, ")")?;
+            }
+            ref other => {
+                self.write_value_type(other)?;
+                write!(self.out, "(0)")?;
+            }
+        }
+        Ok(())
+    }
+
     fn write_named_expr(
         &mut self,
         handle: Handle<crate::Expression>,
@@ -2363,10 +3118,14 @@ impl<'a, W: Write> Writer<'a, W> {
             if info[handle].is_empty() {
                 continue;
             }
-            match self.module.types[var.ty].inner {
+            let ty = match self.module.types[var.ty].inner {
+                crate::TypeInner::BindingArray { base, .. } => base,
+                _ => var.ty,
+            };
+            match self.module.types[ty].inner {
                 crate::TypeInner::Struct { .. } => match var.class {
                     crate::StorageClass::Uniform | crate::StorageClass::Storage => {
-                        let name = self.reflection_names[&var.ty].clone();
+                        let name = self.reflection_names[&ty].clone();
                         uniforms.insert(handle, name);
                     }
                     _ => (),
@@ -2398,6 +3157,25 @@ struct ScalarString<'a> {
 ///
 /// # Errors
 /// If a [`Float`](crate::ScalarKind::Float) with an width that isn't 4 or 8
+/// Helper function that returns the glsl function name of an [`AtomicFunction`](crate::AtomicFunction)
+///
+/// # Notes
+/// Subtraction is written as an addition of the negated value, and
+/// compare-exchange is written with `atomicCompSwap` separately
+fn glsl_atomic_function(fun: &crate::AtomicFunction) -> &'static str {
+    use crate::AtomicFunction as Af;
+
+    match *fun {
+        Af::Add | Af::Subtract => "atomicAdd",
+        Af::And => "atomicAnd",
+        Af::InclusiveOr => "atomicOr",
+        Af::ExclusiveOr => "atomicXor",
+        Af::Min => "atomicMin",
+        Af::Max => "atomicMax",
+        Af::Exchange { .. } => "atomicExchange",
+    }
+}
+
 fn glsl_scalar(
     kind: crate::ScalarKind,
     width: crate::Bytes,
@@ -2469,6 +3247,9 @@ fn glsl_built_in(built_in: crate::BuiltIn, output: bool) -> &'static str {
         Bi::LocalInvocationIndex => "gl_LocalInvocationIndex",
         Bi::WorkGroupId => "gl_WorkGroupID",
         Bi::WorkGroupSize => "gl_WorkGroupSize",
+        // subgroup
+        Bi::SubgroupSize => "gl_SubgroupSize",
+        Bi::SubgroupInvocationId => "gl_SubgroupInvocationID",
     }
 }
 
@@ -22,7 +22,10 @@
 // ```
 
 use super::{super::FunctionCtx, writer::BackendResult};
-use crate::arena::Handle;
+use crate::{
+    arena::Handle,
+    back::{ImageAddress, IndexBoundsCheckPolicy},
+};
 use std::fmt::Write;
 
 #[derive(Clone, Copy, Debug, Hash, Eq, Ord, PartialEq, PartialOrd)]
@@ -86,13 +89,6 @@ impl From<crate::ImageQuery> for ImageQuery {
     }
 }
 
-#[derive(Clone, Copy, PartialEq)]
-pub(super) enum MipLevelCoordinate {
-    NotApplicable,
-    Zero,
-    Expression(Handle<crate::Expression>),
-}
-
 impl<'a, W: Write> super::Writer<'a, W> {
     pub(super) fn write_wrapped_image_query_function_name(
         &mut self,
@@ -124,174 +120,428 @@ impl<'a, W: Write> super::Writer<'a, W> {
 
     /// Helper function that write wrapped function for `Expression::ImageQuery`
     /// https://docs.microsoft.com/en-us/windows/win32/direct3dhlsl/dx-graphics-hlsl-to-getdimensions
+    ///
+    /// With a bounds check policy other than `UndefinedBehavior`, this also writes
+    /// the image queries used to check the indices of `ImageLoad` and `ImageStore`.
     pub(super) fn write_wrapped_image_query_functions(
         &mut self,
         module: &crate::Module,
         func_ctx: &FunctionCtx,
     ) -> BackendResult {
-        for (handle, _) in func_ctx.expressions.iter() {
-            if let crate::Expression::ImageQuery { image, query } = func_ctx.expressions[handle] {
-                let image_ty = func_ctx.info[image].ty.inner_with(&module.types);
-                match *image_ty {
-                    crate::TypeInner::Image {
-                        dim,
+        let checked =
+            self.options.index_bounds_check_policy != IndexBoundsCheckPolicy::UndefinedBehavior;
+        for (handle, expression) in func_ctx.expressions.iter() {
+            match *expression {
+                crate::Expression::ImageQuery { image, query } => {
+                    self.write_wrapped_image_query(module, func_ctx, image, query.into())?;
+                }
+                crate::Expression::ImageLoad {
+                    image,
+                    array_index,
+                    index,
+                    ..
+                } if checked => {
+                    let multi = match *func_ctx.info[image].ty.inner_with(&module.types) {
+                        crate::TypeInner::Image {
+                            class: crate::ImageClass::Sampled { multi, .. },
+                            ..
+                        } => multi,
+                        _ => false,
+                    };
+                    let mut queries = Vec::new();
+                    match index {
+                        Some(_) if multi => {
+                            queries.push(ImageQuery::Size);
+                            queries.push(ImageQuery::NumSamples);
+                        }
+                        Some(_) => {
+                            queries.push(ImageQuery::SizeLevel);
+                            queries.push(ImageQuery::NumLevels);
+                        }
+                        None => queries.push(ImageQuery::Size),
+                    }
+                    if array_index.is_some() {
+                        queries.push(ImageQuery::NumLayers);
+                    }
+                    for query in queries {
+                        self.write_wrapped_image_query(module, func_ctx, image, query)?;
+                    }
+                }
+                _ if checked => {
+                    // `ImageStore` is a statement, so check all storage images instead
+                    if let crate::TypeInner::Image {
+                        class: crate::ImageClass::Storage(_),
                         arrayed,
-                        class,
-                    } => {
-                        use crate::back::INDENT;
-
-                        let ret_ty = func_ctx.info[handle].ty.inner_with(&module.types);
-
-                        let wrapped_image_query = WrappedImageQuery {
-                            dim,
-                            arrayed,
-                            class,
-                            query: query.into(),
-                        };
-
-                        if !self.wrapped_image_queries.contains(&wrapped_image_query) {
-                            // Write function return type and name
-                            self.write_value_type(module, ret_ty)?;
-                            write!(self.out, " ")?;
-                            self.write_wrapped_image_query_function_name(wrapped_image_query)?;
-
-                            // Write function parameters
-                            write!(self.out, "(")?;
-                            // Texture always first parameter
-                            self.write_value_type(module, image_ty)?;
-                            // Mipmap is a second parameter if exists
-                            const MIP_LEVEL_PARAM: &str = "MipLevel";
-                            if let crate::ImageQuery::Size { level: Some(_) } = query {
-                                write!(self.out, ", uint {}", MIP_LEVEL_PARAM)?;
-                            }
-                            writeln!(self.out, ")")?;
-
-                            // Write function body
-                            writeln!(self.out, "{{")?;
-                            const RETURN_VARIABLE_NAME: &str = "ret";
-
-                            use crate::ImageDimension as IDim;
-                            use crate::ImageQuery as Iq;
-
-                            let array_coords = if arrayed { 1 } else { 0 };
-                            // GetDimensions Overloaded Methods
-                            // https://docs.microsoft.com/en-us/windows/win32/direct3dhlsl/dx-graphics-hlsl-to-getdimensions#overloaded-methods
-                            let (ret_swizzle, number_of_params) = match query {
-                                Iq::Size { .. } => match dim {
-                                    IDim::D1 => ("x", 1 + array_coords),
-                                    IDim::D2 => ("xy", 3 + array_coords),
-                                    IDim::D3 => ("xyz", 4),
-                                    IDim::Cube => ("xy", 3 + array_coords),
-                                },
-                                Iq::NumLevels | Iq::NumSamples | Iq::NumLayers => {
-                                    if arrayed || dim == IDim::D3 {
-                                        ("w", 4)
-                                    } else {
-                                        ("z", 3)
-                                    }
-                                }
-                            };
-
-                            // Write `GetDimensions` function.
-                            writeln!(self.out, "{}uint4 {};", INDENT, RETURN_VARIABLE_NAME)?;
-                            write!(self.out, "{}", INDENT)?;
-                            self.write_expr(module, image, func_ctx)?;
-                            write!(self.out, ".GetDimensions(")?;
-                            match query {
-                                Iq::Size { level: Some(_) } => {
-                                    write!(self.out, "{}, ", MIP_LEVEL_PARAM)?;
-                                }
-                                _ =>
-                                // Write zero mipmap level for supported types
-                                {
-                                    if let crate::ImageClass::Sampled { multi: true, .. } = class {
-                                    } else {
-                                        match dim {
-                                            IDim::D2 | IDim::D3 | IDim::Cube => {
-                                                write!(self.out, "0, ")?;
-                                            }
-                                            IDim::D1 => {}
-                                        }
-                                    }
-                                }
-                            }
-
-                            for component in crate::back::COMPONENTS[..number_of_params - 1].iter()
-                            {
-                                write!(self.out, "{}.{}, ", RETURN_VARIABLE_NAME, component)?;
-                            }
-
-                            // write last parameter without comma and space for last parameter
-                            write!(
-                                self.out,
-                                "{}.{}",
-                                RETURN_VARIABLE_NAME,
-                                crate::back::COMPONENTS[number_of_params - 1]
+                        ..
+                    } = *func_ctx.info[handle].ty.inner_with(&module.types)
+                    {
+                        self.write_wrapped_image_query(module, func_ctx, handle, ImageQuery::Size)?;
+                        if arrayed {
+                            self.write_wrapped_image_query(
+                                module,
+                                func_ctx,
+                                handle,
+                                ImageQuery::NumLayers,
                             )?;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
 
-                            writeln!(self.out, ");")?;
+        Ok(())
+    }
 
-                            // Write return value
-                            writeln!(
-                                self.out,
-                                "{}return {}.{};",
-                                INDENT, RETURN_VARIABLE_NAME, ret_swizzle
-                            )?;
+    /// Helper function that writes the wrapped function for `query` on `image`,
+    /// unless it was already written.
+    fn write_wrapped_image_query(
+        &mut self,
+        module: &crate::Module,
+        func_ctx: &FunctionCtx,
+        image: Handle<crate::Expression>,
+        query: ImageQuery,
+    ) -> BackendResult {
+        use crate::back::INDENT;
+
+        let image_ty = func_ctx.info[image].ty.inner_with(&module.types);
+        let (dim, arrayed, class) = match *image_ty {
+            crate::TypeInner::Image {
+                dim,
+                arrayed,
+                class,
+            } => (dim, arrayed, class),
+            // Here we work only with image types
+            _ => unreachable!(),
+        };
 
-                            // End of function body
-                            writeln!(self.out, "}}")?;
-                            // Write extra new line
-                            writeln!(self.out)?;
+        let wrapped_image_query = WrappedImageQuery {
+            dim,
+            arrayed,
+            class,
+            query,
+        };
+        if self.wrapped_image_queries.contains(&wrapped_image_query) {
+            return Ok(());
+        }
 
-                            self.wrapped_image_queries.insert(wrapped_image_query);
-                        }
+        use crate::ImageDimension as IDim;
+
+        // Write function return type and name
+        let ret_ty = match query {
+            ImageQuery::Size | ImageQuery::SizeLevel => match dim {
+                IDim::D1 => "int",
+                IDim::D2 | IDim::Cube => "int2",
+                IDim::D3 => "int3",
+            },
+            ImageQuery::NumLevels | ImageQuery::NumLayers | ImageQuery::NumSamples => "int",
+        };
+        write!(self.out, "{} ", ret_ty)?;
+        self.write_wrapped_image_query_function_name(wrapped_image_query)?;
+
+        // Write function parameters
+        write!(self.out, "(")?;
+        // Texture always first parameter
+        const TEXTURE_PARAM: &str = "tex";
+        self.write_value_type(module, image_ty)?;
+        write!(self.out, " {}", TEXTURE_PARAM)?;
+        // Mipmap is a second parameter if exists
+        const MIP_LEVEL_PARAM: &str = "MipLevel";
+        if query == ImageQuery::SizeLevel {
+            write!(self.out, ", uint {}", MIP_LEVEL_PARAM)?;
+        }
+        writeln!(self.out, ")")?;
+
+        // Write function body
+        writeln!(self.out, "{{")?;
+        const RETURN_VARIABLE_NAME: &str = "ret";
+
+        let array_coords = if arrayed { 1 } else { 0 };
+        // GetDimensions Overloaded Methods
+        // https://docs.microsoft.com/en-us/windows/win32/direct3dhlsl/dx-graphics-hlsl-to-getdimensions#overloaded-methods
+        let (ret_swizzle, number_of_params) = match class {
+            // Storage textures have neither mipmaps nor samples
+            crate::ImageClass::Storage(_) => match query {
+                ImageQuery::Size | ImageQuery::SizeLevel => match dim {
+                    IDim::D1 => ("x", 1 + array_coords),
+                    IDim::D2 | IDim::Cube => ("xy", 2 + array_coords),
+                    IDim::D3 => ("xyz", 3),
+                },
+                ImageQuery::NumLevels | ImageQuery::NumSamples | ImageQuery::NumLayers => match dim
+                {
+                    IDim::D1 => ("y", 2),
+                    _ => ("z", 3),
+                },
+            },
+            _ => match query {
+                ImageQuery::Size | ImageQuery::SizeLevel => match dim {
+                    IDim::D1 => ("x", 1 + array_coords),
+                    IDim::D2 => ("xy", 3 + array_coords),
+                    IDim::D3 => ("xyz", 4),
+                    IDim::Cube => ("xy", 3 + array_coords),
+                },
+                ImageQuery::NumLevels | ImageQuery::NumSamples | ImageQuery::NumLayers => {
+                    if arrayed || dim == IDim::D3 {
+                        ("w", 4)
+                    } else {
+                        ("z", 3)
                     }
-                    // Here we work only with image types
-                    _ => unreachable!(),
                 }
+            },
+        };
+
+        // Write `GetDimensions` function.
+        writeln!(self.out, "{}uint4 {};", INDENT, RETURN_VARIABLE_NAME)?;
+        write!(self.out, "{}{}.GetDimensions(", INDENT, TEXTURE_PARAM)?;
+        match query {
+            ImageQuery::SizeLevel => {
+                write!(self.out, "{}, ", MIP_LEVEL_PARAM)?;
             }
+            _ => match class {
+                crate::ImageClass::Sampled { multi: true, .. } | crate::ImageClass::Storage(_) => {}
+                _ => match dim {
+                    // Write zero mipmap level for supported types
+                    IDim::D2 | IDim::D3 | IDim::Cube => {
+                        write!(self.out, "0, ")?;
+                    }
+                    IDim::D1 => {}
+                },
+            },
         }
 
+        for component in crate::back::COMPONENTS[..number_of_params - 1].iter() {
+            write!(self.out, "{}.{}, ", RETURN_VARIABLE_NAME, component)?;
+        }
+
+        // write last parameter without comma and space for last parameter
+        write!(
+            self.out,
+            "{}.{}",
+            RETURN_VARIABLE_NAME,
+            crate::back::COMPONENTS[number_of_params - 1]
+        )?;
+
+        writeln!(self.out, ");")?;
+
+        // Write return value
+        writeln!(
+            self.out,
+            "{}return {}.{};",
+            INDENT, RETURN_VARIABLE_NAME, ret_swizzle
+        )?;
+
+        // End of function body
+        writeln!(self.out, "}}")?;
+        // Write extra new line
+        writeln!(self.out)?;
+
+        self.wrapped_image_queries.insert(wrapped_image_query);
+
         Ok(())
     }
 
-    pub(super) fn write_texture_coordinates(
+    /// Write a call to the wrapped function for `query` on the image of `address`.
+    ///
+    /// The level of `address` is passed to `ImageQuery::SizeLevel` queries.
+    fn write_image_query_call(
         &mut self,
-        kind: &str,
-        coordinate: Handle<crate::Expression>,
-        array_index: Option<Handle<crate::Expression>>,
-        mip_level: MipLevelCoordinate,
         module: &crate::Module,
         func_ctx: &FunctionCtx,
+        address: &ImageAddress,
+        query: ImageQuery,
     ) -> BackendResult {
-        // HLSL expects the array index to be merged with the coordinate
-        let extra = array_index.is_some() as usize
-            + (mip_level != MipLevelCoordinate::NotApplicable) as usize;
-        if extra == 0 {
-            self.write_expr(module, coordinate, func_ctx)?;
+        let arrayed = match *func_ctx.info[address.image].ty.inner_with(&module.types) {
+            crate::TypeInner::Image { arrayed, .. } => arrayed,
+            _ => unreachable!(),
+        };
+        self.write_wrapped_image_query_function_name(WrappedImageQuery {
+            dim: address.dim,
+            arrayed,
+            class: address.class,
+            query,
+        })?;
+        write!(self.out, "(")?;
+        self.write_expr(module, address.image, func_ctx)?;
+        if query == ImageQuery::SizeLevel {
+            write!(self.out, ", ")?;
+            self.write_image_index(
+                module,
+                func_ctx,
+                address,
+                address.level,
+                ImageQuery::NumLevels,
+            )?;
+        }
+        write!(self.out, ")")?;
+        Ok(())
+    }
+
+    /// Write the query giving the size of the image of `address`.
+    fn write_image_size(
+        &mut self,
+        module: &crate::Module,
+        func_ctx: &FunctionCtx,
+        address: &ImageAddress,
+    ) -> BackendResult {
+        let query = match address.level {
+            Some(_) => ImageQuery::SizeLevel,
+            None => ImageQuery::Size,
+        };
+        self.write_image_query_call(module, func_ctx, address, query)
+    }
+
+    /// Write an array, level or sample index of an image access, per the bounds check policy.
+    ///
+    /// With the `Restrict` policy, the index is clamped to the value of the `limit` query.
+    pub(super) fn write_image_index(
+        &mut self,
+        module: &crate::Module,
+        func_ctx: &FunctionCtx,
+        address: &ImageAddress,
+        index: Option<Handle<crate::Expression>>,
+        limit: ImageQuery,
+    ) -> BackendResult {
+        let index = match index {
+            Some(index) => index,
+            None => {
+                write!(self.out, "0")?;
+                return Ok(());
+            }
+        };
+        if self.options.index_bounds_check_policy == IndexBoundsCheckPolicy::Restrict {
+            write!(self.out, "clamp(")?;
+            self.write_expr(module, index, func_ctx)?;
+            write!(self.out, ", 0, ")?;
+            self.write_image_query_call(module, func_ctx, address, limit)?;
+            write!(self.out, " - 1)")?;
         } else {
-            let num_coords = match *func_ctx.info[coordinate].ty.inner_with(&module.types) {
+            self.write_expr(module, index, func_ctx)?;
+        }
+        Ok(())
+    }
+
+    /// Write the texel address of an image load or store, per the bounds check policy.
+    ///
+    /// The array index and the mipmap level (if `mip_level` is set) are merged
+    /// with the coordinates, as HLSL expects. With the `Restrict` policy, all of
+    /// them are clamped to the size of the image.
+    pub(super) fn write_image_address(
+        &mut self,
+        module: &crate::Module,
+        func_ctx: &FunctionCtx,
+        address: &ImageAddress,
+        mip_level: bool,
+    ) -> BackendResult {
+        let extra = address.array_index.is_some() as usize + mip_level as usize;
+        if extra != 0 {
+            let num_coords = match *func_ctx.info[address.coordinate]
+                .ty
+                .inner_with(&module.types)
+            {
                 crate::TypeInner::Scalar { .. } => 1,
                 crate::TypeInner::Vector { size, .. } => size as usize,
                 _ => unreachable!(),
             };
-            write!(self.out, "{}{}(", kind, num_coords + extra)?;
-            self.write_expr(module, coordinate, func_ctx)?;
-            if let Some(expr) = array_index {
+            write!(self.out, "int{}(", num_coords + extra)?;
+        }
+
+        if self.options.index_bounds_check_policy == IndexBoundsCheckPolicy::Restrict {
+            write!(self.out, "clamp(")?;
+            self.write_expr(module, address.coordinate, func_ctx)?;
+            write!(self.out, ", 0, ")?;
+            self.write_image_size(module, func_ctx, address)?;
+            write!(self.out, " - 1)")?;
+        } else {
+            self.write_expr(module, address.coordinate, func_ctx)?;
+        }
+        if address.array_index.is_some() {
+            write!(self.out, ", ")?;
+            self.write_image_index(
+                module,
+                func_ctx,
+                address,
+                address.array_index,
+                ImageQuery::NumLayers,
+            )?;
+        }
+        if mip_level {
+            write!(self.out, ", ")?;
+            self.write_image_index(
+                module,
+                func_ctx,
+                address,
+                address.level,
+                ImageQuery::NumLevels,
+            )?;
+        }
+
+        if extra != 0 {
+            write!(self.out, ")")?;
+        }
+        Ok(())
+    }
+
+    /// Write the conditions under which an image access is in bounds.
+    pub(super) fn write_image_bounds_checks(
+        &mut self,
+        module: &crate::Module,
+        func_ctx: &FunctionCtx,
+        address: &ImageAddress,
+    ) -> BackendResult {
+        let limits = [
+            (address.level, ImageQuery::NumLevels),
+            (address.sample, ImageQuery::NumSamples),
+            (address.array_index, ImageQuery::NumLayers),
+        ];
+        for &(index, limit) in limits.iter() {
+            if let Some(index) = index {
+                write!(self.out, "uint(")?;
+                self.write_expr(module, index, func_ctx)?;
+                write!(self.out, ") < uint(")?;
+                self.write_image_query_call(module, func_ctx, address, limit)?;
+                write!(self.out, ") && ")?;
+            }
+        }
+
+        let size = match *func_ctx.info[address.coordinate]
+            .ty
+            .inner_with(&module.types)
+        {
+            crate::TypeInner::Scalar { .. } => "",
+            crate::TypeInner::Vector { size, .. } => crate::back::vector_size_str(size),
+            _ => unreachable!(),
+        };
+        write!(self.out, "all(uint{}(", size)?;
+        self.write_expr(module, address.coordinate, func_ctx)?;
+        write!(self.out, ") < uint{}(", size)?;
+        self.write_image_size(module, func_ctx, address)?;
+        write!(self.out, "))")?;
+        Ok(())
+    }
+
+    pub(super) fn write_texture_coordinates(
+        &mut self,
+        kind: &str,
+        coordinate: Handle<crate::Expression>,
+        array_index: Option<Handle<crate::Expression>>,
+        module: &crate::Module,
+        func_ctx: &FunctionCtx,
+    ) -> BackendResult {
+        // HLSL expects the array index to be merged with the coordinate
+        match array_index {
+            None => self.write_expr(module, coordinate, func_ctx)?,
+            Some(expr) => {
+                let num_coords = match *func_ctx.info[coordinate].ty.inner_with(&module.types) {
+                    crate::TypeInner::Scalar { .. } => 1,
+                    crate::TypeInner::Vector { size, .. } => size as usize,
+                    _ => unreachable!(),
+                };
+                write!(self.out, "{}{}(", kind, num_coords + 1)?;
+                self.write_expr(module, coordinate, func_ctx)?;
                 write!(self.out, ", ")?;
                 self.write_expr(module, expr, func_ctx)?;
+                write!(self.out, ")")?;
             }
-            match mip_level {
-                MipLevelCoordinate::NotApplicable => {}
-                MipLevelCoordinate::Zero => {
-                    write!(self.out, ", 0")?;
-                }
-                MipLevelCoordinate::Expression(expr) => {
-                    write!(self.out, ", ")?;
-                    self.write_expr(module, expr, func_ctx)?;
-                }
-            }
-            write!(self.out, ")")?;
         }
         Ok(())
     }
@@ -0,0 +1,396 @@
+// Important note about `Expression::Math`, `Expression::Relational` and hlsl backend:
+// HLSL doesn't have intrinsics for some of the IR functions, like the outer product,
+// the inverse of a matrix, `isnormal`, bitfield extraction or data packing.
+// Instead, backend generates a unique wrapped function per function and argument type.
+// See `WrappedMath` struct that represents a unique function and will be generated before writing all statements and expressions.
+//
+// For example:
+// ```wgsl
+// let m = outerProduct(a, b);
+// ```
+//
+// ```hlsl
+// float2x3 NagaOuter2x3(float3 a, float2 b)
+// {
+//     return float2x3(a * b.x, a * b.y);
+// }
+//
+// float2x3 m = NagaOuter2x3(a, b);
+// ```
+//
+// Note that the columns of IR matrices are written as the rows of HLSL matrices.
+// The matrix inverse doesn't care, since it commutes with the transposition.
+
+use super::{super::FunctionCtx, writer::BackendResult};
+use crate::back::{self, INDENT};
+use std::fmt::Write;
+
+#[derive(Clone, Copy, Debug, Hash, Eq, Ord, PartialEq, PartialOrd)]
+pub(super) enum WrappedMath {
+    Outer {
+        columns: crate::VectorSize,
+        rows: crate::VectorSize,
+        width: crate::Bytes,
+    },
+    Inverse {
+        size: crate::VectorSize,
+        width: crate::Bytes,
+    },
+    IsNormal {
+        size: Option<crate::VectorSize>,
+        width: crate::Bytes,
+    },
+    ExtractBits {
+        size: Option<crate::VectorSize>,
+        kind: crate::ScalarKind,
+    },
+    InsertBits {
+        size: Option<crate::VectorSize>,
+        kind: crate::ScalarKind,
+    },
+    /// One of the data packing or unpacking functions.
+    Pack(crate::MathFunction),
+}
+
+impl WrappedMath {
+    /// Returns the wrapped function needed by `expression`, if any.
+    pub(super) fn new(
+        module: &crate::Module,
+        func_ctx: &FunctionCtx,
+        expression: &crate::Expression,
+    ) -> Option<Self> {
+        use crate::TypeInner as Ti;
+
+        match *expression {
+            crate::Expression::Math {
+                fun: crate::MathFunction::Outer,
+                arg,
+                arg1: Some(arg1),
+                ..
+            } => match (
+                func_ctx.info[arg].ty.inner_with(&module.types),
+                func_ctx.info[arg1].ty.inner_with(&module.types),
+            ) {
+                (
+                    &Ti::Vector {
+                        size: rows, width, ..
+                    },
+                    &Ti::Vector { size: columns, .. },
+                ) => Some(WrappedMath::Outer {
+                    columns,
+                    rows,
+                    width,
+                }),
+                _ => None,
+            },
+            crate::Expression::Math {
+                fun: crate::MathFunction::Inverse,
+                arg,
+                ..
+            } => match *func_ctx.info[arg].ty.inner_with(&module.types) {
+                Ti::Matrix { columns, width, .. } => Some(WrappedMath::Inverse {
+                    size: columns,
+                    width,
+                }),
+                _ => None,
+            },
+            crate::Expression::Relational {
+                fun: crate::RelationalFunction::IsNormal,
+                argument,
+            } => match *func_ctx.info[argument].ty.inner_with(&module.types) {
+                Ti::Scalar { width, .. } => Some(WrappedMath::IsNormal { size: None, width }),
+                Ti::Vector { size, width, .. } => Some(WrappedMath::IsNormal {
+                    size: Some(size),
+                    width,
+                }),
+                _ => None,
+            },
+            crate::Expression::Math {
+                fun: fun @ crate::MathFunction::ExtractBits,
+                arg,
+                ..
+            }
+            | crate::Expression::Math {
+                fun: fun @ crate::MathFunction::InsertBits,
+                arg,
+                ..
+            } => {
+                let (size, kind) = match *func_ctx.info[arg].ty.inner_with(&module.types) {
+                    Ti::Scalar { kind, .. } => (None, kind),
+                    Ti::Vector { size, kind, .. } => (Some(size), kind),
+                    _ => return None,
+                };
+                Some(match fun {
+                    crate::MathFunction::ExtractBits => WrappedMath::ExtractBits { size, kind },
+                    _ => WrappedMath::InsertBits { size, kind },
+                })
+            }
+            crate::Expression::Math { fun, .. } => match fun {
+                crate::MathFunction::Pack4x8snorm
+                | crate::MathFunction::Pack4x8unorm
+                | crate::MathFunction::Pack2x16snorm
+                | crate::MathFunction::Pack2x16unorm
+                | crate::MathFunction::Pack2x16float
+                | crate::MathFunction::Unpack4x8snorm
+                | crate::MathFunction::Unpack4x8unorm
+                | crate::MathFunction::Unpack2x16snorm
+                | crate::MathFunction::Unpack2x16unorm
+                | crate::MathFunction::Unpack2x16float => Some(WrappedMath::Pack(fun)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+impl<'a, W: Write> super::Writer<'a, W> {
+    pub(super) fn write_wrapped_math_function_name(&mut self, math: WrappedMath) -> BackendResult {
+        let width = match math {
+            WrappedMath::Outer {
+                columns,
+                rows,
+                width,
+            } => {
+                write!(
+                    self.out,
+                    "NagaOuter{}x{}",
+                    back::vector_size_str(columns),
+                    back::vector_size_str(rows)
+                )?;
+                width
+            }
+            WrappedMath::Inverse { size, width } => {
+                let size = back::vector_size_str(size);
+                write!(self.out, "NagaInverse{}x{}", size, size)?;
+                width
+            }
+            WrappedMath::IsNormal { size, width } => {
+                write!(self.out, "NagaIsNormal")?;
+                if let Some(size) = size {
+                    write!(self.out, "{}", back::vector_size_str(size))?;
+                }
+                width
+            }
+            WrappedMath::ExtractBits { size, kind } | WrappedMath::InsertBits { size, kind } => {
+                let name = match math {
+                    WrappedMath::ExtractBits { .. } => "ExtractBits",
+                    _ => "InsertBits",
+                };
+                let kind = match kind {
+                    crate::ScalarKind::Sint => "Int",
+                    _ => "Uint",
+                };
+                write!(self.out, "Naga{}{}", name, kind)?;
+                if let Some(size) = size {
+                    write!(self.out, "{}", back::vector_size_str(size))?;
+                }
+                4
+            }
+            WrappedMath::Pack(fun) => {
+                write!(self.out, "Naga{:?}", fun)?;
+                4
+            }
+        };
+        if width == 8 {
+            write!(self.out, "Double")?;
+        }
+
+        Ok(())
+    }
+
+    /// Helper function that write wrapped functions for `Expression::Math`
+    /// and `Expression::Relational` without HLSL intrinsics.
+    pub(super) fn write_wrapped_math_functions(
+        &mut self,
+        module: &crate::Module,
+        func_ctx: &FunctionCtx,
+    ) -> BackendResult {
+        for (_, expression) in func_ctx.expressions.iter() {
+            if let Some(math) = WrappedMath::new(module, func_ctx, expression) {
+                if self.wrapped_math.insert(math) {
+                    self.write_wrapped_math(math)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_wrapped_math(&mut self, math: WrappedMath) -> BackendResult {
+        match math {
+            WrappedMath::Outer {
+                columns,
+                rows,
+                width,
+            } => {
+                let scalar = super::writer::scalar_kind_str(crate::ScalarKind::Float, width)?;
+                let columns = columns as usize;
+                let rows = rows as usize;
+                write!(self.out, "{}{}x{} ", scalar, columns, rows)?;
+                self.write_wrapped_math_function_name(math)?;
+                writeln!(self.out, "({}{} a, {}{} b)", scalar, rows, scalar, columns)?;
+                writeln!(self.out, "{{")?;
+                // column `j` is `a * b[j]`
+                write!(self.out, "{}return {}{}x{}(", INDENT, scalar, columns, rows)?;
+                for (index, component) in back::COMPONENTS[..columns].iter().enumerate() {
+                    if index != 0 {
+                        write!(self.out, ", ")?;
+                    }
+                    write!(self.out, "a * b.{}", component)?;
+                }
+                writeln!(self.out, ");")?;
+            }
+            WrappedMath::Inverse { size, width } => {
+                let scalar = super::writer::scalar_kind_str(crate::ScalarKind::Float, width)?;
+                let size = size as usize;
+                write!(self.out, "{}{}x{} ", scalar, size, size)?;
+                self.write_wrapped_math_function_name(math)?;
+                writeln!(self.out, "({}{}x{} m)", scalar, size, size)?;
+                writeln!(self.out, "{{")?;
+                match size {
+                    2 => {
+                        writeln!(
+                            self.out,
+                            "{}return {}2x2(m[1][1], -m[0][1], -m[1][0], m[0][0]) / determinant(m);",
+                            INDENT, scalar
+                        )?;
+                    }
+                    3 => {
+                        writeln!(
+                            self.out,
+                            "{}{}3x3 adj = transpose({}3x3(cross(m[1], m[2]), cross(m[2], m[0]), cross(m[0], m[1])));",
+                            INDENT, scalar, scalar
+                        )?;
+                        writeln!(
+                            self.out,
+                            "{}return adj / dot(m[0], cross(m[1], m[2]));",
+                            INDENT
+                        )?;
+                    }
+                    _ => {
+                        let matrix = format!("{}4x4", scalar);
+                        back::write_inverse_4x4(&mut self.out, scalar, &matrix)?;
+                    }
+                }
+            }
+            WrappedMath::IsNormal { size, width } => {
+                let scalar = super::writer::scalar_kind_str(crate::ScalarKind::Float, width)?;
+                let size = size.map_or("", back::vector_size_str);
+                // The smallest positive normal value
+                let min_normal = if width == 8 {
+                    "2.2250738585072014e-308L"
+                } else {
+                    "1.17549435e-38"
+                };
+                write!(self.out, "bool{} ", size)?;
+                self.write_wrapped_math_function_name(math)?;
+                writeln!(self.out, "({}{} x)", scalar, size)?;
+                writeln!(self.out, "{{")?;
+                writeln!(
+                    self.out,
+                    "{}return isfinite(x) && abs(x) >= {};",
+                    INDENT, min_normal
+                )?;
+            }
+            WrappedMath::ExtractBits { size, kind } | WrappedMath::InsertBits { size, kind } => {
+                // Offset and count are clamped to the width of the integer,
+                // and the shifts are arithmetic for signed integers.
+                let ty = format!(
+                    "{}{}",
+                    super::writer::scalar_kind_str(kind, 4)?,
+                    size.map_or("", back::vector_size_str)
+                );
+                write!(self.out, "{} ", ty)?;
+                self.write_wrapped_math_function_name(math)?;
+                let insert = match math {
+                    WrappedMath::InsertBits { .. } => true,
+                    _ => false,
+                };
+                if insert {
+                    writeln!(
+                        self.out,
+                        "({} e, {} newbits, uint offset, uint count)",
+                        ty, ty
+                    )?;
+                } else {
+                    writeln!(self.out, "({} e, uint offset, uint count)", ty)?;
+                }
+                writeln!(self.out, "{{")?;
+                writeln!(self.out, "{}uint o = min(offset, 32u);", INDENT)?;
+                writeln!(self.out, "{}uint c = min(count, 32u - o);", INDENT)?;
+                if insert {
+                    writeln!(
+                        self.out,
+                        "{}uint mask = (0xFFFFFFFFu >> (32u - c)) << o;",
+                        INDENT
+                    )?;
+                    writeln!(
+                        self.out,
+                        "{}return c == 0u ? e : ((e & ~mask) | ((newbits << o) & mask));",
+                        INDENT
+                    )?;
+                } else {
+                    writeln!(
+                        self.out,
+                        "{}return c == 0u ? 0 : ((e << (32u - c - o)) >> (32u - c));",
+                        INDENT
+                    )?;
+                }
+            }
+            WrappedMath::Pack(fun) => {
+                use crate::MathFunction as Mf;
+
+                let (ret, arg) = match fun {
+                    Mf::Pack4x8snorm | Mf::Pack4x8unorm => ("uint", "float4"),
+                    Mf::Pack2x16snorm | Mf::Pack2x16unorm | Mf::Pack2x16float => ("uint", "float2"),
+                    Mf::Unpack4x8snorm | Mf::Unpack4x8unorm => ("float4", "uint"),
+                    _ => ("float2", "uint"),
+                };
+                write!(self.out, "{} ", ret)?;
+                self.write_wrapped_math_function_name(math)?;
+                writeln!(self.out, "({} x)", arg)?;
+                writeln!(self.out, "{{")?;
+                let lines: &[&str] = match fun {
+                    Mf::Pack4x8snorm => &[
+                        "uint4 v = uint4(int4(round(clamp(x, -1.0, 1.0) * 127.0)) & 0xFF);",
+                        "return v.x | (v.y << 8) | (v.z << 16) | (v.w << 24);",
+                    ],
+                    Mf::Pack4x8unorm => &[
+                        "uint4 v = uint4(round(saturate(x) * 255.0));",
+                        "return v.x | (v.y << 8) | (v.z << 16) | (v.w << 24);",
+                    ],
+                    Mf::Pack2x16snorm => &[
+                        "uint2 v = uint2(int2(round(clamp(x, -1.0, 1.0) * 32767.0)) & 0xFFFF);",
+                        "return v.x | (v.y << 16);",
+                    ],
+                    Mf::Pack2x16unorm => &[
+                        "uint2 v = uint2(round(saturate(x) * 65535.0));",
+                        "return v.x | (v.y << 16);",
+                    ],
+                    Mf::Pack2x16float => &["return f32tof16(x.x) | (f32tof16(x.y) << 16);"],
+                    Mf::Unpack4x8snorm => &[
+                        "int4 v = int4(x << 24, x << 16, x << 8, x) >> 24;",
+                        "return clamp(float4(v) / 127.0, -1.0, 1.0);",
+                    ],
+                    Mf::Unpack4x8unorm => &[
+                        "return float4(x & 0xFF, (x >> 8) & 0xFF, (x >> 16) & 0xFF, x >> 24) / 255.0;",
+                    ],
+                    Mf::Unpack2x16snorm => &[
+                        "int2 v = int2(x << 16, x) >> 16;",
+                        "return clamp(float2(v) / 32767.0, -1.0, 1.0);",
+                    ],
+                    Mf::Unpack2x16unorm => &["return float2(x & 0xFFFF, x >> 16) / 65535.0;"],
+                    _ => &["return float2(f16tof32(x), f16tof32(x >> 16));"],
+                };
+                for line in lines {
+                    writeln!(self.out, "{}{}", INDENT, line)?;
+                }
+            }
+        }
+        writeln!(self.out, "}}")?;
+        // End of function body
+        writeln!(self.out)?;
+
+        Ok(())
+    }
+}
@@ -5,9 +5,14 @@
 //! - 5.1
 //! - 6.0
 //!
+//! # Binding arrays
+//! Binding arrays of textures and samplers are written as resource arrays,
+//! binding arrays of buffers aren't supported.
+//!
 
 mod image;
 mod keywords;
+mod math;
 mod writer;
 
 use std::fmt::Error as FmtError;
@@ -74,6 +79,8 @@ impl crate::ImageDimension {
 pub enum EntryPointError {
     #[error("mapping of {0:?} is missing")]
     MissingBinding(crate::ResourceBinding),
+    #[error("target of the push constants is missing")]
+    MissingPushConstantsTarget,
 }
 
 /// Structure that contains the configuration used in the [`Writer`](Writer)
@@ -87,6 +94,14 @@ pub struct Options {
     pub binding_map: BindingMap,
     /// Don't panic on missing bindings, instead generate any HLSL.
     pub fake_missing_bindings: bool,
+    /// How should the generated code handle array, vector, matrix, or image indices
+    /// that are out of range?
+    #[cfg_attr(feature = "deserialize", serde(default))]
+    pub index_bounds_check_policy: crate::back::IndexBoundsCheckPolicy,
+    /// Register and space of the constant buffer holding the push constants,
+    /// which are meant to be root constants in D3D12.
+    #[cfg_attr(feature = "deserialize", serde(default))]
+    pub push_constants_target: Option<BindTarget>,
 }
 
 impl Default for Options {
@@ -95,6 +110,8 @@ impl Default for Options {
             shader_model: ShaderModel::V5_0,
             binding_map: BindingMap::default(),
             fake_missing_bindings: true,
+            index_bounds_check_policy: crate::back::IndexBoundsCheckPolicy::default(),
+            push_constants_target: None,
         }
     }
 }
@@ -113,6 +130,37 @@ impl Options {
             None => Err(EntryPointError::MissingBinding(res_binding.clone())),
         }
     }
+
+    fn resolve_push_constants_target(&self) -> Result<BindTarget, EntryPointError> {
+        match self.push_constants_target {
+            Some(ref target) => Ok(target.clone()),
+            None if self.fake_missing_bindings => Ok(BindTarget::default()),
+            None => Err(EntryPointError::MissingPushConstantsTarget),
+        }
+    }
+
+    /// Resolve the register of a global variable, if it has one.
+    fn resolve_global(
+        &self,
+        global: &crate::GlobalVariable,
+    ) -> Option<Result<BindTarget, EntryPointError>> {
+        match global.class {
+            crate::StorageClass::PushConstant => Some(self.resolve_push_constants_target()),
+            _ => global
+                .binding
+                .as_ref()
+                .map(|binding| self.resolve_resource_binding(binding)),
+        }
+    }
+}
+
+/// Root constants holding the push constants of a module.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RootConstants {
+    /// Register and space of the constant buffer.
+    pub target: BindTarget,
+    /// Number of 32-bit values in the constant buffer.
+    pub num_32bit_values: u32,
 }
 
 /// Structure that contains a reflection info
@@ -124,6 +172,8 @@ pub struct ReflectionInfo {
     ///
     ///Note: Some entry points may fail translation because of missing bindings.
     pub entry_point_names: Vec<Result<String, EntryPointError>>,
+    /// Root constants used for the push constants, if any.
+    pub push_constants: Option<RootConstants>,
 }
 
 #[derive(Error, Debug)]
@@ -132,6 +182,8 @@ pub enum Error {
     IoError(#[from] FmtError),
     #[error("A scalar with an unsupported width was requested: {0:?} {1:?}")]
     UnsupportedScalar(crate::ScalarKind, crate::Bytes),
+    #[error("push constants at {0:?} collide with a constant buffer")]
+    PushConstantsCollision(BindTarget),
     #[error("{0}")]
     Unimplemented(String), // TODO: Error used only during development
     #[error("{0}")]
@@ -1,9 +1,10 @@
 use super::{
-    image::{MipLevelCoordinate, WrappedImageQuery},
+    image::{self, WrappedImageQuery},
+    math::WrappedMath,
     Error, Options,
 };
 use crate::{
-    back,
+    back::{self, ImageAddress, IndexBoundsCheckPolicy},
     proc::{self, NameKey},
     valid, Handle, Module, ShaderStage, TypeInner,
 };
@@ -34,12 +35,17 @@ pub struct Writer<'a, W> {
     names: crate::FastHashMap<NameKey, String>,
     namer: proc::Namer,
     /// HLSL backend options
-    options: &'a Options,
+    pub(super) options: &'a Options,
     /// Information about entry point arguments wrapped into structure
     ep_inputs: Vec<Option<EntryPointBinding>>,
     /// Set of expressions that have associated temporary variables
     named_expressions: crate::NamedExpressions,
     pub(super) wrapped_image_queries: crate::FastHashSet<WrappedImageQuery>,
+    pub(super) wrapped_math: crate::FastHashSet<WrappedMath>,
+    /// Storage buffers with a runtime-sized array whose length helper is written
+    wrapped_array_lengths: crate::FastHashSet<Handle<crate::GlobalVariable>>,
+    /// Root constants of the written push constants
+    push_constants: Option<super::RootConstants>,
 }
 
 impl<'a, W: Write> Writer<'a, W> {
@@ -52,6 +58,9 @@ impl<'a, W: Write> Writer<'a, W> {
             ep_inputs: Vec::new(),
             named_expressions: crate::NamedExpressions::default(),
             wrapped_image_queries: crate::FastHashSet::default(),
+            wrapped_math: crate::FastHashSet::default(),
+            wrapped_array_lengths: crate::FastHashSet::default(),
+            push_constants: None,
         }
     }
 
@@ -62,6 +71,9 @@ impl<'a, W: Write> Writer<'a, W> {
         self.named_expressions.clear();
         self.ep_inputs.clear();
         self.wrapped_image_queries.clear();
+        self.wrapped_math.clear();
+        self.wrapped_array_lengths.clear();
+        self.push_constants = None;
     }
 
     pub fn write(
@@ -152,15 +164,10 @@ impl<'a, W: Write> Writer<'a, W> {
             // Check if all of the globals are accessible
             if !self.options.fake_missing_bindings {
                 if let Some((var_handle, _)) =
-                    module
-                        .global_variables
-                        .iter()
-                        .find(|&(var_handle, var)| match var.binding {
-                            Some(ref binding) if !info[var_handle].is_empty() => {
-                                self.options.resolve_resource_binding(binding).is_err()
-                            }
-                            _ => false,
-                        })
+                    module.global_variables.iter().find(|&(var_handle, var)| {
+                        !info[var_handle].is_empty()
+                            && matches!(self.options.resolve_global(var), Some(Err(_)))
+                    })
                 {
                     log::info!(
                         "Skipping function {:?} (name {:?}) because global {:?} is inaccessible",
@@ -182,6 +189,10 @@ impl<'a, W: Write> Writer<'a, W> {
 
             // Write wrapped function for `Expression::ImageQuery` before writing all statements and expressions
             self.write_wrapped_image_query_functions(module, &ctx)?;
+            // Write wrapped functions for math without HLSL intrinsics
+            self.write_wrapped_math_functions(module, &ctx)?;
+            // Write wrapped functions for the lengths of runtime-sized arrays
+            self.write_wrapped_array_length_functions(module, &ctx)?;
 
             self.write_function(module, name.as_str(), function, &ctx)?;
 
@@ -197,14 +208,12 @@ impl<'a, W: Write> Writer<'a, W> {
             if !self.options.fake_missing_bindings {
                 let mut ep_error = None;
                 for (var_handle, var) in module.global_variables.iter() {
-                    match var.binding {
-                        Some(ref binding) if !info[var_handle].is_empty() => {
-                            if let Err(err) = self.options.resolve_resource_binding(binding) {
-                                ep_error = Some(err);
-                                break;
-                            }
-                        }
-                        _ => {}
+                    if info[var_handle].is_empty() {
+                        continue;
+                    }
+                    if let Some(Err(err)) = self.options.resolve_global(var) {
+                        ep_error = Some(err);
+                        break;
                     }
                 }
                 if let Some(err) = ep_error {
@@ -222,6 +231,10 @@ impl<'a, W: Write> Writer<'a, W> {
 
             // Write wrapped function for `Expression::ImageQuery` before writing all statements and expressions
             self.write_wrapped_image_query_functions(module, &ctx)?;
+            // Write wrapped functions for math without HLSL intrinsics
+            self.write_wrapped_math_functions(module, &ctx)?;
+            // Write wrapped functions for the lengths of runtime-sized arrays
+            self.write_wrapped_array_length_functions(module, &ctx)?;
 
             if ep.stage == ShaderStage::Compute {
                 // HLSL is calling workgroup size "num threads"
@@ -243,7 +256,10 @@ impl<'a, W: Write> Writer<'a, W> {
             entry_point_names.push(Ok(name));
         }
 
-        Ok(super::ReflectionInfo { entry_point_names })
+        Ok(super::ReflectionInfo {
+            entry_point_names,
+            push_constants: self.push_constants.take(),
+        })
     }
 
     fn write_semantic(
@@ -300,6 +316,17 @@ impl<'a, W: Write> Writer<'a, W> {
                     binding: arg.binding.clone(),
                 };
 
+                // subgroup builtins are provided by intrinsics instead
+                if member
+                    .binding
+                    .as_ref()
+                    .and_then(subgroup_intrinsic)
+                    .is_some()
+                {
+                    members.push(member);
+                    continue;
+                }
+
                 write!(self.out, "{}", back::INDENT)?;
                 self.write_type(module, member.ty)?;
                 write!(self.out, " {}", &member.name)?;
@@ -337,14 +364,25 @@ impl<'a, W: Write> Writer<'a, W> {
         handle: Handle<crate::GlobalVariable>,
     ) -> BackendResult {
         let global = &module.global_variables[handle];
-        let inner = &module.types[global.ty].inner;
+        let inner = match module.types[global.ty].inner {
+            // resource arrays are only written for textures and samplers
+            TypeInner::BindingArray { base, .. } if global.class == crate::StorageClass::Handle => {
+                &module.types[base].inner
+            }
+            TypeInner::BindingArray { .. } => {
+                return Err(Error::Unimplemented(
+                    "binding arrays of buffers".to_string(),
+                ))
+            }
+            ref other => other,
+        };
 
         // https://docs.microsoft.com/en-us/windows/win32/direct3dhlsl/dx-graphics-hlsl-variable-register
         let (storage, register_ty) = match global.class {
             crate::StorageClass::Function => unreachable!("Function storage class"),
             crate::StorageClass::Private => ("static ", ""),
             crate::StorageClass::WorkGroup => ("groupshared ", ""),
-            crate::StorageClass::Uniform => ("cbuffer", "b"),
+            crate::StorageClass::Uniform | crate::StorageClass::PushConstant => ("cbuffer", "b"),
             crate::StorageClass::Storage | crate::StorageClass::Handle => {
                 if let TypeInner::Sampler { .. } = *inner {
                     ("", "s")
@@ -354,24 +392,58 @@ impl<'a, W: Write> Writer<'a, W> {
                     ("", "t")
                 }
             }
-            crate::StorageClass::PushConstant => unimplemented!("Push constants"),
         };
 
+        let target = match self.options.resolve_global(global) {
+            // Without a target, the push constants aren't written,
+            // and the functions using them are skipped.
+            Some(Err(_)) if global.class == crate::StorageClass::PushConstant => return Ok(()),
+            // this was already resolved earlier when we started evaluating an entry point.
+            target => target.map(Result::unwrap),
+        };
+        if global.class == crate::StorageClass::PushConstant {
+            let target = target.clone().unwrap();
+            // the fallback register may be taken by a constant buffer already
+            let collides = module.global_variables.iter().any(|(_, other)| {
+                other.class == crate::StorageClass::Uniform
+                    && self.options.resolve_global(other).and_then(Result::ok)
+                        == Some(target.clone())
+            });
+            if collides {
+                return Err(Error::PushConstantsCollision(target));
+            }
+            self.push_constants = Some(super::RootConstants {
+                target,
+                num_32bit_values: proc::Layouter::round_up(
+                    proc::Alignment::new(4).unwrap(),
+                    inner.span(&module.constants),
+                ) / 4,
+            });
+        }
+
         write!(self.out, "{}", storage)?;
+        let is_cbuffer = match global.class {
+            crate::StorageClass::Uniform | crate::StorageClass::PushConstant => true,
+            _ => false,
+        };
         // constant buffer declarations are expected to be inlined, e.g.
         // cbuffer foo: register(b0) { field1: type1; };
-        if global.class != crate::StorageClass::Uniform {
+        if !is_cbuffer {
             self.write_type(module, global.ty)?;
         }
         let name = &self.names[&NameKey::GlobalVariable(handle)];
         write!(self.out, " {}", name)?;
-        if let TypeInner::Array { size, .. } = module.types[global.ty].inner {
-            self.write_array_size(module, size)?;
+        match module.types[global.ty].inner {
+            TypeInner::Array { size, .. } => self.write_array_size(module, size)?,
+            // unlike other arrays, resource arrays may be unbounded
+            TypeInner::BindingArray { size, .. } => match size {
+                crate::ArraySize::Constant(_) => self.write_array_size(module, size)?,
+                crate::ArraySize::Dynamic => write!(self.out, "[]")?,
+            },
+            _ => {}
         }
 
-        if let Some(ref binding) = global.binding {
-            // this was already resolved earlier when we started evaluating an entry point.
-            let bt = self.options.resolve_resource_binding(binding).unwrap();
+        if let Some(bt) = target {
             write!(self.out, " : register({}{}", register_ty, bt.register)?;
             if self.options.shader_model > super::ShaderModel::V5_0 {
                 write!(self.out, ", space{}", bt.space)?;
@@ -386,7 +458,7 @@ impl<'a, W: Write> Writer<'a, W> {
             }
         }
 
-        if global.class == crate::StorageClass::Uniform {
+        if is_cbuffer {
             write!(self.out, " {{ ")?;
             self.write_type(module, global.ty)?;
             let name = &self.names[&NameKey::GlobalVariable(handle)];
@@ -455,16 +527,11 @@ impl<'a, W: Write> Writer<'a, W> {
 
         // Write the array size
         // Writes nothing if `ArraySize::Dynamic`
-        // Panics if `ArraySize::Constant` has a constant that isn't an uint
+        // Panics if `ArraySize::Constant` has a constant that isn't a valid array length
         match size {
             crate::ArraySize::Constant(const_handle) => {
-                match module.constants[const_handle].inner {
-                    crate::ConstantInner::Scalar {
-                        width: _,
-                        value: crate::ScalarValue::Uint(size),
-                    } => write!(self.out, "{}", size)?,
-                    _ => unreachable!(),
-                }
+                let size = module.constants[const_handle].to_array_length().unwrap();
+                write!(self.out, "{}", size)?;
             }
             crate::ArraySize::Dynamic => write!(self.out, "1")?,
         }
@@ -504,7 +571,9 @@ impl<'a, W: Write> Writer<'a, W> {
                     let ty_name = match module.types[base].inner {
                         // Write scalar type by backend so as not to depend on the front-end implementation
                         // Name returned from frontend can be generated (type1, float1, etc.)
-                        TypeInner::Scalar { kind, width } => scalar_kind_str(kind, width)?,
+                        TypeInner::Scalar { kind, width } | TypeInner::Atomic { kind, width } => {
+                            scalar_kind_str(kind, width)?
+                        }
                         _ => &self.names[&NameKey::Type(base)],
                     };
 
@@ -567,7 +636,9 @@ impl<'a, W: Write> Writer<'a, W> {
         match *inner {
             TypeInner::Struct { .. } => write!(self.out, "{}", self.names[&NameKey::Type(ty)])?,
             // hlsl array has the size separated from the base type
-            TypeInner::Array { base, .. } => self.write_type(module, base)?,
+            TypeInner::Array { base, .. } | TypeInner::BindingArray { base, .. } => {
+                self.write_type(module, base)?
+            }
             ref other => self.write_value_type(module, other)?,
         }
 
@@ -580,7 +651,7 @@ impl<'a, W: Write> Writer<'a, W> {
     /// Adds no trailing or leading whitespace
     pub(super) fn write_value_type(&mut self, module: &Module, inner: &TypeInner) -> BackendResult {
         match *inner {
-            TypeInner::Scalar { kind, width } => {
+            TypeInner::Scalar { kind, width } | TypeInner::Atomic { kind, width } => {
                 write!(self.out, "{}", scalar_kind_str(kind, width)?)?;
             }
             TypeInner::Vector { size, kind, width } => {
@@ -896,11 +967,27 @@ impl<'a, W: Write> Writer<'a, W> {
                 }
             }
             Statement::Store { pointer, value } => {
-                write!(self.out, "{}", INDENT.repeat(indent))?;
+                // With the `ReadZeroSkipWrite` policy, the store is skipped if any
+                // index of the pointer's access chain is out of bounds.
+                let checks = self.read_zero_skip_write_checks(module, pointer, func_ctx);
+                let store_indent = if checks.is_empty() {
+                    indent
+                } else {
+                    write!(self.out, "{}if (", INDENT.repeat(indent))?;
+                    self.write_bounds_checks(module, &checks, func_ctx)?;
+                    writeln!(self.out, ") {{")?;
+                    indent + 1
+                };
+
+                write!(self.out, "{}", INDENT.repeat(store_indent))?;
                 self.write_expr(module, pointer, func_ctx)?;
                 write!(self.out, " = ")?;
                 self.write_expr(module, value, func_ctx)?;
-                writeln!(self.out, ";")?
+                writeln!(self.out, ";")?;
+
+                if !checks.is_empty() {
+                    writeln!(self.out, "{}}}", INDENT.repeat(indent))?;
+                }
             }
             Statement::Call {
                 function,
@@ -934,6 +1021,250 @@ impl<'a, W: Write> Writer<'a, W> {
                 }
                 writeln!(self.out, ");")?
             }
+            Statement::Atomic {
+                pointer,
+                ref fun,
+                value,
+                result,
+            } => {
+                let (kind, width) = match *func_ctx.info[value].ty.inner_with(&module.types) {
+                    TypeInner::Scalar { kind, width } => (kind, width),
+                    _ => unreachable!(),
+                };
+                let scalar_str = scalar_kind_str(kind, width)?;
+                let name = format!("{}{}", back::BAKE_PREFIX, result.index());
+                let original = match *fun {
+                    crate::AtomicFunction::Exchange { compare: Some(_) } => format!("{}_old", name),
+                    _ => name.clone(),
+                };
+                writeln!(
+                    self.out,
+                    "{}{} {};",
+                    INDENT.repeat(indent),
+                    scalar_str,
+                    original
+                )?;
+
+                let fun_str = match *fun {
+                    crate::AtomicFunction::Add | crate::AtomicFunction::Subtract => "Add",
+                    crate::AtomicFunction::And => "And",
+                    crate::AtomicFunction::InclusiveOr => "Or",
+                    crate::AtomicFunction::ExclusiveOr => "Xor",
+                    crate::AtomicFunction::Min => "Min",
+                    crate::AtomicFunction::Max => "Max",
+                    crate::AtomicFunction::Exchange { compare: None } => "Exchange",
+                    crate::AtomicFunction::Exchange { compare: Some(_) } => "CompareExchange",
+                };
+                write!(self.out, "{}Interlocked{}(", INDENT.repeat(indent), fun_str)?;
+                self.write_expr(module, pointer, func_ctx)?;
+                write!(self.out, ", ")?;
+                if let crate::AtomicFunction::Exchange { compare: Some(cmp) } = *fun {
+                    self.write_expr(module, cmp, func_ctx)?;
+                    write!(self.out, ", ")?;
+                }
+                // There is no `InterlockedSub`, so negate the operand instead
+                if let crate::AtomicFunction::Subtract = *fun {
+                    write!(self.out, "-")?;
+                }
+                self.write_expr(module, value, func_ctx)?;
+                writeln!(self.out, ", {});", original)?;
+
+                if let crate::AtomicFunction::Exchange { compare: Some(cmp) } = *fun {
+                    write!(
+                        self.out,
+                        "{}{}2 {} = {}2({}, {}({} == ",
+                        INDENT.repeat(indent),
+                        scalar_str,
+                        name,
+                        scalar_str,
+                        original,
+                        scalar_str,
+                        original
+                    )?;
+                    self.write_expr(module, cmp, func_ctx)?;
+                    writeln!(self.out, "));")?;
+                }
+                self.named_expressions.insert(result, name);
+            }
+            Statement::SubgroupBallot { result, predicate } => {
+                let name = self.write_subgroup_result(module, result, func_ctx, indent)?;
+                write!(self.out, "WaveActiveBallot(")?;
+                match predicate {
+                    Some(predicate) => self.write_expr(module, predicate, func_ctx)?,
+                    None => write!(self.out, "true")?,
+                }
+                writeln!(self.out, ");")?;
+                self.named_expressions.insert(result, name);
+            }
+            Statement::SubgroupCollectiveOperation {
+                op,
+                collective_op,
+                argument,
+                result,
+            } => {
+                use crate::{CollectiveOperation as Co, SubgroupOperation as So};
+
+                let (fun_str, combine) = match (collective_op, op) {
+                    (Co::Reduce, So::All) => ("WaveActiveAllTrue", None),
+                    (Co::Reduce, So::Any) => ("WaveActiveAnyTrue", None),
+                    (Co::Reduce, So::Add) => ("WaveActiveSum", None),
+                    (Co::Reduce, So::Mul) => ("WaveActiveProduct", None),
+                    (Co::Reduce, So::Min) => ("WaveActiveMin", None),
+                    (Co::Reduce, So::Max) => ("WaveActiveMax", None),
+                    (Co::Reduce, So::And) => ("WaveActiveBitAnd", None),
+                    (Co::Reduce, So::Or) => ("WaveActiveBitOr", None),
+                    (Co::Reduce, So::Xor) => ("WaveActiveBitXor", None),
+                    (Co::ExclusiveScan, So::Add) => ("WavePrefixSum", None),
+                    (Co::ExclusiveScan, So::Mul) => ("WavePrefixProduct", None),
+                    // HLSL only has exclusive prefix operations,
+                    // so we fold in the value of the invocation itself
+                    (Co::InclusiveScan, So::Add) => ("WavePrefixSum", Some("+")),
+                    (Co::InclusiveScan, So::Mul) => ("WavePrefixProduct", Some("*")),
+                    _ => {
+                        return Err(Error::Unimplemented(format!(
+                            "subgroup {:?} of {:?}",
+                            collective_op, op
+                        )))
+                    }
+                };
+                let name = self.write_subgroup_result(module, result, func_ctx, indent)?;
+                write!(self.out, "{}(", fun_str)?;
+                self.write_expr(module, argument, func_ctx)?;
+                write!(self.out, ")")?;
+                if let Some(combine) = combine {
+                    write!(self.out, " {} ", combine)?;
+                    self.write_expr(module, argument, func_ctx)?;
+                }
+                writeln!(self.out, ";")?;
+                self.named_expressions.insert(result, name);
+            }
+            Statement::SubgroupGather {
+                mode,
+                argument,
+                result,
+            } => {
+                let name = self.write_subgroup_result(module, result, func_ctx, indent)?;
+                match mode {
+                    crate::GatherMode::BroadcastFirst => {
+                        write!(self.out, "WaveReadLaneFirst(")?;
+                        self.write_expr(module, argument, func_ctx)?;
+                    }
+                    crate::GatherMode::Broadcast(index) | crate::GatherMode::Shuffle(index) => {
+                        write!(self.out, "WaveReadLaneAt(")?;
+                        self.write_expr(module, argument, func_ctx)?;
+                        write!(self.out, ", ")?;
+                        self.write_expr(module, index, func_ctx)?;
+                    }
+                    crate::GatherMode::ShuffleDown(index)
+                    | crate::GatherMode::ShuffleUp(index)
+                    | crate::GatherMode::ShuffleXor(index) => {
+                        let op = match mode {
+                            crate::GatherMode::ShuffleDown(_) => "+",
+                            crate::GatherMode::ShuffleUp(_) => "-",
+                            _ => "^",
+                        };
+                        write!(self.out, "WaveReadLaneAt(")?;
+                        self.write_expr(module, argument, func_ctx)?;
+                        write!(self.out, ", WaveGetLaneIndex() {} ", op)?;
+                        self.write_expr(module, index, func_ctx)?;
+                    }
+                }
+                writeln!(self.out, ");")?;
+                self.named_expressions.insert(result, name);
+            }
+            Statement::Switch {
+                selector,
+                ref cases,
+                ref default,
+            } => {
+                // Start the switch
+                write!(self.out, "{}", INDENT.repeat(indent))?;
+                write!(self.out, "switch(")?;
+                self.write_expr(module, selector, func_ctx)?;
+                writeln!(self.out, ") {{")?;
+
+                // Write all cases
+                for (i, case) in cases.iter().enumerate() {
+                    // Empty fall-through cases are just labels of the next case
+                    if case.fall_through && case.body.is_empty() {
+                        writeln!(
+                            self.out,
+                            "{}case {}:",
+                            INDENT.repeat(indent + 1),
+                            case.value
+                        )?;
+                        continue;
+                    }
+
+                    writeln!(
+                        self.out,
+                        "{}case {}: {{",
+                        INDENT.repeat(indent + 1),
+                        case.value
+                    )?;
+
+                    let last_body = if case.fall_through {
+                        // HLSL doesn't allow falling through non-empty cases,
+                        // so we copy the bodies of the following cases here,
+                        // up to the first one that doesn't fall through.
+                        let fall_bodies = cases[i..].iter().map(|case| &case.body);
+                        let bodies: Vec<_> =
+                            match cases[i..].iter().position(|case| !case.fall_through) {
+                                Some(end) => fall_bodies.take(end + 1).collect(),
+                                None => fall_bodies.chain(Some(default)).collect(),
+                            };
+                        for body in bodies.iter().filter(|body| !body.is_empty()) {
+                            // Each copy gets its own scope, and the expressions
+                            // baked inside of it have to be baked again in the next copy.
+                            let named_expressions = self.named_expressions.clone();
+                            writeln!(self.out, "{}{{", INDENT.repeat(indent + 2))?;
+                            for sta in body.iter() {
+                                self.write_stmt(module, sta, func_ctx, indent + 3)?;
+                            }
+                            writeln!(self.out, "{}}}", INDENT.repeat(indent + 2))?;
+                            self.named_expressions = named_expressions;
+                        }
+                        bodies.last().copied()
+                    } else {
+                        for sta in case.body.iter() {
+                            self.write_stmt(module, sta, func_ctx, indent + 2)?;
+                        }
+                        Some(&case.body)
+                    };
+
+                    // Write `break;` unless the case already ends with a jump
+                    match last_body.and_then(|body| body.last()) {
+                        Some(&Statement::Break)
+                        | Some(&Statement::Continue)
+                        | Some(&Statement::Return { .. })
+                        | Some(&Statement::Kill) => {}
+                        _ => writeln!(self.out, "{}break;", INDENT.repeat(indent + 2))?,
+                    }
+                    writeln!(self.out, "{}}}", INDENT.repeat(indent + 1))?;
+                }
+
+                // Only write the default block if the block isn't empty, or if
+                // it's needed to close the labels falling through to it, or to
+                // keep the switch from being empty.
+                let dangling = match cases.last() {
+                    Some(case) => case.fall_through && case.body.is_empty(),
+                    None => true,
+                };
+                if !default.is_empty() || dangling {
+                    writeln!(self.out, "{}default: {{", INDENT.repeat(indent + 1))?;
+
+                    for sta in default.iter() {
+                        self.write_stmt(module, sta, func_ctx, indent + 2)?;
+                    }
+                    if default.is_empty() {
+                        writeln!(self.out, "{}break;", INDENT.repeat(indent + 2))?;
+                    }
+
+                    writeln!(self.out, "{}}}", INDENT.repeat(indent + 1))?;
+                }
+
+                writeln!(self.out, "{}}}", INDENT.repeat(indent))?
+            }
             Statement::Loop {
                 ref body,
                 ref continuing,
@@ -978,27 +1309,49 @@ impl<'a, W: Write> Writer<'a, W> {
                 array_index,
                 value,
             } => {
-                write!(self.out, "{}", INDENT.repeat(indent))?;
+                let (dim, class) = match *func_ctx.info[image].ty.inner_with(&module.types) {
+                    TypeInner::Image { dim, class, .. } => (dim, class),
+                    _ => unreachable!(),
+                };
+                let address = ImageAddress {
+                    image,
+                    class,
+                    dim,
+                    coordinate,
+                    array_index,
+                    level: None,
+                    sample: None,
+                };
+
+                // With the `ReadZeroSkipWrite` policy, out of bounds stores are skipped.
+                let checked = self.options.index_bounds_check_policy
+                    == IndexBoundsCheckPolicy::ReadZeroSkipWrite;
+                let store_indent = if checked {
+                    write!(self.out, "{}if (", INDENT.repeat(indent))?;
+                    self.write_image_bounds_checks(module, func_ctx, &address)?;
+                    writeln!(self.out, ") {{")?;
+                    indent + 1
+                } else {
+                    indent
+                };
+
+                write!(self.out, "{}", INDENT.repeat(store_indent))?;
                 self.write_expr(module, image, func_ctx)?;
 
+                // Array index accepted only for texture_storage_2d_array, so the coordinates
+                // are merged with it as `int3(coordinate, array_index)`
                 write!(self.out, "[")?;
-                if let Some(index) = array_index {
-                    // Array index accepted only for texture_storage_2d_array, so we can safety use int3(coordinate, array_index) here
-                    write!(self.out, "int3(")?;
-                    self.write_expr(module, coordinate, func_ctx)?;
-                    write!(self.out, ", ")?;
-                    self.write_expr(module, index, func_ctx)?;
-                    write!(self.out, ")")?;
-                } else {
-                    self.write_expr(module, coordinate, func_ctx)?;
-                }
+                self.write_image_address(module, func_ctx, &address, false)?;
                 write!(self.out, "]")?;
 
                 write!(self.out, " = ")?;
                 self.write_expr(module, value, func_ctx)?;
                 writeln!(self.out, ";")?;
+
+                if checked {
+                    writeln!(self.out, "{}}}", INDENT.repeat(indent))?;
+                }
             }
-            _ => return Err(Error::Unimplemented(format!("write_stmt {:?}", stmt))),
         }
 
         Ok(())
@@ -1091,7 +1444,12 @@ impl<'a, W: Write> Writer<'a, W> {
                     }
                     TypeInner::Matrix { .. }
                     | TypeInner::Array { .. }
-                    | TypeInner::ValuePointer { .. } => write!(self.out, "[{}]", index)?,
+                    | TypeInner::BindingArray { .. }
+                    | TypeInner::ValuePointer { .. } => {
+                        write!(self.out, "[")?;
+                        self.write_index(module, base, proc::GuardedIndex::Known(index), func_ctx)?;
+                        write!(self.out, "]")?;
+                    }
                     TypeInner::Struct { .. } => {
                         // This will never panic in case the type is a `Struct`, this is not true
                         // for other types so we can only check while inside this match arm
@@ -1116,13 +1474,16 @@ impl<'a, W: Write> Writer<'a, W> {
                         // EntryPoint arguments wrapped into structure
                         // We can safery unwrap here, because if we write function arguments it means, that ep_input struct already exists
                         let ep_input = self.ep_inputs[index as usize].as_ref().unwrap();
-                        let member_name = &ep_input.members[pos as usize].name;
-                        write!(
-                            self.out,
-                            "{}.{}",
-                            &ep_input.name.to_lowercase(),
-                            member_name
-                        )?
+                        let member = &ep_input.members[pos as usize];
+                        match member.binding.as_ref().and_then(subgroup_intrinsic) {
+                            Some(intrinsic) => write!(self.out, "{}", intrinsic)?,
+                            None => write!(
+                                self.out,
+                                "{}.{}",
+                                &ep_input.name.to_lowercase(),
+                                member.name
+                            )?,
+                        }
                     }
                 };
             }
@@ -1134,35 +1495,35 @@ impl<'a, W: Write> Writer<'a, W> {
                 offset,
                 level,
                 depth_ref,
+                gather,
             } => {
                 use crate::SampleLevel as Sl;
-
-                let texture_func = match level {
-                    Sl::Auto => {
+                use crate::SwizzleComponent as Sc;
+
+                let texture_func = match (gather, level) {
+                    (Some(_), _) if depth_ref.is_some() => "GatherCmp",
+                    (Some(Sc::X), _) => "GatherRed",
+                    (Some(Sc::Y), _) => "GatherGreen",
+                    (Some(Sc::Z), _) => "GatherBlue",
+                    (Some(Sc::W), _) => "GatherAlpha",
+                    (None, Sl::Auto) => {
                         if depth_ref.is_some() {
                             "SampleCmp"
                         } else {
                             "Sample"
                         }
                     }
-                    Sl::Zero => "SampleCmpLevelZero",
-                    Sl::Exact(_) => "SampleLevel",
-                    Sl::Bias(_) => "SampleBias",
-                    Sl::Gradient { .. } => "SampleGrad",
+                    (None, Sl::Zero) => "SampleCmpLevelZero",
+                    (None, Sl::Exact(_)) => "SampleLevel",
+                    (None, Sl::Bias(_)) => "SampleBias",
+                    (None, Sl::Gradient { .. }) => "SampleGrad",
                 };
 
                 self.write_expr(module, image, func_ctx)?;
                 write!(self.out, ".{}(", texture_func)?;
                 self.write_expr(module, sampler, func_ctx)?;
                 write!(self.out, ", ")?;
-                self.write_texture_coordinates(
-                    "float",
-                    coordinate,
-                    array_index,
-                    MipLevelCoordinate::NotApplicable,
-                    module,
-                    func_ctx,
-                )?;
+                self.write_texture_coordinates("float", coordinate, array_index, module, func_ctx)?;
 
                 if let Some(depth_ref) = depth_ref {
                     write!(self.out, ", ")?;
@@ -1227,38 +1588,48 @@ impl<'a, W: Write> Writer<'a, W> {
                 index,
             } => {
                 // https://docs.microsoft.com/en-us/windows/win32/direct3dhlsl/dx-graphics-hlsl-to-load
-                let ms = match *func_ctx.info[image].ty.inner_with(&module.types) {
-                    crate::TypeInner::Image {
-                        class: crate::ImageClass::Sampled { multi, .. },
-                        ..
-                    } => multi,
+                let (dim, class) = match *func_ctx.info[image].ty.inner_with(&module.types) {
+                    TypeInner::Image { dim, class, .. } => (dim, class),
+                    _ => unreachable!(),
+                };
+                let ms = match class {
+                    crate::ImageClass::Sampled { multi, .. } => multi,
                     _ => false,
                 };
+                let (level, sample) = if ms { (None, index) } else { (index, None) };
+                let address = ImageAddress {
+                    image,
+                    class,
+                    dim,
+                    coordinate,
+                    array_index,
+                    level,
+                    sample,
+                };
+
+                // With the `ReadZeroSkipWrite` policy, out of bounds loads yield zero.
+                let checked = self.options.index_bounds_check_policy
+                    == IndexBoundsCheckPolicy::ReadZeroSkipWrite;
+                if checked {
+                    write!(self.out, "(")?;
+                    self.write_image_bounds_checks(module, func_ctx, &address)?;
+                    write!(self.out, " ? ")?;
+                }
 
                 self.write_expr(module, image, func_ctx)?;
                 write!(self.out, ".Load(")?;
 
-                let mip_level = if ms {
-                    MipLevelCoordinate::NotApplicable
-                } else {
-                    match index {
-                        Some(expr) => MipLevelCoordinate::Expression(expr),
-                        None => MipLevelCoordinate::Zero,
-                    }
-                };
-
-                self.write_texture_coordinates(
-                    "int",
-                    coordinate,
-                    array_index,
-                    mip_level,
-                    module,
-                    func_ctx,
-                )?;
+                self.write_image_address(module, func_ctx, &address, !ms)?;
 
                 if ms {
                     write!(self.out, ", ")?;
-                    self.write_expr(module, index.unwrap(), func_ctx)?;
+                    self.write_image_index(
+                        module,
+                        func_ctx,
+                        &address,
+                        sample,
+                        image::ImageQuery::NumSamples,
+                    )?;
                 }
 
                 // close bracket for Load function
@@ -1269,6 +1640,12 @@ impl<'a, W: Write> Writer<'a, W> {
                 {
                     write!(self.out, ".x")?;
                 }
+
+                if checked {
+                    write!(self.out, " : ")?;
+                    self.write_zero_value(module, &func_ctx.info[expr].ty)?;
+                    write!(self.out, ")")?;
+                }
             }
             // TODO: copy-paste from wgsl-out
             Expression::GlobalVariable(handle) => {
@@ -1279,13 +1656,60 @@ impl<'a, W: Write> Writer<'a, W> {
                 write!(self.out, "{}", self.names[&func_ctx.name_key(handle)])?
             }
             Expression::Load { pointer } => {
-                self.write_expr(module, pointer, func_ctx)?;
+                // With the `ReadZeroSkipWrite` policy, the load yields zero if any
+                // index of the pointer's access chain is out of bounds.
+                let checks = self.read_zero_skip_write_checks(module, pointer, func_ctx);
+                if checks.is_empty() {
+                    self.write_expr(module, pointer, func_ctx)?;
+                } else {
+                    write!(self.out, "(")?;
+                    self.write_bounds_checks(module, &checks, func_ctx)?;
+                    write!(self.out, " ? ")?;
+                    self.write_expr(module, pointer, func_ctx)?;
+                    write!(self.out, " : ")?;
+                    self.write_zero_value(module, &func_ctx.info[expr].ty)?;
+                    write!(self.out, ")")?;
+                }
             }
             Expression::Access { base, index } => {
+                // With the `ReadZeroSkipWrite` policy, indexing a value (as opposed to
+                // a pointer, which is checked by `Load` and `Store`) yields zero when
+                // out of bounds.
+                let index = proc::GuardedIndex::Expression(index);
+                let check = match self.options.index_bounds_check_policy {
+                    IndexBoundsCheckPolicy::ReadZeroSkipWrite
+                        if !self.is_pointer(module, base, func_ctx) =>
+                    {
+                        proc::access_needs_check(
+                            base,
+                            index,
+                            module,
+                            func_ctx.expressions,
+                            func_ctx.info,
+                        )
+                        .map(|length| proc::BoundsCheck {
+                            base,
+                            index,
+                            length,
+                        })
+                    }
+                    _ => None,
+                };
+
+                if let Some(check) = check {
+                    write!(self.out, "(")?;
+                    self.write_bounds_checks(module, &[check], func_ctx)?;
+                    write!(self.out, " ? ")?;
+                }
                 self.write_expr(module, base, func_ctx)?;
                 write!(self.out, "[")?;
-                self.write_expr(module, index, func_ctx)?;
+                self.write_index(module, base, index, func_ctx)?;
                 write!(self.out, "]")?;
+                if check.is_some() {
+                    write!(self.out, " : ")?;
+                    self.write_zero_value(module, &func_ctx.info[expr].ty)?;
+                    write!(self.out, ")")?;
+                }
             }
             Expression::Unary { op, expr } => {
                 // https://docs.microsoft.com/en-us/windows/win32/direct3dhlsl/dx-graphics-hlsl-operators#unary-operators
@@ -1316,10 +1740,32 @@ impl<'a, W: Write> Writer<'a, W> {
 
                 write!(self.out, ")")?
             }
-            Expression::As { expr, kind, .. } => {
+            Expression::As {
+                expr,
+                kind,
+                convert: None,
+            } => {
+                // Bitcasts are done with the `asfloat`, `asint` and `asuint` intrinsics
+                let fun_name = match kind {
+                    crate::ScalarKind::Float => "asfloat",
+                    crate::ScalarKind::Sint => "asint",
+                    crate::ScalarKind::Uint => "asuint",
+                    crate::ScalarKind::Bool => {
+                        return Err(Error::Custom(format!("Cannot bitcast to {:?}", kind)))
+                    }
+                };
+                write!(self.out, "{}(", fun_name)?;
+                self.write_expr(module, expr, func_ctx)?;
+                write!(self.out, ")")?;
+            }
+            Expression::As {
+                expr,
+                kind,
+                convert: Some(width),
+            } => {
                 let inner = func_ctx.info[expr].ty.inner_with(&module.types);
                 match *inner {
-                    TypeInner::Vector { size, width, .. } => {
+                    TypeInner::Vector { size, .. } => {
                         write!(
                             self.out,
                             "{}{}",
@@ -1327,7 +1773,7 @@ impl<'a, W: Write> Writer<'a, W> {
                             back::vector_size_str(size),
                         )?;
                     }
-                    TypeInner::Scalar { width, .. } => {
+                    TypeInner::Scalar { .. } => {
                         write!(self.out, "{}", scalar_kind_str(kind, width)?)?
                     }
                     _ => {
@@ -1346,9 +1792,34 @@ impl<'a, W: Write> Writer<'a, W> {
                 arg,
                 arg1,
                 arg2,
+                arg3,
             } => {
                 use crate::MathFunction as Mf;
 
+                if let Some(math) = WrappedMath::new(module, func_ctx, expression) {
+                    self.write_wrapped_math_function_name(math)?;
+                    write!(self.out, "(")?;
+                    self.write_expr(module, arg, func_ctx)?;
+                    for arg in arg1.iter().chain(arg2.iter()).chain(arg3.iter()) {
+                        write!(self.out, ", ")?;
+                        self.write_expr(module, *arg, func_ctx)?;
+                    }
+                    write!(self.out, ")")?;
+                    return Ok(());
+                }
+
+                // `firstbitlow` and `firstbithigh` always return unsigned integers
+                let signed_bits = match fun {
+                    Mf::FirstTrailingBit | Mf::FirstLeadingBit => {
+                        let inner = func_ctx.info[arg].ty.inner_with(&module.types);
+                        inner.scalar_kind() == Some(crate::ScalarKind::Sint)
+                    }
+                    _ => false,
+                };
+                if signed_bits {
+                    write!(self.out, "asint(")?;
+                }
+
                 let fun_name = match fun {
                     // comparison
                     Mf::Abs => "abs",
@@ -1383,7 +1854,6 @@ impl<'a, W: Write> Writer<'a, W> {
                     Mf::Pow => "pow",
                     // geometry
                     Mf::Dot => "dot",
-                    //Mf::Outer => ,
                     Mf::Cross => "cross",
                     Mf::Distance => "distance",
                     Mf::Length => "length",
@@ -1399,13 +1869,34 @@ impl<'a, W: Write> Writer<'a, W> {
                     Mf::SmoothStep => "smoothstep",
                     Mf::Sqrt => "sqrt",
                     Mf::InverseSqrt => "rsqrt",
-                    //Mf::Inverse =>,
                     Mf::Transpose => "transpose",
                     Mf::Determinant => "determinant",
                     // bits
                     Mf::CountOneBits => "countbits",
                     Mf::ReverseBits => "reversebits",
-                    _ => return Err(Error::Unimplemented(format!("write_expr_math {:?}", fun))),
+                    Mf::FirstTrailingBit => "firstbitlow",
+                    Mf::FirstLeadingBit => "firstbithigh",
+                    // written as wrapped functions above, unless the arguments
+                    // have types the wrapped functions don't handle
+                    Mf::Outer
+                    | Mf::Inverse
+                    | Mf::ExtractBits
+                    | Mf::InsertBits
+                    | Mf::Pack4x8snorm
+                    | Mf::Pack4x8unorm
+                    | Mf::Pack2x16snorm
+                    | Mf::Pack2x16unorm
+                    | Mf::Pack2x16float
+                    | Mf::Unpack4x8snorm
+                    | Mf::Unpack4x8unorm
+                    | Mf::Unpack2x16snorm
+                    | Mf::Unpack2x16unorm
+                    | Mf::Unpack2x16float => {
+                        return Err(Error::Unimplemented(format!(
+                            "write_expr math function {:?}",
+                            fun
+                        )))
+                    }
                 };
 
                 write!(self.out, "{}(", fun_name)?;
@@ -1418,7 +1909,10 @@ impl<'a, W: Write> Writer<'a, W> {
                     write!(self.out, ", ")?;
                     self.write_expr(module, arg, func_ctx)?;
                 }
-                write!(self.out, ")")?
+                write!(self.out, ")")?;
+                if signed_bits {
+                    write!(self.out, ")")?;
+                }
             }
             Expression::Swizzle {
                 size,
@@ -1431,11 +1925,8 @@ impl<'a, W: Write> Writer<'a, W> {
                     self.out.write_char(back::COMPONENTS[sc as usize])?;
                 }
             }
-            // `ArrayLength` is written as `expr.length()`
-            Expression::ArrayLength(expr) => {
-                self.write_expr(module, expr, func_ctx)?;
-                write!(self.out, ".length()")?
-            }
+            // `ArrayLength` calls the wrapped function of the buffer
+            Expression::ArrayLength(expr) => self.write_array_length(module, expr, func_ctx)?,
             Expression::Derivative { axis, expr } => {
                 use crate::DerivativeAxis as Da;
 
@@ -1479,9 +1970,32 @@ impl<'a, W: Write> Writer<'a, W> {
                 self.write_expr(module, reject, func_ctx)?;
                 write!(self.out, ")")?
             }
+            Expression::Relational { fun, argument } => {
+                use crate::RelationalFunction as Rf;
+
+                match fun {
+                    Rf::IsNormal => {
+                        let math =
+                            WrappedMath::new(module, func_ctx, expression).ok_or_else(|| {
+                                Error::Unimplemented("write_expr relational isNormal".to_string())
+                            })?;
+                        self.write_wrapped_math_function_name(math)?;
+                    }
+                    Rf::All => write!(self.out, "all")?,
+                    Rf::Any => write!(self.out, "any")?,
+                    Rf::IsNan => write!(self.out, "isnan")?,
+                    Rf::IsInf => write!(self.out, "isinf")?,
+                    Rf::IsFinite => write!(self.out, "isfinite")?,
+                }
+                write!(self.out, "(")?;
+                self.write_expr(module, argument, func_ctx)?;
+                write!(self.out, ")")?
+            }
             // Nothing to do here, since call expression already cached
-            Expression::Call(_) => {}
-            _ => return Err(Error::Unimplemented(format!("write_expr {:?}", expression))),
+            Expression::Call(_)
+            | Expression::AtomicResult { .. }
+            | Expression::SubgroupBallotResult
+            | Expression::SubgroupOperationResult { .. } => {}
         }
 
         Ok(())
@@ -1563,6 +2077,32 @@ impl<'a, W: Write> Writer<'a, W> {
         Ok(())
     }
 
+    /// Helper method to declare the result of a subgroup statement
+    ///
+    /// # Notes
+    /// Leaves the declaration open for the value to be written
+    fn write_subgroup_result(
+        &mut self,
+        module: &Module,
+        result: Handle<crate::Expression>,
+        func_ctx: &back::FunctionCtx,
+        indent: usize,
+    ) -> Result<String, Error> {
+        if self.options.shader_model < super::ShaderModel::V6_0 {
+            return Err(Error::Custom(
+                "Wave intrinsics require shader model 6.0".to_string(),
+            ));
+        }
+        let name = format!("{}{}", back::BAKE_PREFIX, result.index());
+        write!(self.out, "{}", back::INDENT.repeat(indent))?;
+        match func_ctx.info[result].ty {
+            proc::TypeResolution::Handle(ty_handle) => self.write_type(module, ty_handle)?,
+            proc::TypeResolution::Value(ref inner) => self.write_value_type(module, inner)?,
+        }
+        write!(self.out, " {} = ", name)?;
+        Ok(name)
+    }
+
     fn write_named_expr(
         &mut self,
         module: &Module,
@@ -1601,6 +2141,231 @@ impl<'a, W: Write> Writer<'a, W> {
         Ok(())
     }
 
+    /// Returns `true` if the expression is a pointer
+    fn is_pointer(
+        &self,
+        module: &Module,
+        expr: Handle<crate::Expression>,
+        func_ctx: &back::FunctionCtx,
+    ) -> bool {
+        match *func_ctx.info[expr].ty.inner_with(&module.types) {
+            TypeInner::Pointer { .. } | TypeInner::ValuePointer { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the indices of the access chain `pointer` that need a bounds check
+    ///
+    /// This is always empty unless the `ReadZeroSkipWrite` policy is used.
+    fn read_zero_skip_write_checks(
+        &self,
+        module: &Module,
+        pointer: Handle<crate::Expression>,
+        func_ctx: &back::FunctionCtx,
+    ) -> Vec<proc::BoundsCheck> {
+        match self.options.index_bounds_check_policy {
+            IndexBoundsCheckPolicy::ReadZeroSkipWrite => {
+                proc::bounds_check_iter(pointer, module, func_ctx.expressions, func_ctx.info)
+                    .collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Helper method to write an index, restricting it to be in bounds when needed
+    ///
+    /// With the `Restrict` policy, indices are written as `min(uint(index), length - 1u)`.
+    fn write_index(
+        &mut self,
+        module: &Module,
+        base: Handle<crate::Expression>,
+        index: proc::GuardedIndex,
+        func_ctx: &back::FunctionCtx,
+    ) -> BackendResult {
+        if let proc::GuardedIndex::Expression(expr) = index {
+            if proc::is_non_uniform_binding_array_access(base, expr, module, func_ctx.info) {
+                write!(self.out, "NonUniformResourceIndex(")?;
+                self.write_expr(module, expr, func_ctx)?;
+                write!(self.out, ")")?;
+                return Ok(());
+            }
+        }
+
+        let length = match self.options.index_bounds_check_policy {
+            IndexBoundsCheckPolicy::Restrict => {
+                proc::access_needs_check(base, index, module, func_ctx.expressions, func_ctx.info)
+            }
+            _ => None,
+        };
+
+        match (index, length) {
+            (proc::GuardedIndex::Known(value), None) => write!(self.out, "{}", value)?,
+            (proc::GuardedIndex::Expression(expr), None) => {
+                self.write_expr(module, expr, func_ctx)?
+            }
+            (_, Some(length)) => {
+                write!(self.out, "min(")?;
+                self.write_guarded_index(module, index, func_ctx)?;
+                write!(self.out, ", ")?;
+                match length {
+                    proc::IndexableLength::Known(length) => write!(self.out, "{}u", length - 1)?,
+                    _ => {
+                        self.write_index_length(module, base, length, func_ctx)?;
+                        write!(self.out, " - 1u")?;
+                    }
+                }
+                write!(self.out, ")")?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Helper method to write the given index as a `uint`
+    fn write_guarded_index(
+        &mut self,
+        module: &Module,
+        index: proc::GuardedIndex,
+        func_ctx: &back::FunctionCtx,
+    ) -> BackendResult {
+        match index {
+            proc::GuardedIndex::Known(value) => write!(self.out, "{}u", value)?,
+            proc::GuardedIndex::Expression(expr) => {
+                write!(self.out, "uint(")?;
+                self.write_expr(module, expr, func_ctx)?;
+                write!(self.out, ")")?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Helper method to write the length of the indexable `base` as a `uint`
+    fn write_index_length(
+        &mut self,
+        module: &Module,
+        base: Handle<crate::Expression>,
+        length: proc::IndexableLength,
+        func_ctx: &back::FunctionCtx,
+    ) -> BackendResult {
+        match length {
+            proc::IndexableLength::Known(length) => write!(self.out, "{}u", length)?,
+            proc::IndexableLength::Specializable(constant) => {
+                write!(self.out, "uint(")?;
+                self.write_constant(module, constant)?;
+                write!(self.out, ")")?;
+            }
+            proc::IndexableLength::Dynamic => self.write_array_length(module, base, func_ctx)?,
+        }
+        Ok(())
+    }
+
+    /// Helper method to write a call to the wrapped function returning the
+    /// length of the runtime-sized `array`, as a `uint`
+    fn write_array_length(
+        &mut self,
+        module: &Module,
+        array: Handle<crate::Expression>,
+        func_ctx: &back::FunctionCtx,
+    ) -> BackendResult {
+        let handle = array_length_buffer(module, func_ctx, array)
+            .ok_or_else(|| Error::Unimplemented(format!("length of the array {:?}", array)))?;
+        let name = &self.names[&NameKey::GlobalVariable(handle)];
+        write!(self.out, "NagaArrayLength_{}()", name)?;
+        Ok(())
+    }
+
+    /// Helper method to write the functions returning the lengths of the
+    /// runtime-sized arrays used by a function, unless they were already written
+    ///
+    /// The length is computed from the byte size of the buffer, given by `GetDimensions`:
+    /// ```hlsl
+    /// uint NagaArrayLength_globals()
+    /// {
+    ///     uint size;
+    ///     globals.GetDimensions(size);
+    ///     return (size - 64u) / 4u;
+    /// }
+    /// ```
+    fn write_wrapped_array_length_functions(
+        &mut self,
+        module: &Module,
+        func_ctx: &back::FunctionCtx,
+    ) -> BackendResult {
+        // Bounds checks may need the lengths of any indexed array
+        let checked =
+            self.options.index_bounds_check_policy != IndexBoundsCheckPolicy::UndefinedBehavior;
+        for (handle, expression) in func_ctx.expressions.iter() {
+            let array = match *expression {
+                crate::Expression::ArrayLength(array) => array,
+                _ if checked => handle,
+                _ => continue,
+            };
+            let global = match array_length_buffer(module, func_ctx, array) {
+                Some(global) => global,
+                None => continue,
+            };
+            if !self.wrapped_array_lengths.insert(global) {
+                continue;
+            }
+            let (offset, stride) = match module.types[module.global_variables[global].ty].inner {
+                TypeInner::Struct { ref members, .. } => {
+                    let last = members.last().unwrap();
+                    match module.types[last.ty].inner {
+                        TypeInner::Array { stride, .. } => (last.offset, stride),
+                        _ => unreachable!(),
+                    }
+                }
+                TypeInner::Array { stride, .. } => (0, stride),
+                _ => unreachable!(),
+            };
+            let name = &self.names[&NameKey::GlobalVariable(global)];
+            writeln!(self.out, "uint NagaArrayLength_{}()", name)?;
+            writeln!(self.out, "{{")?;
+            writeln!(self.out, "{}uint size;", back::INDENT)?;
+            writeln!(self.out, "{}{}.GetDimensions(size);", back::INDENT, name)?;
+            writeln!(
+                self.out,
+                "{}return (size - {}u) / {}u;",
+                back::INDENT,
+                offset,
+                stride
+            )?;
+            writeln!(self.out, "}}")?;
+            writeln!(self.out)?;
+        }
+        Ok(())
+    }
+
+    /// Helper method to write the bounds checks of `checks`, joined with `&&`
+    fn write_bounds_checks(
+        &mut self,
+        module: &Module,
+        checks: &[proc::BoundsCheck],
+        func_ctx: &back::FunctionCtx,
+    ) -> BackendResult {
+        for (i, check) in checks.iter().enumerate() {
+            if i != 0 {
+                write!(self.out, " && ")?;
+            }
+            self.write_guarded_index(module, check.index, func_ctx)?;
+            write!(self.out, " < ")?;
+            self.write_index_length(module, check.base, check.length, func_ctx)?;
+        }
+        Ok(())
+    }
+
+    /// Helper method to write the zero value of a type, e.g. `(float4)0`
+    fn write_zero_value(&mut self, module: &Module, ty: &proc::TypeResolution) -> BackendResult {
+        match *ty {
+            proc::TypeResolution::Handle(handle) => self.write_default_init(module, handle),
+            proc::TypeResolution::Value(ref inner) => {
+                write!(self.out, "(")?;
+                self.write_value_type(module, inner)?;
+                write!(self.out, ")0")?;
+                Ok(())
+            }
+        }
+    }
+
     /// Helper function that write default zero initialization
     fn write_default_init(&mut self, module: &Module, ty: Handle<crate::Type>) -> BackendResult {
         write!(self.out, "(")?;
@@ -1614,6 +2379,33 @@ impl<'a, W: Write> Writer<'a, W> {
     }
 }
 
+/// Returns the storage buffer holding the runtime-sized `array`, if it is one.
+fn array_length_buffer(
+    module: &Module,
+    func_ctx: &back::FunctionCtx,
+    array: Handle<crate::Expression>,
+) -> Option<Handle<crate::GlobalVariable>> {
+    let global = match func_ctx.expressions[array] {
+        crate::Expression::GlobalVariable(global) => global,
+        crate::Expression::AccessIndex { base, .. } => match func_ctx.expressions[base] {
+            crate::Expression::GlobalVariable(global) => global,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let pointee = match *func_ctx.info[array].ty.inner_with(&module.types) {
+        TypeInner::Pointer { base, .. } => &module.types[base].inner,
+        ref other => other,
+    };
+    match *pointee {
+        TypeInner::Array {
+            size: crate::ArraySize::Dynamic,
+            ..
+        } if module.global_variables[global].class == crate::StorageClass::Storage => Some(global),
+        _ => None,
+    }
+}
+
 fn builtin_str(built_in: crate::BuiltIn) -> &'static str {
     use crate::BuiltIn as Bi;
 
@@ -1642,9 +2434,21 @@ fn builtin_str(built_in: crate::BuiltIn) -> &'static str {
     }
 }
 
+/// Intrinsic providing the value of a subgroup builtin, which has no semantic.
+fn subgroup_intrinsic(binding: &crate::Binding) -> Option<&'static str> {
+    match *binding {
+        crate::Binding::BuiltIn(crate::BuiltIn::SubgroupSize) => Some("WaveGetLaneCount()"),
+        crate::Binding::BuiltIn(crate::BuiltIn::SubgroupInvocationId) => Some("WaveGetLaneIndex()"),
+        _ => None,
+    }
+}
+
 /// Helper function that returns scalar related strings
 /// https://docs.microsoft.com/en-us/windows/win32/direct3dhlsl/dx-graphics-hlsl-scalar
-fn scalar_kind_str(kind: crate::ScalarKind, width: crate::Bytes) -> Result<&'static str, Error> {
+pub(super) fn scalar_kind_str(
+    kind: crate::ScalarKind,
+    width: crate::Bytes,
+) -> Result<&'static str, Error> {
     use crate::ScalarKind as Sk;
 
     match kind {
@@ -1709,3 +2513,90 @@ fn storage_format_to_texture_type(format: crate::StorageFormat) -> &'static str
         Sf::Rgba8Sint | Sf::Rgba16Sint | Sf::R32Sint | Sf::Rg32Sint | Sf::Rgba32Sint => "int4",
     }
 }
+
+#[cfg(feature = "wgsl-in")]
+#[test]
+fn test_push_constants() {
+    let source = "
+        [[block]]
+        struct PushConstants {
+            index: u32;
+            scale: vec2<f32>;
+        };
+        var<push_constant> pc: PushConstants;
+
+        [[stage(compute), workgroup_size(1)]]
+        fn main() {
+            let index = pc.index;
+        }
+    ";
+    let module = crate::front::wgsl::parse_str(source).unwrap();
+    let info = crate::valid::Validator::new(
+        crate::valid::ValidationFlags::all(),
+        crate::valid::Capabilities::PUSH_CONSTANT,
+    )
+    .validate(&module)
+    .unwrap();
+
+    let options = super::Options {
+        push_constants_target: Some(super::BindTarget {
+            space: 1,
+            register: 2,
+        }),
+        ..Default::default()
+    };
+    let mut buffer = String::new();
+    let reflection_info = Writer::new(&mut buffer, &options)
+        .write(&module, &info)
+        .unwrap();
+    assert_eq!(
+        reflection_info.push_constants,
+        Some(super::RootConstants {
+            target: super::BindTarget {
+                space: 1,
+                register: 2,
+            },
+            num_32bit_values: 4,
+        })
+    );
+}
+
+#[cfg(feature = "wgsl-in")]
+#[test]
+fn test_push_constants_collision() {
+    let source = "
+        [[block]]
+        struct Globals {
+            value: f32;
+        };
+        [[group(0), binding(0)]]
+        var<uniform> globals: Globals;
+        [[block]]
+        struct PushConstants {
+            index: u32;
+        };
+        var<push_constant> pc: PushConstants;
+
+        [[stage(compute), workgroup_size(1)]]
+        fn main() {
+            let value = globals.value;
+            let index = pc.index;
+        }
+    ";
+    let module = crate::front::wgsl::parse_str(source).unwrap();
+    let info = crate::valid::Validator::new(
+        crate::valid::ValidationFlags::all(),
+        crate::valid::Capabilities::PUSH_CONSTANT,
+    )
+    .validate(&module)
+    .unwrap();
+
+    // both fall back to b0 in space0
+    let mut buffer = String::new();
+    match Writer::new(&mut buffer, &super::Options::default()).write(&module, &info) {
+        Err(Error::PushConstantsCollision(target)) => {
+            assert_eq!(target, super::BindTarget::default())
+        }
+        other => panic!("expected a collision, got {:?}", other.map(|_| ())),
+    }
+}
@@ -68,6 +68,25 @@ impl<'a> FunctionCtx<'_> {
     }
 }
 
+/// The operands of an image load or store, as needed for bounds checks.
+#[allow(dead_code)]
+struct ImageAddress {
+    /// The image being accessed
+    image: crate::Handle<crate::Expression>,
+    /// The class of the image
+    class: crate::ImageClass,
+    /// The dimension of the image
+    dim: crate::ImageDimension,
+    /// The texel coordinates
+    coordinate: crate::Handle<crate::Expression>,
+    /// The array layer, for arrayed images
+    array_index: Option<crate::Handle<crate::Expression>>,
+    /// The level of detail, for mipmapped images
+    level: Option<crate::Handle<crate::Expression>>,
+    /// The sample index, for multisampled images
+    sample: Option<crate::Handle<crate::Expression>>,
+}
+
 /// How should code generated by Naga do indexing bounds checks?
 ///
 /// When a vector, matrix, or array index is out of bounds—either negative, or
@@ -85,7 +104,9 @@ impl<'a> FunctionCtx<'_> {
 ///
 /// -   Naga's own default is `UndefinedBehavior`, so that shader translations
 ///     are as faithful to the original as possible.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+#[cfg_attr(feature = "deserialize", derive(serde::Deserialize))]
 pub enum IndexBoundsCheckPolicy {
     /// Replace out-of-bounds indexes with some arbitrary in-bounds index.
     ///
@@ -176,6 +197,77 @@ fn vector_size_str(size: crate::VectorSize) -> &'static str {
     }
 }
 
+/// Helper function that writes the body of a function returning the inverse of
+/// the 4x4 matrix `m`, by Laplace expansion over the 2x2 minors of its first
+/// and last two columns. `scalar` and `matrix` are the names of the types.
+/// # Notes
+/// Used by `msl-out`, `hlsl-out`.
+#[allow(dead_code)]
+fn write_inverse_4x4(
+    out: &mut impl std::fmt::Write,
+    scalar: &str,
+    matrix: &str,
+) -> std::fmt::Result {
+    const MINORS: [(&str, usize, usize, usize, usize); 12] = [
+        ("s0", 0, 1, 0, 1),
+        ("s1", 0, 1, 0, 2),
+        ("s2", 0, 1, 0, 3),
+        ("s3", 0, 1, 1, 2),
+        ("s4", 0, 1, 1, 3),
+        ("s5", 0, 1, 2, 3),
+        ("c0", 2, 3, 0, 1),
+        ("c1", 2, 3, 0, 2),
+        ("c2", 2, 3, 0, 3),
+        ("c3", 2, 3, 1, 2),
+        ("c4", 2, 3, 1, 3),
+        ("c5", 2, 3, 2, 3),
+    ];
+    const ADJUGATE: [&str; 16] = [
+        "m[1][1] * c5 - m[1][2] * c4 + m[1][3] * c3",
+        "-m[0][1] * c5 + m[0][2] * c4 - m[0][3] * c3",
+        "m[3][1] * s5 - m[3][2] * s4 + m[3][3] * s3",
+        "-m[2][1] * s5 + m[2][2] * s4 - m[2][3] * s3",
+        "-m[1][0] * c5 + m[1][2] * c2 - m[1][3] * c1",
+        "m[0][0] * c5 - m[0][2] * c2 + m[0][3] * c1",
+        "-m[3][0] * s5 + m[3][2] * s2 - m[3][3] * s1",
+        "m[2][0] * s5 - m[2][2] * s2 + m[2][3] * s1",
+        "m[1][0] * c4 - m[1][1] * c2 + m[1][3] * c0",
+        "-m[0][0] * c4 + m[0][1] * c2 - m[0][3] * c0",
+        "m[3][0] * s4 - m[3][1] * s2 + m[3][3] * s0",
+        "-m[2][0] * s4 + m[2][1] * s2 - m[2][3] * s0",
+        "-m[1][0] * c3 + m[1][1] * c1 - m[1][2] * c0",
+        "m[0][0] * c3 - m[0][1] * c1 + m[0][2] * c0",
+        "-m[3][0] * s3 + m[3][1] * s1 - m[3][2] * s0",
+        "m[2][0] * s3 - m[2][1] * s1 + m[2][2] * s0",
+    ];
+
+    for &(name, a, b, i, j) in MINORS.iter() {
+        writeln!(
+            out,
+            "{}{} {} = m[{a}][{i}] * m[{b}][{j}] - m[{b}][{i}] * m[{a}][{j}];",
+            INDENT,
+            scalar,
+            name,
+            a = a,
+            b = b,
+            i = i,
+            j = j
+        )?;
+    }
+    writeln!(
+        out,
+        "{}{} det = s0 * c5 - s1 * c4 + s2 * c3 + s3 * c2 - s4 * c1 + s5 * c0;",
+        INDENT, scalar
+    )?;
+    writeln!(out, "{}{} adj = {}(", INDENT, matrix, matrix)?;
+    for (index, cofactor) in ADJUGATE.iter().enumerate() {
+        let separator = if index + 1 == ADJUGATE.len() { "" } else { "," };
+        writeln!(out, "{}{}{}{}", INDENT, INDENT, cofactor, separator)?;
+    }
+    writeln!(out, "{});", INDENT)?;
+    writeln!(out, "{}return adj / det;", INDENT)
+}
+
 impl crate::TypeInner {
     #[allow(unused)]
     fn is_handle(&self) -> bool {
@@ -7,6 +7,10 @@ from SPIR-V's descriptor sets, we require a separate mapping provided in the opt
 This mapping may have one or more resource end points for each descriptor set + index
 pair.
 
+Binding arrays of textures and samplers are written as `metal::array`s, which
+need Metal 2.0 and a fixed size. Binding arrays of buffers and runtime-sized
+binding arrays aren't supported.
+
 ## Entry points
 
 Even though MSL and our IR appear to be similar in that the entry points in both can
@@ -184,6 +188,10 @@ pub struct Options {
     pub spirv_cross_compatibility: bool,
     /// Don't panic on missing bindings, instead generate invalid MSL.
     pub fake_missing_bindings: bool,
+    /// How should the generated code handle array, vector, matrix, or image
+    /// indices that are out of range?
+    #[cfg_attr(feature = "deserialize", serde(default))]
+    pub index_bounds_check_policy: crate::back::IndexBoundsCheckPolicy,
 }
 
 impl Default for Options {
@@ -194,6 +202,7 @@ impl Default for Options {
             inline_samplers: Vec::new(),
             spirv_cross_compatibility: false,
             fake_missing_bindings: true,
+            index_bounds_check_policy: crate::back::IndexBoundsCheckPolicy::default(),
         }
     }
 }
@@ -357,6 +366,9 @@ impl ResolvedBinding {
                     Bi::LocalInvocationIndex => "thread_index_in_threadgroup",
                     Bi::WorkGroupId => "threadgroup_position_in_grid",
                     Bi::WorkGroupSize => "dispatch_threads_per_threadgroup",
+                    // subgroup
+                    Bi::SubgroupSize => "threads_per_simdgroup",
+                    Bi::SubgroupInvocationId => "thread_index_in_simdgroup",
                     _ => return Err(Error::UnsupportedBuiltIn(built_in)),
                 };
                 write!(out, "{}", name)?;
@@ -1,7 +1,7 @@
 use super::{sampler as sm, Error, LocationMode, Options, PipelineOptions, TranslationInfo};
 use crate::{
     arena::Handle,
-    back,
+    back::{self, ImageAddress, IndexBoundsCheckPolicy},
     proc::{self, NameKey, TypeResolution},
     valid, FastHashMap,
 };
@@ -15,6 +15,9 @@ type BackendResult = Result<(), Error>;
 
 const NAMESPACE: &str = "metal";
 const WRAPPED_ARRAY_FIELD: &str = "inner";
+// Used as the value of out of bounds loads with the `ReadZeroSkipWrite` policy.
+// It's converted to the zero value of whatever type is expected.
+const DEFAULT_CONSTRUCTIBLE: &str = "DefaultConstructible";
 
 #[derive(Clone)]
 struct Level(usize);
@@ -75,6 +78,9 @@ impl<'a> Display for TypeContext<'a> {
                     back::vector_size_str(rows),
                 )
             }
+            crate::TypeInner::Atomic { kind, .. } => {
+                write!(out, "{}::atomic_{}", NAMESPACE, scalar_kind_string(kind))
+            }
             crate::TypeInner::Pointer { base, class } => {
                 let sub = Self {
                     handle: base,
@@ -128,6 +134,16 @@ impl<'a> Display for TypeContext<'a> {
                 // so just print the element type here.
                 write!(out, "{}", sub)
             }
+            crate::TypeInner::BindingArray { base, .. } => {
+                let sub = Self {
+                    handle: base,
+                    first_time: false,
+                    ..*self
+                };
+                // Binding arrays are wrapped into `metal::array` by
+                // `TypedGlobalVariable`, which knows the length.
+                write!(out, "{}", sub)
+            }
             crate::TypeInner::Struct { .. } => unreachable!(),
             crate::TypeInner::Image {
                 dim,
@@ -223,6 +239,29 @@ impl<'a> TypedGlobalVariable<'a> {
             _ => ("", "", ""),
         };
 
+        if let crate::TypeInner::BindingArray { size, .. } = self.module.types[var.ty].inner {
+            let length = match size {
+                crate::ArraySize::Constant(handle) => self.module.constants[handle]
+                    .to_array_length()
+                    .ok_or(Error::Validation)?,
+                crate::ArraySize::Dynamic => {
+                    return Err(Error::FeatureNotImplemented(
+                        "runtime-sized binding arrays".to_string(),
+                    ))
+                }
+            };
+            if !space.is_empty() {
+                return Err(Error::FeatureNotImplemented(
+                    "binding arrays of buffers".to_string(),
+                ));
+            }
+            return Ok(write!(
+                out,
+                "{}::array<{}, {}> {}",
+                NAMESPACE, ty_name, length, name,
+            )?);
+        }
+
         Ok(write!(
             out,
             "{}{}{}{}{}{} {}",
@@ -393,12 +432,13 @@ impl crate::Type {
             Ti::Scalar { .. }
             | Ti::Vector { .. }
             | Ti::Matrix { .. }
+            | Ti::Atomic { .. }
             | Ti::Pointer { .. }
             | Ti::ValuePointer { .. } => self.name.is_some(),
             // composite types are better to be aliased, regardless of the name
             Ti::Struct { .. } | Ti::Array { .. } => true,
             // handle types may be different, depending on the global var access, so we always inline them
-            Ti::Image { .. } | Ti::Sampler { .. } => false,
+            Ti::Image { .. } | Ti::Sampler { .. } | Ti::BindingArray { .. } => false,
         }
     }
 }
@@ -407,12 +447,64 @@ impl crate::Constant {
     // Returns `true` if we need to emit an alias for this constant.
     fn needs_alias(&self) -> bool {
         match self.inner {
-            crate::ConstantInner::Scalar { .. } => self.name.is_some(),
+            crate::ConstantInner::Scalar { .. } => {
+                self.name.is_some() || self.specialization.is_some()
+            }
             crate::ConstantInner::Composite { .. } => true,
         }
     }
 }
 
+/// A math function Metal has no intrinsic for, written as a helper function
+/// for each argument type.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+enum WrappedMath {
+    Outer {
+        columns: crate::VectorSize,
+        rows: crate::VectorSize,
+    },
+    Inverse(crate::VectorSize),
+}
+
+impl WrappedMath {
+    fn new(
+        fun: crate::MathFunction,
+        arg: &crate::TypeInner,
+        arg1: Option<&crate::TypeInner>,
+    ) -> Option<Self> {
+        use crate::TypeInner as Ti;
+
+        match (fun, arg, arg1) {
+            (
+                crate::MathFunction::Outer,
+                &Ti::Vector { size: rows, .. },
+                Some(&Ti::Vector { size: columns, .. }),
+            ) => Some(WrappedMath::Outer { columns, rows }),
+            (crate::MathFunction::Inverse, &Ti::Matrix { columns, .. }, None) => {
+                Some(WrappedMath::Inverse(columns))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Display for WrappedMath {
+    fn fmt(&self, out: &mut Formatter<'_>) -> Result<(), FmtError> {
+        match *self {
+            WrappedMath::Outer { columns, rows } => write!(
+                out,
+                "NagaOuter{}x{}",
+                back::vector_size_str(columns),
+                back::vector_size_str(rows)
+            ),
+            WrappedMath::Inverse(size) => {
+                let size = back::vector_size_str(size);
+                write!(out, "NagaInverse{}x{}", size, size)
+            }
+        }
+    }
+}
+
 enum FunctionOrigin {
     Handle(Handle<crate::Function>),
     EntryPoint(proc::EntryPointIndex),
@@ -424,18 +516,55 @@ struct ExpressionContext<'a> {
     info: &'a valid::FunctionInfo,
     module: &'a crate::Module,
     pipeline_options: &'a PipelineOptions,
+    policy: IndexBoundsCheckPolicy,
 }
 
 impl<'a> ExpressionContext<'a> {
     fn resolve_type(&self, handle: Handle<crate::Expression>) -> &'a crate::TypeInner {
         self.info[handle].ty.inner_with(&self.module.types)
     }
+
+    /// Returns `true` if the expression is a pointer.
+    fn resolve_is_pointer(&self, handle: Handle<crate::Expression>) -> bool {
+        match *self.resolve_type(handle) {
+            crate::TypeInner::Pointer { .. } | crate::TypeInner::ValuePointer { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the indices of the access chain `pointer` that need a bounds check.
+    ///
+    /// This is always empty unless the `ReadZeroSkipWrite` policy is used.
+    fn read_zero_skip_write_checks(
+        &self,
+        pointer: Handle<crate::Expression>,
+    ) -> Vec<proc::BoundsCheck> {
+        match self.policy {
+            IndexBoundsCheckPolicy::ReadZeroSkipWrite => {
+                proc::bounds_check_iter(pointer, self.module, &self.function.expressions, self.info)
+                    .collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns `true` if the expression is a pointer to an atomic.
+    fn resolve_pointee_is_atomic(&self, handle: Handle<crate::Expression>) -> bool {
+        match *self.resolve_type(handle) {
+            crate::TypeInner::Pointer { base, .. } => match self.module.types[base].inner {
+                crate::TypeInner::Atomic { .. } => true,
+                _ => false,
+            },
+            _ => false,
+        }
+    }
 }
 
 struct StatementContext<'a> {
     expression: ExpressionContext<'a>,
     mod_info: &'a valid::ModuleInfo,
     result_struct: Option<&'a str>,
+    lang_version: (u8, u8),
 }
 
 impl<W: Write> Writer<W> {
@@ -693,6 +822,219 @@ impl<W: Write> Writer<W> {
         }
     }
 
+    /// Write the coordinates of an image access, per the bounds check policy.
+    ///
+    /// With the `Restrict` policy, the coordinates and the array index are
+    /// clamped to the size of the image.
+    fn put_image_address(
+        &mut self,
+        address: &ImageAddress,
+        context: &ExpressionContext,
+    ) -> BackendResult {
+        if context.policy == IndexBoundsCheckPolicy::Restrict {
+            write!(self.out, "{}::min(", NAMESPACE)?;
+            self.put_storage_image_coordinate(address.coordinate, context)?;
+            write!(self.out, ", ")?;
+            self.put_image_texel_size(address, context)?;
+            write!(self.out, " - 1)")?;
+        } else {
+            self.put_storage_image_coordinate(address.coordinate, context)?;
+        }
+        if let Some(array_index) = address.array_index {
+            write!(self.out, ", ")?;
+            self.put_image_index(address.image, array_index, "array_size", context)?;
+        }
+        Ok(())
+    }
+
+    /// Write an array, level or sample index of an image access, per the bounds check policy.
+    ///
+    /// The `limit` is the name of the image query that gives the number of valid indices.
+    fn put_image_index(
+        &mut self,
+        image: Handle<crate::Expression>,
+        index: Handle<crate::Expression>,
+        limit: &str,
+        context: &ExpressionContext,
+    ) -> BackendResult {
+        if context.policy == IndexBoundsCheckPolicy::Restrict {
+            write!(self.out, "{}::min({}::uint(", NAMESPACE, NAMESPACE)?;
+            self.put_expression(index, context, true)?;
+            write!(self.out, "), ")?;
+            self.put_image_query(image, limit, None, context)?;
+            write!(self.out, " - 1)")?;
+        } else {
+            self.put_expression(index, context, true)?;
+        }
+        Ok(())
+    }
+
+    /// Write the size of the image accessed by `address`, as unsigned texel coordinates.
+    fn put_image_texel_size(
+        &mut self,
+        address: &ImageAddress,
+        context: &ExpressionContext,
+    ) -> BackendResult {
+        let queries: &[&str] = match address.dim {
+            crate::ImageDimension::D1 => {
+                // 1D textures have neither levels nor a vector size
+                return self.put_image_query(address.image, "width", None, context);
+            }
+            crate::ImageDimension::D2 | crate::ImageDimension::Cube => &["width", "height"],
+            crate::ImageDimension::D3 => &["width", "height", "depth"],
+        };
+        write!(self.out, "{}::uint{}(", NAMESPACE, queries.len())?;
+        for (i, query) in queries.iter().enumerate() {
+            if i != 0 {
+                write!(self.out, ", ")?;
+            }
+            self.put_expression(address.image, context, false)?;
+            write!(self.out, ".get_{}(", query)?;
+            if let Some(level) = address.level {
+                self.put_image_index(address.image, level, "num_mip_levels", context)?;
+            }
+            write!(self.out, ")")?;
+        }
+        write!(self.out, ")")?;
+        Ok(())
+    }
+
+    /// Write the conditions under which an image access is in bounds.
+    fn put_image_bounds_checks(
+        &mut self,
+        address: &ImageAddress,
+        context: &ExpressionContext,
+    ) -> BackendResult {
+        let limits = [
+            (address.level, "num_mip_levels"),
+            (address.sample, "num_samples"),
+            (address.array_index, "array_size"),
+        ];
+        for &(index, limit) in limits.iter() {
+            if let Some(index) = index {
+                write!(self.out, "{}::uint(", NAMESPACE)?;
+                self.put_expression(index, context, true)?;
+                write!(self.out, ") < ")?;
+                self.put_image_query(address.image, limit, None, context)?;
+                write!(self.out, " && ")?;
+            }
+        }
+
+        let is_vector = match *context.resolve_type(address.coordinate) {
+            crate::TypeInner::Vector { .. } => true,
+            _ => false,
+        };
+        if is_vector {
+            write!(self.out, "{}::all(", NAMESPACE)?;
+        }
+        self.put_storage_image_coordinate(address.coordinate, context)?;
+        write!(self.out, " < ")?;
+        self.put_image_texel_size(address, context)?;
+        if is_vector {
+            write!(self.out, ")")?;
+        }
+        Ok(())
+    }
+
+    /// Write an index, restricting it to be in bounds when needed.
+    ///
+    /// With the `Restrict` policy, indices are written as `metal::min(uint(index), length - 1)`.
+    fn put_index(
+        &mut self,
+        base: Handle<crate::Expression>,
+        index: proc::GuardedIndex,
+        context: &ExpressionContext,
+    ) -> BackendResult {
+        let length = match context.policy {
+            IndexBoundsCheckPolicy::Restrict => proc::access_needs_check(
+                base,
+                index,
+                context.module,
+                &context.function.expressions,
+                context.info,
+            ),
+            _ => None,
+        };
+
+        match (index, length) {
+            (proc::GuardedIndex::Known(value), None) => write!(self.out, "{}", value)?,
+            (proc::GuardedIndex::Expression(expr), None) => {
+                self.put_expression(expr, context, true)?
+            }
+            (_, Some(length)) => {
+                write!(self.out, "{}::min(", NAMESPACE)?;
+                self.put_guarded_index(index, context)?;
+                write!(self.out, ", ")?;
+                match length {
+                    proc::IndexableLength::Known(length) => write!(self.out, "{}u", length - 1)?,
+                    _ => {
+                        self.put_index_length(base, length, context)?;
+                        write!(self.out, " - 1")?;
+                    }
+                }
+                write!(self.out, ")")?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the given index as an unsigned integer.
+    fn put_guarded_index(
+        &mut self,
+        index: proc::GuardedIndex,
+        context: &ExpressionContext,
+    ) -> BackendResult {
+        match index {
+            proc::GuardedIndex::Known(value) => write!(self.out, "{}u", value)?,
+            proc::GuardedIndex::Expression(expr) => {
+                write!(self.out, "{}::uint(", NAMESPACE)?;
+                self.put_expression(expr, context, true)?;
+                write!(self.out, ")")?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the length of the indexable `base`.
+    fn put_index_length(
+        &mut self,
+        base: Handle<crate::Expression>,
+        length: proc::IndexableLength,
+        context: &ExpressionContext,
+    ) -> BackendResult {
+        match length {
+            proc::IndexableLength::Known(length) => write!(self.out, "{}u", length)?,
+            proc::IndexableLength::Specializable(constant) => {
+                let coco = ConstantContext {
+                    handle: constant,
+                    arena: &context.module.constants,
+                    names: &self.names,
+                    first_time: false,
+                };
+                write!(self.out, "{}::uint({})", NAMESPACE, coco)?;
+            }
+            proc::IndexableLength::Dynamic => self.put_array_length(base, context)?,
+        }
+        Ok(())
+    }
+
+    /// Write the bounds checks of `checks`, joined with `&&`.
+    fn put_bounds_checks(
+        &mut self,
+        checks: &[proc::BoundsCheck],
+        context: &ExpressionContext,
+    ) -> BackendResult {
+        for (i, check) in checks.iter().enumerate() {
+            if i != 0 {
+                write!(self.out, " && ")?;
+            }
+            self.put_guarded_index(check.index, context)?;
+            write!(self.out, " < ")?;
+            self.put_index_length(check.base, check.length, context)?;
+        }
+        Ok(())
+    }
+
     fn put_expression(
         &mut self,
         expr_handle: Handle<crate::Expression>,
@@ -729,13 +1071,45 @@ impl<W: Write> Writer<W> {
                         _ => false,
                     };
 
+                // With the `ReadZeroSkipWrite` policy, indexing a value (as opposed to
+                // a pointer, which is checked by `Load` and `Store`) yields zero when
+                // out of bounds.
+                let index = proc::GuardedIndex::Expression(index);
+                let check = match context.policy {
+                    IndexBoundsCheckPolicy::ReadZeroSkipWrite
+                        if !context.resolve_is_pointer(base) =>
+                    {
+                        proc::access_needs_check(
+                            base,
+                            index,
+                            context.module,
+                            &context.function.expressions,
+                            context.info,
+                        )
+                        .map(|length| proc::BoundsCheck {
+                            base,
+                            index,
+                            length,
+                        })
+                    }
+                    _ => None,
+                };
+
+                if let Some(check) = check {
+                    write!(self.out, "(")?;
+                    self.put_bounds_checks(&[check], context)?;
+                    write!(self.out, " ? ")?;
+                }
                 self.put_expression(base, context, false)?;
                 if accessing_wrapped_array {
                     write!(self.out, ".{}", WRAPPED_ARRAY_FIELD)?;
                 }
                 write!(self.out, "[")?;
-                self.put_expression(index, context, true)?;
+                self.put_index(base, index, context)?;
                 write!(self.out, "]")?;
+                if check.is_some() {
+                    write!(self.out, " : {}())", DEFAULT_CONSTRUCTIBLE)?;
+                }
             }
             crate::Expression::AccessIndex { base, index } => {
                 self.put_expression(base, context, false)?;
@@ -758,10 +1132,17 @@ impl<W: Write> Writer<W> {
                         write!(self.out, ".{}", back::COMPONENTS[index as usize])?;
                     }
                     crate::TypeInner::Matrix { .. } => {
-                        write!(self.out, "[{}]", index)?;
+                        write!(self.out, "[")?;
+                        self.put_index(base, proc::GuardedIndex::Known(index), context)?;
+                        write!(self.out, "]")?;
                     }
                     crate::TypeInner::Array { .. } => {
-                        write!(self.out, ".{}[{}]", WRAPPED_ARRAY_FIELD, index)?;
+                        write!(self.out, ".{}[", WRAPPED_ARRAY_FIELD)?;
+                        self.put_index(base, proc::GuardedIndex::Known(index), context)?;
+                        write!(self.out, "]")?;
+                    }
+                    crate::TypeInner::BindingArray { .. } => {
+                        write!(self.out, "[{}]", index)?;
                     }
                     _ => {
                         // unexpected indexing, should fail validation
@@ -857,7 +1238,20 @@ impl<W: Write> Writer<W> {
                     _ => None,
                 };
 
-                if let Some(scalar_kind) = wrap_packed_vec_scalar_kind {
+                // With the `ReadZeroSkipWrite` policy, the load yields zero if any
+                // index of the pointer's access chain is out of bounds.
+                let checks = context.read_zero_skip_write_checks(pointer);
+                if !checks.is_empty() {
+                    write!(self.out, "(")?;
+                    self.put_bounds_checks(&checks, context)?;
+                    write!(self.out, " ? ")?;
+                }
+
+                if context.resolve_pointee_is_atomic(pointer) {
+                    write!(self.out, "{}::atomic_load_explicit(&", NAMESPACE)?;
+                    self.put_expression(pointer, context, true)?;
+                    write!(self.out, ", {}::memory_order_relaxed)", NAMESPACE)?;
+                } else if let Some(scalar_kind) = wrap_packed_vec_scalar_kind {
                     write!(
                         self.out,
                         "{}::{}3(",
@@ -870,7 +1264,11 @@ impl<W: Write> Writer<W> {
                     // We don't do any dereferencing with `*` here as pointer arguments to functions
                     // are done by `&` references and not `*` pointers. These do not need to be
                     // dereferenced.
-                    self.put_expression(pointer, context, is_scoped)?;
+                    self.put_expression(pointer, context, is_scoped && checks.is_empty())?;
+                }
+
+                if !checks.is_empty() {
+                    write!(self.out, " : {}())", DEFAULT_CONSTRUCTIBLE)?;
                 }
             }
             crate::Expression::ImageSample {
@@ -881,10 +1279,13 @@ impl<W: Write> Writer<W> {
                 offset,
                 level,
                 depth_ref,
+                gather,
             } => {
-                let op = match depth_ref {
-                    Some(_) => "sample_compare",
-                    None => "sample",
+                let op = match (gather, depth_ref) {
+                    (Some(_), Some(_)) => "gather_compare",
+                    (Some(_), None) => "gather",
+                    (None, Some(_)) => "sample_compare",
+                    (None, None) => "sample",
                 };
                 self.put_expression(image, context, false)?;
                 write!(self.out, ".{}(", op)?;
@@ -900,7 +1301,9 @@ impl<W: Write> Writer<W> {
                     self.put_expression(dref, context, true)?;
                 }
 
-                self.put_image_sample_level(image, level, context)?;
+                if gather.is_none() {
+                    self.put_image_sample_level(image, level, context)?;
+                }
 
                 if let Some(constant) = offset {
                     let coco = ConstantContext {
@@ -911,6 +1314,24 @@ impl<W: Write> Writer<W> {
                     };
                     write!(self.out, ", {}", coco)?;
                 }
+                match gather {
+                    None | Some(crate::SwizzleComponent::X) => {}
+                    Some(component) => {
+                        let is_cube_map = match *context.resolve_type(image) {
+                            crate::TypeInner::Image {
+                                dim: crate::ImageDimension::Cube,
+                                ..
+                            } => true,
+                            _ => false,
+                        };
+                        // Offset always comes before component, except for cube maps
+                        if offset.is_none() && !is_cube_map {
+                            write!(self.out, ", {}::int2(0)", NAMESPACE)?;
+                        }
+                        let letter = back::COMPONENTS[component as usize];
+                        write!(self.out, ", {}::component::{}", NAMESPACE, letter)?;
+                    }
+                }
                 write!(self.out, ")")?;
             }
             crate::Expression::ImageLoad {
@@ -919,18 +1340,48 @@ impl<W: Write> Writer<W> {
                 array_index,
                 index,
             } => {
+                let (dim, class) = match *context.resolve_type(image) {
+                    crate::TypeInner::Image { dim, class, .. } => (dim, class),
+                    _ => return Err(Error::Validation),
+                };
+                let (level, sample) = match class {
+                    crate::ImageClass::Sampled { multi: true, .. } => (None, index),
+                    _ => (index, None),
+                };
+                let address = ImageAddress {
+                    image,
+                    class,
+                    dim,
+                    coordinate,
+                    array_index,
+                    level,
+                    sample,
+                };
+
+                // With the `ReadZeroSkipWrite` policy, out of bounds loads yield zero.
+                let checked = context.policy == IndexBoundsCheckPolicy::ReadZeroSkipWrite;
+                if checked {
+                    write!(self.out, "(")?;
+                    self.put_image_bounds_checks(&address, context)?;
+                    write!(self.out, " ? ")?;
+                }
+
                 self.put_expression(image, context, false)?;
                 write!(self.out, ".read(")?;
-                self.put_storage_image_coordinate(coordinate, context)?;
-                if let Some(expr) = array_index {
+                self.put_image_address(&address, context)?;
+                if let Some(level) = level {
                     write!(self.out, ", ")?;
-                    self.put_expression(expr, context, true)?;
+                    self.put_image_index(image, level, "num_mip_levels", context)?;
                 }
-                if let Some(index) = index {
+                if let Some(sample) = sample {
                     write!(self.out, ", ")?;
-                    self.put_expression(index, context, true)?;
+                    self.put_image_index(image, sample, "num_samples", context)?;
                 }
                 write!(self.out, ")")?;
+
+                if checked {
+                    write!(self.out, " : {}())", DEFAULT_CONSTRUCTIBLE)?;
+                }
             }
             //Note: for all the queries, the signed integers are expected,
             // so a conversion is needed.
@@ -1047,13 +1498,16 @@ impl<W: Write> Writer<W> {
                 arg,
                 arg1,
                 arg2,
+                arg3,
             } => {
                 use crate::MathFunction as Mf;
 
-                let scalar_argument = match *context.resolve_type(arg) {
+                let arg_type = context.resolve_type(arg);
+                let scalar_argument = match *arg_type {
                     crate::TypeInner::Scalar { .. } => true,
                     _ => false,
                 };
+                let signed_argument = arg_type.scalar_kind() == Some(crate::ScalarKind::Sint);
 
                 let fun_name = match fun {
                     // comparison
@@ -1089,7 +1543,7 @@ impl<W: Write> Writer<W> {
                     Mf::Pow => "pow",
                     // geometry
                     Mf::Dot => "dot",
-                    Mf::Outer => return Err(Error::UnsupportedCall(format!("{:?}", fun))),
+                    Mf::Outer => "",
                     Mf::Cross => "cross",
                     Mf::Distance => "distance",
                     Mf::Length if scalar_argument => "abs",
@@ -1106,23 +1560,88 @@ impl<W: Write> Writer<W> {
                     Mf::SmoothStep => "smoothstep",
                     Mf::Sqrt => "sqrt",
                     Mf::InverseSqrt => "rsqrt",
-                    Mf::Inverse => return Err(Error::UnsupportedCall(format!("{:?}", fun))),
+                    Mf::Inverse => "",
                     Mf::Transpose => "transpose",
                     Mf::Determinant => "determinant",
                     // bits
                     Mf::CountOneBits => "popcount",
                     Mf::ReverseBits => "reverse_bits",
+                    Mf::ExtractBits => "extract_bits",
+                    Mf::InsertBits => "insert_bits",
+                    Mf::FirstTrailingBit => "ctz",
+                    Mf::FirstLeadingBit => "clz",
+                    // data packing
+                    Mf::Pack4x8snorm => "pack_float_to_snorm4x8",
+                    Mf::Pack4x8unorm => "pack_float_to_unorm4x8",
+                    Mf::Pack2x16snorm => "pack_float_to_snorm2x16",
+                    Mf::Pack2x16unorm => "pack_float_to_unorm2x16",
+                    Mf::Pack2x16float => "",
+                    // data unpacking
+                    Mf::Unpack4x8snorm => "unpack_snorm4x8_to_float",
+                    Mf::Unpack4x8unorm => "unpack_unorm4x8_to_float",
+                    Mf::Unpack2x16snorm => "unpack_snorm2x16_to_float",
+                    Mf::Unpack2x16unorm => "unpack_unorm2x16_to_float",
+                    Mf::Unpack2x16float => "",
                 };
 
-                if fun == Mf::Distance && scalar_argument {
-                    write!(self.out, "{}::abs(", NAMESPACE)?;
-                    self.put_expression(arg, context, false)?;
-                    write!(self.out, " - ")?;
-                    self.put_expression(arg1.unwrap(), context, false)?;
-                    write!(self.out, ")")?;
-                } else {
-                    write!(self.out, "{}::{}", NAMESPACE, fun_name)?;
-                    self.put_call_parameters(iter::once(arg).chain(arg1).chain(arg2), context)?;
+                match fun {
+                    Mf::Distance if scalar_argument => {
+                        write!(self.out, "{}::abs(", NAMESPACE)?;
+                        self.put_expression(arg, context, false)?;
+                        write!(self.out, " - ")?;
+                        self.put_expression(arg1.unwrap(), context, false)?;
+                        write!(self.out, ")")?;
+                    }
+                    // `ctz` and `clz` return the bit width for zero,
+                    // which is turned into -1 here
+                    Mf::FirstTrailingBit => {
+                        write!(self.out, "((({}::ctz(", NAMESPACE)?;
+                        self.put_expression(arg, context, true)?;
+                        write!(self.out, ") + 1) % 33) - 1)")?;
+                    }
+                    Mf::FirstLeadingBit => {
+                        write!(self.out, "(31 - {}::clz(", NAMESPACE)?;
+                        if signed_argument {
+                            // negative values look for the leading zero bit instead
+                            write!(self.out, "{}::select(", NAMESPACE)?;
+                            self.put_expression(arg, context, true)?;
+                            write!(self.out, ", ~")?;
+                            self.put_expression(arg, context, false)?;
+                            write!(self.out, ", ")?;
+                            self.put_expression(arg, context, false)?;
+                            write!(self.out, " < 0)")?;
+                        } else {
+                            self.put_expression(arg, context, true)?;
+                        }
+                        write!(self.out, "))")?;
+                    }
+                    Mf::Outer | Mf::Inverse => {
+                        let math = WrappedMath::new(
+                            fun,
+                            arg_type,
+                            arg1.map(|arg1| context.resolve_type(arg1)),
+                        )
+                        .ok_or(Error::Validation)?;
+                        write!(self.out, "{}", math)?;
+                        self.put_call_parameters(iter::once(arg).chain(arg1), context)?;
+                    }
+                    Mf::Pack2x16float => {
+                        write!(self.out, "as_type<uint>(half2(")?;
+                        self.put_expression(arg, context, true)?;
+                        write!(self.out, "))")?;
+                    }
+                    Mf::Unpack2x16float => {
+                        write!(self.out, "float2(as_type<half2>(")?;
+                        self.put_expression(arg, context, true)?;
+                        write!(self.out, "))")?;
+                    }
+                    _ => {
+                        write!(self.out, "{}::{}", NAMESPACE, fun_name)?;
+                        self.put_call_parameters(
+                            iter::once(arg).chain(arg1).chain(arg2).chain(arg3),
+                            context,
+                        )?;
+                    }
                 }
             }
             crate::Expression::As {
@@ -1151,7 +1670,10 @@ impl<W: Write> Writer<W> {
                 write!(self.out, ")")?;
             }
             // has to be a named expression
-            crate::Expression::Call(_) => unreachable!(),
+            crate::Expression::Call(_)
+            | crate::Expression::AtomicResult { .. }
+            | crate::Expression::SubgroupBallotResult
+            | crate::Expression::SubgroupOperationResult { .. } => unreachable!(),
             crate::Expression::ArrayLength(expr) => {
                 self.put_array_length(expr, context)?;
             }
@@ -1303,6 +1825,16 @@ impl<W: Write> Writer<W> {
         for statement in statements {
             log::trace!("statement[{}] {:?}", level.0, statement);
             match *statement {
+                // SIMD-group functions need Metal 2.1
+                crate::Statement::SubgroupBallot { .. }
+                | crate::Statement::SubgroupCollectiveOperation { .. }
+                | crate::Statement::SubgroupGather { .. }
+                    if context.lang_version < (2, 1) =>
+                {
+                    return Err(Error::FeatureNotImplemented(
+                        "subgroup operations before Metal 2.1".to_string(),
+                    ));
+                }
                 crate::Statement::Emit(ref range) => {
                     for handle in range.clone() {
                         let expr_name = if let Some(name) =
@@ -1443,6 +1975,18 @@ impl<W: Write> Writer<W> {
                     }
                 }
                 crate::Statement::Store { pointer, value } => {
+                    // With the `ReadZeroSkipWrite` policy, the store is skipped if any
+                    // index of the pointer's access chain is out of bounds.
+                    let checks = context.expression.read_zero_skip_write_checks(pointer);
+                    let level = if checks.is_empty() {
+                        level.clone()
+                    } else {
+                        write!(self.out, "{}if (", level)?;
+                        self.put_bounds_checks(&checks, &context.expression)?;
+                        writeln!(self.out, ") {{")?;
+                        level.next()
+                    };
+
                     // we can't assign fixed-size arrays
                     let pointer_info = &context.expression.info[pointer];
                     let array_size =
@@ -1469,6 +2013,13 @@ impl<W: Write> Writer<W> {
                             self.put_expression(value, &context.expression, true)?;
                             writeln!(self.out, ".{}[_i];", WRAPPED_ARRAY_FIELD)?;
                         }
+                        None if context.expression.resolve_pointee_is_atomic(pointer) => {
+                            write!(self.out, "{}{}::atomic_store_explicit(&", level, NAMESPACE)?;
+                            self.put_expression(pointer, &context.expression, true)?;
+                            write!(self.out, ", ")?;
+                            self.put_expression(value, &context.expression, true)?;
+                            writeln!(self.out, ", {}::memory_order_relaxed);", NAMESPACE)?;
+                        }
                         None => {
                             write!(self.out, "{}", level)?;
                             self.put_expression(pointer, &context.expression, true)?;
@@ -1477,6 +2028,10 @@ impl<W: Write> Writer<W> {
                             writeln!(self.out, ";")?;
                         }
                     }
+
+                    if !checks.is_empty() {
+                        writeln!(self.out, "{}}}", Level(level.0 - 1))?;
+                    }
                 }
                 crate::Statement::ImageStore {
                     image,
@@ -1484,17 +2039,43 @@ impl<W: Write> Writer<W> {
                     array_index,
                     value,
                 } => {
-                    write!(self.out, "{}", level)?;
+                    let (dim, class) = match *context.expression.resolve_type(image) {
+                        crate::TypeInner::Image { dim, class, .. } => (dim, class),
+                        _ => return Err(Error::Validation),
+                    };
+                    let address = ImageAddress {
+                        image,
+                        class,
+                        dim,
+                        coordinate,
+                        array_index,
+                        level: None,
+                        sample: None,
+                    };
+
+                    // With the `ReadZeroSkipWrite` policy, out of bounds stores are skipped.
+                    let checked =
+                        context.expression.policy == IndexBoundsCheckPolicy::ReadZeroSkipWrite;
+                    let store_level = if checked {
+                        write!(self.out, "{}if (", level)?;
+                        self.put_image_bounds_checks(&address, &context.expression)?;
+                        writeln!(self.out, ") {{")?;
+                        level.next()
+                    } else {
+                        level.clone()
+                    };
+
+                    write!(self.out, "{}", store_level)?;
                     self.put_expression(image, &context.expression, false)?;
                     write!(self.out, ".write(")?;
                     self.put_expression(value, &context.expression, true)?;
                     write!(self.out, ", ")?;
-                    self.put_storage_image_coordinate(coordinate, &context.expression)?;
-                    if let Some(expr) = array_index {
-                        write!(self.out, ", ")?;
-                        self.put_expression(expr, &context.expression, true)?;
-                    }
+                    self.put_image_address(&address, &context.expression)?;
                     writeln!(self.out, ");")?;
+
+                    if checked {
+                        writeln!(self.out, "{}}}", level)?;
+                    }
                 }
                 crate::Statement::Call {
                     function,
@@ -1546,6 +2127,152 @@ impl<W: Write> Writer<W> {
                     // done
                     writeln!(self.out, ");")?;
                 }
+                crate::Statement::Atomic {
+                    pointer,
+                    ref fun,
+                    value,
+                    result,
+                } => {
+                    let name = format!("{}{}", back::BAKE_PREFIX, result.index());
+                    let (kind, compare) = match *fun {
+                        crate::AtomicFunction::Add => ("fetch_add", None),
+                        crate::AtomicFunction::Subtract => ("fetch_sub", None),
+                        crate::AtomicFunction::And => ("fetch_and", None),
+                        crate::AtomicFunction::InclusiveOr => ("fetch_or", None),
+                        crate::AtomicFunction::ExclusiveOr => ("fetch_xor", None),
+                        crate::AtomicFunction::Min => ("fetch_min", None),
+                        crate::AtomicFunction::Max => ("fetch_max", None),
+                        crate::AtomicFunction::Exchange { compare: None } => ("exchange", None),
+                        crate::AtomicFunction::Exchange { compare: Some(cmp) } => {
+                            ("compare_exchange_weak", Some(cmp))
+                        }
+                    };
+                    let scalar = match *context.expression.resolve_type(result) {
+                        crate::TypeInner::Scalar { kind, .. }
+                        | crate::TypeInner::Vector { kind, .. } => scalar_kind_string(kind),
+                        _ => unreachable!(),
+                    };
+                    match compare {
+                        // The expected value is updated in-place with the old one,
+                        // so we compose the result vector afterwards.
+                        Some(cmp) => {
+                            write!(self.out, "{}{} {}_old = ", level, scalar, name)?;
+                            self.put_expression(cmp, &context.expression, true)?;
+                            writeln!(self.out, ";")?;
+                            write!(
+                                self.out,
+                                "{}bool {}_exchanged = {}::atomic_{}_explicit(&",
+                                level, name, NAMESPACE, kind
+                            )?;
+                            self.put_expression(pointer, &context.expression, true)?;
+                            write!(self.out, ", &{}_old, ", name)?;
+                            self.put_expression(value, &context.expression, true)?;
+                            writeln!(
+                                self.out,
+                                ", {}::memory_order_relaxed, {}::memory_order_relaxed);",
+                                NAMESPACE, NAMESPACE
+                            )?;
+                            writeln!(
+                                self.out,
+                                "{}{}::{}2 {} = {}::{}2({}_old, {}_exchanged ? 1 : 0);",
+                                level, NAMESPACE, scalar, name, NAMESPACE, scalar, name, name
+                            )?;
+                        }
+                        None => {
+                            write!(self.out, "{}", level)?;
+                            self.start_baking_expression(result, &context.expression, &name)?;
+                            write!(self.out, "{}::atomic_{}_explicit(&", NAMESPACE, kind)?;
+                            self.put_expression(pointer, &context.expression, true)?;
+                            write!(self.out, ", ")?;
+                            self.put_expression(value, &context.expression, true)?;
+                            writeln!(self.out, ", {}::memory_order_relaxed);", NAMESPACE)?;
+                        }
+                    }
+                    self.named_expressions.insert(result, name);
+                }
+                crate::Statement::SubgroupBallot { result, predicate } => {
+                    let name = format!("{}{}", back::BAKE_PREFIX, result.index());
+                    write!(
+                        self.out,
+                        "{}uint64_t {}_vote = static_cast<uint64_t>({}::simd_ballot(",
+                        level, name, NAMESPACE
+                    )?;
+                    match predicate {
+                        Some(predicate) => {
+                            self.put_expression(predicate, &context.expression, true)?
+                        }
+                        None => write!(self.out, "true")?,
+                    }
+                    writeln!(self.out, "));")?;
+                    writeln!(
+                        self.out,
+                        "{}{}::uint4 {} = {}::uint4(uint({}_vote), uint({}_vote >> 32), 0, 0);",
+                        level, NAMESPACE, name, NAMESPACE, name, name
+                    )?;
+                    self.named_expressions.insert(result, name);
+                }
+                crate::Statement::SubgroupCollectiveOperation {
+                    op,
+                    collective_op,
+                    argument,
+                    result,
+                } => {
+                    use crate::{CollectiveOperation as Co, SubgroupOperation as So};
+
+                    let fun_name = match (collective_op, op) {
+                        (Co::Reduce, So::All) => "simd_all",
+                        (Co::Reduce, So::Any) => "simd_any",
+                        (Co::Reduce, So::Add) => "simd_sum",
+                        (Co::Reduce, So::Mul) => "simd_product",
+                        (Co::Reduce, So::Min) => "simd_min",
+                        (Co::Reduce, So::Max) => "simd_max",
+                        (Co::Reduce, So::And) => "simd_and",
+                        (Co::Reduce, So::Or) => "simd_or",
+                        (Co::Reduce, So::Xor) => "simd_xor",
+                        (Co::InclusiveScan, So::Add) => "simd_prefix_inclusive_sum",
+                        (Co::InclusiveScan, So::Mul) => "simd_prefix_inclusive_product",
+                        (Co::ExclusiveScan, So::Add) => "simd_prefix_exclusive_sum",
+                        (Co::ExclusiveScan, So::Mul) => "simd_prefix_exclusive_product",
+                        _ => {
+                            return Err(Error::FeatureNotImplemented(format!(
+                                "subgroup {:?} of {:?}",
+                                collective_op, op
+                            )))
+                        }
+                    };
+                    let name = format!("{}{}", back::BAKE_PREFIX, result.index());
+                    write!(self.out, "{}", level)?;
+                    self.start_baking_expression(result, &context.expression, &name)?;
+                    write!(self.out, "{}::{}(", NAMESPACE, fun_name)?;
+                    self.put_expression(argument, &context.expression, true)?;
+                    writeln!(self.out, ");")?;
+                    self.named_expressions.insert(result, name);
+                }
+                crate::Statement::SubgroupGather {
+                    mode,
+                    argument,
+                    result,
+                } => {
+                    let (fun_name, index) = match mode {
+                        crate::GatherMode::BroadcastFirst => ("simd_broadcast_first", None),
+                        crate::GatherMode::Broadcast(index) => ("simd_broadcast", Some(index)),
+                        crate::GatherMode::Shuffle(index) => ("simd_shuffle", Some(index)),
+                        crate::GatherMode::ShuffleDown(index) => ("simd_shuffle_down", Some(index)),
+                        crate::GatherMode::ShuffleUp(index) => ("simd_shuffle_up", Some(index)),
+                        crate::GatherMode::ShuffleXor(index) => ("simd_shuffle_xor", Some(index)),
+                    };
+                    let name = format!("{}{}", back::BAKE_PREFIX, result.index());
+                    write!(self.out, "{}", level)?;
+                    self.start_baking_expression(result, &context.expression, &name)?;
+                    write!(self.out, "{}::{}(", NAMESPACE, fun_name)?;
+                    self.put_expression(argument, &context.expression, true)?;
+                    if let Some(index) = index {
+                        write!(self.out, ", ")?;
+                        self.put_expression(index, &context.expression, true)?;
+                    }
+                    writeln!(self.out, ");")?;
+                    self.named_expressions.insert(result, name);
+                }
             }
         }
 
@@ -1604,9 +2331,20 @@ impl<W: Write> Writer<W> {
             }
         };
 
-        self.write_scalar_constants(module)?;
+        if options.index_bounds_check_policy == IndexBoundsCheckPolicy::ReadZeroSkipWrite {
+            writeln!(self.out, "struct {} {{", DEFAULT_CONSTRUCTIBLE)?;
+            writeln!(self.out, "{}template<typename T>", back::INDENT)?;
+            writeln!(self.out, "{}operator T() && {{", back::INDENT)?;
+            writeln!(self.out, "{}{}return T {{}};", back::INDENT, back::INDENT)?;
+            writeln!(self.out, "{}}}", back::INDENT)?;
+            writeln!(self.out, "}};")?;
+            writeln!(self.out)?;
+        }
+
+        self.write_scalar_constants(module, options)?;
         self.write_type_defs(module)?;
         self.write_composite_constants(module)?;
+        self.write_wrapped_math_functions(module, info)?;
         self.write_functions(module, info, options, pipeline_options)
     }
 
@@ -1728,29 +2466,23 @@ impl<W: Write> Writer<W> {
         Ok(())
     }
 
-    fn write_scalar_constants(&mut self, module: &crate::Module) -> BackendResult {
+    fn write_scalar_constants(
+        &mut self,
+        module: &crate::Module,
+        options: &Options,
+    ) -> BackendResult {
         for (handle, constant) in module.constants.iter() {
             match constant.inner {
                 crate::ConstantInner::Scalar {
                     width: _,
                     ref value,
-                } if constant.name.is_some() => {
-                    debug_assert!(constant.needs_alias());
-                    write!(self.out, "constexpr constant ")?;
-                    match *value {
-                        crate::ScalarValue::Sint(_) => {
-                            write!(self.out, "int")?;
-                        }
-                        crate::ScalarValue::Uint(_) => {
-                            write!(self.out, "unsigned")?;
-                        }
-                        crate::ScalarValue::Float(_) => {
-                            write!(self.out, "float")?;
-                        }
-                        crate::ScalarValue::Bool(_) => {
-                            write!(self.out, "bool")?;
-                        }
-                    }
+                } if constant.needs_alias() => {
+                    let ty_name = match *value {
+                        crate::ScalarValue::Sint(_) => "int",
+                        crate::ScalarValue::Uint(_) => "unsigned",
+                        crate::ScalarValue::Float(_) => "float",
+                        crate::ScalarValue::Bool(_) => "bool",
+                    };
                     let name = &self.names[&NameKey::Constant(handle)];
                     let coco = ConstantContext {
                         handle,
@@ -1758,7 +2490,37 @@ impl<W: Write> Writer<W> {
                         names: &self.names,
                         first_time: true,
                     };
-                    writeln!(self.out, " {} = {};", name, coco)?;
+                    match constant.specialization {
+                        // Function constants can't have an initializer, so the
+                        // default value is selected when the pipeline doesn't
+                        // provide one.
+                        Some(id) => {
+                            // function constants need Metal 1.2
+                            if options.lang_version < (1, 2) {
+                                return Err(Error::FeatureNotImplemented(
+                                    "function constants before Metal 1.2".to_string(),
+                                ));
+                            }
+                            let fc_name = self.namer.call(&format!("{}_fc", name));
+                            writeln!(
+                                self.out,
+                                "constant {} {} [[function_constant({})]];",
+                                ty_name, fc_name, id
+                            )?;
+                            writeln!(
+                                self.out,
+                                "constant {} {} = is_function_constant_defined({}) ? {} : {};",
+                                ty_name, name, fc_name, fc_name, coco
+                            )?;
+                        }
+                        None => {
+                            writeln!(
+                                self.out,
+                                "constexpr constant {} {} = {};",
+                                ty_name, name, coco
+                            )?;
+                        }
+                    }
                 }
                 _ => {}
             }
@@ -1798,6 +2560,108 @@ impl<W: Write> Writer<W> {
         Ok(())
     }
 
+    /// Writes the helper functions of the outer products and matrix inverses
+    /// used by `module`.
+    fn write_wrapped_math_functions(
+        &mut self,
+        module: &crate::Module,
+        mod_info: &valid::ModuleInfo,
+    ) -> BackendResult {
+        let functions = module
+            .functions
+            .iter()
+            .map(|(handle, function)| (function, &mod_info[handle]))
+            .chain(
+                module
+                    .entry_points
+                    .iter()
+                    .enumerate()
+                    .map(|(index, ep)| (&ep.function, mod_info.get_entry_point(index))),
+            );
+        let mut wrapped = std::collections::BTreeSet::new();
+        for (function, fun_info) in functions {
+            for (_, expression) in function.expressions.iter() {
+                if let crate::Expression::Math { fun, arg, arg1, .. } = *expression {
+                    let arg = fun_info[arg].ty.inner_with(&module.types);
+                    let arg1 = arg1.map(|arg1| fun_info[arg1].ty.inner_with(&module.types));
+                    wrapped.extend(WrappedMath::new(fun, arg, arg1));
+                }
+            }
+        }
+
+        for math in wrapped {
+            writeln!(self.out)?;
+            match math {
+                WrappedMath::Outer { columns, rows } => {
+                    let matrix = format!(
+                        "{}::float{}x{}",
+                        NAMESPACE,
+                        back::vector_size_str(columns),
+                        back::vector_size_str(rows)
+                    );
+                    writeln!(
+                        self.out,
+                        "{} {}({}::float{} a, {}::float{} b) {{",
+                        matrix,
+                        math,
+                        NAMESPACE,
+                        back::vector_size_str(rows),
+                        NAMESPACE,
+                        back::vector_size_str(columns)
+                    )?;
+                    // column `j` is `a * b[j]`
+                    write!(self.out, "{}return {}(", back::INDENT, matrix)?;
+                    for (index, component) in
+                        back::COMPONENTS[..columns as usize].iter().enumerate()
+                    {
+                        if index != 0 {
+                            write!(self.out, ", ")?;
+                        }
+                        write!(self.out, "a * b.{}", component)?;
+                    }
+                    writeln!(self.out, ");")?;
+                }
+                // Same as the HLSL helpers: the formulas hold on the columns
+                // as well as on the rows, since the inverse commutes with the
+                // transposition.
+                WrappedMath::Inverse(size) => {
+                    let size = back::vector_size_str(size);
+                    let matrix = format!("{}::float{}x{}", NAMESPACE, size, size);
+                    writeln!(self.out, "{} {}({} m) {{", matrix, math, matrix)?;
+                    match size {
+                        "2" => writeln!(
+                            self.out,
+                            "{}return {}(m[1][1], -m[0][1], -m[1][0], m[0][0]) / {}::determinant(m);",
+                            back::INDENT,
+                            matrix,
+                            NAMESPACE
+                        )?,
+                        "3" => {
+                            writeln!(
+                                self.out,
+                                "{}{} adj = {}::transpose({}({ns}::cross(m[1], m[2]), {ns}::cross(m[2], m[0]), {ns}::cross(m[0], m[1])));",
+                                back::INDENT,
+                                matrix,
+                                NAMESPACE,
+                                matrix,
+                                ns = NAMESPACE
+                            )?;
+                            writeln!(
+                                self.out,
+                                "{}return adj / {ns}::dot(m[0], {ns}::cross(m[1], m[2]));",
+                                back::INDENT,
+                                ns = NAMESPACE
+                            )?;
+                        }
+                        _ => back::write_inverse_4x4(&mut self.out, "float", &matrix)?,
+                    }
+                }
+            }
+            writeln!(self.out, "}}")?;
+        }
+        Ok(())
+    }
+
     fn put_inline_sampler_properties(
         &mut self,
         level: Level,
@@ -1994,9 +2858,11 @@ impl<W: Write> Writer<W> {
                     info: fun_info,
                     module,
                     pipeline_options,
+                    policy: options.index_bounds_check_policy,
                 },
                 mod_info,
                 result_struct: None,
+                lang_version: options.lang_version,
             };
             self.named_expressions.clear();
             self.put_block(Level(1), &fun.body, &context)?;
@@ -2010,7 +2876,9 @@ impl<W: Write> Writer<W> {
             let fun = &ep.function;
             let fun_info = mod_info.get_entry_point(ep_index);
             let mut ep_error = None;
-            let mut supports_array_length = false;
+            let supports_array_length = module.global_variables.iter().any(|(var_handle, var)| {
+                !fun_info[var_handle].is_empty() && needs_array_length(var.ty, &module.types)
+            });
 
             // skip this entry point if any global bindings are missing,
             // or their types are incompatible.
@@ -2042,7 +2910,6 @@ impl<W: Write> Writer<W> {
                             break;
                         }
                     }
-                    supports_array_length |= needs_array_length(var.ty, &module.types);
                 }
                 if supports_array_length {
                     if let Err(err) = options.resolve_sizes_buffer(ep.stage) {
@@ -2245,6 +3112,15 @@ impl<W: Write> Writer<W> {
                     }
                 }
 
+                if let crate::TypeInner::BindingArray { .. } = module.types[var.ty].inner {
+                    // arrays of textures and samplers need Metal 2.0
+                    if options.lang_version < (2, 0) {
+                        return Err(Error::FeatureNotImplemented(
+                            "binding arrays before Metal 2.0".to_string(),
+                        ));
+                    }
+                }
+
                 let tyvar = TypedGlobalVariable {
                     module,
                     names: &self.names,
@@ -2416,9 +3292,11 @@ impl<W: Write> Writer<W> {
                     info: fun_info,
                     module,
                     pipeline_options,
+                    policy: options.index_bounds_check_policy,
                 },
                 mod_info,
                 result_struct: Some(&stage_out_name),
+                lang_version: options.lang_version,
             };
             self.named_expressions.clear();
             self.put_block(Level(1), &fun.body, &context)?;
@@ -2437,30 +3315,41 @@ fn test_stack_size() {
     use crate::valid::{Capabilities, ValidationFlags};
     // create a module with at least one expression nested
     let mut module = crate::Module::default();
-    let constant = module.constants.append(crate::Constant {
-        name: None,
-        specialization: None,
-        inner: crate::ConstantInner::Scalar {
-            value: crate::ScalarValue::Float(1.0),
-            width: 4,
+    let constant = module.constants.append(
+        crate::Constant {
+            name: None,
+            specialization: None,
+            inner: crate::ConstantInner::Scalar {
+                value: crate::ScalarValue::Float(1.0),
+                width: 4,
+            },
         },
-    });
+        Default::default(),
+    );
     let mut fun = crate::Function::default();
     let const_expr = fun
         .expressions
-        .append(crate::Expression::Constant(constant));
-    let nested_expr = fun.expressions.append(crate::Expression::Unary {
-        op: crate::UnaryOperator::Negate,
-        expr: const_expr,
-    });
-    fun.body
-        .push(crate::Statement::Emit(fun.expressions.range_from(1)));
-    fun.body.push(crate::Statement::If {
-        condition: nested_expr,
-        accept: Vec::new(),
-        reject: Vec::new(),
-    });
-    let _ = module.functions.append(fun);
+        .append(crate::Expression::Constant(constant), Default::default());
+    let nested_expr = fun.expressions.append(
+        crate::Expression::Unary {
+            op: crate::UnaryOperator::Negate,
+            expr: const_expr,
+        },
+        Default::default(),
+    );
+    fun.body.push(
+        crate::Statement::Emit(fun.expressions.range_from(1)),
+        Default::default(),
+    );
+    fun.body.push(
+        crate::Statement::If {
+            condition: nested_expr,
+            accept: crate::Block::new(),
+            reject: crate::Block::new(),
+        },
+        Default::default(),
+    );
+    let _ = module.functions.append(fun, Default::default());
     // analyse the module
     let info = crate::valid::Validator::new(ValidationFlags::empty(), Capabilities::empty())
         .validate(&module)
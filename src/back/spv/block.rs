@@ -8,12 +8,12 @@ use super::{
 use crate::{arena::Handle, proc::TypeResolution};
 use spirv::Word;
 
-fn get_dimension(type_inner: &crate::TypeInner) -> Dimension {
+fn get_dimension(type_inner: &crate::TypeInner) -> Option<Dimension> {
     match *type_inner {
-        crate::TypeInner::Scalar { .. } => Dimension::Scalar,
-        crate::TypeInner::Vector { .. } => Dimension::Vector,
-        crate::TypeInner::Matrix { .. } => Dimension::Matrix,
-        _ => unreachable!(),
+        crate::TypeInner::Scalar { .. } => Some(Dimension::Scalar),
+        crate::TypeInner::Vector { .. } => Some(Dimension::Vector),
+        crate::TypeInner::Matrix { .. } => Some(Dimension::Matrix),
+        _ => None,
     }
 }
 
@@ -147,9 +147,11 @@ impl<'w> BlockContext<'w> {
                         }
                     }
                 }
-                ref other => {
-                    log::error!("wrong coordinate type {:?}", other);
-                    return Err(Error::Validation("coordinate type"));
+                _ => {
+                    return Err(Error::InvalidExpressionType(
+                        coordinates,
+                        "texture coordinates",
+                    ))
                 }
             };
 
@@ -185,6 +187,317 @@ impl<'w> BlockContext<'w> {
         })
     }
 
+    /// Construct a vector of `size` out of copies of a scalar.
+    fn write_splat(
+        &mut self,
+        scalar_id: Word,
+        kind: crate::ScalarKind,
+        width: crate::Bytes,
+        size: crate::VectorSize,
+        block: &mut Block,
+    ) -> Result<Word, Error> {
+        let vector_type_id = self.get_type_id(LookupType::Local(LocalType::Value {
+            vector_size: Some(size),
+            kind,
+            width,
+            pointer_class: None,
+        }))?;
+        self.temp_list.clear();
+        self.temp_list.resize(size as usize, scalar_id);
+
+        let id = self.gen_id();
+        block.body.push(Instruction::composite_construct(
+            vector_type_id,
+            id,
+            &self.temp_list,
+        ));
+        Ok(id)
+    }
+
+    /// Apply a unary or binary `op` to matrices column by column.
+    ///
+    /// SPIR-V only has instructions multiplying matrices, everything
+    /// else has to be done on their column vectors.
+    #[allow(clippy::too_many_arguments)]
+    fn write_matrix_columns(
+        &mut self,
+        op: spirv::Op,
+        result_type_id: Word,
+        columns: crate::VectorSize,
+        rows: crate::VectorSize,
+        width: crate::Bytes,
+        operand_ids: &[Word],
+        block: &mut Block,
+    ) -> Result<Word, Error> {
+        let column_type_id = self.get_type_id(LookupType::Local(LocalType::Value {
+            vector_size: Some(rows),
+            kind: crate::ScalarKind::Float,
+            width,
+            pointer_class: None,
+        }))?;
+
+        let mut column_ids = Vec::with_capacity(columns as usize);
+        for index in 0..columns as Word {
+            let mut extracted_ids = [0; 2];
+            for (extracted_id, &operand_id) in extracted_ids.iter_mut().zip(operand_ids) {
+                *extracted_id = self.gen_id();
+                block.body.push(Instruction::composite_extract(
+                    column_type_id,
+                    *extracted_id,
+                    operand_id,
+                    &[index],
+                ));
+            }
+            let column_id = self.gen_id();
+            block.body.push(match operand_ids.len() {
+                1 => Instruction::unary(op, column_type_id, column_id, extracted_ids[0]),
+                _ => Instruction::binary(
+                    op,
+                    column_type_id,
+                    column_id,
+                    extracted_ids[0],
+                    extracted_ids[1],
+                ),
+            });
+            column_ids.push(column_id);
+        }
+
+        let id = self.gen_id();
+        block.body.push(Instruction::composite_construct(
+            result_type_id,
+            id,
+            &column_ids,
+        ));
+        Ok(id)
+    }
+
+    fn write_unary_operation(
+        &mut self,
+        expr_handle: Handle<crate::Expression>,
+        op: crate::UnaryOperator,
+        expr: Handle<crate::Expression>,
+        result_type_id: Word,
+        block: &mut Block,
+    ) -> Result<Word, Error> {
+        use crate::{ScalarKind as Sk, UnaryOperator as Uo};
+
+        let expr_id = self.cached[expr];
+        let ir_module = self.ir_module;
+        let expr_ty_inner = self.fun_info[expr].ty.inner_with(&ir_module.types);
+
+        if let (
+            Uo::Negate,
+            &crate::TypeInner::Matrix {
+                columns,
+                rows,
+                width,
+            },
+        ) = (op, expr_ty_inner)
+        {
+            return self.write_matrix_columns(
+                spirv::Op::FNegate,
+                result_type_id,
+                columns,
+                rows,
+                width,
+                &[expr_id],
+                block,
+            );
+        }
+
+        let spirv_op = match (op, expr_ty_inner.scalar_kind()) {
+            (Uo::Negate, Some(Sk::Float)) => spirv::Op::FNegate,
+            // Two's complement negation is the same for unsigned integers.
+            (Uo::Negate, Some(Sk::Sint)) | (Uo::Negate, Some(Sk::Uint)) => spirv::Op::SNegate,
+            (Uo::Negate, Some(Sk::Bool)) | (Uo::Not, Some(Sk::Bool)) => spirv::Op::LogicalNot,
+            (Uo::Not, Some(Sk::Sint)) | (Uo::Not, Some(Sk::Uint)) => spirv::Op::Not,
+            (Uo::Negate, None) | (Uo::Not, Some(Sk::Float)) | (Uo::Not, None) => {
+                return Err(Error::UnsupportedUnaryOperation(expr_handle, op));
+            }
+        };
+
+        let id = self.gen_id();
+        block
+            .body
+            .push(Instruction::unary(spirv_op, result_type_id, id, expr_id));
+        Ok(id)
+    }
+
+    fn write_binary_operation(
+        &mut self,
+        expr_handle: Handle<crate::Expression>,
+        op: crate::BinaryOperator,
+        left: Handle<crate::Expression>,
+        right: Handle<crate::Expression>,
+        result_type_id: Word,
+        block: &mut Block,
+    ) -> Result<Word, Error> {
+        use crate::{BinaryOperator as Bo, ScalarKind as Sk, TypeInner as Ti};
+
+        let unsupported = || Error::UnsupportedBinaryOperation(expr_handle, op);
+        let mut left_id = self.cached[left];
+        let mut right_id = self.cached[right];
+
+        let ir_module = self.ir_module;
+        let left_ty_inner = self.fun_info[left].ty.inner_with(&ir_module.types);
+        let right_ty_inner = self.fun_info[right].ty.inner_with(&ir_module.types);
+
+        let (mut left_dimension, mut right_dimension) =
+            match (get_dimension(left_ty_inner), get_dimension(right_ty_inner)) {
+                (Some(left_dimension), Some(right_dimension)) => (left_dimension, right_dimension),
+                _ => return Err(unsupported()),
+            };
+        let kind = left_ty_inner.scalar_kind().ok_or_else(unsupported)?;
+
+        if let Ti::Matrix {
+            columns,
+            rows,
+            width,
+        } = *left_ty_inner
+        {
+            let column_op = match op {
+                Bo::Add => Some(spirv::Op::FAdd),
+                Bo::Subtract => Some(spirv::Op::FSub),
+                _ => None,
+            };
+            if let Some(column_op) = column_op {
+                if right_dimension != Dimension::Matrix {
+                    return Err(unsupported());
+                }
+                return self.write_matrix_columns(
+                    column_op,
+                    result_type_id,
+                    columns,
+                    rows,
+                    width,
+                    &[left_id, right_id],
+                    block,
+                );
+            }
+        }
+
+        // Only float vectors can be multiplied by scalars directly,
+        // the other operations need the scalar to be splatted first.
+        if op != Bo::Multiply || kind != Sk::Float {
+            match (left_ty_inner, right_ty_inner) {
+                (&Ti::Vector { size, .. }, &Ti::Scalar { kind, width }) => {
+                    right_id = self.write_splat(right_id, kind, width, size, block)?;
+                    right_dimension = Dimension::Vector;
+                }
+                (&Ti::Scalar { kind, width }, &Ti::Vector { size, .. }) => {
+                    left_id = self.write_splat(left_id, kind, width, size, block)?;
+                    left_dimension = Dimension::Vector;
+                }
+                _ => {}
+            }
+        }
+
+        let mut preserve_order = true;
+
+        let spirv_op = match op {
+            Bo::Add => match kind {
+                Sk::Float => spirv::Op::FAdd,
+                Sk::Sint | Sk::Uint => spirv::Op::IAdd,
+                Sk::Bool => return Err(unsupported()),
+            },
+            Bo::Subtract => match kind {
+                Sk::Float => spirv::Op::FSub,
+                Sk::Sint | Sk::Uint => spirv::Op::ISub,
+                Sk::Bool => return Err(unsupported()),
+            },
+            Bo::Multiply => match (left_dimension, right_dimension) {
+                (Dimension::Scalar, Dimension::Vector) => {
+                    preserve_order = false;
+                    spirv::Op::VectorTimesScalar
+                }
+                (Dimension::Vector, Dimension::Scalar) => spirv::Op::VectorTimesScalar,
+                (Dimension::Vector, Dimension::Matrix) => spirv::Op::VectorTimesMatrix,
+                (Dimension::Scalar, Dimension::Matrix) => {
+                    preserve_order = false;
+                    spirv::Op::MatrixTimesScalar
+                }
+                (Dimension::Matrix, Dimension::Scalar) => spirv::Op::MatrixTimesScalar,
+                (Dimension::Matrix, Dimension::Vector) => spirv::Op::MatrixTimesVector,
+                (Dimension::Matrix, Dimension::Matrix) => spirv::Op::MatrixTimesMatrix,
+                (Dimension::Vector, Dimension::Vector) | (Dimension::Scalar, Dimension::Scalar) => {
+                    match kind {
+                        Sk::Float => spirv::Op::FMul,
+                        Sk::Sint | Sk::Uint => spirv::Op::IMul,
+                        Sk::Bool => return Err(unsupported()),
+                    }
+                }
+            },
+            Bo::Divide => match kind {
+                Sk::Sint => spirv::Op::SDiv,
+                Sk::Uint => spirv::Op::UDiv,
+                Sk::Float => spirv::Op::FDiv,
+                Sk::Bool => return Err(unsupported()),
+            },
+            // The remainder takes the sign of the dividend, like in the other backends.
+            Bo::Modulo => match kind {
+                Sk::Sint => spirv::Op::SRem,
+                Sk::Uint => spirv::Op::UMod,
+                Sk::Float => spirv::Op::FRem,
+                Sk::Bool => return Err(unsupported()),
+            },
+            Bo::Equal => match kind {
+                Sk::Sint | Sk::Uint => spirv::Op::IEqual,
+                Sk::Float => spirv::Op::FOrdEqual,
+                Sk::Bool => spirv::Op::LogicalEqual,
+            },
+            Bo::NotEqual => match kind {
+                Sk::Sint | Sk::Uint => spirv::Op::INotEqual,
+                Sk::Float => spirv::Op::FOrdNotEqual,
+                Sk::Bool => spirv::Op::LogicalNotEqual,
+            },
+            Bo::Less => match kind {
+                Sk::Sint => spirv::Op::SLessThan,
+                Sk::Uint => spirv::Op::ULessThan,
+                Sk::Float => spirv::Op::FOrdLessThan,
+                Sk::Bool => return Err(unsupported()),
+            },
+            Bo::LessEqual => match kind {
+                Sk::Sint => spirv::Op::SLessThanEqual,
+                Sk::Uint => spirv::Op::ULessThanEqual,
+                Sk::Float => spirv::Op::FOrdLessThanEqual,
+                Sk::Bool => return Err(unsupported()),
+            },
+            Bo::Greater => match kind {
+                Sk::Sint => spirv::Op::SGreaterThan,
+                Sk::Uint => spirv::Op::UGreaterThan,
+                Sk::Float => spirv::Op::FOrdGreaterThan,
+                Sk::Bool => return Err(unsupported()),
+            },
+            Bo::GreaterEqual => match kind {
+                Sk::Sint => spirv::Op::SGreaterThanEqual,
+                Sk::Uint => spirv::Op::UGreaterThanEqual,
+                Sk::Float => spirv::Op::FOrdGreaterThanEqual,
+                Sk::Bool => return Err(unsupported()),
+            },
+            Bo::And => spirv::Op::BitwiseAnd,
+            Bo::ExclusiveOr => spirv::Op::BitwiseXor,
+            Bo::InclusiveOr => spirv::Op::BitwiseOr,
+            Bo::LogicalAnd => spirv::Op::LogicalAnd,
+            Bo::LogicalOr => spirv::Op::LogicalOr,
+            Bo::ShiftLeft => spirv::Op::ShiftLeftLogical,
+            Bo::ShiftRight => match kind {
+                Sk::Sint => spirv::Op::ShiftRightArithmetic,
+                Sk::Uint => spirv::Op::ShiftRightLogical,
+                Sk::Float | Sk::Bool => return Err(unsupported()),
+            },
+        };
+
+        let id = self.gen_id();
+        block.body.push(Instruction::binary(
+            spirv_op,
+            result_type_id,
+            id,
+            if preserve_order { left_id } else { right_id },
+            if preserve_order { right_id } else { left_id },
+        ));
+        Ok(id)
+    }
+
     /// Decide whether to put off emitting instructions for `expr_handle`.
     ///
     /// We would like to gather together chains of `Access` and `AccessIndex`
@@ -222,6 +535,19 @@ impl<'w> BlockContext<'w> {
         let result_type_id = self.get_expression_type_id(&self.fun_info[expr_handle].ty)?;
 
         let id = match self.ir_function.expressions[expr_handle] {
+            crate::Expression::Access { base, index }
+                if self.handle_binding_array(base).is_some() =>
+            {
+                let non_uniform = self.check_non_uniform_index(base, index)?;
+                let index_id = self.cached[index];
+                self.write_binding_array_access(expr_handle, base, index_id, non_uniform, block)?
+            }
+            crate::Expression::AccessIndex { base, index }
+                if self.handle_binding_array(base).is_some() =>
+            {
+                let index_id = self.get_index_constant(index)?;
+                self.write_binding_array_access(expr_handle, base, index_id, false, block)?
+            }
             crate::Expression::Access { base, index: _ } if self.is_intermediate(base) => {
                 // See `is_intermediate`; we'll handle this later in
                 // `write_expression_pointer`.
@@ -231,15 +557,11 @@ impl<'w> BlockContext<'w> {
                 let base_ty = self.fun_info[base].ty.inner_with(&self.ir_module.types);
                 match *base_ty {
                     crate::TypeInner::Vector { .. } => (),
-                    ref other => {
-                        log::error!(
-                            "Unable to access base {:?} of type {:?}",
-                            self.ir_function.expressions[base],
-                            other
-                        );
-                        return Err(Error::Validation(
-                            "only vectors may be dynamically indexed by value",
-                        ));
+                    _ => {
+                        return Err(Error::InvalidExpressionType(
+                            base,
+                            "dynamic indexing by value",
+                        ))
                     }
                 };
 
@@ -266,10 +588,7 @@ impl<'w> BlockContext<'w> {
                         ));
                         id
                     }
-                    ref other => {
-                        log::error!("Unable to access index of {:?}", other);
-                        return Err(Error::FeatureNotImplemented("access index for type"));
-                    }
+                    _ => return Err(Error::InvalidExpressionType(base, "indexing by value")),
                 }
             }
             crate::Expression::GlobalVariable(handle) => {
@@ -327,164 +646,17 @@ impl<'w> BlockContext<'w> {
                 id
             }
             crate::Expression::Unary { op, expr } => {
-                let id = self.gen_id();
-                let expr_id = self.cached[expr];
-                let expr_ty_inner = self.fun_info[expr].ty.inner_with(&self.ir_module.types);
-
-                let spirv_op = match op {
-                    crate::UnaryOperator::Negate => match expr_ty_inner.scalar_kind() {
-                        Some(crate::ScalarKind::Float) => spirv::Op::FNegate,
-                        Some(crate::ScalarKind::Sint) => spirv::Op::SNegate,
-                        Some(crate::ScalarKind::Bool) => spirv::Op::LogicalNot,
-                        Some(crate::ScalarKind::Uint) | None => {
-                            log::error!("Unable to negate {:?}", expr_ty_inner);
-                            return Err(Error::FeatureNotImplemented("negation"));
-                        }
-                    },
-                    crate::UnaryOperator::Not => match expr_ty_inner.scalar_kind() {
-                        Some(crate::ScalarKind::Bool) => spirv::Op::LogicalNot,
-                        _ => spirv::Op::Not,
-                    },
-                };
-
-                block
-                    .body
-                    .push(Instruction::unary(spirv_op, result_type_id, id, expr_id));
-                id
+                self.write_unary_operation(expr_handle, op, expr, result_type_id, block)?
             }
             crate::Expression::Binary { op, left, right } => {
-                let id = self.gen_id();
-                let left_id = self.cached[left];
-                let right_id = self.cached[right];
-
-                let left_ty_inner = self.fun_info[left].ty.inner_with(&self.ir_module.types);
-                let right_ty_inner = self.fun_info[right].ty.inner_with(&self.ir_module.types);
-
-                let left_dimension = get_dimension(left_ty_inner);
-                let right_dimension = get_dimension(right_ty_inner);
-
-                let mut preserve_order = true;
-
-                let spirv_op = match op {
-                    crate::BinaryOperator::Add => match *left_ty_inner {
-                        crate::TypeInner::Scalar { kind, .. }
-                        | crate::TypeInner::Vector { kind, .. } => match kind {
-                            crate::ScalarKind::Float => spirv::Op::FAdd,
-                            _ => spirv::Op::IAdd,
-                        },
-                        _ => unimplemented!(),
-                    },
-                    crate::BinaryOperator::Subtract => match *left_ty_inner {
-                        crate::TypeInner::Scalar { kind, .. }
-                        | crate::TypeInner::Vector { kind, .. } => match kind {
-                            crate::ScalarKind::Float => spirv::Op::FSub,
-                            _ => spirv::Op::ISub,
-                        },
-                        _ => unimplemented!(),
-                    },
-                    crate::BinaryOperator::Multiply => match (left_dimension, right_dimension) {
-                        (Dimension::Scalar, Dimension::Vector { .. }) => {
-                            preserve_order = false;
-                            spirv::Op::VectorTimesScalar
-                        }
-                        (Dimension::Vector, Dimension::Scalar { .. }) => {
-                            spirv::Op::VectorTimesScalar
-                        }
-                        (Dimension::Vector, Dimension::Matrix) => spirv::Op::VectorTimesMatrix,
-                        (Dimension::Matrix, Dimension::Scalar { .. }) => {
-                            spirv::Op::MatrixTimesScalar
-                        }
-                        (Dimension::Matrix, Dimension::Vector) => spirv::Op::MatrixTimesVector,
-                        (Dimension::Matrix, Dimension::Matrix) => spirv::Op::MatrixTimesMatrix,
-                        (Dimension::Vector, Dimension::Vector)
-                        | (Dimension::Scalar, Dimension::Scalar)
-                            if left_ty_inner.scalar_kind() == Some(crate::ScalarKind::Float) =>
-                        {
-                            spirv::Op::FMul
-                        }
-                        (Dimension::Vector, Dimension::Vector)
-                        | (Dimension::Scalar, Dimension::Scalar) => spirv::Op::IMul,
-                        other => unimplemented!("Mul {:?}", other),
-                    },
-                    crate::BinaryOperator::Divide => match left_ty_inner.scalar_kind() {
-                        Some(crate::ScalarKind::Sint) => spirv::Op::SDiv,
-                        Some(crate::ScalarKind::Uint) => spirv::Op::UDiv,
-                        Some(crate::ScalarKind::Float) => spirv::Op::FDiv,
-                        _ => unimplemented!(),
-                    },
-                    crate::BinaryOperator::Modulo => match left_ty_inner.scalar_kind() {
-                        Some(crate::ScalarKind::Sint) => spirv::Op::SMod,
-                        Some(crate::ScalarKind::Uint) => spirv::Op::UMod,
-                        Some(crate::ScalarKind::Float) => spirv::Op::FMod,
-                        _ => unimplemented!(),
-                    },
-                    crate::BinaryOperator::Equal => match left_ty_inner.scalar_kind() {
-                        Some(crate::ScalarKind::Sint) | Some(crate::ScalarKind::Uint) => {
-                            spirv::Op::IEqual
-                        }
-                        Some(crate::ScalarKind::Float) => spirv::Op::FOrdEqual,
-                        Some(crate::ScalarKind::Bool) => spirv::Op::LogicalEqual,
-                        _ => unimplemented!(),
-                    },
-                    crate::BinaryOperator::NotEqual => match left_ty_inner.scalar_kind() {
-                        Some(crate::ScalarKind::Sint) | Some(crate::ScalarKind::Uint) => {
-                            spirv::Op::INotEqual
-                        }
-                        Some(crate::ScalarKind::Float) => spirv::Op::FOrdNotEqual,
-                        Some(crate::ScalarKind::Bool) => spirv::Op::LogicalNotEqual,
-                        _ => unimplemented!(),
-                    },
-                    crate::BinaryOperator::Less => match left_ty_inner.scalar_kind() {
-                        Some(crate::ScalarKind::Sint) => spirv::Op::SLessThan,
-                        Some(crate::ScalarKind::Uint) => spirv::Op::ULessThan,
-                        Some(crate::ScalarKind::Float) => spirv::Op::FOrdLessThan,
-                        _ => unimplemented!(),
-                    },
-                    crate::BinaryOperator::LessEqual => match left_ty_inner.scalar_kind() {
-                        Some(crate::ScalarKind::Sint) => spirv::Op::SLessThanEqual,
-                        Some(crate::ScalarKind::Uint) => spirv::Op::ULessThanEqual,
-                        Some(crate::ScalarKind::Float) => spirv::Op::FOrdLessThanEqual,
-                        _ => unimplemented!(),
-                    },
-                    crate::BinaryOperator::Greater => match left_ty_inner.scalar_kind() {
-                        Some(crate::ScalarKind::Sint) => spirv::Op::SGreaterThan,
-                        Some(crate::ScalarKind::Uint) => spirv::Op::UGreaterThan,
-                        Some(crate::ScalarKind::Float) => spirv::Op::FOrdGreaterThan,
-                        _ => unimplemented!(),
-                    },
-                    crate::BinaryOperator::GreaterEqual => match left_ty_inner.scalar_kind() {
-                        Some(crate::ScalarKind::Sint) => spirv::Op::SGreaterThanEqual,
-                        Some(crate::ScalarKind::Uint) => spirv::Op::UGreaterThanEqual,
-                        Some(crate::ScalarKind::Float) => spirv::Op::FOrdGreaterThanEqual,
-                        _ => unimplemented!(),
-                    },
-                    crate::BinaryOperator::And => spirv::Op::BitwiseAnd,
-                    crate::BinaryOperator::ExclusiveOr => spirv::Op::BitwiseXor,
-                    crate::BinaryOperator::InclusiveOr => spirv::Op::BitwiseOr,
-                    crate::BinaryOperator::LogicalAnd => spirv::Op::LogicalAnd,
-                    crate::BinaryOperator::LogicalOr => spirv::Op::LogicalOr,
-                    crate::BinaryOperator::ShiftLeft => spirv::Op::ShiftLeftLogical,
-                    crate::BinaryOperator::ShiftRight => match left_ty_inner.scalar_kind() {
-                        Some(crate::ScalarKind::Sint) => spirv::Op::ShiftRightArithmetic,
-                        Some(crate::ScalarKind::Uint) => spirv::Op::ShiftRightLogical,
-                        _ => unimplemented!(),
-                    },
-                };
-
-                block.body.push(Instruction::binary(
-                    spirv_op,
-                    result_type_id,
-                    id,
-                    if preserve_order { left_id } else { right_id },
-                    if preserve_order { right_id } else { left_id },
-                ));
-                id
+                self.write_binary_operation(expr_handle, op, left, right, result_type_id, block)?
             }
             crate::Expression::Math {
                 fun,
                 arg,
                 arg1,
                 arg2,
+                arg3,
             } => {
                 use crate::MathFunction as Mf;
                 enum MathOp {
@@ -493,10 +665,9 @@ impl<'w> BlockContext<'w> {
                 }
 
                 let arg0_id = self.cached[arg];
-                let arg_scalar_kind = self.fun_info[arg]
-                    .ty
-                    .inner_with(&self.ir_module.types)
-                    .scalar_kind();
+                let ir_module = self.ir_module;
+                let arg_ty_inner = self.fun_info[arg].ty.inner_with(&ir_module.types);
+                let arg_scalar_kind = arg_ty_inner.scalar_kind();
                 let arg1_id = match arg1 {
                     Some(handle) => self.cached[handle],
                     None => 0,
@@ -505,6 +676,10 @@ impl<'w> BlockContext<'w> {
                     Some(handle) => self.cached[handle],
                     None => 0,
                 };
+                let arg3_id = match arg3 {
+                    Some(handle) => self.cached[handle],
+                    None => 0,
+                };
 
                 let id = self.gen_id();
                 let math_op = match fun {
@@ -521,26 +696,34 @@ impl<'w> BlockContext<'w> {
                                     arg0_id,
                                 ))
                             }
-                            other => unimplemented!("Unexpected abs({:?})", other),
+                            Some(crate::ScalarKind::Bool) | None => {
+                                return Err(Error::UnsupportedMathFunction(expr_handle, fun))
+                            }
                         }
                     }
                     Mf::Min => MathOp::Ext(match arg_scalar_kind {
                         Some(crate::ScalarKind::Float) => spirv::GLOp::FMin,
                         Some(crate::ScalarKind::Sint) => spirv::GLOp::SMin,
                         Some(crate::ScalarKind::Uint) => spirv::GLOp::UMin,
-                        other => unimplemented!("Unexpected min({:?})", other),
+                        Some(crate::ScalarKind::Bool) | None => {
+                            return Err(Error::UnsupportedMathFunction(expr_handle, fun))
+                        }
                     }),
                     Mf::Max => MathOp::Ext(match arg_scalar_kind {
                         Some(crate::ScalarKind::Float) => spirv::GLOp::FMax,
                         Some(crate::ScalarKind::Sint) => spirv::GLOp::SMax,
                         Some(crate::ScalarKind::Uint) => spirv::GLOp::UMax,
-                        other => unimplemented!("Unexpected max({:?})", other),
+                        Some(crate::ScalarKind::Bool) | None => {
+                            return Err(Error::UnsupportedMathFunction(expr_handle, fun))
+                        }
                     }),
                     Mf::Clamp => MathOp::Ext(match arg_scalar_kind {
                         Some(crate::ScalarKind::Float) => spirv::GLOp::FClamp,
                         Some(crate::ScalarKind::Sint) => spirv::GLOp::SClamp,
                         Some(crate::ScalarKind::Uint) => spirv::GLOp::UClamp,
-                        other => unimplemented!("Unexpected max({:?})", other),
+                        Some(crate::ScalarKind::Bool) | None => {
+                            return Err(Error::UnsupportedMathFunction(expr_handle, fun))
+                        }
                     }),
                     // trigonometry
                     Mf::Sin => MathOp::Ext(spirv::GLOp::Sin),
@@ -555,12 +738,35 @@ impl<'w> BlockContext<'w> {
                     Mf::Atan2 => MathOp::Ext(spirv::GLOp::Atan2),
                     // decomposition
                     Mf::Ceil => MathOp::Ext(spirv::GLOp::Ceil),
-                    Mf::Round => MathOp::Ext(spirv::GLOp::Round),
+                    Mf::Round => MathOp::Ext(spirv::GLOp::RoundEven),
                     Mf::Floor => MathOp::Ext(spirv::GLOp::Floor),
                     Mf::Fract => MathOp::Ext(spirv::GLOp::Fract),
                     Mf::Trunc => MathOp::Ext(spirv::GLOp::Trunc),
-                    Mf::Modf => MathOp::Ext(spirv::GLOp::Modf),
-                    Mf::Frexp => MathOp::Ext(spirv::GLOp::Frexp),
+                    Mf::Modf | Mf::Frexp => {
+                        // The second operand is an out-parameter, so it has
+                        // to be written as a pointer rather than a value.
+                        let pointer_id = match arg1 {
+                            Some(pointer) => match self.write_expression_pointer(pointer, block)? {
+                                ExpressionPointer::Ready { pointer_id } => pointer_id,
+                                ExpressionPointer::Conditional { .. } => {
+                                    return Err(Error::FeatureNotImplemented(
+                                        "math out-parameter out-of-bounds handling",
+                                    ));
+                                }
+                            },
+                            None => return Err(Error::UnsupportedMathFunction(expr_handle, fun)),
+                        };
+                        MathOp::Custom(Instruction::ext_inst(
+                            self.writer.gl450_ext_inst_id,
+                            match fun {
+                                Mf::Modf => spirv::GLOp::Modf,
+                                _ => spirv::GLOp::Frexp,
+                            },
+                            result_type_id,
+                            id,
+                            &[arg0_id, pointer_id],
+                        ))
+                    }
                     Mf::Ldexp => MathOp::Ext(spirv::GLOp::Ldexp),
                     // geometry
                     Mf::Dot => MathOp::Custom(Instruction::binary(
@@ -591,11 +797,40 @@ impl<'w> BlockContext<'w> {
                     Mf::Log2 => MathOp::Ext(spirv::GLOp::Log2),
                     Mf::Pow => MathOp::Ext(spirv::GLOp::Pow),
                     // computational
-                    Mf::Sign => MathOp::Ext(match arg_scalar_kind {
-                        Some(crate::ScalarKind::Float) => spirv::GLOp::FSign,
-                        Some(crate::ScalarKind::Sint) => spirv::GLOp::SSign,
-                        other => unimplemented!("Unexpected sign({:?})", other),
-                    }),
+                    Mf::Sign => match arg_scalar_kind {
+                        Some(crate::ScalarKind::Float) => MathOp::Ext(spirv::GLOp::FSign),
+                        Some(crate::ScalarKind::Sint) => MathOp::Ext(spirv::GLOp::SSign),
+                        // The sign of an unsigned integer is `min(x, 1)`.
+                        Some(crate::ScalarKind::Uint) => {
+                            let (size, width) = match *arg_ty_inner {
+                                crate::TypeInner::Scalar { width, .. } => (None, width),
+                                crate::TypeInner::Vector { size, width, .. } => (Some(size), width),
+                                _ => return Err(Error::UnsupportedMathFunction(expr_handle, fun)),
+                            };
+                            let mut one_id = self
+                                .writer
+                                .get_constant_scalar(crate::ScalarValue::Uint(1), width)?;
+                            if let Some(size) = size {
+                                one_id = self.write_splat(
+                                    one_id,
+                                    crate::ScalarKind::Uint,
+                                    width,
+                                    size,
+                                    block,
+                                )?;
+                            }
+                            MathOp::Custom(Instruction::ext_inst(
+                                self.writer.gl450_ext_inst_id,
+                                spirv::GLOp::UMin,
+                                result_type_id,
+                                id,
+                                &[arg0_id, one_id],
+                            ))
+                        }
+                        Some(crate::ScalarKind::Bool) | None => {
+                            return Err(Error::UnsupportedMathFunction(expr_handle, fun))
+                        }
+                    },
                     Mf::Fma => MathOp::Ext(spirv::GLOp::Fma),
                     Mf::Mix => MathOp::Ext(spirv::GLOp::FMix),
                     Mf::Step => MathOp::Ext(spirv::GLOp::Step),
@@ -610,10 +845,51 @@ impl<'w> BlockContext<'w> {
                         arg0_id,
                     )),
                     Mf::Determinant => MathOp::Ext(spirv::GLOp::Determinant),
-                    Mf::ReverseBits | Mf::CountOneBits => {
-                        log::error!("unimplemented math function {:?}", fun);
-                        return Err(Error::FeatureNotImplemented("math function"));
-                    }
+                    // bits
+                    Mf::CountOneBits => MathOp::Custom(Instruction::unary(
+                        spirv::Op::BitCount,
+                        result_type_id,
+                        id,
+                        arg0_id,
+                    )),
+                    Mf::ReverseBits => MathOp::Custom(Instruction::unary(
+                        spirv::Op::BitReverse,
+                        result_type_id,
+                        id,
+                        arg0_id,
+                    )),
+                    Mf::ExtractBits => MathOp::Custom(Instruction::bit_field(
+                        match arg_scalar_kind {
+                            Some(crate::ScalarKind::Sint) => spirv::Op::BitFieldSExtract,
+                            _ => spirv::Op::BitFieldUExtract,
+                        },
+                        result_type_id,
+                        id,
+                        &[arg0_id, arg1_id, arg2_id],
+                    )),
+                    Mf::InsertBits => MathOp::Custom(Instruction::bit_field(
+                        spirv::Op::BitFieldInsert,
+                        result_type_id,
+                        id,
+                        &[arg0_id, arg1_id, arg2_id, arg3_id],
+                    )),
+                    Mf::FirstTrailingBit => MathOp::Ext(spirv::GLOp::FindILsb),
+                    Mf::FirstLeadingBit => MathOp::Ext(match arg_scalar_kind {
+                        Some(crate::ScalarKind::Sint) => spirv::GLOp::FindSMsb,
+                        _ => spirv::GLOp::FindUMsb,
+                    }),
+                    // data packing
+                    Mf::Pack4x8snorm => MathOp::Ext(spirv::GLOp::PackSnorm4x8),
+                    Mf::Pack4x8unorm => MathOp::Ext(spirv::GLOp::PackUnorm4x8),
+                    Mf::Pack2x16snorm => MathOp::Ext(spirv::GLOp::PackSnorm2x16),
+                    Mf::Pack2x16unorm => MathOp::Ext(spirv::GLOp::PackUnorm2x16),
+                    Mf::Pack2x16float => MathOp::Ext(spirv::GLOp::PackHalf2x16),
+                    // data unpacking
+                    Mf::Unpack4x8snorm => MathOp::Ext(spirv::GLOp::UnpackSnorm4x8),
+                    Mf::Unpack4x8unorm => MathOp::Ext(spirv::GLOp::UnpackUnorm4x8),
+                    Mf::Unpack2x16snorm => MathOp::Ext(spirv::GLOp::UnpackSnorm2x16),
+                    Mf::Unpack2x16unorm => MathOp::Ext(spirv::GLOp::UnpackUnorm2x16),
+                    Mf::Unpack2x16float => MathOp::Ext(spirv::GLOp::UnpackHalf2x16),
                 };
 
                 block.body.push(match math_op {
@@ -662,6 +938,9 @@ impl<'w> BlockContext<'w> {
             }
             crate::Expression::FunctionArgument(index) => self.function.parameter_id(index),
             crate::Expression::Call(_function) => self.writer.lookup_function_call[&expr_handle],
+            crate::Expression::AtomicResult { .. }
+            | crate::Expression::SubgroupBallotResult
+            | crate::Expression::SubgroupOperationResult { .. } => self.cached[expr_handle],
             crate::Expression::As {
                 expr,
                 kind,
@@ -679,10 +958,7 @@ impl<'w> BlockContext<'w> {
                             size: _,
                         } => (kind, width),
                         crate::TypeInner::Matrix { width, .. } => (crate::ScalarKind::Float, width),
-                        ref other => {
-                            log::error!("As source {:?}", other);
-                            return Err(Error::Validation("Unexpected Expression::As source"));
-                        }
+                        _ => return Err(Error::InvalidExpressionType(expr, "conversion")),
                     };
 
                 let op = match (src_kind, kind, convert) {
@@ -715,7 +991,7 @@ impl<'w> BlockContext<'w> {
                 array_index,
                 index,
             } => {
-                let image_id = self.get_image_id(image);
+                let image_id = self.get_image_id(image)?;
                 let coordinate_id =
                     self.write_texture_coordinates(coordinate, array_index, block)?;
 
@@ -782,10 +1058,11 @@ impl<'w> BlockContext<'w> {
                 offset,
                 level,
                 depth_ref,
+                gather,
             } => {
                 use super::instructions::SampleLod;
                 // image
-                let image_id = self.get_image_id(image);
+                let image_id = self.get_image_id(image)?;
                 let image_type = self.fun_info[image].ty.handle().unwrap();
                 // Vulkan doesn't know about our `Depth` class, and it returns `vec4<f32>`,
                 // so we need to grab the first component out of it.
@@ -793,7 +1070,7 @@ impl<'w> BlockContext<'w> {
                     crate::TypeInner::Image {
                         class: crate::ImageClass::Depth,
                         ..
-                    } => depth_ref.is_none(),
+                    } => depth_ref.is_none() && gather.is_none(),
                     _ => false,
                 };
                 let sample_result_type_id = if needs_sub_access {
@@ -812,7 +1089,7 @@ impl<'w> BlockContext<'w> {
                 let sampled_image_type_id =
                     self.get_type_id(LookupType::Local(LocalType::SampledImage { image_type_id }))?;
 
-                let sampler_id = self.get_image_id(sampler);
+                let sampler_id = self.get_image_id(sampler)?;
                 let coordinate_id =
                     self.write_texture_coordinates(coordinate, array_index, block)?;
 
@@ -823,14 +1100,40 @@ impl<'w> BlockContext<'w> {
                     image_id,
                     sampler_id,
                 ));
+                // The combination is as divergent as its parts.
+                if self.writer.is_non_uniform(image_id) || self.writer.is_non_uniform(sampler_id) {
+                    self.writer.decorate_non_uniform(sampled_image_id);
+                }
                 let id = self.gen_id();
 
                 let depth_id = depth_ref.map(|handle| self.cached[handle]);
                 let mut mask = spirv::ImageOperands::empty();
                 mask.set(spirv::ImageOperands::CONST_OFFSET, offset.is_some());
 
-                let mut main_instruction = match level {
-                    crate::SampleLevel::Zero => {
+                let mut main_instruction = match (level, gather) {
+                    (_, Some(component)) => {
+                        // The component is implied by the depth reference
+                        let component_id = match depth_id {
+                            Some(_) => None,
+                            None => Some(self.writer.get_constant_scalar(
+                                crate::ScalarValue::Uint(component as u64),
+                                4,
+                            )?),
+                        };
+                        let mut inst = Instruction::image_gather(
+                            sample_result_type_id,
+                            id,
+                            sampled_image_id,
+                            coordinate_id,
+                            component_id,
+                            depth_id,
+                        );
+                        if !mask.is_empty() {
+                            inst.add_operand(mask.bits());
+                        }
+                        inst
+                    }
+                    (crate::SampleLevel::Zero, None) => {
                         let mut inst = Instruction::image_sample(
                             sample_result_type_id,
                             id,
@@ -850,7 +1153,7 @@ impl<'w> BlockContext<'w> {
 
                         inst
                     }
-                    crate::SampleLevel::Auto => {
+                    (crate::SampleLevel::Auto, None) => {
                         let mut inst = Instruction::image_sample(
                             sample_result_type_id,
                             id,
@@ -864,7 +1167,7 @@ impl<'w> BlockContext<'w> {
                         }
                         inst
                     }
-                    crate::SampleLevel::Exact(lod_handle) => {
+                    (crate::SampleLevel::Exact(lod_handle), None) => {
                         let mut inst = Instruction::image_sample(
                             sample_result_type_id,
                             id,
@@ -881,7 +1184,7 @@ impl<'w> BlockContext<'w> {
 
                         inst
                     }
-                    crate::SampleLevel::Bias(bias_handle) => {
+                    (crate::SampleLevel::Bias(bias_handle), None) => {
                         let mut inst = Instruction::image_sample(
                             sample_result_type_id,
                             id,
@@ -898,7 +1201,7 @@ impl<'w> BlockContext<'w> {
 
                         inst
                     }
-                    crate::SampleLevel::Gradient { x, y } => {
+                    (crate::SampleLevel::Gradient { x, y }, None) => {
                         let mut inst = Instruction::image_sample(
                             sample_result_type_id,
                             id,
@@ -1005,7 +1308,7 @@ impl<'w> BlockContext<'w> {
             crate::Expression::ImageQuery { image, query } => {
                 use crate::{ImageClass as Ic, ImageDimension as Id, ImageQuery as Iq};
 
-                let image_id = self.get_image_id(image);
+                let image_id = self.get_image_id(image)?;
                 let image_type = self.fun_info[image].ty.handle().unwrap();
                 let (dim, arrayed, class) = match self.ir_module.types[image_type].inner {
                     crate::TypeInner::Image {
@@ -1191,11 +1494,17 @@ impl<'w> BlockContext<'w> {
         // but we expect these checks to almost always succeed, and keeping branches to a
         // minimum is essential.
         let mut accumulated_checks = None;
+        // Set if any of the indices selects a binding array element non-uniformly.
+        let mut non_uniform = false;
 
         self.temp_list.clear();
         let root_id = loop {
             expr_handle = match self.ir_function.expressions[expr_handle] {
                 crate::Expression::Access { base, index } => {
+                    if self.check_non_uniform_index(base, index)? {
+                        self.writer.decorate_non_uniform(self.cached[index]);
+                        non_uniform = true;
+                    }
                     let index_id = match self.write_bounds_check(base, index, block)? {
                         BoundsCheckResult::KnownInBounds(known_index) => {
                             // Even if the index is known, `OpAccessIndex`
@@ -1247,7 +1556,7 @@ impl<'w> BlockContext<'w> {
                 crate::Expression::FunctionArgument(index) => {
                     break self.function.parameter_id(index);
                 }
-                ref other => unimplemented!("Unexpected pointer expression {:?}", other),
+                _ => return Err(Error::UnexpectedPointerExpression(expr_handle)),
             }
         };
 
@@ -1260,6 +1569,9 @@ impl<'w> BlockContext<'w> {
             let pointer_id = self.gen_id();
             let access =
                 Instruction::access_chain(result_type_id, pointer_id, root_id, &self.temp_list);
+            if non_uniform {
+                self.writer.decorate_non_uniform(pointer_id);
+            }
 
             // If we generated some bounds checks, we need to leave it to our
             // caller to generate the branch, the access, the load or store, and
@@ -1277,7 +1589,7 @@ impl<'w> BlockContext<'w> {
         Ok(pointer)
     }
 
-    fn get_image_id(&mut self, expr_handle: Handle<crate::Expression>) -> Word {
+    fn get_image_id(&mut self, expr_handle: Handle<crate::Expression>) -> Result<Word, Error> {
         let id = match self.ir_function.expressions[expr_handle] {
             crate::Expression::GlobalVariable(handle) => {
                 self.writer.global_variables[handle.index()].handle_id
@@ -1285,29 +1597,32 @@ impl<'w> BlockContext<'w> {
             crate::Expression::FunctionArgument(i) => {
                 self.function.parameters[i as usize].handle_id
             }
-            ref other => unreachable!("Unexpected image expression {:?}", other),
+            crate::Expression::Access { .. } | crate::Expression::AccessIndex { .. } => {
+                self.cached[expr_handle]
+            }
+            _ => 0,
         };
 
         if id == 0 {
-            unreachable!(
-                "Image expression {:?} doesn't have a handle ID",
-                expr_handle
-            );
+            return Err(Error::UnexpectedImageExpression(expr_handle));
         }
 
-        id
+        Ok(id)
     }
 
     pub(super) fn write_block(
         &mut self,
         label_id: Word,
-        statements: &[crate::Statement],
+        statements: &crate::Block,
         exit_id: Option<Word>,
         loop_context: LoopContext,
     ) -> Result<(), Error> {
         let mut block = Block::new(label_id);
 
-        for statement in statements {
+        for (statement, &span) in statements.span_iter() {
+            if let Some(line) = self.writer.debug_line(span) {
+                block.body.push(line);
+            }
             match *statement {
                 crate::Statement::Emit(ref range) => {
                     for handle in range.clone() {
@@ -1392,6 +1707,11 @@ impl<'w> BlockContext<'w> {
                         Instruction::switch(selector_id, default_id, &raw_cases),
                     );
 
+                    let inner_context = LoopContext {
+                        break_id: Some(merge_id),
+                        ..loop_context
+                    };
+
                     for (i, (case, raw_case)) in cases.iter().zip(raw_cases.iter()).enumerate() {
                         let case_finish_id = if case.fall_through {
                             match raw_cases.get(i + 1) {
@@ -1405,11 +1725,11 @@ impl<'w> BlockContext<'w> {
                             raw_case.label_id,
                             &case.body,
                             Some(case_finish_id),
-                            LoopContext::default(),
+                            inner_context,
                         )?;
                     }
 
-                    self.write_block(default_id, default, Some(merge_id), LoopContext::default())?;
+                    self.write_block(default_id, default, Some(merge_id), inner_context)?;
 
                     block = Block::new(merge_id);
                 }
@@ -1568,7 +1888,7 @@ impl<'w> BlockContext<'w> {
                     array_index,
                     value,
                 } => {
-                    let image_id = self.get_image_id(image);
+                    let image_id = self.get_image_id(image)?;
                     let coordinate_id =
                         self.write_texture_coordinates(coordinate, array_index, &mut block)?;
                     let value_id = self.cached[value];
@@ -1577,6 +1897,152 @@ impl<'w> BlockContext<'w> {
                         .body
                         .push(Instruction::image_write(image_id, coordinate_id, value_id));
                 }
+                crate::Statement::Atomic {
+                    pointer,
+                    ref fun,
+                    value,
+                    result,
+                } => {
+                    let id = self.gen_id();
+                    let result_type_id = self.get_expression_type_id(&self.fun_info[result].ty)?;
+                    self.cached[result] = id;
+
+                    let pointer_id = match self.write_expression_pointer(pointer, &mut block)? {
+                        ExpressionPointer::Ready { pointer_id } => pointer_id,
+                        ExpressionPointer::Conditional { .. } => {
+                            return Err(Error::FeatureNotImplemented(
+                                "Atomics out-of-bounds handling",
+                            ));
+                        }
+                    };
+
+                    let class = match *self.fun_info[pointer].ty.inner_with(&self.ir_module.types) {
+                        crate::TypeInner::Pointer { class, .. } => class,
+                        _ => return Err(Error::UnexpectedPointerExpression(pointer)),
+                    };
+                    let scope = match class {
+                        crate::StorageClass::WorkGroup => spirv::Scope::Workgroup,
+                        _ => spirv::Scope::Device,
+                    };
+                    let scope_id = self.get_index_constant(scope as u32)?;
+                    let semantics_id =
+                        self.get_index_constant(spirv::MemorySemantics::empty().bits())?;
+                    let value_id = self.cached[value];
+
+                    let (kind, width) =
+                        match *self.fun_info[value].ty.inner_with(&self.ir_module.types) {
+                            crate::TypeInner::Scalar { kind, width } => (kind, width),
+                            _ => return Err(Error::InvalidExpressionType(value, "atomic")),
+                        };
+
+                    let instruction = match *fun {
+                        crate::AtomicFunction::Exchange { compare: Some(cmp) } => {
+                            let scalar_type_id =
+                                self.get_type_id(LookupType::Local(LocalType::Value {
+                                    vector_size: None,
+                                    kind,
+                                    width,
+                                    pointer_class: None,
+                                }))?;
+                            let bool_type_id = self.writer.get_bool_type_id()?;
+                            let cmp_id = self.cached[cmp];
+
+                            let old_id = self.gen_id();
+                            block.body.push(Instruction::atomic_compare_exchange(
+                                scalar_type_id,
+                                old_id,
+                                pointer_id,
+                                scope_id,
+                                semantics_id,
+                                semantics_id,
+                                value_id,
+                                cmp_id,
+                            ));
+
+                            let (one, zero) = match kind {
+                                crate::ScalarKind::Sint => {
+                                    (crate::ScalarValue::Sint(1), crate::ScalarValue::Sint(0))
+                                }
+                                _ => (crate::ScalarValue::Uint(1), crate::ScalarValue::Uint(0)),
+                            };
+                            let one_id = self.writer.get_constant_scalar(one, width)?;
+                            let zero_id = self.writer.get_constant_scalar(zero, width)?;
+
+                            let equal_id = self.gen_id();
+                            block.body.push(Instruction::binary(
+                                spirv::Op::IEqual,
+                                bool_type_id,
+                                equal_id,
+                                old_id,
+                                cmp_id,
+                            ));
+                            let exchanged_id = self.gen_id();
+                            block.body.push(Instruction::select(
+                                scalar_type_id,
+                                exchanged_id,
+                                equal_id,
+                                one_id,
+                                zero_id,
+                            ));
+                            Instruction::composite_construct(
+                                result_type_id,
+                                id,
+                                &[old_id, exchanged_id],
+                            )
+                        }
+                        ref other => {
+                            let op = match *other {
+                                crate::AtomicFunction::Add => spirv::Op::AtomicIAdd,
+                                crate::AtomicFunction::Subtract => spirv::Op::AtomicISub,
+                                crate::AtomicFunction::And => spirv::Op::AtomicAnd,
+                                crate::AtomicFunction::InclusiveOr => spirv::Op::AtomicOr,
+                                crate::AtomicFunction::ExclusiveOr => spirv::Op::AtomicXor,
+                                crate::AtomicFunction::Min => match kind {
+                                    crate::ScalarKind::Sint => spirv::Op::AtomicSMin,
+                                    _ => spirv::Op::AtomicUMin,
+                                },
+                                crate::AtomicFunction::Max => match kind {
+                                    crate::ScalarKind::Sint => spirv::Op::AtomicSMax,
+                                    _ => spirv::Op::AtomicUMax,
+                                },
+                                crate::AtomicFunction::Exchange { compare: None } => {
+                                    spirv::Op::AtomicExchange
+                                }
+                                crate::AtomicFunction::Exchange { compare: Some(_) } => {
+                                    unreachable!()
+                                }
+                            };
+                            Instruction::atomic_binary(
+                                op,
+                                result_type_id,
+                                id,
+                                pointer_id,
+                                scope_id,
+                                semantics_id,
+                                value_id,
+                            )
+                        }
+                    };
+                    block.body.push(instruction);
+                }
+                crate::Statement::SubgroupBallot { result, predicate } => {
+                    self.write_subgroup_ballot(predicate, result, &mut block)?;
+                }
+                crate::Statement::SubgroupCollectiveOperation {
+                    op,
+                    collective_op,
+                    argument,
+                    result,
+                } => {
+                    self.write_subgroup_operation(op, collective_op, argument, result, &mut block)?;
+                }
+                crate::Statement::SubgroupGather {
+                    ref mode,
+                    argument,
+                    result,
+                } => {
+                    self.write_subgroup_gather(mode, argument, result, &mut block)?;
+                }
                 crate::Statement::Call {
                     function: local_function,
                     ref arguments,
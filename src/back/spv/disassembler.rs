@@ -0,0 +1,315 @@
+/*! Disassembler of SPIR-V modules.
+
+The output follows the conventions of `spirv-dis`: every instruction is on its
+own line, results are assigned with `%id = `, and ids are named after their
+`OpName` when it's unambiguous. It's accepted by `front::spv::assemble`.
+!*/
+
+use crate::{
+    spv_grammar::{self, EnumKind, MaskKind, NumberType, OperandKind},
+    FastHashMap, FastHashSet,
+};
+use num_traits::FromPrimitive;
+use spirv::{Op, Word};
+use std::fmt::Write as _;
+
+struct Disassembler {
+    names: FastHashMap<Word, String>,
+    number_types: FastHashMap<Word, NumberType>,
+    value_types: FastHashMap<Word, Word>,
+    glsl_sets: FastHashSet<Word>,
+    out: String,
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn decode_string(words: &[Word]) -> (String, usize) {
+    let mut bytes = Vec::new();
+    for (index, &word) in words.iter().enumerate() {
+        for byte in word.to_le_bytes().iter() {
+            if *byte == 0 {
+                return (String::from_utf8_lossy(&bytes).into_owned(), index + 1);
+            }
+            bytes.push(*byte);
+        }
+    }
+    (String::from_utf8_lossy(&bytes).into_owned(), words.len())
+}
+
+impl Disassembler {
+    fn collect_names(&mut self, instructions: &[(Word, &[Word])]) {
+        let mut taken = FastHashSet::default();
+        for &(opcode, operands) in instructions {
+            if opcode != Op::Name as Word
+                || operands.len() < 2
+                || self.names.contains_key(&operands[0])
+            {
+                continue;
+            }
+            let (name, _) = decode_string(&operands[1..]);
+            if !is_identifier(&name) {
+                continue;
+            }
+            let mut unique = name.clone();
+            let mut suffix = 1;
+            while !taken.insert(unique.clone()) {
+                unique = format!("{}_{}", name, suffix);
+                suffix += 1;
+            }
+            self.names.insert(operands[0], unique);
+        }
+    }
+
+    fn write_id(&mut self, id: Word) {
+        match self.names.get(&id) {
+            Some(name) => write!(self.out, "%{}", name),
+            None => write!(self.out, "%{}", id),
+        }
+        .unwrap();
+    }
+
+    fn write_number(&mut self, ty: Option<NumberType>, words: &[Word]) {
+        let low = words[0];
+        let wide = words
+            .get(1)
+            .map_or(low as u64, |&high| (high as u64) << 32 | low as u64);
+        match ty {
+            Some(NumberType::Int {
+                signed: true,
+                width,
+            }) if width > 32 => {
+                write!(self.out, " {}", wide as i64)
+            }
+            Some(NumberType::Int { signed: true, .. }) => write!(self.out, " {}", low as i32),
+            Some(NumberType::Float { width: 32 }) => {
+                let value = f32::from_bits(low);
+                if value.is_finite() {
+                    write!(self.out, " {:?}", value)
+                } else {
+                    write!(self.out, " 0x{:08x}", low)
+                }
+            }
+            Some(NumberType::Float { width: 64 }) => {
+                let value = f64::from_bits(wide);
+                if value.is_finite() {
+                    write!(self.out, " {:?}", value)
+                } else {
+                    write!(self.out, " 0x{:016x}", wide)
+                }
+            }
+            // Half floats are written as their bit patterns.
+            Some(NumberType::Float { .. }) => write!(self.out, " 0x{:04x}", low),
+            _ => write!(self.out, " {}", wide),
+        }
+        .unwrap();
+    }
+
+    fn write_enum(&mut self, kind: EnumKind, value: Word) {
+        match kind.name(value) {
+            Some(name) => write!(self.out, " {}", name),
+            None => write!(self.out, " {}", value),
+        }
+        .unwrap();
+    }
+
+    fn write_mask(&mut self, kind: MaskKind, value: Word) {
+        if value == 0 {
+            self.out.push_str(" None");
+            return;
+        }
+        let mut rest = value;
+        let mut separator = " ";
+        for &(name, bit) in kind.bits() {
+            if value & bit != 0 {
+                self.out.push_str(separator);
+                self.out.push_str(name);
+                separator = "|";
+                rest &= !bit;
+            }
+        }
+        if rest != 0 {
+            write!(self.out, "{}0x{:x}", separator, rest).unwrap();
+        }
+    }
+
+    fn write_instruction(&mut self, op: Op, words: &[Word]) {
+        let layout = spv_grammar::layout_of(op);
+        let mut operands = words;
+        let result_type = if layout.result_type && !operands.is_empty() {
+            operands = &operands[1..];
+            Some(words[0])
+        } else {
+            None
+        };
+        if layout.result && !operands.is_empty() {
+            let result = operands[0];
+            operands = &operands[1..];
+            self.write_id(result);
+            self.out.push_str(" = ");
+            if let Some(ty) = result_type {
+                self.value_types.insert(result, ty);
+            }
+            if let Some(number_type) = NumberType::from_instruction(op, operands) {
+                self.number_types.insert(result, number_type);
+            }
+            if op == Op::ExtInstImport && decode_string(operands).0 == "GLSL.std.450" {
+                self.glsl_sets.insert(result);
+            }
+        }
+        write!(self.out, "Op{:?}", op).unwrap();
+        if let Some(ty) = result_type {
+            self.out.push(' ');
+            self.write_id(ty);
+        }
+
+        let number_type = result_type.and_then(|ty| self.number_types.get(&ty).cloned());
+        let selector_type = match (op, operands.first()) {
+            (Op::Switch, Some(selector)) => self
+                .value_types
+                .get(selector)
+                .and_then(|ty| self.number_types.get(ty))
+                .cloned(),
+            _ => None,
+        };
+        let mut kinds = layout.operands.iter().rev().cloned().collect::<Vec<_>>();
+        let mut last_id = None;
+        while !operands.is_empty() {
+            let kind = kinds.pop().unwrap_or(OperandKind::LiteralRest);
+            let count = match kind {
+                OperandKind::Id | OperandKind::IdRest => {
+                    self.out.push(' ');
+                    self.write_id(operands[0]);
+                    last_id = Some(operands[0]);
+                    1
+                }
+                OperandKind::Literal | OperandKind::LiteralRest => {
+                    write!(self.out, " {}", operands[0]).unwrap();
+                    1
+                }
+                OperandKind::String => {
+                    let (string, count) = decode_string(operands);
+                    let escaped = string.replace('\\', "\\\\").replace('"', "\\\"");
+                    write!(self.out, " \"{}\"", escaped).unwrap();
+                    count
+                }
+                OperandKind::Number => {
+                    let count = number_type.map_or(1, NumberType::word_count);
+                    let count = count.min(operands.len());
+                    self.write_number(number_type, &operands[..count]);
+                    count
+                }
+                OperandKind::Enum(enum_kind) => {
+                    self.write_enum(enum_kind, operands[0]);
+                    1
+                }
+                OperandKind::Mask(mask_kind) => {
+                    self.write_mask(mask_kind, operands[0]);
+                    kinds.extend(mask_kind.followed_by(operands[0]).iter().rev());
+                    1
+                }
+                OperandKind::Decoration => {
+                    self.write_enum(EnumKind::Decoration, operands[0]);
+                    kinds.extend(spv_grammar::decoration_operands(operands[0]).iter().rev());
+                    1
+                }
+                OperandKind::ExecutionMode => {
+                    self.write_enum(EnumKind::ExecutionMode, operands[0]);
+                    kinds.push(OperandKind::LiteralRest);
+                    1
+                }
+                OperandKind::ExtInstruction => {
+                    match last_id {
+                        Some(set) if self.glsl_sets.contains(&set) => {
+                            self.write_enum(EnumKind::GlslStd450, operands[0])
+                        }
+                        _ => write!(self.out, " {}", operands[0]).unwrap(),
+                    }
+                    1
+                }
+                OperandKind::SwitchTargets => {
+                    let count = selector_type.map_or(1, NumberType::word_count);
+                    let count = count.min(operands.len());
+                    self.write_number(selector_type, &operands[..count]);
+                    if let Some(&label) = operands.get(count) {
+                        self.out.push(' ');
+                        self.write_id(label);
+                        count + 1
+                    } else {
+                        count
+                    }
+                }
+            };
+            match kind {
+                OperandKind::IdRest | OperandKind::LiteralRest | OperandKind::SwitchTargets => {
+                    kinds.push(kind)
+                }
+                _ => {}
+            }
+            operands = &operands[count..];
+        }
+        self.out.push('\n');
+    }
+}
+
+/// Render a SPIR-V module as assembly text.
+///
+/// Malformed parts of the module are reported in comments, so that the
+/// rest of it can still be inspected.
+pub fn disassemble(words: &[Word]) -> String {
+    let mut disassembler = Disassembler {
+        names: FastHashMap::default(),
+        number_types: FastHashMap::default(),
+        value_types: FastHashMap::default(),
+        glsl_sets: FastHashSet::default(),
+        out: String::new(),
+    };
+    if words.len() < 5 || words[0] != spirv::MAGIC_NUMBER {
+        disassembler.out.push_str("; invalid SPIR-V header\n");
+        return disassembler.out;
+    }
+    let version = words[1];
+    writeln!(disassembler.out, "; SPIR-V").unwrap();
+    writeln!(
+        disassembler.out,
+        "; Version: {}.{}",
+        (version >> 16) & 0xFF,
+        (version >> 8) & 0xFF
+    )
+    .unwrap();
+    writeln!(disassembler.out, "; Generator: {}", words[2]).unwrap();
+    writeln!(disassembler.out, "; Bound: {}", words[3]).unwrap();
+
+    let mut instructions = Vec::new();
+    let mut rest = &words[5..];
+    while let Some(&first) = rest.first() {
+        let count = (first >> 16) as usize;
+        let opcode = first & 0xFFFF;
+        if count == 0 || count > rest.len() {
+            writeln!(
+                disassembler.out,
+                "; invalid word count {} of opcode {}",
+                count, opcode
+            )
+            .unwrap();
+            break;
+        }
+        instructions.push((opcode, &rest[1..count]));
+        rest = &rest[count..];
+    }
+
+    disassembler.collect_names(&instructions);
+    for &(opcode, operands) in instructions.iter() {
+        match Op::from_u32(opcode) {
+            Some(op) => disassembler.write_instruction(op, operands),
+            None => writeln!(disassembler.out, "; unknown opcode {}", opcode).unwrap(),
+        }
+    }
+    disassembler.out
+}
@@ -361,6 +361,10 @@ impl<'w> BlockContext<'w> {
         index: Handle<crate::Expression>,
         block: &mut Block,
     ) -> Result<BoundsCheckResult, Error> {
+        // Descriptor indices are the API's responsibility, not ours.
+        if crate::proc::binding_array_element(base, self.ir_module, self.fun_info).is_some() {
+            return Ok(BoundsCheckResult::Computed(self.cached[index]));
+        }
         Ok(match self.writer.index_bounds_check_policy {
             IndexBoundsCheckPolicy::Restrict => self.write_restricted_index(base, index, block)?,
             IndexBoundsCheckPolicy::ReadZeroSkipWrite => {
@@ -432,4 +436,116 @@ impl<'w> BlockContext<'w> {
 
         Ok(result_id)
     }
+
+    /// Return the global variable behind `base` if it is a binding array
+    /// of images or samplers, which are accessed by loading each element.
+    pub(super) fn handle_binding_array(
+        &self,
+        base: Handle<crate::Expression>,
+    ) -> Option<Handle<crate::GlobalVariable>> {
+        match self.ir_function.expressions[base] {
+            crate::Expression::GlobalVariable(handle) => {
+                let var = &self.ir_module.global_variables[handle];
+                match self.ir_module.types[var.ty].inner {
+                    crate::TypeInner::BindingArray { .. }
+                        if var.class == crate::StorageClass::Handle =>
+                    {
+                        Some(handle)
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Check if `index` into the binding array `base` may differ between
+    /// invocations, and require the capabilities to index it if so.
+    pub(super) fn check_non_uniform_index(
+        &mut self,
+        base: Handle<crate::Expression>,
+        index: Handle<crate::Expression>,
+    ) -> Result<bool, Error> {
+        use spirv::Capability as Cap;
+
+        if !crate::proc::is_non_uniform_binding_array_access(
+            base,
+            index,
+            self.ir_module,
+            self.fun_info,
+        ) {
+            return Ok(false);
+        }
+        let element = match crate::proc::binding_array_element(base, self.ir_module, self.fun_info)
+        {
+            Some(element) => element,
+            None => return Ok(false),
+        };
+
+        let capability = match self.ir_module.types[element].inner {
+            crate::TypeInner::Image {
+                class: crate::ImageClass::Storage(_),
+                ..
+            } => Cap::StorageImageArrayNonUniformIndexing,
+            crate::TypeInner::Image { .. } | crate::TypeInner::Sampler { .. } => {
+                Cap::SampledImageArrayNonUniformIndexing
+            }
+            _ => match *self.fun_info[base].ty.inner_with(&self.ir_module.types) {
+                crate::TypeInner::Pointer {
+                    class: crate::StorageClass::Uniform,
+                    ..
+                } => Cap::UniformBufferArrayNonUniformIndexing,
+                _ => Cap::StorageBufferArrayNonUniformIndexing,
+            },
+        };
+        self.writer.check(&[Cap::ShaderNonUniform])?;
+        self.writer.check(&[capability])?;
+        Ok(true)
+    }
+
+    /// Emit code to fetch the image or sampler at `index_id` of the binding
+    /// array global `base`.
+    ///
+    /// Handles can't be loaded out of a binding array as a whole, so we
+    /// build an access chain to the element and load just that.
+    /// Return the id of the loaded handle.
+    pub(super) fn write_binding_array_access(
+        &mut self,
+        expr_handle: Handle<crate::Expression>,
+        base: Handle<crate::Expression>,
+        index_id: Word,
+        non_uniform: bool,
+        block: &mut Block,
+    ) -> Result<Word, Error> {
+        let result_type_id = self.get_expression_type_id(&self.fun_info[expr_handle].ty)?;
+        let element = match crate::proc::binding_array_element(base, self.ir_module, self.fun_info)
+        {
+            Some(element) => element,
+            None => return Err(Error::Validation("binding array access base")),
+        };
+        let pointer_type_id = self.writer.get_pointer_id(
+            &self.ir_module.types,
+            element,
+            spirv::StorageClass::UniformConstant,
+        )?;
+
+        let pointer_id = self.gen_id();
+        block.body.push(Instruction::access_chain(
+            pointer_type_id,
+            pointer_id,
+            self.cached[base],
+            &[index_id],
+        ));
+        let id = self.gen_id();
+        block
+            .body
+            .push(Instruction::load(result_type_id, id, pointer_id, None));
+
+        if non_uniform {
+            self.writer.decorate_non_uniform(index_id);
+            self.writer.decorate_non_uniform(pointer_id);
+            self.writer.decorate_non_uniform(id);
+        }
+        Ok(id)
+    }
 }
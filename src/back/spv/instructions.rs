@@ -21,10 +21,31 @@ impl super::Instruction {
     //  Debug Instructions
     //
 
-    pub(super) fn source(source_language: spirv::SourceLanguage, version: u32) -> Self {
+    pub(super) fn string(name: &str, id: Word) -> Self {
+        let mut instruction = Self::new(Op::String);
+        instruction.set_result(id);
+        instruction.add_operands(helpers::string_to_words(name));
+        instruction
+    }
+
+    pub(super) fn source(
+        source_language: spirv::SourceLanguage,
+        version: u32,
+        source: Option<(Word, &str)>,
+    ) -> Self {
         let mut instruction = Self::new(Op::Source);
         instruction.add_operand(source_language as u32);
         instruction.add_operands(helpers::bytes_to_words(&version.to_le_bytes()));
+        if let Some((file_id, source_code)) = source {
+            instruction.add_operand(file_id);
+            instruction.add_operands(helpers::string_to_words(source_code));
+        }
+        instruction
+    }
+
+    pub(super) fn source_continued(source_code: &str) -> Self {
+        let mut instruction = Self::new(Op::SourceContinued);
+        instruction.add_operands(helpers::string_to_words(source_code));
         instruction
     }
 
@@ -43,6 +64,14 @@ impl super::Instruction {
         instruction
     }
 
+    pub(super) fn line(file_id: Word, line: Word, column: Word) -> Self {
+        let mut instruction = Self::new(Op::Line);
+        instruction.add_operand(file_id);
+        instruction.add_operand(line);
+        instruction.add_operand(column);
+        instruction
+    }
+
     //
     //  Annotation Instructions
     //
@@ -399,6 +428,48 @@ impl super::Instruction {
         instruction
     }
 
+    pub(super) fn spec_constant_true(result_type_id: Word, id: Word) -> Self {
+        let mut instruction = Self::new(Op::SpecConstantTrue);
+        instruction.set_type(result_type_id);
+        instruction.set_result(id);
+        instruction
+    }
+
+    pub(super) fn spec_constant_false(result_type_id: Word, id: Word) -> Self {
+        let mut instruction = Self::new(Op::SpecConstantFalse);
+        instruction.set_type(result_type_id);
+        instruction.set_result(id);
+        instruction
+    }
+
+    pub(super) fn spec_constant(result_type_id: Word, id: Word, values: &[Word]) -> Self {
+        let mut instruction = Self::new(Op::SpecConstant);
+        instruction.set_type(result_type_id);
+        instruction.set_result(id);
+
+        for value in values {
+            instruction.add_operand(*value);
+        }
+
+        instruction
+    }
+
+    pub(super) fn spec_constant_composite(
+        result_type_id: Word,
+        id: Word,
+        constituent_ids: &[Word],
+    ) -> Self {
+        let mut instruction = Self::new(Op::SpecConstantComposite);
+        instruction.set_type(result_type_id);
+        instruction.set_result(id);
+
+        for constituent_id in constituent_ids {
+            instruction.add_operand(*constituent_id);
+        }
+
+        instruction
+    }
+
     //
     //  Memory Instructions
     //
@@ -579,6 +650,34 @@ impl super::Instruction {
         instruction
     }
 
+    pub(super) fn image_gather(
+        result_type_id: Word,
+        id: Word,
+        sampled_image: Word,
+        coordinates: Word,
+        component: Option<Word>,
+        depth_ref: Option<Word>,
+    ) -> Self {
+        let op = match depth_ref {
+            None => Op::ImageGather,
+            Some(_) => Op::ImageDrefGather,
+        };
+
+        let mut instruction = Self::new(op);
+        instruction.set_type(result_type_id);
+        instruction.set_result(id);
+        instruction.add_operand(sampled_image);
+        instruction.add_operand(coordinates);
+        if let Some(component) = component {
+            instruction.add_operand(component);
+        }
+        if let Some(dref) = depth_ref {
+            instruction.add_operand(dref);
+        }
+
+        instruction
+    }
+
     pub(super) fn image_fetch(
         result_type_id: Word,
         id: Word,
@@ -726,6 +825,20 @@ impl super::Instruction {
         instruction
     }
 
+    //
+    // Bit Instructions
+    //
+
+    pub(super) fn bit_field(op: Op, result_type_id: Word, id: Word, operands: &[Word]) -> Self {
+        let mut instruction = Self::new(op);
+        instruction.set_type(result_type_id);
+        instruction.set_result(id);
+        for &operand in operands {
+            instruction.add_operand(operand);
+        }
+        instruction
+    }
+
     pub(super) fn relational(op: Op, result_type_id: Word, id: Word, expr_id: Word) -> Self {
         let mut instruction = Self::new(op);
         instruction.set_type(result_type_id);
@@ -865,6 +978,48 @@ impl super::Instruction {
     //  Atomic Instructions
     //
 
+    pub(super) fn atomic_binary(
+        op: Op,
+        result_type_id: Word,
+        id: Word,
+        pointer: Word,
+        scope_id: Word,
+        semantics_id: Word,
+        value: Word,
+    ) -> Self {
+        let mut instruction = Self::new(op);
+        instruction.set_type(result_type_id);
+        instruction.set_result(id);
+        instruction.add_operand(pointer);
+        instruction.add_operand(scope_id);
+        instruction.add_operand(semantics_id);
+        instruction.add_operand(value);
+        instruction
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub(super) fn atomic_compare_exchange(
+        result_type_id: Word,
+        id: Word,
+        pointer: Word,
+        scope_id: Word,
+        equal_semantics_id: Word,
+        unequal_semantics_id: Word,
+        value: Word,
+        comparator: Word,
+    ) -> Self {
+        let mut instruction = Self::new(Op::AtomicCompareExchange);
+        instruction.set_type(result_type_id);
+        instruction.set_result(id);
+        instruction.add_operand(pointer);
+        instruction.add_operand(scope_id);
+        instruction.add_operand(equal_semantics_id);
+        instruction.add_operand(unequal_semantics_id);
+        instruction.add_operand(value);
+        instruction.add_operand(comparator);
+        instruction
+    }
+
     //
     //  Primitive Instructions
     //
@@ -882,4 +1037,73 @@ impl super::Instruction {
         instruction.add_operand(semantics_id);
         instruction
     }
+
+    //
+    //  Non-Uniform Instructions
+    //
+
+    pub(super) fn group_non_uniform_ballot(
+        result_type_id: Word,
+        id: Word,
+        exec_scope_id: Word,
+        predicate: Word,
+    ) -> Self {
+        let mut instruction = Self::new(Op::GroupNonUniformBallot);
+        instruction.set_type(result_type_id);
+        instruction.set_result(id);
+        instruction.add_operand(exec_scope_id);
+        instruction.add_operand(predicate);
+        instruction
+    }
+
+    pub(super) fn group_non_uniform_vote(
+        op: Op,
+        result_type_id: Word,
+        id: Word,
+        exec_scope_id: Word,
+        predicate: Word,
+    ) -> Self {
+        let mut instruction = Self::new(op);
+        instruction.set_type(result_type_id);
+        instruction.set_result(id);
+        instruction.add_operand(exec_scope_id);
+        instruction.add_operand(predicate);
+        instruction
+    }
+
+    pub(super) fn group_non_uniform_arithmetic(
+        op: Op,
+        result_type_id: Word,
+        id: Word,
+        exec_scope_id: Word,
+        group_op: spirv::GroupOperation,
+        value: Word,
+    ) -> Self {
+        let mut instruction = Self::new(op);
+        instruction.set_type(result_type_id);
+        instruction.set_result(id);
+        instruction.add_operand(exec_scope_id);
+        instruction.add_operand(group_op as u32);
+        instruction.add_operand(value);
+        instruction
+    }
+
+    pub(super) fn group_non_uniform_gather(
+        op: Op,
+        result_type_id: Word,
+        id: Word,
+        exec_scope_id: Word,
+        value: Word,
+        index: Option<Word>,
+    ) -> Self {
+        let mut instruction = Self::new(op);
+        instruction.set_type(result_type_id);
+        instruction.set_result(id);
+        instruction.add_operand(exec_scope_id);
+        instruction.add_operand(value);
+        if let Some(index) = index {
+            instruction.add_operand(index);
+        }
+        instruction
+    }
 }
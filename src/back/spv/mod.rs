@@ -2,13 +2,16 @@
 !*/
 
 mod block;
+mod disassembler;
 mod helpers;
 mod index;
 mod instructions;
 mod layout;
 mod recyclable;
+mod subgroup;
 mod writer;
 
+pub use disassembler::disassemble;
 pub use spirv::Capability;
 
 use crate::{arena::Handle, back::IndexBoundsCheckPolicy, proc::TypeResolution};
@@ -61,6 +64,18 @@ pub enum Error {
     FeatureNotImplemented(&'static str),
     #[error("module is not validated properly: {0}")]
     Validation(&'static str),
+    #[error("expression {0:?} has an unexpected type for {1}")]
+    InvalidExpressionType(Handle<crate::Expression>, &'static str),
+    #[error("unary operation {1:?} of expression {0:?} is not supported for its operand")]
+    UnsupportedUnaryOperation(Handle<crate::Expression>, crate::UnaryOperator),
+    #[error("binary operation {1:?} of expression {0:?} is not supported for its operands")]
+    UnsupportedBinaryOperation(Handle<crate::Expression>, crate::BinaryOperator),
+    #[error("math function {1:?} of expression {0:?} is not supported for its arguments")]
+    UnsupportedMathFunction(Handle<crate::Expression>, crate::MathFunction),
+    #[error("expression {0:?} can't be used as a pointer")]
+    UnexpectedPointerExpression(Handle<crate::Expression>),
+    #[error("expression {0:?} can't be used as an image or a sampler")]
+    UnexpectedImageExpression(Handle<crate::Expression>),
     #[error(transparent)]
     Proc(#[from] crate::proc::ProcError),
 }
@@ -211,12 +226,14 @@ struct LookupFunctionType {
 
 fn make_local(inner: &crate::TypeInner) -> Option<LocalType> {
     Some(match *inner {
-        crate::TypeInner::Scalar { kind, width } => LocalType::Value {
-            vector_size: None,
-            kind,
-            width,
-            pointer_class: None,
-        },
+        crate::TypeInner::Scalar { kind, width } | crate::TypeInner::Atomic { kind, width } => {
+            LocalType::Value {
+                vector_size: None,
+                kind,
+                width,
+                pointer_class: None,
+            }
+        }
         crate::TypeInner::Vector { size, kind, width } => LocalType::Value {
             vector_size: Some(size),
             kind,
@@ -261,7 +278,7 @@ fn make_local(inner: &crate::TypeInner) -> Option<LocalType> {
     })
 }
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 enum Dimension {
     Scalar,
     Vector,
@@ -384,6 +401,11 @@ pub struct Writer {
     annotations: Vec<Instruction>,
     flags: WriterFlags,
     index_bounds_check_policy: IndexBoundsCheckPolicy,
+    debug_info: Option<DebugInfo>,
+    /// Byte offsets at which the lines of the debug source code start.
+    source_line_starts: Vec<usize>,
+    /// Id of the `OpString` with the source file name, for `OpLine`.
+    source_file_id: Option<Word>,
     void_type: Word,
     //TODO: convert most of these into vectors, addressable by handle indices
     lookup_type: crate::FastHashMap<LookupType, Word>,
@@ -393,6 +415,9 @@ pub struct Writer {
     constant_ids: Vec<Word>,
     cached_constants: crate::FastHashMap<(crate::ScalarValue, crate::Bytes), Word>,
     global_variables: Vec<GlobalVariable>,
+    /// Ids decorated as `NonUniform`, so that values shared by several
+    /// accesses are only decorated once.
+    non_uniform_ids: crate::FastHashSet<Word>,
 
     // Cached expressions are only meaningful within a BlockContext, but we
     // retain the table here between functions to save heap allocations.
@@ -412,6 +437,18 @@ bitflags::bitflags! {
     }
 }
 
+/// Source code to embed into the module, so that debuggers can map
+/// instructions back to it.
+///
+/// Line information is only available with the `span` feature.
+#[derive(Debug, Clone)]
+pub struct DebugInfo {
+    /// Name of the source file, referenced by `OpLine` instructions.
+    pub file_name: String,
+    /// Text of the source file, embedded with `OpSource`.
+    pub source_code: String,
+}
+
 #[derive(Debug, Clone)]
 pub struct Options {
     /// (Major, Minor) target version of the SPIR-V.
@@ -425,6 +462,8 @@ pub struct Options {
     /// How should the generated code handle array, vector, or matrix indices
     /// that are out of range?
     pub index_bounds_check_policy: IndexBoundsCheckPolicy,
+    /// Source code and file name to describe in the debug instructions.
+    pub debug_info: Option<DebugInfo>,
 }
 
 impl Default for Options {
@@ -438,6 +477,7 @@ impl Default for Options {
             flags,
             capabilities: None,
             index_bounds_check_policy: super::IndexBoundsCheckPolicy::default(),
+            debug_info: None,
         }
     }
 }
@@ -0,0 +1,186 @@
+//! Implementations for subgroup statements of `BlockContext`.
+
+use super::{Block, BlockContext, Error, Instruction};
+use crate::arena::Handle;
+
+impl<'w> BlockContext<'w> {
+    /// Require the non-uniform group capabilities, available since SPIR-V 1.3.
+    fn require_group_non_uniform(&mut self, capability: spirv::Capability) -> Result<(), Error> {
+        let version = self.writer.physical_layout.version;
+        if version < 0x10300 {
+            return Err(Error::UnsupportedVersion(
+                (version >> 16) as u8,
+                (version >> 8) as u8,
+            ));
+        }
+        self.writer.check(&[spirv::Capability::GroupNonUniform])?;
+        self.writer.check(&[capability])
+    }
+
+    pub(super) fn write_subgroup_ballot(
+        &mut self,
+        predicate: Option<Handle<crate::Expression>>,
+        result: Handle<crate::Expression>,
+        block: &mut Block,
+    ) -> Result<(), Error> {
+        self.require_group_non_uniform(spirv::Capability::GroupNonUniformBallot)?;
+        let id = self.gen_id();
+        let result_type_id = self.get_expression_type_id(&self.fun_info[result].ty)?;
+        self.cached[result] = id;
+
+        let exec_scope_id = self.get_index_constant(spirv::Scope::Subgroup as u32)?;
+        let predicate_id = match predicate {
+            Some(predicate) => self.cached[predicate],
+            None => self
+                .writer
+                .get_constant_scalar(crate::ScalarValue::Bool(true), crate::BOOL_WIDTH)?,
+        };
+        block.body.push(Instruction::group_non_uniform_ballot(
+            result_type_id,
+            id,
+            exec_scope_id,
+            predicate_id,
+        ));
+        Ok(())
+    }
+
+    pub(super) fn write_subgroup_operation(
+        &mut self,
+        op: crate::SubgroupOperation,
+        collective_op: crate::CollectiveOperation,
+        argument: Handle<crate::Expression>,
+        result: Handle<crate::Expression>,
+        block: &mut Block,
+    ) -> Result<(), Error> {
+        use crate::{ScalarKind as Sk, SubgroupOperation as So};
+        use spirv::Op;
+
+        let id = self.gen_id();
+        let result_type_id = self.get_expression_type_id(&self.fun_info[result].ty)?;
+        self.cached[result] = id;
+
+        let exec_scope_id = self.get_index_constant(spirv::Scope::Subgroup as u32)?;
+        let argument_id = self.cached[argument];
+
+        let kind = match self.fun_info[argument]
+            .ty
+            .inner_with(&self.ir_module.types)
+            .scalar_kind()
+        {
+            Some(kind) => kind,
+            None => return Err(Error::Validation("subgroup operand type")),
+        };
+
+        let vote = match op {
+            So::All => Some(Op::GroupNonUniformAll),
+            So::Any => Some(Op::GroupNonUniformAny),
+            _ => None,
+        };
+        if let Some(spirv_op) = vote {
+            self.require_group_non_uniform(spirv::Capability::GroupNonUniformVote)?;
+            block.body.push(Instruction::group_non_uniform_vote(
+                spirv_op,
+                result_type_id,
+                id,
+                exec_scope_id,
+                argument_id,
+            ));
+            return Ok(());
+        }
+
+        self.require_group_non_uniform(spirv::Capability::GroupNonUniformArithmetic)?;
+        let spirv_op = match (op, kind) {
+            (So::Add, Sk::Float) => Op::GroupNonUniformFAdd,
+            (So::Add, _) => Op::GroupNonUniformIAdd,
+            (So::Mul, Sk::Float) => Op::GroupNonUniformFMul,
+            (So::Mul, _) => Op::GroupNonUniformIMul,
+            (So::Min, Sk::Float) => Op::GroupNonUniformFMin,
+            (So::Min, Sk::Sint) => Op::GroupNonUniformSMin,
+            (So::Min, _) => Op::GroupNonUniformUMin,
+            (So::Max, Sk::Float) => Op::GroupNonUniformFMax,
+            (So::Max, Sk::Sint) => Op::GroupNonUniformSMax,
+            (So::Max, _) => Op::GroupNonUniformUMax,
+            (So::And, Sk::Bool) => Op::GroupNonUniformLogicalAnd,
+            (So::And, _) => Op::GroupNonUniformBitwiseAnd,
+            (So::Or, Sk::Bool) => Op::GroupNonUniformLogicalOr,
+            (So::Or, _) => Op::GroupNonUniformBitwiseOr,
+            (So::Xor, Sk::Bool) => Op::GroupNonUniformLogicalXor,
+            (So::Xor, _) => Op::GroupNonUniformBitwiseXor,
+            (So::All, _) | (So::Any, _) => unreachable!(),
+        };
+        let group_op = match collective_op {
+            crate::CollectiveOperation::Reduce => spirv::GroupOperation::Reduce,
+            crate::CollectiveOperation::InclusiveScan => spirv::GroupOperation::InclusiveScan,
+            crate::CollectiveOperation::ExclusiveScan => spirv::GroupOperation::ExclusiveScan,
+        };
+        block.body.push(Instruction::group_non_uniform_arithmetic(
+            spirv_op,
+            result_type_id,
+            id,
+            exec_scope_id,
+            group_op,
+            argument_id,
+        ));
+        Ok(())
+    }
+
+    pub(super) fn write_subgroup_gather(
+        &mut self,
+        mode: &crate::GatherMode,
+        argument: Handle<crate::Expression>,
+        result: Handle<crate::Expression>,
+        block: &mut Block,
+    ) -> Result<(), Error> {
+        use crate::GatherMode as Gm;
+        use spirv::{Capability as Cap, Op};
+
+        let (spirv_op, capability, index) = match *mode {
+            Gm::BroadcastFirst => (
+                Op::GroupNonUniformBroadcastFirst,
+                Cap::GroupNonUniformBallot,
+                None,
+            ),
+            Gm::Broadcast(index) => (
+                Op::GroupNonUniformBroadcast,
+                Cap::GroupNonUniformBallot,
+                Some(index),
+            ),
+            Gm::Shuffle(index) => (
+                Op::GroupNonUniformShuffle,
+                Cap::GroupNonUniformShuffle,
+                Some(index),
+            ),
+            Gm::ShuffleXor(index) => (
+                Op::GroupNonUniformShuffleXor,
+                Cap::GroupNonUniformShuffle,
+                Some(index),
+            ),
+            Gm::ShuffleDown(index) => (
+                Op::GroupNonUniformShuffleDown,
+                Cap::GroupNonUniformShuffleRelative,
+                Some(index),
+            ),
+            Gm::ShuffleUp(index) => (
+                Op::GroupNonUniformShuffleUp,
+                Cap::GroupNonUniformShuffleRelative,
+                Some(index),
+            ),
+        };
+        self.require_group_non_uniform(capability)?;
+
+        let id = self.gen_id();
+        let result_type_id = self.get_expression_type_id(&self.fun_info[result].ty)?;
+        self.cached[result] = id;
+
+        let exec_scope_id = self.get_index_constant(spirv::Scope::Subgroup as u32)?;
+        block.body.push(Instruction::group_non_uniform_gather(
+            spirv_op,
+            result_type_id,
+            id,
+            exec_scope_id,
+            self.cached[argument],
+            index.map(|index| self.cached[index]),
+        ));
+        Ok(())
+    }
+}
@@ -64,6 +64,19 @@ impl Writer {
         let gl450_ext_inst_id = id_gen.next();
         let void_type = id_gen.next();
 
+        let source_line_starts = match options.debug_info {
+            Some(ref debug_info) => Some(0)
+                .into_iter()
+                .chain(
+                    debug_info
+                        .source_code
+                        .match_indices('\n')
+                        .map(|(offset, _)| offset + 1),
+                )
+                .collect(),
+            None => Vec::new(),
+        };
+
         Ok(Writer {
             physical_layout: PhysicalLayout::new(raw_version),
             logical_layout: LogicalLayout::default(),
@@ -74,6 +87,9 @@ impl Writer {
             annotations: vec![],
             flags: options.flags,
             index_bounds_check_policy: options.index_bounds_check_policy,
+            debug_info: options.debug_info.clone(),
+            source_line_starts,
+            source_file_id: None,
             void_type,
             lookup_type: crate::FastHashMap::default(),
             lookup_function: crate::FastHashMap::default(),
@@ -82,6 +98,7 @@ impl Writer {
             constant_ids: Vec::new(),
             cached_constants: crate::FastHashMap::default(),
             global_variables: Vec::new(),
+            non_uniform_ids: crate::FastHashSet::default(),
             saved_cached: CachedExpressions::default(),
             gl450_ext_inst_id,
             temp_list: Vec::new(),
@@ -113,9 +130,12 @@ impl Writer {
             index_bounds_check_policy: self.index_bounds_check_policy,
             capabilities: take(&mut self.capabilities),
             forbidden_caps: take(&mut self.forbidden_caps),
+            debug_info: take(&mut self.debug_info),
+            source_line_starts: take(&mut self.source_line_starts),
 
             // Initialized afresh:
             id_gen,
+            source_file_id: None,
             void_type,
             gl450_ext_inst_id,
 
@@ -131,6 +151,7 @@ impl Writer {
             constant_ids: take(&mut self.constant_ids).recycle(),
             cached_constants: take(&mut self.cached_constants).recycle(),
             global_variables: take(&mut self.global_variables).recycle(),
+            non_uniform_ids: take(&mut self.non_uniform_ids).recycle(),
             saved_cached: take(&mut self.saved_cached).recycle(),
             temp_list: take(&mut self.temp_list).recycle(),
         };
@@ -138,6 +159,26 @@ impl Writer {
         *self = fresh;
     }
 
+    /// Return an `OpLine` pointing at the start of `span` in the debug source.
+    pub(super) fn debug_line(&self, span: crate::Span) -> Option<Instruction> {
+        let file_id = self.source_file_id?;
+        let offset = span.to_range()?.start;
+        // spans past the end don't come from this source
+        if offset > self.debug_info.as_ref()?.source_code.len() {
+            return None;
+        }
+        let line = match self.source_line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next_line) => next_line - 1,
+        };
+        let column = offset - self.source_line_starts[line];
+        Some(Instruction::line(
+            file_id,
+            line as Word + 1,
+            column as Word + 1,
+        ))
+    }
+
     pub(super) fn check(&mut self, capabilities: &[spirv::Capability]) -> Result<(), Error> {
         if capabilities.is_empty()
             || capabilities
@@ -220,11 +261,23 @@ impl Writer {
         self.get_type_id(local_type.into())
     }
 
-    fn decorate(&mut self, id: Word, decoration: spirv::Decoration, operands: &[Word]) {
+    pub(super) fn decorate(&mut self, id: Word, decoration: spirv::Decoration, operands: &[Word]) {
         self.annotations
             .push(Instruction::decorate(id, decoration, operands));
     }
 
+    /// Decorate `id` as `NonUniform`, unless it already is.
+    pub(super) fn decorate_non_uniform(&mut self, id: Word) {
+        if self.non_uniform_ids.insert(id) {
+            self.decorate(id, spirv::Decoration::NonUniform, &[]);
+        }
+    }
+
+    /// Return true if `id` was decorated as `NonUniform`.
+    pub(super) fn is_non_uniform(&self, id: Word) -> bool {
+        self.non_uniform_ids.contains(&id)
+    }
+
     fn write_function(
         &mut self,
         ir_function: &crate::Function,
@@ -413,6 +466,10 @@ impl Writer {
             if info[handle].is_empty() || var.class != crate::StorageClass::Handle {
                 continue;
             }
+            // Binding arrays are indexed first, and loaded element by element.
+            if let crate::TypeInner::BindingArray { .. } = ir_module.types[var.ty].inner {
+                continue;
+            }
             let id = self.id_gen.next();
             let result_type_id = self.get_type_id(LookupType::Handle(var.ty))?;
             let gv = &mut self.global_variables[handle.index()];
@@ -671,7 +728,9 @@ impl Writer {
         use spirv::Decoration;
 
         let instruction = match ty.inner {
-            crate::TypeInner::Scalar { kind, width } => self.make_scalar(id, kind, width),
+            crate::TypeInner::Scalar { kind, width } | crate::TypeInner::Atomic { kind, width } => {
+                self.make_scalar(id, kind, width)
+            }
             crate::TypeInner::Vector { size, kind, width } => {
                 let scalar_id = self.get_type_id(LookupType::Local(LocalType::Value {
                     vector_size: None,
@@ -738,6 +797,19 @@ impl Writer {
                     crate::ArraySize::Dynamic => Instruction::type_runtime_array(id, type_id),
                 }
             }
+            crate::TypeInner::BindingArray { base, size } => {
+                let type_id = self.get_type_id(LookupType::Handle(base))?;
+                match size {
+                    crate::ArraySize::Constant(const_handle) => {
+                        let length_id = self.constant_ids[const_handle.index()];
+                        Instruction::type_array(id, type_id, length_id)
+                    }
+                    crate::ArraySize::Dynamic => {
+                        self.check(&[spirv::Capability::RuntimeDescriptorArray])?;
+                        Instruction::type_runtime_array(id, type_id)
+                    }
+                }
+            }
             crate::TypeInner::Struct {
                 top_level,
                 ref members,
@@ -839,7 +911,7 @@ impl Writer {
             return Ok(id);
         }
         let id = self.id_gen.next();
-        self.write_constant_scalar(id, &value, width, None)?;
+        self.write_constant_scalar(id, &value, width, None, None)?;
         self.cached_constants.insert((value, width), id);
         Ok(id)
     }
@@ -850,12 +922,20 @@ impl Writer {
         value: &crate::ScalarValue,
         width: crate::Bytes,
         debug_name: Option<&String>,
+        spec_id: Option<Word>,
     ) -> Result<(), Error> {
         if self.flags.contains(WriterFlags::DEBUG) {
             if let Some(name) = debug_name {
                 self.debugs.push(Instruction::name(id, name));
             }
         }
+        if let Some(spec_id) = spec_id {
+            self.decorate(id, spirv::Decoration::SpecId, &[spec_id]);
+        }
+        let constant = match spec_id {
+            Some(_) => Instruction::spec_constant,
+            None => Instruction::constant,
+        };
         let type_id = self.get_type_id(LookupType::Local(LocalType::Value {
             vector_size: None,
             kind: value.scalar_kind(),
@@ -870,13 +950,14 @@ impl Writer {
                         solo = [val as u32];
                         &solo[..]
                     }
+                    // the low-order word comes first
                     8 => {
-                        pair = [(val >> 32) as u32, val as u32];
+                        pair = [val as u32, (val >> 32) as u32];
                         &pair
                     }
-                    _ => unreachable!(),
+                    _ => return Err(Error::Validation("scalar width")),
                 };
-                Instruction::constant(type_id, id, words)
+                constant(type_id, id, words)
             }
             crate::ScalarValue::Uint(val) => {
                 let words = match width {
@@ -884,13 +965,14 @@ impl Writer {
                         solo = [val as u32];
                         &solo[..]
                     }
+                    // the low-order word comes first
                     8 => {
-                        pair = [(val >> 32) as u32, val as u32];
+                        pair = [val as u32, (val >> 32) as u32];
                         &pair
                     }
-                    _ => unreachable!(),
+                    _ => return Err(Error::Validation("scalar width")),
                 };
-                Instruction::constant(type_id, id, words)
+                constant(type_id, id, words)
             }
             crate::ScalarValue::Float(val) => {
                 let words = match width {
@@ -900,15 +982,21 @@ impl Writer {
                     }
                     8 => {
                         let bits = f64::to_bits(val);
-                        pair = [(bits >> 32) as u32, bits as u32];
+                        pair = [bits as u32, (bits >> 32) as u32];
                         &pair
                     }
-                    _ => unreachable!(),
+                    _ => return Err(Error::Validation("scalar width")),
                 };
-                Instruction::constant(type_id, id, words)
+                constant(type_id, id, words)
             }
-            crate::ScalarValue::Bool(true) => Instruction::constant_true(type_id, id),
-            crate::ScalarValue::Bool(false) => Instruction::constant_false(type_id, id),
+            crate::ScalarValue::Bool(true) => match spec_id {
+                Some(_) => Instruction::spec_constant_true(type_id, id),
+                None => Instruction::constant_true(type_id, id),
+            },
+            crate::ScalarValue::Bool(false) => match spec_id {
+                Some(_) => Instruction::spec_constant_false(type_id, id),
+                None => Instruction::constant_false(type_id, id),
+            },
         };
 
         instruction.to_words(&mut self.logical_layout.declarations);
@@ -920,6 +1008,7 @@ impl Writer {
         id: Word,
         ty: Handle<crate::Type>,
         components: &[Handle<crate::Constant>],
+        specialized: bool,
     ) -> Result<(), Error> {
         let mut constituent_ids = Vec::with_capacity(components.len());
         for constituent in components.iter() {
@@ -928,8 +1017,12 @@ impl Writer {
         }
 
         let type_id = self.get_type_id(LookupType::Handle(ty))?;
-        Instruction::constant_composite(type_id, id, constituent_ids.as_slice())
-            .to_words(&mut self.logical_layout.declarations);
+        let instruction = if specialized {
+            Instruction::spec_constant_composite(type_id, id, constituent_ids.as_slice())
+        } else {
+            Instruction::constant_composite(type_id, id, constituent_ids.as_slice())
+        };
+        instruction.to_words(&mut self.logical_layout.declarations);
         Ok(())
     }
 
@@ -1024,6 +1117,15 @@ impl Writer {
                     Bi::LocalInvocationIndex => BuiltIn::LocalInvocationIndex,
                     Bi::WorkGroupId => BuiltIn::WorkgroupId,
                     Bi::WorkGroupSize => BuiltIn::WorkgroupSize,
+                    // subgroup
+                    Bi::SubgroupSize => {
+                        self.capabilities.insert(spirv::Capability::GroupNonUniform);
+                        BuiltIn::SubgroupSize
+                    }
+                    Bi::SubgroupInvocationId => {
+                        self.capabilities.insert(spirv::Capability::GroupNonUniform);
+                        BuiltIn::SubgroupLocalInvocationId
+                    }
                 };
 
                 self.decorate(id, Decoration::BuiltIn, &[built_in as u32]);
@@ -1043,9 +1145,13 @@ impl Writer {
         let class = map_storage_class(global_variable.class);
         self.check(class.required_capabilities())?;
 
-        let init_word = global_variable
-            .init
-            .map(|constant| self.constant_ids[constant.index()]);
+        // Only private variables can have initializers
+        let init_word = match global_variable.class {
+            crate::StorageClass::Private => global_variable
+                .init
+                .map(|constant| self.constant_ids[constant.index()]),
+            _ => None,
+        };
         let pointer_type_id = self.get_pointer_id(&ir_module.types, global_variable.ty, class)?;
         let instruction = Instruction::variable(pointer_type_id, id, class, init_word);
 
@@ -1071,7 +1177,6 @@ impl Writer {
             self.decorate(id, Decoration::Binding, &[res_binding.binding]);
         }
 
-        // TODO Initializer is optional and not (yet) included in the IR
         Ok((instruction, id))
     }
 
@@ -1117,25 +1222,72 @@ impl Writer {
         Instruction::ext_inst_import(self.gl450_ext_inst_id, "GLSL.std.450")
             .to_words(&mut self.logical_layout.ext_inst_imports);
 
-        if self.flags.contains(WriterFlags::DEBUG) {
+        if let Some(ref debug_info) = self.debug_info {
+            let file_id = self.id_gen.next();
+            Instruction::string(&debug_info.file_name, file_id)
+                .to_words(&mut self.logical_layout.debugs);
+            // Instructions are limited to 0xFFFF words, so long sources
+            // are split across `OpSourceContinued` instructions.
+            const MAX_SOURCE_CHUNK: usize = 0xFFF0 * 4;
+            let mut source_code = debug_info.source_code.as_str();
+            let mut chunks = Vec::new();
+            while source_code.len() > MAX_SOURCE_CHUNK {
+                let mut end = MAX_SOURCE_CHUNK;
+                while !source_code.is_char_boundary(end) {
+                    end -= 1;
+                }
+                let (chunk, rest) = source_code.split_at(end);
+                chunks.push(chunk);
+                source_code = rest;
+            }
+            chunks.push(source_code);
+            Instruction::source(
+                spirv::SourceLanguage::Unknown,
+                0,
+                Some((file_id, chunks[0])),
+            )
+            .to_words(&mut self.logical_layout.debugs);
+            for chunk in chunks[1..].iter() {
+                Instruction::source_continued(chunk).to_words(&mut self.logical_layout.debugs);
+            }
+            self.source_file_id = Some(file_id);
+        } else if self.flags.contains(WriterFlags::DEBUG) {
             self.debugs
-                .push(Instruction::source(spirv::SourceLanguage::GLSL, 450));
+                .push(Instruction::source(spirv::SourceLanguage::GLSL, 450, None));
         }
 
         self.constant_ids.resize(ir_module.constants.len(), 0);
+        // A composite is a specialization constant if any of its components is.
+        let mut specialized = Vec::with_capacity(ir_module.constants.len());
         // first, output all the scalar constants
         for (handle, constant) in ir_module.constants.iter() {
+            specialized.push(match constant.inner {
+                crate::ConstantInner::Scalar { .. } => constant.specialization.is_some(),
+                crate::ConstantInner::Composite { ref components, .. } => {
+                    constant.specialization.is_some()
+                        || components
+                            .iter()
+                            .any(|component| specialized[component.index()])
+                }
+            });
             match constant.inner {
                 crate::ConstantInner::Composite { .. } => continue,
                 crate::ConstantInner::Scalar { width, ref value } => {
-                    self.constant_ids[handle.index()] = match constant.name {
-                        Some(ref name) => {
-                            let id = self.id_gen.next();
-                            self.write_constant_scalar(id, value, width, Some(name))?;
-                            id
-                        }
-                        None => self.get_constant_scalar(*value, width)?,
-                    };
+                    self.constant_ids[handle.index()] =
+                        match (&constant.name, constant.specialization) {
+                            (&None, None) => self.get_constant_scalar(*value, width)?,
+                            (name, spec_id) => {
+                                let id = self.id_gen.next();
+                                self.write_constant_scalar(
+                                    id,
+                                    value,
+                                    width,
+                                    name.as_ref(),
+                                    spec_id,
+                                )?;
+                                id
+                            }
+                        };
                 }
             }
         }
@@ -1157,7 +1309,7 @@ impl Writer {
                             self.debugs.push(Instruction::name(id, name));
                         }
                     }
-                    self.write_constant_composite(id, ty, components)?;
+                    self.write_constant_composite(id, ty, components, specialized[handle.index()])?;
                 }
             }
         }
@@ -1185,6 +1337,21 @@ impl Writer {
             ep_instruction.to_words(&mut self.logical_layout.entry_points);
         }
 
+        let uses_descriptor_indexing = self.capabilities.iter().any(|&cap| match cap {
+            spirv::Capability::RuntimeDescriptorArray
+            | spirv::Capability::ShaderNonUniform
+            | spirv::Capability::SampledImageArrayNonUniformIndexing
+            | spirv::Capability::StorageImageArrayNonUniformIndexing
+            | spirv::Capability::UniformBufferArrayNonUniformIndexing
+            | spirv::Capability::StorageBufferArrayNonUniformIndexing => true,
+            _ => false,
+        });
+        if self.physical_layout.version < 0x10500 && uses_descriptor_indexing {
+            // descriptor indexing is only core since SPV-1.5
+            Instruction::extension("SPV_EXT_descriptor_indexing")
+                .to_words(&mut self.logical_layout.extensions);
+        }
+
         for capability in self.capabilities.iter() {
             Instruction::capability(*capability).to_words(&mut self.logical_layout.capabilities);
         }
@@ -1239,3 +1406,122 @@ fn test_write_physical_layout() {
     writer.write_physical_layout();
     assert_eq!(writer.physical_layout.bound, 3);
 }
+
+#[cfg(feature = "wgsl-in")]
+#[test]
+fn test_write_debug_info() {
+    let source_code = "[[stage(compute), workgroup_size(1)]]\nfn main() {\n    var x: f32 = 1.0;\n    x = x * 2.0;\n}\n";
+    let module = crate::front::wgsl::parse_str(source_code).unwrap();
+    let info = crate::valid::Validator::new(
+        crate::valid::ValidationFlags::all(),
+        crate::valid::Capabilities::empty(),
+    )
+    .validate(&module)
+    .unwrap();
+    let options = Options {
+        debug_info: Some(super::DebugInfo {
+            file_name: "main.wgsl".to_string(),
+            source_code: source_code.to_string(),
+        }),
+        ..Options::default()
+    };
+    let words = super::write_vec(&module, &info, &options).unwrap();
+
+    let mut file_id = None;
+    let mut lines = Vec::new();
+    let mut rest = &words[5..];
+    while !rest.is_empty() {
+        let (wc, op) = ((rest[0] >> 16) as usize, rest[0] & 0xFFFF);
+        if op == spirv::Op::String as u32 {
+            file_id = Some(rest[1]);
+        } else if op == spirv::Op::Source as u32 {
+            assert_eq!(Some(rest[3]), file_id);
+        } else if op == spirv::Op::Line as u32 {
+            assert_eq!(Some(rest[1]), file_id);
+            lines.push((rest[2], rest[3]));
+        }
+        rest = &rest[wc..];
+    }
+    assert!(file_id.is_some());
+    if cfg!(feature = "span") {
+        // the assignment in the fourth line
+        assert!(lines.contains(&(4, 5)));
+    }
+}
+
+#[cfg(feature = "wgsl-in")]
+#[test]
+fn test_write_arithmetic() {
+    let source_code = "
+        [[stage(compute), workgroup_size(1)]]
+        fn main() {
+            let m = mat2x2<f32>(vec2<f32>(1.0, 0.0), vec2<f32>(0.0, 1.0));
+            let v = vec2<i32>(1, 2) * 3;
+            let sum = m + m;
+            let negated = -m;
+            let scaled = 2.0 * m;
+            let remainder = 5.5 % 2.0;
+        }
+    ";
+    let module = crate::front::wgsl::parse_str(source_code).unwrap();
+    let info = crate::valid::Validator::new(
+        crate::valid::ValidationFlags::all(),
+        crate::valid::Capabilities::empty(),
+    )
+    .validate(&module)
+    .unwrap();
+    let words = super::write_vec(&module, &info, &Options::default()).unwrap();
+
+    let mut ops = Vec::new();
+    let mut rest = &words[5..];
+    while !rest.is_empty() {
+        ops.push(rest[0] & 0xFFFF);
+        rest = &rest[(rest[0] >> 16) as usize..];
+    }
+    let count = |op: spirv::Op| ops.iter().filter(|&&other| other == op as u32).count();
+    // the integer vector is multiplied by a splatted scalar
+    assert_eq!(count(spirv::Op::IMul), 1);
+    assert_eq!(count(spirv::Op::VectorTimesScalar), 0);
+    // matrices are added and negated column by column
+    assert_eq!(count(spirv::Op::FAdd), 2);
+    assert_eq!(count(spirv::Op::FNegate), 2);
+    assert_eq!(count(spirv::Op::MatrixTimesScalar), 1);
+    assert_eq!(count(spirv::Op::FRem), 1);
+}
+
+#[cfg(feature = "wgsl-in")]
+#[test]
+fn test_write_unsupported_operation() {
+    let source_code = "
+        [[stage(compute), workgroup_size(1)]]
+        fn main() {
+            var x: f32 = 1.0;
+            x = x + 2.0;
+        }
+    ";
+    let mut module = crate::front::wgsl::parse_str(source_code).unwrap();
+    let info = crate::valid::Validator::new(
+        crate::valid::ValidationFlags::all(),
+        crate::valid::Capabilities::empty(),
+    )
+    .validate(&module)
+    .unwrap();
+
+    // floats can't be shifted, but only the validator checks that
+    let (handle, op) = module.entry_points[0]
+        .function
+        .expressions
+        .iter_mut()
+        .find_map(|(handle, expr)| match *expr {
+            crate::Expression::Binary { ref mut op, .. } => Some((handle, op)),
+            _ => None,
+        })
+        .unwrap();
+    *op = crate::BinaryOperator::ShiftRight;
+    match super::write_vec(&module, &info, &Options::default()) {
+        Err(Error::UnsupportedBinaryOperation(expr, crate::BinaryOperator::ShiftRight)) => {
+            assert_eq!(expr, handle)
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+}
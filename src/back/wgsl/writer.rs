@@ -76,7 +76,7 @@ impl<W: Write> Writer<W> {
 
         // Write all constants
         for (handle, constant) in module.constants.iter() {
-            if constant.name.is_some() {
+            if constant.name.is_some() || constant.specialization.is_some() {
                 self.write_global_constant(module, &constant.inner, handle)?;
             }
         }
@@ -502,6 +502,9 @@ impl<W: Write> Writer<W> {
             TypeInner::Scalar { kind, .. } => {
                 write!(self.out, "{}", scalar_kind_str(kind))?;
             }
+            TypeInner::Atomic { kind, .. } => {
+                write!(self.out, "atomic<{}>", scalar_kind_str(kind))?;
+            }
             TypeInner::Array { base, size, .. } => {
                 // More info https://gpuweb.github.io/gpuweb/wgsl/#array-types
                 // array<A, 3> -- Constant array
@@ -519,6 +522,17 @@ impl<W: Write> Writer<W> {
                 }
                 write!(self.out, ">")?;
             }
+            TypeInner::BindingArray { base, size } => {
+                // binding_array<A, 3> -- Constant array
+                // binding_array<A> -- Dynamic array
+                write!(self.out, "binding_array<")?;
+                self.write_type(module, base)?;
+                if let crate::ArraySize::Constant(handle) = size {
+                    write!(self.out, ",")?;
+                    self.write_constant(module, handle)?;
+                }
+                write!(self.out, ">")?;
+            }
             TypeInner::Matrix {
                 columns,
                 rows,
@@ -668,10 +682,18 @@ impl<W: Write> Writer<W> {
                     }
                 }
                 write!(self.out, "{}", INDENT.repeat(indent))?;
-                self.write_expr(module, pointer, func_ctx)?;
-                write!(self.out, " = ")?;
-                self.write_expr(module, value, func_ctx)?;
-                writeln!(self.out, ";")?
+                if is_atomic_pointer(pointer, module, func_ctx.info) {
+                    write!(self.out, "atomicStore(&")?;
+                    self.write_expr(module, pointer, func_ctx)?;
+                    write!(self.out, ", ")?;
+                    self.write_expr(module, value, func_ctx)?;
+                    writeln!(self.out, ");")?
+                } else {
+                    self.write_expr(module, pointer, func_ctx)?;
+                    write!(self.out, " = ")?;
+                    self.write_expr(module, value, func_ctx)?;
+                    writeln!(self.out, ";")?
+                }
             }
             Statement::Call {
                 function,
@@ -697,6 +719,116 @@ impl<W: Write> Writer<W> {
                 }
                 writeln!(self.out, ");")?
             }
+            Statement::Atomic {
+                pointer,
+                ref fun,
+                value,
+                result,
+            } => {
+                write!(self.out, "{}", INDENT.repeat(indent))?;
+                let name = format!("{}{}", back::BAKE_PREFIX, result.index());
+                self.start_named_expr(module, result, func_ctx, &name)?;
+                self.named_expressions.insert(result, name);
+
+                let fun_str = match *fun {
+                    crate::AtomicFunction::Add => "atomicAdd",
+                    crate::AtomicFunction::Subtract => "atomicSub",
+                    crate::AtomicFunction::And => "atomicAnd",
+                    crate::AtomicFunction::InclusiveOr => "atomicOr",
+                    crate::AtomicFunction::ExclusiveOr => "atomicXor",
+                    crate::AtomicFunction::Min => "atomicMin",
+                    crate::AtomicFunction::Max => "atomicMax",
+                    crate::AtomicFunction::Exchange { compare: None } => "atomicExchange",
+                    crate::AtomicFunction::Exchange { compare: Some(_) } => {
+                        "atomicCompareExchangeWeak"
+                    }
+                };
+                write!(self.out, "{}(&", fun_str)?;
+                self.write_expr(module, pointer, func_ctx)?;
+                if let crate::AtomicFunction::Exchange { compare: Some(cmp) } = *fun {
+                    write!(self.out, ", ")?;
+                    self.write_expr(module, cmp, func_ctx)?;
+                }
+                write!(self.out, ", ")?;
+                self.write_expr(module, value, func_ctx)?;
+                writeln!(self.out, ");")?
+            }
+            Statement::SubgroupBallot { result, predicate } => {
+                write!(self.out, "{}", INDENT.repeat(indent))?;
+                let name = format!("{}{}", back::BAKE_PREFIX, result.index());
+                self.start_named_expr(module, result, func_ctx, &name)?;
+                self.named_expressions.insert(result, name);
+
+                write!(self.out, "subgroupBallot(")?;
+                if let Some(predicate) = predicate {
+                    self.write_expr(module, predicate, func_ctx)?;
+                }
+                writeln!(self.out, ");")?;
+            }
+            Statement::SubgroupCollectiveOperation {
+                op,
+                collective_op,
+                argument,
+                result,
+            } => {
+                use crate::{CollectiveOperation as Co, SubgroupOperation as So};
+
+                write!(self.out, "{}", INDENT.repeat(indent))?;
+                let name = format!("{}{}", back::BAKE_PREFIX, result.index());
+                self.start_named_expr(module, result, func_ctx, &name)?;
+                self.named_expressions.insert(result, name);
+
+                let fun_str = match (collective_op, op) {
+                    (Co::Reduce, So::All) => "subgroupAll",
+                    (Co::Reduce, So::Any) => "subgroupAny",
+                    (Co::Reduce, So::Add) => "subgroupAdd",
+                    (Co::Reduce, So::Mul) => "subgroupMul",
+                    (Co::Reduce, So::Min) => "subgroupMin",
+                    (Co::Reduce, So::Max) => "subgroupMax",
+                    (Co::Reduce, So::And) => "subgroupAnd",
+                    (Co::Reduce, So::Or) => "subgroupOr",
+                    (Co::Reduce, So::Xor) => "subgroupXor",
+                    (Co::ExclusiveScan, So::Add) => "subgroupExclusiveAdd",
+                    (Co::ExclusiveScan, So::Mul) => "subgroupExclusiveMul",
+                    (Co::InclusiveScan, So::Add) => "subgroupInclusiveAdd",
+                    (Co::InclusiveScan, So::Mul) => "subgroupInclusiveMul",
+                    _ => {
+                        return Err(Error::Unimplemented(format!(
+                            "subgroup {:?} of {:?}",
+                            collective_op, op
+                        )))
+                    }
+                };
+                write!(self.out, "{}(", fun_str)?;
+                self.write_expr(module, argument, func_ctx)?;
+                writeln!(self.out, ");")?;
+            }
+            Statement::SubgroupGather {
+                mode,
+                argument,
+                result,
+            } => {
+                write!(self.out, "{}", INDENT.repeat(indent))?;
+                let name = format!("{}{}", back::BAKE_PREFIX, result.index());
+                self.start_named_expr(module, result, func_ctx, &name)?;
+                self.named_expressions.insert(result, name);
+
+                let (fun_str, index) = match mode {
+                    crate::GatherMode::BroadcastFirst => ("subgroupBroadcastFirst", None),
+                    crate::GatherMode::Broadcast(index) => ("subgroupBroadcast", Some(index)),
+                    crate::GatherMode::Shuffle(index) => ("subgroupShuffle", Some(index)),
+                    crate::GatherMode::ShuffleDown(index) => ("subgroupShuffleDown", Some(index)),
+                    crate::GatherMode::ShuffleUp(index) => ("subgroupShuffleUp", Some(index)),
+                    crate::GatherMode::ShuffleXor(index) => ("subgroupShuffleXor", Some(index)),
+                };
+                write!(self.out, "{}(", fun_str)?;
+                self.write_expr(module, argument, func_ctx)?;
+                if let Some(index) = index {
+                    write!(self.out, ", ")?;
+                    self.write_expr(module, index, func_ctx)?;
+                }
+                writeln!(self.out, ");")?;
+            }
             Statement::ImageStore {
                 image,
                 coordinate,
@@ -763,12 +895,14 @@ impl<W: Write> Writer<W> {
                         if case.fall_through {
                             writeln!(self.out, "{}fallthrough;", INDENT.repeat(indent + 2))?;
                         }
-                    }
 
-                    writeln!(self.out, "{}}}", INDENT.repeat(indent + 1))?;
+                        writeln!(self.out, "{}}}", INDENT.repeat(indent + 1))?;
+                    }
                 }
 
-                if !default.is_empty() {
+                // The last case can't fall through to a missing default
+                let falls_to_default = cases.last().map(|case| case.fall_through) == Some(true);
+                if !default.is_empty() || falls_to_default {
                     writeln!(self.out, "{}default: {{", INDENT.repeat(indent + 1))?;
 
                     for sta in default {
@@ -951,6 +1085,7 @@ impl<W: Write> Writer<W> {
                     }
                     TypeInner::Matrix { .. }
                     | TypeInner::Array { .. }
+                    | TypeInner::BindingArray { .. }
                     | TypeInner::ValuePointer { .. } => write!(self.out, "[{}]", index)?,
                     TypeInner::Struct { .. } => {
                         // This will never panic in case the type is a `Struct`, this is not true
@@ -966,6 +1101,52 @@ impl<W: Write> Writer<W> {
                     ref other => return Err(Error::Custom(format!("Cannot index {:?}", other))),
                 }
             }
+            Expression::ImageSample {
+                image,
+                sampler,
+                coordinate,
+                array_index,
+                offset,
+                level: _,
+                depth_ref,
+                gather: Some(component),
+            } => {
+                let suffix_cmp = match depth_ref {
+                    Some(_) => "Compare",
+                    None => "",
+                };
+                write!(self.out, "textureGather{}(", suffix_cmp)?;
+                // The component is implied for depth images
+                match *func_ctx.info[image].ty.inner_with(&module.types) {
+                    TypeInner::Image {
+                        class: crate::ImageClass::Depth,
+                        ..
+                    } => {}
+                    _ => write!(self.out, "{}, ", component as u8)?,
+                }
+                self.write_expr(module, image, func_ctx)?;
+                write!(self.out, ", ")?;
+                self.write_expr(module, sampler, func_ctx)?;
+                write!(self.out, ", ")?;
+                self.write_expr(module, coordinate, func_ctx)?;
+
+                if let Some(array_index) = array_index {
+                    write!(self.out, ", ")?;
+                    self.write_expr(module, array_index, func_ctx)?;
+                }
+
+                if let Some(depth_ref) = depth_ref {
+                    write!(self.out, ", ")?;
+                    self.write_expr(module, depth_ref, func_ctx)?;
+                }
+
+                if let Some(offset) = offset {
+                    write!(self.out, ", ")?;
+                    self.write_constant(module, offset)?;
+                }
+
+                write!(self.out, ")")?;
+            }
             Expression::ImageSample {
                 image,
                 sampler,
@@ -974,6 +1155,7 @@ impl<W: Write> Writer<W> {
                 offset,
                 level,
                 depth_ref,
+                gather: None,
             } => {
                 use crate::SampleLevel as Sl;
 
@@ -1127,7 +1309,15 @@ impl<W: Write> Writer<W> {
                 self.write_expr(module, value, func_ctx)?;
                 write!(self.out, ")")?;
             }
-            Expression::Load { pointer } => self.write_expr(module, pointer, func_ctx)?,
+            Expression::Load { pointer } => {
+                if is_atomic_pointer(pointer, module, func_ctx.info) {
+                    write!(self.out, "atomicLoad(&")?;
+                    self.write_expr(module, pointer, func_ctx)?;
+                    write!(self.out, ")")?;
+                } else {
+                    self.write_expr(module, pointer, func_ctx)?;
+                }
+            }
             Expression::LocalVariable(handle) => {
                 write!(self.out, "{}", self.names[&func_ctx.name_key(handle)])?
             }
@@ -1144,6 +1334,7 @@ impl<W: Write> Writer<W> {
                 arg,
                 arg1,
                 arg2,
+                arg3,
             } => {
                 use crate::MathFunction as Mf;
 
@@ -1187,6 +1378,7 @@ impl<W: Write> Writer<W> {
                     Mf::Normalize => "normalize",
                     Mf::FaceForward => "faceForward",
                     Mf::Reflect => "reflect",
+                    Mf::Refract => "refract",
                     // computational
                     Mf::Sign => "sign",
                     Mf::Fma => "fma",
@@ -1197,24 +1389,50 @@ impl<W: Write> Writer<W> {
                     Mf::InverseSqrt => "inverseSqrt",
                     Mf::Transpose => "transpose",
                     Mf::Determinant => "determinant",
+                    Mf::Inverse => "inverse",
                     // bits
                     Mf::CountOneBits => "countOneBits",
                     Mf::ReverseBits => "reverseBits",
-                    _ => {
-                        return Err(Error::UnsupportedMathFunction(fun));
-                    }
+                    Mf::ExtractBits => "extractBits",
+                    Mf::InsertBits => "insertBits",
+                    Mf::FirstTrailingBit => "firstTrailingBit",
+                    Mf::FirstLeadingBit => "firstLeadingBit",
+                    // data packing
+                    Mf::Pack4x8snorm => "pack4x8snorm",
+                    Mf::Pack4x8unorm => "pack4x8unorm",
+                    Mf::Pack2x16snorm => "pack2x16snorm",
+                    Mf::Pack2x16unorm => "pack2x16unorm",
+                    Mf::Pack2x16float => "pack2x16float",
+                    // data unpacking
+                    Mf::Unpack4x8snorm => "unpack4x8snorm",
+                    Mf::Unpack4x8unorm => "unpack4x8unorm",
+                    Mf::Unpack2x16snorm => "unpack2x16snorm",
+                    Mf::Unpack2x16unorm => "unpack2x16unorm",
+                    Mf::Unpack2x16float => "unpack2x16float",
                 };
 
                 write!(self.out, "{}(", fun_name)?;
                 self.write_expr(module, arg, func_ctx)?;
                 if let Some(arg) = arg1 {
                     write!(self.out, ", ")?;
+                    // `modf` and `frexp` store into a pointer, which has to be
+                    // taken unless it's already a pointer argument
+                    if let Mf::Modf | Mf::Frexp = fun {
+                        match func_ctx.expressions[arg] {
+                            Expression::FunctionArgument(_) => {}
+                            _ => write!(self.out, "&")?,
+                        }
+                    }
                     self.write_expr(module, arg, func_ctx)?;
                 }
                 if let Some(arg) = arg2 {
                     write!(self.out, ", ")?;
                     self.write_expr(module, arg, func_ctx)?;
                 }
+                if let Some(arg) = arg3 {
+                    write!(self.out, ", ")?;
+                    self.write_expr(module, arg, func_ctx)?;
+                }
                 write!(self.out, ")")?
             }
             Expression::Swizzle {
@@ -1291,7 +1509,10 @@ impl<W: Write> Writer<W> {
                 write!(self.out, ")")?
             }
             // Nothing to do here, since call expression already cached
-            Expression::Call(_) => {}
+            Expression::Call(_)
+            | Expression::AtomicResult { .. }
+            | Expression::SubgroupBallotResult
+            | Expression::SubgroupOperationResult { .. } => {}
         }
 
         Ok(())
@@ -1359,7 +1580,7 @@ impl<W: Write> Writer<W> {
                 width: _,
                 ref value,
             } => {
-                if constant.name.is_some() {
+                if constant.name.is_some() || constant.specialization.is_some() {
                     write!(self.out, "{}", self.names[&NameKey::Constant(handle)])?;
                 } else {
                     self.write_scalar_value(*value)?;
@@ -1418,6 +1639,9 @@ impl<W: Write> Writer<W> {
                 ref value,
             } => {
                 let name = self.names[&NameKey::Constant(handle)].clone();
+                if let Some(id) = module.constants[handle].specialization {
+                    write!(self.out, "[[override({})]] ", id)?;
+                }
                 // First write only constant name
                 write!(self.out, "let {}: ", name)?;
                 // Next write constant type and value
@@ -1489,6 +1713,8 @@ fn builtin_str(built_in: crate::BuiltIn) -> Option<&'static str> {
         Bi::SampleIndex => Some("sample_index"),
         Bi::SampleMask => Some("sample_mask"),
         Bi::PrimitiveIndex => Some("primitive_index"),
+        Bi::SubgroupSize => Some("subgroup_size"),
+        Bi::SubgroupInvocationId => Some("subgroup_invocation_id"),
         _ => None,
     }
 }
@@ -1626,6 +1852,20 @@ fn is_deref_required(
     }
 }
 
+fn is_atomic_pointer(
+    expr: Handle<crate::Expression>,
+    module: &Module,
+    info: &valid::FunctionInfo,
+) -> bool {
+    match *info[expr].ty.inner_with(&module.types) {
+        TypeInner::Pointer { base, class: _ } => match module.types[base].inner {
+            TypeInner::Atomic { .. } => true,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
 /// Helper function that check that expression don't access to structure member with unsupported builtin.
 fn access_to_unsupported_builtin(
     expr: Handle<crate::Expression>,
@@ -0,0 +1,189 @@
+use crate::{Span, Statement};
+use std::ops::{Deref, DerefMut, RangeBounds};
+
+/// A code block is a vector of statements, with maybe a vector of spans.
+///
+/// The spans are only stored if the `span` feature is enabled.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+#[cfg_attr(feature = "serialize", serde(transparent))]
+pub struct Block {
+    body: Vec<Statement>,
+    #[cfg(feature = "span")]
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    span_info: Vec<Span>,
+}
+
+impl Block {
+    /// Creates an empty block.
+    pub fn new() -> Self {
+        Self {
+            body: Vec::new(),
+            #[cfg(feature = "span")]
+            span_info: Vec::new(),
+        }
+    }
+
+    /// Creates a block from statements, with undefined spans.
+    pub fn from_vec(body: Vec<Statement>) -> Self {
+        #[cfg(feature = "span")]
+        let span_info = vec![Span::default(); body.len()];
+        Self {
+            body,
+            #[cfg(feature = "span")]
+            span_info,
+        }
+    }
+
+    /// Creates an empty block with space for `capacity` statements.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            body: Vec::with_capacity(capacity),
+            #[cfg(feature = "span")]
+            span_info: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Appends a statement with its source span.
+    #[allow(unused_variables)]
+    pub fn push(&mut self, end: Statement, span: Span) {
+        self.body.push(end);
+        #[cfg(feature = "span")]
+        self.span_info.push(span);
+    }
+
+    /// Appends a statement with its span, if there is one.
+    ///
+    /// This is handy for the output of `Emitter::finish`.
+    pub fn extend(&mut self, item: Option<(Statement, Span)>) {
+        if let Some((end, span)) = item {
+            self.push(end, span)
+        }
+    }
+
+    /// Appends all the statements of `other`, keeping their spans.
+    pub fn extend_block(&mut self, other: Self) {
+        #[cfg(feature = "span")]
+        self.span_info.extend(other.span_info);
+        self.body.extend(other.body);
+    }
+
+    /// Moves all the statements of `other` into `self`, leaving `other` empty.
+    pub fn append(&mut self, other: &mut Self) {
+        #[cfg(feature = "span")]
+        self.span_info.append(&mut other.span_info);
+        self.body.append(&mut other.body);
+    }
+
+    /// Removes the statements in `range`.
+    pub fn cull<R: RangeBounds<usize> + Clone>(&mut self, range: R) {
+        #[cfg(feature = "span")]
+        self.span_info.drain(range.clone());
+        self.body.drain(range);
+    }
+
+    /// Replaces the statements in `range` with the contents of `other`.
+    pub fn splice<R: RangeBounds<usize> + Clone>(&mut self, range: R, other: Self) {
+        #[cfg(feature = "span")]
+        self.span_info.splice(range.clone(), other.span_info);
+        self.body.splice(range, other.body);
+    }
+
+    /// Iterates over the statements together with their spans.
+    ///
+    /// Spans are undefined if the `span` feature is disabled.
+    pub fn span_iter(&self) -> impl Iterator<Item = (&Statement, &Span)> {
+        #[cfg(feature = "span")]
+        let span_iter = self.span_info.iter();
+        #[cfg(not(feature = "span"))]
+        let span_iter = std::iter::repeat_with(|| &Span::UNDEFINED);
+
+        self.body.iter().zip(span_iter)
+    }
+
+    /// Iterates mutably over the statements together with their spans.
+    ///
+    /// Spans are `None` if the `span` feature is disabled.
+    pub fn span_iter_mut(&mut self) -> impl Iterator<Item = (&mut Statement, Option<&mut Span>)> {
+        #[cfg(feature = "span")]
+        let span_iter = self.span_info.iter_mut().map(Some);
+        #[cfg(not(feature = "span"))]
+        let span_iter = std::iter::repeat_with(|| None);
+
+        self.body.iter_mut().zip(span_iter)
+    }
+
+    /// Returns `true` if there are no statements.
+    pub fn is_empty(&self) -> bool {
+        self.body.is_empty()
+    }
+
+    /// Returns the number of statements.
+    pub fn len(&self) -> usize {
+        self.body.len()
+    }
+}
+
+impl Deref for Block {
+    type Target = [Statement];
+    fn deref(&self) -> &[Statement] {
+        &self.body
+    }
+}
+
+impl DerefMut for Block {
+    fn deref_mut(&mut self) -> &mut [Statement] {
+        &mut self.body
+    }
+}
+
+impl<'a> IntoIterator for &'a Block {
+    type Item = &'a Statement;
+    type IntoIter = std::slice::Iter<'a, Statement>;
+
+    fn into_iter(self) -> std::slice::Iter<'a, Statement> {
+        self.iter()
+    }
+}
+
+impl IntoIterator for Block {
+    type Item = Statement;
+    type IntoIter = std::vec::IntoIter<Statement>;
+
+    fn into_iter(self) -> std::vec::IntoIter<Statement> {
+        self.body.into_iter()
+    }
+}
+
+impl From<Vec<Statement>> for Block {
+    fn from(body: Vec<Statement>) -> Self {
+        Self::from_vec(body)
+    }
+}
+
+// Spans aren't serialized, so every statement gets an undefined one.
+#[cfg(feature = "deserialize")]
+impl<'de> serde::Deserialize<'de> for Block {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Self::from_vec(Vec::deserialize(deserializer)?))
+    }
+}
+
+#[cfg(all(test, feature = "serialize", feature = "deserialize"))]
+#[test]
+fn serde_round_trip() {
+    let mut block = Block::new();
+    block.push(Statement::Break, Span::default());
+    block.push(Statement::Kill, Span::default());
+
+    let text = ron::ser::to_string(&block).unwrap();
+    let block: Block = ron::de::from_str(&text).unwrap();
+    let statements = block
+        .span_iter()
+        .map(|(statement, _)| statement)
+        .collect::<Vec<_>>();
+    match statements[..] {
+        [&Statement::Break, &Statement::Kill] => {}
+        ref other => panic!("unexpected statements {:?}", other),
+    }
+}
@@ -1,12 +1,12 @@
 use super::{
     super::{Emitter, Typifier},
-    constants::ConstantSolver,
     error::ErrorKind,
     SourceMetadata,
 };
 use crate::{
-    proc::ResolveContext, Arena, BinaryOperator, Binding, Block, Constant, Expression, FastHashMap,
-    Function, FunctionArgument, GlobalVariable, Handle, Interpolation, LocalVariable, Module,
+    proc::{ConstantSolver, ResolveContext},
+    Arena, BinaryOperator, Binding, Block, Constant, Expression, FastHashMap, Function,
+    FunctionArgument, GlobalVariable, Handle, Interpolation, LocalVariable, Module,
     RelationalFunction, ResourceBinding, Sampling, ScalarKind, ScalarValue, ShaderStage, Statement,
     StorageClass, Type, TypeInner, UnaryOperator, VectorSize,
 };
@@ -161,7 +161,7 @@ impl<'a> Program<'a> {
         meta: SourceMetadata,
     ) -> Result<Handle<Constant>, ErrorKind> {
         let mut solver = ConstantSolver {
-            types: &self.module.types,
+            types: &mut self.module.types,
             expressions: ctx.expressions,
             constants: &mut self.module.constants,
         };
@@ -230,7 +230,8 @@ impl<'function> Context<'function> {
             let (expr, load) = match kind {
                 GlobalLookupKind::Variable(v) => {
                     let res = (
-                        this.expressions.append(Expression::GlobalVariable(v)),
+                        this.expressions
+                            .append(Expression::GlobalVariable(v), Default::default()),
                         program.module.global_variables[v].class != StorageClass::Handle,
                     );
                     this.emit_start();
@@ -238,11 +239,13 @@ impl<'function> Context<'function> {
                     res
                 }
                 GlobalLookupKind::BlockSelect(handle, index) => {
-                    let base = this.expressions.append(Expression::GlobalVariable(handle));
+                    let base = this
+                        .expressions
+                        .append(Expression::GlobalVariable(handle), Default::default());
                     this.emit_start();
                     let expr = this
                         .expressions
-                        .append(Expression::AccessIndex { base, index });
+                        .append(Expression::AccessIndex { base, index }, Default::default());
 
                     (expr, {
                         let ty = program.module.global_variables[handle].ty;
@@ -264,7 +267,11 @@ impl<'function> Context<'function> {
                     })
                 }
                 GlobalLookupKind::Constant(v) => {
-                    let res = (this.expressions.append(Expression::Constant(v)), false);
+                    let res = (
+                        this.expressions
+                            .append(Expression::Constant(v), Default::default()),
+                        false,
+                    );
                     this.emit_start();
                     res
                 }
@@ -291,14 +298,26 @@ impl<'function> Context<'function> {
         body.extend(self.emitter.finish(self.expressions))
     }
 
-    pub fn add_expression(&mut self, expr: Expression, body: &mut Block) -> Handle<Expression> {
+    /// Restarts emitting without emitting the pending expressions,
+    /// used when they were folded into a constant.
+    pub fn emit_discard(&mut self) {
+        let _ = self.emitter.finish(self.expressions);
+        self.emitter.start(self.expressions)
+    }
+
+    pub fn add_expression(
+        &mut self,
+        expr: Expression,
+        meta: SourceMetadata,
+        body: &mut Block,
+    ) -> Handle<Expression> {
         if expr.needs_pre_emit() {
             self.emit_flush(body);
-            let handle = self.expressions.append(expr);
+            let handle = self.expressions.append(expr, meta.into());
             self.emit_start();
             handle
         } else {
-            self.expressions.append(expr)
+            self.expressions.append(expr, meta.into())
         }
     }
 
@@ -340,6 +359,7 @@ impl<'function> Context<'function> {
     }
 
     /// Add function argument to current scope
+    #[allow(clippy::too_many_arguments)]
     pub fn add_function_arg(
         &mut self,
         program: &mut Program,
@@ -348,6 +368,7 @@ impl<'function> Context<'function> {
         name: Option<String>,
         ty: Handle<Type>,
         qualifier: ParameterQualifier,
+        meta: SourceMetadata,
     ) {
         let index = self.arguments.len();
         let mut arg = FunctionArgument {
@@ -363,37 +384,46 @@ impl<'function> Context<'function> {
         };
 
         if qualifier.is_lhs() {
-            arg.ty = program.module.types.fetch_or_append(Type {
-                name: None,
-                inner: TypeInner::Pointer {
-                    base: arg.ty,
-                    class: StorageClass::Function,
+            arg.ty = program.module.types.fetch_or_append(
+                Type {
+                    name: None,
+                    inner: TypeInner::Pointer {
+                        base: arg.ty,
+                        class: StorageClass::Function,
+                    },
                 },
-            })
+                Default::default(),
+            )
         }
 
         self.arguments.push(arg);
 
         if let Some(name) = name {
-            let expr = self.add_expression(Expression::FunctionArgument(index as u32), body);
+            let expr = self.add_expression(Expression::FunctionArgument(index as u32), meta, body);
             let mutable = qualifier != ParameterQualifier::Const && !opaque;
             let load = qualifier.is_lhs();
 
             if mutable && !load {
-                let handle = self.locals.append(LocalVariable {
-                    name: Some(name.clone()),
-                    ty,
-                    init: None,
-                });
-                let local_expr = self.add_expression(Expression::LocalVariable(handle), body);
+                let handle = self.locals.append(
+                    LocalVariable {
+                        name: Some(name.clone()),
+                        ty,
+                        init: None,
+                    },
+                    Default::default(),
+                );
+                let local_expr = self.add_expression(Expression::LocalVariable(handle), meta, body);
 
                 self.emit_flush(body);
                 self.emit_start();
 
-                body.push(Statement::Store {
-                    pointer: local_expr,
-                    value: expr,
-                });
+                body.push(
+                    Statement::Store {
+                        pointer: local_expr,
+                        value: expr,
+                    },
+                    meta.into(),
+                );
 
                 if let Some(current) = self.scopes.last_mut() {
                     (*current).insert(
@@ -484,18 +514,19 @@ impl<'function> Context<'function> {
                                     _ => return None,
                                 },
                             },
+                            meta,
                             body,
                         ))
                     })
                     .unwrap_or_else(|| {
-                        self.add_expression(Expression::Access { base, index }, body)
+                        self.add_expression(Expression::Access { base, index }, meta, body)
                     });
 
                 if !lhs {
                     match *program.resolve_type(self, pointer, meta)? {
                         TypeInner::Pointer { .. } | TypeInner::ValuePointer { .. } => {
                             return Ok((
-                                Some(self.add_expression(Expression::Load { pointer }, body)),
+                                Some(self.add_expression(Expression::Load { pointer }, meta, body)),
                                 meta,
                             ));
                         }
@@ -511,7 +542,7 @@ impl<'function> Context<'function> {
                 program.field_selection(self, lhs, body, base, &field, meta)?
             }
             HirExprKind::Constant(constant) if !lhs => {
-                self.add_expression(Expression::Constant(constant), body)
+                self.add_expression(Expression::Constant(constant), meta, body)
             }
             HirExprKind::Binary { left, op, right } if !lhs => {
                 let (mut left, left_meta) = self.lower_expect(program, left, false, body)?;
@@ -548,13 +579,19 @@ impl<'function> Context<'function> {
                                 false => (BinaryOperator::NotEqual, RelationalFunction::Any),
                             };
 
-                            let argument =
-                                self.expressions
-                                    .append(Expression::Binary { op, left, right });
+                            let argument = self
+                                .expressions
+                                .append(Expression::Binary { op, left, right }, Default::default());
 
-                            self.add_expression(Expression::Relational { fun, argument }, body)
+                            self.add_expression(
+                                Expression::Relational { fun, argument },
+                                meta,
+                                body,
+                            )
+                        }
+                        _ => {
+                            self.add_expression(Expression::Binary { left, op, right }, meta, body)
                         }
-                        _ => self.add_expression(Expression::Binary { left, op, right }, body),
                     },
                     (&TypeInner::Vector { size, .. }, &TypeInner::Scalar { .. }) => match op {
                         BinaryOperator::Add
@@ -562,8 +599,11 @@ impl<'function> Context<'function> {
                         | BinaryOperator::Divide
                         | BinaryOperator::ShiftLeft
                         | BinaryOperator::ShiftRight => {
-                            let scalar_vector =
-                                self.add_expression(Expression::Splat { size, value: right }, body);
+                            let scalar_vector = self.add_expression(
+                                Expression::Splat { size, value: right },
+                                meta,
+                                body,
+                            );
 
                             self.add_expression(
                                 Expression::Binary {
@@ -571,15 +611,21 @@ impl<'function> Context<'function> {
                                     left,
                                     right: scalar_vector,
                                 },
+                                meta,
                                 body,
                             )
                         }
-                        _ => self.add_expression(Expression::Binary { left, op, right }, body),
+                        _ => {
+                            self.add_expression(Expression::Binary { left, op, right }, meta, body)
+                        }
                     },
                     (&TypeInner::Scalar { .. }, &TypeInner::Vector { size, .. }) => match op {
                         BinaryOperator::Add | BinaryOperator::Subtract | BinaryOperator::Divide => {
-                            let scalar_vector =
-                                self.add_expression(Expression::Splat { size, value: left }, body);
+                            let scalar_vector = self.add_expression(
+                                Expression::Splat { size, value: left },
+                                meta,
+                                body,
+                            );
 
                             self.add_expression(
                                 Expression::Binary {
@@ -587,18 +633,21 @@ impl<'function> Context<'function> {
                                     left: scalar_vector,
                                     right,
                                 },
+                                meta,
                                 body,
                             )
                         }
-                        _ => self.add_expression(Expression::Binary { left, op, right }, body),
+                        _ => {
+                            self.add_expression(Expression::Binary { left, op, right }, meta, body)
+                        }
                     },
-                    _ => self.add_expression(Expression::Binary { left, op, right }, body),
+                    _ => self.add_expression(Expression::Binary { left, op, right }, meta, body),
                 }
             }
             HirExprKind::Unary { op, expr } if !lhs => {
                 let expr = self.lower_expect(program, expr, false, body)?.0;
 
-                self.add_expression(Expression::Unary { op, expr }, body)
+                self.add_expression(Expression::Unary { op, expr }, meta, body)
             }
             HirExprKind::Variable(var) => {
                 if lhs {
@@ -620,7 +669,7 @@ impl<'function> Context<'function> {
                     }
 
                     if var.load {
-                        self.add_expression(Expression::Load { pointer: var.expr }, body)
+                        self.add_expression(Expression::Load { pointer: var.expr }, meta, body)
                     } else {
                         var.expr
                     }
@@ -653,6 +702,7 @@ impl<'function> Context<'function> {
                         accept,
                         reject,
                     },
+                    meta,
                     body,
                 )
             }
@@ -691,6 +741,7 @@ impl<'function> Context<'function> {
                                 base: vector,
                                 index: pattern[index].index(),
                             },
+                            meta,
                             body,
                         );
                         let src = self.add_expression(
@@ -698,22 +749,26 @@ impl<'function> Context<'function> {
                                 base: value,
                                 index: index as u32,
                             },
+                            meta,
                             body,
                         );
 
                         self.emit_flush(body);
                         self.emit_start();
 
-                        body.push(Statement::Store {
-                            pointer: dst,
-                            value: src,
-                        });
+                        body.push(
+                            Statement::Store {
+                                pointer: dst,
+                                value: src,
+                            },
+                            meta.into(),
+                        );
                     }
                 } else {
                     self.emit_flush(body);
                     self.emit_start();
 
-                    body.push(Statement::Store { pointer, value });
+                    body.push(Statement::Store { pointer, value }, meta.into());
                 }
 
                 value
@@ -729,7 +784,7 @@ impl<'function> Context<'function> {
                 };
 
                 let pointer = self.lower_expect(program, expr, true, body)?.0;
-                let left = self.add_expression(Expression::Load { pointer }, body);
+                let left = self.add_expression(Expression::Load { pointer }, meta, body);
 
                 let uint = if let Some(kind) = program.resolve_type(self, left, meta)?.scalar_kind()
                 {
@@ -750,59 +805,71 @@ impl<'function> Context<'function> {
                     ));
                 };
 
-                let one = program.module.constants.append(Constant {
-                    name: None,
-                    specialization: None,
-                    inner: crate::ConstantInner::Scalar {
-                        width: 4,
-                        value: match uint {
-                            true => crate::ScalarValue::Uint(1),
-                            false => crate::ScalarValue::Sint(1),
+                let one = program.module.constants.append(
+                    Constant {
+                        name: None,
+                        specialization: None,
+                        inner: crate::ConstantInner::Scalar {
+                            width: 4,
+                            value: match uint {
+                                true => crate::ScalarValue::Uint(1),
+                                false => crate::ScalarValue::Sint(1),
+                            },
                         },
                     },
-                });
-                let right = self.add_expression(Expression::Constant(one), body);
+                    Default::default(),
+                );
+                let right = self.add_expression(Expression::Constant(one), meta, body);
 
-                let value = self.add_expression(Expression::Binary { op, left, right }, body);
+                let value = self.add_expression(Expression::Binary { op, left, right }, meta, body);
 
                 if postfix {
-                    let local = self.locals.append(LocalVariable {
-                        name: None,
-                        ty: program.module.types.fetch_or_append(Type {
+                    let local = self.locals.append(
+                        LocalVariable {
                             name: None,
-                            inner: TypeInner::Scalar {
-                                kind: match uint {
-                                    true => ScalarKind::Uint,
-                                    false => ScalarKind::Sint,
+                            ty: program.module.types.fetch_or_append(
+                                Type {
+                                    name: None,
+                                    inner: TypeInner::Scalar {
+                                        kind: match uint {
+                                            true => ScalarKind::Uint,
+                                            false => ScalarKind::Sint,
+                                        },
+                                        width: 4,
+                                    },
                                 },
-                                width: 4,
-                            },
-                        }),
-                        init: None,
-                    });
+                                Default::default(),
+                            ),
+                            init: None,
+                        },
+                        Default::default(),
+                    );
 
-                    let expr = self.add_expression(Expression::LocalVariable(local), body);
-                    let load = self.add_expression(Expression::Load { pointer: expr }, body);
+                    let expr = self.add_expression(Expression::LocalVariable(local), meta, body);
+                    let load = self.add_expression(Expression::Load { pointer: expr }, meta, body);
 
                     self.emit_flush(body);
                     self.emit_start();
 
-                    body.push(Statement::Store {
-                        pointer: expr,
-                        value: left,
-                    });
+                    body.push(
+                        Statement::Store {
+                            pointer: expr,
+                            value: left,
+                        },
+                        meta.into(),
+                    );
 
                     self.emit_flush(body);
                     self.emit_start();
 
-                    body.push(Statement::Store { pointer, value });
+                    body.push(Statement::Store { pointer, value }, meta.into());
 
                     load
                 } else {
                     self.emit_flush(body);
                     self.emit_start();
 
-                    body.push(Statement::Store { pointer, value });
+                    body.push(Statement::Store { pointer, value }, meta.into());
 
                     left
                 }
@@ -855,11 +922,14 @@ impl<'function> Context<'function> {
             (type_power(kind), self.expr_power(program, *expr, meta)?)
         {
             if tgt_power > expr_power {
-                *expr = self.expressions.append(Expression::As {
-                    expr: *expr,
-                    kind,
-                    convert: Some(width),
-                })
+                *expr = self.expressions.append(
+                    Expression::As {
+                        expr: *expr,
+                        kind,
+                        convert: Some(width),
+                    },
+                    Default::default(),
+                )
             }
         }
 
@@ -886,19 +956,25 @@ impl<'function> Context<'function> {
         ) {
             match left_power.cmp(&right_power) {
                 std::cmp::Ordering::Less => {
-                    *left = self.expressions.append(Expression::As {
-                        expr: *left,
-                        kind: right_kind,
-                        convert: Some(right_width),
-                    })
+                    *left = self.expressions.append(
+                        Expression::As {
+                            expr: *left,
+                            kind: right_kind,
+                            convert: Some(right_width),
+                        },
+                        Default::default(),
+                    )
                 }
                 std::cmp::Ordering::Equal => {}
                 std::cmp::Ordering::Greater => {
-                    *right = self.expressions.append(Expression::As {
-                        expr: *right,
-                        kind: left_kind,
-                        convert: Some(left_width),
-                    })
+                    *right = self.expressions.append(
+                        Expression::As {
+                            expr: *right,
+                            kind: left_kind,
+                            convert: Some(left_width),
+                        },
+                        Default::default(),
+                    )
                 }
             }
         }
@@ -918,7 +994,7 @@ impl<'function> Context<'function> {
         if let (&TypeInner::Scalar { .. }, Some(size)) = (expr_type, vector_size) {
             *expr = self
                 .expressions
-                .append(Expression::Splat { size, value: *expr })
+                .append(Expression::Splat { size, value: *expr }, Default::default())
         }
 
         Ok(())
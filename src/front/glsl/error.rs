@@ -1,7 +1,5 @@
-use super::{
-    constants::ConstantSolvingError,
-    token::{SourceMetadata, Token, TokenValue},
-};
+use super::token::{SourceMetadata, Token, TokenValue};
+use crate::proc::ConstantSolvingError;
 use std::borrow::Cow;
 use thiserror::Error;
 
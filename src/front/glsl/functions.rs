@@ -1,8 +1,10 @@
 use crate::{
-    proc::ensure_block_returns, Arena, BinaryOperator, Block, Constant, ConstantInner, EntryPoint,
-    Expression, Function, FunctionArgument, FunctionResult, Handle, ImageQuery, LocalVariable,
-    MathFunction, RelationalFunction, SampleLevel, ScalarKind, ScalarValue, ShaderStage, Statement,
-    StructMember, SwizzleComponent, Type, TypeInner, VectorSize,
+    proc::{ensure_block_returns, TypeResolution},
+    Arena, BinaryOperator, Block, CollectiveOperation, Constant, ConstantInner, EntryPoint,
+    Expression, Function, FunctionArgument, FunctionResult, GatherMode, Handle, ImageQuery,
+    LocalVariable, MathFunction, RelationalFunction, SampleLevel, ScalarKind, ScalarValue,
+    ShaderStage, Statement, StructMember, SubgroupOperation, SwizzleComponent, Type, TypeInner,
+    VectorSize,
 };
 
 use super::{ast::*, error::ErrorKind, SourceMetadata};
@@ -16,11 +18,14 @@ impl Program<'_> {
             _ => unreachable!(),
         };
 
-        self.module.constants.fetch_or_append(Constant {
-            name: None,
-            specialization: None,
-            inner: ConstantInner::Scalar { width: 4, value },
-        })
+        self.module.constants.fetch_or_append(
+            Constant {
+                name: None,
+                specialization: None,
+                inner: ConstantInner::Scalar { width: 4, value },
+            },
+            Default::default(),
+        )
     }
 
     pub fn function_call(
@@ -54,8 +59,10 @@ impl Program<'_> {
                         {
                             let c0 = self.add_constant_value(result_scalar_kind, 0u64);
                             let c1 = self.add_constant_value(result_scalar_kind, 1u64);
-                            let mut reject = ctx.add_expression(Expression::Constant(c0), body);
-                            let mut accept = ctx.add_expression(Expression::Constant(c1), body);
+                            let mut reject =
+                                ctx.add_expression(Expression::Constant(c0), meta, body);
+                            let mut accept =
+                                ctx.add_expression(Expression::Constant(c1), meta, body);
 
                             ctx.implicit_splat(self, &mut reject, meta, vector_size)?;
                             ctx.implicit_splat(self, &mut accept, meta, vector_size)?;
@@ -66,6 +73,7 @@ impl Program<'_> {
                                     reject,
                                     condition: args[0].0,
                                 },
+                                meta,
                                 body,
                             );
 
@@ -79,7 +87,7 @@ impl Program<'_> {
                             let (mut value, meta) = args[0];
                             ctx.implicit_conversion(self, &mut value, meta, kind, width)?;
 
-                            ctx.add_expression(Expression::Splat { size, value }, body)
+                            ctx.add_expression(Expression::Splat { size, value }, meta, body)
                         }
                         TypeInner::Scalar { kind, width } => ctx.add_expression(
                             Expression::As {
@@ -87,6 +95,7 @@ impl Program<'_> {
                                 expr: args[0].0,
                                 convert: Some(width),
                             },
+                            meta,
                             body,
                         ),
                         TypeInner::Vector { size, kind, width } => {
@@ -96,6 +105,7 @@ impl Program<'_> {
                                     vector: args[0].0,
                                     pattern: SwizzleComponent::XYZW,
                                 },
+                                meta,
                                 body,
                             );
 
@@ -105,6 +115,7 @@ impl Program<'_> {
                                     expr,
                                     convert: Some(width),
                                 },
+                                meta,
                                 body,
                             )
                         }
@@ -126,8 +137,11 @@ impl Program<'_> {
                                 width,
                             )?;
                             let column = match *self.resolve_type(ctx, args[0].0, args[0].1)? {
-                                TypeInner::Scalar { .. } => ctx
-                                    .add_expression(Expression::Splat { size: rows, value }, body),
+                                TypeInner::Scalar { .. } => ctx.add_expression(
+                                    Expression::Splat { size: rows, value },
+                                    meta,
+                                    body,
+                                ),
                                 TypeInner::Matrix { .. } => {
                                     let mut components = Vec::new();
 
@@ -137,6 +151,7 @@ impl Program<'_> {
                                                 base: value,
                                                 index: n,
                                             },
+                                            meta,
                                             body,
                                         );
 
@@ -146,6 +161,7 @@ impl Program<'_> {
                                                 vector,
                                                 pattern: SwizzleComponent::XYZW,
                                             },
+                                            meta,
                                             body,
                                         );
 
@@ -154,6 +170,7 @@ impl Program<'_> {
 
                                     let h = ctx.add_expression(
                                         Expression::Compose { ty, components },
+                                        meta,
                                         body,
                                     );
 
@@ -170,6 +187,7 @@ impl Program<'_> {
                                     ty,
                                     components: columns,
                                 },
+                                meta,
                                 body,
                             )
                         }
@@ -178,6 +196,7 @@ impl Program<'_> {
                                 ty,
                                 components: args.into_iter().map(|arg| arg.0).collect(),
                             },
+                            meta,
                             body,
                         ),
                         _ => return Err(ErrorKind::SemanticError(meta, "Bad cast".into())),
@@ -193,7 +212,7 @@ impl Program<'_> {
                         components.push(arg)
                     }
 
-                    ctx.add_expression(Expression::Compose { ty, components }, body)
+                    ctx.add_expression(Expression::Compose { ty, components }, meta, body)
                 };
 
                 Ok(Some(h))
@@ -237,7 +256,9 @@ impl Program<'_> {
                                         SampleLevel::Bias(expr)
                                     }),
                                     depth_ref: None,
+                                    gather: None,
                                 },
+                                meta,
                                 body,
                             )))
                         } else {
@@ -254,6 +275,7 @@ impl Program<'_> {
                                 expr: args[2].0,
                                 convert: Some(4),
                             },
+                            meta,
                             body,
                         );
                         if let Some(sampler) = ctx.samplers.get(&args[0].0).copied() {
@@ -266,7 +288,9 @@ impl Program<'_> {
                                     offset: None,      //TODO
                                     level: SampleLevel::Exact(exact),
                                     depth_ref: None,
+                                    gather: None,
                                 },
+                                meta,
                                 body,
                             )))
                         } else {
@@ -287,6 +311,7 @@ impl Program<'_> {
                                     expr,
                                     convert: Some(4),
                                 },
+                                meta,
                                 body,
                             );
                             SampleLevel::Bias(exact)
@@ -336,7 +361,9 @@ impl Program<'_> {
                                     offset: None,      //TODO
                                     level,
                                     depth_ref: None,
+                                    gather: None,
                                 },
+                                meta,
                                 body,
                             )))
                         } else {
@@ -346,6 +373,55 @@ impl Program<'_> {
                             ))
                         }
                     }
+                    "textureGather" => {
+                        if !(2..=3).contains(&args.len()) {
+                            return Err(ErrorKind::wrong_function_args(name, 2, args.len(), meta));
+                        }
+                        let component = match args.get(2) {
+                            Some(&(expr, meta)) => {
+                                let constant = self.solve_constant(ctx, expr, meta)?;
+                                let index = match self.module.constants[constant].inner {
+                                    ConstantInner::Scalar {
+                                        value: ScalarValue::Sint(value),
+                                        ..
+                                    } => value as u64,
+                                    ConstantInner::Scalar {
+                                        value: ScalarValue::Uint(value),
+                                        ..
+                                    } => value,
+                                    _ => u64::MAX,
+                                };
+                                *SwizzleComponent::XYZW.get(index as usize).ok_or_else(|| {
+                                    ErrorKind::SemanticError(
+                                        meta,
+                                        "Gather component must be 0, 1, 2 or 3".into(),
+                                    )
+                                })?
+                            }
+                            None => SwizzleComponent::X,
+                        };
+                        if let Some(sampler) = ctx.samplers.get(&args[0].0).copied() {
+                            Ok(Some(ctx.add_expression(
+                                Expression::ImageSample {
+                                    image: args[0].0,
+                                    sampler,
+                                    coordinate: args[1].0,
+                                    array_index: None, //TODO
+                                    offset: None,      //TODO
+                                    level: SampleLevel::Zero,
+                                    depth_ref: None,
+                                    gather: Some(component),
+                                },
+                                meta,
+                                body,
+                            )))
+                        } else {
+                            Err(ErrorKind::SemanticError(
+                                meta,
+                                "Bad call to textureGather".into(),
+                            ))
+                        }
+                    }
                     "textureSize" => {
                         if !(1..=2).contains(&args.len()) {
                             return Err(ErrorKind::wrong_function_args(name, 1, args.len(), meta));
@@ -358,6 +434,7 @@ impl Program<'_> {
                                     level: args.get(1).map(|e| e.0),
                                 },
                             },
+                            meta,
                             body,
                         )))
                     }
@@ -380,6 +457,7 @@ impl Program<'_> {
                                                 base: args[1].0,
                                                 index: 0,
                                             },
+                                            meta,
                                             body,
                                         ),
                                         crate::ImageDimension::D2 => ctx.add_expression(
@@ -388,6 +466,7 @@ impl Program<'_> {
                                                 vector: args[1].0,
                                                 pattern: SwizzleComponent::XYZW,
                                             },
+                                            meta,
                                             body,
                                         ),
                                         _ => ctx.add_expression(
@@ -396,6 +475,7 @@ impl Program<'_> {
                                                 vector: args[1].0,
                                                 pattern: SwizzleComponent::XYZW,
                                             },
+                                            meta,
                                             body,
                                         ),
                                     },
@@ -409,6 +489,7 @@ impl Program<'_> {
                                                 crate::ImageDimension::Cube => 2,
                                             },
                                         },
+                                        meta,
                                         body,
                                     )),
                                 )
@@ -423,6 +504,7 @@ impl Program<'_> {
                                     array_index,
                                     index: Some(args[2].0),
                                 },
+                                meta,
                                 body,
                             )))
                         } else {
@@ -436,7 +518,9 @@ impl Program<'_> {
                     | "inversesqrt" | "exp" | "exp2" | "sign" | "transpose" | "inverse"
                     | "normalize" | "sinh" | "cos" | "cosh" | "tan" | "tanh" | "acos" | "asin"
                     | "log" | "log2" | "length" | "determinant" | "bitCount"
-                    | "bitfieldReverse" => {
+                    | "bitfieldReverse" | "packSnorm4x8" | "packUnorm4x8" | "packSnorm2x16"
+                    | "packUnorm2x16" | "packHalf2x16" | "unpackSnorm4x8" | "unpackUnorm4x8"
+                    | "unpackSnorm2x16" | "unpackUnorm2x16" | "unpackHalf2x16" => {
                         if args.len() != 1 {
                             return Err(ErrorKind::wrong_function_args(name, 1, args.len(), meta));
                         }
@@ -471,15 +555,102 @@ impl Program<'_> {
                                     "determinant" => MathFunction::Determinant,
                                     "bitCount" => MathFunction::CountOneBits,
                                     "bitfieldReverse" => MathFunction::ReverseBits,
+                                    "packSnorm4x8" => MathFunction::Pack4x8snorm,
+                                    "packUnorm4x8" => MathFunction::Pack4x8unorm,
+                                    "packSnorm2x16" => MathFunction::Pack2x16snorm,
+                                    "packUnorm2x16" => MathFunction::Pack2x16unorm,
+                                    "packHalf2x16" => MathFunction::Pack2x16float,
+                                    "unpackSnorm4x8" => MathFunction::Unpack4x8snorm,
+                                    "unpackUnorm4x8" => MathFunction::Unpack4x8unorm,
+                                    "unpackSnorm2x16" => MathFunction::Unpack2x16snorm,
+                                    "unpackUnorm2x16" => MathFunction::Unpack2x16unorm,
+                                    "unpackHalf2x16" => MathFunction::Unpack2x16float,
                                     _ => unreachable!(),
                                 },
                                 arg: args[0].0,
                                 arg1: None,
                                 arg2: None,
+                                arg3: None,
                             },
+                            meta,
                             body,
                         )))
                     }
+                    "findLSB" | "findMSB" => {
+                        if args.len() != 1 {
+                            return Err(ErrorKind::wrong_function_args(name, 1, args.len(), meta));
+                        }
+                        let expr = ctx.add_expression(
+                            Expression::Math {
+                                fun: match name.as_str() {
+                                    "findLSB" => MathFunction::FirstTrailingBit,
+                                    _ => MathFunction::FirstLeadingBit,
+                                },
+                                arg: args[0].0,
+                                arg1: None,
+                                arg2: None,
+                                arg3: None,
+                            },
+                            meta,
+                            body,
+                        );
+                        // glsl always returns signed integers here
+                        match self.resolve_type(ctx, expr, meta)?.scalar_kind() {
+                            Some(ScalarKind::Uint) => Ok(Some(ctx.add_expression(
+                                Expression::As {
+                                    expr,
+                                    kind: ScalarKind::Sint,
+                                    convert: None,
+                                },
+                                meta,
+                                body,
+                            ))),
+                            _ => Ok(Some(expr)),
+                        }
+                    }
+                    "bitfieldExtract" | "bitfieldInsert" => {
+                        let count = if name == "bitfieldExtract" { 3 } else { 4 };
+                        if args.len() != count {
+                            return Err(ErrorKind::wrong_function_args(
+                                name,
+                                count,
+                                args.len(),
+                                meta,
+                            ));
+                        }
+                        // the offset and the bit count are the last two arguments,
+                        // which are signed in glsl
+                        let mut bits = [None; 2];
+                        for (bit, &(expr, meta)) in bits.iter_mut().zip(&args[count - 2..]) {
+                            *bit = Some(ctx.add_expression(
+                                Expression::As {
+                                    expr,
+                                    kind: ScalarKind::Uint,
+                                    convert: Some(4),
+                                },
+                                meta,
+                                body,
+                            ));
+                        }
+                        let expr = if count == 3 {
+                            Expression::Math {
+                                fun: MathFunction::ExtractBits,
+                                arg: args[0].0,
+                                arg1: bits[0],
+                                arg2: bits[1],
+                                arg3: None,
+                            }
+                        } else {
+                            Expression::Math {
+                                fun: MathFunction::InsertBits,
+                                arg: args[0].0,
+                                arg1: Some(args[1].0),
+                                arg2: bits[0],
+                                arg3: bits[1],
+                            }
+                        };
+                        Ok(Some(ctx.add_expression(expr, meta, body)))
+                    }
                     "atan" => {
                         let expr = match args.len() {
                             1 => Expression::Math {
@@ -487,12 +658,14 @@ impl Program<'_> {
                                 arg: args[0].0,
                                 arg1: None,
                                 arg2: None,
+                                arg3: None,
                             },
                             2 => Expression::Math {
                                 fun: MathFunction::Atan2,
                                 arg: args[0].0,
                                 arg1: Some(args[1].0),
                                 arg2: None,
+                                arg3: None,
                             },
                             _ => {
                                 return Err(ErrorKind::wrong_function_args(
@@ -503,7 +676,7 @@ impl Program<'_> {
                                 ))
                             }
                         };
-                        Ok(Some(ctx.add_expression(expr, body)))
+                        Ok(Some(ctx.add_expression(expr, meta, body)))
                     }
                     "mod" => {
                         if args.len() != 2 {
@@ -523,6 +696,7 @@ impl Program<'_> {
                                 left,
                                 right,
                             },
+                            meta,
                             body,
                         )))
                     }
@@ -556,7 +730,9 @@ impl Program<'_> {
                                 arg: arg0,
                                 arg1: Some(arg1),
                                 arg2: None,
+                                arg3: None,
                             },
+                            meta,
                             body,
                         )))
                     }
@@ -592,7 +768,9 @@ impl Program<'_> {
                                 arg: arg0,
                                 arg1: Some(arg1),
                                 arg2: None,
+                                arg3: None,
                             },
+                            meta,
                             body,
                         )))
                     }
@@ -627,6 +805,7 @@ impl Program<'_> {
                                         size,
                                         value: selector,
                                     },
+                                    meta,
                                     body,
                                 )
                             }
@@ -647,10 +826,11 @@ impl Program<'_> {
                                 arg,
                                 arg1: Some(arg1),
                                 arg2: Some(selector),
+                                arg3: None,
                             },
                         };
 
-                        Ok(Some(ctx.add_expression(expr, body)))
+                        Ok(Some(ctx.add_expression(expr, meta, body)))
                     }
                     "clamp" => {
                         if args.len() != 3 {
@@ -685,7 +865,9 @@ impl Program<'_> {
                                 arg: arg0,
                                 arg1: Some(arg1),
                                 arg2: Some(arg2),
+                                arg3: None,
                             },
+                            meta,
                             body,
                         )))
                     }
@@ -705,7 +887,9 @@ impl Program<'_> {
                                 arg: args[0].0,
                                 arg1: Some(args[1].0),
                                 arg2: Some(args[2].0),
+                                arg3: None,
                             },
+                            meta,
                             body,
                         )))
                     }
@@ -728,6 +912,7 @@ impl Program<'_> {
                                 left: args[0].0,
                                 right: args[1].0,
                             },
+                            meta,
                             body,
                         )))
                     }
@@ -744,6 +929,139 @@ impl Program<'_> {
                             self.parse_relational_fun(ctx, body, name, &args, fun, meta)?,
                         ))
                     }
+                    "nonuniformEXT" => {
+                        if args.len() != 1 {
+                            return Err(ErrorKind::wrong_function_args(name, 1, args.len(), meta));
+                        }
+                        // Non-uniformity is derived by the validator, so the
+                        // qualifier doesn't need an expression of its own.
+                        Ok(Some(args[0].0))
+                    }
+                    "subgroupBallot" => {
+                        if args.len() != 1 {
+                            return Err(ErrorKind::wrong_function_args(name, 1, args.len(), meta));
+                        }
+                        let predicate = Some(args[0].0);
+                        Ok(Some(self.push_subgroup(
+                            ctx,
+                            body,
+                            Expression::SubgroupBallotResult,
+                            meta,
+                            |result| Statement::SubgroupBallot { result, predicate },
+                        )))
+                    }
+                    "subgroupAll"
+                    | "subgroupAny"
+                    | "subgroupAdd"
+                    | "subgroupMul"
+                    | "subgroupMin"
+                    | "subgroupMax"
+                    | "subgroupAnd"
+                    | "subgroupOr"
+                    | "subgroupXor"
+                    | "subgroupInclusiveAdd"
+                    | "subgroupInclusiveMul"
+                    | "subgroupInclusiveMin"
+                    | "subgroupInclusiveMax"
+                    | "subgroupInclusiveAnd"
+                    | "subgroupInclusiveOr"
+                    | "subgroupInclusiveXor"
+                    | "subgroupExclusiveAdd"
+                    | "subgroupExclusiveMul"
+                    | "subgroupExclusiveMin"
+                    | "subgroupExclusiveMax"
+                    | "subgroupExclusiveAnd"
+                    | "subgroupExclusiveOr"
+                    | "subgroupExclusiveXor" => {
+                        if args.len() != 1 {
+                            return Err(ErrorKind::wrong_function_args(name, 1, args.len(), meta));
+                        }
+                        let (collective_op, op) = if name.starts_with("subgroupInclusive") {
+                            (CollectiveOperation::InclusiveScan, &name[17..])
+                        } else if name.starts_with("subgroupExclusive") {
+                            (CollectiveOperation::ExclusiveScan, &name[17..])
+                        } else {
+                            (CollectiveOperation::Reduce, &name[8..])
+                        };
+                        let op = match op {
+                            "All" => SubgroupOperation::All,
+                            "Any" => SubgroupOperation::Any,
+                            "Add" => SubgroupOperation::Add,
+                            "Mul" => SubgroupOperation::Mul,
+                            "Min" => SubgroupOperation::Min,
+                            "Max" => SubgroupOperation::Max,
+                            "And" => SubgroupOperation::And,
+                            "Or" => SubgroupOperation::Or,
+                            "Xor" => SubgroupOperation::Xor,
+                            _ => unreachable!(),
+                        };
+                        let argument = args[0].0;
+                        let expression = self.subgroup_result(ctx, argument, meta)?;
+                        Ok(Some(self.push_subgroup(
+                            ctx,
+                            body,
+                            expression,
+                            meta,
+                            |result| Statement::SubgroupCollectiveOperation {
+                                op,
+                                collective_op,
+                                argument,
+                                result,
+                            },
+                        )))
+                    }
+                    "subgroupBroadcastFirst"
+                    | "subgroupBroadcast"
+                    | "subgroupShuffle"
+                    | "subgroupShuffleXor"
+                    | "subgroupShuffleUp"
+                    | "subgroupShuffleDown" => {
+                        let mode = match name.as_str() {
+                            "subgroupBroadcastFirst" => {
+                                if args.len() != 1 {
+                                    return Err(ErrorKind::wrong_function_args(
+                                        name,
+                                        1,
+                                        args.len(),
+                                        meta,
+                                    ));
+                                }
+                                GatherMode::BroadcastFirst
+                            }
+                            _ => {
+                                if args.len() != 2 {
+                                    return Err(ErrorKind::wrong_function_args(
+                                        name,
+                                        2,
+                                        args.len(),
+                                        meta,
+                                    ));
+                                }
+                                let index = args[1].0;
+                                match name.as_str() {
+                                    "subgroupBroadcast" => GatherMode::Broadcast(index),
+                                    "subgroupShuffle" => GatherMode::Shuffle(index),
+                                    "subgroupShuffleXor" => GatherMode::ShuffleXor(index),
+                                    "subgroupShuffleUp" => GatherMode::ShuffleUp(index),
+                                    "subgroupShuffleDown" => GatherMode::ShuffleDown(index),
+                                    _ => unreachable!(),
+                                }
+                            }
+                        };
+                        let argument = args[0].0;
+                        let expression = self.subgroup_result(ctx, argument, meta)?;
+                        Ok(Some(self.push_subgroup(
+                            ctx,
+                            body,
+                            expression,
+                            meta,
+                            |result| Statement::SubgroupGather {
+                                mode,
+                                argument,
+                                result,
+                            },
+                        )))
+                    }
                     _ => {
                         let declarations = self.lookup_function.get(&name).ok_or_else(|| {
                             ErrorKind::SemanticError(
@@ -865,22 +1183,34 @@ impl Program<'_> {
                                         Expression::Swizzle { .. }
                                     )
                                 {
-                                    let ty = self.module.types.fetch_or_append(Type {
-                                        name: None,
-                                        inner: TypeInner::Vector { size, kind, width },
-                                    });
-                                    let temp_var = ctx.locals.append(LocalVariable {
-                                        name: None,
-                                        ty,
-                                        init: None,
-                                    });
-                                    let temp_expr = ctx
-                                        .add_expression(Expression::LocalVariable(temp_var), body);
-
-                                    body.push(Statement::Store {
-                                        pointer: temp_expr,
-                                        value: handle,
-                                    });
+                                    let ty = self.module.types.fetch_or_append(
+                                        Type {
+                                            name: None,
+                                            inner: TypeInner::Vector { size, kind, width },
+                                        },
+                                        Default::default(),
+                                    );
+                                    let temp_var = ctx.locals.append(
+                                        LocalVariable {
+                                            name: None,
+                                            ty,
+                                            init: None,
+                                        },
+                                        Default::default(),
+                                    );
+                                    let temp_expr = ctx.add_expression(
+                                        Expression::LocalVariable(temp_var),
+                                        meta,
+                                        body,
+                                    );
+
+                                    body.push(
+                                        Statement::Store {
+                                            pointer: temp_expr,
+                                            value: handle,
+                                        },
+                                        meta.into(),
+                                    );
 
                                     arguments.push(temp_expr);
                                     proxy_writes.push((*expr, temp_expr));
@@ -900,35 +1230,44 @@ impl Program<'_> {
                         ctx.emit_flush(body);
 
                         let result = if !is_void {
-                            Some(ctx.add_expression(Expression::Call(function), body))
+                            Some(ctx.add_expression(Expression::Call(function), meta, body))
                         } else {
                             None
                         };
 
-                        body.push(crate::Statement::Call {
-                            function,
-                            arguments,
-                            result,
-                        });
+                        body.push(
+                            crate::Statement::Call {
+                                function,
+                                arguments,
+                                result,
+                            },
+                            meta.into(),
+                        );
 
                         ctx.emit_start();
                         for (tgt, pointer) in proxy_writes {
-                            let temp_ref = ctx.hir_exprs.append(HirExpr {
-                                kind: HirExprKind::Variable(VariableReference {
-                                    expr: pointer,
-                                    load: true,
-                                    mutable: true,
-                                    entry_arg: None,
-                                }),
-                                meta,
-                            });
-                            let assign = ctx.hir_exprs.append(HirExpr {
-                                kind: HirExprKind::Assign {
-                                    tgt,
-                                    value: temp_ref,
+                            let temp_ref = ctx.hir_exprs.append(
+                                HirExpr {
+                                    kind: HirExprKind::Variable(VariableReference {
+                                        expr: pointer,
+                                        load: true,
+                                        mutable: true,
+                                        entry_arg: None,
+                                    }),
+                                    meta,
                                 },
-                                meta,
-                            });
+                                Default::default(),
+                            );
+                            let assign = ctx.hir_exprs.append(
+                                HirExpr {
+                                    kind: HirExprKind::Assign {
+                                        tgt,
+                                        value: temp_ref,
+                                    },
+                                    meta,
+                                },
+                                Default::default(),
+                            );
 
                             let _ = ctx.lower_expect(self, assign, false, body)?;
                         }
@@ -960,10 +1299,59 @@ impl Program<'_> {
                 fun,
                 argument: args[0].0,
             },
+            meta,
             body,
         ))
     }
 
+    /// Result expression of a subgroup operation on `argument`.
+    fn subgroup_result(
+        &mut self,
+        ctx: &mut Context,
+        argument: Handle<Expression>,
+        meta: SourceMetadata,
+    ) -> Result<Expression, ErrorKind> {
+        self.typifier_grow(ctx, argument, meta)?;
+        let ty = match ctx.typifier[argument] {
+            TypeResolution::Handle(ty) => ty,
+            TypeResolution::Value(ref inner) => {
+                let inner = match *inner {
+                    TypeInner::Scalar { kind, width } => TypeInner::Scalar { kind, width },
+                    TypeInner::Vector { size, kind, width } => {
+                        TypeInner::Vector { size, kind, width }
+                    }
+                    _ => {
+                        return Err(ErrorKind::SemanticError(
+                            meta,
+                            "Subgroup operand must be a scalar or a vector".into(),
+                        ))
+                    }
+                };
+                self.module
+                    .types
+                    .fetch_or_append(Type { name: None, inner }, Default::default())
+            }
+        };
+        Ok(Expression::SubgroupOperationResult { ty })
+    }
+
+    /// Append the result `expression` of a subgroup statement, and the
+    /// statement itself, produced by `statement` from the result handle.
+    fn push_subgroup(
+        &mut self,
+        ctx: &mut Context,
+        body: &mut Block,
+        expression: Expression,
+        meta: SourceMetadata,
+        statement: impl FnOnce(Handle<Expression>) -> Statement,
+    ) -> Handle<Expression> {
+        ctx.emit_flush(body);
+        let result = ctx.add_expression(expression, meta, body);
+        body.push(statement(result), meta.into());
+        ctx.emit_start();
+        result
+    }
+
     pub fn add_function(
         &mut self,
         mut function: Function,
@@ -977,7 +1365,7 @@ impl Program<'_> {
         let stage = self.entry_points.get(&name);
 
         Ok(if let Some(&stage) = stage {
-            let handle = self.module.functions.append(function);
+            let handle = self.module.functions.append(function, Default::default());
             self.entries.push((name, stage, handle));
             self.function_arg_use.push(Vec::new());
             handle
@@ -1021,7 +1409,7 @@ impl Program<'_> {
             }
 
             self.function_arg_use.push(Vec::new());
-            let handle = module.functions.append(function);
+            let handle = module.functions.append(function, Default::default());
             declarations.push(FunctionDeclaration {
                 parameters,
                 qualifiers,
@@ -1073,7 +1461,7 @@ impl Program<'_> {
         }
 
         self.function_arg_use.push(Vec::new());
-        let handle = module.functions.append(function);
+        let handle = module.functions.append(function, Default::default());
         declarations.push(FunctionDeclaration {
             parameters,
             qualifiers,
@@ -1157,7 +1545,7 @@ impl Program<'_> {
         for (name, stage, function) in self.entries.iter().cloned() {
             let mut arguments = Vec::new();
             let mut expressions = Arena::new();
-            let mut body = Vec::new();
+            let mut body = Block::new();
 
             let can_strip_stage_inputs =
                 self.strip_unused_linkages || stage != ShaderStage::Fragment;
@@ -1190,17 +1578,22 @@ impl Program<'_> {
                     binding: Some(arg.binding.clone()),
                 });
 
-                let pointer = expressions.append(Expression::GlobalVariable(arg.handle));
-                let value = expressions.append(Expression::FunctionArgument(idx));
+                let pointer =
+                    expressions.append(Expression::GlobalVariable(arg.handle), Default::default());
+                let value =
+                    expressions.append(Expression::FunctionArgument(idx), Default::default());
 
-                body.push(Statement::Store { pointer, value });
+                body.push(Statement::Store { pointer, value }, Default::default());
             }
 
-            body.push(Statement::Call {
-                function,
-                arguments: Vec::new(),
-                result: None,
-            });
+            body.push(
+                Statement::Call {
+                    function,
+                    arguments: Vec::new(),
+                    result: None,
+                },
+                Default::default(),
+            );
 
             let mut span = 0;
             let mut members = Vec::new();
@@ -1230,33 +1623,44 @@ impl Program<'_> {
 
                 span += self.module.types[ty].inner.span(&self.module.constants);
 
-                let pointer = expressions.append(Expression::GlobalVariable(arg.handle));
+                let pointer =
+                    expressions.append(Expression::GlobalVariable(arg.handle), Default::default());
                 let len = expressions.len();
-                let load = expressions.append(Expression::Load { pointer });
-                body.push(Statement::Emit(expressions.range_from(len)));
+                let load = expressions.append(Expression::Load { pointer }, Default::default());
+                body.push(
+                    Statement::Emit(expressions.range_from(len)),
+                    Default::default(),
+                );
                 components.push(load)
             }
 
             let (ty, value) = if !components.is_empty() {
-                let ty = self.module.types.append(Type {
-                    name: None,
-                    inner: TypeInner::Struct {
-                        top_level: false,
-                        members,
-                        span,
+                let ty = self.module.types.append(
+                    Type {
+                        name: None,
+                        inner: TypeInner::Struct {
+                            top_level: false,
+                            members,
+                            span,
+                        },
                     },
-                });
+                    Default::default(),
+                );
 
                 let len = expressions.len();
-                let res = expressions.append(Expression::Compose { ty, components });
-                body.push(Statement::Emit(expressions.range_from(len)));
+                let res =
+                    expressions.append(Expression::Compose { ty, components }, Default::default());
+                body.push(
+                    Statement::Emit(expressions.range_from(len)),
+                    Default::default(),
+                );
 
                 (Some(ty), Some(res))
             } else {
                 (None, None)
             };
 
-            body.push(Statement::Return { value });
+            body.push(Statement::Return { value }, Default::default());
 
             self.module.entry_points.push(EntryPoint {
                 name,
@@ -10,7 +10,6 @@ use ast::Program;
 
 mod error;
 pub use error::ParseError;
-mod constants;
 mod functions;
 mod parser;
 #[cfg(test)]
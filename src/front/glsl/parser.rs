@@ -77,7 +77,11 @@ impl<'source, 'program, 'options> Parser<'source, 'program, 'options> {
         self.parse_version()?;
 
         while self.lexer.peek().is_some() {
-            self.parse_external_declaration()?;
+            if self.bump_if(TokenValue::Extension).is_some() {
+                self.parse_extension()?;
+            } else {
+                self.parse_external_declaration()?;
+            }
         }
 
         self.program.add_entry_points();
@@ -121,6 +125,24 @@ impl<'source, 'program, 'options> Parser<'source, 'program, 'options> {
         Ok(())
     }
 
+    /// Parses the rest of an `#extension name : behavior` directive.
+    ///
+    /// Extensions only unlock builtins that are always recognized,
+    /// so the directive is checked but otherwise ignored.
+    fn parse_extension(&mut self) -> Result<()> {
+        let _ = self.expect_ident()?;
+        self.expect(TokenValue::Colon)?;
+        let (behavior, meta) = self.expect_ident()?;
+
+        match behavior.as_str() {
+            "require" | "enable" | "warn" | "disable" => Ok(()),
+            _ => Err(ErrorKind::SemanticError(
+                meta,
+                format!("Unknown extension behavior '{}'", behavior).into(),
+            )),
+        }
+    }
+
     /// Parses an optional array_specifier returning `Ok(None)` if there is no
     /// LeftBracket
     fn parse_array_specifier(&mut self) -> Result<Option<ArraySize>> {
@@ -141,7 +163,12 @@ impl<'source, 'program, 'options> Parser<'source, 'program, 'options> {
         let token = self.bump()?;
         let handle = match token.value {
             TokenValue::Void => None,
-            TokenValue::TypeName(ty) => Some(self.program.module.types.fetch_or_append(ty)),
+            TokenValue::TypeName(ty) => Some(
+                self.program
+                    .module
+                    .types
+                    .fetch_or_append(ty, Default::default()),
+            ),
             TokenValue::Struct => {
                 let ty_name = self.expect_ident()?.0;
                 self.expect(TokenValue::LeftBrace)?;
@@ -149,14 +176,17 @@ impl<'source, 'program, 'options> Parser<'source, 'program, 'options> {
                 let span = self.parse_struct_declaration_list(&mut members)?;
                 self.expect(TokenValue::RightBrace)?;
 
-                let ty = self.program.module.types.append(Type {
-                    name: Some(ty_name.clone()),
-                    inner: TypeInner::Struct {
-                        top_level: false,
-                        members,
-                        span,
+                let ty = self.program.module.types.append(
+                    Type {
+                        name: Some(ty_name.clone()),
+                        inner: TypeInner::Struct {
+                            top_level: false,
+                            members,
+                            span,
+                        },
                     },
-                });
+                    Default::default(),
+                );
                 self.program.lookup_type.insert(ty_name, ty);
                 Some(ty)
             }
@@ -190,16 +220,21 @@ impl<'source, 'program, 'options> Parser<'source, 'program, 'options> {
 
     fn maybe_array(&mut self, base: Handle<Type>, size: Option<ArraySize>) -> Handle<Type> {
         size.map(|size| {
-            self.program.module.types.fetch_or_append(Type {
-                name: None,
-                inner: TypeInner::Array {
+            let inner = match self.program.module.types[base].inner {
+                // Arrays of textures and samplers are binding arrays
+                TypeInner::Image { .. } | TypeInner::Sampler { .. } => {
+                    TypeInner::BindingArray { base, size }
+                }
+                ref other => TypeInner::Array {
                     base,
                     size,
-                    stride: self.program.module.types[base]
-                        .inner
-                        .span(&self.program.module.constants),
+                    stride: other.span(&self.program.module.constants),
                 },
-            })
+            };
+            self.program
+                .module
+                .types
+                .fetch_or_append(Type { name: None, inner }, Default::default())
         })
         .unwrap_or(base)
     }
@@ -517,7 +552,7 @@ impl<'source, 'program, 'options> Parser<'source, 'program, 'options> {
             }
 
             Ok((
-                ctx.add_expression(Expression::Compose { ty, components }, body),
+                ctx.add_expression(Expression::Compose { ty, components }, meta, body),
                 meta,
             ))
         } else {
@@ -589,6 +624,10 @@ impl<'source, 'program, 'options> Parser<'source, 'program, 'options> {
             let init = self
                 .bump_if(TokenValue::Assign)
                 .map::<Result<_>, _>(|_| {
+                    // Keep the statements of the initializer apart, so they can be
+                    // dropped if it gets folded into a constant
+                    ctx.flush_expressions();
+                    let body_len = ctx.body.len();
                     let (mut expr, init_meta) = self.parse_initializer(ty, ctx.ctx, ctx.body)?;
 
                     let scalar_components =
@@ -605,21 +644,31 @@ impl<'source, 'program, 'options> Parser<'source, 'program, 'options> {
 
                     meta = meta.union(&init_meta);
 
-                    Ok((expr, init_meta))
+                    Ok((expr, init_meta, body_len))
                 })
                 .transpose()?;
 
             // TODO: Should we try to make constants here?
             // This is mostly a hack because we don't yet support adding
             // bodies to entry points for variable initialization
-            let maybe_constant =
-                init.and_then(|(root, meta)| self.program.solve_constant(ctx.ctx, root, meta).ok());
+            let maybe_constant = init
+                .filter(|&(_, _, body_len)| {
+                    ctx.body[body_len..]
+                        .iter()
+                        .all(|statement| matches!(*statement, Statement::Emit(_)))
+                })
+                .and_then(|(root, meta, _)| self.program.solve_constant(ctx.ctx, root, meta).ok());
 
             let pointer = ctx.add_var(self.program, ty, name, maybe_constant, meta)?;
 
-            if let Some((value, _)) = init.filter(|_| maybe_constant.is_none()) {
-                ctx.flush_expressions();
-                ctx.body.push(Statement::Store { pointer, value });
+            if let Some((value, _, body_len)) = init {
+                if maybe_constant.is_some() {
+                    ctx.discard_expressions(body_len);
+                } else {
+                    ctx.flush_expressions();
+                    ctx.body
+                        .push(Statement::Store { pointer, value }, meta.into());
+                }
             }
 
             let token = self.bump()?;
@@ -897,30 +946,36 @@ impl<'source, 'program, 'options> Parser<'source, 'program, 'options> {
         let span = self.parse_struct_declaration_list(&mut members)?;
         self.expect(TokenValue::RightBrace)?;
 
-        let mut ty = self.program.module.types.append(Type {
-            name: Some(ty_name),
-            inner: TypeInner::Struct {
-                top_level: true,
-                members: members.clone(),
-                span,
+        let mut ty = self.program.module.types.append(
+            Type {
+                name: Some(ty_name),
+                inner: TypeInner::Struct {
+                    top_level: true,
+                    members: members.clone(),
+                    span,
+                },
             },
-        });
+            Default::default(),
+        );
 
         let token = self.bump()?;
         let name = match token.value {
             TokenValue::Semicolon => None,
             TokenValue::Identifier(name) => {
                 if let Some(size) = self.parse_array_specifier()? {
-                    ty = self.program.module.types.fetch_or_append(Type {
-                        name: None,
-                        inner: TypeInner::Array {
-                            base: ty,
-                            size,
-                            stride: self.program.module.types[ty]
-                                .inner
-                                .span(&self.program.module.constants),
+                    ty = self.program.module.types.fetch_or_append(
+                        Type {
+                            name: None,
+                            inner: TypeInner::Array {
+                                base: ty,
+                                size,
+                                stride: self.program.module.types[ty]
+                                    .inner
+                                    .span(&self.program.module.constants),
+                            },
                         },
-                    });
+                        Default::default(),
+                    );
                 }
 
                 self.expect(TokenValue::Semicolon)?;
@@ -1039,16 +1094,22 @@ impl<'source, 'program, 'options> Parser<'source, 'program, 'options> {
             }
         };
 
-        let handle = self.program.module.constants.fetch_or_append(Constant {
-            name: None,
-            specialization: None,
-            inner: ConstantInner::Scalar { width, value },
-        });
+        let handle = self.program.module.constants.fetch_or_append(
+            Constant {
+                name: None,
+                specialization: None,
+                inner: ConstantInner::Scalar { width, value },
+            },
+            Default::default(),
+        );
 
-        Ok(ctx.hir_exprs.append(HirExpr {
-            kind: HirExprKind::Constant(handle),
-            meta: token.meta,
-        }))
+        Ok(ctx.hir_exprs.append(
+            HirExpr {
+                kind: HirExprKind::Constant(handle),
+                meta: token.meta,
+            },
+            Default::default(),
+        ))
     }
 
     fn parse_function_call_args(
@@ -1113,13 +1174,16 @@ impl<'source, 'program, 'options> Parser<'source, 'program, 'options> {
                     }
                 };
 
-                ctx.hir_exprs.append(expr)
+                ctx.hir_exprs.append(expr, Default::default())
             }
             TokenValue::TypeName(_) => {
                 let Token { value, mut meta } = self.bump()?;
 
                 let mut handle = if let TokenValue::TypeName(ty) = value {
-                    self.program.module.types.fetch_or_append(ty)
+                    self.program
+                        .module
+                        .types
+                        .fetch_or_append(ty, Default::default())
                 } else {
                     unreachable!()
                 };
@@ -1137,37 +1201,45 @@ impl<'source, 'program, 'options> Parser<'source, 'program, 'options> {
                     let size = match array_size {
                         ArraySize::Constant(size) => ArraySize::Constant(size),
                         ArraySize::Dynamic => {
-                            let constant =
-                                self.program.module.constants.fetch_or_append(Constant {
+                            let constant = self.program.module.constants.fetch_or_append(
+                                Constant {
                                     name: None,
                                     specialization: None,
                                     inner: ConstantInner::Scalar {
                                         width: 4,
                                         value: ScalarValue::Sint(args.len() as i64),
                                     },
-                                });
+                                },
+                                Default::default(),
+                            );
 
                             ArraySize::Constant(constant)
                         }
                     };
 
-                    handle = self.program.module.types.fetch_or_append(Type {
-                        name: None,
-                        inner: TypeInner::Array {
-                            base: handle,
-                            size,
-                            stride,
+                    handle = self.program.module.types.fetch_or_append(
+                        Type {
+                            name: None,
+                            inner: TypeInner::Array {
+                                base: handle,
+                                size,
+                                stride,
+                            },
                         },
-                    })
+                        Default::default(),
+                    )
                 }
 
-                ctx.hir_exprs.append(HirExpr {
-                    kind: HirExprKind::Call(FunctionCall {
-                        kind: FunctionCallKind::TypeConstructor(handle),
-                        args,
-                    }),
-                    meta,
-                })
+                ctx.hir_exprs.append(
+                    HirExpr {
+                        kind: HirExprKind::Call(FunctionCall {
+                            kind: FunctionCallKind::TypeConstructor(handle),
+                            args,
+                        }),
+                        meta,
+                    },
+                    Default::default(),
+                )
             }
             _ => self.parse_primary(ctx, body)?,
         };
@@ -1184,38 +1256,52 @@ impl<'source, 'program, 'options> Parser<'source, 'program, 'options> {
                     let index = self.parse_expression(ctx, body)?;
                     let end_meta = self.expect(TokenValue::RightBracket)?.meta;
 
-                    base = ctx.hir_exprs.append(HirExpr {
-                        kind: HirExprKind::Access { base, index },
-                        meta: meta.union(&end_meta),
-                    })
+                    let meta = ctx.hir_exprs[base].meta.union(&end_meta);
+                    base = ctx.hir_exprs.append(
+                        HirExpr {
+                            kind: HirExprKind::Access { base, index },
+                            meta,
+                        },
+                        Default::default(),
+                    )
                 }
                 TokenValue::Dot => {
                     let (field, end_meta) = self.expect_ident()?;
 
-                    base = ctx.hir_exprs.append(HirExpr {
-                        kind: HirExprKind::Select { base, field },
-                        meta: meta.union(&end_meta),
-                    })
+                    let meta = ctx.hir_exprs[base].meta.union(&end_meta);
+                    base = ctx.hir_exprs.append(
+                        HirExpr {
+                            kind: HirExprKind::Select { base, field },
+                            meta,
+                        },
+                        Default::default(),
+                    )
                 }
                 TokenValue::Increment => {
-                    base = ctx.hir_exprs.append(HirExpr {
-                        kind: HirExprKind::IncDec {
-                            increment: true,
-                            postfix: true,
-                            expr: base,
+                    base = ctx.hir_exprs.append(
+                        HirExpr {
+                            kind: HirExprKind::IncDec {
+                                increment: true,
+                                postfix: true,
+                                expr: base,
+                            },
+                            meta,
                         },
-                        meta,
-                    })
+                        Default::default(),
+                    )
                 }
                 TokenValue::Decrement => {
-                    base = ctx.hir_exprs.append(HirExpr {
-                        kind: HirExprKind::IncDec {
-                            increment: false,
-                            postfix: true,
-                            expr: base,
+                    base = ctx.hir_exprs.append(
+                        HirExpr {
+                            kind: HirExprKind::IncDec {
+                                increment: false,
+                                postfix: true,
+                                expr: base,
+                            },
+                            meta,
                         },
-                        meta,
-                    })
+                        Default::default(),
+                    )
                 }
                 _ => unreachable!(),
             }
@@ -1245,28 +1331,34 @@ impl<'source, 'program, 'options> Parser<'source, 'program, 'options> {
                     _ => return Ok(expr),
                 };
 
-                ctx.hir_exprs.append(HirExpr {
-                    kind,
-                    meta: meta.union(&end_meta),
-                })
+                ctx.hir_exprs.append(
+                    HirExpr {
+                        kind,
+                        meta: meta.union(&end_meta),
+                    },
+                    Default::default(),
+                )
             }
             TokenValue::Increment | TokenValue::Decrement => {
                 let Token { value, meta } = self.bump()?;
 
                 let expr = self.parse_unary(ctx, body)?;
 
-                ctx.hir_exprs.append(HirExpr {
-                    kind: HirExprKind::IncDec {
-                        increment: match value {
-                            TokenValue::Increment => true,
-                            TokenValue::Decrement => false,
-                            _ => unreachable!(),
+                ctx.hir_exprs.append(
+                    HirExpr {
+                        kind: HirExprKind::IncDec {
+                            increment: match value {
+                                TokenValue::Increment => true,
+                                TokenValue::Decrement => false,
+                                _ => unreachable!(),
+                            },
+                            postfix: false,
+                            expr,
                         },
-                        postfix: false,
-                        expr,
+                        meta,
                     },
-                    meta,
-                })
+                    Default::default(),
+                )
             }
             _ => self.parse_postfix(ctx, body)?,
         })
@@ -1294,35 +1386,38 @@ impl<'source, 'program, 'options> Parser<'source, 'program, 'options> {
             let right = self.parse_binary(ctx, body, None, r_bp)?;
             let end_meta = ctx.hir_exprs[right].meta;
 
-            left = ctx.hir_exprs.append(HirExpr {
-                kind: HirExprKind::Binary {
-                    left,
-                    op: match value {
-                        TokenValue::LogicalOr => BinaryOperator::LogicalOr,
-                        TokenValue::LogicalXor => BinaryOperator::NotEqual,
-                        TokenValue::LogicalAnd => BinaryOperator::LogicalAnd,
-                        TokenValue::VerticalBar => BinaryOperator::InclusiveOr,
-                        TokenValue::Caret => BinaryOperator::ExclusiveOr,
-                        TokenValue::Ampersand => BinaryOperator::And,
-                        TokenValue::Equal => BinaryOperator::Equal,
-                        TokenValue::NotEqual => BinaryOperator::NotEqual,
-                        TokenValue::GreaterEqual => BinaryOperator::GreaterEqual,
-                        TokenValue::LessEqual => BinaryOperator::LessEqual,
-                        TokenValue::LeftAngle => BinaryOperator::Less,
-                        TokenValue::RightAngle => BinaryOperator::Greater,
-                        TokenValue::LeftShift => BinaryOperator::ShiftLeft,
-                        TokenValue::RightShift => BinaryOperator::ShiftRight,
-                        TokenValue::Plus => BinaryOperator::Add,
-                        TokenValue::Dash => BinaryOperator::Subtract,
-                        TokenValue::Star => BinaryOperator::Multiply,
-                        TokenValue::Slash => BinaryOperator::Divide,
-                        TokenValue::Percent => BinaryOperator::Modulo,
-                        _ => unreachable!(),
+            left = ctx.hir_exprs.append(
+                HirExpr {
+                    kind: HirExprKind::Binary {
+                        left,
+                        op: match value {
+                            TokenValue::LogicalOr => BinaryOperator::LogicalOr,
+                            TokenValue::LogicalXor => BinaryOperator::NotEqual,
+                            TokenValue::LogicalAnd => BinaryOperator::LogicalAnd,
+                            TokenValue::VerticalBar => BinaryOperator::InclusiveOr,
+                            TokenValue::Caret => BinaryOperator::ExclusiveOr,
+                            TokenValue::Ampersand => BinaryOperator::And,
+                            TokenValue::Equal => BinaryOperator::Equal,
+                            TokenValue::NotEqual => BinaryOperator::NotEqual,
+                            TokenValue::GreaterEqual => BinaryOperator::GreaterEqual,
+                            TokenValue::LessEqual => BinaryOperator::LessEqual,
+                            TokenValue::LeftAngle => BinaryOperator::Less,
+                            TokenValue::RightAngle => BinaryOperator::Greater,
+                            TokenValue::LeftShift => BinaryOperator::ShiftLeft,
+                            TokenValue::RightShift => BinaryOperator::ShiftRight,
+                            TokenValue::Plus => BinaryOperator::Add,
+                            TokenValue::Dash => BinaryOperator::Subtract,
+                            TokenValue::Star => BinaryOperator::Multiply,
+                            TokenValue::Slash => BinaryOperator::Divide,
+                            TokenValue::Percent => BinaryOperator::Modulo,
+                            _ => unreachable!(),
+                        },
+                        right,
                     },
-                    right,
+                    meta: start_meta.union(&end_meta),
                 },
-                meta: start_meta.union(&end_meta),
-            })
+                Default::default(),
+            )
         }
 
         Ok(left)
@@ -1343,14 +1438,17 @@ impl<'source, 'program, 'options> Parser<'source, 'program, 'options> {
             let reject = self.parse_assignment(ctx, body)?;
             let end_meta = ctx.hir_exprs[reject].meta;
 
-            condition = ctx.hir_exprs.append(HirExpr {
-                kind: HirExprKind::Conditional {
-                    condition,
-                    accept,
-                    reject,
+            condition = ctx.hir_exprs.append(
+                HirExpr {
+                    kind: HirExprKind::Conditional {
+                        condition,
+                        accept,
+                        reject,
+                    },
+                    meta: start_meta.union(&end_meta),
                 },
-                meta: start_meta.union(&end_meta),
-            })
+                Default::default(),
+            )
         }
 
         Ok(condition)
@@ -1366,10 +1464,13 @@ impl<'source, 'program, 'options> Parser<'source, 'program, 'options> {
                 let value = self.parse_assignment(ctx, body)?;
                 let end_meta = ctx.hir_exprs[value].meta;
 
-                ctx.hir_exprs.append(HirExpr {
-                    kind: HirExprKind::Assign { tgt, value },
-                    meta: start_meta.union(&end_meta),
-                })
+                ctx.hir_exprs.append(
+                    HirExpr {
+                        kind: HirExprKind::Assign { tgt, value },
+                        meta: start_meta.union(&end_meta),
+                    },
+                    Default::default(),
+                )
             }
             TokenValue::OrAssign
             | TokenValue::AndAssign
@@ -1385,31 +1486,37 @@ impl<'source, 'program, 'options> Parser<'source, 'program, 'options> {
                 let right = self.parse_assignment(ctx, body)?;
                 let end_meta = ctx.hir_exprs[right].meta;
 
-                let value = ctx.hir_exprs.append(HirExpr {
-                    meta: start_meta.union(&end_meta),
-                    kind: HirExprKind::Binary {
-                        left: tgt,
-                        op: match token.value {
-                            TokenValue::OrAssign => BinaryOperator::InclusiveOr,
-                            TokenValue::AndAssign => BinaryOperator::And,
-                            TokenValue::AddAssign => BinaryOperator::Add,
-                            TokenValue::DivAssign => BinaryOperator::Divide,
-                            TokenValue::ModAssign => BinaryOperator::Modulo,
-                            TokenValue::SubAssign => BinaryOperator::Subtract,
-                            TokenValue::MulAssign => BinaryOperator::Multiply,
-                            TokenValue::LeftShiftAssign => BinaryOperator::ShiftLeft,
-                            TokenValue::RightShiftAssign => BinaryOperator::ShiftRight,
-                            TokenValue::XorAssign => BinaryOperator::ExclusiveOr,
-                            _ => unreachable!(),
+                let value = ctx.hir_exprs.append(
+                    HirExpr {
+                        meta: start_meta.union(&end_meta),
+                        kind: HirExprKind::Binary {
+                            left: tgt,
+                            op: match token.value {
+                                TokenValue::OrAssign => BinaryOperator::InclusiveOr,
+                                TokenValue::AndAssign => BinaryOperator::And,
+                                TokenValue::AddAssign => BinaryOperator::Add,
+                                TokenValue::DivAssign => BinaryOperator::Divide,
+                                TokenValue::ModAssign => BinaryOperator::Modulo,
+                                TokenValue::SubAssign => BinaryOperator::Subtract,
+                                TokenValue::MulAssign => BinaryOperator::Multiply,
+                                TokenValue::LeftShiftAssign => BinaryOperator::ShiftLeft,
+                                TokenValue::RightShiftAssign => BinaryOperator::ShiftRight,
+                                TokenValue::XorAssign => BinaryOperator::ExclusiveOr,
+                                _ => unreachable!(),
+                            },
+                            right,
                         },
-                        right,
                     },
-                });
+                    Default::default(),
+                );
 
-                ctx.hir_exprs.append(HirExpr {
-                    kind: HirExprKind::Assign { tgt, value },
-                    meta: start_meta.union(&end_meta),
-                })
+                ctx.hir_exprs.append(
+                    HirExpr {
+                        kind: HirExprKind::Assign { tgt, value },
+                        meta: start_meta.union(&end_meta),
+                    },
+                    Default::default(),
+                )
             }
             _ => self.parse_conditional(ctx, body, Some(tgt))?,
         })
@@ -1439,40 +1546,42 @@ impl<'source, 'program, 'options> Parser<'source, 'program, 'options> {
             return Ok(());
         }
 
+        let meta = self.expect_peek()?.meta;
+
         match self.expect_peek()?.value {
             TokenValue::Continue => {
                 self.bump()?;
-                body.push(Statement::Continue);
+                body.push(Statement::Continue, meta.into());
                 self.expect(TokenValue::Semicolon)?;
             }
             TokenValue::Break => {
                 self.bump()?;
-                body.push(Statement::Break);
+                body.push(Statement::Break, meta.into());
                 self.expect(TokenValue::Semicolon)?;
             }
             TokenValue::Return => {
                 self.bump()?;
-                let value = match self.expect_peek()?.value {
-                    TokenValue::Semicolon => {
-                        self.bump()?;
-                        None
-                    }
+                let (value, end_meta) = match self.expect_peek()?.value {
+                    TokenValue::Semicolon => (None, self.bump()?.meta),
                     _ => {
                         // TODO: Implicit conversions
                         let expr = self.parse_expression(ctx, body)?;
-                        self.expect(TokenValue::Semicolon)?;
-                        Some(ctx.lower_expect(self.program, expr, false, body)?.0)
+                        let end_meta = self.expect(TokenValue::Semicolon)?.meta;
+                        (
+                            Some(ctx.lower_expect(self.program, expr, false, body)?.0),
+                            end_meta,
+                        )
                     }
                 };
 
                 ctx.emit_flush(body);
                 ctx.emit_start();
 
-                body.push(Statement::Return { value })
+                body.push(Statement::Return { value }, meta.union(&end_meta).into())
             }
             TokenValue::Discard => {
                 self.bump()?;
-                body.push(Statement::Kill);
+                body.push(Statement::Kill, meta.into());
                 self.expect(TokenValue::Semicolon)?;
             }
             TokenValue::If => {
@@ -1496,11 +1605,14 @@ impl<'source, 'program, 'options> Parser<'source, 'program, 'options> {
                     self.parse_statement(ctx, &mut reject)?;
                 }
 
-                body.push(Statement::If {
-                    condition,
-                    accept,
-                    reject,
-                });
+                body.push(
+                    Statement::If {
+                        condition,
+                        accept,
+                        reject,
+                    },
+                    meta.into(),
+                );
             }
             TokenValue::Switch => {
                 self.bump()?;
@@ -1606,11 +1718,14 @@ impl<'source, 'program, 'options> Parser<'source, 'program, 'options> {
                     }
                 }
 
-                body.push(Statement::Switch {
-                    selector,
-                    cases,
-                    default,
-                });
+                body.push(
+                    Statement::Switch {
+                        selector,
+                        cases,
+                        default,
+                    },
+                    meta.into(),
+                );
             }
             TokenValue::While => {
                 self.bump()?;
@@ -1629,24 +1744,31 @@ impl<'source, 'program, 'options> Parser<'source, 'program, 'options> {
                         op: UnaryOperator::Not,
                         expr,
                     },
+                    meta,
                     &mut loop_body,
                 );
 
                 ctx.emit_flush(&mut loop_body);
                 ctx.emit_start();
 
-                loop_body.push(Statement::If {
-                    condition,
-                    accept: vec![Statement::Break],
-                    reject: Block::new(),
-                });
+                loop_body.push(
+                    Statement::If {
+                        condition,
+                        accept: Block::from_vec(vec![Statement::Break]),
+                        reject: Block::new(),
+                    },
+                    meta.into(),
+                );
 
                 self.parse_statement(ctx, &mut loop_body)?;
 
-                body.push(Statement::Loop {
-                    body: loop_body,
-                    continuing: Block::new(),
-                })
+                body.push(
+                    Statement::Loop {
+                        body: loop_body,
+                        continuing: Block::new(),
+                    },
+                    meta.into(),
+                )
             }
             TokenValue::Do => {
                 self.bump()?;
@@ -1667,22 +1789,29 @@ impl<'source, 'program, 'options> Parser<'source, 'program, 'options> {
                         op: UnaryOperator::Not,
                         expr,
                     },
+                    meta,
                     &mut loop_body,
                 );
 
                 ctx.emit_flush(&mut loop_body);
                 ctx.emit_start();
 
-                loop_body.push(Statement::If {
-                    condition,
-                    accept: vec![Statement::Break],
-                    reject: Block::new(),
-                });
+                loop_body.push(
+                    Statement::If {
+                        condition,
+                        accept: Block::from_vec(vec![Statement::Break]),
+                        reject: Block::new(),
+                    },
+                    meta.into(),
+                );
 
-                body.push(Statement::Loop {
-                    body: loop_body,
-                    continuing: Block::new(),
-                })
+                body.push(
+                    Statement::Loop {
+                        body: loop_body,
+                        continuing: Block::new(),
+                    },
+                    meta.into(),
+                )
             }
             TokenValue::For => {
                 self.bump()?;
@@ -1727,7 +1856,7 @@ impl<'source, 'program, 'options> Parser<'source, 'program, 'options> {
                         ctx.emit_flush(&mut block);
                         ctx.emit_start();
 
-                        block.push(Statement::Store { pointer, value });
+                        block.push(Statement::Store { pointer, value }, meta.into());
 
                         value
                     } else {
@@ -1740,17 +1869,21 @@ impl<'source, 'program, 'options> Parser<'source, 'program, 'options> {
                             op: UnaryOperator::Not,
                             expr,
                         },
+                        meta,
                         &mut block,
                     );
 
                     ctx.emit_flush(&mut block);
                     ctx.emit_start();
 
-                    block.push(Statement::If {
-                        condition,
-                        accept: vec![Statement::Break],
-                        reject: Block::new(),
-                    });
+                    block.push(
+                        Statement::If {
+                            condition,
+                            accept: Block::from_vec(vec![Statement::Break]),
+                            reject: Block::new(),
+                        },
+                        meta.into(),
+                    );
 
                     self.expect(TokenValue::Semicolon)?;
                 }
@@ -1767,10 +1900,13 @@ impl<'source, 'program, 'options> Parser<'source, 'program, 'options> {
 
                 self.parse_statement(ctx, &mut block)?;
 
-                body.push(Statement::Loop {
-                    body: block,
-                    continuing,
-                });
+                body.push(
+                    Statement::Loop {
+                        body: block,
+                        continuing,
+                    },
+                    meta.into(),
+                );
 
                 ctx.remove_current_scope();
             }
@@ -1783,7 +1919,7 @@ impl<'source, 'program, 'options> Parser<'source, 'program, 'options> {
                 self.parse_compound_statement(ctx, &mut block)?;
 
                 ctx.remove_current_scope();
-                body.push(Statement::Block(block));
+                body.push(Statement::Block(block), meta.into());
             }
             TokenValue::Plus
             | TokenValue::Dash
@@ -1831,7 +1967,7 @@ impl<'source, 'program, 'options> Parser<'source, 'program, 'options> {
             if self.peek_type_name() || self.peek_parameter_qualifier() {
                 let qualifier = self.parse_parameter_qualifier();
                 qualifiers.push(qualifier);
-                let ty = self.parse_type_non_void()?.0;
+                let (ty, meta) = self.parse_type_non_void()?;
 
                 match self.expect_peek()?.value {
                     TokenValue::Comma => {
@@ -1843,11 +1979,12 @@ impl<'source, 'program, 'options> Parser<'source, 'program, 'options> {
                             None,
                             ty,
                             qualifier,
+                            meta,
                         );
                         continue;
                     }
                     TokenValue::Identifier(_) => {
-                        let name = self.expect_ident()?.0;
+                        let (name, end_meta) = self.expect_ident()?;
 
                         let size = self.parse_array_specifier()?;
                         let ty = self.maybe_array(ty, size);
@@ -1859,6 +1996,7 @@ impl<'source, 'program, 'options> Parser<'source, 'program, 'options> {
                             Some(name),
                             ty,
                             qualifier,
+                            meta.union(&end_meta),
                         );
 
                         if self.bump_if(TokenValue::Comma).is_some() {
@@ -1910,7 +2048,7 @@ impl<'ctx, 'fun> DeclarationContext<'ctx, 'fun> {
                     GlobalOrConstant::Global(handle) => Expression::GlobalVariable(handle),
                     GlobalOrConstant::Constant(handle) => Expression::Constant(handle),
                 };
-                Ok(self.ctx.add_expression(expr, self.body))
+                Ok(self.ctx.add_expression(expr, meta, self.body))
             }
             false => program.add_local_var(self.ctx, self.body, decl),
         }
@@ -1920,6 +2058,12 @@ impl<'ctx, 'fun> DeclarationContext<'ctx, 'fun> {
         self.ctx.emit_flush(self.body);
         self.ctx.emit_start()
     }
+
+    /// Drops the expressions emitted since the body had `body_len` statements.
+    fn discard_expressions(&mut self, body_len: usize) {
+        self.ctx.emit_discard();
+        self.body.cull(body_len..);
+    }
 }
 
 fn binding_power(value: &TokenValue) -> Option<(u8, u8)> {
@@ -691,3 +691,36 @@ fn vector_indexing() {
     )
     .unwrap();
 }
+
+#[cfg(feature = "span")]
+#[test]
+fn spans() {
+    let mut entry_points = crate::FastHashMap::default();
+    entry_points.insert("".to_string(), ShaderStage::Fragment);
+
+    let source = r#"
+        #  version 450
+        float main(int index) {
+            vec4 v = vec4(1.0, 2.0, 3.0, 4.0);
+            return v[index] + 1.0;
+        }
+        "#;
+    let program = parse_program(source, &entry_points).unwrap();
+    let (_, function) = program.module.functions.iter().next().unwrap();
+
+    let (_, &span) = function.body.span_iter().last().unwrap();
+    assert_eq!(&source[span.to_range().unwrap()], "return v[index] + 1.0;");
+
+    let spans: Vec<_> = function
+        .expressions
+        .iter()
+        .filter_map(|(handle, expr)| match *expr {
+            crate::Expression::Access { .. } | crate::Expression::Binary { .. } => {
+                function.expressions.get_span(handle).to_range()
+            }
+            _ => None,
+        })
+        .map(|range| &source[range])
+        .collect();
+    assert_eq!(spans, ["v[index]", "v[index] + 1.0"]);
+}
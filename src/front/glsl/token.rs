@@ -1,7 +1,7 @@
 pub use pp_rs::token::{Float, Integer, PreprocessorError};
 
 use super::ast::Precision;
-use crate::{Interpolation, Sampling, Type};
+use crate::{Interpolation, Sampling, Span, Type};
 use std::{fmt, ops::Range};
 
 #[derive(Debug, Clone, Copy, Default)]
@@ -29,6 +29,12 @@ impl From<SourceMetadata> for Range<usize> {
     }
 }
 
+impl From<SourceMetadata> for Span {
+    fn from(meta: SourceMetadata) -> Self {
+        Span::new(meta.start as u32, meta.end as u32)
+    }
+}
+
 #[derive(Debug)]
 #[cfg_attr(test, derive(PartialEq))]
 pub struct Token {
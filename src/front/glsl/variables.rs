@@ -49,16 +49,19 @@ impl Program<'_> {
             let ty = self
                 .module
                 .types
-                .fetch_or_append(Type { name: None, inner });
-
-            let handle = self.module.global_variables.append(GlobalVariable {
-                name: Some(name.into()),
-                class: StorageClass::Private,
-                binding: None,
-                ty,
-                init: None,
-                storage_access: StorageAccess::empty(),
-            });
+                .fetch_or_append(Type { name: None, inner }, Default::default());
+
+            let handle = self.module.global_variables.append(
+                GlobalVariable {
+                    name: Some(name.into()),
+                    class: StorageClass::Private,
+                    binding: None,
+                    ty,
+                    init: None,
+                    storage_access: StorageAccess::empty(),
+                },
+                Default::default(),
+            );
 
             let idx = self.entry_args.len();
             self.entry_args.push(EntryArg {
@@ -79,7 +82,11 @@ impl Program<'_> {
             ));
             ctx.arg_use.push(EntryArgUse::empty());
 
-            let expr = ctx.add_expression(Expression::GlobalVariable(handle), body);
+            let expr = ctx.add_expression(
+                Expression::GlobalVariable(handle),
+                SourceMetadata::default(),
+                body,
+            );
             ctx.lookup_global_var_exps.insert(
                 name.into(),
                 VariableReference {
@@ -176,6 +183,26 @@ impl Program<'_> {
                 PrologueStage::FRAGMENT,
                 StorageQualifier::Input,
             ),
+            "gl_SubgroupSize" => add_builtin(
+                TypeInner::Scalar {
+                    kind: ScalarKind::Uint,
+                    width: 4,
+                },
+                BuiltIn::SubgroupSize,
+                false,
+                PrologueStage::COMPUTE | PrologueStage::FRAGMENT,
+                StorageQualifier::Input,
+            ),
+            "gl_SubgroupInvocationID" => add_builtin(
+                TypeInner::Scalar {
+                    kind: ScalarKind::Uint,
+                    width: 4,
+                },
+                BuiltIn::SubgroupInvocationId,
+                false,
+                PrologueStage::COMPUTE | PrologueStage::FRAGMENT,
+                StorageQualifier::Input,
+            ),
             _ => Ok(None),
         }
     }
@@ -204,6 +231,7 @@ impl Program<'_> {
                         base: expression,
                         index: index as u32,
                     },
+                    meta,
                     body,
                 ))
             }
@@ -275,6 +303,7 @@ impl Program<'_> {
                                     base: expression,
                                     index: pattern[0].index(),
                                 },
+                                meta,
                                 body,
                             ));
                         }
@@ -297,6 +326,7 @@ impl Program<'_> {
                             Expression::Load {
                                 pointer: expression,
                             },
+                            meta,
                             body,
                         );
                     }
@@ -307,6 +337,7 @@ impl Program<'_> {
                             vector: expression,
                             pattern,
                         },
+                        meta,
                         body,
                     ))
                 } else {
@@ -439,14 +470,17 @@ impl Program<'_> {
                 }
             });
 
-            let handle = self.module.global_variables.append(GlobalVariable {
-                name: name.clone(),
-                class: StorageClass::Private,
-                binding: None,
-                ty,
-                init,
-                storage_access: StorageAccess::empty(),
-            });
+            let handle = self.module.global_variables.append(
+                GlobalVariable {
+                    name: name.clone(),
+                    class: StorageClass::Private,
+                    binding: None,
+                    ty,
+                    init,
+                    storage_access: StorageAccess::empty(),
+                },
+                Default::default(),
+            );
 
             let idx = self.entry_args.len();
             self.entry_args.push(EntryArg {
@@ -490,7 +524,12 @@ impl Program<'_> {
             return Ok(GlobalOrConstant::Constant(init));
         }
 
-        let (class, storage_access) = match self.module.types[ty].inner {
+        let element_ty = match self.module.types[ty].inner {
+            TypeInner::BindingArray { base, .. } => base,
+            _ => ty,
+        };
+
+        let (class, storage_access) = match self.module.types[element_ty].inner {
             TypeInner::Image { class, .. } => (
                 StorageClass::Handle,
                 if let ImageClass::Storage(_) = class {
@@ -517,14 +556,17 @@ impl Program<'_> {
             }
         };
 
-        let handle = self.module.global_variables.append(GlobalVariable {
-            name: name.clone(),
-            class,
-            binding,
-            ty,
-            init,
-            storage_access,
-        });
+        let handle = self.module.global_variables.append(
+            GlobalVariable {
+                name: name.clone(),
+                class,
+                binding,
+                ty,
+                init,
+                storage_access,
+            },
+            Default::default(),
+        );
 
         if let Some(name) = name {
             self.global_variables.push((
@@ -544,8 +586,7 @@ impl Program<'_> {
         &mut self,
         ctx: &mut Context,
         body: &mut Block,
-        #[cfg_attr(not(feature = "glsl-validate"), allow(unused_variables))]
-        VarDeclaration {
+        #[cfg_attr(not(feature = "glsl-validate"), allow(unused_variables))] VarDeclaration {
             qualifiers,
             ty,
             name,
@@ -583,12 +624,15 @@ impl Program<'_> {
             }
         }
 
-        let handle = ctx.locals.append(LocalVariable {
-            name: name.clone(),
-            ty,
-            init,
-        });
-        let expr = ctx.add_expression(Expression::LocalVariable(handle), body);
+        let handle = ctx.locals.append(
+            LocalVariable {
+                name: name.clone(),
+                ty,
+                init,
+            },
+            Default::default(),
+        );
+        let expr = ctx.add_expression(Expression::LocalVariable(handle), meta, body);
 
         if let Some(name) = name {
             ctx.add_local_var(name, expr, mutable);
@@ -0,0 +1,288 @@
+use crate::{ImageDimension as Dim, ScalarKind as Sk, VectorSize as Vs};
+
+pub fn map_scalar(word: &str) -> Option<(Sk, crate::Bytes)> {
+    Some(match word {
+        "float" | "half" | "min16float" | "min10float" | "float32_t" => (Sk::Float, 4),
+        "double" | "float64_t" => (Sk::Float, 8),
+        "int" | "min16int" | "min12int" | "int32_t" => (Sk::Sint, 4),
+        "uint" | "dword" | "min16uint" | "uint32_t" => (Sk::Uint, 4),
+        "bool" => (Sk::Bool, crate::BOOL_WIDTH),
+        _ => return None,
+    })
+}
+
+pub fn map_vector_size(size: u32) -> Option<Vs> {
+    match size {
+        2 => Some(Vs::Bi),
+        3 => Some(Vs::Tri),
+        4 => Some(Vs::Quad),
+        _ => None,
+    }
+}
+
+/// Maps the scalar, vector and matrix type names, like `float`, `uint3`
+/// or `float4x4`.
+///
+/// HLSL matrices are written as `floatRxC`, with `R` rows and `C` columns,
+/// which is a matrix with `C` columns in the IR.
+pub fn map_numeric_type(word: &str) -> Option<crate::TypeInner> {
+    if let Some((kind, width)) = map_scalar(word) {
+        return Some(crate::TypeInner::Scalar { kind, width });
+    }
+    let split = word.find(|c: char| c.is_ascii_digit())?;
+    let (kind, width) = map_scalar(&word[..split])?;
+    let dims = &word[split..];
+    let dim = |s: &str| match s.parse::<u32>() {
+        Ok(1) => Some(None),
+        Ok(size) => map_vector_size(size).map(Some),
+        Err(_) => None,
+    };
+    match dims.find('x') {
+        None => Some(match dim(dims)? {
+            None => crate::TypeInner::Scalar { kind, width },
+            Some(size) => crate::TypeInner::Vector { size, kind, width },
+        }),
+        Some(x) => match (kind, dim(&dims[..x])?, dim(&dims[x + 1..])?) {
+            (Sk::Float, Some(rows), Some(columns)) => Some(crate::TypeInner::Matrix {
+                columns,
+                rows,
+                width,
+            }),
+            _ => None,
+        },
+    }
+}
+
+/// The kind of a texture type, like `Texture2DArray` or `RWTexture3D`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextureKind {
+    pub dim: Dim,
+    pub arrayed: bool,
+    pub multi: bool,
+    pub storage: bool,
+}
+
+pub fn map_texture(word: &str) -> Option<TextureKind> {
+    //TODO: use `strip_prefix` when MSRV reaches 1.45.0
+    let (storage, word) = if word.starts_with("RW") {
+        (true, &word[2..])
+    } else {
+        (false, word)
+    };
+    let (dim, arrayed, multi) = match word {
+        "Texture1D" => (Dim::D1, false, false),
+        "Texture1DArray" => (Dim::D1, true, false),
+        "Texture2D" => (Dim::D2, false, false),
+        "Texture2DArray" => (Dim::D2, true, false),
+        "Texture2DMS" if !storage => (Dim::D2, false, true),
+        "Texture2DMSArray" if !storage => (Dim::D2, true, true),
+        "Texture3D" => (Dim::D3, false, false),
+        "TextureCube" if !storage => (Dim::Cube, false, false),
+        "TextureCubeArray" if !storage => (Dim::Cube, true, false),
+        _ => return None,
+    };
+    Some(TextureKind {
+        dim,
+        arrayed,
+        multi,
+        storage,
+    })
+}
+
+/// Maps the element type of a `RWTexture*` to a storage format.
+///
+/// HLSL doesn't specify the format of typed UAVs, so the widest format
+/// with the same components is used, unless the element is `unorm` or
+/// `snorm`, which select the 8-bit normalized formats.
+pub fn map_storage_format(
+    kind: Sk,
+    size: Option<Vs>,
+    norm: Option<Sk>,
+) -> Option<crate::StorageFormat> {
+    use crate::StorageFormat as Sf;
+
+    Some(match (kind, size, norm) {
+        (Sk::Float, None, None) => Sf::R32Float,
+        (Sk::Float, Some(Vs::Bi), None) => Sf::Rg32Float,
+        (Sk::Float, Some(Vs::Quad), None) => Sf::Rgba32Float,
+        (Sk::Float, None, Some(Sk::Uint)) => Sf::R8Unorm,
+        (Sk::Float, Some(Vs::Bi), Some(Sk::Uint)) => Sf::Rg8Unorm,
+        (Sk::Float, Some(Vs::Quad), Some(Sk::Uint)) => Sf::Rgba8Unorm,
+        (Sk::Float, None, Some(Sk::Sint)) => Sf::R8Snorm,
+        (Sk::Float, Some(Vs::Bi), Some(Sk::Sint)) => Sf::Rg8Snorm,
+        (Sk::Float, Some(Vs::Quad), Some(Sk::Sint)) => Sf::Rgba8Snorm,
+        (Sk::Uint, None, None) => Sf::R32Uint,
+        (Sk::Uint, Some(Vs::Bi), None) => Sf::Rg32Uint,
+        (Sk::Uint, Some(Vs::Quad), None) => Sf::Rgba32Uint,
+        (Sk::Sint, None, None) => Sf::R32Sint,
+        (Sk::Sint, Some(Vs::Bi), None) => Sf::Rg32Sint,
+        (Sk::Sint, Some(Vs::Quad), None) => Sf::Rgba32Sint,
+        _ => return None,
+    })
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Semantic {
+    BuiltIn(crate::BuiltIn),
+    /// `SV_Target` with its index.
+    Target(u32),
+    /// A user defined semantic, with the index made explicit.
+    User(String),
+}
+
+/// Maps a semantic, returning `None` for unknown system values.
+pub fn map_semantic(name: &str) -> Option<Semantic> {
+    use crate::BuiltIn as Bi;
+
+    let split = name.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    let (base, index) = name.split_at(split);
+    let lower = base.to_ascii_lowercase();
+    if !lower.starts_with("sv_") {
+        let index = if index.is_empty() { "0" } else { index };
+        return Some(Semantic::User(format!(
+            "{}{}",
+            base.to_ascii_uppercase(),
+            index
+        )));
+    }
+    Some(Semantic::BuiltIn(match lower.as_str() {
+        "sv_target" => return Some(Semantic::Target(index.parse().unwrap_or(0))),
+        "sv_position" => Bi::Position,
+        "sv_vertexid" => Bi::VertexIndex,
+        "sv_instanceid" => Bi::InstanceIndex,
+        "sv_clipdistance" => Bi::ClipDistance,
+        "sv_culldistance" => Bi::CullDistance,
+        "sv_depth" | "sv_depthgreaterequal" | "sv_depthlessequal" => Bi::FragDepth,
+        "sv_isfrontface" => Bi::FrontFacing,
+        "sv_primitiveid" => Bi::PrimitiveIndex,
+        "sv_sampleindex" => Bi::SampleIndex,
+        "sv_coverage" => Bi::SampleMask,
+        "sv_dispatchthreadid" => Bi::GlobalInvocationId,
+        "sv_groupthreadid" => Bi::LocalInvocationId,
+        "sv_groupindex" => Bi::LocalInvocationIndex,
+        "sv_groupid" => Bi::WorkGroupId,
+        _ => return None,
+    }))
+}
+
+pub fn map_shader_stage(word: &str) -> Option<crate::ShaderStage> {
+    match word {
+        "vertex" => Some(crate::ShaderStage::Vertex),
+        "pixel" => Some(crate::ShaderStage::Fragment),
+        "compute" => Some(crate::ShaderStage::Compute),
+        _ => None,
+    }
+}
+
+/// How the arguments of an intrinsic are converted before the call.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArgumentConversion {
+    /// Convert all the arguments to a common type.
+    Common,
+    /// Convert all the arguments to a common floating-point type.
+    Float,
+    /// Convert the first arguments to a common floating-point type,
+    /// and the last one to the matching scalar.
+    FloatLastScalar,
+    /// Leave the arguments as they are.
+    None,
+}
+
+/// Maps the intrinsics matching a `MathFunction`.
+pub fn map_math_function(name: &str) -> Option<(crate::MathFunction, usize, ArgumentConversion)> {
+    use crate::MathFunction as Mf;
+    use ArgumentConversion as Ac;
+
+    Some(match name {
+        "abs" => (Mf::Abs, 1, Ac::Common),
+        "min" => (Mf::Min, 2, Ac::Common),
+        "max" => (Mf::Max, 2, Ac::Common),
+        "clamp" => (Mf::Clamp, 3, Ac::Common),
+        "cos" => (Mf::Cos, 1, Ac::Float),
+        "cosh" => (Mf::Cosh, 1, Ac::Float),
+        "sin" => (Mf::Sin, 1, Ac::Float),
+        "sinh" => (Mf::Sinh, 1, Ac::Float),
+        "tan" => (Mf::Tan, 1, Ac::Float),
+        "tanh" => (Mf::Tanh, 1, Ac::Float),
+        "acos" => (Mf::Acos, 1, Ac::Float),
+        "asin" => (Mf::Asin, 1, Ac::Float),
+        "atan" => (Mf::Atan, 1, Ac::Float),
+        "atan2" => (Mf::Atan2, 2, Ac::Float),
+        "ceil" => (Mf::Ceil, 1, Ac::Float),
+        "floor" => (Mf::Floor, 1, Ac::Float),
+        "round" => (Mf::Round, 1, Ac::Float),
+        "frac" => (Mf::Fract, 1, Ac::Float),
+        "trunc" => (Mf::Trunc, 1, Ac::Float),
+        "exp" => (Mf::Exp, 1, Ac::Float),
+        "exp2" => (Mf::Exp2, 1, Ac::Float),
+        "log" => (Mf::Log, 1, Ac::Float),
+        "log2" => (Mf::Log2, 1, Ac::Float),
+        "pow" => (Mf::Pow, 2, Ac::Float),
+        "dot" => (Mf::Dot, 2, Ac::Float),
+        "cross" => (Mf::Cross, 2, Ac::Float),
+        "distance" => (Mf::Distance, 2, Ac::Float),
+        "length" => (Mf::Length, 1, Ac::Float),
+        "normalize" => (Mf::Normalize, 1, Ac::Float),
+        "faceforward" => (Mf::FaceForward, 3, Ac::Float),
+        "reflect" => (Mf::Reflect, 2, Ac::Float),
+        "refract" => (Mf::Refract, 3, Ac::FloatLastScalar),
+        "sign" => (Mf::Sign, 1, Ac::Common),
+        "mad" => (Mf::Fma, 3, Ac::Float),
+        "lerp" => (Mf::Mix, 3, Ac::Float),
+        "step" => (Mf::Step, 2, Ac::Float),
+        "smoothstep" => (Mf::SmoothStep, 3, Ac::Float),
+        "sqrt" => (Mf::Sqrt, 1, Ac::Float),
+        "rsqrt" => (Mf::InverseSqrt, 1, Ac::Float),
+        "transpose" => (Mf::Transpose, 1, Ac::None),
+        "determinant" => (Mf::Determinant, 1, Ac::None),
+        "countbits" => (Mf::CountOneBits, 1, Ac::None),
+        "reversebits" => (Mf::ReverseBits, 1, Ac::None),
+        "firstbitlow" => (Mf::FirstTrailingBit, 1, Ac::None),
+        "firstbithigh" => (Mf::FirstLeadingBit, 1, Ac::None),
+        _ => return None,
+    })
+}
+
+#[test]
+fn numeric_types() {
+    assert_eq!(
+        map_numeric_type("float3x2"),
+        Some(crate::TypeInner::Matrix {
+            columns: Vs::Bi,
+            rows: Vs::Tri,
+            width: 4,
+        })
+    );
+    assert_eq!(
+        map_numeric_type("uint4"),
+        Some(crate::TypeInner::Vector {
+            size: Vs::Quad,
+            kind: Sk::Uint,
+            width: 4,
+        })
+    );
+    assert_eq!(
+        map_numeric_type("half1"),
+        Some(crate::TypeInner::Scalar {
+            kind: Sk::Float,
+            width: 4,
+        })
+    );
+    assert_eq!(map_numeric_type("int2x2"), None);
+    assert_eq!(map_numeric_type("float5"), None);
+    assert_eq!(map_numeric_type("floaty"), None);
+}
+
+#[test]
+fn semantics() {
+    assert_eq!(
+        map_semantic("SV_Position"),
+        Some(Semantic::BuiltIn(crate::BuiltIn::Position))
+    );
+    assert_eq!(map_semantic("SV_Target2"), Some(Semantic::Target(2)));
+    assert_eq!(
+        map_semantic("TexCoord"),
+        Some(Semantic::User("TEXCOORD0".to_string()))
+    );
+    assert_eq!(map_semantic("SV_Barycentrics"), None);
+}
@@ -0,0 +1,3677 @@
+//! Parsing of function bodies: statements, expressions and intrinsics.
+
+use super::{
+    conv,
+    lexer::{Lexer, Token},
+    type_name, Error, FunctionDecl, GlobalLookup, Parameter, ParameterMode, Parser, Span,
+};
+use crate::{
+    arena::{Arena, Handle},
+    front::{Emitter, Typifier},
+    proc::{ResolveContext, TypeResolution},
+    BinaryOperator as Bo, Block, Bytes, Expression, FastHashMap, FastHashSet, ScalarKind as Sk,
+    Statement, TypeInner as Ti, VectorSize as Vs,
+};
+
+const SWIZZLE_IDENTITY: [crate::SwizzleComponent; 4] = [
+    crate::SwizzleComponent::X,
+    crate::SwizzleComponent::Y,
+    crate::SwizzleComponent::Z,
+    crate::SwizzleComponent::W,
+];
+
+const ASSIGNMENT_OPERATORS: &[&str] = &[
+    "=", "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=", "<<=", ">>=",
+];
+
+/// Precedence of the shift operators, the loosest binding ones allowed in
+/// template arguments.
+pub(super) const SHIFT_PRECEDENCE: u8 = 8;
+
+/// Binary operators with their precedence, higher binding tighter.
+fn binary_operator(punct: &str) -> Option<(Bo, u8)> {
+    Some(match punct {
+        "||" => (Bo::LogicalOr, 1),
+        "&&" => (Bo::LogicalAnd, 2),
+        "|" => (Bo::InclusiveOr, 3),
+        "^" => (Bo::ExclusiveOr, 4),
+        "&" => (Bo::And, 5),
+        "==" => (Bo::Equal, 6),
+        "!=" => (Bo::NotEqual, 6),
+        "<" => (Bo::Less, 7),
+        "<=" => (Bo::LessEqual, 7),
+        ">" => (Bo::Greater, 7),
+        ">=" => (Bo::GreaterEqual, 7),
+        "<<" => (Bo::ShiftLeft, SHIFT_PRECEDENCE),
+        ">>" => (Bo::ShiftRight, SHIFT_PRECEDENCE),
+        "+" => (Bo::Add, 9),
+        "-" => (Bo::Subtract, 9),
+        "*" => (Bo::Multiply, 10),
+        "/" => (Bo::Divide, 10),
+        "%" => (Bo::Modulo, 10),
+        _ => return None,
+    })
+}
+
+fn compound_operator(punct: &str) -> Option<Bo> {
+    Some(match punct {
+        "+=" => Bo::Add,
+        "-=" => Bo::Subtract,
+        "*=" => Bo::Multiply,
+        "/=" => Bo::Divide,
+        "%=" => Bo::Modulo,
+        "&=" => Bo::And,
+        "|=" => Bo::InclusiveOr,
+        "^=" => Bo::ExclusiveOr,
+        "<<=" => Bo::ShiftLeft,
+        ">>=" => Bo::ShiftRight,
+        _ => return None,
+    })
+}
+
+fn parse_number<'a>(text: &str, span: &Span) -> Result<crate::ConstantInner, Error<'a>> {
+    use crate::ScalarValue as Sv;
+
+    let bad_number = || Error::BadNumber(span.clone());
+    let lower = text.to_ascii_lowercase();
+    let (value, width) = if lower.starts_with("0x") {
+        let digits = lower[2..].trim_end_matches(&['u', 'l'][..]);
+        let value = u64::from_str_radix(digits, 16).map_err(|_| bad_number())?;
+        if lower[2 + digits.len()..].contains('u') {
+            (Sv::Uint(value), 4)
+        } else {
+            (Sv::Sint(value as i64), 4)
+        }
+    } else if lower.contains('.')
+        || lower.contains('e')
+        || lower.ends_with('f')
+        || lower.ends_with('h')
+    {
+        let (digits, width) = if lower.ends_with("lf") {
+            (&lower[..lower.len() - 2], 8)
+        } else if lower.ends_with('l') {
+            (&lower[..lower.len() - 1], 8)
+        } else if lower.ends_with('f') || lower.ends_with('h') {
+            (&lower[..lower.len() - 1], 4)
+        } else {
+            (&lower[..], 4)
+        };
+        let value = digits.parse::<f64>().map_err(|_| bad_number())?;
+        (Sv::Float(value), width)
+    } else {
+        let digits = lower.trim_end_matches(&['u', 'l'][..]);
+        let value = digits.parse::<u64>().map_err(|_| bad_number())?;
+        if lower[digits.len()..].contains('u') {
+            (Sv::Uint(value), 4)
+        } else {
+            (Sv::Sint(value as i64), 4)
+        }
+    };
+    Ok(crate::ConstantInner::Scalar { width, value })
+}
+
+fn scalar_value(kind: Sk, value: f64) -> crate::ScalarValue {
+    match kind {
+        Sk::Sint => crate::ScalarValue::Sint(value as i64),
+        Sk::Uint => crate::ScalarValue::Uint(value as u64),
+        Sk::Float => crate::ScalarValue::Float(value),
+        Sk::Bool => crate::ScalarValue::Bool(value != 0.0),
+    }
+}
+
+fn convert_scalar_value(value: crate::ScalarValue, kind: Sk) -> crate::ScalarValue {
+    use crate::ScalarValue as Sv;
+
+    let float = match value {
+        Sv::Sint(v) => v as f64,
+        Sv::Uint(v) => v as f64,
+        Sv::Float(v) => v,
+        Sv::Bool(v) => v as u8 as f64,
+    };
+    match (kind, value) {
+        (Sk::Sint, Sv::Uint(v)) => Sv::Sint(v as i64),
+        (Sk::Uint, Sv::Sint(v)) => Sv::Uint(v as u64),
+        (Sk::Sint, Sv::Sint(_)) | (Sk::Uint, Sv::Uint(_)) => value,
+        _ => scalar_value(kind, float),
+    }
+}
+
+fn kind_rank(kind: Sk) -> u8 {
+    match kind {
+        Sk::Bool => 0,
+        Sk::Sint => 1,
+        Sk::Uint => 2,
+        Sk::Float => 3,
+    }
+}
+
+fn argument_count<'a>(span: &Span, expected: usize, found: usize) -> Result<(), Error<'a>> {
+    if expected == found {
+        Ok(())
+    } else {
+        Err(Error::BadArgumentCount {
+            span: span.clone(),
+            expected,
+            found,
+        })
+    }
+}
+
+/// The shape of a value, seen through pointers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Shape {
+    Scalar { kind: Sk, width: Bytes },
+    Vector { size: Vs, kind: Sk, width: Bytes },
+    Matrix { columns: Vs, rows: Vs, width: Bytes },
+    Other,
+}
+
+impl Shape {
+    fn new(inner: &Ti, types: &Arena<crate::Type>) -> Self {
+        match *inner {
+            Ti::Scalar { kind, width }
+            | Ti::ValuePointer {
+                size: None,
+                kind,
+                width,
+                ..
+            } => Shape::Scalar { kind, width },
+            Ti::Vector { size, kind, width }
+            | Ti::ValuePointer {
+                size: Some(size),
+                kind,
+                width,
+                ..
+            } => Shape::Vector { size, kind, width },
+            Ti::Matrix {
+                columns,
+                rows,
+                width,
+            } => Shape::Matrix {
+                columns,
+                rows,
+                width,
+            },
+            Ti::Pointer { base, .. } => Shape::new(&types[base].inner, types),
+            _ => Shape::Other,
+        }
+    }
+
+    fn from_parts(size: Option<Vs>, kind: Sk, width: Bytes) -> Self {
+        match size {
+            Some(size) => Shape::Vector { size, kind, width },
+            None => Shape::Scalar { kind, width },
+        }
+    }
+
+    fn scalar(self) -> Option<(Sk, Bytes)> {
+        match self {
+            Shape::Scalar { kind, width } | Shape::Vector { kind, width, .. } => {
+                Some((kind, width))
+            }
+            Shape::Matrix { width, .. } => Some((Sk::Float, width)),
+            Shape::Other => None,
+        }
+    }
+
+    fn size(self) -> Option<Vs> {
+        match self {
+            Shape::Vector { size, .. } => Some(size),
+            _ => None,
+        }
+    }
+
+    fn is_numeric(self) -> bool {
+        match self {
+            Shape::Scalar { .. } | Shape::Vector { .. } => true,
+            Shape::Matrix { .. } | Shape::Other => false,
+        }
+    }
+
+    fn with_scalar(self, kind: Sk, width: Bytes) -> Self {
+        match self {
+            Shape::Scalar { .. } => Shape::Scalar { kind, width },
+            Shape::Vector { size, .. } => Shape::Vector { size, kind, width },
+            other => other,
+        }
+    }
+
+    /// The floating-point shape with the same dimensions.
+    fn float(self) -> Self {
+        match self.scalar() {
+            Some((Sk::Float, width)) => self.with_scalar(Sk::Float, width),
+            _ => self.with_scalar(Sk::Float, 4),
+        }
+    }
+
+    fn inner(self) -> Option<Ti> {
+        match self {
+            Shape::Scalar { kind, width } => Some(Ti::Scalar { kind, width }),
+            Shape::Vector { size, kind, width } => Some(Ti::Vector { size, kind, width }),
+            Shape::Matrix {
+                columns,
+                rows,
+                width,
+            } => Some(Ti::Matrix {
+                columns,
+                rows,
+                width,
+            }),
+            Shape::Other => None,
+        }
+    }
+
+    /// The shape both operands of a component-wise operation are converted to.
+    ///
+    /// Vectors are truncated to the smaller size, and the scalar kinds are
+    /// promoted from `bool` to `int`, `uint` and `float`.
+    fn common(self, other: Self) -> Option<Self> {
+        if !self.is_numeric() || !other.is_numeric() {
+            return if self == other && self != Shape::Other {
+                Some(self)
+            } else {
+                None
+            };
+        }
+        let (kind, width) = self.scalar()?;
+        let (other_kind, other_width) = other.scalar()?;
+        let kind = if kind_rank(kind) >= kind_rank(other_kind) {
+            kind
+        } else {
+            other_kind
+        };
+        let size = match (self.size(), other.size()) {
+            (Some(size), Some(other_size)) => Some(if size as u8 <= other_size as u8 {
+                size
+            } else {
+                other_size
+            }),
+            (Some(size), None) | (None, Some(size)) => Some(size),
+            (None, None) => None,
+        };
+        Some(Shape::from_parts(size, kind, width.max(other_width)))
+    }
+}
+
+/// What a `return` statement produces.
+#[derive(Clone, Debug)]
+pub(super) enum ReturnKind {
+    /// A value of the given type, if any.
+    Value(Option<Handle<crate::Type>>),
+    /// An entry point output structure, with the returned value as the first
+    /// member if `has_result` is set, followed by the `out` parameters.
+    EntryPointOutputs {
+        ty: Handle<crate::Type>,
+        has_result: bool,
+        outputs: Vec<Handle<Expression>>,
+    },
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(super) struct TypedExpression {
+    handle: Handle<Expression>,
+    /// Whether the expression is a pointer to the value.
+    is_reference: bool,
+}
+
+impl TypedExpression {
+    fn value(handle: Handle<Expression>) -> Self {
+        TypedExpression {
+            handle,
+            is_reference: false,
+        }
+    }
+
+    fn reference(handle: Handle<Expression>) -> Self {
+        TypedExpression {
+            handle,
+            is_reference: true,
+        }
+    }
+}
+
+/// An assignable value that isn't a pointer in the IR.
+#[derive(Clone, Debug)]
+enum LValue {
+    /// Components of a vector behind `pointer`.
+    Swizzle {
+        pointer: Handle<Expression>,
+        components: Vec<u32>,
+    },
+    /// The elements in `columns` of a row of a matrix behind `pointer`.
+    MatrixRow {
+        pointer: Handle<Expression>,
+        row: Handle<Expression>,
+        columns: Vec<u32>,
+    },
+    /// A texel of a storage texture.
+    Texel {
+        image: Handle<Expression>,
+        coordinate: Handle<Expression>,
+        array_index: Option<Handle<Expression>>,
+    },
+}
+
+/// Index of a matrix column, known or computed.
+#[derive(Clone, Copy, Debug)]
+enum ColumnIndex {
+    Constant(u32),
+    Dynamic(Handle<Expression>),
+}
+
+/// A texture coordinate split into the coordinate itself, the array layer,
+/// and the extra trailing components.
+type SplitCoordinate = (
+    Handle<Expression>,
+    Option<Handle<Expression>>,
+    Vec<Handle<Expression>>,
+);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Breakable {
+    Loop,
+    DoWhile,
+    Switch,
+}
+
+pub(super) struct FunctionContext {
+    pub function: crate::Function,
+    typifier: Typifier,
+    emitter: Emitter,
+    scopes: Vec<FastHashMap<String, TypedExpression>>,
+    lvalues: FastHashMap<Handle<Expression>, LValue>,
+    return_kind: ReturnKind,
+    breakables: Vec<Breakable>,
+}
+
+impl FunctionContext {
+    pub fn new(function: crate::Function, return_kind: ReturnKind) -> Self {
+        FunctionContext {
+            function,
+            typifier: Typifier::new(),
+            emitter: Emitter::default(),
+            scopes: vec![FastHashMap::default()],
+            lvalues: FastHashMap::default(),
+            return_kind,
+            breakables: Vec::new(),
+        }
+    }
+
+    pub fn start_emit(&mut self) {
+        self.emitter.start(&self.function.expressions);
+    }
+
+    /// Emit the pending expressions into `block`, and keep emitting.
+    fn flush(&mut self, block: &mut Block) {
+        block.extend(self.emitter.finish(&self.function.expressions));
+        self.emitter.start(&self.function.expressions);
+    }
+
+    fn lookup(&self, name: &str) -> Option<TypedExpression> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name).cloned())
+    }
+
+    fn in_loop(&self) -> bool {
+        self.breakables
+            .iter()
+            .any(|&breakable| breakable != Breakable::Switch)
+    }
+}
+
+impl<'o> Parser<'o> {
+    fn grow<'a>(
+        &self,
+        ctx: &mut FunctionContext,
+        handle: Handle<Expression>,
+        span: &Span,
+    ) -> Result<(), Error<'a>> {
+        let resolve_ctx = ResolveContext {
+            constants: &self.module.constants,
+            types: &self.module.types,
+            global_vars: &self.module.global_variables,
+            local_vars: &ctx.function.local_variables,
+            functions: &self.module.functions,
+            arguments: &ctx.function.arguments,
+        };
+        ctx.typifier
+            .grow(handle, &ctx.function.expressions, &resolve_ctx)
+            .map_err(|error| Error::InvalidResolve(span.clone(), error))
+    }
+
+    fn append<'a>(
+        &self,
+        ctx: &mut FunctionContext,
+        expression: Expression,
+        span: &Span,
+    ) -> Result<Handle<Expression>, Error<'a>> {
+        let handle = ctx
+            .function
+            .expressions
+            .append(expression, span.clone().into());
+        self.grow(ctx, handle, span)?;
+        Ok(handle)
+    }
+
+    /// Append an expression that must not be covered by `Statement::Emit`.
+    fn append_unemitted<'a>(
+        &self,
+        ctx: &mut FunctionContext,
+        block: &mut Block,
+        expression: Expression,
+        span: &Span,
+    ) -> Result<Handle<Expression>, Error<'a>> {
+        block.extend(ctx.emitter.finish(&ctx.function.expressions));
+        let handle = ctx
+            .function
+            .expressions
+            .append(expression, span.clone().into());
+        ctx.emitter.start(&ctx.function.expressions);
+        self.grow(ctx, handle, span)?;
+        Ok(handle)
+    }
+
+    fn push_statement(
+        &self,
+        ctx: &mut FunctionContext,
+        block: &mut Block,
+        statement: Statement,
+        span: &Span,
+    ) {
+        block.extend(ctx.emitter.finish(&ctx.function.expressions));
+        block.push(statement, span.clone().into());
+        ctx.emitter.start(&ctx.function.expressions);
+    }
+
+    fn shape(&self, ctx: &FunctionContext, handle: Handle<Expression>) -> Shape {
+        Shape::new(
+            ctx.typifier.get(handle, &self.module.types),
+            &self.module.types,
+        )
+    }
+
+    pub(super) fn apply_load<'a>(
+        &self,
+        ctx: &mut FunctionContext,
+        expression: TypedExpression,
+        span: &Span,
+    ) -> Result<Handle<Expression>, Error<'a>> {
+        if expression.is_reference {
+            self.append(
+                ctx,
+                Expression::Load {
+                    pointer: expression.handle,
+                },
+                span,
+            )
+        } else {
+            Ok(expression.handle)
+        }
+    }
+
+    /// The type of the value of `expression`, behind the pointer for
+    /// references.
+    fn value_type(
+        &mut self,
+        ctx: &FunctionContext,
+        expression: TypedExpression,
+    ) -> Handle<crate::Type> {
+        let ty = match ctx.typifier[expression.handle] {
+            TypeResolution::Handle(ty) => match self.module.types[ty].inner {
+                Ti::Pointer { base, .. } => Some(base),
+                _ => Some(ty),
+            },
+            TypeResolution::Value(Ti::Pointer { base, .. }) => Some(base),
+            TypeResolution::Value(_) => None,
+        };
+        match ty {
+            Some(ty) => ty,
+            None => {
+                let inner = self
+                    .shape(ctx, expression.handle)
+                    .inner()
+                    .expect("unexpected type resolution");
+                self.add_type(inner)
+            }
+        }
+    }
+
+    fn add_constant(&mut self, inner: crate::ConstantInner) -> Handle<crate::Constant> {
+        self.module.constants.fetch_or_append(
+            crate::Constant {
+                name: None,
+                specialization: None,
+                inner,
+            },
+            Default::default(),
+        )
+    }
+
+    /// Append a constant with the given `shape`, splatted for vectors.
+    fn constant_expression<'a>(
+        &mut self,
+        ctx: &mut FunctionContext,
+        block: &mut Block,
+        shape: Shape,
+        value: f64,
+        span: &Span,
+    ) -> Result<Handle<Expression>, Error<'a>> {
+        let (kind, width) = shape
+            .scalar()
+            .ok_or_else(|| Error::BadOperands(span.clone()))?;
+        let constant = self.add_constant(crate::ConstantInner::Scalar {
+            width,
+            value: scalar_value(kind, value),
+        });
+        let scalar = self.append_unemitted(ctx, block, Expression::Constant(constant), span)?;
+        match shape {
+            Shape::Vector { size, .. } => self.append(
+                ctx,
+                Expression::Splat {
+                    size,
+                    value: scalar,
+                },
+                span,
+            ),
+            _ => Ok(scalar),
+        }
+    }
+
+    /// A constant of type `ty` with all the components set to zero.
+    fn zero_constant(&mut self, ty: Handle<crate::Type>) -> Option<Handle<crate::Constant>> {
+        let (component_ty, count) = match self.module.types[ty].inner {
+            Ti::Scalar { kind, width } => {
+                return Some(self.add_constant(crate::ConstantInner::Scalar {
+                    width,
+                    value: scalar_value(kind, 0.0),
+                }))
+            }
+            Ti::Vector { size, kind, width } => {
+                let scalar = self.add_type(Ti::Scalar { kind, width });
+                (scalar, size as usize)
+            }
+            Ti::Matrix {
+                columns,
+                rows,
+                width,
+            } => {
+                let column = self.add_type(Ti::Vector {
+                    size: rows,
+                    kind: Sk::Float,
+                    width,
+                });
+                (column, columns as usize)
+            }
+            Ti::Array {
+                base,
+                size: crate::ArraySize::Constant(size),
+                ..
+            } => (base, self.constant_u32(size)? as usize),
+            Ti::Struct { ref members, .. } => {
+                let member_types = members.iter().map(|member| member.ty).collect::<Vec<_>>();
+                let components = member_types
+                    .into_iter()
+                    .map(|member_ty| self.zero_constant(member_ty))
+                    .collect::<Option<Vec<_>>>()?;
+                return Some(self.add_constant(crate::ConstantInner::Composite { ty, components }));
+            }
+            _ => return None,
+        };
+        let component = self.zero_constant(component_ty)?;
+        Some(self.add_constant(crate::ConstantInner::Composite {
+            ty,
+            components: vec![component; count],
+        }))
+    }
+
+    fn constant_u32(&self, constant: Handle<crate::Constant>) -> Option<u32> {
+        match self.module.constants[constant].inner {
+            crate::ConstantInner::Scalar {
+                value: crate::ScalarValue::Sint(value),
+                ..
+            } if value >= 0 => Some(value as u32),
+            crate::ConstantInner::Scalar {
+                value: crate::ScalarValue::Uint(value),
+                ..
+            } => Some(value as u32),
+            _ => None,
+        }
+    }
+
+    fn constant_index(&self, ctx: &FunctionContext, index: Handle<Expression>) -> Option<u32> {
+        match ctx.function.expressions[index] {
+            Expression::Constant(constant) => self.constant_u32(constant),
+            _ => None,
+        }
+    }
+
+    pub(super) fn solve_constant<'a>(
+        &mut self,
+        ctx: &mut FunctionContext,
+        expression: Handle<Expression>,
+        span: Span,
+    ) -> Result<Handle<crate::Constant>, Error<'a>> {
+        let mut solver = crate::proc::ConstantSolver {
+            types: &mut self.module.types,
+            expressions: &ctx.function.expressions,
+            constants: &mut self.module.constants,
+        };
+        solver
+            .solve(expression)
+            .map_err(|_| Error::NotConstant(span))
+    }
+
+    fn conversion_error<'a>(
+        &self,
+        ctx: &FunctionContext,
+        value: Handle<Expression>,
+        to: String,
+        span: &Span,
+    ) -> Error<'a> {
+        let types = &self.module.types;
+        Error::BadConversion {
+            span: span.clone(),
+            from: type_name(ctx.typifier.get(value, types), types),
+            to,
+        }
+    }
+
+    /// Convert the scalar kind of a scalar or vector `value`.
+    fn convert_kind<'a>(
+        &mut self,
+        ctx: &mut FunctionContext,
+        block: &mut Block,
+        value: Handle<Expression>,
+        from: Shape,
+        to: Shape,
+        span: &Span,
+    ) -> Result<Handle<Expression>, Error<'a>> {
+        let (from_kind, from_width) = from.scalar().unwrap();
+        let (kind, width) = to.scalar().unwrap();
+        if (from_kind, from_width) == (kind, width) {
+            return Ok(value);
+        }
+        if let Expression::Constant(constant) = ctx.function.expressions[value] {
+            if let crate::ConstantInner::Scalar { value: scalar, .. } =
+                self.module.constants[constant].inner
+            {
+                let constant = self.add_constant(crate::ConstantInner::Scalar {
+                    width,
+                    value: convert_scalar_value(scalar, kind),
+                });
+                return self.append_unemitted(ctx, block, Expression::Constant(constant), span);
+            }
+        }
+        let expression = if kind == Sk::Bool {
+            let zero = self.constant_expression(ctx, block, from, 0.0, span)?;
+            Expression::Binary {
+                op: Bo::NotEqual,
+                left: value,
+                right: zero,
+            }
+        } else if from_kind == Sk::Bool {
+            let accept = self.constant_expression(ctx, block, to, 1.0, span)?;
+            let reject = self.constant_expression(ctx, block, to, 0.0, span)?;
+            Expression::Select {
+                condition: value,
+                accept,
+                reject,
+            }
+        } else {
+            Expression::As {
+                expr: value,
+                kind,
+                convert: Some(width),
+            }
+        };
+        self.append(ctx, expression, span)
+    }
+
+    /// Implicitly convert a numeric `value` to `to`, following the HLSL rules.
+    fn convert<'a>(
+        &mut self,
+        ctx: &mut FunctionContext,
+        block: &mut Block,
+        value: Handle<Expression>,
+        to: Shape,
+        span: &Span,
+    ) -> Result<Handle<Expression>, Error<'a>> {
+        let from = self.shape(ctx, value);
+        if from == to && from != Shape::Other {
+            return Ok(value);
+        }
+        match (from, to) {
+            (Shape::Scalar { .. }, Shape::Scalar { .. }) => {
+                self.convert_kind(ctx, block, value, from, to, span)
+            }
+            (Shape::Vector { size, .. }, Shape::Vector { size: to_size, .. })
+                if to_size as u8 <= size as u8 =>
+            {
+                let vector = if to_size != size {
+                    self.append(
+                        ctx,
+                        Expression::Swizzle {
+                            size: to_size,
+                            vector: value,
+                            pattern: SWIZZLE_IDENTITY,
+                        },
+                        span,
+                    )?
+                } else {
+                    value
+                };
+                let from = self.shape(ctx, vector);
+                self.convert_kind(ctx, block, vector, from, to, span)
+            }
+            (Shape::Scalar { .. }, Shape::Vector { size, kind, width }) => {
+                let scalar = self.convert_kind(
+                    ctx,
+                    block,
+                    value,
+                    from,
+                    Shape::Scalar { kind, width },
+                    span,
+                )?;
+                self.append(
+                    ctx,
+                    Expression::Splat {
+                        size,
+                        value: scalar,
+                    },
+                    span,
+                )
+            }
+            (Shape::Vector { .. }, Shape::Scalar { .. }) => {
+                let first = self.append(
+                    ctx,
+                    Expression::AccessIndex {
+                        base: value,
+                        index: 0,
+                    },
+                    span,
+                )?;
+                self.convert(ctx, block, first, to, span)
+            }
+            (
+                Shape::Matrix {
+                    columns,
+                    rows,
+                    width,
+                },
+                Shape::Matrix {
+                    columns: to_columns,
+                    rows: to_rows,
+                    width: to_width,
+                },
+            ) if width == to_width
+                && to_columns as u8 <= columns as u8
+                && to_rows as u8 <= rows as u8 =>
+            {
+                let ty = self.add_type(to.inner().unwrap());
+                let mut components = Vec::with_capacity(to_columns as usize);
+                for index in 0..to_columns as u32 {
+                    let column =
+                        self.append(ctx, Expression::AccessIndex { base: value, index }, span)?;
+                    let column = if to_rows != rows {
+                        self.append(
+                            ctx,
+                            Expression::Swizzle {
+                                size: to_rows,
+                                vector: column,
+                                pattern: SWIZZLE_IDENTITY,
+                            },
+                            span,
+                        )?
+                    } else {
+                        column
+                    };
+                    components.push(column);
+                }
+                self.append(ctx, Expression::Compose { ty, components }, span)
+            }
+            _ => {
+                let to = match to.inner() {
+                    Some(ref inner) => type_name(inner, &self.module.types),
+                    None => "?".to_string(),
+                };
+                Err(self.conversion_error(ctx, value, to, span))
+            }
+        }
+    }
+
+    /// Convert `value` to the type `ty`.
+    fn convert_to_type<'a>(
+        &mut self,
+        ctx: &mut FunctionContext,
+        block: &mut Block,
+        value: Handle<Expression>,
+        ty: Handle<crate::Type>,
+        span: &Span,
+    ) -> Result<Handle<Expression>, Error<'a>> {
+        match Shape::new(&self.module.types[ty].inner, &self.module.types) {
+            Shape::Other => {
+                if self.value_type(ctx, TypedExpression::value(value)) == ty {
+                    Ok(value)
+                } else {
+                    let to = type_name(&self.module.types[ty].inner, &self.module.types);
+                    Err(self.conversion_error(ctx, value, to, span))
+                }
+            }
+            shape => self.convert(ctx, block, value, shape, span),
+        }
+    }
+
+    fn binary<'a>(
+        &mut self,
+        ctx: &mut FunctionContext,
+        block: &mut Block,
+        op: Bo,
+        left: Handle<Expression>,
+        right: Handle<Expression>,
+        span: &Span,
+    ) -> Result<Handle<Expression>, Error<'a>> {
+        let bad_operands = || Error::BadOperands(span.clone());
+        let left_shape = self.shape(ctx, left);
+        let right_shape = self.shape(ctx, right);
+        let (left, right) = match op {
+            Bo::LogicalAnd | Bo::LogicalOr => {
+                let shape = left_shape
+                    .common(right_shape)
+                    .ok_or_else(bad_operands)?
+                    .with_scalar(Sk::Bool, crate::BOOL_WIDTH);
+                (
+                    self.convert(ctx, block, left, shape, span)?,
+                    self.convert(ctx, block, right, shape, span)?,
+                )
+            }
+            Bo::ShiftLeft | Bo::ShiftRight => {
+                let width = match left_shape.scalar() {
+                    Some((Sk::Sint, width)) | Some((Sk::Uint, width))
+                        if left_shape.is_numeric() =>
+                    {
+                        width
+                    }
+                    _ => return Err(bad_operands()),
+                };
+                let shape = left_shape.with_scalar(Sk::Uint, width);
+                (left, self.convert(ctx, block, right, shape, span)?)
+            }
+            Bo::Multiply if !left_shape.is_numeric() || !right_shape.is_numeric() => {
+                match (left_shape, right_shape) {
+                    (Shape::Matrix { width, .. }, Shape::Scalar { .. }) => {
+                        let scalar = Shape::Scalar {
+                            kind: Sk::Float,
+                            width,
+                        };
+                        (left, self.convert(ctx, block, right, scalar, span)?)
+                    }
+                    (Shape::Scalar { .. }, Shape::Matrix { width, .. }) => {
+                        let scalar = Shape::Scalar {
+                            kind: Sk::Float,
+                            width,
+                        };
+                        (right, self.convert(ctx, block, left, scalar, span)?)
+                    }
+                    (Shape::Matrix { .. }, Shape::Matrix { .. }) => {
+                        return Err(Error::Unsupported(
+                            span.clone(),
+                            "component-wise matrix products",
+                        ))
+                    }
+                    _ => return Err(bad_operands()),
+                }
+            }
+            _ if !left_shape.is_numeric() || !right_shape.is_numeric() => match op {
+                Bo::Add | Bo::Subtract
+                    if left_shape == right_shape && left_shape != Shape::Other =>
+                {
+                    (left, right)
+                }
+                _ => return Err(bad_operands()),
+            },
+            _ => {
+                let mut shape = left_shape.common(right_shape).ok_or_else(bad_operands)?;
+                let (kind, _) = shape.scalar().unwrap();
+                match op {
+                    Bo::And | Bo::ExclusiveOr | Bo::InclusiveOr if kind == Sk::Float => {
+                        return Err(bad_operands())
+                    }
+                    Bo::Equal | Bo::NotEqual => {}
+                    _ if kind == Sk::Bool => shape = shape.with_scalar(Sk::Sint, 4),
+                    _ => {}
+                }
+                (
+                    self.convert(ctx, block, left, shape, span)?,
+                    self.convert(ctx, block, right, shape, span)?,
+                )
+            }
+        };
+        self.append(ctx, Expression::Binary { op, left, right }, span)
+    }
+
+    /// Pointer to the element at `row` of the column `column` of the matrix
+    /// behind `pointer`.
+    fn matrix_element<'a>(
+        &mut self,
+        ctx: &mut FunctionContext,
+        pointer: Handle<Expression>,
+        row: Handle<Expression>,
+        column: ColumnIndex,
+        span: &Span,
+    ) -> Result<Handle<Expression>, Error<'a>> {
+        let column = match column {
+            ColumnIndex::Constant(index) => Expression::AccessIndex {
+                base: pointer,
+                index,
+            },
+            ColumnIndex::Dynamic(index) => Expression::Access {
+                base: pointer,
+                index,
+            },
+        };
+        let column = self.append(ctx, column, span)?;
+        self.append(
+            ctx,
+            Expression::Access {
+                base: column,
+                index: row,
+            },
+            span,
+        )
+    }
+
+    /// Store `value` into the assignable `target`.
+    fn assign<'a>(
+        &mut self,
+        ctx: &mut FunctionContext,
+        block: &mut Block,
+        target: TypedExpression,
+        value: Handle<Expression>,
+        span: &Span,
+    ) -> Result<(), Error<'a>> {
+        match ctx.lvalues.get(&target.handle).cloned() {
+            Some(LValue::Swizzle {
+                pointer,
+                components,
+            }) => {
+                let (kind, width) = self.shape(ctx, pointer).scalar().unwrap();
+                let shape =
+                    Shape::from_parts(conv::map_vector_size(components.len() as u32), kind, width);
+                let value = self.convert(ctx, block, value, shape, span)?;
+                for (index, &component) in components.iter().enumerate() {
+                    let pointer = self.append(
+                        ctx,
+                        Expression::AccessIndex {
+                            base: pointer,
+                            index: component,
+                        },
+                        span,
+                    )?;
+                    let value = self.append(
+                        ctx,
+                        Expression::AccessIndex {
+                            base: value,
+                            index: index as u32,
+                        },
+                        span,
+                    )?;
+                    self.push_statement(ctx, block, Statement::Store { pointer, value }, span);
+                }
+            }
+            Some(LValue::MatrixRow {
+                pointer,
+                row,
+                columns,
+            }) => {
+                let (_, width) = self.shape(ctx, pointer).scalar().unwrap();
+                let shape = Shape::Vector {
+                    size: conv::map_vector_size(columns.len() as u32).unwrap(),
+                    kind: Sk::Float,
+                    width,
+                };
+                let value = self.convert(ctx, block, value, shape, span)?;
+                for (index, &column) in columns.iter().enumerate() {
+                    let pointer = self.matrix_element(
+                        ctx,
+                        pointer,
+                        row,
+                        ColumnIndex::Constant(column),
+                        span,
+                    )?;
+                    let value = self.append(
+                        ctx,
+                        Expression::AccessIndex {
+                            base: value,
+                            index: index as u32,
+                        },
+                        span,
+                    )?;
+                    self.push_statement(ctx, block, Statement::Store { pointer, value }, span);
+                }
+            }
+            Some(LValue::Texel {
+                image,
+                coordinate,
+                array_index,
+            }) => {
+                // Storage textures always store 4 components
+                let texel = self.shape(ctx, target.handle);
+                let (kind, width) = texel.scalar().unwrap();
+                let value_shape = self.shape(ctx, value);
+                let value = match value_shape.size() {
+                    Some(Vs::Quad) => self.convert(ctx, block, value, texel, span)?,
+                    size => {
+                        let shape = Shape::from_parts(size, kind, width);
+                        let value = self.convert(ctx, block, value, shape, span)?;
+                        let mut components = vec![value];
+                        for _ in size.map_or(1, |size| size as u8)..4 {
+                            let zero = self.constant_expression(
+                                ctx,
+                                block,
+                                Shape::Scalar { kind, width },
+                                0.0,
+                                span,
+                            )?;
+                            components.push(zero);
+                        }
+                        let ty = self.add_type(texel.inner().unwrap());
+                        self.append(ctx, Expression::Compose { ty, components }, span)?
+                    }
+                };
+                self.push_statement(
+                    ctx,
+                    block,
+                    Statement::ImageStore {
+                        image,
+                        coordinate,
+                        array_index,
+                        value,
+                    },
+                    span,
+                );
+            }
+            None if target.is_reference => {
+                let ty = self.value_type(ctx, target);
+                let value = self.convert_to_type(ctx, block, value, ty, span)?;
+                self.push_statement(
+                    ctx,
+                    block,
+                    Statement::Store {
+                        pointer: target.handle,
+                        value,
+                    },
+                    span,
+                );
+            }
+            None => return Err(Error::NotAssignable(span.clone())),
+        }
+        Ok(())
+    }
+
+    /// Increment or decrement `target`, returning the new value if `prefix`
+    /// is set, and the old one otherwise.
+    fn increment<'a>(
+        &mut self,
+        ctx: &mut FunctionContext,
+        block: &mut Block,
+        target: TypedExpression,
+        op: Bo,
+        prefix: bool,
+        span: &Span,
+    ) -> Result<TypedExpression, Error<'a>> {
+        let old = self.apply_load(ctx, target, span)?;
+        let shape = self.shape(ctx, old);
+        if !shape.is_numeric() {
+            return Err(Error::BadOperands(span.clone()));
+        }
+        let one = self.constant_expression(ctx, block, shape, 1.0, span)?;
+        let new = self.append(
+            ctx,
+            Expression::Binary {
+                op,
+                left: old,
+                right: one,
+            },
+            span,
+        )?;
+        self.assign(ctx, block, target, new, span)?;
+        Ok(TypedExpression::value(if prefix { new } else { old }))
+    }
+
+    /// Build a value of type `ty` from the `components`, like the
+    /// constructors `float4(uv, 0.0, 1.0)` or `float2x2(1, 0, 0, 1)`.
+    fn construct<'a>(
+        &mut self,
+        ctx: &mut FunctionContext,
+        block: &mut Block,
+        ty: Handle<crate::Type>,
+        components: Vec<Handle<Expression>>,
+        span: &Span,
+    ) -> Result<Handle<Expression>, Error<'a>> {
+        let shape = Shape::new(&self.module.types[ty].inner, &self.module.types);
+        match shape {
+            Shape::Scalar { .. } => {
+                argument_count(span, 1, components.len())?;
+                self.convert(ctx, block, components[0], shape, span)
+            }
+            Shape::Vector { size, kind, width } => {
+                if components.len() == 1 {
+                    match self.shape(ctx, components[0]) {
+                        Shape::Scalar { .. } => {
+                            return self.convert(ctx, block, components[0], shape, span)
+                        }
+                        Shape::Vector {
+                            size: component_size,
+                            ..
+                        } if component_size as u8 >= size as u8 => {
+                            return self.convert(ctx, block, components[0], shape, span)
+                        }
+                        _ => {}
+                    }
+                }
+                let mut converted = Vec::with_capacity(components.len());
+                let mut count = 0;
+                for component in components {
+                    let component_shape = self.shape(ctx, component);
+                    count += match component_shape {
+                        Shape::Scalar { .. } => 1,
+                        Shape::Vector { size, .. } => size as usize,
+                        _ => return Err(Error::BadArgument(span.clone())),
+                    };
+                    let component_shape = component_shape.with_scalar(kind, width);
+                    converted.push(self.convert(ctx, block, component, component_shape, span)?);
+                }
+                argument_count(span, size as usize, count)?;
+                self.append(
+                    ctx,
+                    Expression::Compose {
+                        ty,
+                        components: converted,
+                    },
+                    span,
+                )
+            }
+            Shape::Matrix {
+                columns,
+                rows,
+                width,
+            } => {
+                if components.len() == 1 {
+                    if let Shape::Matrix { .. } = self.shape(ctx, components[0]) {
+                        return self.convert(ctx, block, components[0], shape, span);
+                    }
+                }
+                // Flatten the arguments, which are given row by row
+                let scalar = Shape::Scalar {
+                    kind: Sk::Float,
+                    width,
+                };
+                let mut scalars = Vec::new();
+                for component in components {
+                    match self.shape(ctx, component) {
+                        Shape::Scalar { .. } => {
+                            scalars.push(self.convert(ctx, block, component, scalar, span)?)
+                        }
+                        Shape::Vector { size, .. } => {
+                            let vector = self.convert(
+                                ctx,
+                                block,
+                                component,
+                                Shape::Vector {
+                                    size,
+                                    kind: Sk::Float,
+                                    width,
+                                },
+                                span,
+                            )?;
+                            for index in 0..size as u32 {
+                                scalars.push(self.append(
+                                    ctx,
+                                    Expression::AccessIndex {
+                                        base: vector,
+                                        index,
+                                    },
+                                    span,
+                                )?);
+                            }
+                        }
+                        _ => return Err(Error::BadArgument(span.clone())),
+                    }
+                }
+                argument_count(span, columns as usize * rows as usize, scalars.len())?;
+                let column_ty = self.add_type(Ti::Vector {
+                    size: rows,
+                    kind: Sk::Float,
+                    width,
+                });
+                let mut column_handles = Vec::with_capacity(columns as usize);
+                for column in 0..columns as usize {
+                    let components = (0..rows as usize)
+                        .map(|row| scalars[row * columns as usize + column])
+                        .collect();
+                    column_handles.push(self.append(
+                        ctx,
+                        Expression::Compose {
+                            ty: column_ty,
+                            components,
+                        },
+                        span,
+                    )?);
+                }
+                self.append(
+                    ctx,
+                    Expression::Compose {
+                        ty,
+                        components: column_handles,
+                    },
+                    span,
+                )
+            }
+            Shape::Other => Err(Error::Unsupported(
+                span.clone(),
+                "constructors of structures and arrays",
+            )),
+        }
+    }
+
+    /// Parse an initializer for a value of type `ty`, which can be a brace
+    /// enclosed list.
+    pub(super) fn parse_initializer<'a>(
+        &mut self,
+        lexer: &mut Lexer<'a>,
+        ctx: &mut FunctionContext,
+        block: &mut Block,
+        ty: Handle<crate::Type>,
+    ) -> Result<Handle<Expression>, Error<'a>> {
+        let start = lexer.next_start();
+        if !lexer.skip(Token::Punct("{")) {
+            let value = self.parse_value(lexer, ctx, block)?;
+            return self.convert_to_type(ctx, block, value, ty, &lexer.span_from(start));
+        }
+
+        let member_types = match self.module.types[ty].inner {
+            Ti::Array {
+                base,
+                size: crate::ArraySize::Constant(size),
+                ..
+            } => Some(vec![base; self.constant_u32(size).unwrap_or(0) as usize]),
+            Ti::Struct { ref members, .. } => {
+                Some(members.iter().map(|member| member.ty).collect::<Vec<_>>())
+            }
+            _ => None,
+        };
+        let mut components = Vec::new();
+        while !lexer.skip(Token::Punct("}")) {
+            if !components.is_empty() {
+                lexer.expect_punct(",")?;
+                if lexer.skip(Token::Punct("}")) {
+                    break;
+                }
+            }
+            let component = match member_types {
+                Some(ref member_types) => match member_types.get(components.len()) {
+                    Some(&member_ty) => self.parse_initializer(lexer, ctx, block, member_ty)?,
+                    None => {
+                        let _ = self.parse_value(lexer, ctx, block)?;
+                        return Err(Error::BadArgumentCount {
+                            span: lexer.span_from(start),
+                            expected: member_types.len(),
+                            found: components.len() + 1,
+                        });
+                    }
+                },
+                None => self.parse_value(lexer, ctx, block)?,
+            };
+            components.push(component);
+        }
+        let span = lexer.span_from(start);
+        match member_types {
+            Some(member_types) => {
+                argument_count(&span, member_types.len(), components.len())?;
+                self.append(ctx, Expression::Compose { ty, components }, &span)
+            }
+            None => self.construct(ctx, block, ty, components, &span),
+        }
+    }
+
+    pub(super) fn parse_expression<'a>(
+        &mut self,
+        lexer: &mut Lexer<'a>,
+        ctx: &mut FunctionContext,
+        block: &mut Block,
+    ) -> Result<TypedExpression, Error<'a>> {
+        let start = lexer.next_start();
+        let condition = self.parse_binary(lexer, ctx, block, 1)?;
+        if !lexer.skip(Token::Punct("?")) {
+            return Ok(condition);
+        }
+        let condition = self.apply_load(ctx, condition, &lexer.span_from(start))?;
+        let accept = self.parse_value(lexer, ctx, block)?;
+        lexer.expect_punct(":")?;
+        let reject = self.parse_expression(lexer, ctx, block)?;
+        let span = lexer.span_from(start);
+        let reject = self.apply_load(ctx, reject, &span)?;
+
+        let accept_shape = self.shape(ctx, accept);
+        let reject_shape = self.shape(ctx, reject);
+        let (accept, reject, size) = match accept_shape.common(reject_shape) {
+            Some(shape) => (
+                self.convert(ctx, block, accept, shape, &span)?,
+                self.convert(ctx, block, reject, shape, &span)?,
+                shape.size(),
+            ),
+            None => {
+                let ty = self.value_type(ctx, TypedExpression::value(accept));
+                (
+                    accept,
+                    self.convert_to_type(ctx, block, reject, ty, &span)?,
+                    None,
+                )
+            }
+        };
+        let condition_size = match self.shape(ctx, condition) {
+            Shape::Vector { .. } => size,
+            _ => None,
+        };
+        let condition = self.convert(
+            ctx,
+            block,
+            condition,
+            Shape::from_parts(condition_size, Sk::Bool, crate::BOOL_WIDTH),
+            &span,
+        )?;
+        let select = self.append(
+            ctx,
+            Expression::Select {
+                condition,
+                accept,
+                reject,
+            },
+            &span,
+        )?;
+        Ok(TypedExpression::value(select))
+    }
+
+    /// Parse an expression, and load its value.
+    fn parse_value<'a>(
+        &mut self,
+        lexer: &mut Lexer<'a>,
+        ctx: &mut FunctionContext,
+        block: &mut Block,
+    ) -> Result<Handle<Expression>, Error<'a>> {
+        let start = lexer.next_start();
+        let expression = self.parse_expression(lexer, ctx, block)?;
+        self.apply_load(ctx, expression, &lexer.span_from(start))
+    }
+
+    pub(super) fn parse_binary<'a>(
+        &mut self,
+        lexer: &mut Lexer<'a>,
+        ctx: &mut FunctionContext,
+        block: &mut Block,
+        min_precedence: u8,
+    ) -> Result<TypedExpression, Error<'a>> {
+        let start = lexer.next_start();
+        let mut left = self.parse_unary(lexer, ctx, block)?;
+        while let Token::Punct(punct) = lexer.peek().0 {
+            let (op, precedence) = match binary_operator(punct) {
+                Some(operator) => operator,
+                None => break,
+            };
+            if precedence < min_precedence {
+                break;
+            }
+            let _ = lexer.next();
+            let right = self.parse_binary(lexer, ctx, block, precedence + 1)?;
+            let span = lexer.span_from(start);
+            let left_value = self.apply_load(ctx, left, &span)?;
+            let right_value = self.apply_load(ctx, right, &span)?;
+            let result = self.binary(ctx, block, op, left_value, right_value, &span)?;
+            left = TypedExpression::value(result);
+        }
+        Ok(left)
+    }
+
+    /// Check if the lexer is at a cast, like `(float3)`.
+    fn is_cast(&self, lexer: &Lexer) -> bool {
+        let mut lexer = lexer.clone();
+        if !lexer.skip(Token::Punct("(")) {
+            return false;
+        }
+        match lexer.next().0 {
+            Token::Word(word) if word != "void" && self.is_type_name(word) => {
+                if word == "vector" || word == "matrix" {
+                    if !lexer.skip(Token::Punct("<")) {
+                        return false;
+                    }
+                    loop {
+                        match lexer.next().0 {
+                            Token::Punct(">") => break,
+                            Token::End => return false,
+                            _ => {}
+                        }
+                    }
+                }
+                lexer.next().0 == Token::Punct(")")
+            }
+            _ => false,
+        }
+    }
+
+    fn parse_unary<'a>(
+        &mut self,
+        lexer: &mut Lexer<'a>,
+        ctx: &mut FunctionContext,
+        block: &mut Block,
+    ) -> Result<TypedExpression, Error<'a>> {
+        let (token, start_span) = lexer.peek();
+        let start = start_span.start;
+        let expression = match token {
+            Token::Punct("-") | Token::Punct("!") | Token::Punct("~") => {
+                let _ = lexer.next();
+                let operand = self.parse_unary(lexer, ctx, block)?;
+                let span = lexer.span_from(start);
+                let value = self.apply_load(ctx, operand, &span)?;
+                let (op, value) = match token {
+                    Token::Punct("-") => {
+                        if let Expression::Constant(constant) = ctx.function.expressions[value] {
+                            use crate::ScalarValue as Sv;
+                            let negated = match self.module.constants[constant].inner {
+                                crate::ConstantInner::Scalar {
+                                    width,
+                                    value: Sv::Sint(v),
+                                } => Some((width, Sv::Sint(-v))),
+                                crate::ConstantInner::Scalar {
+                                    width,
+                                    value: Sv::Float(v),
+                                } => Some((width, Sv::Float(-v))),
+                                _ => None,
+                            };
+                            if let Some((width, value)) = negated {
+                                let constant = self
+                                    .add_constant(crate::ConstantInner::Scalar { width, value });
+                                let handle = self.append_unemitted(
+                                    ctx,
+                                    block,
+                                    Expression::Constant(constant),
+                                    &span,
+                                )?;
+                                return Ok(TypedExpression::value(handle));
+                            }
+                        }
+                        (crate::UnaryOperator::Negate, value)
+                    }
+                    Token::Punct("!") => {
+                        let shape = self
+                            .shape(ctx, value)
+                            .with_scalar(Sk::Bool, crate::BOOL_WIDTH);
+                        let value = self.convert(ctx, block, value, shape, &span)?;
+                        (crate::UnaryOperator::Not, value)
+                    }
+                    _ => (crate::UnaryOperator::Not, value),
+                };
+                let handle = self.append(ctx, Expression::Unary { op, expr: value }, &span)?;
+                TypedExpression::value(handle)
+            }
+            Token::Punct("+") => {
+                let _ = lexer.next();
+                self.parse_unary(lexer, ctx, block)?
+            }
+            Token::Punct("++") | Token::Punct("--") => {
+                let _ = lexer.next();
+                let target = self.parse_unary(lexer, ctx, block)?;
+                let op = if token == Token::Punct("++") {
+                    Bo::Add
+                } else {
+                    Bo::Subtract
+                };
+                self.increment(ctx, block, target, op, true, &lexer.span_from(start))?
+            }
+            Token::Punct("(") if self.is_cast(lexer) => {
+                let _ = lexer.next();
+                let ty = self.parse_data_type(lexer)?;
+                lexer.expect_punct(")")?;
+                let operand = self.parse_unary(lexer, ctx, block)?;
+                let span = lexer.span_from(start);
+                let value = self.apply_load(ctx, operand, &span)?;
+                let zero = match ctx.function.expressions[value] {
+                    Expression::Constant(constant) => match self.module.constants[constant].inner {
+                        crate::ConstantInner::Scalar {
+                            value: crate::ScalarValue::Sint(0),
+                            ..
+                        } => true,
+                        _ => false,
+                    },
+                    _ => false,
+                };
+                let shape = Shape::new(&self.module.types[ty].inner, &self.module.types);
+                let handle = match (shape, zero) {
+                    // `(S)0` initializes a whole structure or array with zeros
+                    (Shape::Other, true) => match self.zero_constant(ty) {
+                        Some(constant) => self.append_unemitted(
+                            ctx,
+                            block,
+                            Expression::Constant(constant),
+                            &span,
+                        )?,
+                        None => self.convert_to_type(ctx, block, value, ty, &span)?,
+                    },
+                    (Shape::Other, false) => self.convert_to_type(ctx, block, value, ty, &span)?,
+                    _ => self.convert(ctx, block, value, shape, &span)?,
+                };
+                TypedExpression::value(handle)
+            }
+            _ => match self.parse_postfix(lexer, ctx, block)? {
+                Some(expression) => expression,
+                None => return Err(Error::VoidValue(lexer.span_from(start))),
+            },
+        };
+        Ok(expression)
+    }
+
+    /// Parse a primary expression with its postfix operators, returning
+    /// `None` for calls of functions without a result.
+    fn parse_postfix<'a>(
+        &mut self,
+        lexer: &mut Lexer<'a>,
+        ctx: &mut FunctionContext,
+        block: &mut Block,
+    ) -> Result<Option<TypedExpression>, Error<'a>> {
+        let start = lexer.next_start();
+        let mut expression = self.parse_primary(lexer, ctx, block)?;
+        loop {
+            let current = match expression {
+                Some(current) => current,
+                None => return Ok(None),
+            };
+            expression = match lexer.peek().0 {
+                Token::Punct("[") => {
+                    let _ = lexer.next();
+                    let index = self.parse_value(lexer, ctx, block)?;
+                    lexer.expect_punct("]")?;
+                    let span = lexer.span_from(start);
+                    Some(self.index(ctx, block, current, index, &span)?)
+                }
+                Token::Punct(".") => {
+                    let _ = lexer.next();
+                    let (name, name_span) = lexer.next_ident()?;
+                    if lexer.peek().0 == Token::Punct("(") {
+                        self.parse_method(lexer, ctx, block, current, name, name_span)?
+                    } else {
+                        let span = lexer.span_from(start);
+                        Some(self.member(ctx, current, name, &span)?)
+                    }
+                }
+                Token::Punct("++") | Token::Punct("--") => {
+                    let op = if lexer.next().0 == Token::Punct("++") {
+                        Bo::Add
+                    } else {
+                        Bo::Subtract
+                    };
+                    let span = lexer.span_from(start);
+                    Some(self.increment(ctx, block, current, op, false, &span)?)
+                }
+                _ => return Ok(Some(current)),
+            };
+        }
+    }
+
+    fn parse_primary<'a>(
+        &mut self,
+        lexer: &mut Lexer<'a>,
+        ctx: &mut FunctionContext,
+        block: &mut Block,
+    ) -> Result<Option<TypedExpression>, Error<'a>> {
+        let (token, span) = lexer.peek();
+        let expression = match token {
+            Token::Punct("(") => {
+                let _ = lexer.next();
+                let expression = self.parse_expression(lexer, ctx, block)?;
+                lexer.expect_punct(")")?;
+                expression
+            }
+            Token::Number(text) => {
+                let _ = lexer.next();
+                let constant = self.add_constant(parse_number(text, &span)?);
+                let handle =
+                    self.append_unemitted(ctx, block, Expression::Constant(constant), &span)?;
+                TypedExpression::value(handle)
+            }
+            Token::Word("true") | Token::Word("false") => {
+                let _ = lexer.next();
+                let constant =
+                    self.add_constant(crate::ConstantInner::boolean(token == Token::Word("true")));
+                let handle =
+                    self.append_unemitted(ctx, block, Expression::Constant(constant), &span)?;
+                TypedExpression::value(handle)
+            }
+            Token::Word(word) if word != "void" && self.is_type_name(word) => {
+                let ty = self.parse_data_type(lexer)?;
+                let arguments = self.parse_arguments(lexer, ctx, block)?;
+                let span = lexer.span_from(span.start);
+                let mut components = Vec::with_capacity(arguments.len());
+                for (argument, argument_span) in arguments {
+                    components.push(self.apply_load(ctx, argument, &argument_span)?);
+                }
+                TypedExpression::value(self.construct(ctx, block, ty, components, &span)?)
+            }
+            Token::Word(name) => {
+                let _ = lexer.next();
+                if lexer.peek().0 == Token::Punct("(") {
+                    return self.parse_call(lexer, ctx, block, name, span);
+                }
+                self.lookup_identifier(ctx, block, name, span)?
+            }
+            _ => return Err(Error::Unexpected(token, span, "expression")),
+        };
+        Ok(Some(expression))
+    }
+
+    fn lookup_identifier<'a>(
+        &mut self,
+        ctx: &mut FunctionContext,
+        block: &mut Block,
+        name: &'a str,
+        span: Span,
+    ) -> Result<TypedExpression, Error<'a>> {
+        if let Some(expression) = ctx.lookup(name) {
+            return Ok(expression);
+        }
+        Ok(match self.lookup_global.get(name).cloned() {
+            Some(GlobalLookup::Variable(handle)) => {
+                let is_reference =
+                    self.module.global_variables[handle].class != crate::StorageClass::Handle;
+                let handle =
+                    self.append_unemitted(ctx, block, Expression::GlobalVariable(handle), &span)?;
+                TypedExpression {
+                    handle,
+                    is_reference,
+                }
+            }
+            Some(GlobalLookup::Member(handle, index)) => {
+                let base =
+                    self.append_unemitted(ctx, block, Expression::GlobalVariable(handle), &span)?;
+                let member = self.append(ctx, Expression::AccessIndex { base, index }, &span)?;
+                TypedExpression::reference(member)
+            }
+            Some(GlobalLookup::Constant(constant)) => {
+                let handle =
+                    self.append_unemitted(ctx, block, Expression::Constant(constant), &span)?;
+                TypedExpression::value(handle)
+            }
+            None => return Err(Error::UnknownIdent(span, name)),
+        })
+    }
+
+    fn parse_arguments<'a>(
+        &mut self,
+        lexer: &mut Lexer<'a>,
+        ctx: &mut FunctionContext,
+        block: &mut Block,
+    ) -> Result<Vec<(TypedExpression, Span)>, Error<'a>> {
+        lexer.expect_punct("(")?;
+        let mut arguments = Vec::new();
+        while !lexer.skip(Token::Punct(")")) {
+            if !arguments.is_empty() {
+                lexer.expect_punct(",")?;
+            }
+            let start = lexer.next_start();
+            let argument = self.parse_expression(lexer, ctx, block)?;
+            arguments.push((argument, lexer.span_from(start)));
+        }
+        Ok(arguments)
+    }
+
+    fn parse_call<'a>(
+        &mut self,
+        lexer: &mut Lexer<'a>,
+        ctx: &mut FunctionContext,
+        block: &mut Block,
+        name: &'a str,
+        name_span: Span,
+    ) -> Result<Option<TypedExpression>, Error<'a>> {
+        let arguments = self.parse_arguments(lexer, ctx, block)?;
+        let span = lexer.span_from(name_span.start);
+        match self.lookup_function.get(name).cloned() {
+            Some(decl) => self.call_function(ctx, block, decl, arguments, &span),
+            None => self.call_intrinsic(ctx, block, name, name_span, arguments, &span),
+        }
+    }
+
+    /// Call a user function, passing the `out` and `inout` arguments through
+    /// temporary variables.
+    fn call_function<'a>(
+        &mut self,
+        ctx: &mut FunctionContext,
+        block: &mut Block,
+        decl: FunctionDecl,
+        arguments: Vec<(TypedExpression, Span)>,
+        span: &Span,
+    ) -> Result<Option<TypedExpression>, Error<'a>> {
+        let function = &self.module.functions[decl.handle];
+        argument_count(span, function.arguments.len(), arguments.len())?;
+        let argument_types = function
+            .arguments
+            .iter()
+            .map(|argument| argument.ty)
+            .collect::<Vec<_>>();
+        let has_result = function.result.is_some();
+
+        let mut values = Vec::with_capacity(arguments.len());
+        let mut copies = Vec::new();
+        for ((argument, argument_span), (ty, &mode)) in arguments
+            .into_iter()
+            .zip(argument_types.into_iter().zip(decl.modes.iter()))
+        {
+            if mode == ParameterMode::In {
+                let value = self.apply_load(ctx, argument, &argument_span)?;
+                values.push(self.convert_to_type(ctx, block, value, ty, &argument_span)?);
+                continue;
+            }
+            let base = match self.module.types[ty].inner {
+                Ti::Pointer { base, .. } => base,
+                _ => unreachable!(),
+            };
+            let temporary = ctx.function.local_variables.append(
+                crate::LocalVariable {
+                    name: None,
+                    ty: base,
+                    init: None,
+                },
+                argument_span.clone().into(),
+            );
+            let pointer = self.append_unemitted(
+                ctx,
+                block,
+                Expression::LocalVariable(temporary),
+                &argument_span,
+            )?;
+            if mode == ParameterMode::InOut {
+                let value = self.apply_load(ctx, argument, &argument_span)?;
+                let value = self.convert_to_type(ctx, block, value, base, &argument_span)?;
+                self.push_statement(
+                    ctx,
+                    block,
+                    Statement::Store { pointer, value },
+                    &argument_span,
+                );
+            }
+            values.push(pointer);
+            copies.push((argument, pointer, argument_span));
+        }
+
+        let result = if has_result {
+            Some(self.append_unemitted(ctx, block, Expression::Call(decl.handle), span)?)
+        } else {
+            None
+        };
+        self.push_statement(
+            ctx,
+            block,
+            Statement::Call {
+                function: decl.handle,
+                arguments: values,
+                result,
+            },
+            span,
+        );
+        for (target, pointer, argument_span) in copies {
+            let value = self.append(ctx, Expression::Load { pointer }, &argument_span)?;
+            self.assign(ctx, block, target, value, &argument_span)?;
+        }
+        Ok(result.map(TypedExpression::value))
+    }
+
+    fn call_intrinsic<'a>(
+        &mut self,
+        ctx: &mut FunctionContext,
+        block: &mut Block,
+        name: &'a str,
+        name_span: Span,
+        arguments: Vec<(TypedExpression, Span)>,
+        span: &Span,
+    ) -> Result<Option<TypedExpression>, Error<'a>> {
+        use crate::{DerivativeAxis as Da, MathFunction as Mf, RelationalFunction as Rf};
+
+        let mut values = Vec::with_capacity(arguments.len());
+        for (argument, argument_span) in arguments {
+            values.push(self.apply_load(ctx, argument, &argument_span)?);
+        }
+        let expression = match name {
+            "mul" => {
+                argument_count(span, 2, values.len())?;
+                let (left, right) = (values[0], values[1]);
+                match (self.shape(ctx, left), self.shape(ctx, right)) {
+                    (left_shape @ Shape::Vector { .. }, right_shape @ Shape::Vector { .. }) => {
+                        let shape = left_shape
+                            .common(right_shape)
+                            .ok_or_else(|| Error::BadArgument(span.clone()))?
+                            .float();
+                        Expression::Math {
+                            fun: Mf::Dot,
+                            arg: self.convert(ctx, block, left, shape, span)?,
+                            arg1: Some(self.convert(ctx, block, right, shape, span)?),
+                            arg2: None,
+                            arg3: None,
+                        }
+                    }
+                    (Shape::Vector { size, .. }, Shape::Matrix { width, .. }) => {
+                        let shape = Shape::Vector {
+                            size,
+                            kind: Sk::Float,
+                            width,
+                        };
+                        Expression::Binary {
+                            op: Bo::Multiply,
+                            left: self.convert(ctx, block, left, shape, span)?,
+                            right,
+                        }
+                    }
+                    (Shape::Matrix { width, .. }, Shape::Vector { size, .. }) => {
+                        let shape = Shape::Vector {
+                            size,
+                            kind: Sk::Float,
+                            width,
+                        };
+                        Expression::Binary {
+                            op: Bo::Multiply,
+                            left,
+                            right: self.convert(ctx, block, right, shape, span)?,
+                        }
+                    }
+                    (Shape::Matrix { .. }, Shape::Matrix { .. }) => Expression::Binary {
+                        op: Bo::Multiply,
+                        left,
+                        right,
+                    },
+                    _ => {
+                        let product = self.binary(ctx, block, Bo::Multiply, left, right, span)?;
+                        return Ok(Some(TypedExpression::value(product)));
+                    }
+                }
+            }
+            "saturate" | "rcp" | "log10" | "degrees" | "radians" => {
+                argument_count(span, 1, values.len())?;
+                let shape = self.shape(ctx, values[0]).float();
+                if !shape.is_numeric() {
+                    return Err(Error::BadArgument(span.clone()));
+                }
+                let value = self.convert(ctx, block, values[0], shape, span)?;
+                let factor = match name {
+                    "saturate" => {
+                        let zero = self.constant_expression(ctx, block, shape, 0.0, span)?;
+                        let one = self.constant_expression(ctx, block, shape, 1.0, span)?;
+                        let clamped = self.append(
+                            ctx,
+                            Expression::Math {
+                                fun: Mf::Clamp,
+                                arg: value,
+                                arg1: Some(zero),
+                                arg2: Some(one),
+                                arg3: None,
+                            },
+                            span,
+                        )?;
+                        return Ok(Some(TypedExpression::value(clamped)));
+                    }
+                    "rcp" => {
+                        let one = self.constant_expression(ctx, block, shape, 1.0, span)?;
+                        let reciprocal = self.append(
+                            ctx,
+                            Expression::Binary {
+                                op: Bo::Divide,
+                                left: one,
+                                right: value,
+                            },
+                            span,
+                        )?;
+                        return Ok(Some(TypedExpression::value(reciprocal)));
+                    }
+                    "log10" => std::f64::consts::LN_2 / std::f64::consts::LN_10,
+                    "degrees" => 180.0 / std::f64::consts::PI,
+                    _ => std::f64::consts::PI / 180.0,
+                };
+                let value = if name == "log10" {
+                    self.append(
+                        ctx,
+                        Expression::Math {
+                            fun: Mf::Log2,
+                            arg: value,
+                            arg1: None,
+                            arg2: None,
+                            arg3: None,
+                        },
+                        span,
+                    )?
+                } else {
+                    value
+                };
+                let factor = self.constant_expression(ctx, block, shape, factor, span)?;
+                Expression::Binary {
+                    op: Bo::Multiply,
+                    left: value,
+                    right: factor,
+                }
+            }
+            "fmod" => {
+                argument_count(span, 2, values.len())?;
+                let shape = self
+                    .shape(ctx, values[0])
+                    .common(self.shape(ctx, values[1]))
+                    .ok_or_else(|| Error::BadArgument(span.clone()))?
+                    .float();
+                Expression::Binary {
+                    op: Bo::Modulo,
+                    left: self.convert(ctx, block, values[0], shape, span)?,
+                    right: self.convert(ctx, block, values[1], shape, span)?,
+                }
+            }
+            "ddx" | "ddx_coarse" | "ddx_fine" | "ddy" | "ddy_coarse" | "ddy_fine" | "fwidth" => {
+                argument_count(span, 1, values.len())?;
+                let axis = match name {
+                    "fwidth" => Da::Width,
+                    _ if name.starts_with("ddx") => Da::X,
+                    _ => Da::Y,
+                };
+                let shape = self.shape(ctx, values[0]).float();
+                Expression::Derivative {
+                    axis,
+                    expr: self.convert(ctx, block, values[0], shape, span)?,
+                }
+            }
+            "all" | "any" => {
+                argument_count(span, 1, values.len())?;
+                let shape = self
+                    .shape(ctx, values[0])
+                    .with_scalar(Sk::Bool, crate::BOOL_WIDTH);
+                let argument = self.convert(ctx, block, values[0], shape, span)?;
+                if let Shape::Scalar { .. } = shape {
+                    return Ok(Some(TypedExpression::value(argument)));
+                }
+                Expression::Relational {
+                    fun: if name == "all" { Rf::All } else { Rf::Any },
+                    argument,
+                }
+            }
+            "isnan" | "isinf" | "isfinite" => {
+                argument_count(span, 1, values.len())?;
+                let shape = self.shape(ctx, values[0]).float();
+                Expression::Relational {
+                    fun: match name {
+                        "isnan" => Rf::IsNan,
+                        "isinf" => Rf::IsInf,
+                        _ => Rf::IsFinite,
+                    },
+                    argument: self.convert(ctx, block, values[0], shape, span)?,
+                }
+            }
+            "asfloat" | "asint" | "asuint" => {
+                argument_count(span, 1, values.len())?;
+                let kind = match name {
+                    "asfloat" => Sk::Float,
+                    "asint" => Sk::Sint,
+                    _ => Sk::Uint,
+                };
+                match self.shape(ctx, values[0]).scalar() {
+                    Some((from, _)) if from == kind => {
+                        return Ok(Some(TypedExpression::value(values[0])))
+                    }
+                    Some((Sk::Bool, _)) | None => return Err(Error::BadArgument(span.clone())),
+                    Some(_) => Expression::As {
+                        expr: values[0],
+                        kind,
+                        convert: None,
+                    },
+                }
+            }
+            "clip" => {
+                argument_count(span, 1, values.len())?;
+                let shape = self.shape(ctx, values[0]).float();
+                let value = self.convert(ctx, block, values[0], shape, span)?;
+                let zero = self.constant_expression(ctx, block, shape, 0.0, span)?;
+                let mut condition = self.append(
+                    ctx,
+                    Expression::Binary {
+                        op: Bo::Less,
+                        left: value,
+                        right: zero,
+                    },
+                    span,
+                )?;
+                if let Shape::Vector { .. } = shape {
+                    condition = self.append(
+                        ctx,
+                        Expression::Relational {
+                            fun: Rf::Any,
+                            argument: condition,
+                        },
+                        span,
+                    )?;
+                }
+                let mut accept = Block::new();
+                accept.push(Statement::Kill, span.clone().into());
+                self.push_statement(
+                    ctx,
+                    block,
+                    Statement::If {
+                        condition,
+                        accept,
+                        reject: Block::new(),
+                    },
+                    span,
+                );
+                return Ok(None);
+            }
+            "GroupMemoryBarrier"
+            | "GroupMemoryBarrierWithGroupSync"
+            | "DeviceMemoryBarrier"
+            | "DeviceMemoryBarrierWithGroupSync"
+            | "AllMemoryBarrier"
+            | "AllMemoryBarrierWithGroupSync" => {
+                argument_count(span, 0, values.len())?;
+                let barrier = if name.starts_with("Group") {
+                    crate::Barrier::WORK_GROUP
+                } else if name.starts_with("Device") {
+                    crate::Barrier::STORAGE
+                } else {
+                    crate::Barrier::WORK_GROUP | crate::Barrier::STORAGE
+                };
+                self.push_statement(ctx, block, Statement::Barrier(barrier), span);
+                return Ok(None);
+            }
+            _ => {
+                let (fun, expected, conversion) =
+                    conv::map_math_function(name).ok_or(Error::UnknownIdent(name_span, name))?;
+                argument_count(span, expected, values.len())?;
+                let last = match conversion {
+                    conv::ArgumentConversion::None => 0,
+                    conv::ArgumentConversion::FloatLastScalar => values.len() - 1,
+                    _ => values.len(),
+                };
+                if last != 0 {
+                    let mut shape = self.shape(ctx, values[0]);
+                    for &value in values[1..last].iter() {
+                        shape = shape
+                            .common(self.shape(ctx, value))
+                            .ok_or_else(|| Error::BadArgument(span.clone()))?;
+                    }
+                    if conversion != conv::ArgumentConversion::Common {
+                        shape = shape.float();
+                    }
+                    for value in values[..last].iter_mut() {
+                        *value = self.convert(ctx, block, *value, shape, span)?;
+                    }
+                    if last != values.len() {
+                        let (_, width) = shape.scalar().unwrap();
+                        let scalar = Shape::Scalar {
+                            kind: Sk::Float,
+                            width,
+                        };
+                        values[last] = self.convert(ctx, block, values[last], scalar, span)?;
+                    }
+                }
+                Expression::Math {
+                    fun,
+                    arg: values[0],
+                    arg1: values.get(1).cloned(),
+                    arg2: values.get(2).cloned(),
+                    arg3: values.get(3).cloned(),
+                }
+            }
+        };
+        let handle = self.append(ctx, expression, span)?;
+        Ok(Some(TypedExpression::value(handle)))
+    }
+
+    fn index<'a>(
+        &mut self,
+        ctx: &mut FunctionContext,
+        block: &mut Block,
+        base: TypedExpression,
+        index: Handle<Expression>,
+        span: &Span,
+    ) -> Result<TypedExpression, Error<'a>> {
+        if let Ti::Image {
+            dim,
+            arrayed,
+            class,
+        } = *ctx.typifier.get(base.handle, &self.module.types)
+        {
+            return self.image_index(ctx, block, base.handle, dim, arrayed, class, index, span);
+        }
+
+        let index = match self.shape(ctx, index) {
+            Shape::Scalar { kind: Sk::Sint, .. } | Shape::Scalar { kind: Sk::Uint, .. } => index,
+            Shape::Scalar { .. } => {
+                let shape = Shape::Scalar {
+                    kind: Sk::Sint,
+                    width: 4,
+                };
+                self.convert(ctx, block, index, shape, span)?
+            }
+            _ => return Err(Error::BadOperands(span.clone())),
+        };
+        let constant_index = self.constant_index(ctx, index);
+
+        match self.shape(ctx, base.handle) {
+            // HLSL indexes the rows of matrices
+            Shape::Matrix { columns, width, .. } => {
+                let matrix = self.apply_load(ctx, base, span)?;
+                let mut components = Vec::with_capacity(columns as usize);
+                for column in 0..columns as u32 {
+                    let column = self.append(
+                        ctx,
+                        Expression::AccessIndex {
+                            base: matrix,
+                            index: column,
+                        },
+                        span,
+                    )?;
+                    let expression = match constant_index {
+                        Some(constant) => Expression::AccessIndex {
+                            base: column,
+                            index: constant,
+                        },
+                        None => Expression::Access {
+                            base: column,
+                            index,
+                        },
+                    };
+                    components.push(self.append(ctx, expression, span)?);
+                }
+                let ty = self.add_type(Ti::Vector {
+                    size: columns,
+                    kind: Sk::Float,
+                    width,
+                });
+                let row = self.append(ctx, Expression::Compose { ty, components }, span)?;
+                if base.is_reference {
+                    ctx.lvalues.insert(
+                        row,
+                        LValue::MatrixRow {
+                            pointer: base.handle,
+                            row: index,
+                            columns: (0..columns as u32).collect(),
+                        },
+                    );
+                }
+                Ok(TypedExpression::value(row))
+            }
+            Shape::Vector { .. } => {
+                if let Some(LValue::MatrixRow {
+                    pointer,
+                    row,
+                    columns,
+                }) = ctx.lvalues.get(&base.handle).cloned()
+                {
+                    // An element of a matrix row is stored in a column of the matrix
+                    let column = match constant_index {
+                        Some(constant) => columns
+                            .get(constant as usize)
+                            .map(|&column| ColumnIndex::Constant(column)),
+                        None if columns.iter().enumerate().all(|(i, &c)| i as u32 == c) => {
+                            Some(ColumnIndex::Dynamic(index))
+                        }
+                        None => None,
+                    };
+                    if let Some(column) = column {
+                        let handle = self.matrix_element(ctx, pointer, row, column, span)?;
+                        return Ok(TypedExpression::reference(handle));
+                    }
+                }
+                let expression = match constant_index {
+                    Some(constant) => Expression::AccessIndex {
+                        base: base.handle,
+                        index: constant,
+                    },
+                    None => Expression::Access {
+                        base: base.handle,
+                        index,
+                    },
+                };
+                let handle = self.append(ctx, expression, span)?;
+                Ok(TypedExpression {
+                    handle,
+                    is_reference: base.is_reference,
+                })
+            }
+            Shape::Scalar { .. } => Err(Error::BadOperands(span.clone())),
+            Shape::Other => {
+                let is_array = match *ctx.typifier.get(base.handle, &self.module.types) {
+                    Ti::Array { .. } => true,
+                    Ti::Pointer { base: pointee, .. } => match self.module.types[pointee].inner {
+                        Ti::Array { .. } => true,
+                        _ => false,
+                    },
+                    _ => false,
+                };
+                if !is_array {
+                    return Err(Error::BadOperands(span.clone()));
+                }
+                let base = match constant_index {
+                    None if !base.is_reference => {
+                        // Dynamic indexing of array values goes through a variable
+                        let ty = self.value_type(ctx, base);
+                        let temporary = ctx.function.local_variables.append(
+                            crate::LocalVariable {
+                                name: None,
+                                ty,
+                                init: None,
+                            },
+                            span.clone().into(),
+                        );
+                        let pointer = self.append_unemitted(
+                            ctx,
+                            block,
+                            Expression::LocalVariable(temporary),
+                            span,
+                        )?;
+                        self.push_statement(
+                            ctx,
+                            block,
+                            Statement::Store {
+                                pointer,
+                                value: base.handle,
+                            },
+                            span,
+                        );
+                        TypedExpression::reference(pointer)
+                    }
+                    _ => base,
+                };
+                let expression = match constant_index {
+                    Some(constant) => Expression::AccessIndex {
+                        base: base.handle,
+                        index: constant,
+                    },
+                    None => Expression::Access {
+                        base: base.handle,
+                        index,
+                    },
+                };
+                let handle = self.append(ctx, expression, span)?;
+                Ok(TypedExpression {
+                    handle,
+                    is_reference: base.is_reference,
+                })
+            }
+        }
+    }
+
+    /// Split the texture `coordinate` into the coordinate itself, the array
+    /// layer, and `extra` trailing components, like the mipmap level.
+    #[allow(clippy::too_many_arguments)]
+    fn split_coordinate<'a>(
+        &mut self,
+        ctx: &mut FunctionContext,
+        block: &mut Block,
+        coordinate: Handle<Expression>,
+        dim: crate::ImageDimension,
+        arrayed: bool,
+        extra: u32,
+        kind: Sk,
+        span: &Span,
+    ) -> Result<SplitCoordinate, Error<'a>> {
+        let count = match dim {
+            crate::ImageDimension::D1 => 1,
+            crate::ImageDimension::D2 => 2,
+            crate::ImageDimension::D3 | crate::ImageDimension::Cube => 3,
+        };
+        let total = count + arrayed as u32 + extra;
+        let shape = Shape::from_parts(conv::map_vector_size(total), kind, 4);
+        let value = self.convert(ctx, block, coordinate, shape, span)?;
+        if total == count {
+            return Ok((value, None, Vec::new()));
+        }
+
+        let coordinate = match conv::map_vector_size(count) {
+            Some(size) => self.append(
+                ctx,
+                Expression::Swizzle {
+                    size,
+                    vector: value,
+                    pattern: SWIZZLE_IDENTITY,
+                },
+                span,
+            )?,
+            None => self.append(
+                ctx,
+                Expression::AccessIndex {
+                    base: value,
+                    index: 0,
+                },
+                span,
+            )?,
+        };
+        let array_index = if arrayed {
+            let layer = self.append(
+                ctx,
+                Expression::AccessIndex {
+                    base: value,
+                    index: count,
+                },
+                span,
+            )?;
+            Some(if kind == Sk::Float {
+                let rounded = self.append(
+                    ctx,
+                    Expression::Math {
+                        fun: crate::MathFunction::Round,
+                        arg: layer,
+                        arg1: None,
+                        arg2: None,
+                        arg3: None,
+                    },
+                    span,
+                )?;
+                self.append(
+                    ctx,
+                    Expression::As {
+                        expr: rounded,
+                        kind: Sk::Sint,
+                        convert: Some(4),
+                    },
+                    span,
+                )?
+            } else {
+                layer
+            })
+        } else {
+            None
+        };
+        let mut extras = Vec::with_capacity(extra as usize);
+        for index in count + arrayed as u32..total {
+            extras.push(self.append(ctx, Expression::AccessIndex { base: value, index }, span)?);
+        }
+        Ok((coordinate, array_index, extras))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn image_index<'a>(
+        &mut self,
+        ctx: &mut FunctionContext,
+        block: &mut Block,
+        image: Handle<Expression>,
+        dim: crate::ImageDimension,
+        arrayed: bool,
+        class: crate::ImageClass,
+        coordinate: Handle<Expression>,
+        span: &Span,
+    ) -> Result<TypedExpression, Error<'a>> {
+        let (coordinate, array_index, _) =
+            self.split_coordinate(ctx, block, coordinate, dim, arrayed, 0, Sk::Sint, span)?;
+        let index = match class {
+            crate::ImageClass::Storage(_) => None,
+            crate::ImageClass::Sampled { multi: true, .. } => {
+                return Err(Error::Unsupported(
+                    span.clone(),
+                    "indexing of multisampled textures",
+                ))
+            }
+            _ => {
+                let shape = Shape::Scalar {
+                    kind: Sk::Sint,
+                    width: 4,
+                };
+                Some(self.constant_expression(ctx, block, shape, 0.0, span)?)
+            }
+        };
+        let texel = self.append(
+            ctx,
+            Expression::ImageLoad {
+                image,
+                coordinate,
+                array_index,
+                index,
+            },
+            span,
+        )?;
+        if let crate::ImageClass::Storage(_) = class {
+            ctx.lvalues.insert(
+                texel,
+                LValue::Texel {
+                    image,
+                    coordinate,
+                    array_index,
+                },
+            );
+        }
+        Ok(TypedExpression::value(texel))
+    }
+
+    fn member<'a>(
+        &mut self,
+        ctx: &mut FunctionContext,
+        base: TypedExpression,
+        name: &'a str,
+        span: &Span,
+    ) -> Result<TypedExpression, Error<'a>> {
+        let types = &self.module.types;
+        let inner = match *ctx.typifier.get(base.handle, types) {
+            Ti::Pointer { base, .. } => &types[base].inner,
+            ref other => other,
+        };
+        if let Ti::Struct { ref members, .. } = *inner {
+            let index = members
+                .iter()
+                .position(|member| member.name.as_deref() == Some(name))
+                .ok_or_else(|| Error::UnknownIdent(span.clone(), name))?;
+            let handle = self.append(
+                ctx,
+                Expression::AccessIndex {
+                    base: base.handle,
+                    index: index as u32,
+                },
+                span,
+            )?;
+            return Ok(TypedExpression {
+                handle,
+                is_reference: base.is_reference,
+            });
+        }
+
+        let bad_swizzle = || Error::BadSwizzle(span.clone());
+        match self.shape(ctx, base.handle) {
+            Shape::Matrix { columns, rows, .. } => {
+                // Matrix members like `_m01` or `_12`, possibly repeated
+                let mut elements = Vec::new();
+                for element in name.split('_').skip(1) {
+                    let (digits, first) = if element.starts_with('m') {
+                        (&element[1..], 0)
+                    } else {
+                        (element, 1)
+                    };
+                    let mut chars = digits.chars().map(|c| c.to_digit(10));
+                    let (row, column) = match (chars.next(), chars.next(), chars.next()) {
+                        (Some(Some(row)), Some(Some(column)), None)
+                            if row >= first
+                                && column >= first
+                                && row - first < rows as u32
+                                && column - first < columns as u32 =>
+                        {
+                            (row - first, column - first)
+                        }
+                        _ => return Err(bad_swizzle()),
+                    };
+                    elements.push((row, column));
+                }
+                if !name.starts_with('_') || elements.is_empty() || elements.len() > 4 {
+                    return Err(bad_swizzle());
+                }
+                let matrix = if elements.len() == 1 {
+                    base
+                } else {
+                    TypedExpression::value(self.apply_load(ctx, base, span)?)
+                };
+                let mut components = Vec::with_capacity(elements.len());
+                for (row, column) in elements {
+                    let column = self.append(
+                        ctx,
+                        Expression::AccessIndex {
+                            base: matrix.handle,
+                            index: column,
+                        },
+                        span,
+                    )?;
+                    components.push(self.append(
+                        ctx,
+                        Expression::AccessIndex {
+                            base: column,
+                            index: row,
+                        },
+                        span,
+                    )?);
+                }
+                match conv::map_vector_size(components.len() as u32) {
+                    None => Ok(TypedExpression {
+                        handle: components[0],
+                        is_reference: matrix.is_reference,
+                    }),
+                    Some(size) => {
+                        let (_, width) = self.shape(ctx, matrix.handle).scalar().unwrap();
+                        let ty = self.add_type(Ti::Vector {
+                            size,
+                            kind: Sk::Float,
+                            width,
+                        });
+                        let vector =
+                            self.append(ctx, Expression::Compose { ty, components }, span)?;
+                        Ok(TypedExpression::value(vector))
+                    }
+                }
+            }
+            shape @ Shape::Scalar { .. } | shape @ Shape::Vector { .. } => {
+                let available = shape.size().map_or(1, |size| size as u32);
+                let components = name
+                    .chars()
+                    .map(|c| match c {
+                        'x' | 'r' => Some(0),
+                        'y' | 'g' => Some(1),
+                        'z' | 'b' => Some(2),
+                        'w' | 'a' => Some(3),
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<u32>>>()
+                    .ok_or_else(bad_swizzle)?;
+                if components.is_empty()
+                    || components.len() > 4
+                    || components.iter().any(|&component| component >= available)
+                {
+                    return Err(bad_swizzle());
+                }
+                let size = conv::map_vector_size(components.len() as u32);
+                if let Shape::Scalar { .. } = shape {
+                    let value = self.apply_load(ctx, base, span)?;
+                    return Ok(match size {
+                        Some(size) => TypedExpression::value(self.append(
+                            ctx,
+                            Expression::Splat { size, value },
+                            span,
+                        )?),
+                        None => TypedExpression::value(value),
+                    });
+                }
+                let row = match ctx.lvalues.get(&base.handle) {
+                    Some(&LValue::MatrixRow {
+                        pointer,
+                        row,
+                        ref columns,
+                    }) => Some((pointer, row, columns.clone())),
+                    _ => None,
+                };
+                let size = match size {
+                    Some(size) => size,
+                    None => {
+                        if let Some((pointer, row, ref columns)) = row {
+                            let column = ColumnIndex::Constant(columns[components[0] as usize]);
+                            let handle = self.matrix_element(ctx, pointer, row, column, span)?;
+                            return Ok(TypedExpression::reference(handle));
+                        }
+                        let handle = self.append(
+                            ctx,
+                            Expression::AccessIndex {
+                                base: base.handle,
+                                index: components[0],
+                            },
+                            span,
+                        )?;
+                        return Ok(TypedExpression {
+                            handle,
+                            is_reference: base.is_reference,
+                        });
+                    }
+                };
+                let vector = self.apply_load(ctx, base, span)?;
+                let mut pattern = SWIZZLE_IDENTITY;
+                for (slot, &component) in pattern.iter_mut().zip(components.iter()) {
+                    *slot = SWIZZLE_IDENTITY[component as usize];
+                }
+                let swizzle = self.append(
+                    ctx,
+                    Expression::Swizzle {
+                        size,
+                        vector,
+                        pattern,
+                    },
+                    span,
+                )?;
+                let unique = components
+                    .iter()
+                    .enumerate()
+                    .all(|(i, component)| !components[..i].contains(component));
+                match row {
+                    // Swizzling a matrix row picks some of its columns
+                    Some((pointer, row, columns)) if unique => {
+                        let columns = components
+                            .iter()
+                            .map(|&component| columns[component as usize])
+                            .collect();
+                        ctx.lvalues.insert(
+                            swizzle,
+                            LValue::MatrixRow {
+                                pointer,
+                                row,
+                                columns,
+                            },
+                        );
+                    }
+                    _ if base.is_reference && unique => {
+                        ctx.lvalues.insert(
+                            swizzle,
+                            LValue::Swizzle {
+                                pointer: base.handle,
+                                components,
+                            },
+                        );
+                    }
+                    _ => {}
+                }
+                Ok(TypedExpression::value(swizzle))
+            }
+            Shape::Other => Err(Error::UnknownIdent(span.clone(), name)),
+        }
+    }
+
+    fn parse_method<'a>(
+        &mut self,
+        lexer: &mut Lexer<'a>,
+        ctx: &mut FunctionContext,
+        block: &mut Block,
+        base: TypedExpression,
+        name: &'a str,
+        name_span: Span,
+    ) -> Result<Option<TypedExpression>, Error<'a>> {
+        let arguments = self.parse_arguments(lexer, ctx, block)?;
+        let span = lexer.span_from(name_span.start);
+
+        let image = match *ctx.typifier.get(base.handle, &self.module.types) {
+            Ti::Image {
+                dim,
+                arrayed,
+                class,
+            } => Some((dim, arrayed, class)),
+            _ => None,
+        };
+        if let Some((dim, arrayed, class)) = image {
+            if name == "GetDimensions" {
+                self.image_dimensions(
+                    ctx,
+                    block,
+                    base.handle,
+                    dim,
+                    arrayed,
+                    class,
+                    arguments,
+                    &span,
+                )?;
+                return Ok(None);
+            }
+            let mut values = Vec::with_capacity(arguments.len());
+            for (argument, argument_span) in arguments {
+                values.push(self.apply_load(ctx, argument, &argument_span)?);
+            }
+            let expression = self.image_method(
+                ctx,
+                block,
+                base.handle,
+                (dim, arrayed, class),
+                name,
+                name_span,
+                values,
+                &span,
+            )?;
+            let handle = self.append(ctx, expression, &span)?;
+            return Ok(Some(TypedExpression::value(handle)));
+        }
+
+        let stride = match *ctx.typifier.get(base.handle, &self.module.types) {
+            Ti::Pointer { base: pointee, .. } => match self.module.types[pointee].inner {
+                Ti::Array {
+                    size: crate::ArraySize::Dynamic,
+                    stride,
+                    ..
+                } => Some(stride),
+                _ => None,
+            },
+            _ => None,
+        };
+        match stride {
+            Some(stride) if name == "GetDimensions" => {
+                argument_count(&span, 2, arguments.len())?;
+                let length = self.append(ctx, Expression::ArrayLength(base.handle), &span)?;
+                self.assign(ctx, block, arguments[0].0, length, &arguments[0].1)?;
+                let constant = self.add_constant(crate::ConstantInner::Scalar {
+                    width: 4,
+                    value: crate::ScalarValue::Uint(stride as u64),
+                });
+                let stride =
+                    self.append_unemitted(ctx, block, Expression::Constant(constant), &span)?;
+                self.assign(ctx, block, arguments[1].0, stride, &arguments[1].1)?;
+                Ok(None)
+            }
+            _ => Err(Error::UnknownMethod(name_span, name)),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn image_method<'a>(
+        &mut self,
+        ctx: &mut FunctionContext,
+        block: &mut Block,
+        image: Handle<Expression>,
+        (dim, arrayed, class): (crate::ImageDimension, bool, crate::ImageClass),
+        name: &'a str,
+        name_span: Span,
+        values: Vec<Handle<Expression>>,
+        span: &Span,
+    ) -> Result<Expression, Error<'a>> {
+        let float = Shape::Scalar {
+            kind: Sk::Float,
+            width: 4,
+        };
+        let sint = Shape::Scalar {
+            kind: Sk::Sint,
+            width: 4,
+        };
+        let count = match dim {
+            crate::ImageDimension::D1 => 1,
+            crate::ImageDimension::D2 => 2,
+            crate::ImageDimension::D3 | crate::ImageDimension::Cube => 3,
+        };
+        match name {
+            "Sample" | "SampleBias" | "SampleLevel" | "SampleGrad" | "SampleCmp"
+            | "SampleCmpLevelZero" | "Gather" | "GatherRed" | "GatherGreen" | "GatherBlue"
+            | "GatherAlpha" | "GatherCmp" | "GatherCmpRed" => {
+                let required = match name {
+                    "Sample" | "Gather" | "GatherRed" | "GatherGreen" | "GatherBlue"
+                    | "GatherAlpha" => 2,
+                    "SampleGrad" => 4,
+                    _ => 3,
+                };
+                let gather = match name {
+                    "Gather" | "GatherRed" | "GatherCmp" | "GatherCmpRed" => {
+                        Some(crate::SwizzleComponent::X)
+                    }
+                    "GatherGreen" => Some(crate::SwizzleComponent::Y),
+                    "GatherBlue" => Some(crate::SwizzleComponent::Z),
+                    "GatherAlpha" => Some(crate::SwizzleComponent::W),
+                    _ => None,
+                };
+                if values.len() != required + 1 {
+                    argument_count(span, required, values.len())?;
+                }
+                let (coordinate, array_index, _) =
+                    self.split_coordinate(ctx, block, values[1], dim, arrayed, 0, Sk::Float, span)?;
+                let (level, depth_ref) = match name {
+                    "Sample" => (crate::SampleLevel::Auto, None),
+                    "SampleBias" => {
+                        let bias = self.convert(ctx, block, values[2], float, span)?;
+                        (crate::SampleLevel::Bias(bias), None)
+                    }
+                    "SampleLevel" => {
+                        let level = self.convert(ctx, block, values[2], float, span)?;
+                        (crate::SampleLevel::Exact(level), None)
+                    }
+                    "SampleGrad" => {
+                        let shape = Shape::from_parts(conv::map_vector_size(count), Sk::Float, 4);
+                        let x = self.convert(ctx, block, values[2], shape, span)?;
+                        let y = self.convert(ctx, block, values[3], shape, span)?;
+                        (crate::SampleLevel::Gradient { x, y }, None)
+                    }
+                    "SampleCmp" => {
+                        let reference = self.convert(ctx, block, values[2], float, span)?;
+                        (crate::SampleLevel::Auto, Some(reference))
+                    }
+                    "Gather" | "GatherRed" | "GatherGreen" | "GatherBlue" | "GatherAlpha" => {
+                        (crate::SampleLevel::Zero, None)
+                    }
+                    _ => {
+                        let reference = self.convert(ctx, block, values[2], float, span)?;
+                        (crate::SampleLevel::Zero, Some(reference))
+                    }
+                };
+                let offset = match values.get(required) {
+                    Some(&offset) => {
+                        let offset_count = match dim {
+                            crate::ImageDimension::Cube => 2,
+                            _ => count,
+                        };
+                        let shape =
+                            Shape::from_parts(conv::map_vector_size(offset_count), Sk::Sint, 4);
+                        let offset = self.convert(ctx, block, offset, shape, span)?;
+                        Some(self.solve_constant(ctx, offset, span.clone())?)
+                    }
+                    None => None,
+                };
+                Ok(Expression::ImageSample {
+                    image,
+                    sampler: values[0],
+                    coordinate,
+                    array_index,
+                    offset,
+                    level,
+                    depth_ref,
+                    gather,
+                })
+            }
+            "Load" => {
+                let (extra, required) = match class {
+                    crate::ImageClass::Sampled { multi: true, .. } => (0, 2),
+                    crate::ImageClass::Storage(_) => (0, 1),
+                    _ => (1, 1),
+                };
+                argument_count(span, required, values.len())?;
+                let (coordinate, array_index, extras) = self.split_coordinate(
+                    ctx,
+                    block,
+                    values[0],
+                    dim,
+                    arrayed,
+                    extra,
+                    Sk::Sint,
+                    span,
+                )?;
+                let index = match class {
+                    crate::ImageClass::Storage(_) => None,
+                    crate::ImageClass::Sampled { multi: true, .. } => {
+                        Some(self.convert(ctx, block, values[1], sint, span)?)
+                    }
+                    _ => Some(extras[0]),
+                };
+                Ok(Expression::ImageLoad {
+                    image,
+                    coordinate,
+                    array_index,
+                    index,
+                })
+            }
+            _ => Err(Error::UnknownMethod(name_span, name)),
+        }
+    }
+
+    /// Implement `GetDimensions` of textures, which writes the size of the
+    /// texture, and optionally the number of levels or samples, into the
+    /// `out` arguments.
+    #[allow(clippy::too_many_arguments)]
+    fn image_dimensions<'a>(
+        &mut self,
+        ctx: &mut FunctionContext,
+        block: &mut Block,
+        image: Handle<Expression>,
+        dim: crate::ImageDimension,
+        arrayed: bool,
+        class: crate::ImageClass,
+        arguments: Vec<(TypedExpression, Span)>,
+        span: &Span,
+    ) -> Result<(), Error<'a>> {
+        let count = match dim {
+            crate::ImageDimension::D1 => 1,
+            crate::ImageDimension::D2 | crate::ImageDimension::Cube => 2,
+            crate::ImageDimension::D3 => 3,
+        };
+        let outputs = count + arrayed as usize;
+        let (has_level, extra) = match class {
+            crate::ImageClass::Sampled { multi: true, .. } => {
+                (false, Some(crate::ImageQuery::NumSamples))
+            }
+            crate::ImageClass::Storage(_) => (false, None),
+            _ if arguments.len() == outputs + 2 => (true, Some(crate::ImageQuery::NumLevels)),
+            _ => (false, None),
+        };
+        argument_count(
+            span,
+            outputs + has_level as usize + extra.is_some() as usize,
+            arguments.len(),
+        )?;
+
+        let level = if has_level {
+            let (argument, ref argument_span) = arguments[0];
+            let value = self.apply_load(ctx, argument, argument_span)?;
+            let sint = Shape::Scalar {
+                kind: Sk::Sint,
+                width: 4,
+            };
+            Some(self.convert(ctx, block, value, sint, argument_span)?)
+        } else {
+            None
+        };
+        let mut outputs = arguments[has_level as usize..].iter();
+        let size = self.append(
+            ctx,
+            Expression::ImageQuery {
+                image,
+                query: crate::ImageQuery::Size { level },
+            },
+            span,
+        )?;
+        for index in 0..count as u32 {
+            let component = if count == 1 {
+                size
+            } else {
+                self.append(ctx, Expression::AccessIndex { base: size, index }, span)?
+            };
+            let &(target, ref target_span) = outputs.next().unwrap();
+            self.assign(ctx, block, target, component, target_span)?;
+        }
+        let queries = Some(crate::ImageQuery::NumLayers)
+            .filter(|_| arrayed)
+            .into_iter()
+            .chain(extra);
+        for query in queries {
+            let value = self.append(ctx, Expression::ImageQuery { image, query }, span)?;
+            let &(target, ref target_span) = outputs.next().unwrap();
+            self.assign(ctx, block, target, value, target_span)?;
+        }
+        Ok(())
+    }
+
+    /// Parse the body of a function, after its signature.
+    pub(super) fn parse_function_body<'a>(
+        &mut self,
+        lexer: &mut Lexer<'a>,
+        ctx: &mut FunctionContext,
+        parameters: &[Parameter<'a>],
+    ) -> Result<Block, Error<'a>> {
+        let mut block = Block::new();
+        ctx.start_emit();
+        let assigned = scan_assigned_parameters(lexer, parameters);
+        let has_outputs = match ctx.return_kind {
+            ReturnKind::EntryPointOutputs { .. } => true,
+            ReturnKind::Value(_) => false,
+        };
+
+        let mut argument_index = 0;
+        let mut outputs = Vec::new();
+        for parameter in parameters {
+            let span = &parameter.name_span;
+            let expression = if parameter.mode == ParameterMode::Out && has_outputs {
+                let local = ctx.function.local_variables.append(
+                    crate::LocalVariable {
+                        name: Some(parameter.name.to_string()),
+                        ty: parameter.ty,
+                        init: None,
+                    },
+                    span.clone().into(),
+                );
+                let pointer =
+                    self.append_unemitted(ctx, &mut block, Expression::LocalVariable(local), span)?;
+                outputs.push(pointer);
+                TypedExpression::reference(pointer)
+            } else {
+                let argument = self.append_unemitted(
+                    ctx,
+                    &mut block,
+                    Expression::FunctionArgument(argument_index),
+                    span,
+                )?;
+                argument_index += 1;
+                if parameter.mode != ParameterMode::In {
+                    TypedExpression::reference(argument)
+                } else if assigned.contains(parameter.name) {
+                    // Parameters are values in the IR, so copy the assigned ones
+                    let local = ctx.function.local_variables.append(
+                        crate::LocalVariable {
+                            name: Some(parameter.name.to_string()),
+                            ty: parameter.ty,
+                            init: None,
+                        },
+                        span.clone().into(),
+                    );
+                    let pointer = self.append_unemitted(
+                        ctx,
+                        &mut block,
+                        Expression::LocalVariable(local),
+                        span,
+                    )?;
+                    self.push_statement(
+                        ctx,
+                        &mut block,
+                        Statement::Store {
+                            pointer,
+                            value: argument,
+                        },
+                        span,
+                    );
+                    TypedExpression::reference(pointer)
+                } else {
+                    TypedExpression::value(argument)
+                }
+            };
+            let scope = ctx.scopes.last_mut().unwrap();
+            if scope.contains_key(parameter.name) {
+                return Err(Error::Redefinition(span.clone(), parameter.name));
+            }
+            scope.insert(parameter.name.to_string(), expression);
+        }
+        if let ReturnKind::EntryPointOutputs {
+            outputs: ref mut return_outputs,
+            ..
+        } = ctx.return_kind
+        {
+            *return_outputs = outputs;
+        }
+
+        lexer.expect_punct("{")?;
+        while !lexer.skip(Token::Punct("}")) {
+            self.parse_statement(lexer, ctx, &mut block)?;
+        }
+
+        // Falling off the end of an entry point returns its outputs
+        if let ReturnKind::EntryPointOutputs {
+            has_result: false, ..
+        } = ctx.return_kind
+        {
+            match block.last() {
+                Some(&Statement::Return { .. }) | Some(&Statement::Kill) => {}
+                _ => {
+                    let span = lexer.span_from(lexer.next_start());
+                    let value = self.output_value(ctx, &mut block, None, &span)?;
+                    self.push_statement(
+                        ctx,
+                        &mut block,
+                        Statement::Return { value: Some(value) },
+                        &span,
+                    );
+                }
+            }
+        }
+        block.extend(ctx.emitter.finish(&ctx.function.expressions));
+        Ok(block)
+    }
+
+    /// Compose the output structure of an entry point with `out` parameters.
+    fn output_value<'a>(
+        &mut self,
+        ctx: &mut FunctionContext,
+        block: &mut Block,
+        result: Option<Handle<Expression>>,
+        span: &Span,
+    ) -> Result<Handle<Expression>, Error<'a>> {
+        let (ty, has_result, outputs) = match ctx.return_kind {
+            ReturnKind::EntryPointOutputs {
+                ty,
+                has_result,
+                ref outputs,
+            } => (ty, has_result, outputs.clone()),
+            ReturnKind::Value(_) => unreachable!(),
+        };
+        let member_types = match self.module.types[ty].inner {
+            Ti::Struct { ref members, .. } => {
+                members.iter().map(|member| member.ty).collect::<Vec<_>>()
+            }
+            _ => unreachable!(),
+        };
+        let mut components = Vec::with_capacity(member_types.len());
+        match (has_result, result) {
+            (true, Some(value)) => {
+                components.push(self.convert_to_type(ctx, block, value, member_types[0], span)?)
+            }
+            (false, None) => {}
+            (true, None) => {
+                return Err(Error::BadConversion {
+                    span: span.clone(),
+                    from: "void".to_string(),
+                    to: type_name(
+                        &self.module.types[member_types[0]].inner,
+                        &self.module.types,
+                    ),
+                })
+            }
+            (false, Some(value)) => {
+                return Err(self.conversion_error(ctx, value, "void".to_string(), span))
+            }
+        }
+        for pointer in outputs {
+            components.push(self.append(ctx, Expression::Load { pointer }, span)?);
+        }
+        self.append(ctx, Expression::Compose { ty, components }, span)
+    }
+
+    /// Parse a statement nested in a control flow statement, in its own block.
+    fn parse_nested_statement<'a>(
+        &mut self,
+        lexer: &mut Lexer<'a>,
+        ctx: &mut FunctionContext,
+        block: &mut Block,
+    ) -> Result<Block, Error<'a>> {
+        ctx.flush(block);
+        let mut nested = Block::new();
+        ctx.scopes.push(FastHashMap::default());
+        self.parse_statement(lexer, ctx, &mut nested)?;
+        ctx.scopes.pop();
+        ctx.flush(&mut nested);
+        Ok(nested)
+    }
+
+    /// Check if the lexer is at a local variable declaration.
+    fn is_declaration(&self, lexer: &Lexer) -> bool {
+        match lexer.peek().0 {
+            Token::Word("const") | Token::Word("static") | Token::Word("precise") => true,
+            Token::Word("vector") | Token::Word("matrix") => true,
+            Token::Word(word) if word != "void" && self.is_type_name(word) => {
+                match lexer.peek_second() {
+                    Token::Word(_) => true,
+                    _ => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    fn parse_local_declaration<'a>(
+        &mut self,
+        lexer: &mut Lexer<'a>,
+        ctx: &mut FunctionContext,
+        block: &mut Block,
+    ) -> Result<(), Error<'a>> {
+        let start = lexer.next_start();
+        let modifiers = self.parse_modifiers(lexer)?;
+        if modifiers.is_static && !modifiers.is_const {
+            return Err(Error::Unsupported(
+                lexer.span_from(start),
+                "static local variables",
+            ));
+        }
+        let base = self.parse_data_type(lexer)?;
+        loop {
+            let (name, name_span) = lexer.next_ident()?;
+            let ty = self.parse_array_suffix(lexer, base, false)?;
+            let local = ctx.function.local_variables.append(
+                crate::LocalVariable {
+                    name: Some(name.to_string()),
+                    ty,
+                    init: None,
+                },
+                name_span.clone().into(),
+            );
+            let pointer =
+                self.append_unemitted(ctx, block, Expression::LocalVariable(local), &name_span)?;
+            if lexer.skip(Token::Punct("=")) {
+                let value_start = lexer.next_start();
+                let value = self.parse_initializer(lexer, ctx, block, ty)?;
+                let span = lexer.span_from(value_start);
+                // Constant initializers are only applied once, so they can't
+                // be used for the variables declared in loops.
+                let constant = if ctx.in_loop() {
+                    None
+                } else {
+                    self.solve_constant(ctx, value, span.clone()).ok()
+                };
+                match constant {
+                    Some(constant) => {
+                        ctx.function.local_variables.get_mut(local).init = Some(constant)
+                    }
+                    None => {
+                        self.push_statement(ctx, block, Statement::Store { pointer, value }, &span)
+                    }
+                }
+            }
+            let scope = ctx.scopes.last_mut().unwrap();
+            if scope.contains_key(name) {
+                return Err(Error::Redefinition(name_span, name));
+            }
+            scope.insert(name.to_string(), TypedExpression::reference(pointer));
+            if !lexer.skip(Token::Punct(",")) {
+                break;
+            }
+        }
+        lexer.expect_punct(";")?;
+        Ok(())
+    }
+
+    /// Parse an expression used as a statement, which can be an assignment.
+    /// The trailing `;` isn't consumed.
+    fn parse_expression_statement<'a>(
+        &mut self,
+        lexer: &mut Lexer<'a>,
+        ctx: &mut FunctionContext,
+        block: &mut Block,
+    ) -> Result<(), Error<'a>> {
+        let start = lexer.next_start();
+        let target = match lexer.peek().0 {
+            Token::Punct("++") | Token::Punct("--") => Some(self.parse_unary(lexer, ctx, block)?),
+            _ => self.parse_postfix(lexer, ctx, block)?,
+        };
+        let target = match target {
+            Some(target) => target,
+            None => return Ok(()),
+        };
+        match lexer.peek().0 {
+            Token::Punct("=") => {
+                let _ = lexer.next();
+                let value = self.parse_value(lexer, ctx, block)?;
+                self.assign(ctx, block, target, value, &lexer.span_from(start))
+            }
+            Token::Punct(punct) if compound_operator(punct).is_some() => {
+                let _ = lexer.next();
+                let op = compound_operator(punct).unwrap();
+                let value = self.parse_value(lexer, ctx, block)?;
+                let span = lexer.span_from(start);
+                let old = self.apply_load(ctx, target, &span)?;
+                let new = self.binary(ctx, block, op, old, value, &span)?;
+                self.assign(ctx, block, target, new, &span)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn parse_const_i32<'a>(&mut self, lexer: &mut Lexer<'a>) -> Result<i32, Error<'a>> {
+        let start = lexer.next_start();
+        let constant = self.parse_global_constant(lexer, None)?;
+        match self.module.constants[constant].inner {
+            crate::ConstantInner::Scalar {
+                value: crate::ScalarValue::Sint(value),
+                ..
+            } => Ok(value as i32),
+            crate::ConstantInner::Scalar {
+                value: crate::ScalarValue::Uint(value),
+                ..
+            } => Ok(value as i32),
+            _ => Err(Error::NotConstant(lexer.span_from(start))),
+        }
+    }
+
+    /// Build `if (!condition) { break; }` for loops.
+    fn push_loop_condition<'a>(
+        &mut self,
+        lexer: &mut Lexer<'a>,
+        ctx: &mut FunctionContext,
+        block: &mut Block,
+    ) -> Result<(), Error<'a>> {
+        let start = lexer.next_start();
+        let condition = self.parse_value(lexer, ctx, block)?;
+        let span = lexer.span_from(start);
+        let condition = self.convert(
+            ctx,
+            block,
+            condition,
+            Shape::Scalar {
+                kind: Sk::Bool,
+                width: crate::BOOL_WIDTH,
+            },
+            &span,
+        )?;
+        let mut reject = Block::new();
+        reject.push(Statement::Break, span.clone().into());
+        self.push_statement(
+            ctx,
+            block,
+            Statement::If {
+                condition,
+                accept: Block::new(),
+                reject,
+            },
+            &span,
+        );
+        Ok(())
+    }
+
+    fn parse_statement<'a>(
+        &mut self,
+        lexer: &mut Lexer<'a>,
+        ctx: &mut FunctionContext,
+        block: &mut Block,
+    ) -> Result<(), Error<'a>> {
+        // Attributes like `[unroll]` are only hints
+        let _ = self.parse_attributes(lexer)?;
+        let (token, span) = lexer.peek();
+        match token {
+            Token::Punct(";") => {
+                let _ = lexer.next();
+            }
+            Token::Punct("{") => {
+                let _ = lexer.next();
+                ctx.scopes.push(FastHashMap::default());
+                while !lexer.skip(Token::Punct("}")) {
+                    self.parse_statement(lexer, ctx, block)?;
+                }
+                ctx.scopes.pop();
+            }
+            Token::Directive => return Err(Error::Directive(span)),
+            Token::Word("if") => {
+                let _ = lexer.next();
+                lexer.expect_punct("(")?;
+                let start = lexer.next_start();
+                let condition = self.parse_value(lexer, ctx, block)?;
+                let condition_span = lexer.span_from(start);
+                let condition = self.convert(
+                    ctx,
+                    block,
+                    condition,
+                    Shape::Scalar {
+                        kind: Sk::Bool,
+                        width: crate::BOOL_WIDTH,
+                    },
+                    &condition_span,
+                )?;
+                lexer.expect_punct(")")?;
+                let accept = self.parse_nested_statement(lexer, ctx, block)?;
+                let reject = if lexer.skip(Token::Word("else")) {
+                    self.parse_nested_statement(lexer, ctx, block)?
+                } else {
+                    Block::new()
+                };
+                self.push_statement(
+                    ctx,
+                    block,
+                    Statement::If {
+                        condition,
+                        accept,
+                        reject,
+                    },
+                    &lexer.span_from(span.start),
+                );
+            }
+            Token::Word("for") => {
+                let _ = lexer.next();
+                lexer.expect_punct("(")?;
+                ctx.scopes.push(FastHashMap::default());
+                if !lexer.skip(Token::Punct(";")) {
+                    if self.is_declaration(lexer) {
+                        self.parse_local_declaration(lexer, ctx, block)?;
+                    } else {
+                        self.parse_expression_statement(lexer, ctx, block)?;
+                        lexer.expect_punct(";")?;
+                    }
+                }
+                ctx.flush(block);
+                let mut body = Block::new();
+                if !lexer.skip(Token::Punct(";")) {
+                    self.push_loop_condition(lexer, ctx, &mut body)?;
+                    lexer.expect_punct(";")?;
+                }
+                let mut continuing = Block::new();
+                if !lexer.skip(Token::Punct(")")) {
+                    loop {
+                        self.parse_expression_statement(lexer, ctx, &mut continuing)?;
+                        if !lexer.skip(Token::Punct(",")) {
+                            break;
+                        }
+                    }
+                    lexer.expect_punct(")")?;
+                }
+                ctx.flush(&mut continuing);
+                ctx.breakables.push(Breakable::Loop);
+                self.parse_statement(lexer, ctx, &mut body)?;
+                ctx.breakables.pop();
+                ctx.scopes.pop();
+                ctx.flush(&mut body);
+                self.push_statement(
+                    ctx,
+                    block,
+                    Statement::Loop { body, continuing },
+                    &lexer.span_from(span.start),
+                );
+            }
+            Token::Word("while") => {
+                let _ = lexer.next();
+                lexer.expect_punct("(")?;
+                ctx.flush(block);
+                let mut body = Block::new();
+                self.push_loop_condition(lexer, ctx, &mut body)?;
+                lexer.expect_punct(")")?;
+                ctx.breakables.push(Breakable::Loop);
+                ctx.scopes.push(FastHashMap::default());
+                self.parse_statement(lexer, ctx, &mut body)?;
+                ctx.scopes.pop();
+                ctx.breakables.pop();
+                ctx.flush(&mut body);
+                self.push_statement(
+                    ctx,
+                    block,
+                    Statement::Loop {
+                        body,
+                        continuing: Block::new(),
+                    },
+                    &lexer.span_from(span.start),
+                );
+            }
+            Token::Word("do") => {
+                let _ = lexer.next();
+                ctx.flush(block);
+                let mut body = Block::new();
+                ctx.breakables.push(Breakable::DoWhile);
+                ctx.scopes.push(FastHashMap::default());
+                self.parse_statement(lexer, ctx, &mut body)?;
+                ctx.scopes.pop();
+                ctx.breakables.pop();
+                lexer.expect(Token::Word("while"))?;
+                lexer.expect_punct("(")?;
+                self.push_loop_condition(lexer, ctx, &mut body)?;
+                lexer.expect_punct(")")?;
+                lexer.expect_punct(";")?;
+                ctx.flush(&mut body);
+                self.push_statement(
+                    ctx,
+                    block,
+                    Statement::Loop {
+                        body,
+                        continuing: Block::new(),
+                    },
+                    &lexer.span_from(span.start),
+                );
+            }
+            Token::Word("switch") => {
+                let _ = lexer.next();
+                lexer.expect_punct("(")?;
+                let start = lexer.next_start();
+                let selector = self.parse_value(lexer, ctx, block)?;
+                let selector_span = lexer.span_from(start);
+                let selector = self.convert(
+                    ctx,
+                    block,
+                    selector,
+                    Shape::Scalar {
+                        kind: Sk::Sint,
+                        width: 4,
+                    },
+                    &selector_span,
+                )?;
+                lexer.expect_punct(")")?;
+                lexer.expect_punct("{")?;
+                ctx.flush(block);
+                ctx.breakables.push(Breakable::Switch);
+                let mut cases = Vec::new();
+                let mut default = None;
+                while !lexer.skip(Token::Punct("}")) {
+                    let value = match lexer.next() {
+                        (Token::Word("case"), _) => Some(self.parse_const_i32(lexer)?),
+                        (Token::Word("default"), _) => None,
+                        (token, span) => {
+                            return Err(Error::Unexpected(token, span, "'case' or 'default'"))
+                        }
+                    };
+                    let label_span = lexer.expect_punct(":")?;
+                    let mut body = Block::new();
+                    ctx.scopes.push(FastHashMap::default());
+                    loop {
+                        match lexer.peek().0 {
+                            Token::Word("case") | Token::Word("default") | Token::Punct("}") => {
+                                break
+                            }
+                            _ => self.parse_statement(lexer, ctx, &mut body)?,
+                        }
+                    }
+                    ctx.scopes.pop();
+                    ctx.flush(&mut body);
+                    let fall_through = match body.last() {
+                        Some(&Statement::Break) => {
+                            body.cull(body.len() - 1..);
+                            false
+                        }
+                        Some(&Statement::Return { .. })
+                        | Some(&Statement::Continue)
+                        | Some(&Statement::Kill) => false,
+                        _ => true,
+                    };
+                    match value {
+                        Some(value) => cases.push(crate::SwitchCase {
+                            value,
+                            body,
+                            fall_through,
+                        }),
+                        None => {
+                            if fall_through && lexer.peek().0 != Token::Punct("}") {
+                                return Err(Error::Unsupported(
+                                    label_span,
+                                    "fall-throughs out of `default` cases",
+                                ));
+                            }
+                            default = Some(body);
+                        }
+                    }
+                }
+                ctx.breakables.pop();
+                self.push_statement(
+                    ctx,
+                    block,
+                    Statement::Switch {
+                        selector,
+                        cases,
+                        default: default.unwrap_or_default(),
+                    },
+                    &lexer.span_from(span.start),
+                );
+            }
+            Token::Word("return") => {
+                let _ = lexer.next();
+                let start = lexer.next_start();
+                let value = if lexer.peek().0 == Token::Punct(";") {
+                    None
+                } else {
+                    Some(self.parse_value(lexer, ctx, block)?)
+                };
+                let value_span = lexer.span_from(start);
+                lexer.expect_punct(";")?;
+                let value = match ctx.return_kind {
+                    ReturnKind::Value(None) => match value {
+                        Some(value) => {
+                            return Err(self.conversion_error(
+                                ctx,
+                                value,
+                                "void".to_string(),
+                                &value_span,
+                            ))
+                        }
+                        None => None,
+                    },
+                    ReturnKind::Value(Some(ty)) => match value {
+                        Some(value) => {
+                            Some(self.convert_to_type(ctx, block, value, ty, &value_span)?)
+                        }
+                        None => {
+                            return Err(Error::BadConversion {
+                                span: value_span,
+                                from: "void".to_string(),
+                                to: type_name(&self.module.types[ty].inner, &self.module.types),
+                            })
+                        }
+                    },
+                    ReturnKind::EntryPointOutputs { .. } => {
+                        Some(self.output_value(ctx, block, value, &value_span)?)
+                    }
+                };
+                self.push_statement(
+                    ctx,
+                    block,
+                    Statement::Return { value },
+                    &lexer.span_from(span.start),
+                );
+            }
+            Token::Word("discard") => {
+                let _ = lexer.next();
+                lexer.expect_punct(";")?;
+                self.push_statement(ctx, block, Statement::Kill, &span);
+            }
+            Token::Word("break") => {
+                let _ = lexer.next();
+                lexer.expect_punct(";")?;
+                if ctx.breakables.is_empty() {
+                    return Err(Error::OutsideOfLoop(span, "break"));
+                }
+                self.push_statement(ctx, block, Statement::Break, &span);
+            }
+            Token::Word("continue") => {
+                let _ = lexer.next();
+                lexer.expect_punct(";")?;
+                let breakable = ctx
+                    .breakables
+                    .iter()
+                    .rev()
+                    .find(|&&breakable| breakable != Breakable::Switch);
+                match breakable {
+                    Some(&Breakable::Loop) => {
+                        self.push_statement(ctx, block, Statement::Continue, &span)
+                    }
+                    Some(_) => {
+                        return Err(Error::Unsupported(
+                            span,
+                            "`continue` statements in `do`-`while` loops",
+                        ))
+                    }
+                    None => return Err(Error::OutsideOfLoop(span, "continue")),
+                }
+            }
+            _ if self.is_declaration(lexer) => self.parse_local_declaration(lexer, ctx, block)?,
+            _ => {
+                self.parse_expression_statement(lexer, ctx, block)?;
+                lexer.expect_punct(";")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Find the `in` parameters that are assigned in the function body, which
+/// starts at the lexer position.
+fn scan_assigned_parameters<'a>(
+    lexer: &Lexer<'a>,
+    parameters: &[Parameter<'a>],
+) -> FastHashSet<&'a str> {
+    let names = parameters
+        .iter()
+        .filter(|parameter| parameter.mode == ParameterMode::In)
+        .map(|parameter| parameter.name)
+        .collect::<Vec<_>>();
+    let mut lexer = lexer.clone();
+    let mut assigned = FastHashSet::default();
+    let mut depth = 0;
+    let mut previous = Token::End;
+    loop {
+        let token = lexer.next().0;
+        match token {
+            Token::End => break,
+            Token::Punct("{") => depth += 1,
+            Token::Punct("}") => {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+            Token::Word(word) if previous != Token::Punct(".") && names.contains(&word) => {
+                if previous == Token::Punct("++") || previous == Token::Punct("--") {
+                    assigned.insert(word);
+                } else {
+                    // Skip the member accesses and indices
+                    let mut ahead = lexer.clone();
+                    let mut next = ahead.next().0;
+                    loop {
+                        match next {
+                            Token::Punct(".") => {
+                                let _ = ahead.next();
+                            }
+                            Token::Punct("[") => {
+                                let mut nesting = 1;
+                                while nesting != 0 {
+                                    match ahead.next().0 {
+                                        Token::Punct("[") => nesting += 1,
+                                        Token::Punct("]") => nesting -= 1,
+                                        Token::End => break,
+                                        _ => {}
+                                    }
+                                }
+                            }
+                            _ => break,
+                        }
+                        next = ahead.next().0;
+                    }
+                    match next {
+                        Token::Punct(punct)
+                            if ASSIGNMENT_OPERATORS.contains(&punct)
+                                || punct == "++"
+                                || punct == "--" =>
+                        {
+                            assigned.insert(word);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+        previous = token;
+    }
+    assigned
+}
@@ -0,0 +1,269 @@
+use super::{Error, Span};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Token<'a> {
+    Word(&'a str),
+    Number(&'a str),
+    String(&'a str),
+    Punct(&'static str),
+    Directive,
+    Unknown(char),
+    End,
+}
+
+/// Punctuation, longest sequences first.
+const PUNCTUATION: &[&str] = &[
+    "<<=", ">>=", "==", "!=", "<=", ">=", "&&", "||", "<<", ">>", "+=", "-=", "*=", "/=", "%=",
+    "&=", "|=", "^=", "++", "--", "::", "(", ")", "[", "]", "{", "}", ";", ",", ".", ":", "?", "~",
+    "!", "+", "-", "*", "/", "%", "&", "|", "^", "<", ">", "=",
+];
+
+/// Preprocessor directives that don't affect the meaning of the source.
+const IGNORED_DIRECTIVES: &[&str] = &["pragma", "line"];
+
+fn consume_any(input: &str, what: impl Fn(char) -> bool) -> (&str, &str) {
+    let pos = input.find(|c| !what(c)).unwrap_or(input.len());
+    input.split_at(pos)
+}
+
+fn consume_number(input: &str) -> (&str, &str) {
+    let is_hex = input.starts_with("0x") || input.starts_with("0X");
+    let mut prev = '\0';
+    let pos = input
+        .char_indices()
+        .find(|&(_, c)| {
+            let is_exponent_sign =
+                !is_hex && (c == '+' || c == '-') && (prev == 'e' || prev == 'E');
+            prev = c;
+            !(c.is_ascii_alphanumeric() || c == '.' || c == '_' || is_exponent_sign)
+        })
+        .map_or(input.len(), |(pos, _)| pos);
+    input.split_at(pos)
+}
+
+fn consume_token(input: &str, at_line_start: bool) -> (Token<'_>, &str) {
+    let mut chars = input.chars();
+    let cur = match chars.next() {
+        Some(c) => c,
+        None => return (Token::End, input),
+    };
+    match cur {
+        '0'..='9' => {
+            let (number, rest) = consume_number(input);
+            (Token::Number(number), rest)
+        }
+        '.' if matches!(chars.next(), Some(c) if c.is_ascii_digit()) => {
+            let (number, rest) = consume_number(input);
+            (Token::Number(number), rest)
+        }
+        'a'..='z' | 'A'..='Z' | '_' => {
+            let (word, rest) = consume_any(input, |c| c.is_ascii_alphanumeric() || c == '_');
+            (Token::Word(word), rest)
+        }
+        '"' => match chars.as_str().find('"') {
+            Some(end) => {
+                let rest = &input[1..];
+                (Token::String(&rest[..end]), &rest[end + 1..])
+            }
+            None => (Token::Unknown(cur), chars.as_str()),
+        },
+        '#' if at_line_start => {
+            let (_, rest) = consume_any(input, |c| c != '\n');
+            (Token::Directive, rest)
+        }
+        _ => match PUNCTUATION.iter().find(|punct| input.starts_with(*punct)) {
+            Some(punct) => (Token::Punct(punct), &input[punct.len()..]),
+            None => (Token::Unknown(cur), chars.as_str()),
+        },
+    }
+}
+
+#[derive(Clone)]
+pub struct Lexer<'a> {
+    input: &'a str,
+    source: &'a str,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Lexer {
+            input,
+            source: input,
+        }
+    }
+
+    fn current_byte_offset(&self) -> usize {
+        self.source.len() - self.input.len()
+    }
+
+    /// Skip whitespace and comments, returning whether the next token
+    /// starts a line.
+    fn skip_trivia(&mut self) -> bool {
+        let mut at_line_start = self.source[..self.current_byte_offset()]
+            .trim_end_matches(&[' ', '\t'][..])
+            .ends_with('\n')
+            || self.current_byte_offset() == 0;
+        loop {
+            let trimmed = self.input.trim_start();
+            if trimmed.len() != self.input.len() {
+                at_line_start |= self.input[..self.input.len() - trimmed.len()].contains('\n');
+                self.input = trimmed;
+            }
+            if self.input.starts_with("//") {
+                let (_, rest) = consume_any(self.input, |c| c != '\n');
+                self.input = rest;
+            } else if self.input.starts_with("/*") {
+                self.input = match self.input[2..].find("*/") {
+                    Some(end) => &self.input[end + 4..],
+                    None => "",
+                };
+            } else if at_line_start && self.input.starts_with('#') {
+                let (line, rest) = consume_any(self.input, |c| c != '\n');
+                let (_, directive) = consume_any(&line[1..], |c| c == ' ' || c == '\t');
+                let (name, _) = consume_any(directive, |c| c.is_ascii_alphanumeric());
+                if !IGNORED_DIRECTIVES.contains(&name) {
+                    return at_line_start;
+                }
+                self.input = rest;
+            } else {
+                return at_line_start;
+            }
+        }
+    }
+
+    pub fn next(&mut self) -> (Token<'a>, Span) {
+        let at_line_start = self.skip_trivia();
+        let start = self.current_byte_offset();
+        let (token, rest) = consume_token(self.input, at_line_start);
+        self.input = rest;
+        let end = self.source[..self.current_byte_offset()].trim_end().len();
+        (token, start..end.max(start))
+    }
+
+    pub fn peek(&self) -> (Token<'a>, Span) {
+        self.clone().next()
+    }
+
+    /// Returns the token following the next one.
+    pub fn peek_second(&self) -> Token<'a> {
+        let mut lexer = self.clone();
+        let _ = lexer.next();
+        lexer.next().0
+    }
+
+    pub fn skip(&mut self, what: Token<'_>) -> bool {
+        if self.peek().0 == what {
+            let _ = self.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn expect(&mut self, expected: Token<'a>) -> Result<Span, Error<'a>> {
+        match self.next() {
+            (token, span) if token == expected => Ok(span),
+            (token, span) => Err(Error::Unexpected(token, span, describe(expected))),
+        }
+    }
+
+    pub fn expect_punct(&mut self, punct: &'static str) -> Result<Span, Error<'a>> {
+        self.expect(Token::Punct(punct))
+    }
+
+    /// Expects the `>` closing a template argument list, splitting `>>`.
+    pub fn expect_template_end(&mut self) -> Result<(), Error<'a>> {
+        match self.peek() {
+            (Token::Punct(">>"), _) | (Token::Punct(">>="), _) | (Token::Punct(">="), _) => {
+                self.skip_trivia();
+                self.input = &self.input[1..];
+                Ok(())
+            }
+            _ => self.expect_punct(">").map(|_| ()),
+        }
+    }
+
+    pub fn next_ident(&mut self) -> Result<(&'a str, Span), Error<'a>> {
+        match self.next() {
+            (Token::Word(word), span) => Ok((word, span)),
+            (token, span) => Err(Error::Unexpected(token, span, "identifier")),
+        }
+    }
+
+    pub fn span_from(&self, start: usize) -> Span {
+        let end = self.source[..self.current_byte_offset()].trim_end().len();
+        start..end.max(start)
+    }
+
+    pub fn next_start(&self) -> usize {
+        self.peek().1.start
+    }
+}
+
+pub fn describe(token: Token<'_>) -> &'static str {
+    match token {
+        Token::Punct(punct) => match punct {
+            "(" => "'('",
+            ")" => "')'",
+            "[" => "'['",
+            "]" => "']'",
+            "{" => "'{'",
+            "}" => "'}'",
+            ";" => "';'",
+            "," => "','",
+            ":" => "':'",
+            "=" => "'='",
+            "<" => "'<'",
+            ">" => "'>'",
+            _ => "punctuation",
+        },
+        Token::Word(_) => "identifier",
+        Token::Number(_) => "number",
+        Token::String(_) => "string",
+        Token::Directive => "preprocessor directive",
+        Token::Unknown(_) => "unknown character",
+        Token::End => "end of file",
+    }
+}
+
+/// Describe a token found in place of an expected one, quoting its text
+/// when it has one.
+pub fn describe_found(token: Token<'_>) -> String {
+    match token {
+        Token::Punct(text) | Token::Word(text) | Token::Number(text) => format!("'{}'", text),
+        _ => describe(token).to_string(),
+    }
+}
+
+#[test]
+fn tokens() {
+    let mut lexer =
+        Lexer::new("#pragma once\nfloat4 x/*c*/ = 1.5e-3f;//tail\n RWTexture2D<float4>>= .5 0x1Fu");
+    let mut tokens = Vec::new();
+    loop {
+        match lexer.next().0 {
+            Token::End => break,
+            token => tokens.push(token),
+        }
+    }
+    assert_eq!(
+        tokens,
+        [
+            Token::Word("float4"),
+            Token::Word("x"),
+            Token::Punct("="),
+            Token::Number("1.5e-3f"),
+            Token::Punct(";"),
+            Token::Word("RWTexture2D"),
+            Token::Punct("<"),
+            Token::Word("float4"),
+            Token::Punct(">>="),
+            Token::Number(".5"),
+            Token::Number("0x1Fu"),
+        ]
+    );
+
+    let mut lexer = Lexer::new("  #include \"a.hlsl\"\nx");
+    assert_eq!(lexer.next(), (Token::Directive, 2..19));
+    assert_eq!(lexer.next().0, Token::Word("x"));
+}
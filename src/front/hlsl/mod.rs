@@ -0,0 +1,1685 @@
+/*!
+Frontend for [HLSL][hlsl] (High-Level Shading Language).
+
+The parser covers the shader model 5 subset used by vertex, pixel and compute
+shaders: structures with semantics, `cbuffer` blocks, `ConstantBuffer<T>`,
+`StructuredBuffer<T>` and `RWStructuredBuffer<T>`, textures, samplers,
+`static` and `groupshared` globals, functions, and the usual statements,
+operators and intrinsics. The source has to be preprocessed already.
+
+The translation follows a few conventions:
+- A `register(xN, spaceM)` is mapped to the bind group `M`, and the binding
+  `N` shifted by [`RegisterShifts`] for its register type. Resources without
+  a register get the lowest free one in the space 0.
+- System value semantics are mapped to built-ins, and `SV_TargetN` to the
+  location `N`. Every other semantic gets a location of its own, in the order
+  of its first appearance in the module, so that the outputs of one stage
+  match the inputs of the next one.
+- An HLSL `floatRxC` matrix has `C` columns of `R` components in the IR, so
+  its memory layout is the default `column_major` packing.
+- Textures sampled with `SampleCmp` are declared as depth textures.
+- Entry points are the functions with a `[numthreads]` or `[shader]`
+  attribute, and the ones listed in [`Options::entry_points`].
+
+[hlsl]: https://docs.microsoft.com/en-us/windows/win32/direct3dhlsl/dx-graphics-hlsl
+*/
+
+mod conv;
+mod function;
+mod lexer;
+#[cfg(test)]
+mod tests;
+
+use self::{
+    function::{FunctionContext, ReturnKind},
+    lexer::{Lexer, Token},
+};
+use crate::{
+    arena::{Arena, Handle},
+    proc::{Alignment, Layouter, ResolveError},
+    FastHashMap, FastHashSet,
+};
+use codespan_reporting::{
+    diagnostic::{Diagnostic, Label},
+    files::{Files, SimpleFile},
+    term::{
+        self,
+        termcolor::{ColorChoice, NoColor, StandardStream},
+    },
+};
+use std::{borrow::Cow, ops::Range};
+
+type Span = Range<usize>;
+
+/// Offsets added to the register numbers of each register type, to get the
+/// binding numbers.
+///
+/// All the register types share the bindings of a group in the IR, so the
+/// default shifts keep the register ranges of shader model 5 apart.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+#[cfg_attr(feature = "deserialize", derive(serde::Deserialize))]
+pub struct RegisterShifts {
+    /// Shift of the constant buffer registers, `b`.
+    pub constant_buffer: u32,
+    /// Shift of the sampler registers, `s`.
+    pub sampler: u32,
+    /// Shift of the unordered access view registers, `u`.
+    pub unordered_access: u32,
+    /// Shift of the texture and read-only buffer registers, `t`.
+    pub texture: u32,
+}
+
+impl Default for RegisterShifts {
+    fn default() -> Self {
+        RegisterShifts {
+            constant_buffer: 0,
+            sampler: 16,
+            unordered_access: 32,
+            texture: 96,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Options {
+    /// Functions to use as entry points, in addition to the ones with a
+    /// `[numthreads]` or `[shader]` attribute.
+    pub entry_points: FastHashMap<String, crate::ShaderStage>,
+    pub register_shifts: RegisterShifts,
+}
+
+#[derive(Clone, Debug)]
+pub enum Error<'a> {
+    Unexpected(Token<'a>, Span, &'static str),
+    Directive(Span),
+    BadNumber(Span),
+    BadRegister(Span),
+    BadTextureElement(Span),
+    BadSwizzle(Span),
+    BadArgumentCount {
+        span: Span,
+        expected: usize,
+        found: usize,
+    },
+    BadArgument(Span),
+    BadOperands(Span),
+    BadConversion {
+        span: Span,
+        from: String,
+        to: String,
+    },
+    UnknownIdent(Span, &'a str),
+    UnknownType(Span, &'a str),
+    UnknownSemantic(Span, &'a str),
+    UnknownMethod(Span, &'a str),
+    UnknownShaderStage(Span, &'a str),
+    MissingSemantic(Span),
+    NotConstant(Span),
+    NotAssignable(Span),
+    VoidValue(Span),
+    OutsideOfLoop(Span, &'static str),
+    Redefinition(Span, &'a str),
+    InvalidResolve(Span, ResolveError),
+    Unsupported(Span, &'static str),
+}
+
+impl<'a> Error<'a> {
+    fn as_parse_error(&self) -> ParseError {
+        match *self {
+            Error::Unexpected(token, ref span, expected) => ParseError {
+                message: format!(
+                    "expected {}, found {}",
+                    expected,
+                    lexer::describe_found(token)
+                ),
+                labels: vec![(span.clone(), format!("expected {}", expected).into())],
+                notes: vec![],
+            },
+            Error::Directive(ref span) => ParseError {
+                message: "preprocessor directives are not supported".to_string(),
+                labels: vec![(span.clone(), "directive".into())],
+                notes: vec!["preprocess the source before parsing it".to_string()],
+            },
+            Error::BadNumber(ref span) => ParseError {
+                message: "invalid numeric literal".to_string(),
+                labels: vec![(span.clone(), "invalid literal".into())],
+                notes: vec![],
+            },
+            Error::BadRegister(ref span) => ParseError {
+                message: "invalid register for this resource".to_string(),
+                labels: vec![(span.clone(), "register".into())],
+                notes: vec![],
+            },
+            Error::BadTextureElement(ref span) => ParseError {
+                message: "invalid texture element type".to_string(),
+                labels: vec![(span.clone(), "element type".into())],
+                notes: vec![],
+            },
+            Error::BadSwizzle(ref span) => ParseError {
+                message: "invalid swizzle".to_string(),
+                labels: vec![(span.clone(), "swizzle".into())],
+                notes: vec![],
+            },
+            Error::BadArgumentCount {
+                ref span,
+                expected,
+                found,
+            } => ParseError {
+                message: format!("expected {} arguments, found {}", expected, found),
+                labels: vec![(span.clone(), "call".into())],
+                notes: vec![],
+            },
+            Error::BadArgument(ref span) => ParseError {
+                message: "invalid argument type".to_string(),
+                labels: vec![(span.clone(), "argument".into())],
+                notes: vec![],
+            },
+            Error::BadOperands(ref span) => ParseError {
+                message: "invalid operand types".to_string(),
+                labels: vec![(span.clone(), "operation".into())],
+                notes: vec![],
+            },
+            Error::BadConversion {
+                ref span,
+                ref from,
+                ref to,
+            } => ParseError {
+                message: format!("cannot convert `{}` to `{}`", from, to),
+                labels: vec![(
+                    span.clone(),
+                    format!("expression of type `{}`", from).into(),
+                )],
+                notes: vec![],
+            },
+            Error::UnknownIdent(ref span, name) => ParseError {
+                message: format!("no definition in scope for identifier: '{}'", name),
+                labels: vec![(span.clone(), "unknown identifier".into())],
+                notes: vec![],
+            },
+            Error::UnknownType(ref span, name) => ParseError {
+                message: format!("unknown type: '{}'", name),
+                labels: vec![(span.clone(), "unknown type".into())],
+                notes: vec![],
+            },
+            Error::UnknownSemantic(ref span, name) => ParseError {
+                message: format!("unknown semantic: '{}'", name),
+                labels: vec![(span.clone(), "unknown semantic".into())],
+                notes: vec![],
+            },
+            Error::UnknownMethod(ref span, name) => ParseError {
+                message: format!("unknown method: '{}'", name),
+                labels: vec![(span.clone(), "unknown method".into())],
+                notes: vec![],
+            },
+            Error::UnknownShaderStage(ref span, name) => ParseError {
+                message: format!("unknown shader stage: '{}'", name),
+                labels: vec![(span.clone(), "unknown shader stage".into())],
+                notes: vec![],
+            },
+            Error::MissingSemantic(ref span) => ParseError {
+                message: "entry point interface is missing a semantic".to_string(),
+                labels: vec![(span.clone(), "needs a semantic".into())],
+                notes: vec![],
+            },
+            Error::NotConstant(ref span) => ParseError {
+                message: "expected a constant expression".to_string(),
+                labels: vec![(span.clone(), "not constant".into())],
+                notes: vec![],
+            },
+            Error::NotAssignable(ref span) => ParseError {
+                message: "expression is not assignable".to_string(),
+                labels: vec![(span.clone(), "not an l-value".into())],
+                notes: vec![],
+            },
+            Error::VoidValue(ref span) => ParseError {
+                message: "expression doesn't have a value".to_string(),
+                labels: vec![(span.clone(), "call of a `void` function".into())],
+                notes: vec![],
+            },
+            Error::OutsideOfLoop(ref span, statement) => ParseError {
+                message: format!("`{}` statement outside of a loop", statement),
+                labels: vec![(span.clone(), statement.into())],
+                notes: vec![],
+            },
+            Error::Redefinition(ref span, name) => ParseError {
+                message: format!("redefinition of '{}'", name),
+                labels: vec![(span.clone(), "redefinition".into())],
+                notes: vec![],
+            },
+            Error::InvalidResolve(ref span, ref error) => ParseError {
+                message: error.to_string(),
+                labels: vec![(span.clone(), "invalid expression".into())],
+                notes: vec![],
+            },
+            Error::Unsupported(ref span, what) => ParseError {
+                message: format!("{} are not supported", what),
+                labels: vec![(span.clone(), "unsupported".into())],
+                notes: vec![],
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ParseError {
+    message: String,
+    labels: Vec<(Span, Cow<'static, str>)>,
+    notes: Vec<String>,
+}
+
+impl ParseError {
+    fn diagnostic(&self) -> Diagnostic<()> {
+        Diagnostic::error()
+            .with_message(self.message.to_string())
+            .with_labels(
+                self.labels
+                    .iter()
+                    .map(|label| {
+                        Label::primary((), label.0.clone()).with_message(label.1.to_string())
+                    })
+                    .collect(),
+            )
+            .with_notes(
+                self.notes
+                    .iter()
+                    .map(|note| format!("note: {}", note))
+                    .collect(),
+            )
+    }
+
+    /// Emits a summary of the error to standard error stream.
+    pub fn emit_to_stderr(&self, source: &str) {
+        let files = SimpleFile::new("hlsl", source);
+        let config = term::Config::default();
+        let writer = StandardStream::stderr(ColorChoice::Always);
+        term::emit(&mut writer.lock(), &config, &files, &self.diagnostic())
+            .expect("cannot write error");
+    }
+
+    /// Emits a summary of the error to a string.
+    pub fn emit_to_string(&self, source: &str) -> String {
+        let files = SimpleFile::new("hlsl", source);
+        let config = term::Config::default();
+        let mut writer = NoColor::new(Vec::new());
+        term::emit(&mut writer, &config, &files, &self.diagnostic()).expect("cannot write error");
+        String::from_utf8(writer.into_inner()).unwrap()
+    }
+
+    /// Returns the 1-based line number and column of the first label in the
+    /// error message.
+    pub fn location(&self, source: &str) -> (usize, usize) {
+        let files = SimpleFile::new("hlsl", source);
+        match self.labels.first() {
+            Some(label) => {
+                let location = files
+                    .location((), label.0.start)
+                    .expect("invalid span location");
+                (location.line_number, location.column_number)
+            }
+            None => (1, 1),
+        }
+    }
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        None
+    }
+}
+
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq)]
+enum RegisterType {
+    ConstantBuffer,
+    Sampler,
+    UnorderedAccess,
+    Texture,
+}
+
+impl RegisterType {
+    fn from_letter(letter: char) -> Option<Self> {
+        match letter.to_ascii_lowercase() {
+            'b' => Some(RegisterType::ConstantBuffer),
+            's' => Some(RegisterType::Sampler),
+            'u' => Some(RegisterType::UnorderedAccess),
+            't' => Some(RegisterType::Texture),
+            _ => None,
+        }
+    }
+
+    fn shift(self, shifts: &RegisterShifts) -> u32 {
+        match self {
+            RegisterType::ConstantBuffer => shifts.constant_buffer,
+            RegisterType::Sampler => shifts.sampler,
+            RegisterType::UnorderedAccess => shifts.unordered_access,
+            RegisterType::Texture => shifts.texture,
+        }
+    }
+}
+
+/// A global name that expressions can refer to.
+#[derive(Clone, Copy, Debug)]
+enum GlobalLookup {
+    Variable(Handle<crate::GlobalVariable>),
+    /// A member of a `cbuffer`, or the array of a structured buffer.
+    Member(Handle<crate::GlobalVariable>, u32),
+    Constant(Handle<crate::Constant>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ParameterMode {
+    In,
+    Out,
+    InOut,
+}
+
+#[derive(Clone, Debug)]
+struct FunctionDecl {
+    handle: Handle<crate::Function>,
+    modes: Vec<ParameterMode>,
+}
+
+/// A parsed type, which isn't always a data type.
+#[derive(Clone, Copy, Debug)]
+enum TypeDecl {
+    Data(Handle<crate::Type>),
+    Texture {
+        kind: conv::TextureKind,
+        element: Option<(
+            crate::ScalarKind,
+            Option<crate::VectorSize>,
+            Option<crate::ScalarKind>,
+        )>,
+        span: (usize, usize),
+    },
+    Sampler {
+        comparison: bool,
+    },
+    StructuredBuffer {
+        element: Handle<crate::Type>,
+        writable: bool,
+    },
+    ConstantBuffer(Handle<crate::Type>),
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Modifiers {
+    is_static: bool,
+    is_const: bool,
+    groupshared: bool,
+    mode: Option<ParameterMode>,
+    interpolation: Option<crate::Interpolation>,
+    sampling: Option<crate::Sampling>,
+}
+
+#[derive(Clone, Debug)]
+enum Attribute {
+    NumThreads([u32; 3]),
+    Shader(crate::ShaderStage),
+    EarlyDepthStencil,
+}
+
+#[derive(Clone, Debug)]
+struct Parameter<'a> {
+    name: &'a str,
+    name_span: Span,
+    ty: Handle<crate::Type>,
+    mode: ParameterMode,
+    binding: Option<crate::Binding>,
+}
+
+pub struct Parser<'o> {
+    options: &'o Options,
+    module: crate::Module,
+    layouter: Layouter,
+    lookup_type: FastHashMap<String, Handle<crate::Type>>,
+    lookup_global: FastHashMap<String, GlobalLookup>,
+    lookup_function: FastHashMap<String, FunctionDecl>,
+    varying_locations: FastHashMap<String, u32>,
+    comparison_textures: FastHashSet<String>,
+    used_registers: FastHashSet<(RegisterType, u32, u32)>,
+    unassigned_registers: Vec<(Handle<crate::GlobalVariable>, RegisterType)>,
+}
+
+impl<'o> Parser<'o> {
+    pub fn new(options: &'o Options) -> Self {
+        Parser {
+            options,
+            module: crate::Module::default(),
+            layouter: Layouter::default(),
+            lookup_type: FastHashMap::default(),
+            lookup_global: FastHashMap::default(),
+            lookup_function: FastHashMap::default(),
+            varying_locations: FastHashMap::default(),
+            comparison_textures: FastHashSet::default(),
+            used_registers: FastHashSet::default(),
+            unassigned_registers: Vec::new(),
+        }
+    }
+
+    pub fn parse(mut self, source: &str) -> Result<crate::Module, ParseError> {
+        self.scan_comparison_textures(source);
+        let mut lexer = Lexer::new(source);
+        loop {
+            match self.parse_global_item(&mut lexer) {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(error) => return Err(error.as_parse_error()),
+            }
+        }
+        self.assign_registers();
+        self.module.apply_common_default_interpolation();
+        Ok(self.module)
+    }
+
+    /// Collect the names of the textures sampled with a comparison, since
+    /// HLSL doesn't have depth texture types.
+    fn scan_comparison_textures(&mut self, source: &str) {
+        let mut lexer = Lexer::new(source);
+        let mut previous = (Token::End, Token::End);
+        loop {
+            let token = lexer.next().0;
+            match (previous, token) {
+                (_, Token::End) => break,
+                ((Token::Word(name), Token::Punct(".")), Token::Word(method))
+                    if method.starts_with("SampleCmp") || method.starts_with("GatherCmp") =>
+                {
+                    self.comparison_textures.insert(name.to_string());
+                }
+                _ => {}
+            }
+            previous = (previous.1, token);
+        }
+    }
+
+    fn assign_registers(&mut self) {
+        let unassigned = std::mem::take(&mut self.unassigned_registers);
+        for (handle, register_type) in unassigned {
+            let used = &self.used_registers;
+            let index = (0..)
+                .find(|&index| !used.contains(&(register_type, 0, index)))
+                .unwrap();
+            self.used_registers.insert((register_type, 0, index));
+            self.module.global_variables.get_mut(handle).binding = Some(crate::ResourceBinding {
+                group: 0,
+                binding: index + register_type.shift(&self.options.register_shifts),
+            });
+        }
+    }
+
+    /// Parse a top-level item, returning `false` at the end of the source.
+    fn parse_global_item<'a>(&mut self, lexer: &mut Lexer<'a>) -> Result<bool, Error<'a>> {
+        let attributes = self.parse_attributes(lexer)?;
+        match lexer.peek() {
+            (Token::End, _) => return Ok(false),
+            (Token::Directive, span) => return Err(Error::Directive(span)),
+            (Token::Punct(";"), _) => {
+                let _ = lexer.next();
+            }
+            (Token::Word("struct"), _) => {
+                let _ = lexer.next();
+                self.parse_struct(lexer)?;
+                lexer.expect_punct(";")?;
+            }
+            (Token::Word("typedef"), _) => {
+                let _ = lexer.next();
+                let _ = self.parse_modifiers(lexer)?;
+                let ty = self.parse_data_type(lexer)?;
+                let (name, _) = lexer.next_ident()?;
+                let ty = self.parse_array_suffix(lexer, ty, false)?;
+                self.lookup_type.insert(name.to_string(), ty);
+                lexer.expect_punct(";")?;
+            }
+            (Token::Word("cbuffer"), _) => {
+                let _ = lexer.next();
+                self.parse_cbuffer(lexer)?;
+            }
+            (Token::Word("tbuffer"), span) => {
+                return Err(Error::Unsupported(span, "texture buffers"));
+            }
+            _ => self.parse_global_declaration(lexer, attributes)?,
+        }
+        Ok(true)
+    }
+
+    fn parse_attributes<'a>(&mut self, lexer: &mut Lexer<'a>) -> Result<Vec<Attribute>, Error<'a>> {
+        let mut attributes = Vec::new();
+        while lexer.skip(Token::Punct("[")) {
+            let (name, name_span) = lexer.next_ident()?;
+            match name {
+                "numthreads" => {
+                    lexer.expect_punct("(")?;
+                    let mut size = [1; 3];
+                    for (i, component) in size.iter_mut().enumerate() {
+                        if i != 0 {
+                            lexer.expect_punct(",")?;
+                        }
+                        *component = self.parse_const_u32(lexer)?;
+                    }
+                    lexer.expect_punct(")")?;
+                    attributes.push(Attribute::NumThreads(size));
+                }
+                "shader" => {
+                    lexer.expect_punct("(")?;
+                    let stage = match lexer.next() {
+                        (Token::String(stage), span) => conv::map_shader_stage(stage)
+                            .ok_or(Error::UnknownShaderStage(span, stage))?,
+                        (token, span) => return Err(Error::Unexpected(token, span, "string")),
+                    };
+                    lexer.expect_punct(")")?;
+                    attributes.push(Attribute::Shader(stage));
+                }
+                "earlydepthstencil" => attributes.push(Attribute::EarlyDepthStencil),
+                _ => {
+                    // Attributes like `[unroll]` or `[branch]` are only hints.
+                    log::debug!("Ignoring attribute '{}' at {:?}", name, name_span);
+                    if lexer.skip(Token::Punct("(")) {
+                        let mut depth = 1;
+                        while depth != 0 {
+                            match lexer.next() {
+                                (Token::Punct("("), _) => depth += 1,
+                                (Token::Punct(")"), _) => depth -= 1,
+                                (Token::End, span) => {
+                                    return Err(Error::Unexpected(Token::End, span, "')'"))
+                                }
+                                _ => {}
+                            }
+                        }
+                    }
+                }
+            }
+            lexer.expect_punct("]")?;
+        }
+        Ok(attributes)
+    }
+
+    fn parse_modifiers<'a>(&mut self, lexer: &mut Lexer<'a>) -> Result<Modifiers, Error<'a>> {
+        let mut modifiers = Modifiers::default();
+        loop {
+            match lexer.peek() {
+                (Token::Word("static"), _) => modifiers.is_static = true,
+                (Token::Word("const"), _) => modifiers.is_const = true,
+                (Token::Word("groupshared"), _) => modifiers.groupshared = true,
+                (Token::Word("in"), _) => modifiers.mode = Some(ParameterMode::In),
+                (Token::Word("out"), _) => modifiers.mode = Some(ParameterMode::Out),
+                (Token::Word("inout"), _) => modifiers.mode = Some(ParameterMode::InOut),
+                (Token::Word("nointerpolation"), _) => {
+                    modifiers.interpolation = Some(crate::Interpolation::Flat)
+                }
+                (Token::Word("noperspective"), _) => {
+                    modifiers.interpolation = Some(crate::Interpolation::Linear)
+                }
+                (Token::Word("linear"), _) => {
+                    modifiers.interpolation = Some(crate::Interpolation::Perspective)
+                }
+                (Token::Word("centroid"), _) => {
+                    modifiers.sampling = Some(crate::Sampling::Centroid)
+                }
+                (Token::Word("sample"), _) => modifiers.sampling = Some(crate::Sampling::Sample),
+                (Token::Word("uniform"), _)
+                | (Token::Word("extern"), _)
+                | (Token::Word("inline"), _)
+                | (Token::Word("precise"), _)
+                | (Token::Word("column_major"), _) => {}
+                (Token::Word("row_major"), span) => {
+                    return Err(Error::Unsupported(span, "`row_major` matrices"))
+                }
+                _ => return Ok(modifiers),
+            }
+            let _ = lexer.next();
+        }
+    }
+
+    fn is_type_name(&self, word: &str) -> bool {
+        conv::map_numeric_type(word).is_some()
+            || self.lookup_type.contains_key(word)
+            || word == "vector"
+            || word == "matrix"
+            || word == "void"
+    }
+
+    fn add_type(&mut self, inner: crate::TypeInner) -> Handle<crate::Type> {
+        self.module
+            .types
+            .fetch_or_append(crate::Type { name: None, inner }, Default::default())
+    }
+
+    /// Parse any type, including the resource types.
+    fn parse_type<'a>(&mut self, lexer: &mut Lexer<'a>) -> Result<TypeDecl, Error<'a>> {
+        let (word, span) = match lexer.peek() {
+            (Token::Word(word), span) => (word, span),
+            (token, span) => return Err(Error::Unexpected(token, span, "type")),
+        };
+        if let Some(kind) = conv::map_texture(word) {
+            let _ = lexer.next();
+            let start = lexer.next_start();
+            let element = if lexer.skip(Token::Punct("<")) {
+                let norm = match lexer.peek().0 {
+                    Token::Word("unorm") => Some(crate::ScalarKind::Uint),
+                    Token::Word("snorm") => Some(crate::ScalarKind::Sint),
+                    _ => None,
+                };
+                if norm.is_some() {
+                    let _ = lexer.next();
+                }
+                let ty = self.parse_data_type(lexer)?;
+                if kind.multi && lexer.skip(Token::Punct(",")) {
+                    // The sample count doesn't matter
+                    let _ = self.parse_template_u32(lexer)?;
+                }
+                lexer.expect_template_end()?;
+                let element = match self.module.types[ty].inner {
+                    crate::TypeInner::Scalar { kind, .. } => (kind, None, norm),
+                    crate::TypeInner::Vector { kind, size, .. } => (kind, Some(size), norm),
+                    _ => return Err(Error::BadTextureElement(lexer.span_from(start))),
+                };
+                Some(element)
+            } else {
+                None
+            };
+            return Ok(TypeDecl::Texture {
+                kind,
+                element,
+                span: (start, lexer.span_from(start).end),
+            });
+        }
+        let decl = match word {
+            "SamplerState" | "sampler" => TypeDecl::Sampler { comparison: false },
+            "SamplerComparisonState" => TypeDecl::Sampler { comparison: true },
+            "StructuredBuffer" | "RWStructuredBuffer" => {
+                let _ = lexer.next();
+                lexer.expect_punct("<")?;
+                let element = self.parse_data_type(lexer)?;
+                lexer.expect_template_end()?;
+                return Ok(TypeDecl::StructuredBuffer {
+                    element,
+                    writable: word == "RWStructuredBuffer",
+                });
+            }
+            "ConstantBuffer" => {
+                let _ = lexer.next();
+                lexer.expect_punct("<")?;
+                let ty = self.parse_data_type(lexer)?;
+                lexer.expect_template_end()?;
+                return Ok(TypeDecl::ConstantBuffer(ty));
+            }
+            "ByteAddressBuffer"
+            | "RWByteAddressBuffer"
+            | "Buffer"
+            | "RWBuffer"
+            | "AppendStructuredBuffer"
+            | "ConsumeStructuredBuffer" => {
+                return Err(Error::Unsupported(span, "typed and raw buffers"))
+            }
+            _ => return self.parse_data_type(lexer).map(TypeDecl::Data),
+        };
+        let _ = lexer.next();
+        Ok(decl)
+    }
+
+    /// Parse a data type, without the array dimensions.
+    fn parse_data_type<'a>(
+        &mut self,
+        lexer: &mut Lexer<'a>,
+    ) -> Result<Handle<crate::Type>, Error<'a>> {
+        let (word, span) = lexer.next_ident()?;
+        if let Some(inner) = conv::map_numeric_type(word) {
+            return Ok(self.add_type(inner));
+        }
+        if let Some(&ty) = self.lookup_type.get(word) {
+            return Ok(ty);
+        }
+        let inner = match word {
+            "vector" => {
+                lexer.expect_punct("<")?;
+                let (scalar, scalar_span) = lexer.next_ident()?;
+                let (kind, width) =
+                    conv::map_scalar(scalar).ok_or(Error::UnknownType(scalar_span, scalar))?;
+                lexer.expect_punct(",")?;
+                let size_span = lexer.peek().1;
+                let size = self.parse_template_u32(lexer)?;
+                lexer.expect_template_end()?;
+                match size {
+                    1 => crate::TypeInner::Scalar { kind, width },
+                    _ => crate::TypeInner::Vector {
+                        size: conv::map_vector_size(size).ok_or(Error::NotConstant(size_span))?,
+                        kind,
+                        width,
+                    },
+                }
+            }
+            "matrix" => {
+                lexer.expect_punct("<")?;
+                let (scalar, scalar_span) = lexer.next_ident()?;
+                let width = match conv::map_scalar(scalar) {
+                    Some((crate::ScalarKind::Float, width)) => width,
+                    _ => return Err(Error::UnknownType(scalar_span, scalar)),
+                };
+                lexer.expect_punct(",")?;
+                let rows_span = lexer.peek().1;
+                let rows = conv::map_vector_size(self.parse_template_u32(lexer)?)
+                    .ok_or(Error::NotConstant(rows_span))?;
+                lexer.expect_punct(",")?;
+                let columns_span = lexer.peek().1;
+                let columns = conv::map_vector_size(self.parse_template_u32(lexer)?)
+                    .ok_or(Error::NotConstant(columns_span))?;
+                lexer.expect_template_end()?;
+                crate::TypeInner::Matrix {
+                    columns,
+                    rows,
+                    width,
+                }
+            }
+            _ => return Err(Error::UnknownType(span, word)),
+        };
+        Ok(self.add_type(inner))
+    }
+
+    /// Wrap `base` into the arrays declared after a name, like `[4][2]`.
+    ///
+    /// In `uniform` layout, the array elements are aligned to 16 bytes, like
+    /// in constant buffers.
+    fn parse_array_suffix<'a>(
+        &mut self,
+        lexer: &mut Lexer<'a>,
+        base: Handle<crate::Type>,
+        uniform: bool,
+    ) -> Result<Handle<crate::Type>, Error<'a>> {
+        let mut sizes = Vec::new();
+        while lexer.skip(Token::Punct("[")) {
+            sizes.push(self.parse_const_u32(lexer)?);
+            lexer.expect_punct("]")?;
+        }
+        let mut ty = base;
+        for &size in sizes.iter().rev() {
+            ty = self.add_array_type(ty, size, uniform);
+        }
+        Ok(ty)
+    }
+
+    fn add_array_type(
+        &mut self,
+        base: Handle<crate::Type>,
+        size: u32,
+        uniform: bool,
+    ) -> Handle<crate::Type> {
+        self.layouter
+            .update(&self.module.types, &self.module.constants)
+            .unwrap();
+        let layout = self.layouter[base];
+        let alignment = if uniform {
+            layout.alignment.max(Alignment::new(16).unwrap())
+        } else {
+            layout.alignment
+        };
+        let size = self.module.constants.fetch_or_append(
+            crate::Constant {
+                name: None,
+                specialization: None,
+                inner: crate::ConstantInner::Scalar {
+                    width: 4,
+                    value: crate::ScalarValue::Uint(size as u64),
+                },
+            },
+            Default::default(),
+        );
+        self.add_type(crate::TypeInner::Array {
+            base,
+            size: crate::ArraySize::Constant(size),
+            stride: Layouter::round_up(alignment, layout.size),
+        })
+    }
+
+    /// Parse a constant expression outside of a function.
+    fn parse_global_constant<'a>(
+        &mut self,
+        lexer: &mut Lexer<'a>,
+        ty: Option<Handle<crate::Type>>,
+    ) -> Result<Handle<crate::Constant>, Error<'a>> {
+        let mut ctx = FunctionContext::new(crate::Function::default(), ReturnKind::Value(None));
+        let mut block = crate::Block::new();
+        ctx.start_emit();
+        let start = lexer.next_start();
+        let value = match ty {
+            Some(ty) => self.parse_initializer(lexer, &mut ctx, &mut block, ty)?,
+            None => {
+                let expr = self.parse_expression(lexer, &mut ctx, &mut block)?;
+                self.apply_load(&mut ctx, expr, &lexer.span_from(start))?
+            }
+        };
+        let span = lexer.span_from(start);
+        self.solve_constant(&mut ctx, value, span)
+    }
+
+    /// Parse a template argument like the size in `vector<float, 3>`, where
+    /// a `>` closes the template instead of being a comparison.
+    fn parse_template_u32<'a>(&mut self, lexer: &mut Lexer<'a>) -> Result<u32, Error<'a>> {
+        let mut ctx = FunctionContext::new(crate::Function::default(), ReturnKind::Value(None));
+        let mut block = crate::Block::new();
+        ctx.start_emit();
+        let start = lexer.next_start();
+        let expr = self.parse_binary(lexer, &mut ctx, &mut block, function::SHIFT_PRECEDENCE)?;
+        let span = lexer.span_from(start);
+        let value = self.apply_load(&mut ctx, expr, &span)?;
+        let constant = self.solve_constant(&mut ctx, value, span.clone())?;
+        self.constant_to_u32(constant, span)
+    }
+
+    fn parse_const_u32<'a>(&mut self, lexer: &mut Lexer<'a>) -> Result<u32, Error<'a>> {
+        let start = lexer.next_start();
+        let constant = self.parse_global_constant(lexer, None)?;
+        self.constant_to_u32(constant, lexer.span_from(start))
+    }
+
+    fn constant_to_u32<'a>(
+        &self,
+        constant: Handle<crate::Constant>,
+        span: Span,
+    ) -> Result<u32, Error<'a>> {
+        match self.module.constants[constant].inner {
+            crate::ConstantInner::Scalar {
+                value: crate::ScalarValue::Sint(value),
+                ..
+            } if value >= 0 => Ok(value as u32),
+            crate::ConstantInner::Scalar {
+                value: crate::ScalarValue::Uint(value),
+                ..
+            } => Ok(value as u32),
+            _ => Err(Error::NotConstant(span)),
+        }
+    }
+
+    /// Parse a semantic, or a register for resources, after a `:`.
+    fn parse_semantic<'a>(
+        &mut self,
+        lexer: &mut Lexer<'a>,
+        modifiers: &Modifiers,
+    ) -> Result<Option<crate::Binding>, Error<'a>> {
+        if !lexer.skip(Token::Punct(":")) {
+            return Ok(None);
+        }
+        let (name, span) = lexer.next_ident()?;
+        let binding = match conv::map_semantic(name).ok_or(Error::UnknownSemantic(span, name))? {
+            conv::Semantic::BuiltIn(built_in) => crate::Binding::BuiltIn(built_in),
+            conv::Semantic::Target(location) => crate::Binding::Location {
+                location,
+                interpolation: None,
+                sampling: None,
+            },
+            conv::Semantic::User(semantic) => {
+                let next = self.varying_locations.len() as u32;
+                let location = *self.varying_locations.entry(semantic).or_insert(next);
+                crate::Binding::Location {
+                    location,
+                    interpolation: modifiers.interpolation.or_else(|| {
+                        modifiers
+                            .sampling
+                            .map(|_| crate::Interpolation::Perspective)
+                    }),
+                    sampling: modifiers.sampling,
+                }
+            }
+        };
+        Ok(Some(binding))
+    }
+
+    /// Parse the optional `: register(xN, spaceM)` of a resource.
+    fn parse_register<'a>(
+        &mut self,
+        lexer: &mut Lexer<'a>,
+        handle: Handle<crate::GlobalVariable>,
+        register_type: RegisterType,
+    ) -> Result<(), Error<'a>> {
+        if !lexer.skip(Token::Punct(":")) {
+            self.unassigned_registers.push((handle, register_type));
+            return Ok(());
+        }
+        lexer.expect(Token::Word("register"))?;
+        lexer.expect_punct("(")?;
+        let (register, span) = lexer.next_ident()?;
+        let mut chars = register.chars();
+        let index = match (
+            chars.next().and_then(RegisterType::from_letter),
+            chars.as_str(),
+        ) {
+            (Some(ty), index) if ty == register_type => {
+                index.parse::<u32>().map_err(|_| Error::BadRegister(span))?
+            }
+            _ => return Err(Error::BadRegister(span)),
+        };
+        let space = if lexer.skip(Token::Punct(",")) {
+            let (space, span) = lexer.next_ident()?;
+            match (
+                space.starts_with("space"),
+                space[5.min(space.len())..].parse(),
+            ) {
+                (true, Ok(space)) => space,
+                _ => return Err(Error::BadRegister(span)),
+            }
+        } else {
+            0
+        };
+        lexer.expect_punct(")")?;
+        self.used_registers.insert((register_type, space, index));
+        self.module.global_variables.get_mut(handle).binding = Some(crate::ResourceBinding {
+            group: space,
+            binding: index + register_type.shift(&self.options.register_shifts),
+        });
+        Ok(())
+    }
+
+    /// Parse the members of a `struct` or `cbuffer` up to the closing brace,
+    /// and lay them out.
+    fn parse_struct_members<'a>(
+        &mut self,
+        lexer: &mut Lexer<'a>,
+        uniform: bool,
+    ) -> Result<(Vec<crate::StructMember>, u32), Error<'a>> {
+        let mut members = Vec::new();
+        let mut offset = 0;
+        let mut alignment = Alignment::new(if uniform { 16 } else { 1 }).unwrap();
+        while !lexer.skip(Token::Punct("}")) {
+            let modifiers = self.parse_modifiers(lexer)?;
+            let base = self.parse_data_type(lexer)?;
+            loop {
+                let (name, _) = lexer.next_ident()?;
+                let ty = self.parse_array_suffix(lexer, base, uniform)?;
+                if let (Token::Punct(":"), Token::Word("packoffset")) =
+                    (lexer.peek().0, lexer.peek_second())
+                {
+                    return Err(Error::Unsupported(lexer.peek().1, "packing offsets"));
+                }
+                let binding = self.parse_semantic(lexer, &modifiers)?;
+
+                self.layouter
+                    .update(&self.module.types, &self.module.constants)
+                    .unwrap();
+                let member_alignment = match self.module.types[ty].inner {
+                    crate::TypeInner::Array { .. } | crate::TypeInner::Struct { .. } if uniform => {
+                        Some(self.layouter[ty].alignment.max(Alignment::new(16).unwrap()))
+                    }
+                    _ => None,
+                };
+                let (range, align) =
+                    self.layouter
+                        .member_placement(offset, ty, member_alignment, None);
+                alignment = alignment.max(align);
+                offset = range.end;
+                members.push(crate::StructMember {
+                    name: Some(name.to_string()),
+                    ty,
+                    binding,
+                    offset: range.start,
+                });
+                if !lexer.skip(Token::Punct(",")) {
+                    break;
+                }
+            }
+            lexer.expect_punct(";")?;
+        }
+        Ok((members, Layouter::round_up(alignment, offset)))
+    }
+
+    fn parse_struct<'a>(&mut self, lexer: &mut Lexer<'a>) -> Result<(), Error<'a>> {
+        let (name, span) = lexer.next_ident()?;
+        if self.lookup_type.contains_key(name) {
+            return Err(Error::Redefinition(span, name));
+        }
+        lexer.expect_punct("{")?;
+        let (members, span) = self.parse_struct_members(lexer, false)?;
+        let ty = self.module.types.append(
+            crate::Type {
+                name: Some(name.to_string()),
+                inner: crate::TypeInner::Struct {
+                    top_level: false,
+                    members,
+                    span,
+                },
+            },
+            Default::default(),
+        );
+        self.lookup_type.insert(name.to_string(), ty);
+        Ok(())
+    }
+
+    fn parse_cbuffer<'a>(&mut self, lexer: &mut Lexer<'a>) -> Result<(), Error<'a>> {
+        let (name, _) = lexer.next_ident()?;
+        // The register comes before the members, but the global needs the type.
+        let register_lexer = lexer.clone();
+        while !lexer.skip(Token::Punct("{")) {
+            if let (Token::End, span) = lexer.next() {
+                return Err(Error::Unexpected(Token::End, span, "'{'"));
+            }
+        }
+        let (members, span) = self.parse_struct_members(lexer, true)?;
+        let _ = lexer.skip(Token::Punct(";"));
+        let end_lexer = lexer.clone();
+
+        let ty = self.module.types.append(
+            crate::Type {
+                name: Some(name.to_string()),
+                inner: crate::TypeInner::Struct {
+                    top_level: true,
+                    members: members.clone(),
+                    span,
+                },
+            },
+            Default::default(),
+        );
+        let handle = self.module.global_variables.append(
+            crate::GlobalVariable {
+                name: Some(name.to_string()),
+                class: crate::StorageClass::Uniform,
+                binding: None,
+                ty,
+                init: None,
+                storage_access: crate::StorageAccess::empty(),
+            },
+            Default::default(),
+        );
+        *lexer = register_lexer;
+        self.parse_register(lexer, handle, RegisterType::ConstantBuffer)?;
+        lexer.expect_punct("{")?;
+        *lexer = end_lexer;
+
+        for (index, member) in members.into_iter().enumerate() {
+            self.lookup_global.insert(
+                member.name.unwrap(),
+                GlobalLookup::Member(handle, index as u32),
+            );
+        }
+        Ok(())
+    }
+
+    /// Parse a global variable, resource, constant, or function.
+    fn parse_global_declaration<'a>(
+        &mut self,
+        lexer: &mut Lexer<'a>,
+        attributes: Vec<Attribute>,
+    ) -> Result<(), Error<'a>> {
+        let modifiers = self.parse_modifiers(lexer)?;
+        let type_start = lexer.next_start();
+        let decl = match lexer.peek().0 {
+            Token::Word("void") => {
+                let _ = lexer.next();
+                None
+            }
+            _ => Some(self.parse_type(lexer)?),
+        };
+        let (name, name_span) = lexer.next_ident()?;
+        if lexer.peek().0 == Token::Punct("(") {
+            let result = match decl {
+                None => None,
+                Some(TypeDecl::Data(ty)) => Some(ty),
+                Some(_) => {
+                    return Err(Error::Unsupported(
+                        lexer.span_from(type_start),
+                        "functions returning resources",
+                    ))
+                }
+            };
+            return self.parse_function(lexer, attributes, result, name, name_span);
+        }
+        if self.lookup_global.contains_key(name) {
+            return Err(Error::Redefinition(name_span, name));
+        }
+
+        let decl = match decl {
+            Some(decl) => decl,
+            None => return Err(Error::UnknownType(lexer.span_from(type_start), "void")),
+        };
+        let (class, ty, access, register_type) = match decl {
+            TypeDecl::Data(base) => {
+                return self.parse_global_variables(lexer, modifiers, base, name, name_span);
+            }
+            TypeDecl::Texture {
+                kind,
+                element,
+                span,
+            } => {
+                let ty = self.texture_type(kind, element, name, span.0..span.1)?;
+                let (access, register_type) = if kind.storage {
+                    (
+                        crate::StorageAccess::LOAD | crate::StorageAccess::STORE,
+                        RegisterType::UnorderedAccess,
+                    )
+                } else {
+                    (crate::StorageAccess::empty(), RegisterType::Texture)
+                };
+                (crate::StorageClass::Handle, ty, access, register_type)
+            }
+            TypeDecl::Sampler { comparison } => {
+                let ty = self.add_type(crate::TypeInner::Sampler { comparison });
+                (
+                    crate::StorageClass::Handle,
+                    ty,
+                    crate::StorageAccess::empty(),
+                    RegisterType::Sampler,
+                )
+            }
+            TypeDecl::StructuredBuffer { element, writable } => {
+                self.layouter
+                    .update(&self.module.types, &self.module.constants)
+                    .unwrap();
+                let layout = self.layouter[element];
+                let array = self.add_type(crate::TypeInner::Array {
+                    base: element,
+                    size: crate::ArraySize::Dynamic,
+                    stride: Layouter::round_up(layout.alignment, layout.size),
+                });
+                self.layouter
+                    .update(&self.module.types, &self.module.constants)
+                    .unwrap();
+                let (range, alignment) = self.layouter.member_placement(0, array, None, None);
+                let ty = self.add_type(crate::TypeInner::Struct {
+                    top_level: true,
+                    members: vec![crate::StructMember {
+                        name: Some("data".to_string()),
+                        ty: array,
+                        binding: None,
+                        offset: 0,
+                    }],
+                    span: Layouter::round_up(alignment, range.end),
+                });
+                let (access, register_type) = if writable {
+                    (
+                        crate::StorageAccess::LOAD | crate::StorageAccess::STORE,
+                        RegisterType::UnorderedAccess,
+                    )
+                } else {
+                    (crate::StorageAccess::LOAD, RegisterType::Texture)
+                };
+                (crate::StorageClass::Storage, ty, access, register_type)
+            }
+            TypeDecl::ConstantBuffer(ty) => {
+                if let crate::TypeInner::Struct {
+                    ref mut top_level, ..
+                } = self.module.types.get_mut(ty).inner
+                {
+                    *top_level = true;
+                }
+                (
+                    crate::StorageClass::Uniform,
+                    ty,
+                    crate::StorageAccess::empty(),
+                    RegisterType::ConstantBuffer,
+                )
+            }
+        };
+        if lexer.peek().0 == Token::Punct("[") {
+            return Err(Error::Unsupported(lexer.peek().1, "arrays of resources"));
+        }
+
+        let handle = self.module.global_variables.append(
+            crate::GlobalVariable {
+                name: Some(name.to_string()),
+                class,
+                binding: None,
+                ty,
+                init: None,
+                storage_access: access,
+            },
+            Default::default(),
+        );
+        self.parse_register(lexer, handle, register_type)?;
+        lexer.expect_punct(";")?;
+
+        let lookup = match decl {
+            TypeDecl::StructuredBuffer { .. } => GlobalLookup::Member(handle, 0),
+            _ => GlobalLookup::Variable(handle),
+        };
+        self.lookup_global.insert(name.to_string(), lookup);
+        Ok(())
+    }
+
+    fn texture_type<'a>(
+        &mut self,
+        kind: conv::TextureKind,
+        element: Option<(
+            crate::ScalarKind,
+            Option<crate::VectorSize>,
+            Option<crate::ScalarKind>,
+        )>,
+        name: &str,
+        span: Span,
+    ) -> Result<Handle<crate::Type>, Error<'a>> {
+        let (scalar_kind, size, norm) = element.unwrap_or((
+            crate::ScalarKind::Float,
+            Some(crate::VectorSize::Quad),
+            None,
+        ));
+        let class = if kind.storage {
+            crate::ImageClass::Storage(
+                conv::map_storage_format(scalar_kind, size, norm)
+                    .ok_or(Error::BadTextureElement(span))?,
+            )
+        } else if self.comparison_textures.contains(name) && !kind.multi {
+            crate::ImageClass::Depth
+        } else {
+            crate::ImageClass::Sampled {
+                kind: scalar_kind,
+                multi: kind.multi,
+            }
+        };
+        Ok(self.add_type(crate::TypeInner::Image {
+            dim: kind.dim,
+            arrayed: kind.arrayed,
+            class,
+        }))
+    }
+
+    /// Parse the declarators of global variables or constants, after the
+    /// first name.
+    fn parse_global_variables<'a>(
+        &mut self,
+        lexer: &mut Lexer<'a>,
+        modifiers: Modifiers,
+        base: Handle<crate::Type>,
+        mut name: &'a str,
+        mut name_span: Span,
+    ) -> Result<(), Error<'a>> {
+        loop {
+            if self.lookup_global.contains_key(name) {
+                return Err(Error::Redefinition(name_span, name));
+            }
+            let ty = self.parse_array_suffix(lexer, base, false)?;
+            if lexer.peek().0 == Token::Punct(":") {
+                return Err(Error::Unsupported(
+                    lexer.peek().1,
+                    "semantics and registers of global variables",
+                ));
+            }
+            let init = if lexer.skip(Token::Punct("=")) {
+                Some(self.parse_global_constant(lexer, Some(ty))?)
+            } else {
+                None
+            };
+
+            let lookup = if modifiers.groupshared {
+                GlobalLookup::Variable(self.module.global_variables.append(
+                    crate::GlobalVariable {
+                        name: Some(name.to_string()),
+                        class: crate::StorageClass::WorkGroup,
+                        binding: None,
+                        ty,
+                        init: None,
+                        storage_access: crate::StorageAccess::empty(),
+                    },
+                    Default::default(),
+                ))
+            } else if !modifiers.is_static && !modifiers.is_const {
+                return Err(Error::Unsupported(
+                    name_span,
+                    "global variables outside of constant buffers",
+                ));
+            } else if let (true, Some(init), true) = (
+                modifiers.is_const,
+                init,
+                matches!(
+                    self.module.types[ty].inner,
+                    crate::TypeInner::Scalar { .. } | crate::TypeInner::Vector { .. }
+                ),
+            ) {
+                // Name the constant, unless it's shared with other uses
+                let constant = &self.module.constants[init];
+                let init = if constant.name.is_none() && constant.specialization.is_none() {
+                    let inner = constant.inner.clone();
+                    self.module.constants.append(
+                        crate::Constant {
+                            name: Some(name.to_string()),
+                            specialization: None,
+                            inner,
+                        },
+                        Default::default(),
+                    )
+                } else {
+                    init
+                };
+                GlobalLookup::Constant(init)
+            } else {
+                GlobalLookup::Variable(self.module.global_variables.append(
+                    crate::GlobalVariable {
+                        name: Some(name.to_string()),
+                        class: crate::StorageClass::Private,
+                        binding: None,
+                        ty,
+                        init,
+                        storage_access: crate::StorageAccess::empty(),
+                    },
+                    Default::default(),
+                ))
+            };
+            self.lookup_global.insert(name.to_string(), lookup);
+
+            if !lexer.skip(Token::Punct(",")) {
+                break;
+            }
+            let (next_name, next_span) = lexer.next_ident()?;
+            name = next_name;
+            name_span = next_span;
+        }
+        lexer.expect_punct(";")?;
+        Ok(())
+    }
+
+    fn parse_parameters<'a>(
+        &mut self,
+        lexer: &mut Lexer<'a>,
+    ) -> Result<Vec<Parameter<'a>>, Error<'a>> {
+        let mut parameters = Vec::new();
+        lexer.expect_punct("(")?;
+        if lexer.peek().0 == Token::Word("void") && lexer.peek_second() == Token::Punct(")") {
+            let _ = lexer.next();
+        }
+        while !lexer.skip(Token::Punct(")")) {
+            if !parameters.is_empty() {
+                lexer.expect_punct(",")?;
+            }
+            let modifiers = self.parse_modifiers(lexer)?;
+            let type_start = lexer.next_start();
+            let decl = self.parse_type(lexer)?;
+            let type_span = lexer.span_from(type_start);
+            let (name, name_span) = lexer.next_ident()?;
+            let ty = match decl {
+                TypeDecl::Data(base) => self.parse_array_suffix(lexer, base, false)?,
+                TypeDecl::Texture {
+                    kind,
+                    element,
+                    span,
+                } => self.texture_type(kind, element, name, span.0..span.1)?,
+                TypeDecl::Sampler { comparison } => {
+                    self.add_type(crate::TypeInner::Sampler { comparison })
+                }
+                _ => return Err(Error::Unsupported(type_span, "buffer parameters")),
+            };
+            let binding = self.parse_semantic(lexer, &modifiers)?;
+            if lexer.peek().0 == Token::Punct("=") {
+                return Err(Error::Unsupported(lexer.peek().1, "default arguments"));
+            }
+            parameters.push(Parameter {
+                name,
+                name_span,
+                ty,
+                mode: modifiers.mode.unwrap_or(ParameterMode::In),
+                binding,
+            });
+        }
+        Ok(parameters)
+    }
+
+    fn parse_function<'a>(
+        &mut self,
+        lexer: &mut Lexer<'a>,
+        attributes: Vec<Attribute>,
+        result: Option<Handle<crate::Type>>,
+        name: &'a str,
+        name_span: Span,
+    ) -> Result<(), Error<'a>> {
+        let parameters = self.parse_parameters(lexer)?;
+        let result_binding = self.parse_semantic(lexer, &Modifiers::default())?;
+        if lexer.skip(Token::Punct(";")) {
+            // Just a prototype
+            return Ok(());
+        }
+
+        let mut stage = self.options.entry_points.get(name).cloned();
+        let mut workgroup_size = [0; 3];
+        let mut early_depth_test = None;
+        for attribute in attributes {
+            match attribute {
+                Attribute::NumThreads(size) => {
+                    workgroup_size = size;
+                    stage = Some(crate::ShaderStage::Compute);
+                }
+                Attribute::Shader(shader_stage) => stage = Some(shader_stage),
+                Attribute::EarlyDepthStencil => {
+                    early_depth_test = Some(crate::EarlyDepthTest { conservative: None })
+                }
+            }
+        }
+        if stage == Some(crate::ShaderStage::Compute) && workgroup_size == [0; 3] {
+            workgroup_size = [1; 3];
+        }
+
+        let mut function = crate::Function {
+            name: Some(name.to_string()),
+            ..Default::default()
+        };
+        let return_kind = match stage {
+            Some(_) => self.entry_point_interface(
+                &mut function,
+                &parameters,
+                result,
+                result_binding,
+                name,
+                name_span,
+            )?,
+            None => {
+                for parameter in parameters.iter() {
+                    let ty = match parameter.mode {
+                        ParameterMode::In => parameter.ty,
+                        ParameterMode::Out | ParameterMode::InOut => {
+                            self.add_type(crate::TypeInner::Pointer {
+                                base: parameter.ty,
+                                class: crate::StorageClass::Function,
+                            })
+                        }
+                    };
+                    function.arguments.push(crate::FunctionArgument {
+                        name: Some(parameter.name.to_string()),
+                        ty,
+                        binding: None,
+                    });
+                }
+                function.result = result.map(|ty| crate::FunctionResult { ty, binding: None });
+                ReturnKind::Value(result)
+            }
+        };
+
+        let mut ctx = FunctionContext::new(function, return_kind);
+        let body = self.parse_function_body(lexer, &mut ctx, &parameters)?;
+        let mut function = ctx.function;
+        function.body = body;
+        crate::proc::ensure_block_returns(&mut function.body);
+
+        match stage {
+            Some(stage) => {
+                self.module.entry_points.push(crate::EntryPoint {
+                    name: name.to_string(),
+                    stage,
+                    early_depth_test,
+                    workgroup_size,
+                    function,
+                });
+            }
+            None => {
+                let handle = self.module.functions.append(function, Default::default());
+                self.lookup_function.insert(
+                    name.to_string(),
+                    FunctionDecl {
+                        handle,
+                        modes: parameters.iter().map(|p| p.mode).collect(),
+                    },
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Fill in the arguments and the result of an entry point.
+    ///
+    /// The `out` parameters become local variables, which are returned
+    /// together with the result in a structure.
+    fn entry_point_interface<'a>(
+        &mut self,
+        function: &mut crate::Function,
+        parameters: &[Parameter<'a>],
+        result: Option<Handle<crate::Type>>,
+        result_binding: Option<crate::Binding>,
+        name: &str,
+        name_span: Span,
+    ) -> Result<ReturnKind, Error<'a>> {
+        let needs_binding = |parser: &Self, ty: Handle<crate::Type>, binding: &Option<_>| {
+            binding.is_none()
+                && match parser.module.types[ty].inner {
+                    crate::TypeInner::Struct { .. } => false,
+                    crate::TypeInner::Image { .. } | crate::TypeInner::Sampler { .. } => false,
+                    _ => true,
+                }
+        };
+        let mut outputs = Vec::new();
+        for parameter in parameters.iter() {
+            if needs_binding(self, parameter.ty, &parameter.binding) {
+                return Err(Error::MissingSemantic(parameter.name_span.clone()));
+            }
+            match parameter.mode {
+                ParameterMode::In => function.arguments.push(crate::FunctionArgument {
+                    name: Some(parameter.name.to_string()),
+                    ty: parameter.ty,
+                    binding: parameter.binding.clone(),
+                }),
+                ParameterMode::Out => outputs.push(parameter),
+                ParameterMode::InOut => {
+                    return Err(Error::Unsupported(
+                        parameter.name_span.clone(),
+                        "`inout` entry point parameters",
+                    ))
+                }
+            }
+        }
+        if let Some(ty) = result {
+            if needs_binding(self, ty, &result_binding) {
+                return Err(Error::MissingSemantic(name_span));
+            }
+        }
+        if outputs.is_empty() {
+            function.result = result.map(|ty| crate::FunctionResult {
+                ty,
+                binding: result_binding,
+            });
+            return Ok(ReturnKind::Value(result));
+        }
+
+        let mut members = Vec::new();
+        if let Some(ty) = result {
+            if result_binding.is_none() {
+                return Err(Error::Unsupported(
+                    name_span,
+                    "structures returned together with `out` parameters",
+                ));
+            }
+            members.push((None, ty, result_binding));
+        }
+        for parameter in outputs.iter() {
+            if parameter.binding.is_none() {
+                return Err(Error::Unsupported(
+                    parameter.name_span.clone(),
+                    "structures in `out` parameters",
+                ));
+            }
+            members.push((
+                Some(parameter.name.to_string()),
+                parameter.ty,
+                parameter.binding.clone(),
+            ));
+        }
+        self.layouter
+            .update(&self.module.types, &self.module.constants)
+            .unwrap();
+        let mut offset = 0;
+        let mut alignment = Alignment::new(1).unwrap();
+        let members = members
+            .into_iter()
+            .map(|(name, ty, binding)| {
+                let (range, align) = self.layouter.member_placement(offset, ty, None, None);
+                alignment = alignment.max(align);
+                offset = range.end;
+                crate::StructMember {
+                    name: name.or_else(|| Some("result".to_string())),
+                    ty,
+                    binding,
+                    offset: range.start,
+                }
+            })
+            .collect();
+        let ty = self.module.types.append(
+            crate::Type {
+                name: Some(format!("{}Output", name)),
+                inner: crate::TypeInner::Struct {
+                    top_level: false,
+                    members,
+                    span: Layouter::round_up(alignment, offset),
+                },
+            },
+            Default::default(),
+        );
+        function.result = Some(crate::FunctionResult { ty, binding: None });
+        Ok(ReturnKind::EntryPointOutputs {
+            ty,
+            has_result: result.is_some(),
+            outputs: Vec::new(),
+        })
+    }
+}
+
+/// Describe a type the way HLSL spells it, for error messages.
+fn type_name(inner: &crate::TypeInner, types: &Arena<crate::Type>) -> String {
+    use crate::TypeInner as Ti;
+
+    fn scalar(kind: crate::ScalarKind, width: crate::Bytes) -> &'static str {
+        match (kind, width) {
+            (crate::ScalarKind::Float, 8) => "double",
+            (crate::ScalarKind::Float, _) => "float",
+            (crate::ScalarKind::Sint, _) => "int",
+            (crate::ScalarKind::Uint, _) => "uint",
+            (crate::ScalarKind::Bool, _) => "bool",
+        }
+    }
+
+    match *inner {
+        Ti::Scalar { kind, width } | Ti::Atomic { kind, width } => scalar(kind, width).to_string(),
+        Ti::Vector { size, kind, width } => format!("{}{}", scalar(kind, width), size as u8),
+        Ti::Matrix {
+            columns,
+            rows,
+            width,
+        } => format!(
+            "{}{}x{}",
+            scalar(crate::ScalarKind::Float, width),
+            rows as u8,
+            columns as u8
+        ),
+        Ti::Pointer { base, .. } => type_name(&types[base].inner, types),
+        Ti::ValuePointer {
+            size: None,
+            kind,
+            width,
+            ..
+        } => scalar(kind, width).to_string(),
+        Ti::ValuePointer {
+            size: Some(size),
+            kind,
+            width,
+            ..
+        } => format!("{}{}", scalar(kind, width), size as u8),
+        Ti::Array { base, .. } | Ti::BindingArray { base, .. } => {
+            format!("{}[]", type_name(&types[base].inner, types))
+        }
+        Ti::Struct { .. } => types
+            .fetch_if(|ty| ty.inner == *inner)
+            .and_then(|ty| types[ty].name.clone())
+            .unwrap_or_else(|| "struct".to_string()),
+        Ti::Image { .. } => "texture".to_string(),
+        Ti::Sampler { .. } => "sampler".to_string(),
+    }
+}
+
+pub fn parse_str(source: &str, options: &Options) -> Result<crate::Module, ParseError> {
+    Parser::new(options).parse(source)
+}
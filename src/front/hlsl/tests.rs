@@ -0,0 +1,257 @@
+use super::{parse_str, Options};
+
+fn parse(source: &str) -> crate::Module {
+    let module = match parse_str(source, &Options::default()) {
+        Ok(module) => module,
+        Err(error) => panic!("{}", error.emit_to_string(source)),
+    };
+    crate::valid::Validator::new(
+        crate::valid::ValidationFlags::all(),
+        crate::valid::Capabilities::all(),
+    )
+    .validate(&module)
+    .unwrap();
+    module
+}
+
+fn parse_error(source: &str) -> String {
+    match parse_str(source, &Options::default()) {
+        Ok(_) => panic!("expected a parse error"),
+        Err(error) => error.to_string(),
+    }
+}
+
+#[test]
+fn parse_comment() {
+    parse(
+        "// line comment
+        /* block
+           comment */
+        static const float x = 1.0; // trailing",
+    );
+}
+
+#[test]
+fn parse_types() {
+    parse("static const int a = 2;");
+    parse("static const uint2 b = uint2(1, 2);");
+    parse("static const float3x2 c = float3x2(1, 2, 3, 4, 5, 6);");
+    parse("static const vector<float, 3> d = 0.5;");
+    parse("static const min16float e = 1.0h;");
+    parse("Texture2DArray<float4> t : register(t0);");
+    parse("TextureCube<uint4> t : register(t0);");
+    parse("Texture2DMS<float4> t : register(t0);");
+    parse("RWTexture3D<float> t : register(u0);");
+    parse("SamplerComparisonState s : register(s0);");
+    assert!(parse_str("static const float5 a = 1.0;", &Options::default()).is_err());
+}
+
+#[test]
+fn parse_registers() {
+    let module = parse(
+        "
+        cbuffer Camera : register(b1, space2) { float4x4 view; };
+        Texture2D<float4> color : register(t3);
+        SamplerState linear_sampler;
+        RWStructuredBuffer<uint> counters : register(u0, space1);
+        ",
+    );
+    let bindings = module
+        .global_variables
+        .iter()
+        .map(|(_, var)| {
+            let binding = var.binding.as_ref().unwrap();
+            (var.name.as_deref().unwrap(), binding.group, binding.binding)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        bindings,
+        [
+            ("Camera", 2, 1),
+            ("color", 0, 99),
+            ("linear_sampler", 0, 16),
+            ("counters", 1, 32),
+        ]
+    );
+}
+
+#[test]
+fn parse_entry_points() {
+    let module = parse(
+        "
+        struct VsOutput {
+            float4 position : SV_Position;
+            float3 normal : NORMAL;
+        };
+        [shader(\"vertex\")]
+        VsOutput vs(float3 position : POSITION, float3 normal : NORMAL) {
+            VsOutput output;
+            output.position = float4(position, 1.0);
+            output.normal = normal;
+            return output;
+        }
+        [shader(\"pixel\")]
+        void ps(VsOutput input, out float4 color : SV_Target0, out float depth : SV_Depth) {
+            color = float4(normalize(input.normal), 1.0);
+            depth = 0.5;
+        }
+        [numthreads(8, 8, 1)]
+        void cs(uint3 id : SV_DispatchThreadID) {}
+        ",
+    );
+    let entry_points = module
+        .entry_points
+        .iter()
+        .map(|ep| (ep.name.as_str(), ep.stage))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        entry_points,
+        [
+            ("vs", crate::ShaderStage::Vertex),
+            ("ps", crate::ShaderStage::Fragment),
+            ("cs", crate::ShaderStage::Compute),
+        ]
+    );
+    assert_eq!(module.entry_points[2].workgroup_size, [8, 8, 1]);
+}
+
+#[test]
+fn parse_options_entry_points() {
+    let mut options = Options::default();
+    options
+        .entry_points
+        .insert("main".to_string(), crate::ShaderStage::Fragment);
+    let module = parse_str(
+        "float4 main(float2 uv : TEXCOORD0) : SV_Target { return uv.xyxy; }",
+        &options,
+    )
+    .unwrap();
+    assert_eq!(module.entry_points.len(), 1);
+    assert_eq!(module.entry_points[0].stage, crate::ShaderStage::Fragment);
+}
+
+#[test]
+fn parse_statements() {
+    parse(
+        "
+        int sum(int count) {
+            int total = 0;
+            for (int i = 0, j = 1; i < count; ++i, j *= 2) {
+                if (i == 3) continue;
+                total += i & j;
+            }
+            [loop]
+            while (total > 100) {
+                total >>= 1;
+            }
+            do {
+                total--;
+            } while (total > 10 && count != 0);
+            switch (total) {
+                case 0: return -1;
+                case 1:
+                case 2: { total = 2; break; }
+                default: total = 3;
+            }
+            return total;
+        }
+        ",
+    );
+}
+
+#[test]
+fn parse_expressions() {
+    parse(
+        "
+        float4 shade(float3 normal, float4x4 transform, uint mask) {
+            float3 light = normalize(float3(1, -1, 0.5));
+            float ndotl = saturate(dot(normal, light));
+            float4 color = mul(float4(normal, 0), transform) * ndotl;
+            color.rg = color.gr * 0.5 + transform._m00_m11;
+            color.b = (mask & 0x4u) != 0 ? transform[2][1] : -color.a;
+            float2 arr[2] = { float2(1, 2), float2(3, 4) };
+            color.a = arr[mask % 2].y + (float)(mask >> 2) + rcp(fmod(color.r, 2.0));
+            return lerp(color, 1, step(0.5, color.a)) + asfloat(mask);
+        }
+        ",
+    );
+}
+
+#[test]
+fn parse_textures() {
+    parse(
+        "
+        Texture2DArray<float4> layers : register(t0);
+        Texture2D<float> shadow_map : register(t1);
+        RWTexture2D<float4> output : register(u0);
+        SamplerState linear_sampler : register(s0);
+        SamplerComparisonState shadow_sampler : register(s1);
+
+        [numthreads(8, 8, 1)]
+        void main(uint3 id : SV_DispatchThreadID) {
+            uint width, height, elements, levels;
+            layers.GetDimensions(0, width, height, elements, levels);
+            float2 uv = float2(id.xy) / float2(width, height);
+            float4 color = layers.SampleLevel(linear_sampler, float3(uv, 1), 0, int2(1, 0));
+            color += layers.Load(int4(id.xy, 0, 0));
+            color *= shadow_map.SampleCmpLevelZero(shadow_sampler, uv, 0.5);
+            output[id.xy] = color;
+            output[id.xy + 1] = uv.xyxy;
+        }
+        ",
+    );
+}
+
+#[test]
+fn parse_function_calls() {
+    parse(
+        "
+        void split(float value, out int whole, inout float fraction) {
+            whole = (int)value;
+            fraction += frac(value);
+        }
+        float combine(float value) {
+            int whole;
+            float2 parts = 0;
+            split(value, whole, parts.y);
+            return whole + parts.y;
+        }
+        ",
+    );
+}
+
+#[test]
+fn parse_errors() {
+    assert_eq!(
+        parse_error("static const float x = y;"),
+        "no definition in scope for identifier: 'y'"
+    );
+    assert_eq!(
+        parse_error("void f() { int x; int x; }"),
+        "redefinition of 'x'"
+    );
+    assert_eq!(
+        parse_error("void f() {} float g() { return f(); }"),
+        "expression doesn't have a value"
+    );
+    assert_eq!(
+        parse_error("void f() { break; }"),
+        "`break` statement outside of a loop"
+    );
+    assert_eq!(
+        parse_error("void f() { float2 v; v.xq = 0; }"),
+        "invalid swizzle"
+    );
+    assert_eq!(
+        parse_error("void f() { 1 = 2; }"),
+        "expression is not assignable"
+    );
+    assert_eq!(
+        parse_error("[shader(\"vertex\")] float4 main(float4 p) : SV_Position { return p; }"),
+        "entry point interface is missing a semantic"
+    );
+    assert_eq!(
+        parse_error("#include \"common.hlsl\""),
+        "preprocessor directives are not supported"
+    );
+}
@@ -2,6 +2,8 @@
 
 #[cfg(feature = "glsl-in")]
 pub mod glsl;
+#[cfg(feature = "hlsl-in")]
+pub mod hlsl;
 #[cfg(feature = "spv-in")]
 pub mod spv;
 #[cfg(feature = "wgsl-in")]
@@ -29,10 +31,15 @@ impl Emitter {
         self.start_len = Some(arena.len());
     }
     #[must_use]
-    fn finish(&mut self, arena: &Arena<crate::Expression>) -> Option<crate::Statement> {
+    fn finish(
+        &mut self,
+        arena: &Arena<crate::Expression>,
+    ) -> Option<(crate::Statement, crate::Span)> {
         let start_len = self.start_len.take().unwrap();
         if start_len != arena.len() {
-            Some(crate::Statement::Emit(arena.range_from(start_len)))
+            let range = arena.range_from(start_len);
+            let span = crate::Span::total_span(range.clone().map(|h| arena.get_span(h)));
+            Some((crate::Statement::Emit(range), span))
         } else {
             None
         }
@@ -0,0 +1,436 @@
+/*! Assembler of SPIR-V modules from their text form.
+
+The syntax is the one produced by `back::spv::disassemble` and `spirv-dis`.
+Numeric ids like `%12` are kept as they are, while named ids like `%main`
+are given fresh numbers in the order they first appear. Literal numbers can
+be written in decimal, or in hexadecimal with a `0x` prefix, in which case
+floating-point values are taken as their bit patterns.
+!*/
+
+use super::Error;
+use crate::{
+    spv_grammar::{self, EnumKind, NumberType, OperandKind},
+    FastHashMap, FastHashSet,
+};
+use num_traits::FromPrimitive;
+use spirv::{Op, Word};
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Id(String),
+    Word(String),
+    String(String),
+    Equals,
+}
+
+const UNTERMINATED_STRING: &str = "unterminated string";
+
+fn tokenize(line: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = line.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            ';' => break,
+            '=' => {
+                chars.next();
+                tokens.push(Token::Equals);
+            }
+            '"' => {
+                chars.next();
+                let mut string = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(escaped) => string.push(escaped),
+                            None => return Err(UNTERMINATED_STRING.to_string()),
+                        },
+                        Some(other) => string.push(other),
+                        None => return Err(UNTERMINATED_STRING.to_string()),
+                    }
+                }
+                tokens.push(Token::String(string));
+            }
+            _ if c.is_whitespace() => {
+                chars.next();
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == ';' || c == '"' || c == '=' {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                if word.starts_with('%') {
+                    tokens.push(Token::Id(word[1..].to_string()));
+                } else {
+                    tokens.push(Token::Word(word));
+                }
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// Parse an integer literal, keeping the two's complement of negative ones.
+fn parse_integer(token: &str) -> Option<u64> {
+    if token.starts_with('-') {
+        let value = parse_integer(&token[1..])?;
+        if value > i64::MAX as u64 + 1 {
+            return None;
+        }
+        return Some(value.wrapping_neg());
+    }
+    if token.starts_with("0x") || token.starts_with("0X") {
+        u64::from_str_radix(&token[2..], 16).ok()
+    } else {
+        token.parse().ok()
+    }
+}
+
+fn parse_word(token: &str) -> Option<Word> {
+    let value = parse_integer(token)?;
+    if value <= Word::MAX as u64 || (value as i64) >= i32::MIN as i64 {
+        Some(value as Word)
+    } else {
+        None
+    }
+}
+
+fn string_to_words(string: &str) -> Vec<Word> {
+    let mut bytes = string.as_bytes().to_vec();
+    bytes.push(0);
+    bytes
+        .chunks(4)
+        .map(|chunk| {
+            let mut word = [0u8; 4];
+            word[..chunk.len()].copy_from_slice(chunk);
+            u32::from_le_bytes(word)
+        })
+        .collect()
+}
+
+struct Assembler {
+    ids: FastHashMap<String, Word>,
+    instructions: FastHashMap<String, Word>,
+    enumerants: FastHashMap<EnumKind, FastHashMap<String, Word>>,
+    number_types: FastHashMap<Word, NumberType>,
+    value_types: FastHashMap<Word, Word>,
+    glsl_sets: FastHashSet<Word>,
+}
+
+impl Assembler {
+    fn id(&self, token: Option<&Token>) -> Result<Word, String> {
+        match token {
+            Some(token) => match *token {
+                Token::Id(ref name) => Ok(self.ids[name]),
+                ref other => Err(format!("expected an id, found {:?}", other)),
+            },
+            None => Err("expected an id".to_string()),
+        }
+    }
+
+    fn enumerant(&mut self, kind: EnumKind, token: &str) -> Result<Word, String> {
+        let table = self
+            .enumerants
+            .entry(kind)
+            .or_insert_with(|| kind.enumerants().into_iter().collect());
+        match table.get(token) {
+            Some(&value) => Ok(value),
+            None => parse_word(token).ok_or_else(|| format!("unknown {:?} {}", kind, token)),
+        }
+    }
+
+    fn number(&self, ty: Option<NumberType>, token: &str) -> Result<Vec<Word>, String> {
+        let hex = token.starts_with("0x") || token.starts_with("0X");
+        let invalid = || format!("invalid number {}", token);
+        match ty {
+            Some(NumberType::Float { width: 32 }) if !hex => {
+                let value = token.parse::<f32>().map_err(|_| invalid())?;
+                Ok(vec![value.to_bits()])
+            }
+            Some(NumberType::Float { width: 64 }) if !hex => {
+                let bits = token.parse::<f64>().map_err(|_| invalid())?.to_bits();
+                Ok(vec![bits as Word, (bits >> 32) as Word])
+            }
+            Some(ty) if ty.word_count() == 2 => {
+                let value = parse_integer(token).ok_or_else(invalid)?;
+                Ok(vec![value as Word, (value >> 32) as Word])
+            }
+            _ => Ok(vec![parse_word(token).ok_or_else(invalid)?]),
+        }
+    }
+
+    fn instruction(&mut self, tokens: &[Token], words: &mut Vec<Word>) -> Result<(), String> {
+        let (result, rest) = match *tokens {
+            [Token::Id(_), Token::Equals, ref rest @ ..] => (Some(self.id(tokens.first())?), rest),
+            _ => (None, tokens),
+        };
+        let op_name = match rest.first() {
+            Some(token) => match *token {
+                Token::Word(ref name) if name.starts_with("Op") => &name[2..],
+                ref other => return Err(format!("expected an instruction, found {:?}", other)),
+            },
+            None => return Err("expected an instruction".to_string()),
+        };
+        let opcode = *self
+            .instructions
+            .get(op_name)
+            .ok_or_else(|| format!("unknown instruction Op{}", op_name))?;
+        let op = Op::from_u32(opcode).unwrap();
+        let layout = spv_grammar::layout_of(op);
+        let mut tokens = rest[1..].iter();
+
+        let start = words.len();
+        words.push(opcode);
+        let result_type = if layout.result_type {
+            let ty = self.id(tokens.next())?;
+            words.push(ty);
+            Some(ty)
+        } else {
+            None
+        };
+        match (layout.result, result) {
+            (true, Some(id)) => words.push(id),
+            (true, None) => return Err(format!("Op{:?} needs a result id", op)),
+            (false, Some(_)) => return Err(format!("Op{:?} has no result", op)),
+            (false, None) => {}
+        }
+        let operands_start = words.len();
+
+        let number_type = result_type.and_then(|ty| self.number_types.get(&ty).cloned());
+        let mut selector_type = None;
+        let mut kinds = layout.operands.iter().rev().cloned().collect::<Vec<_>>();
+        let mut last_id = None;
+        while let Some(token) = tokens.next() {
+            let kind = kinds.pop().unwrap_or(OperandKind::LiteralRest);
+            match kind {
+                OperandKind::IdRest | OperandKind::LiteralRest | OperandKind::SwitchTargets => {
+                    kinds.push(kind)
+                }
+                _ => {}
+            }
+            let word = match *token {
+                Token::Id(_) => match kind {
+                    OperandKind::Id | OperandKind::IdRest => {
+                        let id = self.id(Some(token))?;
+                        if op == Op::Switch && last_id.is_none() {
+                            selector_type = self
+                                .value_types
+                                .get(&id)
+                                .and_then(|ty| self.number_types.get(ty))
+                                .cloned();
+                        }
+                        last_id = Some(id);
+                        words.push(id);
+                        continue;
+                    }
+                    _ => return Err(format!("unexpected id {:?}", token)),
+                },
+                Token::String(ref string) => match kind {
+                    OperandKind::String => {
+                        words.extend(string_to_words(string));
+                        continue;
+                    }
+                    _ => return Err(format!("unexpected string {:?}", string)),
+                },
+                Token::Equals => return Err("unexpected '='".to_string()),
+                Token::Word(ref word) => word,
+            };
+            match kind {
+                OperandKind::Id | OperandKind::IdRest | OperandKind::String => {
+                    return Err(format!("expected {:?}, found {}", kind, word))
+                }
+                OperandKind::Literal
+                    if op == Op::SpecConstantOp && words.len() == operands_start =>
+                {
+                    // the operation is spelled by its name, without the "Op" prefix
+                    let opcode = match self.instructions.get(word.as_str()) {
+                        Some(&opcode) => opcode,
+                        None => parse_word(word)
+                            .ok_or_else(|| format!("unknown instruction Op{}", word))?,
+                    };
+                    words.push(opcode)
+                }
+                OperandKind::Literal | OperandKind::LiteralRest => {
+                    words.push(parse_word(word).ok_or_else(|| format!("invalid literal {}", word))?)
+                }
+                OperandKind::Number => words.extend(self.number(number_type, word)?),
+                OperandKind::Enum(enum_kind) => {
+                    let value = self.enumerant(enum_kind, word)?;
+                    words.push(value);
+                }
+                OperandKind::Mask(mask_kind) => {
+                    let mut value = 0;
+                    if word != "None" {
+                        for part in word.split('|') {
+                            value |= match mask_kind.bits().iter().find(|&&(name, _)| name == part)
+                            {
+                                Some(&(_, bit)) => bit,
+                                None => parse_word(part)
+                                    .ok_or_else(|| format!("unknown {:?} {}", mask_kind, part))?,
+                            };
+                        }
+                    }
+                    words.push(value);
+                    kinds.extend(mask_kind.followed_by(value).iter().rev());
+                }
+                OperandKind::Decoration => {
+                    let value = self.enumerant(EnumKind::Decoration, word)?;
+                    words.push(value);
+                    kinds.extend(spv_grammar::decoration_operands(value).iter().rev());
+                }
+                OperandKind::ExecutionMode => {
+                    let value = self.enumerant(EnumKind::ExecutionMode, word)?;
+                    words.push(value);
+                    kinds.push(OperandKind::LiteralRest);
+                }
+                OperandKind::ExtInstruction => {
+                    let value = match last_id {
+                        Some(set) if self.glsl_sets.contains(&set) => {
+                            self.enumerant(EnumKind::GlslStd450, word)?
+                        }
+                        _ => parse_word(word).ok_or_else(|| format!("invalid literal {}", word))?,
+                    };
+                    words.push(value);
+                }
+                OperandKind::SwitchTargets => {
+                    words.extend(self.number(selector_type, word)?);
+                    let label = self.id(tokens.next())?;
+                    words.push(label);
+                }
+            }
+        }
+
+        let count = words.len() - start;
+        if count > 0xFFFF {
+            return Err(format!("Op{:?} has too many operands", op));
+        }
+        words[start] |= (count as Word) << 16;
+
+        if let Some(id) = result {
+            if let Some(ty) = result_type {
+                self.value_types.insert(id, ty);
+            }
+            let operands = &words[operands_start..];
+            if let Some(number_type) = NumberType::from_instruction(op, operands) {
+                self.number_types.insert(id, number_type);
+            }
+            if op == Op::ExtInstImport && operands == &string_to_words("GLSL.std.450")[..] {
+                self.glsl_sets.insert(id);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parse a header comment like `; Version: 1.0`, returning the value.
+fn header_value<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let line = line.trim();
+    if !line.starts_with(';') {
+        return None;
+    }
+    let line = line[1..].trim_start();
+    if !line.starts_with(key) {
+        return None;
+    }
+    let line = line[key.len()..].trim_start();
+    if line.starts_with(':') {
+        Some(line[1..].trim())
+    } else {
+        None
+    }
+}
+
+/// Assemble a SPIR-V module from its text form.
+pub fn assemble(text: &str) -> Result<Vec<Word>, Error> {
+    let mut version = 0x1_00_00;
+    let mut generator = 0;
+    let mut bound = 0;
+    let mut lines = Vec::new();
+    let text_lines = text.lines().collect::<Vec<_>>();
+    let mut next_index = 0;
+    while let Some(&line) = text_lines.get(next_index) {
+        let index = next_index;
+        next_index += 1;
+        if let Some(value) = header_value(line, "Version") {
+            let mut parts = value.split('.').map(|part| part.parse::<u8>().ok());
+            if let (Some(Some(major)), Some(Some(minor))) = (parts.next(), parts.next()) {
+                version = (major as Word) << 16 | (minor as Word) << 8;
+            }
+        } else if let Some(value) = header_value(line, "Generator") {
+            generator = value.parse().unwrap_or(0);
+        } else if let Some(value) = header_value(line, "Bound") {
+            bound = value.parse().unwrap_or(0);
+        }
+        // Strings, like embedded sources, may span several lines.
+        let mut joined = line.to_string();
+        let tokens = loop {
+            match tokenize(&joined) {
+                Ok(tokens) => break tokens,
+                Err(ref message)
+                    if message == UNTERMINATED_STRING && next_index < text_lines.len() =>
+                {
+                    joined.push('\n');
+                    joined.push_str(text_lines[next_index]);
+                    next_index += 1;
+                }
+                Err(message) => return Err(Error::InvalidAssembly(index + 1, message)),
+            }
+        };
+        if !tokens.is_empty() {
+            lines.push((index + 1, tokens));
+        }
+    }
+
+    // Numeric ids keep their values, names get the following ones.
+    let mut ids = FastHashMap::default();
+    let mut next_id = 1;
+    for &(line, ref tokens) in lines.iter() {
+        for token in tokens.iter() {
+            if let Token::Id(ref name) = *token {
+                if name.is_empty() || name.starts_with('-') {
+                    return Err(Error::InvalidAssembly(line, "invalid id".to_string()));
+                }
+                if let Ok(id) = name.parse::<Word>() {
+                    next_id = next_id.max(id + 1);
+                    ids.insert(name.clone(), id);
+                }
+            }
+        }
+    }
+    for tokens in lines.iter().map(|line| &line.1) {
+        for token in tokens.iter() {
+            if let Token::Id(ref name) = *token {
+                if !ids.contains_key(name) {
+                    ids.insert(name.clone(), next_id);
+                    next_id += 1;
+                }
+            }
+        }
+    }
+
+    let mut assembler = Assembler {
+        ids,
+        instructions: spv_grammar::instructions().into_iter().collect(),
+        enumerants: FastHashMap::default(),
+        number_types: FastHashMap::default(),
+        value_types: FastHashMap::default(),
+        glsl_sets: FastHashSet::default(),
+    };
+    let mut words = vec![
+        spirv::MAGIC_NUMBER,
+        version,
+        generator,
+        bound.max(next_id),
+        0,
+    ];
+    for &(line, ref tokens) in lines.iter() {
+        assembler
+            .instruction(tokens, &mut words)
+            .map_err(|message| Error::InvalidAssembly(line, message))?;
+    }
+    Ok(words)
+}
@@ -5,6 +5,8 @@ use crate::arena::Handle;
 pub enum Error {
     #[error("invalid header")]
     InvalidHeader,
+    #[error("invalid assembly at line {0}: {1}")]
+    InvalidAssembly(usize, String),
     #[error("invalid word count")]
     InvalidWordCount,
     #[error("unknown instruction {0}")]
@@ -105,9 +107,19 @@ pub enum Error {
     FunctionCallCycle(spirv::Word),
     #[error("invalid array size {0:?}")]
     InvalidArraySize(Handle<crate::Constant>),
+    #[error("invalid gather component %{0}")]
+    InvalidGatherComponent(spirv::Word),
     #[error("invalid barrier scope %{0}")]
     InvalidBarrierScope(spirv::Word),
+    #[error("invalid atomic pointer %{0}")]
+    InvalidAtomicPointer(spirv::Word),
     #[error("invalid barrier memory semantics %{0}")]
     InvalidBarrierMemorySemantics(spirv::Word),
+    #[error("invalid group scope %{0}")]
+    InvalidGroupScope(spirv::Word),
+    #[error("unsupported group operation %{0}")]
+    UnsupportedGroupOperation(spirv::Word),
+    #[error("unsupported specialization constant operation {0:?}")]
+    UnsupportedSpecConstantOp(spirv::Op),
     // incomplete implementation errors
 }
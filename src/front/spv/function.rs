@@ -56,7 +56,9 @@ impl<I: Iterator<Item = u32>> super::Parser<I> {
         from: spirv::Word,
         to: spirv::Word,
     ) -> Handle<crate::Function> {
-        let dummy_handle = self.dummy_functions.append(crate::Function::default());
+        let dummy_handle = self
+            .dummy_functions
+            .append(crate::Function::default(), Default::default());
         self.deferred_function_calls.push(to);
         self.function_call_graph.add_edge(from, to, ());
         dummy_handle
@@ -91,7 +93,7 @@ impl<I: Iterator<Item = u32>> super::Parser<I> {
                 local_variables: Arena::new(),
                 expressions: self.make_expression_storage(),
                 named_expressions: crate::FastHashMap::default(),
-                body: Vec::new(),
+                body: crate::Block::new(),
             }
         };
 
@@ -104,9 +106,10 @@ impl<I: Iterator<Item = u32>> super::Parser<I> {
                 } => {
                     let type_id = self.next()?;
                     let id = self.next()?;
-                    let handle = fun
-                        .expressions
-                        .append(crate::Expression::FunctionArgument(i as u32));
+                    let handle = fun.expressions.append(
+                        crate::Expression::FunctionArgument(i as u32),
+                        Default::default(),
+                    );
                     self.lookup_expression
                         .insert(id, LookupExpression { handle, type_id });
                     //Note: we redo the lookup in order to work around `self` borrowing
@@ -173,6 +176,31 @@ impl<I: Iterator<Item = u32>> super::Parser<I> {
             }
         }
 
+        // Name the expressions after their `OpName`s. Variables, arguments and
+        // the other expressions that aren't emitted are named elsewhere, and
+        // pointers can't be bound to names by all the backends.
+        for (&id, lookup) in self.lookup_expression.iter() {
+            if fun.expressions[lookup.handle].needs_pre_emit() {
+                continue;
+            }
+            match self.lookup_type.get(&lookup.type_id) {
+                Some(lookup_ty) => match module.types[lookup_ty.handle].inner {
+                    crate::TypeInner::Pointer { .. } | crate::TypeInner::ValuePointer { .. } => {
+                        continue
+                    }
+                    _ => {}
+                },
+                None => continue,
+            }
+            if let Some(name) = self
+                .future_decor
+                .get_mut(&id)
+                .and_then(|decor| decor.name.take())
+            {
+                fun.named_expressions.insert(lookup.handle, name);
+            }
+        }
+
         flow_graph.classify();
         flow_graph.remove_phi_instructions(&self.lookup_expression);
 
@@ -191,7 +219,7 @@ impl<I: Iterator<Item = u32>> super::Parser<I> {
         fun.body = flow_graph.convert_to_naga()?;
 
         // done
-        let fun_handle = module.functions.append(fun);
+        let fun_handle = module.functions.append(fun, Default::default());
         self.lookup_function.insert(fun_id, fun_handle);
         self.function_info.push(function_info);
         if let Some(ep) = self.lookup_entry_point.remove(&fun_id) {
@@ -203,41 +231,47 @@ impl<I: Iterator<Item = u32>> super::Parser<I> {
                 local_variables: Arena::new(),
                 expressions: Arena::new(),
                 named_expressions: crate::FastHashMap::default(),
-                body: Vec::new(),
+                body: crate::Block::new(),
             };
 
             // 1. copy the inputs from arguments to privates
             for &v_id in ep.variable_ids.iter() {
                 let lvar = self.lookup_variable.lookup(v_id)?;
                 if let super::Variable::Input(ref arg) = lvar.inner {
-                    let arg_expr =
-                        function
-                            .expressions
-                            .append(crate::Expression::FunctionArgument(
-                                function.arguments.len() as u32,
-                            ));
+                    let arg_expr = function.expressions.append(
+                        crate::Expression::FunctionArgument(function.arguments.len() as u32),
+                        Default::default(),
+                    );
                     let load_expr = if arg.ty == module.global_variables[lvar.handle].ty {
                         arg_expr
                     } else {
                         // The only case where the type is different is if we need to treat
                         // unsigned integer as signed.
                         let old_len = function.expressions.len();
-                        let handle = function.expressions.append(crate::Expression::As {
-                            expr: arg_expr,
-                            kind: crate::ScalarKind::Sint,
-                            convert: Some(4),
-                        });
-                        function.body.push(crate::Statement::Emit(
-                            function.expressions.range_from(old_len),
-                        ));
+                        let handle = function.expressions.append(
+                            crate::Expression::As {
+                                expr: arg_expr,
+                                kind: crate::ScalarKind::Sint,
+                                convert: Some(4),
+                            },
+                            Default::default(),
+                        );
+                        function.body.push(
+                            crate::Statement::Emit(function.expressions.range_from(old_len)),
+                            Default::default(),
+                        );
                         handle
                     };
-                    function.body.push(crate::Statement::Store {
-                        pointer: function
-                            .expressions
-                            .append(crate::Expression::GlobalVariable(lvar.handle)),
-                        value: load_expr,
-                    });
+                    function.body.push(
+                        crate::Statement::Store {
+                            pointer: function.expressions.append(
+                                crate::Expression::GlobalVariable(lvar.handle),
+                                Default::default(),
+                            ),
+                            value: load_expr,
+                        },
+                        Default::default(),
+                    );
 
                     let mut arg = arg.clone();
                     if ep.stage == crate::ShaderStage::Fragment {
@@ -256,11 +290,14 @@ impl<I: Iterator<Item = u32>> super::Parser<I> {
             // 2. call the wrapped function
             let fake_id = !(module.entry_points.len() as u32); // doesn't matter, as long as it's not a collision
             let dummy_handle = self.add_call(fake_id, fun_id);
-            function.body.push(crate::Statement::Call {
-                function: dummy_handle,
-                arguments: Vec::new(),
-                result: None,
-            });
+            function.body.push(
+                crate::Statement::Call {
+                    function: dummy_handle,
+                    arguments: Vec::new(),
+                    result: None,
+                },
+                Default::default(),
+            );
 
             // 3. copy the outputs from privates to the result
             let mut members = Vec::new();
@@ -268,9 +305,10 @@ impl<I: Iterator<Item = u32>> super::Parser<I> {
             for &v_id in ep.variable_ids.iter() {
                 let lvar = self.lookup_variable.lookup(v_id)?;
                 if let super::Variable::Output(ref result) = lvar.inner {
-                    let expr_handle = function
-                        .expressions
-                        .append(crate::Expression::GlobalVariable(lvar.handle));
+                    let expr_handle = function.expressions.append(
+                        crate::Expression::GlobalVariable(lvar.handle),
+                        Default::default(),
+                    );
                     match module.types[result.ty].inner {
                         crate::TypeInner::Struct {
                             members: ref sub_members,
@@ -287,6 +325,7 @@ impl<I: Iterator<Item = u32>> super::Parser<I> {
                                         base: expr_handle,
                                         index: index as u32,
                                     },
+                                    Default::default(),
                                 ));
                             }
                         }
@@ -312,25 +351,37 @@ impl<I: Iterator<Item = u32>> super::Parser<I> {
                     {
                         let old_len = function.expressions.len();
                         let global_expr = components[member_index];
-                        let access_expr =
-                            function.expressions.append(crate::Expression::AccessIndex {
+                        let access_expr = function.expressions.append(
+                            crate::Expression::AccessIndex {
                                 base: global_expr,
                                 index: 1,
-                            });
-                        let load_expr = function.expressions.append(crate::Expression::Load {
-                            pointer: access_expr,
-                        });
-                        let neg_expr = function.expressions.append(crate::Expression::Unary {
-                            op: crate::UnaryOperator::Negate,
-                            expr: load_expr,
-                        });
-                        function.body.push(crate::Statement::Emit(
-                            function.expressions.range_from(old_len),
-                        ));
-                        function.body.push(crate::Statement::Store {
-                            pointer: access_expr,
-                            value: neg_expr,
-                        });
+                            },
+                            Default::default(),
+                        );
+                        let load_expr = function.expressions.append(
+                            crate::Expression::Load {
+                                pointer: access_expr,
+                            },
+                            Default::default(),
+                        );
+                        let neg_expr = function.expressions.append(
+                            crate::Expression::Unary {
+                                op: crate::UnaryOperator::Negate,
+                                expr: load_expr,
+                            },
+                            Default::default(),
+                        );
+                        function.body.push(
+                            crate::Statement::Emit(function.expressions.range_from(old_len)),
+                            Default::default(),
+                        );
+                        function.body.push(
+                            crate::Statement::Store {
+                                pointer: access_expr,
+                                value: neg_expr,
+                            },
+                            Default::default(),
+                        );
                     }
                     _ => {}
                 }
@@ -341,41 +392,53 @@ impl<I: Iterator<Item = u32>> super::Parser<I> {
                 let load_expr = crate::Expression::Load {
                     pointer: *component,
                 };
-                *component = function.expressions.append(load_expr);
+                *component = function.expressions.append(load_expr, Default::default());
             }
 
             match &members[..] {
                 [] => {}
                 [member] => {
-                    function.body.push(crate::Statement::Emit(
-                        function.expressions.range_from(old_len),
-                    ));
-                    function.body.push(crate::Statement::Return {
-                        value: components.first().cloned(),
-                    });
+                    function.body.push(
+                        crate::Statement::Emit(function.expressions.range_from(old_len)),
+                        Default::default(),
+                    );
+                    function.body.push(
+                        crate::Statement::Return {
+                            value: components.first().cloned(),
+                        },
+                        Default::default(),
+                    );
                     function.result = Some(crate::FunctionResult {
                         ty: member.ty,
                         binding: member.binding.clone(),
                     });
                 }
                 _ => {
-                    let ty = module.types.append(crate::Type {
-                        name: None,
-                        inner: crate::TypeInner::Struct {
-                            top_level: false,
-                            members,
-                            span: 0xFFFF, // shouldn't matter
+                    let ty = module.types.append(
+                        crate::Type {
+                            name: None,
+                            inner: crate::TypeInner::Struct {
+                                top_level: false,
+                                members,
+                                span: 0xFFFF, // shouldn't matter
+                            },
                         },
-                    });
-                    let result_expr = function
-                        .expressions
-                        .append(crate::Expression::Compose { ty, components });
-                    function.body.push(crate::Statement::Emit(
-                        function.expressions.range_from(old_len),
-                    ));
-                    function.body.push(crate::Statement::Return {
-                        value: Some(result_expr),
-                    });
+                        Default::default(),
+                    );
+                    let result_expr = function.expressions.append(
+                        crate::Expression::Compose { ty, components },
+                        Default::default(),
+                    );
+                    function.body.push(
+                        crate::Statement::Emit(function.expressions.range_from(old_len)),
+                        Default::default(),
+                    );
+                    function.body.push(
+                        crate::Statement::Return {
+                            value: Some(result_expr),
+                        },
+                        Default::default(),
+                    );
                     function.result = Some(crate::FunctionResult { ty, binding: None });
                 }
             }
@@ -27,13 +27,36 @@ impl Arena<crate::Expression> {
         handle: Handle<crate::Expression>,
         global_vars: &Arena<crate::GlobalVariable>,
         arguments: &[FunctionArgument],
+        types: &Arena<crate::Type>,
     ) -> Result<Handle<crate::Type>, Error> {
         match self[handle] {
             crate::Expression::GlobalVariable(handle) => Ok(global_vars[handle].ty),
             crate::Expression::FunctionArgument(i) => Ok(arguments[i as usize].ty),
+            crate::Expression::Access { base, .. }
+            | crate::Expression::AccessIndex { base, .. } => {
+                let array_ty = self.get_image_expr_ty(base, global_vars, arguments, types)?;
+                match types[array_ty].inner {
+                    crate::TypeInner::BindingArray { base, .. } => Ok(base),
+                    _ => Err(Error::InvalidImageExpression(self[handle].clone())),
+                }
+            }
             ref other => Err(Error::InvalidImageExpression(other.clone())),
         }
     }
+
+    /// Return the global variable that `handle` refers to, directly
+    /// or as an element of a binding array.
+    pub(super) fn get_global_var(
+        &self,
+        handle: Handle<crate::Expression>,
+    ) -> Option<Handle<crate::GlobalVariable>> {
+        match self[handle] {
+            crate::Expression::GlobalVariable(handle) => Some(handle),
+            crate::Expression::Access { base, .. }
+            | crate::Expression::AccessIndex { base, .. } => self.get_global_var(base),
+            _ => None,
+        }
+    }
 }
 
 /// Options of a sampling operation.
@@ -44,6 +67,8 @@ pub struct SamplingOptions {
     pub project: bool,
     /// Depth comparison sampling with a reference value.
     pub compare: bool,
+    /// Gathering a component of the four texels in the footprint.
+    pub gather: bool,
 }
 
 enum ExtraCoordinate {
@@ -92,56 +117,80 @@ fn extract_image_coordinates(
     match extra_coordinate {
         ExtraCoordinate::ArrayLayer => {
             let extracted = match required_size {
-                None => expressions.append(crate::Expression::AccessIndex { base, index: 0 }),
+                None => expressions.append(
+                    crate::Expression::AccessIndex { base, index: 0 },
+                    Default::default(),
+                ),
                 Some(size) => {
                     let mut components = Vec::with_capacity(size as usize);
                     for index in 0..size as u32 {
-                        let comp =
-                            expressions.append(crate::Expression::AccessIndex { base, index });
+                        let comp = expressions.append(
+                            crate::Expression::AccessIndex { base, index },
+                            Default::default(),
+                        );
                         components.push(comp);
                     }
-                    expressions.append(crate::Expression::Compose {
-                        ty: required_ty.unwrap(),
-                        components,
-                    })
+                    expressions.append(
+                        crate::Expression::Compose {
+                            ty: required_ty.unwrap(),
+                            components,
+                        },
+                        Default::default(),
+                    )
                 }
             };
-            let array_index_f32 = expressions.append(extra_expr);
-            let array_index = expressions.append(crate::Expression::As {
-                kind: crate::ScalarKind::Sint,
-                expr: array_index_f32,
-                convert: Some(4),
-            });
+            let array_index_f32 = expressions.append(extra_expr, Default::default());
+            let array_index = expressions.append(
+                crate::Expression::As {
+                    kind: crate::ScalarKind::Sint,
+                    expr: array_index_f32,
+                    convert: Some(4),
+                },
+                Default::default(),
+            );
             (extracted, Some(array_index))
         }
         ExtraCoordinate::Projection => {
-            let projection = expressions.append(extra_expr);
+            let projection = expressions.append(extra_expr, Default::default());
             let divided = match required_size {
                 None => {
-                    let temp =
-                        expressions.append(crate::Expression::AccessIndex { base, index: 0 });
-                    expressions.append(crate::Expression::Binary {
-                        op: crate::BinaryOperator::Divide,
-                        left: temp,
-                        right: projection,
-                    })
+                    let temp = expressions.append(
+                        crate::Expression::AccessIndex { base, index: 0 },
+                        Default::default(),
+                    );
+                    expressions.append(
+                        crate::Expression::Binary {
+                            op: crate::BinaryOperator::Divide,
+                            left: temp,
+                            right: projection,
+                        },
+                        Default::default(),
+                    )
                 }
                 Some(size) => {
                     let mut components = Vec::with_capacity(size as usize);
                     for index in 0..size as u32 {
-                        let temp =
-                            expressions.append(crate::Expression::AccessIndex { base, index });
-                        let comp = expressions.append(crate::Expression::Binary {
-                            op: crate::BinaryOperator::Divide,
-                            left: temp,
-                            right: projection,
-                        });
+                        let temp = expressions.append(
+                            crate::Expression::AccessIndex { base, index },
+                            Default::default(),
+                        );
+                        let comp = expressions.append(
+                            crate::Expression::Binary {
+                                op: crate::BinaryOperator::Divide,
+                                left: temp,
+                                right: projection,
+                            },
+                            Default::default(),
+                        );
                         components.push(comp);
                     }
-                    expressions.append(crate::Expression::Compose {
-                        ty: required_ty.unwrap(),
-                        components,
-                    })
+                    expressions.append(
+                        crate::Expression::Compose {
+                            ty: required_ty.unwrap(),
+                            components,
+                        },
+                        Default::default(),
+                    )
                 }
             };
             (divided, None)
@@ -157,7 +206,7 @@ fn extract_image_coordinates(
                     pattern: [Sc::X, Sc::Y, Sc::Z, Sc::W],
                 },
             };
-            (expressions.append(cut_expr), None)
+            (expressions.append(cut_expr, Default::default()), None)
         }
     }
 }
@@ -175,7 +224,12 @@ pub(super) fn patch_comparison_type(
     }
 
     log::debug!("Flipping comparison for {:?}", var);
-    let original_ty = &arena[var.ty];
+    let (original_ty, binding_array) = match arena[var.ty].inner {
+        crate::TypeInner::BindingArray { base, size } => {
+            (&arena[base], Some((size, arena[var.ty].name.clone())))
+        }
+        _ => (&arena[var.ty], None),
+    };
     let ty_inner = match original_ty.inner {
         crate::TypeInner::Image {
             class: _,
@@ -191,10 +245,22 @@ pub(super) fn patch_comparison_type(
     };
 
     let name = original_ty.name.clone();
-    var.ty = arena.append(crate::Type {
-        name,
-        inner: ty_inner,
-    });
+    var.ty = arena.append(
+        crate::Type {
+            name,
+            inner: ty_inner,
+        },
+        Default::default(),
+    );
+    if let Some((size, name)) = binding_array {
+        var.ty = arena.append(
+            crate::Type {
+                name,
+                inner: crate::TypeInner::BindingArray { base: var.ty, size },
+            },
+            Default::default(),
+        );
+    }
     true
 }
 
@@ -253,7 +319,12 @@ impl<I: Iterator<Item = u32>> super::Parser<I> {
         }
 
         let image_lexp = self.lookup_expression.lookup(image_id)?;
-        let image_ty = expressions.get_image_expr_ty(image_lexp.handle, global_arena, arguments)?;
+        let image_ty = expressions.get_image_expr_ty(
+            image_lexp.handle,
+            global_arena,
+            arguments,
+            type_arena,
+        )?;
 
         let coord_lexp = self.lookup_expression.lookup(coordinate_id)?;
         let coord_type_handle = self.lookup_type.lookup(coord_lexp.type_id)?.handle;
@@ -335,7 +406,12 @@ impl<I: Iterator<Item = u32>> super::Parser<I> {
         }
 
         let image_lexp = self.lookup_expression.lookup(image_id)?;
-        let image_ty = expressions.get_image_expr_ty(image_lexp.handle, global_arena, arguments)?;
+        let image_ty = expressions.get_image_expr_ty(
+            image_lexp.handle,
+            global_arena,
+            arguments,
+            type_arena,
+        )?;
 
         let coord_lexp = self.lookup_expression.lookup(coordinate_id)?;
         let coord_type_handle = self.lookup_type.lookup(coord_lexp.type_id)?.handle;
@@ -368,7 +444,7 @@ impl<I: Iterator<Item = u32>> super::Parser<I> {
         self.lookup_expression.insert(
             result_id,
             LookupExpression {
-                handle: expressions.append(expr),
+                handle: expressions.append(expr, self.span),
                 type_id: result_type_id,
             },
         );
@@ -381,6 +457,7 @@ impl<I: Iterator<Item = u32>> super::Parser<I> {
         mut words_left: u16,
         options: SamplingOptions,
         type_arena: &Arena<crate::Type>,
+        const_arena: &Arena<crate::Constant>,
         global_arena: &Arena<crate::GlobalVariable>,
         arguments: &[FunctionArgument],
         expressions: &mut Arena<crate::Expression>,
@@ -395,6 +472,29 @@ impl<I: Iterator<Item = u32>> super::Parser<I> {
         } else {
             None
         };
+        let gather = if !options.gather {
+            None
+        } else if options.compare {
+            Some(crate::SwizzleComponent::X)
+        } else {
+            let component_id = self.next()?;
+            let component_const = self.lookup_constant.lookup(component_id)?;
+            let index = match const_arena[component_const.handle].inner {
+                crate::ConstantInner::Scalar {
+                    value: crate::ScalarValue::Uint(raw),
+                    width: _,
+                } => raw,
+                crate::ConstantInner::Scalar {
+                    value: crate::ScalarValue::Sint(raw),
+                    width: _,
+                } if raw >= 0 => raw as u64,
+                _ => return Err(Error::InvalidGatherComponent(component_id)),
+            };
+            match crate::SwizzleComponent::XYZW.get(index as usize) {
+                Some(&component) => Some(component),
+                None => return Err(Error::InvalidGatherComponent(component_id)),
+            }
+        };
 
         let mut image_ops = if words_left != 0 {
             words_left -= 1;
@@ -403,7 +503,11 @@ impl<I: Iterator<Item = u32>> super::Parser<I> {
             0
         };
 
-        let mut level = crate::SampleLevel::Auto;
+        let mut level = if options.gather {
+            crate::SampleLevel::Zero
+        } else {
+            crate::SampleLevel::Auto
+        };
         let mut offset = None;
         while image_ops != 0 {
             let bit = 1 << image_ops.trailing_zeros();
@@ -448,30 +552,37 @@ impl<I: Iterator<Item = u32>> super::Parser<I> {
         };
 
         let image_ty = match expressions[si_lexp.image] {
-            crate::Expression::GlobalVariable(handle) => {
-                if let Some(flags) = self.handle_sampling.get_mut(&handle) {
-                    *flags |= sampling_bit;
-                }
-
-                global_arena[handle].ty
-            }
             crate::Expression::FunctionArgument(i) => {
                 let flags = &mut function_info.parameters_sampling[i as usize];
                 *flags |= sampling_bit;
 
                 arguments[i as usize].ty
             }
-            ref other => return Err(Error::InvalidGlobalVar(other.clone())),
+            ref other => match expressions.get_global_var(si_lexp.image) {
+                Some(handle) => {
+                    if let Some(flags) = self.handle_sampling.get_mut(&handle) {
+                        *flags |= sampling_bit;
+                    }
+
+                    expressions.get_image_expr_ty(
+                        si_lexp.image,
+                        global_arena,
+                        arguments,
+                        type_arena,
+                    )?
+                }
+                None => return Err(Error::InvalidGlobalVar(other.clone())),
+            },
         };
         match expressions[si_lexp.sampler] {
-            crate::Expression::GlobalVariable(handle) => {
-                *self.handle_sampling.get_mut(&handle).unwrap() |= sampling_bit
-            }
             crate::Expression::FunctionArgument(i) => {
                 let flags = &mut function_info.parameters_sampling[i as usize];
                 *flags |= sampling_bit;
             }
-            ref other => return Err(Error::InvalidGlobalVar(other.clone())),
+            ref other => match expressions.get_global_var(si_lexp.sampler) {
+                Some(handle) => *self.handle_sampling.get_mut(&handle).unwrap() |= sampling_bit,
+                None => return Err(Error::InvalidGlobalVar(other.clone())),
+            },
         }
 
         let (coordinate, array_index) = match type_arena[image_ty].inner {
@@ -507,11 +618,12 @@ impl<I: Iterator<Item = u32>> super::Parser<I> {
                 Some(id) => Some(self.lookup_expression.lookup(id)?.handle),
                 None => None,
             },
+            gather,
         };
         self.lookup_expression.insert(
             result_id,
             LookupExpression {
-                handle: expressions.append(expr),
+                handle: expressions.append(expr, self.span),
                 type_id: result_type_id,
             },
         );
@@ -544,7 +656,7 @@ impl<I: Iterator<Item = u32>> super::Parser<I> {
         self.lookup_expression.insert(
             result_id,
             LookupExpression {
-                handle: expressions.append(expr),
+                handle: expressions.append(expr, self.span),
                 type_id: result_type_id,
             },
         );
@@ -569,7 +681,7 @@ impl<I: Iterator<Item = u32>> super::Parser<I> {
         self.lookup_expression.insert(
             result_id,
             LookupExpression {
-                handle: expressions.append(expr),
+                handle: expressions.append(expr, self.span),
                 type_id: result_type_id,
             },
         );
@@ -26,6 +26,7 @@ This value then gets used instead of `OpLoad` result later on.
 !*/
 #![allow(dead_code)]
 
+mod assembler;
 mod convert;
 mod error;
 mod flow;
@@ -33,6 +34,7 @@ mod function;
 mod image;
 mod null;
 
+pub use assembler::assemble;
 use convert::*;
 pub use error::Error;
 use flow::*;
@@ -70,6 +72,12 @@ pub const SUPPORTED_CAPABILITIES: &[spirv::Capability] = &[
     spirv::Capability::Int16,
     spirv::Capability::Int64,
     spirv::Capability::Geometry,
+    spirv::Capability::GroupNonUniform,
+    spirv::Capability::GroupNonUniformVote,
+    spirv::Capability::GroupNonUniformArithmetic,
+    spirv::Capability::GroupNonUniformBallot,
+    spirv::Capability::GroupNonUniformShuffle,
+    spirv::Capability::GroupNonUniformShuffleRelative,
     // tricky ones
     spirv::Capability::UniformBufferArrayDynamicIndexing,
     spirv::Capability::StorageBufferArrayDynamicIndexing,
@@ -183,6 +191,18 @@ fn check_sample_coordinates(
     }
 }
 
+/// Return `true` if an array of `base` can only be a binding array:
+/// images and samplers, or blocks without an explicit array stride.
+fn is_binding_array_base(base: &crate::TypeInner, decor: &Decoration) -> bool {
+    match *base {
+        crate::TypeInner::Image { .. } | crate::TypeInner::Sampler { .. } => true,
+        crate::TypeInner::Struct {
+            top_level: true, ..
+        } => decor.array_stride.is_none(),
+        _ => false,
+    }
+}
+
 impl crate::ImageDimension {
     fn required_coordinate_size(&self) -> Option<crate::VectorSize> {
         match *self {
@@ -372,6 +392,51 @@ impl Default for Options {
     }
 }
 
+/// Source code embedded into a module with `OpSource`.
+///
+/// The spans of the parsed module point into it, following `OpLine`.
+#[derive(Clone, Debug)]
+pub struct DebugSource {
+    /// Name of the source file, from the `OpString` referenced by `OpSource`.
+    pub file_name: Option<String>,
+    /// Text of the source file.
+    pub source_code: String,
+}
+
+/// Location given by an `OpLine` instruction.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LineLocation {
+    /// Name of the file, from the `OpString` referenced by `OpLine`.
+    pub file_name: Option<String>,
+    pub line: u32,
+    pub column: u32,
+    /// Span given to the IR following the `OpLine`.
+    pub span: crate::Span,
+}
+
+/// Debug information of a parsed module.
+///
+/// Lines of the file embedded with `OpSource` get spans into its source code.
+/// Lines of other files get spans past the end of it, which only make sense
+/// through the line table.
+#[derive(Clone, Debug, Default)]
+pub struct DebugInfo {
+    /// Source code embedded with `OpSource`, if any.
+    pub source: Option<DebugSource>,
+    /// Line table, with the location of every `OpLine` of the module.
+    pub lines: Vec<LineLocation>,
+}
+
+impl DebugInfo {
+    /// Return the location of the `OpLine` that `span` starts at, if any.
+    pub fn location(&self, span: crate::Span) -> Option<&LineLocation> {
+        let start = span.to_range()?.start;
+        self.lines
+            .iter()
+            .find(|location| location.span.to_range().map(|range| range.start) == Some(start))
+    }
+}
+
 struct FunctionInfo {
     parameters_sampling: Vec<image::SamplingFlags>,
 }
@@ -411,6 +476,25 @@ pub struct Parser<I> {
     index_constants: Vec<Handle<crate::Constant>>,
     index_constant_expressions: Vec<Handle<crate::Expression>>,
     function_info: Vec<FunctionInfo>,
+    /// Global variables, and access chains within them, used by atomic instructions.
+    upgrade_atomics: Vec<(Handle<crate::GlobalVariable>, Vec<Option<u32>>)>,
+    /// Strings declared with `OpString`, naming the files of `OpLine`.
+    debug_strings: FastHashMap<spirv::Word, String>,
+    debug_source: Option<DebugSource>,
+    /// Id of the file name of `debug_source`, if any.
+    debug_source_file_id: Option<spirv::Word>,
+    /// Byte offsets at which the lines of `debug_source` start.
+    source_line_starts: Vec<usize>,
+    /// Set if `OpSourceContinued` extends `debug_source`.
+    source_continued: bool,
+    /// Locations of the `OpLine`s parsed so far, with their spans.
+    line_table: Vec<LineLocation>,
+    /// Spans of the file ids, lines and columns of `line_table`.
+    line_spans: FastHashMap<(spirv::Word, u32, u32), crate::Span>,
+    /// Number of spans given to lines out of `debug_source`.
+    detached_lines: u32,
+    /// Span of the instructions being parsed, as set by the last `OpLine`.
+    span: crate::Span,
 }
 
 impl<I: Iterator<Item = u32>> Parser<I> {
@@ -443,6 +527,16 @@ impl<I: Iterator<Item = u32>> Parser<I> {
             index_constants: Vec::new(),
             index_constant_expressions: Vec::new(),
             function_info: Vec::new(),
+            upgrade_atomics: Vec::new(),
+            debug_strings: FastHashMap::default(),
+            debug_source: None,
+            debug_source_file_id: None,
+            source_line_starts: Vec::new(),
+            source_continued: false,
+            line_table: Vec::new(),
+            line_spans: FastHashMap::default(),
+            detached_lines: 0,
+            span: crate::Span::default(),
         }
     }
 
@@ -579,7 +673,7 @@ impl<I: Iterator<Item = u32>> Parser<I> {
         self.lookup_expression.insert(
             result_id,
             LookupExpression {
-                handle: expressions.append(expr),
+                handle: expressions.append(expr, self.span),
                 type_id: result_type_id,
             },
         );
@@ -607,7 +701,7 @@ impl<I: Iterator<Item = u32>> Parser<I> {
         self.lookup_expression.insert(
             result_id,
             LookupExpression {
-                handle: expressions.append(expr),
+                handle: expressions.append(expr, self.span),
                 type_id: result_type_id,
             },
         );
@@ -638,27 +732,33 @@ impl<I: Iterator<Item = u32>> Parser<I> {
             left: if p1_lexp.type_id == result_type_id {
                 p1_lexp.handle
             } else {
-                expressions.append(crate::Expression::As {
-                    expr: p1_lexp.handle,
-                    kind,
-                    convert: None,
-                })
+                expressions.append(
+                    crate::Expression::As {
+                        expr: p1_lexp.handle,
+                        kind,
+                        convert: None,
+                    },
+                    self.span,
+                )
             },
             right: if p2_lexp.type_id == result_type_id {
                 p2_lexp.handle
             } else {
-                expressions.append(crate::Expression::As {
-                    expr: p2_lexp.handle,
-                    kind,
-                    convert: None,
-                })
+                expressions.append(
+                    crate::Expression::As {
+                        expr: p2_lexp.handle,
+                        kind,
+                        convert: None,
+                    },
+                    self.span,
+                )
             },
         };
 
         self.lookup_expression.insert(
             result_id,
             LookupExpression {
-                handle: expressions.append(expr),
+                handle: expressions.append(expr, self.span),
                 type_id: result_type_id,
             },
         );
@@ -678,11 +778,14 @@ impl<I: Iterator<Item = u32>> Parser<I> {
         let p1_lexp = self.lookup_expression.lookup(p1_id)?;
         let p2_lexp = self.lookup_expression.lookup(p2_id)?;
         // convert the shift to Uint
-        let p2_handle = expressions.append(crate::Expression::As {
-            expr: p2_lexp.handle,
-            kind: crate::ScalarKind::Uint,
-            convert: None,
-        });
+        let p2_handle = expressions.append(
+            crate::Expression::As {
+                expr: p2_lexp.handle,
+                kind: crate::ScalarKind::Uint,
+                convert: None,
+            },
+            self.span,
+        );
 
         let expr = crate::Expression::Binary {
             op,
@@ -692,7 +795,7 @@ impl<I: Iterator<Item = u32>> Parser<I> {
         self.lookup_expression.insert(
             result_id,
             LookupExpression {
-                handle: expressions.append(expr),
+                handle: expressions.append(expr, self.span),
                 type_id: result_type_id,
             },
         );
@@ -717,7 +820,7 @@ impl<I: Iterator<Item = u32>> Parser<I> {
         self.lookup_expression.insert(
             result_id,
             LookupExpression {
-                handle: expressions.append(expr),
+                handle: expressions.append(expr, self.span),
                 type_id: result_type_id,
             },
         );
@@ -759,10 +862,13 @@ impl<I: Iterator<Item = u32>> Parser<I> {
 
         let mut components = Vec::with_capacity(count);
         for index in 0..count as u32 {
-            let expr = expressions.append(crate::Expression::AccessIndex {
-                base: root_expr,
-                index,
-            });
+            let expr = expressions.append(
+                crate::Expression::AccessIndex {
+                    base: root_expr,
+                    index,
+                },
+                self.span,
+            );
             components.push(expr);
         }
         components[selection as usize] = self.insert_composite(
@@ -774,10 +880,13 @@ impl<I: Iterator<Item = u32>> Parser<I> {
             expressions,
         )?;
 
-        Ok(expressions.append(crate::Expression::Compose {
-            ty: root_lookup.handle,
-            components,
-        }))
+        Ok(expressions.append(
+            crate::Expression::Compose {
+                ty: root_lookup.handle,
+                components,
+            },
+            self.span,
+        ))
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -793,8 +902,10 @@ impl<I: Iterator<Item = u32>> Parser<I> {
         arguments: &[crate::FunctionArgument],
         function_info: &mut FunctionInfo,
     ) -> Result<ControlFlowNode, Error> {
-        let mut block = Vec::new();
+        let mut block = crate::Block::new();
         let mut phis = Vec::new();
+        // The scope of `OpLine` ends with the block.
+        self.span = crate::Span::default();
         let mut emitter = super::Emitter::default();
         emitter.start(expressions);
         let mut merge = None;
@@ -804,12 +915,8 @@ impl<I: Iterator<Item = u32>> Parser<I> {
             log::debug!("\t\t{:?} [{}]", inst.op, inst.wc);
 
             match inst.op {
-                Op::Line => {
-                    inst.expect(4)?;
-                    let _file_id = self.next()?;
-                    let _row_id = self.next()?;
-                    let _col_id = self.next()?;
-                }
+                Op::Line => self.parse_line(inst)?,
+                Op::NoLine => self.parse_no_line(inst)?,
                 Op::Undef => {
                     inst.expect(3)?;
                     let _result_type_id = self.next()?;
@@ -840,20 +947,23 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                         log::debug!("\t\t\tid={} name={}", result_id, name);
                     }
                     let lookup_ty = self.lookup_type.lookup(result_type_id)?;
-                    let var_handle = local_arena.append(crate::LocalVariable {
-                        name,
-                        ty: match type_arena[lookup_ty.handle].inner {
-                            crate::TypeInner::Pointer { base, .. } => base,
-                            _ => lookup_ty.handle,
+                    let var_handle = local_arena.append(
+                        crate::LocalVariable {
+                            name,
+                            ty: match type_arena[lookup_ty.handle].inner {
+                                crate::TypeInner::Pointer { base, .. } => base,
+                                _ => lookup_ty.handle,
+                            },
+                            init,
                         },
-                        init,
-                    });
+                        self.span,
+                    );
 
                     self.lookup_expression.insert(
                         result_id,
                         LookupExpression {
                             handle: expressions
-                                .append(crate::Expression::LocalVariable(var_handle)),
+                                .append(crate::Expression::LocalVariable(var_handle), self.span),
                             type_id: result_type_id,
                         },
                     );
@@ -867,12 +977,16 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                     let result_id = self.next()?;
 
                     let name = format!("phi_{}", result_id);
-                    let var_handle = local_arena.append(crate::LocalVariable {
-                        name: Some(name),
-                        ty: self.lookup_type.lookup(result_type_id)?.handle,
-                        init: None,
-                    });
-                    let pointer = expressions.append(crate::Expression::LocalVariable(var_handle));
+                    let var_handle = local_arena.append(
+                        crate::LocalVariable {
+                            name: Some(name),
+                            ty: self.lookup_type.lookup(result_type_id)?.handle,
+                            init: None,
+                        },
+                        self.span,
+                    );
+                    let pointer =
+                        expressions.append(crate::Expression::LocalVariable(var_handle), self.span);
 
                     let in_count = (inst.wc - 3) / 2;
                     let mut phi = PhiInstruction {
@@ -894,7 +1008,8 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                     self.lookup_expression.insert(
                         result_id,
                         LookupExpression {
-                            handle: expressions.append(crate::Expression::Load { pointer }),
+                            handle: expressions
+                                .append(crate::Expression::Load { pointer }, self.span),
                             type_id: result_type_id,
                         },
                     );
@@ -917,9 +1032,14 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                         // so we dereference it here for the traversal
                         let lexp = self.lookup_expression.lookup(base_id)?;
                         let lty = self.lookup_type.lookup(lexp.type_id)?;
+                        let type_id = match type_arena[lty.handle].inner {
+                            // pointers to handles are represented by the pointee type
+                            crate::TypeInner::BindingArray { .. } => lexp.type_id,
+                            _ => lty.base_id.ok_or(Error::InvalidAccessType(lexp.type_id))?,
+                        };
                         AccessExpression {
                             base_handle: lexp.handle,
-                            type_id: lty.base_id.ok_or(Error::InvalidAccessType(lexp.type_id))?,
+                            type_id,
                             load_override: self.lookup_load_override.get(&base_id).cloned(),
                         }
                     };
@@ -948,11 +1068,13 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                                     .lookup_member
                                     .get(&(type_lookup.handle, index))
                                     .ok_or(Error::InvalidAccessType(acex.type_id))?;
-                                let base_handle =
-                                    expressions.append(crate::Expression::AccessIndex {
+                                let base_handle = expressions.append(
+                                    crate::Expression::AccessIndex {
                                         base: acex.base_handle,
                                         index,
-                                    });
+                                    },
+                                    self.span,
+                                );
                                 AccessExpression {
                                     base_handle,
                                     type_id: lookup_member.type_id,
@@ -963,17 +1085,22 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                                         Some(match type_arena[sub_type_lookup.handle].inner {
                                             // load it transposed, to match column major expectations
                                             crate::TypeInner::Matrix { .. } => {
-                                                let loaded =
-                                                    expressions.append(crate::Expression::Load {
+                                                let loaded = expressions.append(
+                                                    crate::Expression::Load {
                                                         pointer: base_handle,
-                                                    });
-                                                let transposed =
-                                                    expressions.append(crate::Expression::Math {
+                                                    },
+                                                    self.span,
+                                                );
+                                                let transposed = expressions.append(
+                                                    crate::Expression::Math {
                                                         fun: crate::MathFunction::Transpose,
                                                         arg: loaded,
                                                         arg1: None,
                                                         arg2: None,
-                                                    });
+                                                        arg3: None,
+                                                    },
+                                                    self.span,
+                                                );
                                                 LookupLoadOverride::Loaded(transposed)
                                             }
                                             _ => LookupLoadOverride::Pending,
@@ -990,11 +1117,13 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                                         let index = index_maybe.ok_or_else(|| {
                                             Error::InvalidAccess(index_expr_data.clone())
                                         })?;
-                                        let sub_handle =
-                                            expressions.append(crate::Expression::AccessIndex {
+                                        let sub_handle = expressions.append(
+                                            crate::Expression::AccessIndex {
                                                 base: load_expr,
                                                 index,
-                                            });
+                                            },
+                                            self.span,
+                                        );
                                         Some(LookupLoadOverride::Loaded(sub_handle))
                                     }
                                     _ => None,
@@ -1010,7 +1139,7 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                                     },
                                 };
                                 AccessExpression {
-                                    base_handle: expressions.append(sub_expr),
+                                    base_handle: expressions.append(sub_expr, self.span),
                                     type_id: type_lookup
                                         .base_id
                                         .ok_or(Error::InvalidAccessType(acex.type_id))?,
@@ -1019,10 +1148,13 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                             }
                             // This must be a vector or an array.
                             _ => {
-                                let base_handle = expressions.append(crate::Expression::Access {
-                                    base: acex.base_handle,
-                                    index: index_expr.handle,
-                                });
+                                let base_handle = expressions.append(
+                                    crate::Expression::Access {
+                                        base: acex.base_handle,
+                                        index: index_expr.handle,
+                                    },
+                                    self.span,
+                                );
                                 let load_override = match acex.load_override {
                                     // If there is a load override in place, then we always end up
                                     // with a side-loaded value here.
@@ -1031,23 +1163,32 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                                             // We must be indexing into the array of row-major matrices.
                                             // Let's load the result of indexing and transpose it.
                                             LookupLoadOverride::Pending => {
-                                                let loaded =
-                                                    expressions.append(crate::Expression::Load {
+                                                let loaded = expressions.append(
+                                                    crate::Expression::Load {
                                                         pointer: base_handle,
-                                                    });
-                                                expressions.append(crate::Expression::Math {
-                                                    fun: crate::MathFunction::Transpose,
-                                                    arg: loaded,
-                                                    arg1: None,
-                                                    arg2: None,
-                                                })
+                                                    },
+                                                    self.span,
+                                                );
+                                                expressions.append(
+                                                    crate::Expression::Math {
+                                                        fun: crate::MathFunction::Transpose,
+                                                        arg: loaded,
+                                                        arg1: None,
+                                                        arg2: None,
+                                                        arg3: None,
+                                                    },
+                                                    self.span,
+                                                )
                                             }
                                             // We are indexing inside a row-major matrix.
                                             LookupLoadOverride::Loaded(load_expr) => expressions
-                                                .append(crate::Expression::Access {
-                                                    base: load_expr,
-                                                    index: index_expr.handle,
-                                                }),
+                                                .append(
+                                                    crate::Expression::Access {
+                                                        base: load_expr,
+                                                        index: index_expr.handle,
+                                                    },
+                                                    self.span,
+                                                ),
                                         };
                                         Some(LookupLoadOverride::Loaded(sub_expr))
                                     }
@@ -1090,25 +1231,37 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                         _ => return Err(Error::InvalidVectorType(root_type_lookup.handle)),
                     };
 
-                    let mut handle = expressions.append(crate::Expression::Access {
-                        base: root_lexp.handle,
-                        index: self.index_constant_expressions[0],
-                    });
-                    for &index_expr in self.index_constant_expressions[1..num_components].iter() {
-                        let access_expr = expressions.append(crate::Expression::Access {
+                    let mut handle = expressions.append(
+                        crate::Expression::Access {
                             base: root_lexp.handle,
-                            index: index_expr,
-                        });
-                        let cond = expressions.append(crate::Expression::Binary {
-                            op: crate::BinaryOperator::Equal,
-                            left: index_expr,
-                            right: index_lexp.handle,
-                        });
-                        handle = expressions.append(crate::Expression::Select {
-                            condition: cond,
-                            accept: access_expr,
-                            reject: handle,
-                        });
+                            index: self.index_constant_expressions[0],
+                        },
+                        self.span,
+                    );
+                    for &index_expr in self.index_constant_expressions[1..num_components].iter() {
+                        let access_expr = expressions.append(
+                            crate::Expression::Access {
+                                base: root_lexp.handle,
+                                index: index_expr,
+                            },
+                            self.span,
+                        );
+                        let cond = expressions.append(
+                            crate::Expression::Binary {
+                                op: crate::BinaryOperator::Equal,
+                                left: index_expr,
+                                right: index_lexp.handle,
+                            },
+                            self.span,
+                        );
+                        handle = expressions.append(
+                            crate::Expression::Select {
+                                condition: cond,
+                                accept: access_expr,
+                                reject: handle,
+                            },
+                            self.span,
+                        );
                     }
 
                     self.lookup_expression.insert(
@@ -1139,26 +1292,38 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                     };
                     let mut components = Vec::with_capacity(num_components);
                     for &index_expr in self.index_constant_expressions[..num_components].iter() {
-                        let access_expr = expressions.append(crate::Expression::Access {
-                            base: root_lexp.handle,
-                            index: index_expr,
-                        });
-                        let cond = expressions.append(crate::Expression::Binary {
-                            op: crate::BinaryOperator::Equal,
-                            left: index_expr,
-                            right: index_lexp.handle,
-                        });
-                        let handle = expressions.append(crate::Expression::Select {
-                            condition: cond,
-                            accept: object_lexp.handle,
-                            reject: access_expr,
-                        });
+                        let access_expr = expressions.append(
+                            crate::Expression::Access {
+                                base: root_lexp.handle,
+                                index: index_expr,
+                            },
+                            self.span,
+                        );
+                        let cond = expressions.append(
+                            crate::Expression::Binary {
+                                op: crate::BinaryOperator::Equal,
+                                left: index_expr,
+                                right: index_lexp.handle,
+                            },
+                            self.span,
+                        );
+                        let handle = expressions.append(
+                            crate::Expression::Select {
+                                condition: cond,
+                                accept: object_lexp.handle,
+                                reject: access_expr,
+                            },
+                            self.span,
+                        );
                         components.push(handle);
                     }
-                    let handle = expressions.append(crate::Expression::Compose {
-                        ty: root_type_lookup.handle,
-                        components,
-                    });
+                    let handle = expressions.append(
+                        crate::Expression::Compose {
+                            ty: root_type_lookup.handle,
+                            components,
+                        },
+                        self.span,
+                    );
 
                     self.lookup_expression.insert(
                         id,
@@ -1198,10 +1363,13 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                             }
                         };
                         lexp = LookupExpression {
-                            handle: expressions.append(crate::Expression::AccessIndex {
-                                base: lexp.handle,
-                                index,
-                            }),
+                            handle: expressions.append(
+                                crate::Expression::AccessIndex {
+                                    base: lexp.handle,
+                                    index,
+                                },
+                                self.span,
+                            ),
                             type_id,
                         };
                     }
@@ -1272,7 +1440,7 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                     self.lookup_expression.insert(
                         id,
                         LookupExpression {
-                            handle: expressions.append(expr),
+                            handle: expressions.append(expr, self.span),
                             type_id: result_type_id,
                         },
                     );
@@ -1297,9 +1465,12 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                         _ => match self.lookup_load_override.get(&pointer_id) {
                             Some(&LookupLoadOverride::Loaded(handle)) => handle,
                             //Note: we aren't handling `LookupLoadOverride::Pending` properly here
-                            _ => expressions.append(crate::Expression::Load {
-                                pointer: base_lexp.handle,
-                            }),
+                            _ => expressions.append(
+                                crate::Expression::Load {
+                                    pointer: base_lexp.handle,
+                                },
+                                self.span,
+                            ),
                         },
                     };
 
@@ -1323,10 +1494,13 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                     }
                     let base_expr = self.lookup_expression.lookup(pointer_id)?;
                     let value_expr = self.lookup_expression.lookup(value_id)?;
-                    block.push(crate::Statement::Store {
-                        pointer: base_expr.handle,
-                        value: value_expr.handle,
-                    });
+                    block.push(
+                        crate::Statement::Store {
+                            pointer: base_expr.handle,
+                            value: value_expr.handle,
+                        },
+                        self.span,
+                    );
                     emitter.start(expressions);
                 }
                 // Arithmetic Instructions +, -, *, /, %
@@ -1390,11 +1564,97 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                         arg: matrix_lexp.handle,
                         arg1: None,
                         arg2: None,
+                        arg3: None,
                     };
                     self.lookup_expression.insert(
                         result_id,
                         LookupExpression {
-                            handle: expressions.append(expr),
+                            handle: expressions.append(expr, self.span),
+                            type_id: result_type_id,
+                        },
+                    );
+                }
+                Op::BitCount | Op::BitReverse => {
+                    inst.expect(4)?;
+
+                    let result_type_id = self.next()?;
+                    let result_id = self.next()?;
+                    let base_id = self.next()?;
+                    let base_lexp = self.lookup_expression.lookup(base_id)?;
+                    let expr = crate::Expression::Math {
+                        fun: match inst.op {
+                            Op::BitCount => crate::MathFunction::CountOneBits,
+                            _ => crate::MathFunction::ReverseBits,
+                        },
+                        arg: base_lexp.handle,
+                        arg1: None,
+                        arg2: None,
+                        arg3: None,
+                    };
+                    self.lookup_expression.insert(
+                        result_id,
+                        LookupExpression {
+                            handle: expressions.append(expr, self.span),
+                            type_id: result_type_id,
+                        },
+                    );
+                }
+                Op::BitFieldInsert | Op::BitFieldSExtract | Op::BitFieldUExtract => {
+                    let insert = inst.op == Op::BitFieldInsert;
+                    inst.expect(if insert { 7 } else { 6 })?;
+
+                    let result_type_id = self.next()?;
+                    let result_id = self.next()?;
+                    let base_id = self.next()?;
+                    let base_handle = self.lookup_expression.lookup(base_id)?.handle;
+                    let insert_handle = if insert {
+                        let insert_id = self.next()?;
+                        Some(self.lookup_expression.lookup(insert_id)?.handle)
+                    } else {
+                        None
+                    };
+                    // offset and count may be signed in SPIR-V
+                    let mut bits = [None; 2];
+                    for bit in bits.iter_mut() {
+                        let bit_id = self.next()?;
+                        let bit_lexp = self.lookup_expression.lookup(bit_id)?;
+                        let bit_handle = bit_lexp.handle;
+                        let bit_type = self.lookup_type.lookup(bit_lexp.type_id)?.handle;
+                        *bit = Some(match type_arena[bit_type].inner {
+                            crate::TypeInner::Scalar {
+                                kind: crate::ScalarKind::Sint,
+                                ..
+                            } => expressions.append(
+                                crate::Expression::As {
+                                    expr: bit_handle,
+                                    kind: crate::ScalarKind::Uint,
+                                    convert: None,
+                                },
+                                self.span,
+                            ),
+                            _ => bit_handle,
+                        });
+                    }
+                    let expr = match insert_handle {
+                        Some(insert_handle) => crate::Expression::Math {
+                            fun: crate::MathFunction::InsertBits,
+                            arg: base_handle,
+                            arg1: Some(insert_handle),
+                            arg2: bits[0],
+                            arg3: bits[1],
+                        },
+                        None => crate::Expression::Math {
+                            fun: crate::MathFunction::ExtractBits,
+                            arg: base_handle,
+                            arg1: bits[0],
+                            arg2: bits[1],
+                            arg3: None,
+                        },
+                    };
+                    self.lookup_expression.insert(
+                        result_id,
+                        LookupExpression {
+                            handle: expressions.append(expr, self.span),
                             type_id: result_type_id,
                         },
                     );
@@ -1413,11 +1673,12 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                         arg: left_lexp.handle,
                         arg1: Some(right_lexp.handle),
                         arg2: None,
+                        arg3: None,
                     };
                     self.lookup_expression.insert(
                         result_id,
                         LookupExpression {
-                            handle: expressions.append(expr),
+                            handle: expressions.append(expr, self.span),
                             type_id: result_type_id,
                         },
                     );
@@ -1436,11 +1697,12 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                         arg: left_lexp.handle,
                         arg1: Some(right_lexp.handle),
                         arg2: None,
+                        arg3: None,
                     };
                     self.lookup_expression.insert(
                         result_id,
                         LookupExpression {
-                            handle: expressions.append(expr),
+                            handle: expressions.append(expr, self.span),
                             type_id: result_type_id,
                         },
                     );
@@ -1495,7 +1757,7 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                         arguments,
                         expressions,
                     )?;
-                    block.push(stmt);
+                    block.push(stmt, self.span);
                     emitter.start(expressions);
                 }
                 Op::ImageFetch | Op::ImageRead => {
@@ -1507,11 +1769,13 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                     let options = image::SamplingOptions {
                         compare: false,
                         project: false,
+                        gather: false,
                     };
                     self.parse_image_sample(
                         extra,
                         options,
                         type_arena,
+                        const_arena,
                         global_arena,
                         arguments,
                         expressions,
@@ -1523,11 +1787,13 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                     let options = image::SamplingOptions {
                         compare: false,
                         project: true,
+                        gather: false,
                     };
                     self.parse_image_sample(
                         extra,
                         options,
                         type_arena,
+                        const_arena,
                         global_arena,
                         arguments,
                         expressions,
@@ -1539,11 +1805,13 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                     let options = image::SamplingOptions {
                         compare: true,
                         project: false,
+                        gather: false,
                     };
                     self.parse_image_sample(
                         extra,
                         options,
                         type_arena,
+                        const_arena,
                         global_arena,
                         arguments,
                         expressions,
@@ -1555,11 +1823,31 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                     let options = image::SamplingOptions {
                         compare: true,
                         project: true,
+                        gather: false,
                     };
                     self.parse_image_sample(
                         extra,
                         options,
                         type_arena,
+                        const_arena,
+                        global_arena,
+                        arguments,
+                        expressions,
+                        function_info,
+                    )?;
+                }
+                Op::ImageGather | Op::ImageDrefGather => {
+                    let extra = inst.expect_at_least(6)?;
+                    let options = image::SamplingOptions {
+                        compare: inst.op == Op::ImageDrefGather,
+                        project: false,
+                        gather: true,
+                    };
+                    self.parse_image_sample(
+                        extra,
+                        options,
+                        type_arena,
+                        const_arena,
                         global_arena,
                         arguments,
                         expressions,
@@ -1603,7 +1891,7 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                     self.lookup_expression.insert(
                         result_id,
                         LookupExpression {
-                            handle: expressions.append(expr),
+                            handle: expressions.append(expr, self.span),
                             type_id: result_type_id,
                         },
                     );
@@ -1681,7 +1969,7 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                             } else {
                                 return Err(Error::InvalidAccessIndex(index));
                             };
-                            components.push(expressions.append(expr));
+                            components.push(expressions.append(expr, self.span));
                         }
                         crate::Expression::Compose {
                             ty: self.lookup_type.lookup(result_type_id)?.handle,
@@ -1692,7 +1980,7 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                     self.lookup_expression.insert(
                         result_id,
                         LookupExpression {
-                            handle: expressions.append(expr),
+                            handle: expressions.append(expr, self.span),
                             type_id: result_type_id,
                         },
                     );
@@ -1731,7 +2019,7 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                     self.lookup_expression.insert(
                         result_id,
                         LookupExpression {
-                            handle: expressions.append(expr),
+                            handle: expressions.append(expr, self.span),
                             type_id: result_type_id,
                         },
                     );
@@ -1756,7 +2044,8 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                     let result = if self.lookup_void_type == Some(result_type_id) {
                         None
                     } else {
-                        let expr_handle = expressions.append(crate::Expression::Call(function));
+                        let expr_handle =
+                            expressions.append(crate::Expression::Call(function), self.span);
                         self.lookup_expression.insert(
                             result_id,
                             LookupExpression {
@@ -1766,11 +2055,14 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                         );
                         Some(expr_handle)
                     };
-                    block.push(crate::Statement::Call {
-                        function,
-                        arguments,
-                        result,
-                    });
+                    block.push(
+                        crate::Statement::Call {
+                            function,
+                            arguments,
+                            result,
+                        },
+                        self.span,
+                    );
                     emitter.start(expressions);
                 }
                 Op::ExtInst => {
@@ -1796,36 +2088,99 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                             self.lookup_expression.lookup(arg_id)?.handle
                         };
 
-                        let constant_handle = const_arena.fetch_or_append(crate::Constant {
-                            name: None,
-                            specialization: None,
-                            inner: crate::ConstantInner::Scalar {
-                                width: 4,
-                                value: crate::ScalarValue::Float(match gl_op {
-                                    Glo::Radians => std::f64::consts::PI / 180.0,
-                                    Glo::Degrees => 180.0 / std::f64::consts::PI,
-                                    _ => unreachable!(),
-                                }),
+                        let constant_handle = const_arena.fetch_or_append(
+                            crate::Constant {
+                                name: None,
+                                specialization: None,
+                                inner: crate::ConstantInner::Scalar {
+                                    width: 4,
+                                    value: crate::ScalarValue::Float(match gl_op {
+                                        Glo::Radians => std::f64::consts::PI / 180.0,
+                                        Glo::Degrees => 180.0 / std::f64::consts::PI,
+                                        _ => unreachable!(),
+                                    }),
+                                },
                             },
-                        });
+                            Default::default(),
+                        );
 
-                        let expr_handle =
-                            expressions.append(crate::Expression::Constant(constant_handle));
+                        let expr_handle = expressions
+                            .append(crate::Expression::Constant(constant_handle), self.span);
 
                         self.lookup_expression.insert(
                             result_id,
                             LookupExpression {
-                                handle: expressions.append(crate::Expression::Binary {
-                                    op: crate::BinaryOperator::Multiply,
-                                    left: arg,
-                                    right: expr_handle,
-                                }),
+                                handle: expressions.append(
+                                    crate::Expression::Binary {
+                                        op: crate::BinaryOperator::Multiply,
+                                        left: arg,
+                                        right: expr_handle,
+                                    },
+                                    self.span,
+                                ),
+                                type_id: result_type_id,
+                            },
+                        );
+                    } else if gl_op == Glo::ModfStruct || gl_op == Glo::FrexpStruct {
+                        inst.expect(base_wc + 1)?;
+                        let arg = {
+                            let arg_id = self.next()?;
+                            self.lookup_expression.lookup(arg_id)?.handle
+                        };
+
+                        // The IR only has the out-parameter forms, so the second
+                        // member is written to a temporary and read back.
+                        let struct_ty = self.lookup_type.lookup(result_type_id)?.handle;
+                        let out_ty = match type_arena[struct_ty].inner {
+                            crate::TypeInner::Struct { ref members, .. } if members.len() == 2 => {
+                                members[1].ty
+                            }
+                            _ => return Err(Error::InvalidInnerType(result_type_id)),
+                        };
+                        block.extend(emitter.finish(expressions));
+                        let var_handle = local_arena.append(
+                            crate::LocalVariable {
+                                name: None,
+                                ty: out_ty,
+                                init: None,
+                            },
+                            self.span,
+                        );
+                        let pointer = expressions
+                            .append(crate::Expression::LocalVariable(var_handle), self.span);
+                        emitter.start(expressions);
+
+                        let first = expressions.append(
+                            crate::Expression::Math {
+                                fun: match gl_op {
+                                    Glo::ModfStruct => Mf::Modf,
+                                    _ => Mf::Frexp,
+                                },
+                                arg,
+                                arg1: Some(pointer),
+                                arg2: None,
+                                arg3: None,
+                            },
+                            self.span,
+                        );
+                        let second =
+                            expressions.append(crate::Expression::Load { pointer }, self.span);
+                        self.lookup_expression.insert(
+                            result_id,
+                            LookupExpression {
+                                handle: expressions.append(
+                                    crate::Expression::Compose {
+                                        ty: struct_ty,
+                                        components: vec![first, second],
+                                    },
+                                    self.span,
+                                ),
                                 type_id: result_type_id,
                             },
                         );
                     } else {
                         let fun = match gl_op {
-                            Glo::Round => Mf::Round,
+                            Glo::Round | Glo::RoundEven => Mf::Round,
                             Glo::Trunc => Mf::Trunc,
                             Glo::FAbs | Glo::SAbs => Mf::Abs,
                             Glo::FSign | Glo::SSign => Mf::Sign,
@@ -1859,7 +2214,7 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                             Glo::Step => Mf::Step,
                             Glo::SmoothStep => Mf::SmoothStep,
                             Glo::Fma => Mf::Fma,
-                            Glo::Frexp => Mf::Frexp, //TODO: FrexpStruct?
+                            Glo::Frexp => Mf::Frexp,
                             Glo::Ldexp => Mf::Ldexp,
                             Glo::Length => Mf::Length,
                             Glo::Distance => Mf::Distance,
@@ -1868,6 +2223,18 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                             Glo::FaceForward => Mf::FaceForward,
                             Glo::Reflect => Mf::Reflect,
                             Glo::Refract => Mf::Refract,
+                            Glo::FindILsb => Mf::FirstTrailingBit,
+                            Glo::FindSMsb | Glo::FindUMsb => Mf::FirstLeadingBit,
+                            Glo::PackSnorm4x8 => Mf::Pack4x8snorm,
+                            Glo::PackUnorm4x8 => Mf::Pack4x8unorm,
+                            Glo::PackSnorm2x16 => Mf::Pack2x16snorm,
+                            Glo::PackUnorm2x16 => Mf::Pack2x16unorm,
+                            Glo::PackHalf2x16 => Mf::Pack2x16float,
+                            Glo::UnpackSnorm4x8 => Mf::Unpack4x8snorm,
+                            Glo::UnpackUnorm4x8 => Mf::Unpack4x8unorm,
+                            Glo::UnpackSnorm2x16 => Mf::Unpack2x16snorm,
+                            Glo::UnpackUnorm2x16 => Mf::Unpack2x16unorm,
+                            Glo::UnpackHalf2x16 => Mf::Unpack2x16float,
                             _ => return Err(Error::UnsupportedExtInst(inst_id)),
                         };
 
@@ -1895,11 +2262,12 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                             arg,
                             arg1,
                             arg2,
+                            arg3: None,
                         };
                         self.lookup_expression.insert(
                             result_id,
                             LookupExpression {
-                                handle: expressions.append(expr),
+                                handle: expressions.append(expr, self.span),
                                 type_id: result_type_id,
                             },
                         );
@@ -1961,7 +2329,7 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                     self.lookup_expression.insert(
                         result_id,
                         LookupExpression {
-                            handle: expressions.append(expr),
+                            handle: expressions.append(expr, self.span),
                             type_id: result_type_id,
                         },
                     );
@@ -2019,11 +2387,14 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                             width: _,
                         } => {
                             // IR expects a signed integer, so do a bitcast
-                            expressions.append(crate::Expression::As {
-                                kind: crate::ScalarKind::Sint,
-                                expr: selector_lexp.handle,
-                                convert: None,
-                            })
+                            expressions.append(
+                                crate::Expression::As {
+                                    kind: crate::ScalarKind::Sint,
+                                    expr: selector_lexp.handle,
+                                    convert: None,
+                                },
+                                self.span,
+                            )
                         }
                         crate::TypeInner::Scalar {
                             kind: crate::ScalarKind::Sint,
@@ -2092,12 +2463,16 @@ impl<I: Iterator<Item = u32>> Parser<I> {
 
                     let structure_ptr = self.lookup_expression.lookup(structure_id)?;
 
-                    let member_ptr = expressions.append(crate::Expression::AccessIndex {
-                        base: structure_ptr.handle,
-                        index: member_index,
-                    });
+                    let member_ptr = expressions.append(
+                        crate::Expression::AccessIndex {
+                            base: structure_ptr.handle,
+                            index: member_index,
+                        },
+                        self.span,
+                    );
 
-                    let length = expressions.append(crate::Expression::ArrayLength(member_ptr));
+                    let length =
+                        expressions.append(crate::Expression::ArrayLength(member_ptr), self.span);
 
                     self.lookup_expression.insert(
                         result_id,
@@ -2124,15 +2499,21 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                     let source = self.lookup_expression.lookup(source_id)?;
 
                     // This operation is practically the same as loading and then storing, I think.
-                    let value_expr = expressions.append(crate::Expression::Load {
-                        pointer: source.handle,
-                    });
+                    let value_expr = expressions.append(
+                        crate::Expression::Load {
+                            pointer: source.handle,
+                        },
+                        self.span,
+                    );
 
                     block.extend(emitter.finish(expressions));
-                    block.push(crate::Statement::Store {
-                        pointer: target.handle,
-                        value: value_expr,
-                    });
+                    block.push(
+                        crate::Statement::Store {
+                            pointer: target.handle,
+                            value: value_expr,
+                        },
+                        self.span,
+                    );
 
                     emitter.start(expressions);
                 }
@@ -2171,11 +2552,338 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                                     .bits()
                                 != 0,
                         );
-                        block.push(crate::Statement::Barrier(flags));
+                        block.push(crate::Statement::Barrier(flags), self.span);
                     } else {
                         log::warn!("Unsupported barrier execution scope: {}", exec_scope);
                     }
                 }
+                Op::AtomicLoad => {
+                    inst.expect(6)?;
+                    let result_type_id = self.next()?;
+                    let result_id = self.next()?;
+                    let pointer_id = self.next()?;
+                    let _scope_id = self.next()?;
+                    let _semantics_id = self.next()?;
+                    let pointer = self.parse_atomic_pointer(pointer_id, expressions)?;
+                    self.lookup_expression.insert(
+                        result_id,
+                        LookupExpression {
+                            handle: expressions
+                                .append(crate::Expression::Load { pointer }, self.span),
+                            type_id: result_type_id,
+                        },
+                    );
+                }
+                Op::AtomicStore => {
+                    inst.expect(5)?;
+                    let pointer_id = self.next()?;
+                    let _scope_id = self.next()?;
+                    let _semantics_id = self.next()?;
+                    let value_id = self.next()?;
+                    let pointer = self.parse_atomic_pointer(pointer_id, expressions)?;
+                    let value = self.lookup_expression.lookup(value_id)?.handle;
+                    block.extend(emitter.finish(expressions));
+                    block.push(crate::Statement::Store { pointer, value }, self.span);
+                    emitter.start(expressions);
+                }
+                Op::AtomicExchange
+                | Op::AtomicCompareExchange
+                | Op::AtomicIIncrement
+                | Op::AtomicIDecrement
+                | Op::AtomicIAdd
+                | Op::AtomicISub
+                | Op::AtomicSMin
+                | Op::AtomicUMin
+                | Op::AtomicSMax
+                | Op::AtomicUMax
+                | Op::AtomicAnd
+                | Op::AtomicOr
+                | Op::AtomicXor => {
+                    let result_type_id = self.next()?;
+                    let result_id = self.next()?;
+                    let pointer_id = self.next()?;
+                    let _scope_id = self.next()?;
+                    let _semantics_id = self.next()?;
+                    let pointer = self.parse_atomic_pointer(pointer_id, expressions)?;
+                    let (kind, width) =
+                        match type_arena[self.lookup_type.lookup(result_type_id)?.handle].inner {
+                            crate::TypeInner::Scalar { kind, width } => (kind, width),
+                            _ => return Err(Error::InvalidAtomicPointer(pointer_id)),
+                        };
+
+                    let (fun, value) = match inst.op {
+                        Op::AtomicIIncrement | Op::AtomicIDecrement => {
+                            inst.expect(6)?;
+                            let value = match kind {
+                                crate::ScalarKind::Sint => crate::ScalarValue::Sint(1),
+                                _ => crate::ScalarValue::Uint(1),
+                            };
+                            let constant = const_arena.fetch_or_append(
+                                crate::Constant {
+                                    name: None,
+                                    specialization: None,
+                                    inner: crate::ConstantInner::Scalar { width, value },
+                                },
+                                Default::default(),
+                            );
+                            let fun = if inst.op == Op::AtomicIIncrement {
+                                crate::AtomicFunction::Add
+                            } else {
+                                crate::AtomicFunction::Subtract
+                            };
+                            (
+                                fun,
+                                expressions
+                                    .append(crate::Expression::Constant(constant), self.span),
+                            )
+                        }
+                        Op::AtomicCompareExchange => {
+                            inst.expect(9)?;
+                            let _unequal_semantics_id = self.next()?;
+                            let value_id = self.next()?;
+                            let comparator_id = self.next()?;
+                            let compare = self.lookup_expression.lookup(comparator_id)?.handle;
+                            (
+                                crate::AtomicFunction::Exchange {
+                                    compare: Some(compare),
+                                },
+                                self.lookup_expression.lookup(value_id)?.handle,
+                            )
+                        }
+                        _ => {
+                            inst.expect(7)?;
+                            let fun = match inst.op {
+                                Op::AtomicExchange => {
+                                    crate::AtomicFunction::Exchange { compare: None }
+                                }
+                                Op::AtomicIAdd => crate::AtomicFunction::Add,
+                                Op::AtomicISub => crate::AtomicFunction::Subtract,
+                                Op::AtomicSMin | Op::AtomicUMin => crate::AtomicFunction::Min,
+                                Op::AtomicSMax | Op::AtomicUMax => crate::AtomicFunction::Max,
+                                Op::AtomicAnd => crate::AtomicFunction::And,
+                                Op::AtomicOr => crate::AtomicFunction::InclusiveOr,
+                                _ => crate::AtomicFunction::ExclusiveOr,
+                            };
+                            let value_id = self.next()?;
+                            (fun, self.lookup_expression.lookup(value_id)?.handle)
+                        }
+                    };
+
+                    let comparison = inst.op == Op::AtomicCompareExchange;
+                    block.extend(emitter.finish(expressions));
+                    let result = expressions.append(
+                        crate::Expression::AtomicResult {
+                            kind,
+                            width,
+                            comparison,
+                        },
+                        self.span,
+                    );
+                    block.push(
+                        crate::Statement::Atomic {
+                            pointer,
+                            fun,
+                            value,
+                            result,
+                        },
+                        self.span,
+                    );
+                    emitter.start(expressions);
+
+                    // SPIR-V only returns the original value
+                    let handle = if comparison {
+                        expressions.append(
+                            crate::Expression::AccessIndex {
+                                base: result,
+                                index: 0,
+                            },
+                            self.span,
+                        )
+                    } else {
+                        result
+                    };
+                    self.lookup_expression.insert(
+                        result_id,
+                        LookupExpression {
+                            handle,
+                            type_id: result_type_id,
+                        },
+                    );
+                }
+                Op::GroupNonUniformBallot => {
+                    inst.expect(5)?;
+                    let result_type_id = self.next()?;
+                    let result_id = self.next()?;
+                    let scope_id = self.next()?;
+                    let predicate_id = self.next()?;
+                    self.check_subgroup_scope(scope_id, const_arena)?;
+                    let predicate = self.lookup_expression.lookup(predicate_id)?.handle;
+
+                    block.extend(emitter.finish(expressions));
+                    let result =
+                        expressions.append(crate::Expression::SubgroupBallotResult, self.span);
+                    block.push(
+                        crate::Statement::SubgroupBallot {
+                            result,
+                            predicate: Some(predicate),
+                        },
+                        self.span,
+                    );
+                    emitter.start(expressions);
+                    self.lookup_expression.insert(
+                        result_id,
+                        LookupExpression {
+                            handle: result,
+                            type_id: result_type_id,
+                        },
+                    );
+                }
+                Op::GroupNonUniformAll
+                | Op::GroupNonUniformAny
+                | Op::GroupNonUniformIAdd
+                | Op::GroupNonUniformFAdd
+                | Op::GroupNonUniformIMul
+                | Op::GroupNonUniformFMul
+                | Op::GroupNonUniformSMin
+                | Op::GroupNonUniformUMin
+                | Op::GroupNonUniformFMin
+                | Op::GroupNonUniformSMax
+                | Op::GroupNonUniformUMax
+                | Op::GroupNonUniformFMax
+                | Op::GroupNonUniformBitwiseAnd
+                | Op::GroupNonUniformBitwiseOr
+                | Op::GroupNonUniformBitwiseXor
+                | Op::GroupNonUniformLogicalAnd
+                | Op::GroupNonUniformLogicalOr
+                | Op::GroupNonUniformLogicalXor => {
+                    let result_type_id = self.next()?;
+                    let result_id = self.next()?;
+                    let scope_id = self.next()?;
+                    self.check_subgroup_scope(scope_id, const_arena)?;
+
+                    let op = match inst.op {
+                        Op::GroupNonUniformAll => crate::SubgroupOperation::All,
+                        Op::GroupNonUniformAny => crate::SubgroupOperation::Any,
+                        Op::GroupNonUniformIAdd | Op::GroupNonUniformFAdd => {
+                            crate::SubgroupOperation::Add
+                        }
+                        Op::GroupNonUniformIMul | Op::GroupNonUniformFMul => {
+                            crate::SubgroupOperation::Mul
+                        }
+                        Op::GroupNonUniformSMin
+                        | Op::GroupNonUniformUMin
+                        | Op::GroupNonUniformFMin => crate::SubgroupOperation::Min,
+                        Op::GroupNonUniformSMax
+                        | Op::GroupNonUniformUMax
+                        | Op::GroupNonUniformFMax => crate::SubgroupOperation::Max,
+                        Op::GroupNonUniformBitwiseAnd | Op::GroupNonUniformLogicalAnd => {
+                            crate::SubgroupOperation::And
+                        }
+                        Op::GroupNonUniformBitwiseOr | Op::GroupNonUniformLogicalOr => {
+                            crate::SubgroupOperation::Or
+                        }
+                        _ => crate::SubgroupOperation::Xor,
+                    };
+                    let collective_op = match op {
+                        crate::SubgroupOperation::All | crate::SubgroupOperation::Any => {
+                            inst.expect(5)?;
+                            crate::CollectiveOperation::Reduce
+                        }
+                        _ => {
+                            inst.expect(6)?;
+                            let group_op = self.next()?;
+                            match spirv::GroupOperation::from_u32(group_op) {
+                                Some(spirv::GroupOperation::Reduce) => {
+                                    crate::CollectiveOperation::Reduce
+                                }
+                                Some(spirv::GroupOperation::InclusiveScan) => {
+                                    crate::CollectiveOperation::InclusiveScan
+                                }
+                                Some(spirv::GroupOperation::ExclusiveScan) => {
+                                    crate::CollectiveOperation::ExclusiveScan
+                                }
+                                _ => return Err(Error::UnsupportedGroupOperation(group_op)),
+                            }
+                        }
+                    };
+                    let argument_id = self.next()?;
+                    let argument = self.lookup_expression.lookup(argument_id)?.handle;
+                    let ty = self.lookup_type.lookup(result_type_id)?.handle;
+
+                    block.extend(emitter.finish(expressions));
+                    let result = expressions
+                        .append(crate::Expression::SubgroupOperationResult { ty }, self.span);
+                    block.push(
+                        crate::Statement::SubgroupCollectiveOperation {
+                            op,
+                            collective_op,
+                            argument,
+                            result,
+                        },
+                        self.span,
+                    );
+                    emitter.start(expressions);
+                    self.lookup_expression.insert(
+                        result_id,
+                        LookupExpression {
+                            handle: result,
+                            type_id: result_type_id,
+                        },
+                    );
+                }
+                Op::GroupNonUniformBroadcastFirst
+                | Op::GroupNonUniformBroadcast
+                | Op::GroupNonUniformShuffle
+                | Op::GroupNonUniformShuffleDown
+                | Op::GroupNonUniformShuffleUp
+                | Op::GroupNonUniformShuffleXor => {
+                    inst.expect(if inst.op == Op::GroupNonUniformBroadcastFirst {
+                        5
+                    } else {
+                        6
+                    })?;
+                    let result_type_id = self.next()?;
+                    let result_id = self.next()?;
+                    let scope_id = self.next()?;
+                    let argument_id = self.next()?;
+                    self.check_subgroup_scope(scope_id, const_arena)?;
+                    let argument = self.lookup_expression.lookup(argument_id)?.handle;
+
+                    let mode = if inst.op == Op::GroupNonUniformBroadcastFirst {
+                        crate::GatherMode::BroadcastFirst
+                    } else {
+                        let index_id = self.next()?;
+                        let index = self.lookup_expression.lookup(index_id)?.handle;
+                        match inst.op {
+                            Op::GroupNonUniformBroadcast => crate::GatherMode::Broadcast(index),
+                            Op::GroupNonUniformShuffle => crate::GatherMode::Shuffle(index),
+                            Op::GroupNonUniformShuffleDown => crate::GatherMode::ShuffleDown(index),
+                            Op::GroupNonUniformShuffleUp => crate::GatherMode::ShuffleUp(index),
+                            _ => crate::GatherMode::ShuffleXor(index),
+                        }
+                    };
+                    let ty = self.lookup_type.lookup(result_type_id)?.handle;
+
+                    block.extend(emitter.finish(expressions));
+                    let result = expressions
+                        .append(crate::Expression::SubgroupOperationResult { ty }, self.span);
+                    block.push(
+                        crate::Statement::SubgroupGather {
+                            mode,
+                            argument,
+                            result,
+                        },
+                        self.span,
+                    );
+                    emitter.start(expressions);
+                    self.lookup_expression.insert(
+                        result_id,
+                        LookupExpression {
+                            handle: result,
+                            type_id: result_type_id,
+                        },
+                    );
+                }
                 _ => return Err(Error::UnsupportedInstruction(self.state, inst.op)),
             }
         };
@@ -2194,6 +2902,99 @@ impl<I: Iterator<Item = u32>> Parser<I> {
         })
     }
 
+    /// Check that a non-uniform group instruction operates on the subgroup.
+    fn check_subgroup_scope(
+        &self,
+        scope_id: spirv::Word,
+        const_arena: &Arena<crate::Constant>,
+    ) -> Result<(), Error> {
+        let scope_const = self.lookup_constant.lookup(scope_id)?;
+        match const_arena[scope_const.handle].inner {
+            crate::ConstantInner::Scalar {
+                value: crate::ScalarValue::Uint(raw),
+                width: _,
+            } if raw == spirv::Scope::Subgroup as u64 => Ok(()),
+            _ => Err(Error::InvalidGroupScope(scope_id)),
+        }
+    }
+
+    /// Look up the pointer operand of an atomic instruction, and remember
+    /// the global variable access chain it refers to, so that the accessed
+    /// type can be turned into an atomic once the module is parsed.
+    fn parse_atomic_pointer(
+        &mut self,
+        pointer_id: spirv::Word,
+        expressions: &Arena<crate::Expression>,
+    ) -> Result<Handle<crate::Expression>, Error> {
+        let pointer = self.lookup_expression.lookup(pointer_id)?.handle;
+        let mut path = Vec::new();
+        let mut current = pointer;
+        let global = loop {
+            match expressions[current] {
+                crate::Expression::Access { base, .. } => {
+                    path.push(None);
+                    current = base;
+                }
+                crate::Expression::AccessIndex { base, index } => {
+                    path.push(Some(index));
+                    current = base;
+                }
+                crate::Expression::GlobalVariable(handle) => break handle,
+                _ => return Err(Error::InvalidAtomicPointer(pointer_id)),
+            }
+        };
+        path.reverse();
+        let upgrade = (global, path);
+        if !self.upgrade_atomics.contains(&upgrade) {
+            self.upgrade_atomics.push(upgrade);
+        }
+        Ok(pointer)
+    }
+
+    /// Replace the type reached by following `path` from `ty` with an atomic,
+    /// returning the handle of the new type.
+    fn upgrade_atomic_type(
+        types: &mut Arena<crate::Type>,
+        ty: Handle<crate::Type>,
+        path: &[Option<u32>],
+    ) -> Result<Handle<crate::Type>, Error> {
+        let (inner, name) = match (&types[ty].inner, path.split_first()) {
+            (&crate::TypeInner::Scalar { kind, width }, None) => {
+                (crate::TypeInner::Atomic { kind, width }, None)
+            }
+            (&crate::TypeInner::Atomic { .. }, None) => return Ok(ty),
+            (&crate::TypeInner::Array { base, size, stride }, Some((_, rest))) => {
+                let base = Self::upgrade_atomic_type(types, base, rest)?;
+                (crate::TypeInner::Array { base, size, stride }, None)
+            }
+            (
+                &crate::TypeInner::Struct {
+                    top_level,
+                    ref members,
+                    span,
+                },
+                Some((&Some(index), rest)),
+            ) => {
+                let mut members = members.clone();
+                let member = members
+                    .get_mut(index as usize)
+                    .ok_or(Error::InvalidAccessIndex(index))?;
+                let name = types[ty].name.clone();
+                member.ty = Self::upgrade_atomic_type(types, member.ty, rest)?;
+                (
+                    crate::TypeInner::Struct {
+                        top_level,
+                        members,
+                        span,
+                    },
+                    name,
+                )
+            }
+            _ => return Err(Error::UnsupportedType(ty)),
+        };
+        Ok(types.fetch_or_append(crate::Type { name, inner }, Default::default()))
+    }
+
     fn make_expression_storage(&mut self) -> Arena<crate::Expression> {
         let mut expressions = Arena::new();
         #[allow(clippy::panic)]
@@ -2202,7 +3003,10 @@ impl<I: Iterator<Item = u32>> Parser<I> {
         }
         // register global variables
         for (&id, var) in self.lookup_variable.iter() {
-            let handle = expressions.append(crate::Expression::GlobalVariable(var.handle));
+            let handle = expressions.append(
+                crate::Expression::GlobalVariable(var.handle),
+                Default::default(),
+            );
             self.lookup_expression.insert(
                 id,
                 LookupExpression {
@@ -2214,12 +3018,14 @@ impl<I: Iterator<Item = u32>> Parser<I> {
         // register special constants
         self.index_constant_expressions.clear();
         for &con_handle in self.index_constants.iter() {
-            let handle = expressions.append(crate::Expression::Constant(con_handle));
+            let handle =
+                expressions.append(crate::Expression::Constant(con_handle), Default::default());
             self.index_constant_expressions.push(handle);
         }
         // register constants
         for (&id, con) in self.lookup_constant.iter() {
-            let handle = expressions.append(crate::Expression::Constant(con.handle));
+            let handle =
+                expressions.append(crate::Expression::Constant(con.handle), Default::default());
             self.lookup_expression.insert(
                 id,
                 LookupExpression {
@@ -2267,7 +3073,7 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                     if let [S::Break] = reject[..] {
                         // uplift "accept" into the parent
                         let extracted = mem::take(accept);
-                        statements.splice(i + 1..i + 1, extracted.into_iter());
+                        statements.splice(i + 1..i + 1, extracted);
                     } else {
                         self.patch_statements(reject, expressions, function)?;
                         self.patch_statements(accept, expressions, function)?;
@@ -2281,7 +3087,7 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                     if cases.is_empty() {
                         // uplift "default" into the parent
                         let extracted = mem::take(default);
-                        statements.splice(i + 1..i + 1, extracted.into_iter());
+                        statements.splice(i + 1..i + 1, extracted);
                     } else {
                         for case in cases.iter_mut() {
                             self.patch_statements(&mut case.body, expressions, function)?;
@@ -2302,7 +3108,11 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                 | S::Kill
                 | S::Barrier(_)
                 | S::Store { .. }
-                | S::ImageStore { .. } => {}
+                | S::ImageStore { .. }
+                | S::Atomic { .. }
+                | S::SubgroupBallot { .. }
+                | S::SubgroupCollectiveOperation { .. }
+                | S::SubgroupGather { .. } => {}
                 S::Call {
                     function: ref mut callee,
                     ref arguments,
@@ -2330,6 +3140,15 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                                     *sampling |= flags
                                 }
                             }
+                            crate::Expression::Access { base, .. }
+                            | crate::Expression::AccessIndex { base, .. } => {
+                                if let crate::Expression::GlobalVariable(handle) = expressions[base]
+                                {
+                                    if let Some(sampling) = self.handle_sampling.get_mut(&handle) {
+                                        *sampling |= flags
+                                    }
+                                }
+                            }
                             crate::Expression::FunctionArgument(i) => {
                                 if let Some(handle) = function {
                                     let function_info =
@@ -2364,7 +3183,13 @@ impl<I: Iterator<Item = u32>> Parser<I> {
         Ok(())
     }
 
-    pub fn parse(mut self) -> Result<crate::Module, Error> {
+    pub fn parse(self) -> Result<crate::Module, Error> {
+        self.parse_with_debug_info().map(|(module, _)| module)
+    }
+
+    /// Parse the module, along with the source code embedded into it and
+    /// the locations of its `OpLine` instructions.
+    pub fn parse_with_debug_info(mut self) -> Result<(crate::Module, DebugInfo), Error> {
         let mut module = {
             if self.next()? != spirv::MAGIC_NUMBER {
                 return Err(Error::InvalidHeader);
@@ -2380,14 +3205,17 @@ impl<I: Iterator<Item = u32>> Parser<I> {
         // register indexing constants
         self.index_constants.clear();
         for i in 0..4 {
-            let handle = module.constants.append(crate::Constant {
-                name: None,
-                specialization: None,
-                inner: crate::ConstantInner::Scalar {
-                    width: 4,
-                    value: crate::ScalarValue::Sint(i),
+            let handle = module.constants.append(
+                crate::Constant {
+                    name: None,
+                    specialization: None,
+                    inner: crate::ConstantInner::Scalar {
+                        width: 4,
+                        value: crate::ScalarValue::Sint(i),
+                    },
                 },
-            });
+                Default::default(),
+            );
             self.index_constants.push(handle);
         }
 
@@ -2415,6 +3243,9 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                 Op::ExecutionMode => self.parse_execution_mode(inst),
                 Op::String => self.parse_string(inst),
                 Op::Source => self.parse_source(inst),
+                Op::SourceContinued => self.parse_source_continued(inst),
+                Op::Line => self.parse_line(inst),
+                Op::NoLine => self.parse_no_line(inst),
                 Op::SourceExtension => self.parse_source_extension(inst),
                 Op::Name => self.parse_name(inst),
                 Op::MemberName => self.parse_member_name(inst),
@@ -2436,10 +3267,17 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                 Op::TypeSampledImage => self.parse_type_sampled_image(inst),
                 Op::TypeSampler => self.parse_type_sampler(inst, &mut module),
                 Op::Constant | Op::SpecConstant => self.parse_constant(inst, &mut module),
-                Op::ConstantComposite => self.parse_composite_constant(inst, &mut module),
+                Op::ConstantComposite | Op::SpecConstantComposite => {
+                    self.parse_composite_constant(inst, &mut module)
+                }
                 Op::ConstantNull | Op::Undef => self.parse_null_constant(inst, &mut module),
-                Op::ConstantTrue => self.parse_bool_constant(inst, true, &mut module),
-                Op::ConstantFalse => self.parse_bool_constant(inst, false, &mut module),
+                Op::SpecConstantOp => self.parse_spec_constant_op(inst),
+                Op::ConstantTrue | Op::SpecConstantTrue => {
+                    self.parse_bool_constant(inst, true, &mut module)
+                }
+                Op::ConstantFalse | Op::SpecConstantFalse => {
+                    self.parse_bool_constant(inst, false, &mut module)
+                }
                 Op::Variable => self.parse_global_variable(inst, &mut module),
                 Op::Function => {
                     self.switch(ModuleState::Function, inst.op)?;
@@ -2465,7 +3303,7 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                 // take out the function from the old array
                 let fun = mem::take(&mut functions[handle.index()]);
                 // add it to the newly formed arena, and adjust the lookup
-                *handle = module.functions.append(fun);
+                *handle = module.functions.append(fun, Default::default());
             }
         }
         // patch all the functions
@@ -2476,6 +3314,12 @@ impl<I: Iterator<Item = u32>> Parser<I> {
             self.patch_function(None, &mut ep.function)?;
         }
 
+        // Turn the types accessed by atomic instructions into atomics.
+        for (handle, path) in self.upgrade_atomics.drain(..) {
+            let var = module.global_variables.get_mut(handle);
+            var.ty = Self::upgrade_atomic_type(&mut module.types, var.ty, &path)?;
+        }
+
         // Check all the images and samplers to have consistent comparison property.
         for (handle, flags) in self.handle_sampling.drain() {
             if !image::patch_comparison_type(
@@ -2496,7 +3340,11 @@ impl<I: Iterator<Item = u32>> Parser<I> {
             self.future_member_decor.clear();
         }
 
-        Ok(module)
+        let debug_info = DebugInfo {
+            source: self.debug_source,
+            lines: self.line_table,
+        };
+        Ok((module, debug_info))
     }
 
     fn parse_capability(&mut self, inst: Instruction) -> Result<(), Error> {
@@ -2634,19 +3482,127 @@ impl<I: Iterator<Item = u32>> Parser<I> {
     fn parse_string(&mut self, inst: Instruction) -> Result<(), Error> {
         self.switch(ModuleState::Source, inst.op)?;
         inst.expect_at_least(3)?;
-        let _id = self.next()?;
-        let (_name, _) = self.next_string(inst.wc - 2)?;
+        let id = self.next()?;
+        let (name, _) = self.next_string(inst.wc - 2)?;
+        self.debug_strings.insert(id, name);
         Ok(())
     }
 
     fn parse_source(&mut self, inst: Instruction) -> Result<(), Error> {
         self.switch(ModuleState::Source, inst.op)?;
-        for _ in 1..inst.wc {
-            let _ = self.next()?;
+        inst.expect_at_least(3)?;
+        let _language = self.next()?;
+        let _version = self.next()?;
+        let file_id = if inst.wc > 3 {
+            Some(self.next()?)
+        } else {
+            None
+        };
+        self.source_continued = false;
+        if inst.wc > 4 {
+            let (source_code, _) = self.next_string(inst.wc - 4)?;
+            // Spans can only point into a single source, so the first one wins.
+            if self.debug_source.is_none() {
+                self.debug_source = Some(DebugSource {
+                    file_name: file_id.and_then(|id| self.debug_strings.get(&id).cloned()),
+                    source_code: String::new(),
+                });
+                self.debug_source_file_id = file_id;
+                self.source_line_starts = vec![0];
+                self.source_continued = true;
+                self.append_source(&source_code);
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_source_continued(&mut self, inst: Instruction) -> Result<(), Error> {
+        self.switch(ModuleState::Source, inst.op)?;
+        inst.expect_at_least(2)?;
+        let (source_code, _) = self.next_string(inst.wc - 1)?;
+        if self.source_continued {
+            self.append_source(&source_code);
+        }
+        Ok(())
+    }
+
+    fn append_source(&mut self, text: &str) {
+        if let Some(ref mut source) = self.debug_source {
+            let base = source.source_code.len();
+            self.source_line_starts.extend(
+                text.match_indices('\n')
+                    .map(|(offset, _)| base + offset + 1),
+            );
+            source.source_code.push_str(text);
         }
+    }
+
+    fn parse_line(&mut self, inst: Instruction) -> Result<(), Error> {
+        inst.expect(4)?;
+        let file_id = self.next()?;
+        let line = self.next()?;
+        let column = self.next()?;
+        self.span = match self.line_spans.get(&(file_id, line, column)) {
+            Some(&span) => span,
+            None => {
+                let span = self.line_span(file_id, line, column);
+                self.line_table.push(LineLocation {
+                    file_name: self.debug_strings.get(&file_id).cloned(),
+                    line,
+                    column,
+                    span,
+                });
+                self.line_spans.insert((file_id, line, column), span);
+                span
+            }
+        };
         Ok(())
     }
 
+    fn parse_no_line(&mut self, inst: Instruction) -> Result<(), Error> {
+        inst.expect(1)?;
+        self.span = crate::Span::default();
+        Ok(())
+    }
+
+    /// Return the span of `line` and `column` in the file `file_id`.
+    ///
+    /// Lines of other files than the embedded source, or out of it, get a new
+    /// span past its end.
+    fn line_span(&mut self, file_id: spirv::Word, line: u32, column: u32) -> crate::Span {
+        if let Some(span) = self.source_line_span(file_id, line, column) {
+            return span;
+        }
+        let source_len = self
+            .debug_source
+            .as_ref()
+            .map_or(0, |source| source.source_code.len() as u32);
+        // Skip the position right after the source, which a span of it may start at.
+        let start = source_len + 1 + self.detached_lines;
+        self.detached_lines += 1;
+        crate::Span::new(start, start + 1)
+    }
+
+    /// Return the span from `column` to the end of `line` in the embedded source.
+    fn source_line_span(
+        &self,
+        file_id: spirv::Word,
+        line: u32,
+        column: u32,
+    ) -> Option<crate::Span> {
+        let source = match self.debug_source {
+            Some(ref source) if self.debug_source_file_id == Some(file_id) => source,
+            _ => return None,
+        };
+        let line_start = *self.source_line_starts.get(line.wrapping_sub(1) as usize)?;
+        let line_end = self
+            .source_line_starts
+            .get(line as usize)
+            .map_or(source.source_code.len(), |&next| next - 1);
+        let start = (line_start + column.saturating_sub(1) as usize).min(line_end);
+        Some(crate::Span::new(start as u32, line_end as u32))
+    }
+
     fn parse_source_extension(&mut self, inst: Instruction) -> Result<(), Error> {
         self.switch(ModuleState::Source, inst.op)?;
         inst.expect_at_least(2)?;
@@ -2742,10 +3698,13 @@ impl<I: Iterator<Item = u32>> Parser<I> {
         self.lookup_type.insert(
             id,
             LookupType {
-                handle: module.types.append(crate::Type {
-                    name: self.future_decor.remove(&id).and_then(|dec| dec.name),
-                    inner,
-                }),
+                handle: module.types.append(
+                    crate::Type {
+                        name: self.future_decor.remove(&id).and_then(|dec| dec.name),
+                        inner,
+                    },
+                    Default::default(),
+                ),
                 base_id: None,
             },
         );
@@ -2773,10 +3732,13 @@ impl<I: Iterator<Item = u32>> Parser<I> {
         self.lookup_type.insert(
             id,
             LookupType {
-                handle: module.types.append(crate::Type {
-                    name: self.future_decor.remove(&id).and_then(|dec| dec.name),
-                    inner,
-                }),
+                handle: module.types.append(
+                    crate::Type {
+                        name: self.future_decor.remove(&id).and_then(|dec| dec.name),
+                        inner,
+                    },
+                    Default::default(),
+                ),
                 base_id: None,
             },
         );
@@ -2799,10 +3761,13 @@ impl<I: Iterator<Item = u32>> Parser<I> {
         self.lookup_type.insert(
             id,
             LookupType {
-                handle: module.types.append(crate::Type {
-                    name: self.future_decor.remove(&id).and_then(|dec| dec.name),
-                    inner,
-                }),
+                handle: module.types.append(
+                    crate::Type {
+                        name: self.future_decor.remove(&id).and_then(|dec| dec.name),
+                        inner,
+                    },
+                    Default::default(),
+                ),
                 base_id: None,
             },
         );
@@ -2832,10 +3797,13 @@ impl<I: Iterator<Item = u32>> Parser<I> {
         self.lookup_type.insert(
             id,
             LookupType {
-                handle: module.types.append(crate::Type {
-                    name: self.future_decor.remove(&id).and_then(|dec| dec.name),
-                    inner,
-                }),
+                handle: module.types.append(
+                    crate::Type {
+                        name: self.future_decor.remove(&id).and_then(|dec| dec.name),
+                        inner,
+                    },
+                    Default::default(),
+                ),
                 base_id: Some(type_id),
             },
         );
@@ -2867,10 +3835,13 @@ impl<I: Iterator<Item = u32>> Parser<I> {
         self.lookup_type.insert(
             id,
             LookupType {
-                handle: module.types.append(crate::Type {
-                    name: decor.and_then(|dec| dec.name),
-                    inner,
-                }),
+                handle: module.types.append(
+                    crate::Type {
+                        name: decor.and_then(|dec| dec.name),
+                        inner,
+                    },
+                    Default::default(),
+                ),
                 base_id: Some(vector_type_id),
             },
         );
@@ -2926,13 +3897,16 @@ impl<I: Iterator<Item = u32>> Parser<I> {
             base_lookup_ty.clone()
         } else {
             LookupType {
-                handle: module.types.append(crate::Type {
-                    name: decor.and_then(|dec| dec.name),
-                    inner: crate::TypeInner::Pointer {
-                        base: base_lookup_ty.handle,
-                        class,
+                handle: module.types.append(
+                    crate::Type {
+                        name: decor.and_then(|dec| dec.name),
+                        inner: crate::TypeInner::Pointer {
+                            base: base_lookup_ty.handle,
+                            class,
+                        },
                     },
-                }),
+                    Default::default(),
+                ),
                 base_id: Some(type_id),
             }
         };
@@ -2954,21 +3928,29 @@ impl<I: Iterator<Item = u32>> Parser<I> {
 
         let decor = self.future_decor.remove(&id).unwrap_or_default();
         let base = self.lookup_type.lookup(type_id)?.handle;
-        let inner = crate::TypeInner::Array {
-            base,
-            size: crate::ArraySize::Constant(length_const.handle),
-            stride: match decor.array_stride {
-                Some(stride) => stride.get(),
-                None => module.types[base].inner.span(&module.constants),
-            },
+        let size = crate::ArraySize::Constant(length_const.handle);
+        let inner = if is_binding_array_base(&module.types[base].inner, &decor) {
+            crate::TypeInner::BindingArray { base, size }
+        } else {
+            crate::TypeInner::Array {
+                base,
+                size,
+                stride: match decor.array_stride {
+                    Some(stride) => stride.get(),
+                    None => module.types[base].inner.span(&module.constants),
+                },
+            }
         };
         self.lookup_type.insert(
             id,
             LookupType {
-                handle: module.types.append(crate::Type {
-                    name: decor.name,
-                    inner,
-                }),
+                handle: module.types.append(
+                    crate::Type {
+                        name: decor.name,
+                        inner,
+                    },
+                    Default::default(),
+                ),
                 base_id: Some(type_id),
             },
         );
@@ -2987,21 +3969,29 @@ impl<I: Iterator<Item = u32>> Parser<I> {
 
         let decor = self.future_decor.remove(&id).unwrap_or_default();
         let base = self.lookup_type.lookup(type_id)?.handle;
-        let inner = crate::TypeInner::Array {
-            base: self.lookup_type.lookup(type_id)?.handle,
-            size: crate::ArraySize::Dynamic,
-            stride: match decor.array_stride {
-                Some(stride) => stride.get(),
-                None => module.types[base].inner.span(&module.constants),
-            },
+        let size = crate::ArraySize::Dynamic;
+        let inner = if is_binding_array_base(&module.types[base].inner, &decor) {
+            crate::TypeInner::BindingArray { base, size }
+        } else {
+            crate::TypeInner::Array {
+                base,
+                size,
+                stride: match decor.array_stride {
+                    Some(stride) => stride.get(),
+                    None => module.types[base].inner.span(&module.constants),
+                },
+            }
         };
         self.lookup_type.insert(
             id,
             LookupType {
-                handle: module.types.append(crate::Type {
-                    name: decor.name,
-                    inner,
-                }),
+                handle: module.types.append(
+                    crate::Type {
+                        name: decor.name,
+                        inner,
+                    },
+                    Default::default(),
+                ),
                 base_id: Some(type_id),
             },
         );
@@ -3098,10 +4088,13 @@ impl<I: Iterator<Item = u32>> Parser<I> {
             },
             members,
         };
-        let ty_handle = module.types.append(crate::Type {
-            name: parent_decor.and_then(|dec| dec.name),
-            inner,
-        });
+        let ty_handle = module.types.append(
+            crate::Type {
+                name: parent_decor.and_then(|dec| dec.name),
+                inner,
+            },
+            Default::default(),
+        );
 
         if block_decor == Some(Block { buffer: true }) {
             self.lookup_storage_buffer_types
@@ -3142,17 +4135,20 @@ impl<I: Iterator<Item = u32>> Parser<I> {
         let decor = self.future_decor.remove(&id).unwrap_or_default();
 
         // ensure there is a type for texture coordinate without extra components
-        module.types.fetch_or_append(crate::Type {
-            name: None,
-            inner: {
-                let kind = crate::ScalarKind::Float;
-                let width = 4;
-                match dim.required_coordinate_size() {
-                    None => crate::TypeInner::Scalar { kind, width },
-                    Some(size) => crate::TypeInner::Vector { size, kind, width },
-                }
+        module.types.fetch_or_append(
+            crate::Type {
+                name: None,
+                inner: {
+                    let kind = crate::ScalarKind::Float;
+                    let width = 4;
+                    match dim.required_coordinate_size() {
+                        None => crate::TypeInner::Scalar { kind, width },
+                        Some(size) => crate::TypeInner::Vector { size, kind, width },
+                    }
+                },
             },
-        });
+            Default::default(),
+        );
 
         let base_handle = self.lookup_type.lookup(sample_type_id)?.handle;
         let kind = module.types[base_handle]
@@ -3173,10 +4169,13 @@ impl<I: Iterator<Item = u32>> Parser<I> {
             arrayed: is_array,
         };
 
-        let handle = module.types.append(crate::Type {
-            name: decor.name,
-            inner,
-        });
+        let handle = module.types.append(
+            crate::Type {
+                name: decor.name,
+                inner,
+            },
+            Default::default(),
+        );
 
         self.lookup_type.insert(
             id,
@@ -3212,10 +4211,13 @@ impl<I: Iterator<Item = u32>> Parser<I> {
         inst.expect(2)?;
         let id = self.next()?;
         let decor = self.future_decor.remove(&id).unwrap_or_default();
-        let handle = module.types.append(crate::Type {
-            name: decor.name,
-            inner: crate::TypeInner::Sampler { comparison: false },
-        });
+        let handle = module.types.append(
+            crate::Type {
+                name: decor.name,
+                inner: crate::TypeInner::Sampler { comparison: false },
+            },
+            Default::default(),
+        );
         self.lookup_type.insert(
             id,
             LookupType {
@@ -3300,11 +4302,14 @@ impl<I: Iterator<Item = u32>> Parser<I> {
         self.lookup_constant.insert(
             id,
             LookupConstant {
-                handle: module.constants.append(crate::Constant {
-                    specialization: decor.specialization,
-                    name: decor.name,
-                    inner,
-                }),
+                handle: module.constants.append(
+                    crate::Constant {
+                        specialization: decor.specialization,
+                        name: decor.name,
+                        inner,
+                    },
+                    Default::default(),
+                ),
                 type_id,
             },
         );
@@ -3330,14 +4335,19 @@ impl<I: Iterator<Item = u32>> Parser<I> {
             components.push(constant.handle);
         }
 
+        let decor = self.future_decor.remove(&id).unwrap_or_default();
+
         self.lookup_constant.insert(
             id,
             LookupConstant {
-                handle: module.constants.append(crate::Constant {
-                    name: self.future_decor.remove(&id).and_then(|dec| dec.name),
-                    specialization: None,
-                    inner: crate::ConstantInner::Composite { ty, components },
-                }),
+                handle: module.constants.append(
+                    crate::Constant {
+                        name: decor.name,
+                        specialization: decor.specialization,
+                        inner: crate::ConstantInner::Composite { ty, components },
+                    },
+                    Default::default(),
+                ),
                 type_id,
             },
         );
@@ -3361,17 +4371,33 @@ impl<I: Iterator<Item = u32>> Parser<I> {
         self.lookup_constant.insert(
             id,
             LookupConstant {
-                handle: module.constants.append(crate::Constant {
-                    name: self.future_decor.remove(&id).and_then(|dec| dec.name),
-                    specialization: None, //TODO
-                    inner,
-                }),
+                handle: module.constants.append(
+                    crate::Constant {
+                        name: self.future_decor.remove(&id).and_then(|dec| dec.name),
+                        // only the `OpSpecConstant*` instructions can be specialized
+                        specialization: None,
+                        inner,
+                    },
+                    Default::default(),
+                ),
                 type_id,
             },
         );
         Ok(())
     }
 
+    fn parse_spec_constant_op(&mut self, inst: Instruction) -> Result<(), Error> {
+        self.switch(ModuleState::Type, inst.op)?;
+        inst.expect_at_least(4)?;
+        let _type_id = self.next()?;
+        let _id = self.next()?;
+        let opcode = self.next()?;
+        let op = spirv::Op::from_u32(opcode).ok_or(Error::UnknownInstruction(opcode as u16))?;
+        // Folding the operation here would bake in the default values of the
+        // specialization constants it depends on, so reject it explicitly.
+        Err(Error::UnsupportedSpecConstantOp(op))
+    }
+
     fn parse_bool_constant(
         &mut self,
         inst: Instruction,
@@ -3383,14 +4409,19 @@ impl<I: Iterator<Item = u32>> Parser<I> {
         let type_id = self.next()?;
         let id = self.next()?;
 
+        let decor = self.future_decor.remove(&id).unwrap_or_default();
+
         self.lookup_constant.insert(
             id,
             LookupConstant {
-                handle: module.constants.append(crate::Constant {
-                    name: self.future_decor.remove(&id).and_then(|dec| dec.name),
-                    specialization: None, //TODO
-                    inner: crate::ConstantInner::boolean(value),
-                }),
+                handle: module.constants.append(
+                    crate::Constant {
+                        name: decor.name,
+                        specialization: decor.specialization,
+                        inner: crate::ConstantInner::boolean(value),
+                    },
+                    Default::default(),
+                ),
                 type_id,
             },
         );
@@ -3422,14 +4453,22 @@ impl<I: Iterator<Item = u32>> Parser<I> {
             crate::TypeInner::Pointer { base, class } => {
                 (base, class == crate::StorageClass::Storage)
             }
+            _ => (original_ty, false),
+        };
+        // resources in a binding array are described by the element type
+        let element_ty = match module.types[effective_ty].inner {
+            crate::TypeInner::BindingArray { base, .. } => base,
+            _ => effective_ty,
+        };
+        let is_storage = match module.types[element_ty].inner {
             crate::TypeInner::Image {
                 class: crate::ImageClass::Storage(_),
                 ..
-            } => (original_ty, true),
-            _ => (original_ty, false),
+            } => true,
+            _ => is_storage,
         };
         let (ext_class, type_storage_access) =
-            match self.lookup_storage_buffer_types.get(&effective_ty) {
+            match self.lookup_storage_buffer_types.get(&element_ty) {
                 Some(&access) => (ExtendedClass::Global(crate::StorageClass::Storage), access),
                 None => (
                     map_storage_class(storage_class)?,
@@ -3504,7 +4543,7 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                     ) {
                         unsigned_ty = module
                             .types
-                            .fetch_or_append(crate::Type { name: None, inner });
+                            .fetch_or_append(crate::Type { name: None, inner }, Default::default());
                     }
                 }
 
@@ -3527,6 +4566,8 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                 // For output interface blocks, this would be a structure.
                 let binding = dec.io_binding().ok();
                 let init = match binding {
+                    // an explicit initializer takes precedence over the defaults
+                    _ if init.is_some() => init,
                     Some(crate::Binding::BuiltIn(built_in)) => {
                         match null::generate_default_built_in(
                             Some(built_in),
@@ -3566,14 +4607,17 @@ impl<I: Iterator<Item = u32>> Parser<I> {
                                 )?;
                                 components.push(handle);
                             }
-                            Some(module.constants.append(crate::Constant {
-                                name: None,
-                                specialization: None,
-                                inner: crate::ConstantInner::Composite {
-                                    ty: effective_ty,
-                                    components,
+                            Some(module.constants.append(
+                                crate::Constant {
+                                    name: None,
+                                    specialization: None,
+                                    inner: crate::ConstantInner::Composite {
+                                        ty: effective_ty,
+                                        components,
+                                    },
                                 },
-                            }))
+                                Default::default(),
+                            ))
                         }
                         _ => None,
                     },
@@ -3595,8 +4639,8 @@ impl<I: Iterator<Item = u32>> Parser<I> {
             }
         };
 
-        let handle = module.global_variables.append(var);
-        if module.types[effective_ty].inner.can_comparison_sample() {
+        let handle = module.global_variables.append(var, Default::default());
+        if module.types[element_ty].inner.can_comparison_sample() {
             log::debug!("\t\ttracking {:?} for sampling properties", handle);
             self.handle_sampling
                 .insert(handle, image::SamplingFlags::empty());
@@ -3625,6 +4669,12 @@ pub fn parse_u8_slice(data: &[u8], options: &Options) -> Result<crate::Module, E
     Parser::new(words, options).parse()
 }
 
+/// Parse a module from SPIR-V assembly text, see [`assemble`].
+pub fn parse_str(source: &str, options: &Options) -> Result<crate::Module, Error> {
+    let words = assemble(source)?;
+    Parser::new(words.into_iter(), options).parse()
+}
+
 #[cfg(test)]
 mod test {
     #[test]
@@ -3640,4 +4690,357 @@ mod test {
         ];
         let _ = super::parse_u8_slice(&bin, &Default::default()).unwrap();
     }
+
+    #[cfg(all(feature = "wgsl-in", feature = "spv-out"))]
+    #[test]
+    fn initializers() {
+        let source = "
+            var<private> offset: vec2<f32> = vec2<f32>(0.5, 1.5);
+            var<private> count: i32 = 3;
+
+            [[stage(compute), workgroup_size(1)]]
+            fn main() {
+                var scale: f32 = 2.0;
+                var index: u32;
+                count = count + i32(scale * offset.x) + i32(index);
+            }
+        ";
+        let module = crate::front::wgsl::parse_str(source).unwrap();
+        let info = crate::valid::Validator::new(
+            crate::valid::ValidationFlags::all(),
+            crate::valid::Capabilities::empty(),
+        )
+        .validate(&module)
+        .unwrap();
+        let words = crate::back::spv::write_vec(&module, &info, &Default::default()).unwrap();
+        let module = super::Parser::new(words.into_iter(), &Default::default())
+            .parse()
+            .unwrap();
+
+        let inits = module
+            .global_variables
+            .iter()
+            .map(|(_, var)| var.init.map(|init| &module.constants[init].inner))
+            .collect::<Vec<_>>();
+        match inits[..] {
+            [Some(&crate::ConstantInner::Composite { ref components, .. }), Some(&crate::ConstantInner::Scalar {
+                value: crate::ScalarValue::Sint(3),
+                ..
+            })] => assert_eq!(components.len(), 2),
+            ref other => panic!("unexpected global initializers {:?}", other),
+        }
+
+        // the entry point calls the parsed function
+        let (_, function) = module.functions.iter().next().unwrap();
+        let inits = function
+            .local_variables
+            .iter()
+            .map(|(_, var)| var.init.map(|init| &module.constants[init].inner))
+            .collect::<Vec<_>>();
+        match inits[..] {
+            [Some(&crate::ConstantInner::Scalar {
+                value: crate::ScalarValue::Float(value),
+                ..
+            }), None] => assert_eq!(value, 2.0),
+            ref other => panic!("unexpected local initializers {:?}", other),
+        }
+    }
+
+    #[cfg(all(feature = "wgsl-in", feature = "spv-out"))]
+    #[test]
+    fn assemble() {
+        let source = "
+            [[group(0), binding(0)]] var t: texture_2d<f32>;
+            [[group(0), binding(1)]] var s: sampler;
+
+            fn pick(selector: i32, x: f32) -> f32 {
+                switch (selector) {
+                    case -1: { return clamp(x, -0.5, 0.5); }
+                    case 4000000: { return x; }
+                    default: { return 0.00000001; }
+                }
+            }
+
+            [[stage(fragment)]]
+            fn main([[location(0)]] uv: vec2<f32>) -> [[location(0)]] vec4<f32> {
+                var total: f32 = 0.0;
+                loop {
+                    if (total > 4.0) { break; }
+                    total = total + pick(i32(total), uv.x);
+                }
+                return textureSample(t, s, uv, vec2<i32>(1, -1)) * total;
+            }
+        ";
+        let module = crate::front::wgsl::parse_str(source).unwrap();
+        let mut validator = crate::valid::Validator::new(
+            crate::valid::ValidationFlags::all(),
+            crate::valid::Capabilities::empty(),
+        );
+        let info = validator.validate(&module).unwrap();
+        let words = crate::back::spv::write_vec(&module, &info, &Default::default()).unwrap();
+
+        let text = crate::back::spv::disassemble(&words);
+        let assembled = super::assemble(&text).unwrap();
+        // named ids are renumbered, so only the bound may change
+        let strip_bound = |text: &str| {
+            text.lines()
+                .filter(|line| !line.starts_with("; Bound:"))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        assert_eq!(
+            strip_bound(&crate::back::spv::disassemble(&assembled)),
+            strip_bound(&text)
+        );
+
+        let module = super::parse_str(&text, &Default::default()).unwrap();
+        validator.validate(&module).unwrap();
+    }
+
+    #[cfg(all(feature = "wgsl-in", feature = "spv-out"))]
+    #[test]
+    fn math_functions() {
+        use crate::MathFunction as Mf;
+
+        let collect_functions = |module: &crate::Module| {
+            let mut functions = Vec::new();
+            for (_, function) in module.functions.iter() {
+                for (_, expression) in function.expressions.iter() {
+                    if let crate::Expression::Math { fun, .. } = *expression {
+                        functions.push(fun);
+                    }
+                }
+            }
+            for entry_point in module.entry_points.iter() {
+                for (_, expression) in entry_point.function.expressions.iter() {
+                    if let crate::Expression::Math { fun, .. } = *expression {
+                        functions.push(fun);
+                    }
+                }
+            }
+            functions
+        };
+
+        let source = "
+            [[stage(fragment)]]
+            fn main([[location(0)]] v: vec4<f32>, [[location(1)]] s: f32) -> [[location(0)]] vec4<f32> {
+                var whole: f32;
+                var exponent: i32;
+                let fraction = modf(s, &whole);
+                let mantissa = frexp(s, &exponent);
+                let scaled = ldexp(mantissa, exponent);
+                let outer = outerProduct(v.xy, v.zw);
+                let bits = countOneBits(u32(s)) + reverseBits(u32(s));
+                let facing = faceForward(v, v, v);
+                let det = determinant(mat2x2<f32>(v.xy, v.zw));
+                let rounded = round(s);
+                return outer[1].xyxy + facing * det + v * (fraction + whole + scaled)
+                    + vec4<f32>(f32(bits) + rounded);
+            }
+        ";
+        let module = crate::front::wgsl::parse_str(source).unwrap();
+        let mut validator = crate::valid::Validator::new(
+            crate::valid::ValidationFlags::all(),
+            crate::valid::Capabilities::empty(),
+        );
+        let info = validator.validate(&module).unwrap();
+        let words = crate::back::spv::write_vec(&module, &info, &Default::default()).unwrap();
+        let text = crate::back::spv::disassemble(&words);
+        // WGSL `round` rounds halfway cases to even
+        assert!(text.contains("RoundEven"), "{}", text);
+        let round_trip = super::parse_str(&text, &Default::default()).unwrap();
+        validator.validate(&round_trip).unwrap();
+        let functions = collect_functions(&round_trip);
+        for fun in [
+            Mf::Modf,
+            Mf::Frexp,
+            Mf::Ldexp,
+            Mf::Outer,
+            Mf::CountOneBits,
+            Mf::ReverseBits,
+            Mf::FaceForward,
+            Mf::Determinant,
+            Mf::Round,
+        ]
+        .iter()
+        {
+            assert!(functions.contains(fun), "{:?} is lost in {}", fun, text);
+        }
+
+        // The struct-returning forms are lowered to the out-parameter ones.
+        let text = "
+            OpCapability Shader
+            %glsl = OpExtInstImport \"GLSL.std.450\"
+            OpMemoryModel Logical GLSL450
+            OpEntryPoint GLCompute %main \"main\"
+            OpExecutionMode %main LocalSize 1 1 1
+            %void = OpTypeVoid
+            %fn = OpTypeFunction %void
+            %float = OpTypeFloat 32
+            %int = OpTypeInt 32 1
+            %modf_result = OpTypeStruct %float %float
+            %frexp_result = OpTypeStruct %float %int
+            %vec2 = OpTypeVector %float 2
+            %mat2 = OpTypeMatrix %vec2 2
+            %x = OpConstant %float 2.5
+            %v = OpConstantComposite %vec2 %x %x
+            %m = OpConstantComposite %mat2 %v %v
+            %main = OpFunction %void None %fn
+            %entry = OpLabel
+            %modf = OpExtInst %modf_result %glsl ModfStruct %x
+            %frexp = OpExtInst %frexp_result %glsl FrexpStruct %x
+            %round = OpExtInst %float %glsl RoundEven %x
+            %refract = OpExtInst %vec2 %glsl Refract %v %v %x
+            %inverse = OpExtInst %mat2 %glsl MatrixInverse %m
+            OpReturn
+            OpFunctionEnd
+        ";
+        let module = super::parse_str(text, &Default::default()).unwrap();
+        let info = validator.validate(&module).unwrap();
+        assert_eq!(
+            collect_functions(&module),
+            vec![Mf::Modf, Mf::Frexp, Mf::Round, Mf::Refract, Mf::Inverse]
+        );
+        crate::back::spv::write_vec(&module, &info, &Default::default()).unwrap();
+    }
+
+    #[test]
+    fn debug_info() {
+        let source = "void main() {\n    float doubled = 2.0 * 4.0;\n}\n";
+        let text = "OpCapability Shader\n\
+            OpMemoryModel Logical GLSL450\n\
+            OpEntryPoint GLCompute %main \"main\"\n\
+            OpExecutionMode %main LocalSize 1 1 1\n\
+            %file = OpString \"double.comp\"\n\
+            OpSource GLSL 450 %file \"void main() {\n    float doubled = 2.0 * 4.0;\n}\n\"\n\
+            OpName %main \"main\"\n\
+            OpName %doubled \"doubled\"\n\
+            %void = OpTypeVoid\n\
+            %fn = OpTypeFunction %void\n\
+            %float = OpTypeFloat 32\n\
+            %two = OpConstant %float 2.0\n\
+            %four = OpConstant %float 4.0\n\
+            %main = OpFunction %void None %fn\n\
+            %entry = OpLabel\n\
+            OpLine %file 2 5\n\
+            %doubled = OpFMul %float %two %four\n\
+            OpNoLine\n\
+            OpReturn\n\
+            OpFunctionEnd\n";
+        let words = super::assemble(text).unwrap();
+        let parser = super::Parser::new(words.into_iter(), &Default::default());
+        let (module, debug_info) = parser.parse_with_debug_info().unwrap();
+
+        let debug_source = debug_info.source.unwrap();
+        assert_eq!(debug_source.file_name.as_deref(), Some("double.comp"));
+        assert_eq!(debug_source.source_code, source);
+
+        let (_, function) = module.functions.iter().next().unwrap();
+        let (&handle, name) = function.named_expressions.iter().next().unwrap();
+        assert_eq!(name, "doubled");
+        match function.expressions[handle] {
+            crate::Expression::Binary {
+                op: crate::BinaryOperator::Multiply,
+                ..
+            } => {}
+            ref other => panic!("unexpected named expression {:?}", other),
+        }
+        #[cfg(feature = "span")]
+        {
+            let range = function.expressions.get_span(handle).to_range().unwrap();
+            assert_eq!(&source[range], "float doubled = 2.0 * 4.0;");
+        }
+    }
+
+    #[test]
+    fn detached_lines() {
+        // the included file has no embedded source
+        let text = "OpCapability Shader\n\
+            OpMemoryModel Logical GLSL450\n\
+            OpEntryPoint GLCompute %main \"main\"\n\
+            OpExecutionMode %main LocalSize 1 1 1\n\
+            %file = OpString \"main.comp\"\n\
+            %include = OpString \"common.glsl\"\n\
+            OpSource GLSL 450 %file \"void main() {}\n\"\n\
+            OpName %doubled \"doubled\"\n\
+            %void = OpTypeVoid\n\
+            %fn = OpTypeFunction %void\n\
+            %float = OpTypeFloat 32\n\
+            %two = OpConstant %float 2.0\n\
+            %four = OpConstant %float 4.0\n\
+            %main = OpFunction %void None %fn\n\
+            %entry = OpLabel\n\
+            OpLine %include 7 3\n\
+            %doubled = OpFMul %float %two %four\n\
+            OpLine %file 1 1\n\
+            OpReturn\n\
+            OpFunctionEnd\n";
+        let words = super::assemble(text).unwrap();
+        let parser = super::Parser::new(words.into_iter(), &Default::default());
+        let (module, debug_info) = parser.parse_with_debug_info().unwrap();
+
+        assert_eq!(
+            debug_info
+                .lines
+                .iter()
+                .map(|location| (
+                    location.file_name.as_deref(),
+                    location.line,
+                    location.column
+                ))
+                .collect::<Vec<_>>(),
+            [(Some("common.glsl"), 7, 3), (Some("main.comp"), 1, 1)]
+        );
+        let source_len = debug_info.source.as_ref().unwrap().source_code.len();
+        let detached = debug_info.lines[0].span.to_range().unwrap();
+        assert!(detached.start > source_len);
+
+        #[cfg(feature = "span")]
+        {
+            let (_, function) = module.functions.iter().next().unwrap();
+            let (&handle, _) = function.named_expressions.iter().next().unwrap();
+            let location = debug_info
+                .location(function.expressions.get_span(handle))
+                .unwrap();
+            assert_eq!(location.file_name.as_deref(), Some("common.glsl"));
+            assert_eq!((location.line, location.column), (7, 3));
+        }
+        #[cfg(not(feature = "span"))]
+        let _ = module;
+    }
+
+    #[test]
+    fn spec_constant_op() {
+        let text = "
+            OpCapability Shader
+            OpMemoryModel Logical GLSL450
+            OpDecorate %count SpecId 0
+            %int = OpTypeInt 32 1
+            %count = OpSpecConstant %int 4
+            %two = OpConstant %int 2
+            %doubled = OpSpecConstantOp %int IMul %count %two
+        ";
+        match super::parse_str(text, &Default::default()) {
+            Err(super::Error::UnsupportedSpecConstantOp(spirv::Op::IMul)) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[cfg(feature = "spv-out")]
+    #[test]
+    fn assemble_errors() {
+        let cases = [
+            ("OpCapability Shader\nOpFoo", 2),
+            ("%1 = OpCapability Shader", 1),
+            ("\n\nOpName %1 \"unterminated", 3),
+            ("%int = OpTypeInt 32 1\n%x = OpConstant %int 1.5", 2),
+            ("OpDecorate %1 Unknown", 1),
+        ];
+        for &(text, expected_line) in cases.iter() {
+            match super::assemble(text) {
+                Err(super::Error::InvalidAssembly(line, _)) => assert_eq!(line, expected_line),
+                other => panic!("unexpected result {:?} for {:?}", other, text),
+            }
+        }
+    }
 }
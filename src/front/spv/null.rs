@@ -23,11 +23,14 @@ pub fn generate_null_constant(
         crate::TypeInner::Vector { size, kind, width } => {
             let mut components = Vec::with_capacity(size as usize);
             for _ in 0..size as usize {
-                components.push(constant_arena.fetch_or_append(crate::Constant {
-                    name: None,
-                    specialization: None,
-                    inner: make_scalar_inner(kind, width),
-                }));
+                components.push(constant_arena.fetch_or_append(
+                    crate::Constant {
+                        name: None,
+                        specialization: None,
+                        inner: make_scalar_inner(kind, width),
+                    },
+                    Default::default(),
+                ));
             }
             crate::ConstantInner::Composite { ty, components }
         }
@@ -36,20 +39,26 @@ pub fn generate_null_constant(
             rows,
             width,
         } => {
-            let vector_ty = type_arena.fetch_or_append(crate::Type {
-                name: None,
-                inner: crate::TypeInner::Vector {
-                    kind: crate::ScalarKind::Float,
-                    size: rows,
-                    width,
+            let vector_ty = type_arena.fetch_or_append(
+                crate::Type {
+                    name: None,
+                    inner: crate::TypeInner::Vector {
+                        kind: crate::ScalarKind::Float,
+                        size: rows,
+                        width,
+                    },
                 },
-            });
+                Default::default(),
+            );
             let vector_inner = generate_null_constant(vector_ty, type_arena, constant_arena)?;
-            let vector_handle = constant_arena.fetch_or_append(crate::Constant {
-                name: None,
-                specialization: None,
-                inner: vector_inner,
-            });
+            let vector_handle = constant_arena.fetch_or_append(
+                crate::Constant {
+                    name: None,
+                    specialization: None,
+                    inner: vector_inner,
+                },
+                Default::default(),
+            );
             crate::ConstantInner::Composite {
                 ty,
                 components: vec![vector_handle; columns as usize],
@@ -61,11 +70,14 @@ pub fn generate_null_constant(
             let member_tys = members.iter().map(|member| member.ty).collect::<Vec<_>>();
             for member_ty in member_tys {
                 let inner = generate_null_constant(member_ty, type_arena, constant_arena)?;
-                components.push(constant_arena.fetch_or_append(crate::Constant {
-                    name: None,
-                    specialization: None,
-                    inner,
-                }));
+                components.push(constant_arena.fetch_or_append(
+                    crate::Constant {
+                        name: None,
+                        specialization: None,
+                        inner,
+                    },
+                    Default::default(),
+                ));
             }
             crate::ConstantInner::Composite { ty, components }
         }
@@ -78,11 +90,14 @@ pub fn generate_null_constant(
                 .to_array_length()
                 .ok_or(Error::InvalidArraySize(handle))?;
             let inner = generate_null_constant(base, type_arena, constant_arena)?;
-            let value = constant_arena.fetch_or_append(crate::Constant {
-                name: None,
-                specialization: None,
-                inner,
-            });
+            let value = constant_arena.fetch_or_append(
+                crate::Constant {
+                    name: None,
+                    specialization: None,
+                    inner,
+                },
+                Default::default(),
+            );
             crate::ConstantInner::Composite {
                 ty,
                 components: vec![value; size as usize],
@@ -105,22 +120,28 @@ pub fn generate_default_built_in(
 ) -> Result<Handle<crate::Constant>, Error> {
     let inner = match built_in {
         Some(crate::BuiltIn::Position) => {
-            let zero = constant_arena.fetch_or_append(crate::Constant {
-                name: None,
-                specialization: None,
-                inner: crate::ConstantInner::Scalar {
-                    value: crate::ScalarValue::Float(0.0),
-                    width: 4,
+            let zero = constant_arena.fetch_or_append(
+                crate::Constant {
+                    name: None,
+                    specialization: None,
+                    inner: crate::ConstantInner::Scalar {
+                        value: crate::ScalarValue::Float(0.0),
+                        width: 4,
+                    },
                 },
-            });
-            let one = constant_arena.fetch_or_append(crate::Constant {
-                name: None,
-                specialization: None,
-                inner: crate::ConstantInner::Scalar {
-                    value: crate::ScalarValue::Float(1.0),
-                    width: 4,
+                Default::default(),
+            );
+            let one = constant_arena.fetch_or_append(
+                crate::Constant {
+                    name: None,
+                    specialization: None,
+                    inner: crate::ConstantInner::Scalar {
+                        value: crate::ScalarValue::Float(1.0),
+                        width: 4,
+                    },
                 },
-            });
+                Default::default(),
+            );
             crate::ConstantInner::Composite {
                 ty,
                 components: vec![zero, zero, zero, one],
@@ -141,9 +162,12 @@ pub fn generate_default_built_in(
         //Note: `crate::BuiltIn::ClipDistance` is intentionally left for the default path
         _ => generate_null_constant(ty, type_arena, constant_arena)?,
     };
-    Ok(constant_arena.fetch_or_append(crate::Constant {
-        name: None,
-        specialization: None,
-        inner,
-    }))
+    Ok(constant_arena.fetch_or_append(
+        crate::Constant {
+            name: None,
+            specialization: None,
+            inner,
+        },
+        Default::default(),
+    ))
 }
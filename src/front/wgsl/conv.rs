@@ -29,6 +29,9 @@ pub fn map_built_in(word: &str, span: Span) -> Result<crate::BuiltIn, Error<'_>>
         "local_invocation_index" => crate::BuiltIn::LocalInvocationIndex,
         "workgroup_id" => crate::BuiltIn::WorkGroupId,
         "workgroup_size" => crate::BuiltIn::WorkGroupSize,
+        // subgroup
+        "subgroup_size" => crate::BuiltIn::SubgroupSize,
+        "subgroup_invocation_id" => crate::BuiltIn::SubgroupInvocationId,
         _ => return Err(Error::UnknownBuiltin(span)),
     })
 }
@@ -126,6 +129,56 @@ pub fn map_derivative_axis(word: &str) -> Option<crate::DerivativeAxis> {
     }
 }
 
+pub fn map_atomic_fun(word: &str) -> Option<crate::AtomicFunction> {
+    match word {
+        "atomicAdd" => Some(crate::AtomicFunction::Add),
+        "atomicSub" => Some(crate::AtomicFunction::Subtract),
+        "atomicAnd" => Some(crate::AtomicFunction::And),
+        "atomicOr" => Some(crate::AtomicFunction::InclusiveOr),
+        "atomicXor" => Some(crate::AtomicFunction::ExclusiveOr),
+        "atomicMin" => Some(crate::AtomicFunction::Min),
+        "atomicMax" => Some(crate::AtomicFunction::Max),
+        "atomicExchange" => Some(crate::AtomicFunction::Exchange { compare: None }),
+        _ => None,
+    }
+}
+
+pub fn map_subgroup_operation(
+    word: &str,
+) -> Option<(crate::SubgroupOperation, crate::CollectiveOperation)> {
+    use crate::{CollectiveOperation as Co, SubgroupOperation as So};
+    Some(match word {
+        "subgroupAll" => (So::All, Co::Reduce),
+        "subgroupAny" => (So::Any, Co::Reduce),
+        "subgroupAdd" => (So::Add, Co::Reduce),
+        "subgroupMul" => (So::Mul, Co::Reduce),
+        "subgroupMin" => (So::Min, Co::Reduce),
+        "subgroupMax" => (So::Max, Co::Reduce),
+        "subgroupAnd" => (So::And, Co::Reduce),
+        "subgroupOr" => (So::Or, Co::Reduce),
+        "subgroupXor" => (So::Xor, Co::Reduce),
+        "subgroupExclusiveAdd" => (So::Add, Co::ExclusiveScan),
+        "subgroupExclusiveMul" => (So::Mul, Co::ExclusiveScan),
+        "subgroupInclusiveAdd" => (So::Add, Co::InclusiveScan),
+        "subgroupInclusiveMul" => (So::Mul, Co::InclusiveScan),
+        _ => return None,
+    })
+}
+
+/// Map the name of a subgroup gather taking an invocation index or offset.
+pub fn map_subgroup_gather(
+    word: &str,
+) -> Option<fn(crate::Handle<crate::Expression>) -> crate::GatherMode> {
+    match word {
+        "subgroupBroadcast" => Some(crate::GatherMode::Broadcast),
+        "subgroupShuffle" => Some(crate::GatherMode::Shuffle),
+        "subgroupShuffleDown" => Some(crate::GatherMode::ShuffleDown),
+        "subgroupShuffleUp" => Some(crate::GatherMode::ShuffleUp),
+        "subgroupShuffleXor" => Some(crate::GatherMode::ShuffleXor),
+        _ => None,
+    }
+}
+
 pub fn map_relational_fun(word: &str) -> Option<crate::RelationalFunction> {
     match word {
         "any" => Some(crate::RelationalFunction::Any),
@@ -181,6 +234,7 @@ pub fn map_standard_fun(word: &str) -> Option<crate::MathFunction> {
         "normalize" => Mf::Normalize,
         "faceForward" => Mf::FaceForward,
         "reflect" => Mf::Reflect,
+        "refract" => Mf::Refract,
         // computational
         "sign" => Mf::Sign,
         "fma" => Mf::Fma,
@@ -191,9 +245,26 @@ pub fn map_standard_fun(word: &str) -> Option<crate::MathFunction> {
         "inverseSqrt" => Mf::InverseSqrt,
         "transpose" => Mf::Transpose,
         "determinant" => Mf::Determinant,
+        "inverse" => Mf::Inverse,
         // bits
         "countOneBits" => Mf::CountOneBits,
         "reverseBits" => Mf::ReverseBits,
+        "extractBits" => Mf::ExtractBits,
+        "insertBits" => Mf::InsertBits,
+        "firstTrailingBit" => Mf::FirstTrailingBit,
+        "firstLeadingBit" => Mf::FirstLeadingBit,
+        // data packing
+        "pack4x8snorm" => Mf::Pack4x8snorm,
+        "pack4x8unorm" => Mf::Pack4x8unorm,
+        "pack2x16snorm" => Mf::Pack2x16snorm,
+        "pack2x16unorm" => Mf::Pack2x16unorm,
+        "pack2x16float" => Mf::Pack2x16float,
+        // data unpacking
+        "unpack4x8snorm" => Mf::Unpack4x8snorm,
+        "unpack4x8unorm" => Mf::Unpack4x8unorm,
+        "unpack2x16snorm" => Mf::Unpack2x16snorm,
+        "unpack2x16unorm" => Mf::Unpack2x16unorm,
+        "unpack2x16float" => Mf::Unpack2x16float,
         _ => return None,
     })
 }
@@ -201,6 +201,23 @@ impl<'a> Lexer<'a> {
         self.source.len() - self.input.len()
     }
 
+    /// Skips any trivia and returns the byte offset of the next token.
+    pub(super) fn start_byte_offset(&mut self) -> usize {
+        loop {
+            let (token, rest) = consume_token(self.input, false);
+            if let Token::Trivia = token {
+                self.input = rest;
+            } else {
+                return self.current_byte_offset();
+            }
+        }
+    }
+
+    /// Returns the span from `offset` up to the end of the last consumed token.
+    pub(super) fn span_from(&self, offset: usize) -> Span {
+        offset..self.current_byte_offset()
+    }
+
     #[must_use]
     pub(super) fn next(&mut self) -> TokenSpan<'a> {
         let mut start_byte_offset = self.current_byte_offset();
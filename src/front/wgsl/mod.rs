@@ -28,7 +28,6 @@ use std::{
     borrow::Cow,
     convert::TryFrom,
     io::{self, Write},
-    iter,
     num::{NonZeroU32, ParseFloatError, ParseIntError},
     ops,
 };
@@ -98,6 +97,7 @@ pub enum Error<'a> {
     BadScalarWidth(Span, &'a str),
     BadAccessor(Span),
     BadTexture(Span),
+    BadGatherComponent(Span, u32),
     BadTypeCast {
         span: Span,
         from_type: String,
@@ -110,6 +110,8 @@ pub enum Error<'a> {
     },
     InvalidResolve(ResolveError),
     InvalidForInitializer(Span),
+    InvalidAtomicPointer(Span),
+    InvalidAtomicOperandType(Span),
     UnknownStorageClass(Span),
     UnknownAttribute(Span),
     UnknownBuiltin(Span),
@@ -126,6 +128,7 @@ pub enum Error<'a> {
     UnknownLocalFunction(Span),
     InitializationTypeMismatch(Span, Handle<crate::Type>),
     MissingType(Span),
+    NonScalarOverride(Span),
     Other,
 }
 
@@ -249,6 +252,21 @@ impl<'a> Error<'a> {
                 labels: vec![(bad_span.clone(), "not an image".into())],
                 notes: vec![],
             },
+            Error::InvalidAtomicPointer(ref span) => ParseError {
+                message: "atomic operation is done on a pointer to a non-atomic".to_string(),
+                labels: vec![(span.clone(), "atomic pointer is invalid".into())],
+                notes: vec![],
+            },
+            Error::InvalidAtomicOperandType(ref span) => ParseError {
+                message: "atomic operand type is inconsistent with the operation".to_string(),
+                labels: vec![(span.clone(), "atomic operand type is invalid".into())],
+                notes: vec![],
+            },
+            Error::BadGatherComponent(ref bad_span, component) => ParseError {
+                message: format!("invalid gather component `{}`", component),
+                labels: vec![(bad_span.clone(), "invalid component".into())],
+                notes: vec!["valid components are 0, 1, 2 and 3".to_string()],
+            },
             Error::BadTypeCast { ref span, ref from_type, ref to_type } => {
                 let msg = format!("cannot cast a {} to a {}", from_type, to_type);
                 ParseError {
@@ -337,6 +355,11 @@ impl<'a> Error<'a> {
                 labels: vec![(name_span.clone(), format!("definition of `{}`", &source[name_span.clone()]).into())],
                 notes: vec![],
             },
+            Error::NonScalarOverride(ref name_span) => ParseError {
+                message: format!("overridable constant `{}` must be a scalar", &source[name_span.clone()]),
+                labels: vec![(name_span.clone(), format!("definition of `{}`", &source[name_span.clone()]).into())],
+                notes: vec![],
+            },
             Error::Other => ParseError {
                 message: "other error".to_string(),
                 labels: vec![],
@@ -414,6 +437,9 @@ impl crate::TypeInner {
                     crate::ScalarKind::Float.to_wgsl(width),
                 )
             }
+            crate::TypeInner::Atomic { kind, width } => {
+                format!("atomic<{}>", kind.to_wgsl(width))
+            }
             crate::TypeInner::Pointer { base, .. } => {
                 let base = &types[base];
                 let name = base.name.as_deref().unwrap_or("unknown");
@@ -476,6 +502,17 @@ impl crate::TypeInner {
                 )
             }
             crate::TypeInner::Sampler { .. } => "sampler".to_string(),
+            crate::TypeInner::BindingArray { base, size, .. } => {
+                let member_type = &types[base];
+                let base = member_type.name.as_deref().unwrap_or("unknown");
+                match size {
+                    crate::ArraySize::Constant(size) => {
+                        let size = constants[size].name.as_deref().unwrap_or("unknown");
+                        format!("binding_array<{}, {}>", base, size)
+                    }
+                    crate::ArraySize::Dynamic => format!("binding_array<{}>", base),
+                }
+            }
         }
     }
 }
@@ -485,31 +522,40 @@ mod type_inner_tests {
     fn to_wgsl() {
         let mut types = crate::Arena::new();
         let mut constants = crate::Arena::new();
-        let c = constants.append(crate::Constant {
-            name: Some("C".to_string()),
-            specialization: None,
-            inner: crate::ConstantInner::Scalar {
-                width: 4,
-                value: crate::ScalarValue::Uint(32),
+        let c = constants.append(
+            crate::Constant {
+                name: Some("C".to_string()),
+                specialization: None,
+                inner: crate::ConstantInner::Scalar {
+                    width: 4,
+                    value: crate::ScalarValue::Uint(32),
+                },
             },
-        });
-
-        let mytype1 = types.append(crate::Type {
-            name: Some("MyType1".to_string()),
-            inner: crate::TypeInner::Struct {
-                top_level: true,
-                members: vec![],
-                span: 0,
+            Default::default(),
+        );
+
+        let mytype1 = types.append(
+            crate::Type {
+                name: Some("MyType1".to_string()),
+                inner: crate::TypeInner::Struct {
+                    top_level: true,
+                    members: vec![],
+                    span: 0,
+                },
             },
-        });
-        let mytype2 = types.append(crate::Type {
-            name: Some("MyType2".to_string()),
-            inner: crate::TypeInner::Struct {
-                top_level: true,
-                members: vec![],
-                span: 0,
+            Default::default(),
+        );
+        let mytype2 = types.append(
+            crate::Type {
+                name: Some("MyType2".to_string()),
+                inner: crate::TypeInner::Struct {
+                    top_level: true,
+                    members: vec![],
+                    span: 0,
+                },
             },
-        });
+            Default::default(),
+        );
 
         let array = crate::TypeInner::Array {
             base: mytype1,
@@ -686,12 +732,68 @@ impl<'a> ExpressionContext<'a, '_, '_> {
         }
     }
 
+    /// Emit the pending expressions, and append a `Statement::Atomic`
+    /// producing the given `AtomicResult` expression.
+    fn push_atomic(
+        &mut self,
+        pointer: Handle<crate::Expression>,
+        fun: crate::AtomicFunction,
+        value: Handle<crate::Expression>,
+        expression: crate::Expression,
+        span: Span,
+    ) -> Handle<crate::Expression> {
+        self.block.extend(self.emitter.finish(self.expressions));
+        let result = self.expressions.append(expression, span.clone().into());
+        self.block.push(
+            crate::Statement::Atomic {
+                pointer,
+                fun,
+                value,
+                result,
+            },
+            span.into(),
+        );
+        // restart the emitter
+        self.emitter.start(self.expressions);
+        result
+    }
+
+    /// Emit the pending expressions, and append a subgroup statement
+    /// producing the given result expression.
+    fn push_subgroup(
+        &mut self,
+        expression: crate::Expression,
+        span: Span,
+        statement: impl FnOnce(Handle<crate::Expression>) -> crate::Statement,
+    ) -> Handle<crate::Expression> {
+        self.block.extend(self.emitter.finish(self.expressions));
+        let result = self.expressions.append(expression, span.clone().into());
+        self.block.push(statement(result), span.into());
+        // restart the emitter
+        self.emitter.start(self.expressions);
+        result
+    }
+
+    /// Result expression of a subgroup operation on `argument`.
+    fn subgroup_result(
+        &mut self,
+        argument: Handle<crate::Expression>,
+    ) -> Result<crate::Expression, Error<'a>> {
+        self.resolve_type(argument)?;
+        let ty = match self.typifier[argument].clone() {
+            TypeResolution::Handle(ty) => ty,
+            TypeResolution::Value(inner) => self
+                .types
+                .fetch_or_append(crate::Type { name: None, inner }, Default::default()),
+        };
+        Ok(crate::Expression::SubgroupOperationResult { ty })
+    }
+
     fn prepare_sampling(
         &mut self,
-        image_name: &'a str,
+        image: Handle<crate::Expression>,
         span: Span,
     ) -> Result<SamplingContext, Error<'a>> {
-        let image = self.lookup_ident.lookup(image_name, span.clone())?;
         Ok(SamplingContext {
             image,
             arrayed: match *self.resolve_type(image)? {
@@ -710,13 +812,15 @@ impl<'a> ExpressionContext<'a, '_, '_> {
             ExpressionContext<'a, '_, '_>,
         ) -> Result<Handle<crate::Expression>, Error<'a>>,
     ) -> Result<Handle<crate::Expression>, Error<'a>> {
+        let start = lexer.start_byte_offset();
         let mut left = parser(lexer, self.reborrow())?;
         while let Some(op) = classifier(lexer.peek().0) {
             let _ = lexer.next();
             let right = parser(lexer, self.reborrow())?;
-            left = self
-                .expressions
-                .append(crate::Expression::Binary { op, left, right });
+            left = self.expressions.append(
+                crate::Expression::Binary { op, left, right },
+                lexer.span_from(start).into(),
+            );
         }
         Ok(left)
     }
@@ -730,6 +834,7 @@ impl<'a> ExpressionContext<'a, '_, '_> {
             ExpressionContext<'a, '_, '_>,
         ) -> Result<Handle<crate::Expression>, Error<'a>>,
     ) -> Result<Handle<crate::Expression>, Error<'a>> {
+        let start = lexer.start_byte_offset();
         let mut left = parser(lexer, self.reborrow())?;
         while let Some(op) = classifier(lexer.peek().0) {
             let _ = lexer.next();
@@ -742,21 +847,24 @@ impl<'a> ExpressionContext<'a, '_, '_> {
                 };
                 match (left_size, self.resolve_type(right)?) {
                     (Some(size), &crate::TypeInner::Scalar { .. }) => {
-                        right = self
-                            .expressions
-                            .append(crate::Expression::Splat { size, value: right });
+                        right = self.expressions.append(
+                            crate::Expression::Splat { size, value: right },
+                            lexer.span_from(start).into(),
+                        );
                     }
                     (None, &crate::TypeInner::Vector { size, .. }) => {
-                        left = self
-                            .expressions
-                            .append(crate::Expression::Splat { size, value: left });
+                        left = self.expressions.append(
+                            crate::Expression::Splat { size, value: left },
+                            lexer.span_from(start).into(),
+                        );
                     }
                     _ => {}
                 }
             }
-            left = self
-                .expressions
-                .append(crate::Expression::Binary { op, left, right });
+            left = self.expressions.append(
+                crate::Expression::Binary { op, left, right },
+                lexer.span_from(start).into(),
+            );
         }
         Ok(left)
     }
@@ -917,6 +1025,7 @@ struct ParsedVariable<'a> {
     ty: Handle<crate::Type>,
     access: crate::StorageAccess,
     init: Option<Handle<crate::Constant>>,
+    span: Span,
 }
 
 #[derive(Clone, Debug)]
@@ -994,7 +1103,7 @@ impl std::error::Error for ParseError {
 }
 
 pub struct Parser {
-    scopes: Vec<Scope>,
+    scopes: Vec<(Scope, usize)>,
     lookup_type: FastHashMap<String, Handle<crate::Type>>,
     layouter: Layouter,
 }
@@ -1008,6 +1117,20 @@ impl Parser {
         }
     }
 
+    fn push_scope(&mut self, scope: Scope, lexer: &mut Lexer<'_>) {
+        self.scopes.push((scope, lexer.start_byte_offset()));
+    }
+
+    fn pop_scope(&mut self, lexer: &Lexer<'_>) -> Span {
+        let (_, initial) = self.scopes.pop().unwrap();
+        lexer.span_from(initial)
+    }
+
+    fn peek_scope(&self, lexer: &Lexer<'_>) -> Span {
+        let &(_, initial) = self.scopes.last().unwrap();
+        lexer.span_from(initial)
+    }
+
     fn get_constant_inner<'a>(
         word: &'a str,
         ty: char,
@@ -1071,6 +1194,26 @@ impl Parser {
         Ok(Some((fun_handle, arguments)))
     }
 
+    /// Parse an image or sampler operand: either a global variable, or an
+    /// element of a global binding array.
+    fn parse_resource<'a>(
+        &mut self,
+        lexer: &mut Lexer<'a>,
+        mut ctx: ExpressionContext<'a, '_, '_>,
+    ) -> Result<(Handle<crate::Expression>, Span), Error<'a>> {
+        let (name, name_span) = lexer.next_ident_with_span()?;
+        let base = ctx.lookup_ident.lookup(name, name_span.clone())?;
+        if !lexer.skip(Token::Paren('[')) {
+            return Ok((base, name_span));
+        }
+        let index = self.parse_general_expression(lexer, ctx.reborrow())?;
+        let close_span = lexer.expect_span(Token::Paren(']'))?;
+        let span = name_span.start..close_span.end;
+        let expression = crate::Expression::Access { base, index };
+        let handle = ctx.expressions.append(expression, span.clone().into());
+        Ok((handle, span))
+    }
+
     fn parse_function_call_inner<'a>(
         &mut self,
         lexer: &mut Lexer<'a>,
@@ -1103,12 +1246,19 @@ impl Parser {
             } else {
                 None
             };
+            let arg3 = if arg_count > 3 {
+                lexer.expect(Token::Separator(','))?;
+                Some(self.parse_general_expression(lexer, ctx.reborrow())?)
+            } else {
+                None
+            };
             lexer.close_arguments()?;
             crate::Expression::Math {
                 fun,
                 arg,
                 arg1,
                 arg2,
+                arg3,
             }
         } else if name == "select" {
             lexer.open_arguments()?;
@@ -1128,17 +1278,98 @@ impl Parser {
             let array = self.parse_singular_expression(lexer, ctx.reborrow())?;
             lexer.close_arguments()?;
             crate::Expression::ArrayLength(array)
+        } else if name == "atomicLoad" {
+            lexer.open_arguments()?;
+            let (pointer, _, _) = self.parse_atomic_pointer(lexer, ctx.reborrow())?;
+            lexer.close_arguments()?;
+            crate::Expression::Load { pointer }
+        } else if let Some(fun) = conv::map_atomic_fun(name) {
+            return self
+                .parse_atomic_helper(lexer, fun, ctx.reborrow())
+                .map(Some);
+        } else if name == "atomicCompareExchangeWeak" {
+            lexer.open_arguments()?;
+            let (pointer, kind, width) = self.parse_atomic_pointer(lexer, ctx.reborrow())?;
+            lexer.expect(Token::Separator(','))?;
+            let cmp = self.parse_atomic_operand(lexer, kind, width, ctx.reborrow())?;
+            lexer.expect(Token::Separator(','))?;
+            let value = self.parse_atomic_operand(lexer, kind, width, ctx.reborrow())?;
+            lexer.close_arguments()?;
+            let expression = crate::Expression::AtomicResult {
+                kind,
+                width,
+                comparison: true,
+            };
+            let fun = crate::AtomicFunction::Exchange { compare: Some(cmp) };
+            return Ok(Some(ctx.push_atomic(
+                pointer,
+                fun,
+                value,
+                expression,
+                self.peek_scope(lexer),
+            )));
+        } else if name == "subgroupBallot" {
+            lexer.open_arguments()?;
+            let predicate = if lexer.skip(Token::Paren(')')) {
+                None
+            } else {
+                let predicate = self.parse_general_expression(lexer, ctx.reborrow())?;
+                lexer.close_arguments()?;
+                Some(predicate)
+            };
+            let expression = crate::Expression::SubgroupBallotResult;
+            return Ok(Some(ctx.push_subgroup(
+                expression,
+                self.peek_scope(lexer),
+                |result| crate::Statement::SubgroupBallot { result, predicate },
+            )));
+        } else if let Some((op, collective_op)) = conv::map_subgroup_operation(name) {
+            lexer.open_arguments()?;
+            let argument = self.parse_general_expression(lexer, ctx.reborrow())?;
+            lexer.close_arguments()?;
+            let expression = ctx.subgroup_result(argument)?;
+            return Ok(Some(ctx.push_subgroup(
+                expression,
+                self.peek_scope(lexer),
+                |result| crate::Statement::SubgroupCollectiveOperation {
+                    op,
+                    collective_op,
+                    argument,
+                    result,
+                },
+            )));
+        } else if name == "subgroupBroadcastFirst" || conv::map_subgroup_gather(name).is_some() {
+            lexer.open_arguments()?;
+            let argument = self.parse_general_expression(lexer, ctx.reborrow())?;
+            let mode = match conv::map_subgroup_gather(name) {
+                Some(mode) => {
+                    lexer.expect(Token::Separator(','))?;
+                    mode(self.parse_general_expression(lexer, ctx.reborrow())?)
+                }
+                None => crate::GatherMode::BroadcastFirst,
+            };
+            lexer.close_arguments()?;
+            let expression = ctx.subgroup_result(argument)?;
+            return Ok(Some(ctx.push_subgroup(
+                expression,
+                self.peek_scope(lexer),
+                |result| crate::Statement::SubgroupGather {
+                    mode,
+                    argument,
+                    result,
+                },
+            )));
         } else {
             // texture sampling
             match name {
                 "textureSample" => {
                     lexer.open_arguments()?;
-                    let (image_name, image_span) = lexer.next_ident_with_span()?;
+                    let (image, image_span) = self.parse_resource(lexer, ctx.reborrow())?;
                     lexer.expect(Token::Separator(','))?;
-                    let (sampler_name, sampler_span) = lexer.next_ident_with_span()?;
+                    let (sampler, _) = self.parse_resource(lexer, ctx.reborrow())?;
                     lexer.expect(Token::Separator(','))?;
                     let coordinate = self.parse_general_expression(lexer, ctx.reborrow())?;
-                    let sc = ctx.prepare_sampling(image_name, image_span)?;
+                    let sc = ctx.prepare_sampling(image, image_span)?;
                     let array_index = if sc.arrayed {
                         lexer.expect(Token::Separator(','))?;
                         Some(self.parse_general_expression(lexer, ctx.reborrow())?)
@@ -1153,22 +1384,23 @@ impl Parser {
                     lexer.close_arguments()?;
                     crate::Expression::ImageSample {
                         image: sc.image,
-                        sampler: ctx.lookup_ident.lookup(sampler_name, sampler_span)?,
+                        sampler,
                         coordinate,
                         array_index,
                         offset,
                         level: crate::SampleLevel::Auto,
                         depth_ref: None,
+                        gather: None,
                     }
                 }
                 "textureSampleLevel" => {
                     lexer.open_arguments()?;
-                    let (image_name, image_span) = lexer.next_ident_with_span()?;
+                    let (image, image_span) = self.parse_resource(lexer, ctx.reborrow())?;
                     lexer.expect(Token::Separator(','))?;
-                    let (sampler_name, sampler_span) = lexer.next_ident_with_span()?;
+                    let (sampler, _) = self.parse_resource(lexer, ctx.reborrow())?;
                     lexer.expect(Token::Separator(','))?;
                     let coordinate = self.parse_general_expression(lexer, ctx.reborrow())?;
-                    let sc = ctx.prepare_sampling(image_name, image_span)?;
+                    let sc = ctx.prepare_sampling(image, image_span)?;
                     let array_index = if sc.arrayed {
                         lexer.expect(Token::Separator(','))?;
                         Some(self.parse_general_expression(lexer, ctx.reborrow())?)
@@ -1185,22 +1417,23 @@ impl Parser {
                     lexer.close_arguments()?;
                     crate::Expression::ImageSample {
                         image: sc.image,
-                        sampler: ctx.lookup_ident.lookup(sampler_name, sampler_span)?,
+                        sampler,
                         coordinate,
                         array_index,
                         offset,
                         level: crate::SampleLevel::Exact(level),
                         depth_ref: None,
+                        gather: None,
                     }
                 }
                 "textureSampleBias" => {
                     lexer.open_arguments()?;
-                    let (image_name, image_span) = lexer.next_ident_with_span()?;
+                    let (image, image_span) = self.parse_resource(lexer, ctx.reborrow())?;
                     lexer.expect(Token::Separator(','))?;
-                    let (sampler_name, sampler_span) = lexer.next_ident_with_span()?;
+                    let (sampler, _) = self.parse_resource(lexer, ctx.reborrow())?;
                     lexer.expect(Token::Separator(','))?;
                     let coordinate = self.parse_general_expression(lexer, ctx.reborrow())?;
-                    let sc = ctx.prepare_sampling(image_name, image_span)?;
+                    let sc = ctx.prepare_sampling(image, image_span)?;
                     let array_index = if sc.arrayed {
                         lexer.expect(Token::Separator(','))?;
                         Some(self.parse_general_expression(lexer, ctx.reborrow())?)
@@ -1217,22 +1450,23 @@ impl Parser {
                     lexer.close_arguments()?;
                     crate::Expression::ImageSample {
                         image: sc.image,
-                        sampler: ctx.lookup_ident.lookup(sampler_name, sampler_span)?,
+                        sampler,
                         coordinate,
                         array_index,
                         offset,
                         level: crate::SampleLevel::Bias(bias),
                         depth_ref: None,
+                        gather: None,
                     }
                 }
                 "textureSampleGrad" => {
                     lexer.open_arguments()?;
-                    let (image_name, image_span) = lexer.next_ident_with_span()?;
+                    let (image, image_span) = self.parse_resource(lexer, ctx.reborrow())?;
                     lexer.expect(Token::Separator(','))?;
-                    let (sampler_name, sampler_span) = lexer.next_ident_with_span()?;
+                    let (sampler, _) = self.parse_resource(lexer, ctx.reborrow())?;
                     lexer.expect(Token::Separator(','))?;
                     let coordinate = self.parse_general_expression(lexer, ctx.reborrow())?;
-                    let sc = ctx.prepare_sampling(image_name, image_span)?;
+                    let sc = ctx.prepare_sampling(image, image_span)?;
                     let array_index = if sc.arrayed {
                         lexer.expect(Token::Separator(','))?;
                         Some(self.parse_general_expression(lexer, ctx.reborrow())?)
@@ -1251,22 +1485,23 @@ impl Parser {
                     lexer.close_arguments()?;
                     crate::Expression::ImageSample {
                         image: sc.image,
-                        sampler: ctx.lookup_ident.lookup(sampler_name, sampler_span)?,
+                        sampler,
                         coordinate,
                         array_index,
                         offset,
                         level: crate::SampleLevel::Gradient { x, y },
                         depth_ref: None,
+                        gather: None,
                     }
                 }
                 "textureSampleCompare" => {
                     lexer.open_arguments()?;
-                    let (image_name, image_span) = lexer.next_ident_with_span()?;
+                    let (image, image_span) = self.parse_resource(lexer, ctx.reborrow())?;
                     lexer.expect(Token::Separator(','))?;
-                    let (sampler_name, sampler_span) = lexer.next_ident_with_span()?;
+                    let (sampler, _) = self.parse_resource(lexer, ctx.reborrow())?;
                     lexer.expect(Token::Separator(','))?;
                     let coordinate = self.parse_general_expression(lexer, ctx.reborrow())?;
-                    let sc = ctx.prepare_sampling(image_name, image_span)?;
+                    let sc = ctx.prepare_sampling(image, image_span)?;
                     let array_index = if sc.arrayed {
                         lexer.expect(Token::Separator(','))?;
                         Some(self.parse_general_expression(lexer, ctx.reborrow())?)
@@ -1283,22 +1518,23 @@ impl Parser {
                     lexer.close_arguments()?;
                     crate::Expression::ImageSample {
                         image: sc.image,
-                        sampler: ctx.lookup_ident.lookup(sampler_name, sampler_span)?,
+                        sampler,
                         coordinate,
                         array_index,
                         offset,
                         level: crate::SampleLevel::Auto,
                         depth_ref: Some(reference),
+                        gather: None,
                     }
                 }
                 "textureSampleCompareLevel" => {
                     lexer.open_arguments()?;
-                    let (image_name, image_span) = lexer.next_ident_with_span()?;
+                    let (image, image_span) = self.parse_resource(lexer, ctx.reborrow())?;
                     lexer.expect(Token::Separator(','))?;
-                    let (sampler_name, sampler_span) = lexer.next_ident_with_span()?;
+                    let (sampler, _) = self.parse_resource(lexer, ctx.reborrow())?;
                     lexer.expect(Token::Separator(','))?;
                     let coordinate = self.parse_general_expression(lexer, ctx.reborrow())?;
-                    let sc = ctx.prepare_sampling(image_name, image_span)?;
+                    let sc = ctx.prepare_sampling(image, image_span)?;
                     let array_index = if sc.arrayed {
                         lexer.expect(Token::Separator(','))?;
                         Some(self.parse_general_expression(lexer, ctx.reborrow())?)
@@ -1315,18 +1551,93 @@ impl Parser {
                     lexer.close_arguments()?;
                     crate::Expression::ImageSample {
                         image: sc.image,
-                        sampler: ctx.lookup_ident.lookup(sampler_name, sampler_span)?,
+                        sampler,
                         coordinate,
                         array_index,
                         offset,
                         level: crate::SampleLevel::Zero,
                         depth_ref: Some(reference),
+                        gather: None,
+                    }
+                }
+                "textureGather" => {
+                    lexer.open_arguments()?;
+                    // The component is omitted for depth textures
+                    let component = match lexer.peek() {
+                        (Token::Number { .. }, span) => {
+                            let index = lexer.next_uint_literal()?;
+                            lexer.expect(Token::Separator(','))?;
+                            *crate::SwizzleComponent::XYZW
+                                .get(index as usize)
+                                .ok_or(Error::BadGatherComponent(span, index))?
+                        }
+                        _ => crate::SwizzleComponent::X,
+                    };
+                    let (image, image_span) = self.parse_resource(lexer, ctx.reborrow())?;
+                    lexer.expect(Token::Separator(','))?;
+                    let (sampler, _) = self.parse_resource(lexer, ctx.reborrow())?;
+                    lexer.expect(Token::Separator(','))?;
+                    let coordinate = self.parse_general_expression(lexer, ctx.reborrow())?;
+                    let sc = ctx.prepare_sampling(image, image_span)?;
+                    let array_index = if sc.arrayed {
+                        lexer.expect(Token::Separator(','))?;
+                        Some(self.parse_general_expression(lexer, ctx.reborrow())?)
+                    } else {
+                        None
+                    };
+                    let offset = if lexer.skip(Token::Separator(',')) {
+                        Some(self.parse_const_expression(lexer, ctx.types, ctx.constants)?)
+                    } else {
+                        None
+                    };
+                    lexer.close_arguments()?;
+                    crate::Expression::ImageSample {
+                        image: sc.image,
+                        sampler,
+                        coordinate,
+                        array_index,
+                        offset,
+                        level: crate::SampleLevel::Zero,
+                        depth_ref: None,
+                        gather: Some(component),
+                    }
+                }
+                "textureGatherCompare" => {
+                    lexer.open_arguments()?;
+                    let (image, image_span) = self.parse_resource(lexer, ctx.reborrow())?;
+                    lexer.expect(Token::Separator(','))?;
+                    let (sampler, _) = self.parse_resource(lexer, ctx.reborrow())?;
+                    lexer.expect(Token::Separator(','))?;
+                    let coordinate = self.parse_general_expression(lexer, ctx.reborrow())?;
+                    let sc = ctx.prepare_sampling(image, image_span)?;
+                    let array_index = if sc.arrayed {
+                        lexer.expect(Token::Separator(','))?;
+                        Some(self.parse_general_expression(lexer, ctx.reborrow())?)
+                    } else {
+                        None
+                    };
+                    lexer.expect(Token::Separator(','))?;
+                    let reference = self.parse_general_expression(lexer, ctx.reborrow())?;
+                    let offset = if lexer.skip(Token::Separator(',')) {
+                        Some(self.parse_const_expression(lexer, ctx.types, ctx.constants)?)
+                    } else {
+                        None
+                    };
+                    lexer.close_arguments()?;
+                    crate::Expression::ImageSample {
+                        image: sc.image,
+                        sampler,
+                        coordinate,
+                        array_index,
+                        offset,
+                        level: crate::SampleLevel::Zero,
+                        depth_ref: Some(reference),
+                        gather: Some(crate::SwizzleComponent::X),
                     }
                 }
                 "textureLoad" => {
                     lexer.open_arguments()?;
-                    let (image_name, image_span) = lexer.next_ident_with_span()?;
-                    let image = ctx.lookup_ident.lookup(image_name, image_span.clone())?;
+                    let (image, image_span) = self.parse_resource(lexer, ctx.reborrow())?;
                     lexer.expect(Token::Separator(','))?;
                     let coordinate = self.parse_general_expression(lexer, ctx.reborrow())?;
                     let (class, arrayed) = match *ctx.resolve_type(image)? {
@@ -1357,8 +1668,7 @@ impl Parser {
                 }
                 "textureDimensions" => {
                     lexer.open_arguments()?;
-                    let (image_name, image_span) = lexer.next_ident_with_span()?;
-                    let image = ctx.lookup_ident.lookup(image_name, image_span)?;
+                    let (image, _) = self.parse_resource(lexer, ctx.reborrow())?;
                     let level = if lexer.skip(Token::Separator(',')) {
                         let expr = self.parse_general_expression(lexer, ctx.reborrow())?;
                         Some(expr)
@@ -1373,8 +1683,7 @@ impl Parser {
                 }
                 "textureNumLevels" => {
                     lexer.open_arguments()?;
-                    let (image_name, image_span) = lexer.next_ident_with_span()?;
-                    let image = ctx.lookup_ident.lookup(image_name, image_span)?;
+                    let (image, _) = self.parse_resource(lexer, ctx.reborrow())?;
                     lexer.close_arguments()?;
                     crate::Expression::ImageQuery {
                         image,
@@ -1383,8 +1692,7 @@ impl Parser {
                 }
                 "textureNumLayers" => {
                     lexer.open_arguments()?;
-                    let (image_name, image_span) = lexer.next_ident_with_span()?;
-                    let image = ctx.lookup_ident.lookup(image_name, image_span)?;
+                    let (image, _) = self.parse_resource(lexer, ctx.reborrow())?;
                     lexer.close_arguments()?;
                     crate::Expression::ImageQuery {
                         image,
@@ -1393,8 +1701,7 @@ impl Parser {
                 }
                 "textureNumSamples" => {
                     lexer.open_arguments()?;
-                    let (image_name, image_span) = lexer.next_ident_with_span()?;
-                    let image = ctx.lookup_ident.lookup(image_name, image_span)?;
+                    let (image, _) = self.parse_resource(lexer, ctx.reborrow())?;
                     lexer.close_arguments()?;
                     crate::Expression::ImageQuery {
                         image,
@@ -1406,14 +1713,20 @@ impl Parser {
                     let handle =
                         match self.parse_local_function_call(lexer, name, ctx.reborrow())? {
                             Some((function, arguments)) => {
+                                let span = self.peek_scope(lexer);
                                 ctx.block.extend(ctx.emitter.finish(ctx.expressions));
-                                let result =
-                                    Some(ctx.expressions.append(crate::Expression::Call(function)));
-                                ctx.block.push(crate::Statement::Call {
-                                    function,
-                                    arguments,
-                                    result,
-                                });
+                                let result = Some(ctx.expressions.append(
+                                    crate::Expression::Call(function),
+                                    span.clone().into(),
+                                ));
+                                ctx.block.push(
+                                    crate::Statement::Call {
+                                        function,
+                                        arguments,
+                                        result,
+                                    },
+                                    span.into(),
+                                );
                                 // restart the emitter
                                 ctx.emitter.start(ctx.expressions);
                                 result
@@ -1424,7 +1737,65 @@ impl Parser {
                 }
             }
         };
-        Ok(Some(ctx.expressions.append(expr)))
+        let span = self.peek_scope(lexer);
+        Ok(Some(ctx.expressions.append(expr, span.into())))
+    }
+
+    /// Parse the pointer argument of an atomic function, returning it
+    /// together with the scalar kind and width of the atomic.
+    fn parse_atomic_pointer<'a>(
+        &mut self,
+        lexer: &mut Lexer<'a>,
+        mut ctx: ExpressionContext<'a, '_, '_>,
+    ) -> Result<(Handle<crate::Expression>, crate::ScalarKind, crate::Bytes), Error<'a>> {
+        let start = lexer.current_byte_offset();
+        let pointer = self.parse_general_expression(lexer, ctx.reborrow())?;
+        let span = start..lexer.current_byte_offset();
+        match *ctx.resolve_type(pointer)? {
+            crate::TypeInner::Pointer { base, .. } => match ctx.types[base].inner {
+                crate::TypeInner::Atomic { kind, width } => Ok((pointer, kind, width)),
+                _ => Err(Error::InvalidAtomicPointer(span)),
+            },
+            _ => Err(Error::InvalidAtomicPointer(span)),
+        }
+    }
+
+    fn parse_atomic_operand<'a>(
+        &mut self,
+        lexer: &mut Lexer<'a>,
+        kind: crate::ScalarKind,
+        width: crate::Bytes,
+        mut ctx: ExpressionContext<'a, '_, '_>,
+    ) -> Result<Handle<crate::Expression>, Error<'a>> {
+        let start = lexer.current_byte_offset();
+        let value = self.parse_general_expression(lexer, ctx.reborrow())?;
+        let span = start..lexer.current_byte_offset();
+        match *ctx.resolve_type(value)? {
+            crate::TypeInner::Scalar {
+                kind: value_kind,
+                width: value_width,
+            } if value_kind == kind && value_width == width => Ok(value),
+            _ => Err(Error::InvalidAtomicOperandType(span)),
+        }
+    }
+
+    fn parse_atomic_helper<'a>(
+        &mut self,
+        lexer: &mut Lexer<'a>,
+        fun: crate::AtomicFunction,
+        mut ctx: ExpressionContext<'a, '_, '_>,
+    ) -> Result<Handle<crate::Expression>, Error<'a>> {
+        lexer.open_arguments()?;
+        let (pointer, kind, width) = self.parse_atomic_pointer(lexer, ctx.reborrow())?;
+        lexer.expect(Token::Separator(','))?;
+        let value = self.parse_atomic_operand(lexer, kind, width, ctx.reborrow())?;
+        lexer.close_arguments()?;
+        let expression = crate::Expression::AtomicResult {
+            kind,
+            width,
+            comparison: false,
+        };
+        Ok(ctx.push_atomic(pointer, fun, value, expression, self.peek_scope(lexer)))
     }
 
     fn parse_construction<'a>(
@@ -1509,15 +1880,16 @@ impl Parser {
         } else {
             let ty = match ty_resolution {
                 TypeResolution::Handle(handle) => handle,
-                TypeResolution::Value(inner) => {
-                    ctx.types.fetch_or_append(crate::Type { name: None, inner })
-                }
+                TypeResolution::Value(inner) => ctx
+                    .types
+                    .fetch_or_append(crate::Type { name: None, inner }, Default::default()),
             };
             components.push(last_component);
             crate::Expression::Compose { ty, components }
         };
 
-        Ok(Some(ctx.expressions.append(expr)))
+        let span = self.peek_scope(lexer);
+        Ok(Some(ctx.expressions.append(expr, span.into())))
     }
 
     fn parse_const_expression_impl<'a>(
@@ -1528,7 +1900,7 @@ impl Parser {
         type_arena: &mut Arena<crate::Type>,
         const_arena: &mut Arena<crate::Constant>,
     ) -> Result<Handle<crate::Constant>, Error<'a>> {
-        self.scopes.push(Scope::ConstantExpr);
+        self.push_scope(Scope::ConstantExpr, lexer);
         let inner = match first_token_span {
             (Token::Word("true"), _) => crate::ConstantInner::boolean(true),
             (Token::Word("false"), _) => crate::ConstantInner::boolean(false),
@@ -1540,7 +1912,7 @@ impl Parser {
                 for (handle, var) in const_arena.iter() {
                     match var.name {
                         Some(ref string) if string == name => {
-                            self.scopes.pop();
+                            self.pop_scope(lexer);
                             return Ok(handle);
                         }
                         _ => {}
@@ -1572,20 +1944,26 @@ impl Parser {
         };
 
         let handle = if let Some(name) = register_name {
-            const_arena.append(crate::Constant {
-                name: Some(name.to_string()),
-                specialization: None,
-                inner,
-            })
+            const_arena.append(
+                crate::Constant {
+                    name: Some(name.to_string()),
+                    specialization: None,
+                    inner,
+                },
+                Default::default(),
+            )
         } else {
-            const_arena.fetch_or_append(crate::Constant {
-                name: None,
-                specialization: None,
-                inner,
-            })
+            const_arena.fetch_or_append(
+                crate::Constant {
+                    name: None,
+                    specialization: None,
+                    inner,
+                },
+                Default::default(),
+            )
         };
 
-        self.scopes.pop();
+        self.pop_scope(lexer);
         Ok(handle)
     }
 
@@ -1603,7 +1981,7 @@ impl Parser {
         lexer: &mut Lexer<'a>,
         mut ctx: ExpressionContext<'a, '_, '_>,
     ) -> Result<Handle<crate::Expression>, Error<'a>> {
-        self.scopes.push(Scope::PrimaryExpr);
+        self.push_scope(Scope::PrimaryExpr, lexer);
         let handle = match lexer.next() {
             (Token::Paren('('), _) => {
                 let expr = self.parse_general_expression(lexer, ctx)?;
@@ -1617,9 +1995,10 @@ impl Parser {
                     self.parse_const_expression_impl(token, lexer, None, ctx.types, ctx.constants)?;
                 // pause the emitter while generating this expression, since it's pre-emitted
                 ctx.block.extend(ctx.emitter.finish(ctx.expressions));
+                let span = self.peek_scope(lexer);
                 let expr = ctx
                     .expressions
-                    .append(crate::Expression::Constant(const_handle));
+                    .append(crate::Expression::Constant(const_handle), span.into());
                 ctx.emitter.start(ctx.expressions);
                 expr
             }
@@ -1639,7 +2018,7 @@ impl Parser {
             }
             other => return Err(Error::Unexpected(other, ExpectedToken::PrimaryExpression)),
         };
-        self.scopes.pop();
+        self.pop_scope(lexer);
         Ok(handle)
     }
 
@@ -1670,7 +2049,9 @@ impl Parser {
                 };
                 if now {
                     let expression = crate::Expression::Load { pointer: handle };
-                    handle = ctx.expressions.append(expression);
+                    handle = ctx
+                        .expressions
+                        .append(expression, self.peek_scope(lexer).into());
                     needs_deref = false;
                 }
             }
@@ -1762,14 +2143,17 @@ impl Parser {
                     // after we reached for the value, load it
                     return Ok(if needs_deref {
                         let expression = crate::Expression::Load { pointer: handle };
-                        ctx.expressions.append(expression)
+                        ctx.expressions
+                            .append(expression, self.peek_scope(lexer).into())
                     } else {
                         handle
                     });
                 }
             };
 
-            handle = ctx.expressions.append(expression);
+            handle = ctx
+                .expressions
+                .append(expression, self.peek_scope(lexer).into());
         }
     }
 
@@ -1778,7 +2162,7 @@ impl Parser {
         lexer: &mut Lexer<'a>,
         mut ctx: ExpressionContext<'a, '_, '_>,
     ) -> Result<Handle<crate::Expression>, Error<'a>> {
-        self.scopes.push(Scope::SingularExpr);
+        self.push_scope(Scope::SingularExpr, lexer);
         //TODO: refactor this to avoid backing up
         let backup = lexer.clone();
         let (allow_deref, handle) = match lexer.next().0 {
@@ -1787,14 +2171,20 @@ impl Parser {
                     op: crate::UnaryOperator::Negate,
                     expr: self.parse_singular_expression(lexer, ctx.reborrow())?,
                 };
-                (true, ctx.expressions.append(expr))
+                (
+                    true,
+                    ctx.expressions.append(expr, self.peek_scope(lexer).into()),
+                )
             }
             Token::Operation('!') | Token::Operation('~') => {
                 let expr = crate::Expression::Unary {
                     op: crate::UnaryOperator::Not,
                     expr: self.parse_singular_expression(lexer, ctx.reborrow())?,
                 };
-                (true, ctx.expressions.append(expr))
+                (
+                    true,
+                    ctx.expressions.append(expr, self.peek_scope(lexer).into()),
+                )
             }
             Token::Operation('&') => {
                 let handle = self.parse_primary_expression(lexer, ctx.reborrow())?;
@@ -1818,7 +2208,7 @@ impl Parser {
         };
 
         let post_handle = self.parse_postfix(lexer, ctx, handle, allow_deref)?;
-        self.scopes.pop();
+        self.pop_scope(lexer);
         Ok(post_handle)
     }
 
@@ -1905,7 +2295,7 @@ impl Parser {
         lexer: &mut Lexer<'a>,
         mut context: ExpressionContext<'a, '_, '_>,
     ) -> Result<Handle<crate::Expression>, Error<'a>> {
-        self.scopes.push(Scope::GeneralExpr);
+        self.push_scope(Scope::GeneralExpr, lexer);
         // logical_or_expression
         let handle = context.parse_binary_op(
             lexer,
@@ -1961,7 +2351,7 @@ impl Parser {
                 )
             },
         )?;
-        self.scopes.pop();
+        self.pop_scope(lexer);
         Ok(handle)
     }
 
@@ -1983,7 +2373,7 @@ impl Parser {
         type_arena: &mut Arena<crate::Type>,
         const_arena: &mut Arena<crate::Constant>,
     ) -> Result<ParsedVariable<'a>, Error<'a>> {
-        self.scopes.push(Scope::VariableDecl);
+        self.push_scope(Scope::VariableDecl, lexer);
         let mut class = None;
         if lexer.skip(Token::Paren('<')) {
             let (class_str, span) = lexer.next_ident_with_span()?;
@@ -2001,13 +2391,14 @@ impl Parser {
             None
         };
         lexer.expect(Token::Separator(';'))?;
-        self.scopes.pop();
+        let span = self.pop_scope(lexer);
         Ok(ParsedVariable {
             name,
             class,
             ty,
             access,
             init,
+            span,
         })
     }
 
@@ -2027,7 +2418,7 @@ impl Parser {
             let bind_start = lexer.current_byte_offset();
             let mut bind_parser = BindingParser::default();
             if lexer.skip(Token::DoubleParen('[')) {
-                self.scopes.push(Scope::Attribute);
+                self.push_scope(Scope::Attribute, lexer);
                 let mut ready = true;
                 loop {
                     match lexer.next() {
@@ -2071,7 +2462,7 @@ impl Parser {
                         }
                     }
                 }
-                self.scopes.pop();
+                self.pop_scope(lexer);
             }
 
             let bind_end = lexer.current_byte_offset();
@@ -2211,6 +2602,10 @@ impl Parser {
                     width,
                 }
             }
+            "atomic" => {
+                let (kind, width) = lexer.next_scalar_generic()?;
+                crate::TypeInner::Atomic { kind, width }
+            }
             "ptr" => {
                 lexer.expect_generic_paren('<')?;
                 let (ident, span) = lexer.next_ident_with_span()?;
@@ -2242,6 +2637,20 @@ impl Parser {
 
                 crate::TypeInner::Array { base, size, stride }
             }
+            "binding_array" => {
+                lexer.expect_generic_paren('<')?;
+                let (base, _access) = self.parse_type_decl(lexer, None, type_arena, const_arena)?;
+                let size = if lexer.skip(Token::Separator(',')) {
+                    let const_handle =
+                        self.parse_const_expression(lexer, type_arena, const_arena)?;
+                    crate::ArraySize::Constant(const_handle)
+                } else {
+                    crate::ArraySize::Dynamic
+                };
+                lexer.expect_generic_paren('>')?;
+
+                crate::TypeInner::BindingArray { base, size }
+            }
             "sampler" => crate::TypeInner::Sampler { comparison: false },
             "sampler_comparison" => crate::TypeInner::Sampler { comparison: true },
             "texture_1d" => {
@@ -2418,10 +2827,16 @@ impl Parser {
             Some(&handle) => handle,
             None => {
                 match self.parse_type_decl_impl(lexer, attribute, name, type_arena, const_arena)? {
-                    Some(inner) => type_arena.fetch_or_append(crate::Type {
-                        name: debug_name.map(|s| s.to_string()),
-                        inner,
-                    }),
+                    Some(inner) => {
+                        let span = name_span.start..lexer.current_byte_offset();
+                        type_arena.fetch_or_append(
+                            crate::Type {
+                                name: debug_name.map(|s| s.to_string()),
+                                inner,
+                            },
+                            span.into(),
+                        )
+                    }
                     None => return Err(Error::UnknownType(name_span)),
                 }
             }
@@ -2435,11 +2850,11 @@ impl Parser {
         type_arena: &mut Arena<crate::Type>,
         const_arena: &mut Arena<crate::Constant>,
     ) -> Result<(Handle<crate::Type>, crate::StorageAccess), Error<'a>> {
-        self.scopes.push(Scope::TypeDecl);
+        self.push_scope(Scope::TypeDecl, lexer);
         let mut attribute = TypeAttributes::default();
 
         if lexer.skip(Token::DoubleParen('[')) {
-            self.scopes.push(Scope::Attribute);
+            self.push_scope(Scope::Attribute, lexer);
             loop {
                 match lexer.next() {
                     (Token::Word("access"), _) => {
@@ -2464,7 +2879,7 @@ impl Parser {
                     other => return Err(Error::Unexpected(other, ExpectedToken::TypeAttribute)),
                 }
             }
-            self.scopes.pop();
+            self.pop_scope(lexer);
         }
 
         let storage_access = attribute.access;
@@ -2478,7 +2893,7 @@ impl Parser {
             type_arena,
             const_arena,
         )?;
-        self.scopes.pop();
+        self.pop_scope(lexer);
         Ok((handle, storage_access))
     }
 
@@ -2501,10 +2916,21 @@ impl Parser {
                     value,
                 }
             }
+            None if conv::map_atomic_fun(ident).is_some()
+                || ident == "atomicCompareExchangeWeak" =>
+            {
+                // the atomic statement is pushed by the call parser,
+                // here we only ignore its result
+                let _ = self.parse_function_call_inner(lexer, ident, context.reborrow())?;
+                context
+                    .block
+                    .extend(context.emitter.finish(context.expressions));
+                return Ok(());
+            }
             None => {
                 let (function, arguments) = self
                     .parse_local_function_call(lexer, ident, context.reborrow())?
-                    .ok_or(Error::UnknownLocalFunction(ident_span))?;
+                    .ok_or_else(|| Error::UnknownLocalFunction(ident_span.clone()))?;
                 crate::Statement::Call {
                     function,
                     arguments,
@@ -2512,10 +2938,11 @@ impl Parser {
                 }
             }
         };
+        let span = ident_span.start..lexer.current_byte_offset();
         context
             .block
             .extend(context.emitter.finish(context.expressions));
-        context.block.push(stmt);
+        context.block.push(stmt, span.into());
         Ok(())
     }
 
@@ -2526,11 +2953,14 @@ impl Parser {
         block: &'out mut crate::Block,
         is_uniform_control_flow: bool,
     ) -> Result<(), Error<'a>> {
+        self.push_scope(Scope::Statement, lexer);
         let (word, word_span) = match lexer.next() {
-            (Token::Separator(';'), _) => return Ok(()),
+            (Token::Separator(';'), _) => {
+                self.pop_scope(lexer);
+                return Ok(());
+            }
             (Token::Paren('{'), _) => {
-                self.scopes.push(Scope::Block);
-                let mut statements = Vec::new();
+                let mut statements = crate::Block::new();
                 while !lexer.skip(Token::Paren('}')) {
                     self.parse_statement(
                         lexer,
@@ -2539,15 +2969,14 @@ impl Parser {
                         is_uniform_control_flow,
                     )?;
                 }
-                self.scopes.pop();
-                block.push(crate::Statement::Block(statements));
+                let span = self.pop_scope(lexer);
+                block.push(crate::Statement::Block(statements), span.into());
                 return Ok(());
             }
             (Token::Word(word), span) => (word, span),
             other => return Err(Error::Unexpected(other, ExpectedToken::Statement)),
         };
 
-        self.scopes.push(Scope::Statement);
         let mut emitter = super::Emitter::default();
         match word {
             "let" => {
@@ -2634,9 +3063,10 @@ impl Parser {
                             // register the type, if needed
                             match context.typifier[value].clone() {
                                 TypeResolution::Handle(ty) => ty,
-                                TypeResolution::Value(inner) => context
-                                    .types
-                                    .fetch_or_append(crate::Type { name: None, inner }),
+                                TypeResolution::Value(inner) => context.types.fetch_or_append(
+                                    crate::Type { name: None, inner },
+                                    Default::default(),
+                                ),
                             }
                         }
                     };
@@ -2659,25 +3089,31 @@ impl Parser {
                 };
 
                 lexer.expect(Token::Separator(';'))?;
-                let var_id = context.variables.append(crate::LocalVariable {
-                    name: Some(name.to_owned()),
-                    ty,
-                    init: match init {
-                        Init::Constant(value) => Some(value),
-                        _ => None,
+                let var_id = context.variables.append(
+                    crate::LocalVariable {
+                        name: Some(name.to_owned()),
+                        ty,
+                        init: match init {
+                            Init::Constant(value) => Some(value),
+                            _ => None,
+                        },
                     },
-                });
+                    self.peek_scope(lexer).into(),
+                );
 
                 let expr_id = context
                     .expressions
-                    .append(crate::Expression::LocalVariable(var_id));
+                    .append(crate::Expression::LocalVariable(var_id), name_span.into());
                 context.lookup_ident.insert(name, expr_id);
 
                 if let Init::Variable(value) = init {
-                    block.push(crate::Statement::Store {
-                        pointer: expr_id,
-                        value,
-                    });
+                    block.push(
+                        crate::Statement::Store {
+                            pointer: expr_id,
+                            value,
+                        },
+                        self.peek_scope(lexer).into(),
+                    );
                 }
             }
             "return" => {
@@ -2693,7 +3129,10 @@ impl Parser {
                     None
                 };
                 lexer.expect(Token::Separator(';'))?;
-                block.push(crate::Statement::Return { value });
+                block.push(
+                    crate::Statement::Return { value },
+                    self.peek_scope(lexer).into(),
+                );
             }
             "if" => {
                 emitter.start(context.expressions);
@@ -2721,26 +3160,32 @@ impl Parser {
                 let mut reject = if lexer.skip(Token::Word("else")) {
                     self.parse_block(lexer, context.reborrow(), false)?
                 } else {
-                    Vec::new()
+                    crate::Block::new()
                 };
                 // reverse-fold the else-if blocks
                 //Note: we may consider uplifting this to the IR
                 for (other_cond, other_emit, other_block) in elsif_stack.drain(..).rev() {
-                    reject = other_emit
-                        .into_iter()
-                        .chain(iter::once(crate::Statement::If {
+                    let mut sub_block = crate::Block::new();
+                    sub_block.extend(other_emit);
+                    sub_block.push(
+                        crate::Statement::If {
                             condition: other_cond,
                             accept: other_block,
                             reject,
-                        }))
-                        .collect();
+                        },
+                        self.peek_scope(lexer).into(),
+                    );
+                    reject = sub_block;
                 }
 
-                block.push(crate::Statement::If {
-                    condition,
-                    accept,
-                    reject,
-                });
+                block.push(
+                    crate::Statement::If {
+                        condition,
+                        accept,
+                        reject,
+                    },
+                    self.peek_scope(lexer).into(),
+                );
             }
             "switch" => {
                 emitter.start(context.expressions);
@@ -2751,7 +3196,7 @@ impl Parser {
                 block.extend(emitter.finish(context.expressions));
                 lexer.expect(Token::Paren('{'))?;
                 let mut cases = Vec::new();
-                let mut default = Vec::new();
+                let mut default = crate::Block::new();
 
                 loop {
                     // cases + default
@@ -2770,12 +3215,12 @@ impl Parser {
                                 }
                                 cases.push(crate::SwitchCase {
                                     value,
-                                    body: Vec::new(),
+                                    body: crate::Block::new(),
                                     fall_through: true,
                                 });
                             };
 
-                            let mut body = Vec::new();
+                            let mut body = crate::Block::new();
                             lexer.expect(Token::Paren('{'))?;
                             let fall_through = loop {
                                 // default statements
@@ -2805,15 +3250,18 @@ impl Parser {
                     }
                 }
 
-                block.push(crate::Statement::Switch {
-                    selector,
-                    cases,
-                    default,
-                });
+                block.push(
+                    crate::Statement::Switch {
+                        selector,
+                        cases,
+                        default,
+                    },
+                    self.peek_scope(lexer).into(),
+                );
             }
             "loop" => {
-                let mut body = Vec::new();
-                let mut continuing = Vec::new();
+                let mut body = crate::Block::new();
+                let mut continuing = crate::Block::new();
                 lexer.expect(Token::Paren('{'))?;
 
                 loop {
@@ -2828,7 +3276,10 @@ impl Parser {
                     self.parse_statement(lexer, context.reborrow(), &mut body, false)?;
                 }
 
-                block.push(crate::Statement::Loop { body, continuing });
+                block.push(
+                    crate::Statement::Loop { body, continuing },
+                    self.peek_scope(lexer).into(),
+                );
             }
             "for" => {
                 lexer.expect(Token::Paren('('))?;
@@ -2851,7 +3302,7 @@ impl Parser {
                     }
                 };
 
-                let mut body = Vec::new();
+                let mut body = crate::Block::new();
                 if !lexer.skip(Token::Separator(';')) {
                     emitter.start(context.expressions);
                     let condition = self.parse_general_expression(
@@ -2860,14 +3311,17 @@ impl Parser {
                     )?;
                     lexer.expect(Token::Separator(';'))?;
                     body.extend(emitter.finish(context.expressions));
-                    body.push(crate::Statement::If {
-                        condition,
-                        accept: Vec::new(),
-                        reject: vec![crate::Statement::Break],
-                    });
+                    body.push(
+                        crate::Statement::If {
+                            condition,
+                            accept: crate::Block::new(),
+                            reject: crate::Block::from_vec(vec![crate::Statement::Break]),
+                        },
+                        self.peek_scope(lexer).into(),
+                    );
                 };
 
-                let mut continuing = Vec::new();
+                let mut continuing = crate::Block::new();
                 if let Token::Word(ident) = lexer.peek().0 {
                     // manually parse the next statement here instead of calling parse_statement
                     // because the statement is not terminated with a semicolon
@@ -2886,28 +3340,35 @@ impl Parser {
                     self.parse_statement(lexer, context.reborrow(), &mut body, false)?;
                 }
 
-                block.push(crate::Statement::Loop { body, continuing });
+                block.push(
+                    crate::Statement::Loop { body, continuing },
+                    self.peek_scope(lexer).into(),
+                );
             }
-            "break" => block.push(crate::Statement::Break),
-            "continue" => block.push(crate::Statement::Continue),
-            "discard" => block.push(crate::Statement::Kill),
+            "break" => block.push(crate::Statement::Break, self.peek_scope(lexer).into()),
+            "continue" => block.push(crate::Statement::Continue, self.peek_scope(lexer).into()),
+            "discard" => block.push(crate::Statement::Kill, self.peek_scope(lexer).into()),
             "storageBarrier" => {
                 lexer.expect(Token::Paren('('))?;
                 lexer.expect(Token::Paren(')'))?;
-                block.push(crate::Statement::Barrier(crate::Barrier::STORAGE));
+                block.push(
+                    crate::Statement::Barrier(crate::Barrier::STORAGE),
+                    self.peek_scope(lexer).into(),
+                );
             }
             "workgroupBarrier" => {
                 lexer.expect(Token::Paren('('))?;
                 lexer.expect(Token::Paren(')'))?;
-                block.push(crate::Statement::Barrier(crate::Barrier::WORK_GROUP));
+                block.push(
+                    crate::Statement::Barrier(crate::Barrier::WORK_GROUP),
+                    self.peek_scope(lexer).into(),
+                );
             }
             "textureStore" => {
                 emitter.start(context.expressions);
                 lexer.open_arguments()?;
-                let (image_name, image_span) = lexer.next_ident_with_span()?;
-                let image = context
-                    .lookup_ident
-                    .lookup(image_name, image_span.clone())?;
+                let (image, image_span) =
+                    self.parse_resource(lexer, context.as_expression(block, &mut emitter))?;
                 lexer.expect(Token::Separator(','))?;
                 let mut expr_context = context.as_expression(block, &mut emitter);
                 let arrayed = match *expr_context.resolve_type(image)? {
@@ -2929,12 +3390,30 @@ impl Parser {
                     .parse_general_expression(lexer, context.as_expression(block, &mut emitter))?;
                 lexer.close_arguments()?;
                 block.extend(emitter.finish(context.expressions));
-                block.push(crate::Statement::ImageStore {
-                    image,
-                    coordinate,
-                    array_index,
-                    value,
-                });
+                block.push(
+                    crate::Statement::ImageStore {
+                        image,
+                        coordinate,
+                        array_index,
+                        value,
+                    },
+                    self.peek_scope(lexer).into(),
+                );
+            }
+            "atomicStore" => {
+                emitter.start(context.expressions);
+                lexer.open_arguments()?;
+                let mut expr_context = context.as_expression(block, &mut emitter);
+                let (pointer, kind, width) =
+                    self.parse_atomic_pointer(lexer, expr_context.reborrow())?;
+                lexer.expect(Token::Separator(','))?;
+                let value = self.parse_atomic_operand(lexer, kind, width, expr_context)?;
+                lexer.close_arguments()?;
+                block.extend(emitter.finish(context.expressions));
+                block.push(
+                    crate::Statement::Store { pointer, value },
+                    self.peek_scope(lexer).into(),
+                );
             }
             // assignment or a function call
             ident => {
@@ -2947,7 +3426,7 @@ impl Parser {
                 lexer.expect(Token::Separator(';'))?;
             }
         }
-        self.scopes.pop();
+        self.pop_scope(lexer);
         Ok(())
     }
 
@@ -2956,10 +3435,10 @@ impl Parser {
         lexer: &mut Lexer<'a>,
         mut context: StatementContext<'a, '_, '_>,
         is_uniform_control_flow: bool,
-    ) -> Result<Vec<crate::Statement>, Error<'a>> {
-        self.scopes.push(Scope::Block);
+    ) -> Result<crate::Block, Error<'a>> {
+        self.push_scope(Scope::Block, lexer);
         lexer.expect(Token::Paren('{'))?;
-        let mut block = Vec::new();
+        let mut block = crate::Block::new();
         while !lexer.skip(Token::Paren('}')) {
             self.parse_statement(
                 lexer,
@@ -2968,7 +3447,7 @@ impl Parser {
                 is_uniform_control_flow,
             )?;
         }
-        self.scopes.pop();
+        self.pop_scope(lexer);
         Ok(block)
     }
 
@@ -2983,7 +3462,7 @@ impl Parser {
         }
 
         let mut bind_parser = BindingParser::default();
-        self.scopes.push(Scope::Attribute);
+        self.push_scope(Scope::Attribute, lexer);
         loop {
             let (word, span) = lexer.next_ident_with_span()?;
             bind_parser.parse(lexer, word, span)?;
@@ -2998,7 +3477,7 @@ impl Parser {
 
         let end = lexer.current_byte_offset();
 
-        self.scopes.pop();
+        self.pop_scope(lexer);
         bind_parser.finish(start..end)
     }
 
@@ -3008,14 +3487,14 @@ impl Parser {
         module: &mut crate::Module,
         lookup_global_expression: &FastHashMap<&'a str, crate::Expression>,
     ) -> Result<(crate::Function, &'a str), Error<'a>> {
-        self.scopes.push(Scope::FunctionDecl);
+        self.push_scope(Scope::FunctionDecl, lexer);
         // read function name
         let mut lookup_ident = FastHashMap::default();
         let fun_name = lexer.next_ident()?;
         // populate initial expressions
         let mut expressions = Arena::new();
         for (&name, expression) in lookup_global_expression.iter() {
-            let expr_handle = expressions.append(expression.clone());
+            let expr_handle = expressions.append(expression.clone(), Default::default());
             lookup_ident.insert(name, expr_handle);
         }
         // read parameter list
@@ -3030,11 +3509,13 @@ impl Parser {
                 ));
             }
             let binding = self.parse_varying_binding(lexer)?;
-            let (param_name, _param_name_span, param_type, _access) =
+            let (param_name, param_name_span, param_type, _access) =
                 self.parse_variable_ident_decl(lexer, &mut module.types, &mut module.constants)?;
             let param_index = arguments.len() as u32;
-            let expression_token =
-                expressions.append(crate::Expression::FunctionArgument(param_index));
+            let expression_token = expressions.append(
+                crate::Expression::FunctionArgument(param_index),
+                param_name_span.into(),
+            );
             lookup_ident.insert(param_name, expression_token);
             arguments.push(crate::FunctionArgument {
                 name: Some(param_name.to_string()),
@@ -3060,7 +3541,7 @@ impl Parser {
             local_variables: Arena::new(),
             expressions,
             named_expressions: crate::NamedExpressions::default(),
-            body: Vec::new(),
+            body: crate::Block::new(),
         };
 
         // read body
@@ -3085,7 +3566,7 @@ impl Parser {
         // fixup the IR
         ensure_block_returns(&mut fun.body);
         // done
-        self.scopes.pop();
+        self.pop_scope(lexer);
 
         // Set named expressions after block parsing ends
         fun.named_expressions = named_expressions;
@@ -3106,10 +3587,11 @@ impl Parser {
         let mut is_block = false;
         let mut workgroup_size = [0u32; 3];
         let mut early_depth_test = None;
+        let mut override_id = None;
 
         if lexer.skip(Token::DoubleParen('[')) {
             let (mut bind_index, mut bind_group) = (None, None);
-            self.scopes.push(Scope::Attribute);
+            self.push_scope(Scope::Attribute, lexer);
             loop {
                 match lexer.next_ident_with_span()? {
                     ("binding", _) => {
@@ -3163,6 +3645,11 @@ impl Parser {
                         };
                         early_depth_test = Some(crate::EarlyDepthTest { conservative });
                     }
+                    ("override", _) => {
+                        lexer.expect(Token::Paren('('))?;
+                        override_id = Some(lexer.next_uint_literal()?);
+                        lexer.expect(Token::Paren(')'))?;
+                    }
                     (_, word_span) => return Err(Error::UnknownAttribute(word_span)),
                 }
                 match lexer.next() {
@@ -3181,7 +3668,7 @@ impl Parser {
                     binding: index,
                 });
             }
-            self.scopes.pop();
+            self.pop_scope(lexer);
         }
 
         // read items
@@ -3191,14 +3678,17 @@ impl Parser {
                 let name = lexer.next_ident()?;
                 let (members, span) =
                     self.parse_struct_body(lexer, &mut module.types, &mut module.constants)?;
-                let ty = module.types.fetch_or_append(crate::Type {
-                    name: Some(name.to_string()),
-                    inner: crate::TypeInner::Struct {
-                        top_level: is_block,
-                        members,
-                        span,
+                let ty = module.types.fetch_or_append(
+                    crate::Type {
+                        name: Some(name.to_string()),
+                        inner: crate::TypeInner::Struct {
+                            top_level: is_block,
+                            members,
+                            span,
+                        },
                     },
-                });
+                    Default::default(),
+                );
                 self.lookup_type.insert(name.to_owned(), ty);
                 lexer.expect(Token::Separator(';'))?;
             }
@@ -3222,13 +3712,32 @@ impl Parser {
                 )?;
                 lexer.expect(Token::Operation('='))?;
                 let first_token_span = lexer.next();
-                let const_handle = self.parse_const_expression_impl(
+                // Overridable constants get their own entry, so they are
+                // never shared with the constant they are initialized from.
+                let mut const_handle = self.parse_const_expression_impl(
                     first_token_span,
                     lexer,
-                    Some(name),
+                    match override_id {
+                        Some(_) => None,
+                        None => Some(name),
+                    },
                     &mut module.types,
                     &mut module.constants,
                 )?;
+                if let Some(id) = override_id {
+                    let inner = module.constants[const_handle].inner.clone();
+                    if let ConstantInner::Composite { .. } = inner {
+                        return Err(Error::NonScalarOverride(name_span));
+                    }
+                    const_handle = module.constants.append(
+                        crate::Constant {
+                            name: Some(name.to_string()),
+                            specialization: Some(id),
+                            inner,
+                        },
+                        name_span.clone().into(),
+                    );
+                }
                 let con = &module.constants[const_handle];
                 let type_match = match con.inner {
                     crate::ConstantInner::Scalar { width, value } => {
@@ -3266,23 +3775,36 @@ impl Parser {
                         crate::TypeInner::Image { .. } | crate::TypeInner::Sampler { .. } => {
                             crate::StorageClass::Handle
                         }
+                        crate::TypeInner::BindingArray { base, .. } => {
+                            match module.types[base].inner {
+                                crate::TypeInner::Struct { .. } if pvar.access.is_empty() => {
+                                    crate::StorageClass::Uniform
+                                }
+                                crate::TypeInner::Struct { .. } => crate::StorageClass::Storage,
+                                _ => crate::StorageClass::Handle,
+                            }
+                        }
                         _ => crate::StorageClass::Private,
                     },
                 };
-                let var_handle = module.global_variables.append(crate::GlobalVariable {
-                    name: Some(pvar.name.to_owned()),
-                    class,
-                    binding: binding.take(),
-                    ty: pvar.ty,
-                    init: pvar.init,
-                    storage_access: pvar.access,
-                });
+                let var_handle = module.global_variables.append(
+                    crate::GlobalVariable {
+                        name: Some(pvar.name.to_owned()),
+                        class,
+                        binding: binding.take(),
+                        ty: pvar.ty,
+                        init: pvar.init,
+                        storage_access: pvar.access,
+                    },
+                    pvar.span.into(),
+                );
                 lookup_global_expression
                     .insert(pvar.name, crate::Expression::GlobalVariable(var_handle));
             }
-            (Token::Word("fn"), _) => {
+            (Token::Word("fn"), fn_span) => {
                 let (function, name) =
                     self.parse_function_decl(lexer, module, lookup_global_expression)?;
+                let span = fn_span.start..lexer.current_byte_offset();
                 match stage {
                     Some(stage) => module.entry_points.push(crate::EntryPoint {
                         name: name.to_string(),
@@ -3292,7 +3814,7 @@ impl Parser {
                         function,
                     }),
                     None => {
-                        module.functions.append(function);
+                        module.functions.append(function, span.into());
                     }
                 }
             }
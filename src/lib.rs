@@ -151,11 +151,17 @@ tree.
 
 mod arena;
 pub mod back;
+mod block;
 pub mod front;
 pub mod proc;
+mod span;
+#[cfg(any(feature = "spv-in", feature = "spv-out"))]
+mod spv_grammar;
 pub mod valid;
 
 pub use crate::arena::{Arena, Handle, Range};
+pub use crate::block::Block;
+pub use crate::span::{Span, SpanContext, WithSpan};
 
 use std::{
     collections::{HashMap, HashSet},
@@ -278,6 +284,9 @@ pub enum BuiltIn {
     LocalInvocationIndex,
     WorkGroupId,
     WorkGroupSize,
+    // subgroup
+    SubgroupSize,
+    SubgroupInvocationId,
 }
 
 /// Number of bytes per scalar.
@@ -503,6 +512,8 @@ pub enum TypeInner {
         rows: VectorSize,
         width: Bytes,
     },
+    /// Atomic scalar.
+    Atomic { kind: ScalarKind, width: Bytes },
     /// Pointer to another type.
     ///
     /// ## Pointers to non-`SIZED` types
@@ -599,6 +610,27 @@ pub enum TypeInner {
     },
     /// Can be used to sample values from images.
     Sampler { comparison: bool },
+
+    /// Array of bindings.
+    ///
+    /// Each element of a `BindingArray` draws its value from a separate
+    /// bound resource, so a single global can stand for a whole table of
+    /// textures, samplers or buffers. The `base` type must be an [`Image`],
+    /// a [`Sampler`], or a top-level [`Struct`].
+    ///
+    /// A `BindingArray` may only be the type of a [`GlobalVariable`] with a
+    /// [`ResourceBinding`], which is the binding of the first element. It is
+    /// indexed with [`Access`] or [`AccessIndex`], and the index doesn't have
+    /// to be uniform if [`Capabilities::BINDING_ARRAY_NON_UNIFORM_INDEXING`]
+    /// is enabled.
+    ///
+    /// [`Image`]: TypeInner::Image
+    /// [`Sampler`]: TypeInner::Sampler
+    /// [`Struct`]: TypeInner::Struct
+    /// [`Access`]: Expression::Access
+    /// [`AccessIndex`]: Expression::AccessIndex
+    /// [`Capabilities::BINDING_ARRAY_NON_UNIFORM_INDEXING`]: valid::Capabilities::BINDING_ARRAY_NON_UNIFORM_INDEXING
+    BindingArray { base: Handle<Type>, size: ArraySize },
 }
 
 /// Constant value.
@@ -730,6 +762,72 @@ pub enum BinaryOperator {
     ShiftRight,
 }
 
+/// Function on an atomic value.
+///
+/// Note: these do not include load/store, which use the existing
+/// [`Expression::Load`] and [`Statement::Store`].
+#[derive(Clone, Copy, Debug, Hash, Eq, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+pub enum AtomicFunction {
+    Add,
+    Subtract,
+    And,
+    ExclusiveOr,
+    InclusiveOr,
+    Min,
+    Max,
+    Exchange { compare: Option<Handle<Expression>> },
+}
+
+/// Operation combining the values of a subgroup.
+#[derive(Clone, Copy, Debug, Hash, Eq, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+pub enum SubgroupOperation {
+    All,
+    Any,
+    Add,
+    Mul,
+    Min,
+    Max,
+    And,
+    Or,
+    Xor,
+}
+
+/// Which invocations of a subgroup a collective operation combines.
+#[derive(Clone, Copy, Debug, Hash, Eq, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+pub enum CollectiveOperation {
+    /// All the active invocations.
+    Reduce,
+    /// The invocations with a lower or equal index.
+    InclusiveScan,
+    /// The invocations with a lower index.
+    ExclusiveScan,
+}
+
+/// Invocation of a subgroup to read a value from.
+#[derive(Clone, Copy, Debug, Hash, Eq, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+pub enum GatherMode {
+    /// The active invocation with the lowest index.
+    BroadcastFirst,
+    /// The given index, uniform across the subgroup.
+    Broadcast(Handle<Expression>),
+    /// The given index.
+    Shuffle(Handle<Expression>),
+    /// The current index plus the given delta.
+    ShuffleDown(Handle<Expression>),
+    /// The current index minus the given delta.
+    ShuffleUp(Handle<Expression>),
+    /// The current index XOR the given mask.
+    ShuffleXor(Handle<Expression>),
+}
+
 /// Axis on which to compute a derivative.
 #[derive(Clone, Copy, Debug, Hash, Eq, Ord, PartialEq, PartialOrd)]
 #[cfg_attr(feature = "serialize", derive(Serialize))]
@@ -813,6 +911,22 @@ pub enum MathFunction {
     // bits
     CountOneBits,
     ReverseBits,
+    ExtractBits,
+    InsertBits,
+    FirstTrailingBit,
+    FirstLeadingBit,
+    // data packing
+    Pack4x8snorm,
+    Pack4x8unorm,
+    Pack2x16snorm,
+    Pack2x16unorm,
+    Pack2x16float,
+    // data unpacking
+    Unpack4x8snorm,
+    Unpack4x8unorm,
+    Unpack2x16snorm,
+    Unpack2x16unorm,
+    Unpack2x16float,
 }
 
 /// Sampling modifier to control the level of detail.
@@ -1009,6 +1123,12 @@ pub enum Expression {
         offset: Option<Handle<Constant>>,
         level: SampleLevel,
         depth_ref: Option<Handle<Expression>>,
+        /// If `Some`, this is a gather operation: it returns the selected
+        /// component of the four texels that would be used for bilinear
+        /// filtering, rather than a filtered texel.
+        ///
+        /// Depth images can only gather the `X` component.
+        gather: Option<SwizzleComponent>,
     },
     /// Load a texel from an image.
     ImageLoad {
@@ -1121,6 +1241,7 @@ pub enum Expression {
         arg: Handle<Expression>,
         arg1: Option<Handle<Expression>>,
         arg2: Option<Handle<Expression>>,
+        arg3: Option<Handle<Expression>>,
     },
     /// Cast a simple type to another kind.
     As {
@@ -1140,11 +1261,25 @@ pub enum Expression {
     /// This doesn't match the semantics of spirv's `OpArrayLength`, which must be passed
     /// a pointer to a structure containing a runtime array in its' last field.
     ArrayLength(Handle<Expression>),
+    /// Result of an atomic operation.
+    ///
+    /// The corresponding [`Statement::Atomic`] has to refer to this expression.
+    /// For a compare-exchange, the result is a two-component vector of the
+    /// atomic's scalar type: the original value, and whether it was exchanged
+    /// (as zero or one).
+    AtomicResult {
+        kind: ScalarKind,
+        width: Bytes,
+        comparison: bool,
+    },
+    /// Result of a [`Statement::SubgroupBallot`]: a `vec4<u32>` bitmask of
+    /// the invocations where the predicate holds.
+    SubgroupBallotResult,
+    /// Result of a [`Statement::SubgroupCollectiveOperation`] or a
+    /// [`Statement::SubgroupGather`].
+    SubgroupOperationResult { ty: Handle<Type> },
 }
 
-/// A code block is just a vector of statements.
-pub type Block = Vec<Statement>;
-
 /// A case for a switch statement.
 // Clone is used only for error reporting and is not intended for end users
 #[derive(Clone, Debug)]
@@ -1287,6 +1422,50 @@ pub enum Statement {
         arguments: Vec<Handle<Expression>>,
         result: Option<Handle<Expression>>,
     },
+    /// Atomic function.
+    ///
+    /// The `pointer` has to refer to an [`Atomic`] value. The `result` has to
+    /// be an [`Expression::AtomicResult`], and this statement serves as a
+    /// barrier for any operations on that expression.
+    ///
+    /// [`Atomic`]: TypeInner::Atomic
+    Atomic {
+        /// Pointer to an atomic value.
+        pointer: Handle<Expression>,
+        /// Function to run on the atomic.
+        fun: AtomicFunction,
+        /// Value to use in the function.
+        value: Handle<Expression>,
+        /// Emitted expression as a result.
+        result: Handle<Expression>,
+    },
+    /// Bitmask of the active invocations of the subgroup where `predicate`
+    /// holds, or of all the active invocations if there is no predicate.
+    ///
+    /// The `result` has to be an [`Expression::SubgroupBallotResult`].
+    SubgroupBallot {
+        result: Handle<Expression>,
+        predicate: Option<Handle<Expression>>,
+    },
+    /// Combine the `argument` values of the subgroup with `op`.
+    ///
+    /// The `result` has to be an [`Expression::SubgroupOperationResult`]
+    /// of the same type as the argument.
+    SubgroupCollectiveOperation {
+        op: SubgroupOperation,
+        collective_op: CollectiveOperation,
+        argument: Handle<Expression>,
+        result: Handle<Expression>,
+    },
+    /// Read the `argument` value of another invocation of the subgroup.
+    ///
+    /// The `result` has to be an [`Expression::SubgroupOperationResult`]
+    /// of the same type as the argument.
+    SubgroupGather {
+        mode: GatherMode,
+        argument: Handle<Expression>,
+        result: Handle<Expression>,
+    },
 }
 
 /// A function argument.
@@ -0,0 +1,750 @@
+//! Removal of the unused parts of a module.
+
+use crate::{
+    arena::{Arena, Handle, Range},
+    ArraySize, AtomicFunction, Block, Constant, ConstantInner, EntryPoint, Expression, Function,
+    GatherMode, GlobalVariable, ImageQuery, Module, SampleLevel, Statement, Type, TypeInner,
+};
+use bit_set::BitSet;
+
+/// Remove the entry points rejected by `keep_entry_point`, and then all the
+/// functions, global variables, constants and types that can't be reached
+/// from the remaining ones.
+///
+/// Expressions of the remaining functions that refer to removed items
+/// without being used, like the global variables some frontends declare in
+/// every function, are removed as well.
+///
+/// The arenas are compacted, and all the handles in the module are updated
+/// to match. Any [`ModuleInfo`] computed for the module before is stale
+/// afterwards, so the module has to go through the [`Validator`] again.
+///
+/// [`ModuleInfo`]: crate::valid::ModuleInfo
+/// [`Validator`]: crate::valid::Validator
+pub fn compact<F>(module: &mut Module, mut keep_entry_point: F)
+where
+    F: FnMut(&EntryPoint) -> bool,
+{
+    module.entry_points.retain(|ep| keep_entry_point(ep));
+
+    let mut tracer = Tracer::new(module);
+    let entry_point_expressions = module
+        .entry_points
+        .iter_mut()
+        .map(|ep| tracer.trace_function(&mut ep.function))
+        .collect::<Vec<_>>();
+    let mut function_expressions = vec![None; module.functions.len()];
+    while let Some(handle) = tracer.function_queue.pop() {
+        function_expressions[handle.index()] =
+            Some(tracer.trace_function(module.functions.get_mut(handle)));
+    }
+    for (handle, var) in module.global_variables.iter() {
+        if tracer.globals.contains(handle.index()) {
+            tracer.trace_type(var.ty);
+            if let Some(init) = var.init {
+                tracer.trace_constant(init);
+            }
+        }
+    }
+    tracer.trace_types_and_constants(module);
+
+    let maps = ModuleMap {
+        types: compact_arena(&mut module.types, &tracer.types),
+        constants: compact_arena(&mut module.constants, &tracer.constants),
+        globals: compact_arena(&mut module.global_variables, &tracer.globals),
+        functions: compact_arena(&mut module.functions, &tracer.functions),
+    };
+
+    for (_, ty) in module.types.iter_mut() {
+        maps.adjust_type(&mut ty.inner);
+    }
+    for (_, constant) in module.constants.iter_mut() {
+        if let ConstantInner::Composite {
+            ref mut ty,
+            ref mut components,
+        } = constant.inner
+        {
+            maps.types.adjust(ty);
+            for component in components.iter_mut() {
+                maps.constants.adjust(component);
+            }
+        }
+    }
+    for (_, var) in module.global_variables.iter_mut() {
+        maps.types.adjust(&mut var.ty);
+        if let Some(ref mut init) = var.init {
+            maps.constants.adjust(init);
+        }
+    }
+    // The kept functions are still in the same order.
+    for ((_, function), live) in module
+        .functions
+        .iter_mut()
+        .zip(function_expressions.iter().flatten())
+    {
+        maps.adjust_function(function, live);
+    }
+    for (ep, live) in module
+        .entry_points
+        .iter_mut()
+        .zip(entry_point_expressions.iter())
+    {
+        maps.adjust_function(&mut ep.function, live);
+    }
+}
+
+/// Call `f` on every expression handle that `expression` refers to.
+pub(super) fn for_each_operand(
+    expression: &mut Expression,
+    mut f: impl FnMut(&mut Handle<Expression>),
+) {
+    match *expression {
+        Expression::Access {
+            ref mut base,
+            ref mut index,
+        } => {
+            f(base);
+            f(index);
+        }
+        Expression::AccessIndex { ref mut base, .. } => f(base),
+        Expression::Splat { ref mut value, .. } => f(value),
+        Expression::Swizzle { ref mut vector, .. } => f(vector),
+        Expression::Compose {
+            ref mut components, ..
+        } => components.iter_mut().for_each(f),
+        Expression::Load { ref mut pointer } => f(pointer),
+        Expression::ImageSample {
+            ref mut image,
+            ref mut sampler,
+            ref mut coordinate,
+            ref mut array_index,
+            ref mut level,
+            ref mut depth_ref,
+            ..
+        } => {
+            f(image);
+            f(sampler);
+            f(coordinate);
+            array_index.iter_mut().for_each(&mut f);
+            match *level {
+                SampleLevel::Auto | SampleLevel::Zero => {}
+                SampleLevel::Exact(ref mut expr) | SampleLevel::Bias(ref mut expr) => f(expr),
+                SampleLevel::Gradient {
+                    ref mut x,
+                    ref mut y,
+                } => {
+                    f(x);
+                    f(y);
+                }
+            }
+            depth_ref.iter_mut().for_each(f);
+        }
+        Expression::ImageLoad {
+            ref mut image,
+            ref mut coordinate,
+            ref mut array_index,
+            ref mut index,
+        } => {
+            f(image);
+            f(coordinate);
+            array_index.iter_mut().for_each(&mut f);
+            index.iter_mut().for_each(f);
+        }
+        Expression::ImageQuery {
+            ref mut image,
+            ref mut query,
+        } => {
+            f(image);
+            if let ImageQuery::Size {
+                level: Some(ref mut level),
+            } = *query
+            {
+                f(level);
+            }
+        }
+        Expression::Unary { ref mut expr, .. }
+        | Expression::Derivative { ref mut expr, .. }
+        | Expression::As { ref mut expr, .. }
+        | Expression::ArrayLength(ref mut expr) => f(expr),
+        Expression::Binary {
+            ref mut left,
+            ref mut right,
+            ..
+        } => {
+            f(left);
+            f(right);
+        }
+        Expression::Select {
+            ref mut condition,
+            ref mut accept,
+            ref mut reject,
+        } => {
+            f(condition);
+            f(accept);
+            f(reject);
+        }
+        Expression::Relational {
+            ref mut argument, ..
+        } => f(argument),
+        Expression::Math {
+            ref mut arg,
+            ref mut arg1,
+            ref mut arg2,
+            ref mut arg3,
+            ..
+        } => {
+            f(arg);
+            arg1.iter_mut().for_each(&mut f);
+            arg2.iter_mut().for_each(&mut f);
+            arg3.iter_mut().for_each(f);
+        }
+        Expression::Constant(_)
+        | Expression::FunctionArgument(_)
+        | Expression::GlobalVariable(_)
+        | Expression::LocalVariable(_)
+        | Expression::Call(_)
+        | Expression::AtomicResult { .. }
+        | Expression::SubgroupBallotResult
+        | Expression::SubgroupOperationResult { .. } => {}
+    }
+}
+
+/// Call `f` on every expression handle that `statement` refers to,
+/// except for the ones in nested blocks and `Emit` ranges.
+pub(super) fn for_each_statement_operand(
+    statement: &mut Statement,
+    mut f: impl FnMut(&mut Handle<Expression>),
+) {
+    match *statement {
+        Statement::If {
+            ref mut condition, ..
+        } => f(condition),
+        Statement::Switch {
+            ref mut selector, ..
+        } => f(selector),
+        Statement::Return { ref mut value } => value.iter_mut().for_each(f),
+        Statement::Store {
+            ref mut pointer,
+            ref mut value,
+        } => {
+            f(pointer);
+            f(value);
+        }
+        Statement::ImageStore {
+            ref mut image,
+            ref mut coordinate,
+            ref mut array_index,
+            ref mut value,
+        } => {
+            f(image);
+            f(coordinate);
+            array_index.iter_mut().for_each(&mut f);
+            f(value);
+        }
+        Statement::Call {
+            ref mut arguments,
+            ref mut result,
+            ..
+        } => {
+            arguments.iter_mut().for_each(&mut f);
+            result.iter_mut().for_each(f);
+        }
+        Statement::Atomic {
+            ref mut pointer,
+            ref mut fun,
+            ref mut value,
+            ref mut result,
+        } => {
+            f(pointer);
+            if let AtomicFunction::Exchange {
+                compare: Some(ref mut compare),
+            } = *fun
+            {
+                f(compare);
+            }
+            f(value);
+            f(result);
+        }
+        Statement::SubgroupBallot {
+            ref mut result,
+            ref mut predicate,
+        } => {
+            predicate.iter_mut().for_each(&mut f);
+            f(result);
+        }
+        Statement::SubgroupCollectiveOperation {
+            ref mut argument,
+            ref mut result,
+            ..
+        } => {
+            f(argument);
+            f(result);
+        }
+        Statement::SubgroupGather {
+            ref mut mode,
+            ref mut argument,
+            ref mut result,
+        } => {
+            match *mode {
+                GatherMode::BroadcastFirst => {}
+                GatherMode::Broadcast(ref mut index)
+                | GatherMode::Shuffle(ref mut index)
+                | GatherMode::ShuffleDown(ref mut index)
+                | GatherMode::ShuffleUp(ref mut index)
+                | GatherMode::ShuffleXor(ref mut index) => f(index),
+            }
+            f(argument);
+            f(result);
+        }
+        Statement::Emit(_)
+        | Statement::Block(_)
+        | Statement::Loop { .. }
+        | Statement::Break
+        | Statement::Continue
+        | Statement::Kill
+        | Statement::Barrier(_) => {}
+    }
+}
+
+/// Call `f` on every block nested in `statement`.
+pub(super) fn for_each_child_block(statement: &mut Statement, mut f: impl FnMut(&mut Block)) {
+    match *statement {
+        Statement::Block(ref mut block) => f(block),
+        Statement::If {
+            ref mut accept,
+            ref mut reject,
+            ..
+        } => {
+            f(accept);
+            f(reject);
+        }
+        Statement::Switch {
+            ref mut cases,
+            ref mut default,
+            ..
+        } => {
+            for case in cases.iter_mut() {
+                f(&mut case.body);
+            }
+            f(default);
+        }
+        Statement::Loop {
+            ref mut body,
+            ref mut continuing,
+        } => {
+            f(body);
+            f(continuing);
+        }
+        _ => {}
+    }
+}
+
+/// The set of handles reachable from the kept entry points.
+struct Tracer {
+    types: BitSet,
+    constants: BitSet,
+    globals: BitSet,
+    functions: BitSet,
+    function_queue: Vec<Handle<Function>>,
+}
+
+impl Tracer {
+    fn new(module: &Module) -> Self {
+        Tracer {
+            types: BitSet::with_capacity(module.types.len()),
+            constants: BitSet::with_capacity(module.constants.len()),
+            globals: BitSet::with_capacity(module.global_variables.len()),
+            functions: BitSet::with_capacity(module.functions.len()),
+            function_queue: Vec::new(),
+        }
+    }
+
+    fn trace_type(&mut self, handle: Handle<Type>) {
+        self.types.insert(handle.index());
+    }
+
+    fn trace_constant(&mut self, handle: Handle<Constant>) {
+        self.constants.insert(handle.index());
+    }
+
+    fn trace_call(&mut self, handle: Handle<Function>) {
+        if self.functions.insert(handle.index()) {
+            self.function_queue.push(handle);
+        }
+    }
+
+    /// Trace the items used by `function`, and return the set of its live expressions.
+    ///
+    /// Expressions are live if they are evaluated, named, or used by a
+    /// statement or another live expression. This only leaves out the
+    /// unused ones that don't need to be emitted.
+    ///
+    /// The function is only borrowed mutably to share the operand visitors
+    /// with the remapping; it is left unchanged.
+    fn trace_function(&mut self, function: &mut Function) -> BitSet {
+        for argument in function.arguments.iter() {
+            self.trace_type(argument.ty);
+        }
+        if let Some(ref result) = function.result {
+            self.trace_type(result.ty);
+        }
+        for (_, local) in function.local_variables.iter() {
+            self.trace_type(local.ty);
+            if let Some(init) = local.init {
+                self.trace_constant(init);
+            }
+        }
+
+        let mut live = BitSet::with_capacity(function.expressions.len());
+        let mut queue = Vec::new();
+        for (handle, expression) in function.expressions.iter() {
+            if !expression.needs_pre_emit() || function.named_expressions.contains_key(&handle) {
+                queue.push(handle);
+            }
+        }
+        self.trace_block(&mut function.body, &mut queue);
+
+        while let Some(handle) = queue.pop() {
+            if !live.insert(handle.index()) {
+                continue;
+            }
+            let expression = function.expressions.get_mut(handle);
+            match *expression {
+                Expression::Constant(constant) => self.trace_constant(constant),
+                Expression::Compose { ty, .. } | Expression::SubgroupOperationResult { ty } => {
+                    self.trace_type(ty)
+                }
+                Expression::GlobalVariable(var) => {
+                    self.globals.insert(var.index());
+                }
+                Expression::ImageSample {
+                    offset: Some(offset),
+                    ..
+                } => self.trace_constant(offset),
+                Expression::Call(function) => self.trace_call(function),
+                _ => {}
+            }
+            for_each_operand(expression, |&mut operand| queue.push(operand));
+        }
+        live
+    }
+
+    fn trace_block(&mut self, block: &mut Block, queue: &mut Vec<Handle<Expression>>) {
+        for statement in block.iter_mut() {
+            if let Statement::Call { function, .. } = *statement {
+                self.trace_call(function);
+            }
+            for_each_statement_operand(statement, |&mut operand| queue.push(operand));
+            for_each_child_block(statement, |block| self.trace_block(block, queue));
+        }
+    }
+
+    /// Close the sets of types and constants over their mutual references.
+    fn trace_types_and_constants(&mut self, module: &Module) {
+        let mut type_queue = module
+            .types
+            .iter()
+            .map(|(handle, _)| handle)
+            .filter(|handle| self.types.contains(handle.index()))
+            .collect::<Vec<_>>();
+        let mut constant_queue = module
+            .constants
+            .iter()
+            .map(|(handle, _)| handle)
+            .filter(|handle| self.constants.contains(handle.index()))
+            .collect::<Vec<_>>();
+
+        while !type_queue.is_empty() || !constant_queue.is_empty() {
+            while let Some(handle) = type_queue.pop() {
+                let mut visit_type = |handle: Handle<Type>| {
+                    if self.types.insert(handle.index()) {
+                        type_queue.push(handle);
+                    }
+                };
+                match module.types[handle].inner {
+                    TypeInner::Pointer { base, .. } => visit_type(base),
+                    TypeInner::Array { base, size, .. }
+                    | TypeInner::BindingArray { base, size } => {
+                        visit_type(base);
+                        if let ArraySize::Constant(constant) = size {
+                            if self.constants.insert(constant.index()) {
+                                constant_queue.push(constant);
+                            }
+                        }
+                    }
+                    TypeInner::Struct { ref members, .. } => {
+                        for member in members.iter() {
+                            visit_type(member.ty);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            while let Some(handle) = constant_queue.pop() {
+                if let ConstantInner::Composite { ty, ref components } =
+                    module.constants[handle].inner
+                {
+                    if self.types.insert(ty.index()) {
+                        type_queue.push(ty);
+                    }
+                    for &component in components.iter() {
+                        if self.constants.insert(component.index()) {
+                            constant_queue.push(component);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The new handles of the items kept in an arena, indexed by the old ones.
+struct HandleMap<T> {
+    new_handles: Vec<Option<Handle<T>>>,
+}
+
+impl<T> HandleMap<T> {
+    fn adjust(&self, handle: &mut Handle<T>) {
+        *handle = self.new_handles[handle.index()].unwrap();
+    }
+
+    fn adjust_range(&self, range: &mut Range<T>) {
+        let mut handles = range.clone();
+        if let Some(first) = handles.next() {
+            let last = handles.last().unwrap_or(first);
+            *range = Range::new_from_bounds(
+                self.new_handles[first.index()].unwrap(),
+                self.new_handles[last.index()].unwrap(),
+            );
+        }
+    }
+}
+
+/// Keep only the items of `arena` whose indices are in `live`, in the same order.
+fn compact_arena<T>(arena: &mut Arena<T>, live: &BitSet) -> HandleMap<T> {
+    let old = std::mem::take(arena);
+    let spans = old
+        .iter()
+        .map(|(handle, _)| old.get_span(handle))
+        .collect::<Vec<_>>();
+    let new_handles = old
+        .into_inner()
+        .into_iter()
+        .zip(spans)
+        .enumerate()
+        .map(|(index, (value, span))| {
+            if live.contains(index) {
+                Some(arena.append(value, span))
+            } else {
+                None
+            }
+        })
+        .collect();
+    HandleMap { new_handles }
+}
+
+struct ModuleMap {
+    types: HandleMap<Type>,
+    constants: HandleMap<Constant>,
+    globals: HandleMap<GlobalVariable>,
+    functions: HandleMap<Function>,
+}
+
+impl ModuleMap {
+    fn adjust_type(&self, inner: &mut TypeInner) {
+        match *inner {
+            TypeInner::Pointer { ref mut base, .. } => self.types.adjust(base),
+            TypeInner::Array {
+                ref mut base,
+                ref mut size,
+                ..
+            }
+            | TypeInner::BindingArray {
+                ref mut base,
+                ref mut size,
+            } => {
+                self.types.adjust(base);
+                if let ArraySize::Constant(ref mut constant) = *size {
+                    self.constants.adjust(constant);
+                }
+            }
+            TypeInner::Struct {
+                ref mut members, ..
+            } => {
+                for member in members.iter_mut() {
+                    self.types.adjust(&mut member.ty);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn adjust_function(&self, function: &mut Function, live_expressions: &BitSet) {
+        for argument in function.arguments.iter_mut() {
+            self.types.adjust(&mut argument.ty);
+        }
+        if let Some(ref mut result) = function.result {
+            self.types.adjust(&mut result.ty);
+        }
+        for (_, local) in function.local_variables.iter_mut() {
+            self.types.adjust(&mut local.ty);
+            if let Some(ref mut init) = local.init {
+                self.constants.adjust(init);
+            }
+        }
+
+        let expressions = compact_arena(&mut function.expressions, live_expressions);
+        for (_, expression) in function.expressions.iter_mut() {
+            match *expression {
+                Expression::Constant(ref mut handle) => self.constants.adjust(handle),
+                Expression::Compose { ref mut ty, .. }
+                | Expression::SubgroupOperationResult { ref mut ty } => self.types.adjust(ty),
+                Expression::GlobalVariable(ref mut handle) => self.globals.adjust(handle),
+                Expression::ImageSample {
+                    offset: Some(ref mut offset),
+                    ..
+                } => self.constants.adjust(offset),
+                Expression::Call(ref mut handle) => self.functions.adjust(handle),
+                _ => {}
+            }
+            for_each_operand(expression, |operand| expressions.adjust(operand));
+        }
+        function.named_expressions = function
+            .named_expressions
+            .drain()
+            .map(|(mut handle, name)| {
+                expressions.adjust(&mut handle);
+                (handle, name)
+            })
+            .collect();
+        self.adjust_block(&mut function.body, &expressions);
+    }
+
+    fn adjust_block(&self, block: &mut Block, expressions: &HandleMap<Expression>) {
+        for statement in block.iter_mut() {
+            match *statement {
+                Statement::Emit(ref mut range) => expressions.adjust_range(range),
+                Statement::Call {
+                    ref mut function, ..
+                } => self.functions.adjust(function),
+                _ => {}
+            }
+            for_each_statement_operand(statement, |operand| expressions.adjust(operand));
+            for_each_child_block(statement, |block| self.adjust_block(block, expressions));
+        }
+    }
+}
+
+#[cfg(all(test, feature = "wgsl-in"))]
+#[test]
+fn compact_unused() {
+    let mut module = crate::front::wgsl::parse_str(
+        "
+        let unused_constant: f32 = 2.0;
+        let used_constant: i32 = 3;
+
+        struct Unused {
+            a: vec4<f32>;
+        };
+
+        var<private> unused_global: Unused;
+        var<private> used_global: array<u32, used_constant>;
+
+        fn unused_function() -> f32 {
+            return unused_constant;
+        }
+
+        fn used_function(x: u32) -> u32 {
+            return used_global[x];
+        }
+
+        [[stage(compute), workgroup_size(1)]]
+        fn kept() {
+            let x = used_function(1u);
+        }
+
+        [[stage(compute), workgroup_size(1)]]
+        fn removed() {
+            let x = unused_function();
+            unused_global.a = vec4<f32>(x);
+        }
+        ",
+    )
+    .unwrap();
+
+    compact(&mut module, |ep| ep.name == "kept");
+
+    assert_eq!(module.entry_points.len(), 1);
+    assert_eq!(
+        module
+            .functions
+            .iter()
+            .map(|(_, function)| function.name.as_deref())
+            .collect::<Vec<_>>(),
+        [Some("used_function")]
+    );
+    assert_eq!(
+        module
+            .global_variables
+            .iter()
+            .map(|(_, var)| var.name.as_deref())
+            .collect::<Vec<_>>(),
+        [Some("used_global")]
+    );
+    assert!(module
+        .constants
+        .iter()
+        .all(|(_, constant)| constant.name.as_deref() != Some("unused_constant")));
+    assert!(module
+        .types
+        .iter()
+        .all(|(_, ty)| ty.name.as_deref() != Some("Unused")));
+
+    crate::valid::Validator::new(
+        crate::valid::ValidationFlags::all(),
+        crate::valid::Capabilities::empty(),
+    )
+    .validate(&module)
+    .unwrap();
+}
+
+#[cfg(all(test, feature = "wgsl-in"))]
+#[test]
+fn compact_binding_array() {
+    let mut module = crate::front::wgsl::parse_str(
+        "
+        struct Unused {
+            a: vec4<f32>;
+        };
+
+        var<private> unused_global: Unused;
+        [[group(0), binding(0)]] var textures: binding_array<texture_2d<f32>, 4>;
+        [[group(0), binding(1)]] var color_sampler: sampler;
+
+        [[stage(fragment)]]
+        fn main() -> [[location(0)]] vec4<f32> {
+            return textureSample(textures[1], color_sampler, vec2<f32>(0.5));
+        }
+
+        [[stage(fragment)]]
+        fn removed() -> [[location(0)]] vec4<f32> {
+            return unused_global.a;
+        }
+        ",
+    )
+    .unwrap();
+
+    compact(&mut module, |ep| ep.name == "main");
+
+    assert!(module
+        .types
+        .iter()
+        .all(|(_, ty)| ty.name.as_deref() != Some("Unused")));
+
+    crate::valid::Validator::new(
+        crate::valid::ValidationFlags::all(),
+        crate::valid::Capabilities::BINDING_ARRAY,
+    )
+    .validate(&module)
+    .unwrap();
+}
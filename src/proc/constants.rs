@@ -0,0 +1,1648 @@
+//! Constant evaluation.
+//!
+//! [`ConstantSolver`] folds expressions that only depend on constants into
+//! new entries of the module's constant arena. Frontends use it to evaluate
+//! constant expressions, like array sizes, and [`fold_constants`] runs it over
+//! all functions as an optional optimization pass.
+
+use crate::{
+    arena::{Arena, Handle, Range},
+    BinaryOperator, Block, Constant, ConstantInner, Expression, MathFunction, ScalarKind,
+    ScalarValue, Statement, Type, TypeInner, UnaryOperator,
+};
+
+#[derive(Debug)]
+pub struct ConstantSolver<'a> {
+    pub types: &'a mut Arena<Type>,
+    pub expressions: &'a Arena<Expression>,
+    pub constants: &'a mut Arena<Constant>,
+}
+
+#[derive(Clone, Debug, PartialEq, thiserror::Error)]
+pub enum ConstantSolvingError {
+    #[error("Constants cannot access function arguments")]
+    FunctionArg,
+    #[error("Constants cannot access global variables")]
+    GlobalVariable,
+    #[error("Constants cannot access local variables")]
+    LocalVariable,
+    #[error("Specialization constants can't be folded before they are specialized")]
+    Specialization,
+    #[error("Cannot get the array length of a non array type")]
+    InvalidArrayLengthArg,
+    #[error("Constants cannot get the array length of a dynamically sized array")]
+    ArrayLengthDynamic,
+    #[error("Constants cannot call functions")]
+    Call,
+    #[error("Constants don't support relational functions")]
+    Relational,
+    #[error("Constants don't support derivative functions")]
+    Derivative,
+    #[error("Constants don't support select expressions")]
+    Select,
+    #[error("Constants don't support load expressions")]
+    Load,
+    #[error("Constants don't support image expressions")]
+    ImageExpression,
+    #[error("Constants don't support atomic functions")]
+    Atomic,
+    #[error("Constants don't support subgroup operations")]
+    Subgroup,
+    #[error("Cannot access the type")]
+    InvalidAccessBase,
+    #[error("Cannot access at the index")]
+    InvalidAccessIndex,
+    #[error("Cannot access with index of type")]
+    InvalidAccessIndexTy,
+    #[error("Constants don't support bitcasts")]
+    Bitcast,
+    #[error("Cannot cast type")]
+    InvalidCastArg,
+    #[error("Cannot apply the unary op to the argument")]
+    InvalidUnaryOpArg,
+    #[error("Cannot apply the binary op to the arguments")]
+    InvalidBinaryOpArgs,
+    #[error("Cannot apply math function to the arguments")]
+    InvalidMathArg,
+    #[error("Division by zero")]
+    DivisionByZero,
+    #[error("Shift amount is too large")]
+    ShiftOverflow,
+    #[error("Splat/swizzle type is not registered")]
+    DestinationTypeNotFound,
+    #[error("Not implemented: {0}")]
+    NotImplemented(String),
+}
+
+impl<'a> ConstantSolver<'a> {
+    pub fn solve(
+        &mut self,
+        expr: Handle<Expression>,
+    ) -> Result<Handle<Constant>, ConstantSolvingError> {
+        match self.expressions[expr] {
+            Expression::Constant(constant) => {
+                if self.constants[constant].specialization.is_some() {
+                    Err(ConstantSolvingError::Specialization)
+                } else {
+                    Ok(constant)
+                }
+            }
+            Expression::AccessIndex { base, index } => self.access(base, index as usize),
+            Expression::Access { base, index } => {
+                let index = self.solve(index)?;
+
+                self.access(base, self.constant_index(index)?)
+            }
+            Expression::Splat {
+                size,
+                value: splat_value,
+            } => {
+                let value_constant = self.solve(splat_value)?;
+                let ty = match self.constants[value_constant].inner {
+                    ConstantInner::Scalar { ref value, width } => {
+                        let kind = value.scalar_kind();
+                        self.register_type(TypeInner::Vector { size, kind, width })
+                    }
+                    ConstantInner::Composite { .. } => {
+                        return Err(ConstantSolvingError::DestinationTypeNotFound)
+                    }
+                };
+
+                Ok(self.register_constant(ConstantInner::Composite {
+                    ty,
+                    components: vec![value_constant; size as usize],
+                }))
+            }
+            Expression::Swizzle {
+                size,
+                vector: src_vector,
+                pattern,
+            } => {
+                let src_constant = self.solve(src_vector)?;
+                let (inner, src_components) = match self.constants[src_constant].inner {
+                    ConstantInner::Composite {
+                        ty,
+                        components: ref src_components,
+                    } => match self.types[ty].inner {
+                        TypeInner::Vector {
+                            size: _,
+                            kind,
+                            width,
+                        } => (
+                            TypeInner::Vector { size, kind, width },
+                            src_components.clone(),
+                        ),
+                        _ => return Err(ConstantSolvingError::DestinationTypeNotFound),
+                    },
+                    ConstantInner::Scalar { .. } => {
+                        return Err(ConstantSolvingError::DestinationTypeNotFound)
+                    }
+                };
+                let ty = self.register_type(inner);
+                let components = pattern[..size as usize]
+                    .iter()
+                    .map(|&sc| src_components[sc as usize])
+                    .collect();
+
+                Ok(self.register_constant(ConstantInner::Composite { ty, components }))
+            }
+            Expression::Compose { ty, ref components } => {
+                let components = components
+                    .iter()
+                    .map(|c| self.solve(*c))
+                    .collect::<Result<_, _>>()?;
+
+                Ok(self.register_constant(ConstantInner::Composite { ty, components }))
+            }
+            Expression::Unary { expr, op } => {
+                let expr_constant = self.solve(expr)?;
+
+                self.unary_op(op, expr_constant)
+            }
+            Expression::Binary { left, right, op } => {
+                let left_constant = self.solve(left)?;
+                let right_constant = self.solve(right)?;
+
+                self.binary_op(op, left_constant, right_constant)
+            }
+            Expression::Math {
+                fun,
+                arg,
+                arg1,
+                arg2,
+                arg3: _,
+            } => {
+                let arg = self.solve(arg)?;
+                let arg1 = arg1.map(|arg| self.solve(arg)).transpose()?;
+                let arg2 = arg2.map(|arg| self.solve(arg)).transpose()?;
+
+                self.math(fun, arg, arg1, arg2)
+            }
+            Expression::As {
+                convert,
+                expr,
+                kind,
+            } => {
+                let expr_constant = self.solve(expr)?;
+
+                match convert {
+                    Some(width) => self.cast(expr_constant, kind, width),
+                    None => Err(ConstantSolvingError::Bitcast),
+                }
+            }
+            Expression::ArrayLength(expr) => {
+                let array = self.solve(expr)?;
+
+                match self.constants[array].inner {
+                    ConstantInner::Scalar { .. } => {
+                        Err(ConstantSolvingError::InvalidArrayLengthArg)
+                    }
+                    ConstantInner::Composite { ty, .. } => match self.types[ty].inner {
+                        TypeInner::Array { size, .. } => match size {
+                            crate::ArraySize::Constant(constant) => Ok(constant),
+                            crate::ArraySize::Dynamic => {
+                                Err(ConstantSolvingError::ArrayLengthDynamic)
+                            }
+                        },
+                        _ => Err(ConstantSolvingError::InvalidArrayLengthArg),
+                    },
+                }
+            }
+
+            Expression::Load { .. } => Err(ConstantSolvingError::Load),
+            Expression::Select { .. } => Err(ConstantSolvingError::Select),
+            Expression::LocalVariable(_) => Err(ConstantSolvingError::LocalVariable),
+            Expression::Derivative { .. } => Err(ConstantSolvingError::Derivative),
+            Expression::Relational { .. } => Err(ConstantSolvingError::Relational),
+            Expression::Call { .. } => Err(ConstantSolvingError::Call),
+            Expression::AtomicResult { .. } => Err(ConstantSolvingError::Atomic),
+            Expression::SubgroupBallotResult | Expression::SubgroupOperationResult { .. } => {
+                Err(ConstantSolvingError::Subgroup)
+            }
+            Expression::FunctionArgument(_) => Err(ConstantSolvingError::FunctionArg),
+            Expression::GlobalVariable(_) => Err(ConstantSolvingError::GlobalVariable),
+            Expression::ImageSample { .. }
+            | Expression::ImageLoad { .. }
+            | Expression::ImageQuery { .. } => Err(ConstantSolvingError::ImageExpression),
+        }
+    }
+
+    fn register_type(&mut self, inner: TypeInner) -> Handle<Type> {
+        self.types
+            .fetch_or_append(Type { name: None, inner }, Default::default())
+    }
+
+    fn register_constant(&mut self, inner: ConstantInner) -> Handle<Constant> {
+        self.constants.fetch_or_append(
+            Constant {
+                name: None,
+                specialization: None,
+                inner,
+            },
+            Default::default(),
+        )
+    }
+
+    /// Returns the type of a composite of type `ty` once its components are replaced
+    /// with `components`.
+    ///
+    /// This differs from `ty` for vectors whose scalar kind changed, for example
+    /// after a cast or a comparison.
+    fn composite_type(
+        &mut self,
+        ty: Handle<Type>,
+        components: &[Handle<Constant>],
+    ) -> Handle<Type> {
+        let size = match self.types[ty].inner {
+            TypeInner::Vector { size, .. } => size,
+            _ => return ty,
+        };
+        match components.first().map(|&c| &self.constants[c].inner) {
+            Some(&ConstantInner::Scalar { ref value, width }) => {
+                let kind = value.scalar_kind();
+                self.register_type(TypeInner::Vector { size, kind, width })
+            }
+            _ => ty,
+        }
+    }
+
+    /// Returns the components of `constant` if it's a vector or a matrix.
+    fn vector_components(
+        &self,
+        constant: Handle<Constant>,
+    ) -> Option<(Handle<Type>, &[Handle<Constant>])> {
+        match self.constants[constant].inner {
+            ConstantInner::Composite { ty, ref components } => match self.types[ty].inner {
+                TypeInner::Vector { .. } | TypeInner::Matrix { .. } => Some((ty, components)),
+                _ => None,
+            },
+            ConstantInner::Scalar { .. } => None,
+        }
+    }
+
+    fn access(
+        &mut self,
+        base: Handle<Expression>,
+        index: usize,
+    ) -> Result<Handle<Constant>, ConstantSolvingError> {
+        let base = self.solve(base)?;
+
+        match self.constants[base].inner {
+            ConstantInner::Scalar { .. } => Err(ConstantSolvingError::InvalidAccessBase),
+            ConstantInner::Composite { ty, ref components } => {
+                match self.types[ty].inner {
+                    TypeInner::Vector { .. }
+                    | TypeInner::Matrix { .. }
+                    | TypeInner::Array { .. }
+                    | TypeInner::Struct { .. } => (),
+                    _ => return Err(ConstantSolvingError::InvalidAccessBase),
+                }
+
+                components
+                    .get(index)
+                    .copied()
+                    .ok_or(ConstantSolvingError::InvalidAccessIndex)
+            }
+        }
+    }
+
+    fn constant_index(&self, constant: Handle<Constant>) -> Result<usize, ConstantSolvingError> {
+        match self.constants[constant].inner {
+            ConstantInner::Scalar {
+                value: ScalarValue::Uint(index),
+                ..
+            } => Ok(index as usize),
+            ConstantInner::Scalar {
+                value: ScalarValue::Sint(index),
+                ..
+            } if index >= 0 => Ok(index as usize),
+            _ => Err(ConstantSolvingError::InvalidAccessIndexTy),
+        }
+    }
+
+    fn cast(
+        &mut self,
+        constant: Handle<Constant>,
+        kind: ScalarKind,
+        target_width: crate::Bytes,
+    ) -> Result<Handle<Constant>, ConstantSolvingError> {
+        fn inner_cast<A: num_traits::FromPrimitive>(
+            value: ScalarValue,
+        ) -> Result<A, ConstantSolvingError> {
+            match value {
+                ScalarValue::Sint(v) => A::from_i64(v),
+                ScalarValue::Uint(v) => A::from_u64(v),
+                ScalarValue::Float(v) => A::from_f64(v),
+                ScalarValue::Bool(v) => A::from_u64(v as u64),
+            }
+            .ok_or(ConstantSolvingError::InvalidCastArg)
+        }
+
+        let mut inner = self.constants[constant].inner.clone();
+
+        match inner {
+            ConstantInner::Scalar {
+                ref mut value,
+                ref mut width,
+            } => {
+                *width = target_width;
+                *value = match kind {
+                    ScalarKind::Sint => ScalarValue::Sint(inner_cast(*value)?),
+                    ScalarKind::Uint => ScalarValue::Uint(inner_cast(*value)?),
+                    ScalarKind::Float => ScalarValue::Float(inner_cast(*value)?),
+                    ScalarKind::Bool => ScalarValue::Bool(inner_cast::<f64>(*value)? != 0.0),
+                }
+                .wrap(target_width);
+            }
+            ConstantInner::Composite {
+                ref mut ty,
+                ref mut components,
+            } => {
+                match self.types[*ty].inner {
+                    TypeInner::Vector { .. } | TypeInner::Matrix { .. } => (),
+                    _ => return Err(ConstantSolvingError::InvalidCastArg),
+                }
+
+                for component in components.iter_mut() {
+                    *component = self.cast(*component, kind, target_width)?;
+                }
+                *ty = self.composite_type(*ty, components);
+            }
+        }
+
+        Ok(self.register_constant(inner))
+    }
+
+    fn unary_op(
+        &mut self,
+        op: UnaryOperator,
+        constant: Handle<Constant>,
+    ) -> Result<Handle<Constant>, ConstantSolvingError> {
+        let mut inner = self.constants[constant].inner.clone();
+
+        match inner {
+            ConstantInner::Scalar {
+                ref mut value,
+                width,
+            } => {
+                *value = match op {
+                    UnaryOperator::Negate => match *value {
+                        ScalarValue::Sint(v) => ScalarValue::Sint(v.wrapping_neg()),
+                        ScalarValue::Float(v) => ScalarValue::Float(-v),
+                        _ => return Err(ConstantSolvingError::InvalidUnaryOpArg),
+                    },
+                    UnaryOperator::Not => match *value {
+                        ScalarValue::Sint(v) => ScalarValue::Sint(!v),
+                        ScalarValue::Uint(v) => ScalarValue::Uint(!v),
+                        ScalarValue::Bool(v) => ScalarValue::Bool(!v),
+                        _ => return Err(ConstantSolvingError::InvalidUnaryOpArg),
+                    },
+                }
+                .wrap(width);
+            }
+            ConstantInner::Composite {
+                ty,
+                ref mut components,
+            } => {
+                match self.types[ty].inner {
+                    TypeInner::Vector { .. } | TypeInner::Matrix { .. } => (),
+                    _ => return Err(ConstantSolvingError::InvalidUnaryOpArg),
+                }
+
+                for component in components {
+                    *component = self.unary_op(op, *component)?
+                }
+            }
+        }
+
+        Ok(self.register_constant(inner))
+    }
+
+    fn binary_op(
+        &mut self,
+        op: BinaryOperator,
+        left: Handle<Constant>,
+        right: Handle<Constant>,
+    ) -> Result<Handle<Constant>, ConstantSolvingError> {
+        let left_inner = &self.constants[left].inner;
+        let right_inner = &self.constants[right].inner;
+
+        let inner = match (left_inner, right_inner) {
+            (
+                &ConstantInner::Scalar {
+                    value: left_value,
+                    width,
+                },
+                &ConstantInner::Scalar {
+                    value: right_value,
+                    width: _,
+                },
+            ) => {
+                let value = match op {
+                    BinaryOperator::Equal => ScalarValue::Bool(left_value == right_value),
+                    BinaryOperator::NotEqual => ScalarValue::Bool(left_value != right_value),
+                    BinaryOperator::Less => ScalarValue::Bool(left_value < right_value),
+                    BinaryOperator::LessEqual => ScalarValue::Bool(left_value <= right_value),
+                    BinaryOperator::Greater => ScalarValue::Bool(left_value > right_value),
+                    BinaryOperator::GreaterEqual => ScalarValue::Bool(left_value >= right_value),
+
+                    _ => match (left_value, right_value) {
+                        (ScalarValue::Sint(a), ScalarValue::Sint(b)) => {
+                            ScalarValue::Sint(match op {
+                                BinaryOperator::Add => a.wrapping_add(b),
+                                BinaryOperator::Subtract => a.wrapping_sub(b),
+                                BinaryOperator::Multiply => a.wrapping_mul(b),
+                                BinaryOperator::Divide => a
+                                    .checked_div(b)
+                                    .ok_or(ConstantSolvingError::DivisionByZero)?,
+                                BinaryOperator::Modulo => a
+                                    .checked_rem(b)
+                                    .ok_or(ConstantSolvingError::DivisionByZero)?,
+                                BinaryOperator::And => a & b,
+                                BinaryOperator::ExclusiveOr => a ^ b,
+                                BinaryOperator::InclusiveOr => a | b,
+                                _ => return Err(ConstantSolvingError::InvalidBinaryOpArgs),
+                            })
+                        }
+                        (ScalarValue::Sint(a), ScalarValue::Uint(b)) => {
+                            let shift = shift_amount(b, width)?;
+                            ScalarValue::Sint(match op {
+                                BinaryOperator::ShiftLeft => a << shift,
+                                BinaryOperator::ShiftRight => a >> shift,
+                                _ => return Err(ConstantSolvingError::InvalidBinaryOpArgs),
+                            })
+                        }
+                        (ScalarValue::Uint(a), ScalarValue::Uint(b)) => {
+                            ScalarValue::Uint(match op {
+                                BinaryOperator::Add => a.wrapping_add(b),
+                                BinaryOperator::Subtract => a.wrapping_sub(b),
+                                BinaryOperator::Multiply => a.wrapping_mul(b),
+                                BinaryOperator::Divide => a
+                                    .checked_div(b)
+                                    .ok_or(ConstantSolvingError::DivisionByZero)?,
+                                BinaryOperator::Modulo => a
+                                    .checked_rem(b)
+                                    .ok_or(ConstantSolvingError::DivisionByZero)?,
+                                BinaryOperator::And => a & b,
+                                BinaryOperator::ExclusiveOr => a ^ b,
+                                BinaryOperator::InclusiveOr => a | b,
+                                BinaryOperator::ShiftLeft => a << shift_amount(b, width)?,
+                                BinaryOperator::ShiftRight => a >> shift_amount(b, width)?,
+                                _ => return Err(ConstantSolvingError::InvalidBinaryOpArgs),
+                            })
+                        }
+                        (ScalarValue::Float(a), ScalarValue::Float(b)) => {
+                            ScalarValue::Float(match op {
+                                BinaryOperator::Add => a + b,
+                                BinaryOperator::Subtract => a - b,
+                                BinaryOperator::Multiply => a * b,
+                                BinaryOperator::Divide => a / b,
+                                BinaryOperator::Modulo => a % b,
+                                _ => return Err(ConstantSolvingError::InvalidBinaryOpArgs),
+                            })
+                        }
+                        (ScalarValue::Bool(a), ScalarValue::Bool(b)) => {
+                            ScalarValue::Bool(match op {
+                                BinaryOperator::LogicalAnd => a && b,
+                                BinaryOperator::LogicalOr => a || b,
+                                BinaryOperator::And => a & b,
+                                BinaryOperator::InclusiveOr => a | b,
+                                _ => return Err(ConstantSolvingError::InvalidBinaryOpArgs),
+                            })
+                        }
+                        _ => return Err(ConstantSolvingError::InvalidBinaryOpArgs),
+                    },
+                };
+
+                ConstantInner::Scalar {
+                    value: value.wrap(width),
+                    width,
+                }
+            }
+            (&ConstantInner::Composite { ref components, ty }, &ConstantInner::Scalar { .. }) => {
+                let mut components = components.clone();
+                for comp in components.iter_mut() {
+                    *comp = self.binary_op(op, *comp, right)?;
+                }
+                let ty = self.composite_type(ty, &components);
+                ConstantInner::Composite { ty, components }
+            }
+            (&ConstantInner::Scalar { .. }, &ConstantInner::Composite { ref components, ty }) => {
+                let mut components = components.clone();
+                for comp in components.iter_mut() {
+                    *comp = self.binary_op(op, left, *comp)?;
+                }
+                let ty = self.composite_type(ty, &components);
+                ConstantInner::Composite { ty, components }
+            }
+            (
+                &ConstantInner::Composite {
+                    components: ref left_components,
+                    ty,
+                },
+                &ConstantInner::Composite {
+                    components: ref right_components,
+                    ty: right_ty,
+                },
+            ) => {
+                // Only component-wise operations are supported, so
+                // the matrix products are left for the runtime.
+                let component_wise = match self.types[ty].inner {
+                    TypeInner::Vector { .. } => true,
+                    TypeInner::Matrix { .. } => match op {
+                        BinaryOperator::Add | BinaryOperator::Subtract => true,
+                        _ => false,
+                    },
+                    _ => false,
+                };
+                if ty != right_ty || !component_wise {
+                    return Err(ConstantSolvingError::InvalidBinaryOpArgs);
+                }
+
+                let right_components = right_components.clone();
+                let mut components = left_components.clone();
+                for (comp, &right_comp) in components.iter_mut().zip(right_components.iter()) {
+                    *comp = self.binary_op(op, *comp, right_comp)?;
+                }
+                let ty = self.composite_type(ty, &components);
+                ConstantInner::Composite { ty, components }
+            }
+        };
+
+        Ok(self.register_constant(inner))
+    }
+
+    fn math(
+        &mut self,
+        fun: MathFunction,
+        arg: Handle<Constant>,
+        arg1: Option<Handle<Constant>>,
+        arg2: Option<Handle<Constant>>,
+    ) -> Result<Handle<Constant>, ConstantSolvingError> {
+        match fun {
+            MathFunction::Dot => {
+                let arg1 = arg1.ok_or(ConstantSolvingError::InvalidMathArg)?;
+                return self.dot(arg, arg1);
+            }
+            MathFunction::Length => {
+                let dot = self.dot(arg, arg)?;
+                return self.math(MathFunction::Sqrt, dot, None, None);
+            }
+            MathFunction::Distance => {
+                let arg1 = arg1.ok_or(ConstantSolvingError::InvalidMathArg)?;
+                let difference = self.binary_op(BinaryOperator::Subtract, arg, arg1)?;
+                return self.math(MathFunction::Length, difference, None, None);
+            }
+            MathFunction::Normalize => {
+                let length = self.math(MathFunction::Length, arg, None, None)?;
+                return self.binary_op(BinaryOperator::Divide, arg, length);
+            }
+            MathFunction::Cross => {
+                let arg1 = arg1.ok_or(ConstantSolvingError::InvalidMathArg)?;
+                return self.cross(arg, arg1);
+            }
+            MathFunction::Modf
+            | MathFunction::Frexp
+            | MathFunction::Ldexp
+            | MathFunction::Outer
+            | MathFunction::FaceForward
+            | MathFunction::Reflect
+            | MathFunction::Refract
+            | MathFunction::Inverse
+            | MathFunction::Transpose
+            | MathFunction::Determinant
+            | MathFunction::ExtractBits
+            | MathFunction::InsertBits
+            | MathFunction::FirstTrailingBit
+            | MathFunction::FirstLeadingBit
+            | MathFunction::Pack4x8snorm
+            | MathFunction::Pack4x8unorm
+            | MathFunction::Pack2x16snorm
+            | MathFunction::Pack2x16unorm
+            | MathFunction::Pack2x16float
+            | MathFunction::Unpack4x8snorm
+            | MathFunction::Unpack4x8unorm
+            | MathFunction::Unpack2x16snorm
+            | MathFunction::Unpack2x16unorm
+            | MathFunction::Unpack2x16float => {
+                return Err(ConstantSolvingError::NotImplemented(format!("{:?}", fun)))
+            }
+            _ => {}
+        }
+
+        // The remaining functions are component-wise, with vector
+        // arguments possibly mixed with scalar ones.
+        if let Some((ty, components)) = self.vector_components(arg) {
+            let components = components.to_vec();
+            let mut result = Vec::with_capacity(components.len());
+            for (i, &component) in components.iter().enumerate() {
+                let arg1 = arg1.map(|arg| self.math_component(arg, i)).transpose()?;
+                let arg2 = arg2.map(|arg| self.math_component(arg, i)).transpose()?;
+                result.push(self.math(fun, component, arg1, arg2)?);
+            }
+            let ty = self.composite_type(ty, &result);
+            return Ok(self.register_constant(ConstantInner::Composite {
+                ty,
+                components: result,
+            }));
+        }
+
+        let (value, width) = match self.constants[arg].inner {
+            ConstantInner::Scalar { value, width } => (value, width),
+            ConstantInner::Composite { .. } => return Err(ConstantSolvingError::InvalidMathArg),
+        };
+        let value1 = arg1.map(|arg| self.scalar_value(arg)).transpose()?;
+        let value2 = arg2.map(|arg| self.scalar_value(arg)).transpose()?;
+        let value = math_scalar(fun, value, value1, value2)?.wrap(width);
+
+        Ok(self.register_constant(ConstantInner::Scalar { value, width }))
+    }
+
+    /// Returns the `index`-th component of a vector argument, or the argument
+    /// itself if it's a scalar.
+    fn math_component(
+        &self,
+        arg: Handle<Constant>,
+        index: usize,
+    ) -> Result<Handle<Constant>, ConstantSolvingError> {
+        match self.vector_components(arg) {
+            Some((_, components)) => components
+                .get(index)
+                .copied()
+                .ok_or(ConstantSolvingError::InvalidMathArg),
+            None => Ok(arg),
+        }
+    }
+
+    fn scalar_value(
+        &self,
+        constant: Handle<Constant>,
+    ) -> Result<ScalarValue, ConstantSolvingError> {
+        match self.constants[constant].inner {
+            ConstantInner::Scalar { value, .. } => Ok(value),
+            ConstantInner::Composite { .. } => Err(ConstantSolvingError::InvalidMathArg),
+        }
+    }
+
+    fn dot(
+        &mut self,
+        left: Handle<Constant>,
+        right: Handle<Constant>,
+    ) -> Result<Handle<Constant>, ConstantSolvingError> {
+        let left_components = match self.vector_components(left) {
+            Some((_, components)) => components.to_vec(),
+            None => return Err(ConstantSolvingError::InvalidMathArg),
+        };
+        let right_components = match self.vector_components(right) {
+            Some((_, components)) => components.to_vec(),
+            None => return Err(ConstantSolvingError::InvalidMathArg),
+        };
+        if left_components.len() != right_components.len() {
+            return Err(ConstantSolvingError::InvalidMathArg);
+        }
+
+        let mut sum = None;
+        for (&a, &b) in left_components.iter().zip(right_components.iter()) {
+            let product = self.binary_op(BinaryOperator::Multiply, a, b)?;
+            sum = Some(match sum {
+                Some(sum) => self.binary_op(BinaryOperator::Add, sum, product)?,
+                None => product,
+            });
+        }
+        sum.ok_or(ConstantSolvingError::InvalidMathArg)
+    }
+
+    fn cross(
+        &mut self,
+        left: Handle<Constant>,
+        right: Handle<Constant>,
+    ) -> Result<Handle<Constant>, ConstantSolvingError> {
+        let (ty, a) = match self.vector_components(left) {
+            Some((ty, components)) if components.len() == 3 => (ty, components.to_vec()),
+            _ => return Err(ConstantSolvingError::InvalidMathArg),
+        };
+        let b = match self.vector_components(right) {
+            Some((_, components)) if components.len() == 3 => components.to_vec(),
+            _ => return Err(ConstantSolvingError::InvalidMathArg),
+        };
+
+        let mut components = Vec::with_capacity(3);
+        for &(i, j) in [(1, 2), (2, 0), (0, 1)].iter() {
+            let first = self.binary_op(BinaryOperator::Multiply, a[i], b[j])?;
+            let second = self.binary_op(BinaryOperator::Multiply, a[j], b[i])?;
+            components.push(self.binary_op(BinaryOperator::Subtract, first, second)?);
+        }
+
+        Ok(self.register_constant(ConstantInner::Composite { ty, components }))
+    }
+}
+
+impl ScalarValue {
+    /// Wraps the value to the range of a scalar of the given `width`.
+    fn wrap(self, width: crate::Bytes) -> Self {
+        match (self, width) {
+            (ScalarValue::Sint(v), 4) => ScalarValue::Sint(v as i32 as i64),
+            (ScalarValue::Uint(v), 4) => ScalarValue::Uint(v as u32 as u64),
+            (ScalarValue::Float(v), 4) => ScalarValue::Float(v as f32 as f64),
+            (other, _) => other,
+        }
+    }
+}
+
+/// Returns the amount by which a scalar of the given `width` can be shifted.
+fn shift_amount(value: u64, width: crate::Bytes) -> Result<u32, ConstantSolvingError> {
+    if value < width as u64 * 8 {
+        Ok(value as u32)
+    } else {
+        Err(ConstantSolvingError::ShiftOverflow)
+    }
+}
+
+/// Evaluates a component-wise math function on scalars.
+#[allow(clippy::manual_clamp)]
+fn math_scalar(
+    fun: MathFunction,
+    arg: ScalarValue,
+    arg1: Option<ScalarValue>,
+    arg2: Option<ScalarValue>,
+) -> Result<ScalarValue, ConstantSolvingError> {
+    use ScalarValue as Sv;
+
+    let value = match (fun, arg, arg1, arg2) {
+        (MathFunction::Abs, Sv::Sint(a), None, None) => Sv::Sint(a.wrapping_abs()),
+        (MathFunction::Abs, Sv::Uint(a), None, None) => Sv::Uint(a),
+        (MathFunction::Sign, Sv::Sint(a), None, None) => Sv::Sint(a.signum()),
+        (MathFunction::Min, Sv::Sint(a), Some(Sv::Sint(b)), None) => Sv::Sint(a.min(b)),
+        (MathFunction::Min, Sv::Uint(a), Some(Sv::Uint(b)), None) => Sv::Uint(a.min(b)),
+        (MathFunction::Max, Sv::Sint(a), Some(Sv::Sint(b)), None) => Sv::Sint(a.max(b)),
+        (MathFunction::Max, Sv::Uint(a), Some(Sv::Uint(b)), None) => Sv::Uint(a.max(b)),
+        (MathFunction::Clamp, Sv::Sint(a), Some(Sv::Sint(b)), Some(Sv::Sint(c))) => {
+            Sv::Sint(a.max(b).min(c))
+        }
+        (MathFunction::Clamp, Sv::Uint(a), Some(Sv::Uint(b)), Some(Sv::Uint(c))) => {
+            Sv::Uint(a.max(b).min(c))
+        }
+        (MathFunction::CountOneBits, Sv::Sint(a), None, None) => {
+            Sv::Sint((a as i32).count_ones() as i64)
+        }
+        (MathFunction::CountOneBits, Sv::Uint(a), None, None) => {
+            Sv::Uint((a as u32).count_ones() as u64)
+        }
+        (MathFunction::ReverseBits, Sv::Sint(a), None, None) => {
+            Sv::Sint((a as i32).reverse_bits() as i64)
+        }
+        (MathFunction::ReverseBits, Sv::Uint(a), None, None) => {
+            Sv::Uint((a as u32).reverse_bits() as u64)
+        }
+        (fun, Sv::Float(a), None, None) => Sv::Float(match fun {
+            MathFunction::Abs => a.abs(),
+            MathFunction::Cos => a.cos(),
+            MathFunction::Cosh => a.cosh(),
+            MathFunction::Sin => a.sin(),
+            MathFunction::Sinh => a.sinh(),
+            MathFunction::Tan => a.tan(),
+            MathFunction::Tanh => a.tanh(),
+            MathFunction::Acos => a.acos(),
+            MathFunction::Asin => a.asin(),
+            MathFunction::Atan => a.atan(),
+            MathFunction::Ceil => a.ceil(),
+            MathFunction::Floor => a.floor(),
+            MathFunction::Round => super::round_even(a),
+            MathFunction::Fract => a - a.floor(),
+            MathFunction::Trunc => a.trunc(),
+            MathFunction::Exp => a.exp(),
+            MathFunction::Exp2 => a.exp2(),
+            MathFunction::Log => a.ln(),
+            MathFunction::Log2 => a.log2(),
+            MathFunction::Sign => {
+                if a > 0.0 {
+                    1.0
+                } else if a < 0.0 {
+                    -1.0
+                } else {
+                    a
+                }
+            }
+            MathFunction::Sqrt => a.sqrt(),
+            MathFunction::InverseSqrt => 1.0 / a.sqrt(),
+            _ => return Err(ConstantSolvingError::InvalidMathArg),
+        }),
+        (fun, Sv::Float(a), Some(Sv::Float(b)), None) => Sv::Float(match fun {
+            MathFunction::Min => a.min(b),
+            MathFunction::Max => a.max(b),
+            MathFunction::Atan2 => a.atan2(b),
+            MathFunction::Pow => a.powf(b),
+            MathFunction::Step => {
+                if b < a {
+                    0.0
+                } else {
+                    1.0
+                }
+            }
+            _ => return Err(ConstantSolvingError::InvalidMathArg),
+        }),
+        (fun, Sv::Float(a), Some(Sv::Float(b)), Some(Sv::Float(c))) => Sv::Float(match fun {
+            MathFunction::Clamp => a.max(b).min(c),
+            MathFunction::Fma => a.mul_add(b, c),
+            MathFunction::Mix => a * (1.0 - c) + b * c,
+            MathFunction::SmoothStep => {
+                let t = ((c - a) / (b - a)).max(0.0).min(1.0);
+                t * t * (3.0 - 2.0 * t)
+            }
+            _ => return Err(ConstantSolvingError::InvalidMathArg),
+        }),
+        _ => return Err(ConstantSolvingError::InvalidMathArg),
+    };
+    Ok(value)
+}
+
+/// Replaces the expressions of `function` that only depend on constants
+/// with `Expression::Constant`, adding the folded values to `constants`.
+///
+/// The folded expressions are removed from the `Emit` statements, since
+/// constants are considered emitted at the start of the function.
+pub fn fold_function_constants(
+    function: &mut crate::Function,
+    types: &mut Arena<Type>,
+    constants: &mut Arena<Constant>,
+) {
+    let mut folded = false;
+    let handles = function
+        .expressions
+        .iter()
+        .map(|(handle, _)| handle)
+        .collect::<Vec<_>>();
+    for handle in handles {
+        match function.expressions[handle] {
+            Expression::Access { .. }
+            | Expression::AccessIndex { .. }
+            | Expression::Splat { .. }
+            | Expression::Swizzle { .. }
+            | Expression::Compose { .. }
+            | Expression::Unary { .. }
+            | Expression::Binary { .. }
+            | Expression::Math { .. }
+            | Expression::As { .. } => {}
+            _ => continue,
+        }
+
+        let mut solver = ConstantSolver {
+            types,
+            expressions: &function.expressions,
+            constants,
+        };
+        if let Ok(constant) = solver.solve(handle) {
+            *function.expressions.get_mut(handle) = Expression::Constant(constant);
+            function.named_expressions.remove(&handle);
+            folded = true;
+        }
+    }
+
+    if folded {
+        remove_folded_emits(&mut function.body, &function.expressions);
+    }
+}
+
+/// Runs [`fold_function_constants`] on all the functions and entry points of `module`.
+pub fn fold_constants(module: &mut crate::Module) {
+    for (_, function) in module.functions.iter_mut() {
+        fold_function_constants(function, &mut module.types, &mut module.constants);
+    }
+    for entry_point in module.entry_points.iter_mut() {
+        fold_function_constants(
+            &mut entry_point.function,
+            &mut module.types,
+            &mut module.constants,
+        );
+    }
+}
+
+/// Splits the `Emit` statements of `block` around the expressions that don't need emitting.
+fn remove_folded_emits(block: &mut Block, expressions: &Arena<Expression>) {
+    let old = std::mem::replace(block, Block::new());
+    let spans = old.span_iter().map(|(_, &span)| span).collect::<Vec<_>>();
+    for (mut statement, span) in old.into_iter().zip(spans) {
+        match statement {
+            Statement::Emit(ref range) => {
+                let mut bounds = None;
+                for handle in range.clone() {
+                    if expressions[handle].needs_pre_emit() {
+                        if let Some((first, last)) = bounds.take() {
+                            block.push(Statement::Emit(Range::new_from_bounds(first, last)), span);
+                        }
+                    } else {
+                        bounds = Some(match bounds {
+                            Some((first, _)) => (first, handle),
+                            None => (handle, handle),
+                        });
+                    }
+                }
+                if let Some((first, last)) = bounds {
+                    block.push(Statement::Emit(Range::new_from_bounds(first, last)), span);
+                }
+                continue;
+            }
+            Statement::Block(ref mut body) => remove_folded_emits(body, expressions),
+            Statement::If {
+                ref mut accept,
+                ref mut reject,
+                ..
+            } => {
+                remove_folded_emits(accept, expressions);
+                remove_folded_emits(reject, expressions);
+            }
+            Statement::Switch {
+                ref mut cases,
+                ref mut default,
+                ..
+            } => {
+                for case in cases.iter_mut() {
+                    remove_folded_emits(&mut case.body, expressions);
+                }
+                remove_folded_emits(default, expressions);
+            }
+            Statement::Loop {
+                ref mut body,
+                ref mut continuing,
+            } => {
+                remove_folded_emits(body, expressions);
+                remove_folded_emits(continuing, expressions);
+            }
+            _ => {}
+        }
+        block.push(statement, span);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec;
+
+    use crate::{
+        Arena, BinaryOperator, Constant, ConstantInner, Expression, Function, Handle, MathFunction,
+        ScalarKind, ScalarValue, Statement, Type, TypeInner, UnaryOperator, VectorSize,
+    };
+
+    use super::{fold_function_constants, ConstantSolver, ConstantSolvingError};
+
+    #[test]
+    fn unary_op() {
+        let mut types = Arena::new();
+        let mut expressions = Arena::new();
+        let mut constants = Arena::new();
+
+        let vec_ty = types.append(
+            Type {
+                name: None,
+                inner: TypeInner::Vector {
+                    size: VectorSize::Bi,
+                    kind: ScalarKind::Sint,
+                    width: 4,
+                },
+            },
+            Default::default(),
+        );
+
+        let h = constants.append(
+            Constant {
+                name: None,
+                specialization: None,
+                inner: ConstantInner::Scalar {
+                    width: 4,
+                    value: ScalarValue::Sint(4),
+                },
+            },
+            Default::default(),
+        );
+
+        let h1 = constants.append(
+            Constant {
+                name: None,
+                specialization: None,
+                inner: ConstantInner::Scalar {
+                    width: 4,
+                    value: ScalarValue::Sint(8),
+                },
+            },
+            Default::default(),
+        );
+
+        let vec_h = constants.append(
+            Constant {
+                name: None,
+                specialization: None,
+                inner: ConstantInner::Composite {
+                    ty: vec_ty,
+                    components: vec![h, h1],
+                },
+            },
+            Default::default(),
+        );
+
+        let expr = expressions.append(Expression::Constant(h), Default::default());
+        let expr1 = expressions.append(Expression::Constant(vec_h), Default::default());
+
+        let root1 = expressions.append(
+            Expression::Unary {
+                op: UnaryOperator::Negate,
+                expr,
+            },
+            Default::default(),
+        );
+
+        let root2 = expressions.append(
+            Expression::Unary {
+                op: UnaryOperator::Not,
+                expr,
+            },
+            Default::default(),
+        );
+
+        let root3 = expressions.append(
+            Expression::Unary {
+                op: UnaryOperator::Not,
+                expr: expr1,
+            },
+            Default::default(),
+        );
+
+        let mut solver = ConstantSolver {
+            types: &mut types,
+            expressions: &expressions,
+            constants: &mut constants,
+        };
+
+        let res1 = solver.solve(root1).unwrap();
+        let res2 = solver.solve(root2).unwrap();
+        let res3 = solver.solve(root3).unwrap();
+
+        assert_eq!(
+            constants[res1].inner,
+            ConstantInner::Scalar {
+                width: 4,
+                value: ScalarValue::Sint(-4),
+            },
+        );
+
+        assert_eq!(
+            constants[res2].inner,
+            ConstantInner::Scalar {
+                width: 4,
+                value: ScalarValue::Sint(!4),
+            },
+        );
+
+        let res3_inner = &constants[res3].inner;
+
+        match *res3_inner {
+            ConstantInner::Composite { ty, ref components } => {
+                assert_eq!(ty, vec_ty);
+                let mut components_iter = components.iter().copied();
+                assert_eq!(
+                    constants[components_iter.next().unwrap()].inner,
+                    ConstantInner::Scalar {
+                        width: 4,
+                        value: ScalarValue::Sint(!4),
+                    },
+                );
+                assert_eq!(
+                    constants[components_iter.next().unwrap()].inner,
+                    ConstantInner::Scalar {
+                        width: 4,
+                        value: ScalarValue::Sint(!8),
+                    },
+                );
+                assert!(components_iter.next().is_none());
+            }
+            _ => panic!("Expected vector"),
+        }
+    }
+
+    #[test]
+    fn cast() {
+        let mut expressions = Arena::new();
+        let mut constants = Arena::new();
+
+        let h = constants.append(
+            Constant {
+                name: None,
+                specialization: None,
+                inner: ConstantInner::Scalar {
+                    width: 4,
+                    value: ScalarValue::Sint(4),
+                },
+            },
+            Default::default(),
+        );
+
+        let expr = expressions.append(Expression::Constant(h), Default::default());
+
+        let root = expressions.append(
+            Expression::As {
+                expr,
+                kind: ScalarKind::Bool,
+                convert: Some(crate::BOOL_WIDTH),
+            },
+            Default::default(),
+        );
+
+        let mut solver = ConstantSolver {
+            types: &mut Arena::new(),
+            expressions: &expressions,
+            constants: &mut constants,
+        };
+
+        let res = solver.solve(root).unwrap();
+
+        assert_eq!(
+            constants[res].inner,
+            ConstantInner::Scalar {
+                width: crate::BOOL_WIDTH,
+                value: ScalarValue::Bool(true),
+            },
+        );
+    }
+
+    #[test]
+    fn access() {
+        let mut types = Arena::new();
+        let mut expressions = Arena::new();
+        let mut constants = Arena::new();
+
+        let matrix_ty = types.append(
+            Type {
+                name: None,
+                inner: TypeInner::Matrix {
+                    columns: VectorSize::Bi,
+                    rows: VectorSize::Tri,
+                    width: 4,
+                },
+            },
+            Default::default(),
+        );
+
+        let vec_ty = types.append(
+            Type {
+                name: None,
+                inner: TypeInner::Vector {
+                    size: VectorSize::Tri,
+                    kind: ScalarKind::Float,
+                    width: 4,
+                },
+            },
+            Default::default(),
+        );
+
+        let mut vec1_components = Vec::with_capacity(3);
+        let mut vec2_components = Vec::with_capacity(3);
+
+        for i in 0..3 {
+            let h = constants.append(
+                Constant {
+                    name: None,
+                    specialization: None,
+                    inner: ConstantInner::Scalar {
+                        width: 4,
+                        value: ScalarValue::Float(i as f64),
+                    },
+                },
+                Default::default(),
+            );
+
+            vec1_components.push(h)
+        }
+
+        for i in 3..6 {
+            let h = constants.append(
+                Constant {
+                    name: None,
+                    specialization: None,
+                    inner: ConstantInner::Scalar {
+                        width: 4,
+                        value: ScalarValue::Float(i as f64),
+                    },
+                },
+                Default::default(),
+            );
+
+            vec2_components.push(h)
+        }
+
+        let vec1 = constants.append(
+            Constant {
+                name: None,
+                specialization: None,
+                inner: ConstantInner::Composite {
+                    ty: vec_ty,
+                    components: vec1_components,
+                },
+            },
+            Default::default(),
+        );
+
+        let vec2 = constants.append(
+            Constant {
+                name: None,
+                specialization: None,
+                inner: ConstantInner::Composite {
+                    ty: vec_ty,
+                    components: vec2_components,
+                },
+            },
+            Default::default(),
+        );
+
+        let h = constants.append(
+            Constant {
+                name: None,
+                specialization: None,
+                inner: ConstantInner::Composite {
+                    ty: matrix_ty,
+                    components: vec![vec1, vec2],
+                },
+            },
+            Default::default(),
+        );
+
+        let base = expressions.append(Expression::Constant(h), Default::default());
+        let root1 = expressions.append(
+            Expression::AccessIndex { base, index: 1 },
+            Default::default(),
+        );
+        let root2 = expressions.append(
+            Expression::AccessIndex {
+                base: root1,
+                index: 2,
+            },
+            Default::default(),
+        );
+
+        let mut solver = ConstantSolver {
+            types: &mut types,
+            expressions: &expressions,
+            constants: &mut constants,
+        };
+
+        let res1 = solver.solve(root1).unwrap();
+        let res2 = solver.solve(root2).unwrap();
+
+        let res1_inner = &constants[res1].inner;
+
+        match *res1_inner {
+            ConstantInner::Composite { ty, ref components } => {
+                assert_eq!(ty, vec_ty);
+                let mut components_iter = components.iter().copied();
+                assert_eq!(
+                    constants[components_iter.next().unwrap()].inner,
+                    ConstantInner::Scalar {
+                        width: 4,
+                        value: ScalarValue::Float(3.),
+                    },
+                );
+                assert_eq!(
+                    constants[components_iter.next().unwrap()].inner,
+                    ConstantInner::Scalar {
+                        width: 4,
+                        value: ScalarValue::Float(4.),
+                    },
+                );
+                assert_eq!(
+                    constants[components_iter.next().unwrap()].inner,
+                    ConstantInner::Scalar {
+                        width: 4,
+                        value: ScalarValue::Float(5.),
+                    },
+                );
+                assert!(components_iter.next().is_none());
+            }
+            _ => panic!("Expected vector"),
+        }
+
+        assert_eq!(
+            constants[res2].inner,
+            ConstantInner::Scalar {
+                width: 4,
+                value: ScalarValue::Float(5.),
+            },
+        );
+    }
+
+    fn scalar(constants: &mut Arena<Constant>, value: ScalarValue) -> Handle<Constant> {
+        constants.append(
+            Constant {
+                name: None,
+                specialization: None,
+                inner: ConstantInner::Scalar { width: 4, value },
+            },
+            Default::default(),
+        )
+    }
+
+    #[test]
+    fn binary_and_math() {
+        let mut types = Arena::new();
+        let mut expressions = Arena::new();
+        let mut constants = Arena::new();
+
+        let vec_ty = types.append(
+            Type {
+                name: None,
+                inner: TypeInner::Vector {
+                    size: VectorSize::Tri,
+                    kind: ScalarKind::Float,
+                    width: 4,
+                },
+            },
+            Default::default(),
+        );
+
+        let components = (1..4)
+            .map(|i| scalar(&mut constants, ScalarValue::Float(i as f64)))
+            .collect();
+        let vec_h = constants.append(
+            Constant {
+                name: None,
+                specialization: None,
+                inner: ConstantInner::Composite {
+                    ty: vec_ty,
+                    components,
+                },
+            },
+            Default::default(),
+        );
+        let two = scalar(&mut constants, ScalarValue::Float(2.0));
+        let max_uint = scalar(&mut constants, ScalarValue::Uint(u32::MAX as u64));
+        let one = scalar(&mut constants, ScalarValue::Uint(1));
+        let zero = scalar(&mut constants, ScalarValue::Sint(0));
+        let half = scalar(&mut constants, ScalarValue::Float(2.5));
+
+        let vec_expr = expressions.append(Expression::Constant(vec_h), Default::default());
+        let two_expr = expressions.append(Expression::Constant(two), Default::default());
+        let max_uint_expr = expressions.append(Expression::Constant(max_uint), Default::default());
+        let one_expr = expressions.append(Expression::Constant(one), Default::default());
+        let zero_expr = expressions.append(Expression::Constant(zero), Default::default());
+        let half_expr = expressions.append(Expression::Constant(half), Default::default());
+
+        let sum = expressions.append(
+            Expression::Binary {
+                op: BinaryOperator::Add,
+                left: vec_expr,
+                right: vec_expr,
+            },
+            Default::default(),
+        );
+        let dot = expressions.append(
+            Expression::Math {
+                fun: MathFunction::Dot,
+                arg: sum,
+                arg1: Some(vec_expr),
+                arg2: None,
+                arg3: None,
+            },
+            Default::default(),
+        );
+        let comparison = expressions.append(
+            Expression::Binary {
+                op: BinaryOperator::Less,
+                left: vec_expr,
+                right: two_expr,
+            },
+            Default::default(),
+        );
+        let wrapped = expressions.append(
+            Expression::Binary {
+                op: BinaryOperator::Add,
+                left: max_uint_expr,
+                right: one_expr,
+            },
+            Default::default(),
+        );
+        let division = expressions.append(
+            Expression::Binary {
+                op: BinaryOperator::Divide,
+                left: zero_expr,
+                right: zero_expr,
+            },
+            Default::default(),
+        );
+        let rounded = expressions.append(
+            Expression::Math {
+                fun: MathFunction::Round,
+                arg: half_expr,
+                arg1: None,
+                arg2: None,
+                arg3: None,
+            },
+            Default::default(),
+        );
+
+        let mut solver = ConstantSolver {
+            types: &mut types,
+            expressions: &expressions,
+            constants: &mut constants,
+        };
+
+        let dot = solver.solve(dot).unwrap();
+        let rounded = solver.solve(rounded).unwrap();
+        let comparison = solver.solve(comparison).unwrap();
+        let wrapped = solver.solve(wrapped).unwrap();
+        assert_eq!(
+            solver.solve(division),
+            Err(ConstantSolvingError::DivisionByZero)
+        );
+
+        assert_eq!(
+            constants[dot].inner,
+            ConstantInner::Scalar {
+                width: 4,
+                value: ScalarValue::Float(28.0),
+            },
+        );
+        assert_eq!(
+            constants[rounded].inner,
+            ConstantInner::Scalar {
+                width: 4,
+                value: ScalarValue::Float(2.0),
+            },
+        );
+        assert_eq!(
+            constants[wrapped].inner,
+            ConstantInner::Scalar {
+                width: 4,
+                value: ScalarValue::Uint(0),
+            },
+        );
+        match constants[comparison].inner {
+            ConstantInner::Composite { ty, ref components } => {
+                assert_eq!(
+                    types[ty].inner,
+                    TypeInner::Vector {
+                        size: VectorSize::Tri,
+                        kind: ScalarKind::Bool,
+                        width: 4,
+                    }
+                );
+                let values = components
+                    .iter()
+                    .map(|&c| constants[c].inner.clone())
+                    .collect::<Vec<_>>();
+                assert_eq!(
+                    values,
+                    [true, false, false]
+                        .iter()
+                        .map(|&value| ConstantInner::Scalar {
+                            width: 4,
+                            value: ScalarValue::Bool(value),
+                        })
+                        .collect::<Vec<_>>()
+                );
+            }
+            _ => panic!("Expected vector"),
+        }
+    }
+
+    #[test]
+    fn fold_function() {
+        let mut types = Arena::new();
+        let mut constants = Arena::new();
+        let mut function = Function::default();
+
+        let three = scalar(&mut constants, ScalarValue::Sint(3));
+        let local = function.local_variables.append(
+            crate::LocalVariable {
+                name: None,
+                ty: types.append(
+                    Type {
+                        name: None,
+                        inner: TypeInner::Scalar {
+                            kind: ScalarKind::Sint,
+                            width: 4,
+                        },
+                    },
+                    Default::default(),
+                ),
+                init: None,
+            },
+            Default::default(),
+        );
+
+        let three_expr = function
+            .expressions
+            .append(Expression::Constant(three), Default::default());
+        let pointer = function
+            .expressions
+            .append(Expression::LocalVariable(local), Default::default());
+        let start = function.expressions.len();
+        let negated = function.expressions.append(
+            Expression::Unary {
+                op: UnaryOperator::Negate,
+                expr: three_expr,
+            },
+            Default::default(),
+        );
+        let load = function
+            .expressions
+            .append(Expression::Load { pointer }, Default::default());
+        let product = function.expressions.append(
+            Expression::Binary {
+                op: BinaryOperator::Multiply,
+                left: negated,
+                right: load,
+            },
+            Default::default(),
+        );
+        function.body.push(
+            Statement::Emit(function.expressions.range_from(start)),
+            Default::default(),
+        );
+        function.body.push(
+            Statement::Store {
+                pointer,
+                value: product,
+            },
+            Default::default(),
+        );
+
+        fold_function_constants(&mut function, &mut types, &mut constants);
+
+        match function.expressions[negated] {
+            Expression::Constant(c) => assert_eq!(
+                constants[c].inner,
+                ConstantInner::Scalar {
+                    width: 4,
+                    value: ScalarValue::Sint(-3),
+                }
+            ),
+            ref other => panic!("Expected a constant, found {:?}", other),
+        }
+        match function.expressions[product] {
+            Expression::Binary { .. } => {}
+            ref other => panic!("Expected a binary expression, found {:?}", other),
+        }
+        match function.body[0] {
+            Statement::Emit(ref range) => {
+                assert_eq!(range.clone().collect::<Vec<_>>(), vec![load, product])
+            }
+            ref other => panic!("Expected an emit, found {:?}", other),
+        }
+        assert_eq!(function.body.len(), 2);
+    }
+
+    #[test]
+    fn keep_specialization() {
+        let mut types = Arena::new();
+        let mut constants = Arena::new();
+        let mut function = Function::default();
+
+        let spec = constants.append(
+            Constant {
+                name: None,
+                specialization: Some(0),
+                inner: ConstantInner::Scalar {
+                    width: 4,
+                    value: ScalarValue::Float(1.0),
+                },
+            },
+            Default::default(),
+        );
+        let two = scalar(&mut constants, ScalarValue::Float(2.0));
+
+        let spec_expr = function
+            .expressions
+            .append(Expression::Constant(spec), Default::default());
+        let two_expr = function
+            .expressions
+            .append(Expression::Constant(two), Default::default());
+        let start = function.expressions.len();
+        let product = function.expressions.append(
+            Expression::Binary {
+                op: BinaryOperator::Multiply,
+                left: spec_expr,
+                right: two_expr,
+            },
+            Default::default(),
+        );
+        function.body.push(
+            Statement::Emit(function.expressions.range_from(start)),
+            Default::default(),
+        );
+
+        let mut solver = ConstantSolver {
+            types: &mut types,
+            expressions: &function.expressions,
+            constants: &mut constants,
+        };
+        assert_eq!(
+            solver.solve(product),
+            Err(ConstantSolvingError::Specialization)
+        );
+
+        fold_function_constants(&mut function, &mut types, &mut constants);
+
+        match function.expressions[product] {
+            Expression::Binary { .. } => {}
+            ref other => panic!("Expected a binary expression, found {:?}", other),
+        }
+        assert_eq!(function.body.len(), 1);
+    }
+}
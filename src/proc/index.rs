@@ -1,6 +1,7 @@
 //! Definitions for index bounds checking.
 
 use super::ProcError;
+use crate::{arena::Handle, valid::FunctionInfo};
 
 impl crate::TypeInner {
     /// Return the length of a subscriptable type.
@@ -17,7 +18,7 @@ impl crate::TypeInner {
         let known_length = match *self {
             Ti::Vector { size, .. } => size as _,
             Ti::Matrix { columns, .. } => columns as _,
-            Ti::Array { size, .. } => {
+            Ti::Array { size, .. } | Ti::BindingArray { size, .. } => {
                 return size.to_indexable_length(module);
             }
             Ti::ValuePointer {
@@ -31,7 +32,9 @@ impl crate::TypeInner {
                 match *base_inner {
                     Ti::Vector { size, .. } => size as _,
                     Ti::Matrix { columns, .. } => columns as _,
-                    Ti::Array { size, .. } => return size.to_indexable_length(module),
+                    Ti::Array { size, .. } | Ti::BindingArray { size, .. } => {
+                        return size.to_indexable_length(module)
+                    }
                     _ => return Err(ProcError::TypeNotIndexable),
                 }
             }
@@ -45,13 +48,14 @@ impl crate::TypeInner {
 ///
 /// This summarizes the length of vectors, matrices, and arrays in a way that is
 /// convenient for indexing and bounds-checking code.
+#[derive(Clone, Copy, Debug)]
 pub enum IndexableLength {
     /// Values of this type always have the given number of elements.
     Known(u32),
 
     /// The value of the given specializable constant is the number of elements.
     /// (Non-specializable constants are reported as `Known`.)
-    Specializable(crate::Handle<crate::Constant>),
+    Specializable(Handle<crate::Constant>),
 
     /// The number of elements is determined at runtime.
     Dynamic,
@@ -77,3 +81,139 @@ impl crate::ArraySize {
         })
     }
 }
+
+/// An index that may be statically known, or may need to be computed at runtime.
+///
+/// This enum lets us handle both [`Access`] and [`AccessIndex`] expressions
+/// with the same code.
+///
+/// [`Access`]: crate::Expression::Access
+/// [`AccessIndex`]: crate::Expression::AccessIndex
+#[derive(Clone, Copy, Debug)]
+pub enum GuardedIndex {
+    /// The index is known at shader translation time.
+    Known(u32),
+    /// The index is the value of the given expression.
+    Expression(Handle<crate::Expression>),
+}
+
+impl GuardedIndex {
+    /// Turn `self` into a `Known` index, if it refers to a constant expression.
+    fn try_resolve_to_constant(
+        self,
+        expressions: &crate::Arena<crate::Expression>,
+        module: &crate::Module,
+    ) -> Self {
+        if let Self::Expression(expr) = self {
+            if let crate::Expression::Constant(handle) = expressions[expr] {
+                if let Some(value) = module.constants[handle].to_array_length() {
+                    return Self::Known(value);
+                }
+            }
+        }
+        self
+    }
+}
+
+/// A single index of an access chain that needs a runtime bounds check.
+#[derive(Clone, Copy, Debug)]
+pub struct BoundsCheck {
+    /// The expression being indexed.
+    pub base: Handle<crate::Expression>,
+    /// The index applied to `base`.
+    pub index: GuardedIndex,
+    /// The length that `index` must be less than.
+    pub length: IndexableLength,
+}
+
+/// Return the element type if `base` is a binding array or a pointer to one.
+pub fn binding_array_element(
+    base: Handle<crate::Expression>,
+    module: &crate::Module,
+    info: &FunctionInfo,
+) -> Option<Handle<crate::Type>> {
+    let inner = match *info[base].ty.inner_with(&module.types) {
+        crate::TypeInner::Pointer { base, .. } => &module.types[base].inner,
+        ref other => other,
+    };
+    match *inner {
+        crate::TypeInner::BindingArray { base, .. } => Some(base),
+        _ => None,
+    }
+}
+
+/// Determine whether `base[index]` selects a binding array element with an
+/// index that may differ between invocations.
+///
+/// Backends have to mark such accesses as non-uniform.
+pub fn is_non_uniform_binding_array_access(
+    base: Handle<crate::Expression>,
+    index: Handle<crate::Expression>,
+    module: &crate::Module,
+    info: &FunctionInfo,
+) -> bool {
+    binding_array_element(base, module, info).is_some()
+        && info[index].uniformity.non_uniform_result.is_some()
+}
+
+/// Determine whether `base[index]` needs a runtime bounds check.
+///
+/// Return `None` if `base` is a structure or a binding array, or if `index`
+/// is known to be in bounds. Otherwise, return the length that `index` has
+/// to be checked against.
+pub fn access_needs_check(
+    base: Handle<crate::Expression>,
+    index: GuardedIndex,
+    module: &crate::Module,
+    expressions: &crate::Arena<crate::Expression>,
+    info: &FunctionInfo,
+) -> Option<IndexableLength> {
+    // Indexing into a binding array selects a resource, which is up to
+    // the API to keep in bounds.
+    if binding_array_element(base, module, info).is_some() {
+        return None;
+    }
+    let base_inner = info[base].ty.inner_with(&module.types);
+    // Structures aren't indexable, and their members are always present.
+    // Any other errors here would have been caught by the validator.
+    let length = base_inner.indexable_length(module).ok()?;
+    let index = index.try_resolve_to_constant(expressions, module);
+    if let (GuardedIndex::Known(index), IndexableLength::Known(length)) = (index, length) {
+        if index < length {
+            return None;
+        }
+    }
+    Some(length)
+}
+
+/// Iterate over the bounds checks needed by the access chain ending at `chain`.
+///
+/// This walks down through the [`Access`] and [`AccessIndex`] expressions
+/// starting at `chain`, until it reaches an expression of any other kind,
+/// and yields each index for which [`access_needs_check`] returns a length.
+/// The checks are produced from the outermost access to the innermost one.
+///
+/// [`Access`]: crate::Expression::Access
+/// [`AccessIndex`]: crate::Expression::AccessIndex
+pub fn bounds_check_iter<'a>(
+    mut chain: Handle<crate::Expression>,
+    module: &'a crate::Module,
+    expressions: &'a crate::Arena<crate::Expression>,
+    info: &'a FunctionInfo,
+) -> impl Iterator<Item = BoundsCheck> + 'a {
+    std::iter::from_fn(move || loop {
+        let (base, index) = match expressions[chain] {
+            crate::Expression::Access { base, index } => (base, GuardedIndex::Expression(index)),
+            crate::Expression::AccessIndex { base, index } => (base, GuardedIndex::Known(index)),
+            _ => return None,
+        };
+        chain = base;
+        if let Some(length) = access_needs_check(base, index, module, expressions, info) {
+            return Some(BoundsCheck {
+                base,
+                index,
+                length,
+            });
+        }
+    })
+}
@@ -0,0 +1,511 @@
+//! Inlining of function calls.
+
+use super::compact::{for_each_child_block, for_each_operand, for_each_statement_operand};
+use crate::{
+    arena::{Arena, Handle, Range},
+    Block, Expression, FastHashMap, Function, LocalVariable, Module, Statement,
+};
+
+/// Configuration for [`inline_calls`].
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+#[cfg_attr(feature = "deserialize", derive(serde::Deserialize))]
+pub struct InlineOptions {
+    /// Only inline the functions with at most this many expressions.
+    /// If `None`, all the functions are inlined where possible.
+    pub size_threshold: Option<usize>,
+}
+
+/// Replace the calls in all the functions and entry points of `module` with
+/// the bodies of the called functions.
+///
+/// The arguments of the callee are replaced with the argument expressions of
+/// the call, its local variables are moved to the caller, and its returned
+/// value is stored in a new local variable that the result of the call loads.
+///
+/// A call is kept if the callee is larger than [`InlineOptions::size_threshold`],
+/// or if it returns from within a loop or a switch, because there is no way to
+/// leave the inlined body from there.
+///
+/// The functions that are no longer called stay in the module, they can be
+/// removed with [`compact`](super::compact).
+pub fn inline_calls(module: &mut Module, options: &InlineOptions) {
+    // Functions can only call the ones before them, so the callees are
+    // always done inlining by the time they are inlined themselves.
+    let handles = module
+        .functions
+        .iter()
+        .map(|(handle, _)| handle)
+        .collect::<Vec<_>>();
+    for handle in handles {
+        let mut function = std::mem::take(module.functions.get_mut(handle));
+        inline_function(&mut function, &module.functions, options);
+        *module.functions.get_mut(handle) = function;
+    }
+    for ep in module.entry_points.iter_mut() {
+        inline_function(&mut ep.function, &module.functions, options);
+    }
+}
+
+/// How the `Return` statements of an inlined function are replaced.
+#[derive(Clone, Copy, PartialEq)]
+enum ReturnMode {
+    /// All the returns are at the end of the control flow, so they can be dropped.
+    Tail,
+    /// The body is wrapped in a loop, and returns break out of it.
+    Break,
+}
+
+impl ReturnMode {
+    fn new(body: &Block) -> Option<Self> {
+        if returns_at_tail(body, true) {
+            Some(ReturnMode::Tail)
+        } else if !returns_in_breakable(body, false) {
+            Some(ReturnMode::Break)
+        } else {
+            None
+        }
+    }
+}
+
+/// Return true if all the `Return` statements in `block` end its control flow.
+fn returns_at_tail(block: &Block, tail: bool) -> bool {
+    block.iter().enumerate().all(|(index, statement)| {
+        let tail = tail && index + 1 == block.len();
+        match *statement {
+            Statement::Return { .. } => tail,
+            Statement::Block(ref block) => returns_at_tail(block, tail),
+            Statement::If {
+                ref accept,
+                ref reject,
+                ..
+            } => returns_at_tail(accept, tail) && returns_at_tail(reject, tail),
+            Statement::Switch {
+                ref cases,
+                ref default,
+                ..
+            } => {
+                cases
+                    .iter()
+                    .all(|case| returns_at_tail(&case.body, tail && !case.fall_through))
+                    && returns_at_tail(default, tail)
+            }
+            Statement::Loop {
+                ref body,
+                ref continuing,
+            } => returns_at_tail(body, false) && returns_at_tail(continuing, false),
+            _ => true,
+        }
+    })
+}
+
+/// Return true if `block` has a `Return` statement that a `Break` can't replace.
+fn returns_in_breakable(block: &Block, breakable: bool) -> bool {
+    block.iter().any(|statement| match *statement {
+        Statement::Return { .. } => breakable,
+        Statement::Block(ref block) => returns_in_breakable(block, breakable),
+        Statement::If {
+            ref accept,
+            ref reject,
+            ..
+        } => returns_in_breakable(accept, breakable) || returns_in_breakable(reject, breakable),
+        Statement::Switch {
+            ref cases,
+            ref default,
+            ..
+        } => {
+            cases
+                .iter()
+                .any(|case| returns_in_breakable(&case.body, true))
+                || returns_in_breakable(default, true)
+        }
+        Statement::Loop {
+            ref body,
+            ref continuing,
+        } => returns_in_breakable(body, true) || returns_in_breakable(continuing, true),
+        _ => false,
+    })
+}
+
+/// A call to be inlined.
+struct CallSite {
+    callee: Handle<Function>,
+    arguments: Vec<Handle<Expression>>,
+    result: Option<Handle<Expression>>,
+    mode: ReturnMode,
+}
+
+/// The copy of a callee in the caller.
+struct Instance {
+    /// The caller expressions, indexed by the callee ones.
+    expressions: Vec<Handle<Expression>>,
+    /// Pointer to the local variable holding the result.
+    result: Option<Handle<Expression>>,
+}
+
+fn inline_function(function: &mut Function, functions: &Arena<Function>, options: &InlineOptions) {
+    // Whether each call statement is inlined, in the order of the body.
+    let mut decisions = Vec::new();
+    let mut call_sites = Vec::new();
+    collect_call_sites(
+        &mut function.body,
+        functions,
+        options,
+        &mut decisions,
+        &mut call_sites,
+    );
+    if call_sites.is_empty() {
+        return;
+    }
+
+    // The callee expressions are placed right before the result of the
+    // call, which becomes a load of the result variable, so that the
+    // expressions still only refer to the ones before them.
+    let results = call_sites
+        .iter()
+        .enumerate()
+        .filter_map(|(index, site)| site.result.map(|result| (result, index)))
+        .collect::<FastHashMap<_, _>>();
+    let mut instances = call_sites.iter().map(|_| None).collect::<Vec<_>>();
+
+    let old = std::mem::take(&mut function.expressions);
+    let old_handles = old.iter().map(|(handle, _)| handle).collect::<Vec<_>>();
+    let spans = old_handles
+        .iter()
+        .map(|&handle| old.get_span(handle))
+        .collect::<Vec<_>>();
+    let mut map = Vec::with_capacity(old_handles.len());
+    for ((handle, mut expression), span) in old_handles.into_iter().zip(old.into_inner()).zip(spans)
+    {
+        if let Some(&index) = results.get(&handle) {
+            let instance = instantiate(function, functions, &call_sites[index], &map);
+            expression = Expression::Load {
+                pointer: instance.result.unwrap(),
+            };
+            instances[index] = Some(instance);
+        } else {
+            for_each_operand(&mut expression, |operand| *operand = map[operand.index()]);
+        }
+        map.push(function.expressions.append(expression, span));
+    }
+    for (site, instance) in call_sites.iter().zip(instances.iter_mut()) {
+        if instance.is_none() {
+            *instance = Some(instantiate(function, functions, site, &map));
+        }
+    }
+
+    function.named_expressions = function
+        .named_expressions
+        .drain()
+        .map(|(handle, name)| (map[handle.index()], name))
+        .chain(
+            call_sites
+                .iter()
+                .zip(instances.iter())
+                .flat_map(|(site, instance)| {
+                    let instance = instance.as_ref().unwrap();
+                    let callee = &functions[site.callee];
+                    callee
+                        .named_expressions
+                        .iter()
+                        .filter(move |&(&handle, _)| match callee.expressions[handle] {
+                            Expression::FunctionArgument(_) => false,
+                            _ => true,
+                        })
+                        .map(move |(&handle, name)| {
+                            (instance.expressions[handle.index()], name.clone())
+                        })
+                })
+                .collect::<Vec<_>>(),
+        )
+        .collect();
+
+    let mut inliner = Inliner {
+        functions,
+        map: &map,
+        decisions: decisions.into_iter(),
+        call_sites: call_sites.iter().zip(instances.iter()),
+    };
+    function.body = inliner.rewrite_block(std::mem::take(&mut function.body));
+}
+
+fn collect_call_sites(
+    block: &mut Block,
+    functions: &Arena<Function>,
+    options: &InlineOptions,
+    decisions: &mut Vec<bool>,
+    call_sites: &mut Vec<CallSite>,
+) {
+    for statement in block.iter_mut() {
+        if let Statement::Call {
+            function,
+            ref arguments,
+            result,
+        } = *statement
+        {
+            let callee = &functions[function];
+            let small = match options.size_threshold {
+                Some(threshold) => callee.expressions.len() <= threshold,
+                None => true,
+            };
+            // The arguments have to come before the result to be usable
+            // by the callee expressions.
+            let ordered = match result {
+                Some(result) => arguments.iter().all(|&argument| argument < result),
+                None => true,
+            };
+            let site = match ReturnMode::new(&callee.body) {
+                Some(mode) if small && ordered => Some(CallSite {
+                    callee: function,
+                    arguments: arguments.clone(),
+                    result,
+                    mode,
+                }),
+                _ => None,
+            };
+            decisions.push(site.is_some());
+            call_sites.extend(site);
+        }
+        for_each_child_block(statement, |block| {
+            collect_call_sites(block, functions, options, decisions, call_sites)
+        });
+    }
+}
+
+/// Append the locals and expressions of the callee at `site` to `function`.
+///
+/// The `map` holds the new handles of the caller expressions processed so far.
+fn instantiate(
+    function: &mut Function,
+    functions: &Arena<Function>,
+    site: &CallSite,
+    map: &[Handle<Expression>],
+) -> Instance {
+    let callee = &functions[site.callee];
+    let locals = callee
+        .local_variables
+        .iter()
+        .map(|(handle, local)| {
+            function
+                .local_variables
+                .append(local.clone(), callee.local_variables.get_span(handle))
+        })
+        .collect::<Vec<_>>();
+
+    let result = match (site.result, callee.result.as_ref()) {
+        (Some(_), Some(result)) => {
+            let local = function.local_variables.append(
+                LocalVariable {
+                    name: callee.name.as_ref().map(|name| format!("{}_result", name)),
+                    ty: result.ty,
+                    init: None,
+                },
+                Default::default(),
+            );
+            Some(
+                function
+                    .expressions
+                    .append(Expression::LocalVariable(local), Default::default()),
+            )
+        }
+        _ => None,
+    };
+
+    let mut expressions = Vec::with_capacity(callee.expressions.len());
+    for (handle, expression) in callee.expressions.iter() {
+        let new_handle = match *expression {
+            Expression::FunctionArgument(index) => map[site.arguments[index as usize].index()],
+            Expression::LocalVariable(local) => function.expressions.append(
+                Expression::LocalVariable(locals[local.index()]),
+                callee.expressions.get_span(handle),
+            ),
+            _ => {
+                let mut expression = expression.clone();
+                for_each_operand(&mut expression, |operand| {
+                    *operand = expressions[operand.index()]
+                });
+                function
+                    .expressions
+                    .append(expression, callee.expressions.get_span(handle))
+            }
+        };
+        expressions.push(new_handle);
+    }
+
+    Instance {
+        expressions,
+        result,
+    }
+}
+
+fn adjust_range(range: &mut Range<Expression>, map: &[Handle<Expression>]) {
+    let mut handles = range.clone();
+    if let Some(first) = handles.next() {
+        let last = handles.last().unwrap_or(first);
+        *range = Range::new_from_bounds(map[first.index()], map[last.index()]);
+    }
+}
+
+/// Split `block` into its statements and their spans.
+fn take_statements(block: Block) -> Vec<(Statement, crate::Span)> {
+    let spans = block.span_iter().map(|(_, &span)| span).collect::<Vec<_>>();
+    block.into_iter().zip(spans).collect()
+}
+
+struct Inliner<'a, D, C> {
+    functions: &'a Arena<Function>,
+    /// The new handles of the caller expressions.
+    map: &'a [Handle<Expression>],
+    decisions: D,
+    call_sites: C,
+}
+
+impl<'a, D, C> Inliner<'a, D, C>
+where
+    D: Iterator<Item = bool>,
+    C: Iterator<Item = (&'a CallSite, &'a Option<Instance>)>,
+{
+    fn rewrite_block(&mut self, block: Block) -> Block {
+        let mut new_block = Block::with_capacity(block.len());
+        for (mut statement, span) in take_statements(block) {
+            if let Statement::Call { .. } = statement {
+                if self.decisions.next().unwrap() {
+                    let (site, instance) = self.call_sites.next().unwrap();
+                    let instance = instance.as_ref().unwrap();
+                    new_block.push(self.inline_call(site, instance), span);
+                    if let Some(result) = site.result {
+                        let load = self.map[result.index()];
+                        new_block.push(Statement::Emit(Range::new_from_bounds(load, load)), span);
+                    }
+                    continue;
+                }
+            }
+
+            let map = self.map;
+            if let Statement::Emit(ref mut range) = statement {
+                adjust_range(range, map);
+            }
+            for_each_statement_operand(&mut statement, |operand| *operand = map[operand.index()]);
+            for_each_child_block(&mut statement, |block| {
+                *block = self.rewrite_block(std::mem::take(block))
+            });
+            new_block.push(statement, span);
+        }
+        new_block
+    }
+
+    fn inline_call(&self, site: &CallSite, instance: &Instance) -> Statement {
+        let callee = &self.functions[site.callee];
+        let mut body = rewrite_callee_block(callee.body.clone(), instance, site.mode);
+        match site.mode {
+            ReturnMode::Tail => Statement::Block(body),
+            ReturnMode::Break => {
+                match body.last() {
+                    Some(&Statement::Break) => {}
+                    _ => body.push(Statement::Break, Default::default()),
+                }
+                Statement::Loop {
+                    body,
+                    continuing: Block::new(),
+                }
+            }
+        }
+    }
+}
+
+fn rewrite_callee_block(block: Block, instance: &Instance, mode: ReturnMode) -> Block {
+    let map = &instance.expressions;
+    let mut new_block = Block::with_capacity(block.len());
+    for (mut statement, span) in take_statements(block) {
+        if let Statement::Return { value } = statement {
+            if let (Some(pointer), Some(value)) = (instance.result, value) {
+                let value = map[value.index()];
+                new_block.push(Statement::Store { pointer, value }, span);
+            }
+            if mode == ReturnMode::Break {
+                new_block.push(Statement::Break, span);
+            }
+            continue;
+        }
+
+        if let Statement::Emit(ref mut range) = statement {
+            adjust_range(range, map);
+        }
+        for_each_statement_operand(&mut statement, |operand| *operand = map[operand.index()]);
+        for_each_child_block(&mut statement, |block| {
+            *block = rewrite_callee_block(std::mem::take(block), instance, mode)
+        });
+        new_block.push(statement, span);
+    }
+    new_block
+}
+
+#[cfg(all(test, feature = "wgsl-in"))]
+#[test]
+fn inline_all() {
+    let mut module = crate::front::wgsl::parse_str(
+        "
+        fn square(x: f32) -> f32 {
+            var result: f32 = x * x;
+            return result;
+        }
+
+        fn clamped(x: f32) -> f32 {
+            if (x < 0.0) {
+                return 0.0;
+            }
+            return square(x);
+        }
+
+        fn large(x: f32) -> f32 {
+            var sum: f32 = 0.0;
+            loop {
+                if (sum > x) {
+                    return sum;
+                }
+                sum = sum + 1.0;
+            }
+            return sum;
+        }
+
+        [[stage(fragment)]]
+        fn main([[location(0)]] x: f32) -> [[location(0)]] vec4<f32> {
+            let a = clamped(x) + square(2.0);
+            let b = large(a);
+            return vec4<f32>(a, b, 0.0, 1.0);
+        }
+        ",
+    )
+    .unwrap();
+
+    inline_calls(&mut module, &InlineOptions::default());
+
+    fn callees(block: &Block, out: &mut Vec<Handle<Function>>) {
+        for statement in block.iter() {
+            let mut statement = statement.clone();
+            if let Statement::Call { function, .. } = statement {
+                out.push(function);
+            }
+            for_each_child_block(&mut statement, |block| callees(block, out));
+        }
+    }
+    let large = module
+        .functions
+        .fetch_if(|function| function.name.as_deref() == Some("large"))
+        .unwrap();
+    let mut calls = Vec::new();
+    callees(&module.entry_points[0].function.body, &mut calls);
+    assert_eq!(calls, [large]);
+    calls.clear();
+    for (_, function) in module.functions.iter() {
+        callees(&function.body, &mut calls);
+    }
+    assert!(calls.is_empty());
+
+    crate::valid::Validator::new(
+        crate::valid::ValidationFlags::all(),
+        crate::valid::Capabilities::empty(),
+    )
+    .validate(&module)
+    .unwrap();
+}
@@ -0,0 +1,1410 @@
+use super::{
+    exec::{Context, Invocation, Operand},
+    memory::{self, Pointee, Pointer, Region},
+    InterpretError, Scalar, Value,
+};
+use crate::{
+    arena::Handle, proc::round_even, BinaryOperator as Bo, Bytes, ConstantInner, Expression,
+    GlobalVariable, MathFunction as Mf, Module, RelationalFunction as Rf, ScalarKind, ScalarValue,
+    UnaryOperator as Uo,
+};
+use std::cmp::Ordering;
+
+pub(super) fn constant_value(
+    module: &Module,
+    handle: Handle<crate::Constant>,
+) -> Result<Value, InterpretError> {
+    Ok(match module.constants[handle].inner {
+        ConstantInner::Scalar { width, value } => Value::Scalar(match (value, width) {
+            (ScalarValue::Sint(value), 4) => Scalar::I32(value as i32),
+            (ScalarValue::Uint(value), 4) => Scalar::U32(value as u32),
+            (ScalarValue::Float(value), 4) => Scalar::F32(value as f32),
+            (ScalarValue::Float(value), 8) => Scalar::F64(value),
+            (ScalarValue::Bool(value), _) => Scalar::Bool(value),
+            _ => return Err(InterpretError::Unsupported("scalars of this width")),
+        }),
+        ConstantInner::Composite { ref components, .. } => Value::Composite(
+            components
+                .iter()
+                .map(|&component| constant_value(module, component))
+                .collect::<Result<_, _>>()?,
+        ),
+    })
+}
+
+pub(super) fn integer(scalar: Scalar) -> Result<i64, InterpretError> {
+    match scalar {
+        Scalar::I32(value) => Ok(value as i64),
+        Scalar::U32(value) => Ok(value as i64),
+        _ => Err(InterpretError::TypeMismatch),
+    }
+}
+
+fn float(scalar: Scalar) -> Result<f64, InterpretError> {
+    match scalar {
+        Scalar::F32(value) => Ok(value as f64),
+        Scalar::F64(value) => Ok(value),
+        _ => Err(InterpretError::TypeMismatch),
+    }
+}
+
+fn from_float(value: f64, width: Bytes) -> Scalar {
+    match width {
+        8 => Scalar::F64(value),
+        _ => Scalar::F32(value as f32),
+    }
+}
+
+/// Flatten a scalar or a vector of floats.
+fn floats(value: &Value) -> Result<(Vec<f64>, Bytes), InterpretError> {
+    let scalars = match *value {
+        Value::Scalar(ref scalar) => std::slice::from_ref(scalar).to_vec(),
+        Value::Composite(ref components) => components
+            .iter()
+            .map(|component| component.as_scalar().ok_or(InterpretError::TypeMismatch))
+            .collect::<Result<_, _>>()?,
+    };
+    let width = scalars.first().map_or(4, Scalar::width);
+    let values = scalars.into_iter().map(float).collect::<Result<_, _>>()?;
+    Ok((values, width))
+}
+
+/// Build a value shaped like `like` out of flattened floats.
+fn unflatten(like: &Value, values: Vec<f64>, width: Bytes) -> Value {
+    match *like {
+        Value::Scalar(_) => Value::Scalar(from_float(values[0], width)),
+        Value::Composite(_) => Value::composite(values.into_iter().map(|v| from_float(v, width))),
+    }
+}
+
+/// Flatten the columns of a square matrix.
+fn matrix(value: &Value) -> Result<(Vec<Vec<f64>>, Bytes), InterpretError> {
+    let mut width = 4;
+    let columns = value
+        .components()
+        .iter()
+        .map(|column| {
+            let (values, column_width) = floats(column)?;
+            width = column_width;
+            Ok(values)
+        })
+        .collect::<Result<Vec<_>, InterpretError>>()?;
+    if columns.iter().any(|column| column.len() != columns.len()) {
+        return Err(InterpretError::TypeMismatch);
+    }
+    Ok((columns, width))
+}
+
+fn is_matrix(value: &Value) -> bool {
+    match value.components().first() {
+        Some(&Value::Composite(_)) => true,
+        _ => false,
+    }
+}
+
+/// Apply `fun` to every scalar of `value`.
+fn map(
+    value: &Value,
+    fun: &mut dyn FnMut(Scalar) -> Result<Scalar, InterpretError>,
+) -> Result<Value, InterpretError> {
+    Ok(match *value {
+        Value::Scalar(scalar) => Value::Scalar(fun(scalar)?),
+        Value::Composite(ref components) => Value::Composite(
+            components
+                .iter()
+                .map(|component| map(component, fun))
+                .collect::<Result<_, _>>()?,
+        ),
+    })
+}
+
+/// Component of a composite, or the value itself if it's a scalar.
+fn broadcast(value: &Value, index: usize) -> &Value {
+    value.components().get(index).unwrap_or(value)
+}
+
+/// Number of components that broadcasting `values` together yields.
+fn broadcast_count(values: &[&Value]) -> Result<usize, InterpretError> {
+    let mut count = 0;
+    for value in values {
+        match (count, value.components().len()) {
+            (_, 0) => {}
+            (0, len) => count = len,
+            (count, len) if count == len => {}
+            _ => return Err(InterpretError::TypeMismatch),
+        }
+    }
+    Ok(count)
+}
+
+/// Apply `fun` to the matching scalars of two values, broadcasting scalars.
+fn zip(
+    left: &Value,
+    right: &Value,
+    fun: &mut dyn FnMut(Scalar, Scalar) -> Result<Scalar, InterpretError>,
+) -> Result<Value, InterpretError> {
+    if let (Some(a), Some(b)) = (left.as_scalar(), right.as_scalar()) {
+        return Ok(Value::Scalar(fun(a, b)?));
+    }
+    (0..broadcast_count(&[left, right])?)
+        .map(|i| zip(broadcast(left, i), broadcast(right, i), fun))
+        .collect::<Result<_, _>>()
+        .map(Value::Composite)
+}
+
+fn zip3(
+    first: &Value,
+    second: &Value,
+    third: &Value,
+    fun: &mut dyn FnMut(Scalar, Scalar, Scalar) -> Result<Scalar, InterpretError>,
+) -> Result<Value, InterpretError> {
+    if let (Some(a), Some(b), Some(c)) = (first.as_scalar(), second.as_scalar(), third.as_scalar())
+    {
+        return Ok(Value::Scalar(fun(a, b, c)?));
+    }
+    (0..broadcast_count(&[first, second, third])?)
+        .map(|i| {
+            let (a, b, c) = (
+                broadcast(first, i),
+                broadcast(second, i),
+                broadcast(third, i),
+            );
+            zip3(a, b, c, fun)
+        })
+        .collect::<Result<_, _>>()
+        .map(Value::Composite)
+}
+
+fn map_float(value: &Value, fun: fn(f64) -> f64) -> Result<Value, InterpretError> {
+    map(value, &mut |scalar| {
+        Ok(from_float(fun(float(scalar)?), scalar.width()))
+    })
+}
+
+fn zip_float(
+    left: &Value,
+    right: &Value,
+    fun: fn(f64, f64) -> f64,
+) -> Result<Value, InterpretError> {
+    zip(left, right, &mut |a, b| {
+        Ok(from_float(fun(float(a)?, float(b)?), a.width()))
+    })
+}
+
+fn zip3_float(
+    first: &Value,
+    second: &Value,
+    third: &Value,
+    fun: fn(f64, f64, f64) -> f64,
+) -> Result<Value, InterpretError> {
+    zip3(first, second, third, &mut |a, b, c| {
+        Ok(from_float(fun(float(a)?, float(b)?, float(c)?), a.width()))
+    })
+}
+
+macro_rules! integer_binary {
+    ($op:expr, $variant:path, $a:expr, $b:expr) => {
+        match $op {
+            Bo::Add => $variant($a.wrapping_add($b)),
+            Bo::Subtract => $variant($a.wrapping_sub($b)),
+            Bo::Multiply => $variant($a.wrapping_mul($b)),
+            Bo::Divide if $b == 0 => return Err(InterpretError::DivisionByZero),
+            Bo::Divide => $variant($a.wrapping_div($b)),
+            Bo::Modulo if $b == 0 => return Err(InterpretError::DivisionByZero),
+            Bo::Modulo => $variant($a.wrapping_rem($b)),
+            Bo::And => $variant($a & $b),
+            Bo::ExclusiveOr => $variant($a ^ $b),
+            Bo::InclusiveOr => $variant($a | $b),
+            Bo::Equal => Scalar::Bool($a == $b),
+            Bo::NotEqual => Scalar::Bool($a != $b),
+            Bo::Less => Scalar::Bool($a < $b),
+            Bo::LessEqual => Scalar::Bool($a <= $b),
+            Bo::Greater => Scalar::Bool($a > $b),
+            Bo::GreaterEqual => Scalar::Bool($a >= $b),
+            _ => return Err(InterpretError::TypeMismatch),
+        }
+    };
+}
+
+macro_rules! float_binary {
+    ($op:expr, $variant:path, $a:expr, $b:expr) => {
+        match $op {
+            Bo::Add => $variant($a + $b),
+            Bo::Subtract => $variant($a - $b),
+            Bo::Multiply => $variant($a * $b),
+            Bo::Divide => $variant($a / $b),
+            Bo::Modulo => $variant($a % $b),
+            Bo::Equal => Scalar::Bool($a == $b),
+            Bo::NotEqual => Scalar::Bool($a != $b),
+            Bo::Less => Scalar::Bool($a < $b),
+            Bo::LessEqual => Scalar::Bool($a <= $b),
+            Bo::Greater => Scalar::Bool($a > $b),
+            Bo::GreaterEqual => Scalar::Bool($a >= $b),
+            _ => return Err(InterpretError::TypeMismatch),
+        }
+    };
+}
+
+fn binary_scalar(op: Bo, left: Scalar, right: Scalar) -> Result<Scalar, InterpretError> {
+    if let Bo::ShiftLeft | Bo::ShiftRight = op {
+        let amount = integer(right)? as u32;
+        return Ok(match (op, left) {
+            (Bo::ShiftLeft, Scalar::I32(value)) => Scalar::I32(value.wrapping_shl(amount)),
+            (Bo::ShiftLeft, Scalar::U32(value)) => Scalar::U32(value.wrapping_shl(amount)),
+            (_, Scalar::I32(value)) => Scalar::I32(value.wrapping_shr(amount)),
+            (_, Scalar::U32(value)) => Scalar::U32(value.wrapping_shr(amount)),
+            _ => return Err(InterpretError::TypeMismatch),
+        });
+    }
+    Ok(match (left, right) {
+        (Scalar::Bool(a), Scalar::Bool(b)) => Scalar::Bool(match op {
+            Bo::Equal => a == b,
+            Bo::NotEqual | Bo::ExclusiveOr => a != b,
+            Bo::And | Bo::LogicalAnd => a && b,
+            Bo::InclusiveOr | Bo::LogicalOr => a || b,
+            _ => return Err(InterpretError::TypeMismatch),
+        }),
+        (Scalar::I32(a), Scalar::I32(b)) => integer_binary!(op, Scalar::I32, a, b),
+        (Scalar::U32(a), Scalar::U32(b)) => integer_binary!(op, Scalar::U32, a, b),
+        (Scalar::F32(a), Scalar::F32(b)) => float_binary!(op, Scalar::F32, a, b),
+        (Scalar::F64(a), Scalar::F64(b)) => float_binary!(op, Scalar::F64, a, b),
+        _ => return Err(InterpretError::TypeMismatch),
+    })
+}
+
+fn dot(left: &[Value], right: &[Value]) -> Result<Scalar, InterpretError> {
+    if left.len() != right.len() || left.is_empty() {
+        return Err(InterpretError::TypeMismatch);
+    }
+    let mut sum = None;
+    for (a, b) in left.iter().zip(right) {
+        let a = a.as_scalar().ok_or(InterpretError::TypeMismatch)?;
+        let b = b.as_scalar().ok_or(InterpretError::TypeMismatch)?;
+        let product = binary_scalar(Bo::Multiply, a, b)?;
+        sum = Some(match sum {
+            Some(sum) => binary_scalar(Bo::Add, sum, product)?,
+            None => product,
+        });
+    }
+    Ok(sum.unwrap())
+}
+
+fn binary(op: Bo, left: &Value, right: &Value) -> Result<Value, InterpretError> {
+    if op == Bo::Multiply && (is_matrix(left) || is_matrix(right)) {
+        let (left_columns, right_columns) = (left.components(), right.components());
+        return match (is_matrix(left), is_matrix(right)) {
+            // matrix * vector: a combination of the columns
+            (true, false) if !right_columns.is_empty() => {
+                let mut sum: Option<Value> = None;
+                for (column, factor) in left_columns.iter().zip(right_columns) {
+                    let term = binary(Bo::Multiply, column, factor)?;
+                    sum = Some(match sum {
+                        Some(sum) => binary(Bo::Add, &sum, &term)?,
+                        None => term,
+                    });
+                }
+                sum.ok_or(InterpretError::TypeMismatch)
+            }
+            // vector * matrix: a dot product with each column
+            (false, true) if !left_columns.is_empty() => right_columns
+                .iter()
+                .map(|column| dot(left_columns, column.components()).map(Value::Scalar))
+                .collect::<Result<_, _>>()
+                .map(Value::Composite),
+            (true, true) => right_columns
+                .iter()
+                .map(|column| binary(Bo::Multiply, left, column))
+                .collect::<Result<_, _>>()
+                .map(Value::Composite),
+            // scalar * matrix
+            _ => zip(left, right, &mut |a, b| binary_scalar(op, a, b)),
+        };
+    }
+    zip(left, right, &mut |a, b| binary_scalar(op, a, b))
+}
+
+fn cast(
+    scalar: Scalar,
+    kind: ScalarKind,
+    convert: Option<Bytes>,
+) -> Result<Scalar, InterpretError> {
+    let width = match convert {
+        Some(width) => width,
+        None => {
+            return Ok(match (scalar, kind) {
+                (_, _) if scalar.kind() == kind => scalar,
+                (Scalar::I32(value), ScalarKind::Uint) => Scalar::U32(value as u32),
+                (Scalar::I32(value), ScalarKind::Float) => {
+                    Scalar::F32(f32::from_bits(value as u32))
+                }
+                (Scalar::U32(value), ScalarKind::Sint) => Scalar::I32(value as i32),
+                (Scalar::U32(value), ScalarKind::Float) => Scalar::F32(f32::from_bits(value)),
+                (Scalar::F32(value), ScalarKind::Sint) => Scalar::I32(value.to_bits() as i32),
+                (Scalar::F32(value), ScalarKind::Uint) => Scalar::U32(value.to_bits()),
+                _ => return Err(InterpretError::TypeMismatch),
+            })
+        }
+    };
+    Ok(match kind {
+        ScalarKind::Bool => Scalar::Bool(match scalar {
+            Scalar::Bool(value) => value,
+            Scalar::I32(value) => value != 0,
+            Scalar::U32(value) => value != 0,
+            Scalar::F32(value) => value != 0.0,
+            Scalar::F64(value) => value != 0.0,
+        }),
+        ScalarKind::Sint => Scalar::I32(match scalar {
+            Scalar::Bool(value) => value as i32,
+            Scalar::I32(value) => value,
+            Scalar::U32(value) => value as i32,
+            Scalar::F32(value) => value as i32,
+            Scalar::F64(value) => value as i32,
+        }),
+        ScalarKind::Uint => Scalar::U32(match scalar {
+            Scalar::Bool(value) => value as u32,
+            Scalar::I32(value) => value as u32,
+            Scalar::U32(value) => value,
+            Scalar::F32(value) => value as u32,
+            Scalar::F64(value) => value as u32,
+        }),
+        ScalarKind::Float => {
+            let value = match scalar {
+                Scalar::Bool(value) => value as u32 as f64,
+                Scalar::I32(value) => value as f64,
+                Scalar::U32(value) => value as f64,
+                Scalar::F32(value) => value as f64,
+                Scalar::F64(value) => value,
+            };
+            from_float(value, width)
+        }
+    })
+}
+
+fn relational(fun: Rf, argument: &Value) -> Result<Value, InterpretError> {
+    let bools = || {
+        argument
+            .components()
+            .iter()
+            .map(|component| match *component {
+                Value::Scalar(Scalar::Bool(value)) => Ok(value),
+                _ => Err(InterpretError::TypeMismatch),
+            })
+            .collect::<Result<Vec<_>, _>>()
+    };
+    match fun {
+        Rf::All => Ok(bools()?.into_iter().all(|value| value).into()),
+        Rf::Any => Ok(bools()?.into_iter().any(|value| value).into()),
+        Rf::IsNan | Rf::IsInf | Rf::IsFinite | Rf::IsNormal => map(argument, &mut |scalar| {
+            Ok(Scalar::Bool(match (fun, scalar) {
+                // single precision has its own range of normal numbers
+                (Rf::IsNormal, Scalar::F32(value)) => value.is_normal(),
+                (Rf::IsNormal, _) => float(scalar)?.is_normal(),
+                (Rf::IsNan, _) => float(scalar)?.is_nan(),
+                (Rf::IsInf, _) => float(scalar)?.is_infinite(),
+                _ => float(scalar)?.is_finite(),
+            }))
+        }),
+    }
+}
+
+fn min_max(
+    scalar_a: Scalar,
+    scalar_b: Scalar,
+    ordering: Ordering,
+) -> Result<Scalar, InterpretError> {
+    let pick = |cmp: Option<Ordering>| cmp == Some(ordering);
+    Ok(match (scalar_a, scalar_b) {
+        (Scalar::I32(a), Scalar::I32(b)) => {
+            Scalar::I32(if pick(b.partial_cmp(&a)) { b } else { a })
+        }
+        (Scalar::U32(a), Scalar::U32(b)) => {
+            Scalar::U32(if pick(b.partial_cmp(&a)) { b } else { a })
+        }
+        (Scalar::F32(a), Scalar::F32(b)) => {
+            Scalar::F32(if pick(b.partial_cmp(&a)) { b } else { a })
+        }
+        (Scalar::F64(a), Scalar::F64(b)) => {
+            Scalar::F64(if pick(b.partial_cmp(&a)) { b } else { a })
+        }
+        _ => return Err(InterpretError::TypeMismatch),
+    })
+}
+
+/// Split a float into a mantissa in `[0.5, 1)` and a power of two.
+fn frexp(value: f64) -> (f64, i32) {
+    if value == 0.0 || !value.is_finite() {
+        return (value, 0);
+    }
+    let mut exponent = value.abs().log2().floor() as i32 + 1;
+    let mut mantissa = value / 2f64.powi(exponent);
+    if mantissa.abs() >= 1.0 {
+        mantissa /= 2.0;
+        exponent += 1;
+    } else if mantissa.abs() < 0.5 {
+        mantissa *= 2.0;
+        exponent -= 1;
+    }
+    (mantissa, exponent)
+}
+
+fn pivot(rows: &[Vec<f64>], column: usize) -> usize {
+    (column..rows.len())
+        .max_by(|&a, &b| {
+            rows[a][column]
+                .abs()
+                .partial_cmp(&rows[b][column].abs())
+                .unwrap_or(Ordering::Equal)
+        })
+        .unwrap()
+}
+
+fn determinant(mut rows: Vec<Vec<f64>>) -> f64 {
+    let mut result = 1.0;
+    for i in 0..rows.len() {
+        let pivot = pivot(&rows, i);
+        if rows[pivot][i] == 0.0 {
+            return 0.0;
+        }
+        if pivot != i {
+            rows.swap(pivot, i);
+            result = -result;
+        }
+        result *= rows[i][i];
+        let (upper, lower) = rows.split_at_mut(i + 1);
+        let pivot_row = &upper[i];
+        for row in lower {
+            let factor = row[i] / pivot_row[i];
+            for (value, &pivot_value) in row[i..].iter_mut().zip(&pivot_row[i..]) {
+                *value -= factor * pivot_value;
+            }
+        }
+    }
+    result
+}
+
+/// Invert a matrix with Gauss-Jordan elimination.
+///
+/// The inverse of the transpose is the transpose of the inverse, so this
+/// works on columns just as well as on rows.
+fn inverse(mut rows: Vec<Vec<f64>>) -> Vec<Vec<f64>> {
+    let n = rows.len();
+    let mut result = (0..n)
+        .map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect())
+        .collect::<Vec<Vec<f64>>>();
+    for i in 0..n {
+        let pivot = pivot(&rows, i);
+        rows.swap(i, pivot);
+        result.swap(i, pivot);
+        let scale = rows[i][i];
+        for k in 0..n {
+            rows[i][k] /= scale;
+            result[i][k] /= scale;
+        }
+        for j in 0..n {
+            if j != i {
+                let factor = rows[j][i];
+                for k in 0..n {
+                    rows[j][k] -= factor * rows[i][k];
+                    result[j][k] -= factor * result[i][k];
+                }
+            }
+        }
+    }
+    result
+}
+
+/// Reinterpret an integer scalar as its bits.
+fn int_bits(scalar: Scalar) -> Result<u32, InterpretError> {
+    match scalar {
+        Scalar::I32(value) => Ok(value as u32),
+        Scalar::U32(value) => Ok(value),
+        _ => Err(InterpretError::TypeMismatch),
+    }
+}
+
+/// Build an integer scalar of the same kind as `like` from bits.
+fn from_int_bits(like: Scalar, bits: u32) -> Scalar {
+    match like {
+        Scalar::I32(_) => Scalar::I32(bits as i32),
+        _ => Scalar::U32(bits),
+    }
+}
+
+/// Offset and count of a bitfield, clamped to 32 bits.
+fn bitfield(offset: &Value, count: &Value) -> Result<(u32, u32), InterpretError> {
+    let scalar = |value: &Value| value.as_scalar().ok_or(InterpretError::TypeMismatch);
+    let offset = int_bits(scalar(offset)?)?.min(32);
+    let count = int_bits(scalar(count)?)?.min(32 - offset);
+    Ok((offset, count))
+}
+
+/// Shift `value` right, rounding to the nearest integer with ties going to even.
+fn shift_round(value: u32, shift: u32) -> u32 {
+    let result = value >> shift;
+    let half = 1 << (shift - 1);
+    let remainder = value & ((1 << shift) - 1);
+    if remainder > half || (remainder == half && result & 1 != 0) {
+        result + 1
+    } else {
+        result
+    }
+}
+
+/// Convert to the bits of the nearest half-precision float.
+fn to_half(value: f32) -> u32 {
+    let bits = value.to_bits();
+    let sign = (bits >> 16) & 0x8000;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+    if exponent == 0xff {
+        let nan = if mantissa != 0 { 0x200 } else { 0 };
+        return sign | 0x7c00 | nan;
+    }
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1f {
+        sign | 0x7c00
+    } else if exponent <= 0 {
+        if exponent < -10 {
+            return sign;
+        }
+        sign | shift_round(mantissa | 0x80_0000, (14 - exponent) as u32)
+    } else {
+        // a carry out of the mantissa correctly bumps the exponent
+        sign | shift_round(((exponent as u32) << 23) | mantissa, 13)
+    }
+}
+
+fn from_half(bits: u32) -> f64 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((bits >> 10) & 0x1f) as i32;
+    let mantissa = (bits & 0x3ff) as f64;
+    match exponent {
+        0 => sign * mantissa * 2f64.powi(-24),
+        0x1f if mantissa == 0.0 => sign * f64::INFINITY,
+        0x1f => f64::NAN,
+        _ => sign * (1.0 + mantissa / 1024.0) * 2f64.powi(exponent - 15),
+    }
+}
+
+/// Pack a vector of floats into the bits of a `u32`.
+fn pack(fun: Mf, value: &Value) -> Result<Value, InterpretError> {
+    let (values, _) = floats(value)?;
+    let bits = 32 / values.len() as u32;
+    let mut packed = 0;
+    for (index, &value) in values.iter().enumerate() {
+        let clamped = match fun {
+            Mf::Pack4x8snorm | Mf::Pack2x16snorm => value.max(-1.0),
+            _ => value.max(0.0),
+        };
+        let clamped = clamped.min(1.0);
+        let component = match fun {
+            Mf::Pack4x8snorm => (0.5 + 127.0 * clamped).floor() as i8 as u8 as u32,
+            Mf::Pack4x8unorm => (0.5 + 255.0 * clamped).floor() as u32,
+            Mf::Pack2x16snorm => (0.5 + 32767.0 * clamped).floor() as i16 as u16 as u32,
+            Mf::Pack2x16unorm => (0.5 + 65535.0 * clamped).floor() as u32,
+            _ => to_half(value as f32),
+        };
+        packed |= component << (index as u32 * bits);
+    }
+    Ok(Value::Scalar(Scalar::U32(packed)))
+}
+
+/// Unpack the bits of a `u32` into a vector of `count` floats.
+fn unpack(fun: Mf, value: &Value, count: u32) -> Result<Value, InterpretError> {
+    let packed = int_bits(value.as_scalar().ok_or(InterpretError::TypeMismatch)?)?;
+    let bits = 32 / count;
+    Ok(Value::composite((0..count).map(|index| {
+        let component = (packed >> (index * bits)) & (u32::MAX >> (32 - bits));
+        let value = match fun {
+            Mf::Unpack4x8snorm => (component as u8 as i8 as f64 / 127.0).max(-1.0),
+            Mf::Unpack4x8unorm => component as f64 / 255.0,
+            Mf::Unpack2x16snorm => (component as u16 as i16 as f64 / 32767.0).max(-1.0),
+            Mf::Unpack2x16unorm => component as f64 / 65535.0,
+            _ => from_half(component),
+        };
+        Scalar::F32(value as f32)
+    })))
+}
+
+fn math(
+    fun: Mf,
+    arg: &Value,
+    arg1: Option<&Value>,
+    arg2: Option<&Value>,
+    arg3: Option<&Value>,
+) -> Result<Value, InterpretError> {
+    let arg1 = || arg1.ok_or(InterpretError::TypeMismatch);
+    let arg2 = || arg2.ok_or(InterpretError::TypeMismatch);
+    let arg3 = || arg3.ok_or(InterpretError::TypeMismatch);
+    let length = |value: &Value| -> Result<Value, InterpretError> {
+        let (values, width) = floats(value)?;
+        let length = values.iter().map(|v| v * v).sum::<f64>().sqrt();
+        Ok(Value::Scalar(from_float(length, width)))
+    };
+    Ok(match fun {
+        Mf::Abs => map(arg, &mut |scalar| {
+            Ok(match scalar {
+                Scalar::I32(value) => Scalar::I32(value.wrapping_abs()),
+                Scalar::U32(value) => Scalar::U32(value),
+                _ => from_float(float(scalar)?.abs(), scalar.width()),
+            })
+        })?,
+        Mf::Min => zip(arg, arg1()?, &mut |a, b| min_max(a, b, Ordering::Less))?,
+        Mf::Max => zip(arg, arg1()?, &mut |a, b| min_max(a, b, Ordering::Greater))?,
+        Mf::Clamp => zip3(arg, arg1()?, arg2()?, &mut |value, low, high| {
+            min_max(
+                min_max(value, low, Ordering::Greater)?,
+                high,
+                Ordering::Less,
+            )
+        })?,
+        Mf::Cos => map_float(arg, f64::cos)?,
+        Mf::Cosh => map_float(arg, f64::cosh)?,
+        Mf::Sin => map_float(arg, f64::sin)?,
+        Mf::Sinh => map_float(arg, f64::sinh)?,
+        Mf::Tan => map_float(arg, f64::tan)?,
+        Mf::Tanh => map_float(arg, f64::tanh)?,
+        Mf::Acos => map_float(arg, f64::acos)?,
+        Mf::Asin => map_float(arg, f64::asin)?,
+        Mf::Atan => map_float(arg, f64::atan)?,
+        Mf::Atan2 => zip_float(arg, arg1()?, f64::atan2)?,
+        Mf::Ceil => map_float(arg, f64::ceil)?,
+        Mf::Floor => map_float(arg, f64::floor)?,
+        Mf::Round => map_float(arg, round_even)?,
+        Mf::Fract => map_float(arg, |value| value - value.floor())?,
+        Mf::Trunc => map_float(arg, f64::trunc)?,
+        Mf::Modf => map_float(arg, |value| value - value.trunc())?,
+        Mf::Frexp => map_float(arg, |value| frexp(value).0)?,
+        Mf::Ldexp => zip(arg, arg1()?, &mut |value, exponent| {
+            let exponent = integer(exponent)? as i32;
+            Ok(from_float(
+                float(value)? * 2f64.powi(exponent),
+                value.width(),
+            ))
+        })?,
+        Mf::Exp => map_float(arg, f64::exp)?,
+        Mf::Exp2 => map_float(arg, f64::exp2)?,
+        Mf::Log => map_float(arg, f64::ln)?,
+        Mf::Log2 => map_float(arg, f64::log2)?,
+        Mf::Pow => zip_float(arg, arg1()?, f64::powf)?,
+        Mf::Dot => Value::Scalar(dot(arg.components(), arg1()?.components())?),
+        // column `j` is `arg * arg1[j]`
+        Mf::Outer => arg1()?
+            .components()
+            .iter()
+            .map(|b| binary(Bo::Multiply, arg, b))
+            .collect::<Result<_, _>>()
+            .map(Value::Composite)?,
+        Mf::Cross => {
+            let (a, b) = (arg.components(), arg1()?.components());
+            if a.len() != 3 || b.len() != 3 {
+                return Err(InterpretError::TypeMismatch);
+            }
+            let term = |i: usize, j: usize| -> Result<Value, InterpretError> {
+                binary(
+                    Bo::Subtract,
+                    &binary(Bo::Multiply, &a[i], &b[j])?,
+                    &binary(Bo::Multiply, &a[j], &b[i])?,
+                )
+            };
+            Value::Composite(vec![term(1, 2)?, term(2, 0)?, term(0, 1)?])
+        }
+        Mf::Distance => length(&binary(Bo::Subtract, arg, arg1()?)?)?,
+        Mf::Length => length(arg)?,
+        Mf::Normalize => binary(Bo::Divide, arg, &length(arg)?)?,
+        Mf::FaceForward => {
+            let (incident, reference) = (arg1()?, arg2()?);
+            if float(dot(reference.components(), incident.components())?)? < 0.0 {
+                arg.clone()
+            } else {
+                map_float(arg, |value| -value)?
+            }
+        }
+        Mf::Reflect => {
+            let normal = arg1()?;
+            let d = dot(normal.components(), arg.components())?;
+            let factor = Value::Scalar(from_float(2.0 * float(d)?, d.width()));
+            binary(Bo::Subtract, arg, &binary(Bo::Multiply, normal, &factor)?)?
+        }
+        Mf::Refract => {
+            let (incident, width) = floats(arg)?;
+            let (normal, _) = floats(arg1()?)?;
+            let eta = float(arg2()?.as_scalar().ok_or(InterpretError::TypeMismatch)?)?;
+            let d = incident
+                .iter()
+                .zip(&normal)
+                .map(|(i, n)| i * n)
+                .sum::<f64>();
+            let k = 1.0 - eta * eta * (1.0 - d * d);
+            let result = if k < 0.0 {
+                vec![0.0; incident.len()]
+            } else {
+                incident
+                    .iter()
+                    .zip(&normal)
+                    .map(|(i, n)| eta * i - (eta * d + k.sqrt()) * n)
+                    .collect()
+            };
+            unflatten(arg, result, width)
+        }
+        Mf::Sign => map(arg, &mut |scalar| {
+            Ok(match scalar {
+                Scalar::I32(value) => Scalar::I32(value.signum()),
+                Scalar::U32(value) => Scalar::U32(value.min(1)),
+                _ => {
+                    let value = float(scalar)?;
+                    let sign = if value > 0.0 {
+                        1.0
+                    } else if value < 0.0 {
+                        -1.0
+                    } else {
+                        value
+                    };
+                    from_float(sign, scalar.width())
+                }
+            })
+        })?,
+        Mf::Fma => zip3_float(arg, arg1()?, arg2()?, f64::mul_add)?,
+        Mf::Mix => zip3_float(arg, arg1()?, arg2()?, |x, y, a| x * (1.0 - a) + y * a)?,
+        Mf::Step => zip_float(arg, arg1()?, |edge, x| if x < edge { 0.0 } else { 1.0 })?,
+        Mf::SmoothStep => zip3_float(arg, arg1()?, arg2()?, |low, high, x| {
+            let t = ((x - low) / (high - low)).max(0.0);
+            let t = t.min(1.0);
+            t * t * (3.0 - 2.0 * t)
+        })?,
+        Mf::Sqrt => map_float(arg, f64::sqrt)?,
+        Mf::InverseSqrt => map_float(arg, |value| 1.0 / value.sqrt())?,
+        Mf::Inverse => {
+            let (columns, width) = matrix(arg)?;
+            Value::Composite(
+                inverse(columns)
+                    .into_iter()
+                    .map(|column| {
+                        Value::composite(column.into_iter().map(|v| from_float(v, width)))
+                    })
+                    .collect(),
+            )
+        }
+        Mf::Transpose => {
+            let columns = arg.components();
+            let rows = columns
+                .first()
+                .map_or(0, |column| column.components().len());
+            Value::Composite(
+                (0..rows)
+                    .map(|row| {
+                        Value::Composite(
+                            columns
+                                .iter()
+                                .map(|column| column.components()[row].clone())
+                                .collect(),
+                        )
+                    })
+                    .collect(),
+            )
+        }
+        Mf::Determinant => {
+            let (columns, width) = matrix(arg)?;
+            Value::Scalar(from_float(determinant(columns), width))
+        }
+        Mf::CountOneBits => map(arg, &mut |scalar| {
+            Ok(match scalar {
+                Scalar::I32(value) => Scalar::I32(value.count_ones() as i32),
+                Scalar::U32(value) => Scalar::U32(value.count_ones()),
+                _ => return Err(InterpretError::TypeMismatch),
+            })
+        })?,
+        Mf::ReverseBits => map(arg, &mut |scalar| {
+            Ok(match scalar {
+                Scalar::I32(value) => Scalar::I32(value.reverse_bits()),
+                Scalar::U32(value) => Scalar::U32(value.reverse_bits()),
+                _ => return Err(InterpretError::TypeMismatch),
+            })
+        })?,
+        Mf::ExtractBits => {
+            let (offset, count) = bitfield(arg1()?, arg2()?)?;
+            map(arg, &mut |scalar| {
+                let bits = int_bits(scalar)?;
+                if count == 0 {
+                    return Ok(from_int_bits(scalar, 0));
+                }
+                let shifted = bits << (32 - count - offset);
+                Ok(match scalar {
+                    Scalar::I32(_) => Scalar::I32((shifted as i32) >> (32 - count)),
+                    _ => Scalar::U32(shifted >> (32 - count)),
+                })
+            })?
+        }
+        Mf::InsertBits => {
+            let (offset, count) = bitfield(arg2()?, arg3()?)?;
+            let mask = if count == 0 {
+                0
+            } else {
+                (u32::MAX >> (32 - count)) << offset
+            };
+            zip(arg, arg1()?, &mut |scalar, insert| {
+                let bits = (int_bits(scalar)? & !mask) | ((int_bits(insert)? << offset) & mask);
+                Ok(from_int_bits(scalar, bits))
+            })?
+        }
+        Mf::FirstTrailingBit => map(arg, &mut |scalar| {
+            let bits = int_bits(scalar)?;
+            let index = if bits == 0 {
+                u32::MAX
+            } else {
+                bits.trailing_zeros()
+            };
+            Ok(from_int_bits(scalar, index))
+        })?,
+        Mf::FirstLeadingBit => map(arg, &mut |scalar| {
+            let bits = match scalar {
+                // negative values look for the leading zero bit instead
+                Scalar::I32(value) if value < 0 => !(value as u32),
+                _ => int_bits(scalar)?,
+            };
+            let index = if bits == 0 {
+                u32::MAX
+            } else {
+                31 - bits.leading_zeros()
+            };
+            Ok(from_int_bits(scalar, index))
+        })?,
+        Mf::Pack4x8snorm
+        | Mf::Pack4x8unorm
+        | Mf::Pack2x16snorm
+        | Mf::Pack2x16unorm
+        | Mf::Pack2x16float => pack(fun, arg)?,
+        Mf::Unpack4x8snorm | Mf::Unpack4x8unorm => unpack(fun, arg, 4)?,
+        Mf::Unpack2x16snorm | Mf::Unpack2x16unorm | Mf::Unpack2x16float => unpack(fun, arg, 2)?,
+    })
+}
+
+/// Apply an atomic function, returning the new contents of the memory
+/// and the result of the operation.
+pub(super) fn atomic(
+    fun: &crate::AtomicFunction,
+    original: Scalar,
+    value: Scalar,
+    compare: Option<Scalar>,
+) -> Result<(Scalar, Value), InterpretError> {
+    use crate::AtomicFunction as Af;
+    let new = match *fun {
+        Af::Add => binary_scalar(Bo::Add, original, value)?,
+        Af::Subtract => binary_scalar(Bo::Subtract, original, value)?,
+        Af::And => binary_scalar(Bo::And, original, value)?,
+        Af::ExclusiveOr => binary_scalar(Bo::ExclusiveOr, original, value)?,
+        Af::InclusiveOr => binary_scalar(Bo::InclusiveOr, original, value)?,
+        Af::Min => min_max(original, value, Ordering::Less)?,
+        Af::Max => min_max(original, value, Ordering::Greater)?,
+        Af::Exchange { compare: None } => value,
+        Af::Exchange { compare: Some(_) } => {
+            let exchanged = Some(original) == compare;
+            let flag = cast(
+                Scalar::Bool(exchanged),
+                original.kind(),
+                Some(original.width()),
+            )?;
+            let new = if exchanged { value } else { original };
+            return Ok((new, Value::composite(vec![original, flag])));
+        }
+    };
+    Ok((new, Value::Scalar(original)))
+}
+
+/// Identity element of a subgroup operation, shaped like `value`.
+///
+/// Exclusive scans of the first invocation in a subgroup produce it.
+pub(super) fn subgroup_identity(
+    op: crate::SubgroupOperation,
+    value: &Value,
+) -> Result<Value, InterpretError> {
+    use crate::SubgroupOperation as So;
+    map(value, &mut |scalar| {
+        Ok(match (op, scalar) {
+            (So::And, Scalar::Bool(_)) => Scalar::Bool(true),
+            (So::Or, Scalar::Bool(_)) | (So::Xor, Scalar::Bool(_)) => Scalar::Bool(false),
+            (So::Add, Scalar::I32(_)) | (So::Or, Scalar::I32(_)) | (So::Xor, Scalar::I32(_)) => {
+                Scalar::I32(0)
+            }
+            (So::Add, Scalar::U32(_))
+            | (So::Or, Scalar::U32(_))
+            | (So::Xor, Scalar::U32(_))
+            | (So::Max, Scalar::U32(_)) => Scalar::U32(0),
+            (So::Add, Scalar::F32(_)) => Scalar::F32(0.0),
+            (So::Add, Scalar::F64(_)) => Scalar::F64(0.0),
+            (So::Mul, Scalar::I32(_)) => Scalar::I32(1),
+            (So::Mul, Scalar::U32(_)) => Scalar::U32(1),
+            (So::Mul, Scalar::F32(_)) => Scalar::F32(1.0),
+            (So::Mul, Scalar::F64(_)) => Scalar::F64(1.0),
+            (So::And, Scalar::I32(_)) => Scalar::I32(-1),
+            (So::And, Scalar::U32(_)) | (So::Min, Scalar::U32(_)) => Scalar::U32(!0),
+            (So::Min, Scalar::I32(_)) => Scalar::I32(i32::MAX),
+            (So::Max, Scalar::I32(_)) => Scalar::I32(i32::MIN),
+            (So::Min, Scalar::F32(_)) => Scalar::F32(f32::INFINITY),
+            (So::Max, Scalar::F32(_)) => Scalar::F32(f32::NEG_INFINITY),
+            (So::Min, Scalar::F64(_)) => Scalar::F64(f64::INFINITY),
+            (So::Max, Scalar::F64(_)) => Scalar::F64(f64::NEG_INFINITY),
+            _ => return Err(InterpretError::TypeMismatch),
+        })
+    })
+}
+
+/// Integer coordinate of a texel, padded to three dimensions.
+pub(super) fn texel_coordinate(value: &Value) -> Result<[i64; 3], InterpretError> {
+    let mut coordinate = [0; 3];
+    match *value {
+        Value::Scalar(scalar) => coordinate[0] = integer(scalar)?,
+        Value::Composite(ref components) => {
+            for (target, component) in coordinate.iter_mut().zip(components) {
+                *target = integer(component.as_scalar().ok_or(InterpretError::TypeMismatch)?)?;
+            }
+        }
+    }
+    Ok(coordinate)
+}
+
+pub(super) fn texel(value: &Value) -> Result<[Scalar; 4], InterpretError> {
+    match *value.components() {
+        [Value::Scalar(x), Value::Scalar(y), Value::Scalar(z), Value::Scalar(w)] => {
+            Ok([x, y, z, w])
+        }
+        _ => Err(InterpretError::TypeMismatch),
+    }
+}
+
+impl<'a, 'r> Context<'a, 'r> {
+    pub(super) fn operand(
+        &mut self,
+        invocation: &mut Invocation<'a>,
+        expr: Handle<Expression>,
+    ) -> Result<Operand, InterpretError> {
+        if let Some(ref operand) = invocation.frame().values[expr.index()] {
+            return Ok(operand.clone());
+        }
+        let operand = self.evaluate(invocation, expr)?;
+        invocation.frame_mut().values[expr.index()] = Some(operand.clone());
+        Ok(operand)
+    }
+
+    pub(super) fn value(
+        &mut self,
+        invocation: &mut Invocation<'a>,
+        expr: Handle<Expression>,
+    ) -> Result<Value, InterpretError> {
+        match self.operand(invocation, expr)? {
+            Operand::Value(value) => Ok(value),
+            _ => Err(InterpretError::TypeMismatch),
+        }
+    }
+
+    pub(super) fn scalar(
+        &mut self,
+        invocation: &mut Invocation<'a>,
+        expr: Handle<Expression>,
+    ) -> Result<Scalar, InterpretError> {
+        self.value(invocation, expr)?
+            .as_scalar()
+            .ok_or(InterpretError::TypeMismatch)
+    }
+
+    pub(super) fn pointer(
+        &mut self,
+        invocation: &mut Invocation<'a>,
+        expr: Handle<Expression>,
+    ) -> Result<Pointer, InterpretError> {
+        match self.operand(invocation, expr)? {
+            Operand::Pointer(pointer) => Ok(pointer),
+            _ => Err(InterpretError::TypeMismatch),
+        }
+    }
+
+    pub(super) fn resource(
+        &mut self,
+        invocation: &mut Invocation<'a>,
+        expr: Handle<Expression>,
+    ) -> Result<Handle<GlobalVariable>, InterpretError> {
+        match self.operand(invocation, expr)? {
+            Operand::Resource(handle) => Ok(handle),
+            _ => Err(InterpretError::TypeMismatch),
+        }
+    }
+
+    fn access(
+        &mut self,
+        invocation: &mut Invocation<'a>,
+        base: Operand,
+        index: i64,
+    ) -> Result<Operand, InterpretError> {
+        match base {
+            Operand::Pointer(pointer) => {
+                let module = self.module;
+                let region_length = self.region(invocation, pointer.region)?.len();
+                memory::element(module, pointer, index, region_length).map(Operand::Pointer)
+            }
+            Operand::Value(Value::Composite(components)) => {
+                let length = components.len() as u32;
+                if index < 0 || index >= length as i64 {
+                    return Err(InterpretError::IndexOutOfBounds { index, length });
+                }
+                Ok(Operand::Value(components[index as usize].clone()))
+            }
+            Operand::Resource(_) => Err(InterpretError::Unsupported("binding arrays")),
+            _ => Err(InterpretError::TypeMismatch),
+        }
+    }
+
+    /// Parameters of the image behind `expr`.
+    fn image_type(
+        &mut self,
+        invocation: &mut Invocation<'a>,
+        expr: Handle<Expression>,
+    ) -> Result<
+        (
+            Handle<GlobalVariable>,
+            crate::ImageDimension,
+            crate::ImageClass,
+        ),
+        InterpretError,
+    > {
+        let handle = self.resource(invocation, expr)?;
+        let ty = self.module.global_variables[handle].ty;
+        match self.module.types[ty].inner {
+            crate::TypeInner::Image { dim, class, .. } => Ok((handle, dim, class)),
+            _ => Err(InterpretError::TypeMismatch),
+        }
+    }
+
+    fn texel_result(class: crate::ImageClass, texel: [Scalar; 4]) -> Value {
+        match class {
+            crate::ImageClass::Depth => Value::Scalar(texel[0]),
+            _ => Value::composite(texel.iter().cloned()),
+        }
+    }
+
+    pub(super) fn evaluate(
+        &mut self,
+        invocation: &mut Invocation<'a>,
+        expr: Handle<Expression>,
+    ) -> Result<Operand, InterpretError> {
+        let module = self.module;
+        let function = invocation.frame().function;
+        let value = match function.expressions[expr] {
+            Expression::Access { base, index } => {
+                let base = self.operand(invocation, base)?;
+                let index = integer(self.scalar(invocation, index)?)?;
+                return self.access(invocation, base, index);
+            }
+            Expression::AccessIndex { base, index } => {
+                let base = self.operand(invocation, base)?;
+                return self.access(invocation, base, index as i64);
+            }
+            Expression::Constant(handle) => constant_value(module, handle)?,
+            Expression::Splat { size, value } => {
+                let value = self.value(invocation, value)?;
+                Value::Composite(vec![value; size as usize])
+            }
+            Expression::Swizzle {
+                size,
+                vector,
+                pattern,
+            } => {
+                let vector = self.value(invocation, vector)?;
+                let components = vector.components();
+                pattern[..size as usize]
+                    .iter()
+                    .map(|&component| {
+                        components
+                            .get(component as usize)
+                            .cloned()
+                            .ok_or(InterpretError::TypeMismatch)
+                    })
+                    .collect::<Result<_, _>>()
+                    .map(Value::Composite)?
+            }
+            Expression::Compose { ty, ref components } => {
+                let is_vector = match module.types[ty].inner {
+                    crate::TypeInner::Vector { .. } => true,
+                    _ => false,
+                };
+                let mut values = Vec::with_capacity(components.len());
+                for &component in components {
+                    match self.value(invocation, component)? {
+                        Value::Composite(inner) if is_vector => values.extend(inner),
+                        value => values.push(value),
+                    }
+                }
+                Value::Composite(values)
+            }
+            Expression::FunctionArgument(index) => {
+                return Ok(invocation.frame().arguments[index as usize].clone())
+            }
+            Expression::GlobalVariable(handle) => {
+                let var = &module.global_variables[handle];
+                return Ok(match var.class {
+                    crate::StorageClass::Handle => Operand::Resource(handle),
+                    _ => Operand::Pointer(Pointer {
+                        region: Region::Global(handle),
+                        offset: 0,
+                        pointee: Pointee::Type(var.ty),
+                    }),
+                });
+            }
+            Expression::LocalVariable(variable) => {
+                return Ok(Operand::Pointer(Pointer {
+                    region: Region::Local {
+                        frame: invocation.frames.len() - 1,
+                        variable,
+                    },
+                    offset: 0,
+                    pointee: Pointee::Type(function.local_variables[variable].ty),
+                }))
+            }
+            Expression::Load { pointer } => {
+                let pointer = self.pointer(invocation, pointer)?;
+                self.load(invocation, pointer)?
+            }
+            Expression::ImageSample {
+                image,
+                sampler: _,
+                coordinate,
+                array_index,
+                offset,
+                level: _,
+                depth_ref,
+                gather,
+            } => {
+                let (image, dim, class) = self.image_type(invocation, image)?;
+                if dim == crate::ImageDimension::Cube {
+                    return Err(InterpretError::Unsupported("sampling of cube images"));
+                }
+                let (coordinate, _) = floats(&self.value(invocation, coordinate)?)?;
+                let offset = match offset {
+                    Some(offset) => texel_coordinate(&constant_value(module, offset)?)?,
+                    None => [0; 3],
+                };
+                let layer = match array_index {
+                    Some(expr) => integer(self.scalar(invocation, expr)?)?,
+                    None => 0,
+                };
+                let depth_ref = match depth_ref {
+                    Some(expr) => Some(float(self.scalar(invocation, expr)?)?),
+                    None => None,
+                };
+                let texture = self.texture(image)?;
+                let layer = layer.max(0).min(texture.layers as i64 - 1);
+                // Fetches the texel at `corner` relative to the texel containing
+                // the coordinate shifted by `shift`, clamping to the edges.
+                let fetch = |shift: f64, corner: [i64; 2]| {
+                    let mut texel_coordinate = [0; 3];
+                    for (axis, &value) in coordinate.iter().enumerate() {
+                        let size = texture.size[axis] as i64;
+                        let texel = (value * size as f64 - shift).floor() as i64
+                            + offset[axis]
+                            + corner.get(axis).cloned().unwrap_or(0);
+                        texel_coordinate[axis] = texel.max(0).min(size - 1);
+                    }
+                    let index = texture.index(texel_coordinate, layer, 0).ok_or(
+                        InterpretError::TexelOutOfBounds {
+                            coordinate: texel_coordinate,
+                            layer,
+                            sample: 0,
+                        },
+                    )?;
+                    Ok(texture.texels[index])
+                };
+                let compare = |texel: [Scalar; 4], reference: f64| -> Result<Scalar, _> {
+                    let passed = reference <= float(texel[0])?;
+                    Ok(Scalar::F32(if passed { 1.0 } else { 0.0 }))
+                };
+                match gather {
+                    Some(component) => {
+                        // The four texels of the bilinear footprint, in the
+                        // order defined by `OpImageGather`.
+                        let mut components = Vec::with_capacity(4);
+                        for &corner in [[0, 1], [1, 1], [1, 0], [0, 0]].iter() {
+                            let texel = fetch(0.5, corner)?;
+                            components.push(match depth_ref {
+                                Some(reference) => compare(texel, reference)?,
+                                None => texel[component as usize],
+                            });
+                        }
+                        Value::composite(components)
+                    }
+                    None => {
+                        let texel = fetch(0.0, [0, 0])?;
+                        match depth_ref {
+                            Some(reference) => Value::Scalar(compare(texel, reference)?),
+                            None => Self::texel_result(class, texel),
+                        }
+                    }
+                }
+            }
+            Expression::ImageLoad {
+                image,
+                coordinate,
+                array_index,
+                index,
+            } => {
+                let (image, _, class) = self.image_type(invocation, image)?;
+                let coordinate = texel_coordinate(&self.value(invocation, coordinate)?)?;
+                let layer = match array_index {
+                    Some(expr) => integer(self.scalar(invocation, expr)?)?,
+                    None => 0,
+                };
+                let index = match index {
+                    Some(expr) => integer(self.scalar(invocation, expr)?)?,
+                    None => 0,
+                };
+                let sample = match class {
+                    crate::ImageClass::Sampled { multi: true, .. } => index,
+                    _ if index != 0 => return Err(InterpretError::Unsupported("mipmap levels")),
+                    _ => 0,
+                };
+                let texture = self.texture(image)?;
+                let texel_index = texture.index(coordinate, layer, sample).ok_or(
+                    InterpretError::TexelOutOfBounds {
+                        coordinate,
+                        layer,
+                        sample,
+                    },
+                )?;
+                Self::texel_result(class, texture.texels[texel_index])
+            }
+            Expression::ImageQuery { image, query } => {
+                let (image, dim, _) = self.image_type(invocation, image)?;
+                let level = match query {
+                    crate::ImageQuery::Size { level: Some(expr) } => {
+                        integer(self.scalar(invocation, expr)?)? as u32
+                    }
+                    _ => 0,
+                };
+                let texture = self.texture(image)?;
+                let extent = |axis: usize| Value::from((texture.size[axis] >> level).max(1) as i32);
+                match query {
+                    crate::ImageQuery::Size { .. } => match dim {
+                        crate::ImageDimension::D1 => extent(0),
+                        crate::ImageDimension::D2 | crate::ImageDimension::Cube => {
+                            Value::Composite(vec![extent(0), extent(1)])
+                        }
+                        crate::ImageDimension::D3 => {
+                            Value::Composite(vec![extent(0), extent(1), extent(2)])
+                        }
+                    },
+                    crate::ImageQuery::NumLevels => Value::from(1),
+                    crate::ImageQuery::NumLayers => Value::from(texture.layers as i32),
+                    crate::ImageQuery::NumSamples => Value::from(texture.samples as i32),
+                }
+            }
+            Expression::Unary { op, expr } => {
+                let value = self.value(invocation, expr)?;
+                map(&value, &mut |scalar| {
+                    Ok(match (op, scalar) {
+                        (Uo::Negate, Scalar::I32(value)) => Scalar::I32(value.wrapping_neg()),
+                        (Uo::Negate, Scalar::U32(value)) => Scalar::U32(value.wrapping_neg()),
+                        (Uo::Negate, Scalar::F32(value)) => Scalar::F32(-value),
+                        (Uo::Negate, Scalar::F64(value)) => Scalar::F64(-value),
+                        (Uo::Not, Scalar::Bool(value)) => Scalar::Bool(!value),
+                        (Uo::Not, Scalar::I32(value)) => Scalar::I32(!value),
+                        (Uo::Not, Scalar::U32(value)) => Scalar::U32(!value),
+                        _ => return Err(InterpretError::TypeMismatch),
+                    })
+                })?
+            }
+            Expression::Binary { op, left, right } => {
+                let left = self.value(invocation, left)?;
+                let right = self.value(invocation, right)?;
+                binary(op, &left, &right)?
+            }
+            Expression::Select {
+                condition,
+                accept,
+                reject,
+            } => {
+                let condition = self.value(invocation, condition)?;
+                let accept = self.value(invocation, accept)?;
+                let reject = self.value(invocation, reject)?;
+                match condition {
+                    Value::Scalar(Scalar::Bool(true)) => accept,
+                    Value::Scalar(Scalar::Bool(false)) => reject,
+                    _ => zip3(
+                        &condition,
+                        &accept,
+                        &reject,
+                        &mut |condition, a, b| match condition {
+                            Scalar::Bool(true) => Ok(a),
+                            Scalar::Bool(false) => Ok(b),
+                            _ => Err(InterpretError::TypeMismatch),
+                        },
+                    )?,
+                }
+            }
+            // A single invocation doesn't have neighbors to compare with.
+            Expression::Derivative { axis: _, expr } => {
+                let value = self.value(invocation, expr)?;
+                map_float(&value, |_| 0.0)?
+            }
+            Expression::Relational { fun, argument } => {
+                let argument = self.value(invocation, argument)?;
+                relational(fun, &argument)?
+            }
+            Expression::Math {
+                fun,
+                arg,
+                arg1,
+                arg2,
+                arg3,
+            } => {
+                let arg = self.value(invocation, arg)?;
+                match (fun, arg1) {
+                    // the second result is written through a pointer
+                    (Mf::Modf, Some(pointer)) | (Mf::Frexp, Some(pointer)) => {
+                        let pointer = self.pointer(invocation, pointer)?;
+                        let other = if fun == Mf::Modf {
+                            map_float(&arg, f64::trunc)?
+                        } else {
+                            map(&arg, &mut |scalar| Ok(Scalar::I32(frexp(float(scalar)?).1)))?
+                        };
+                        self.store(invocation, pointer, &other)?;
+                        math(fun, &arg, None, None, None)?
+                    }
+                    _ => {
+                        let arg1 = match arg1 {
+                            Some(expr) => Some(self.value(invocation, expr)?),
+                            None => None,
+                        };
+                        let arg2 = match arg2 {
+                            Some(expr) => Some(self.value(invocation, expr)?),
+                            None => None,
+                        };
+                        let arg3 = match arg3 {
+                            Some(expr) => Some(self.value(invocation, expr)?),
+                            None => None,
+                        };
+                        math(fun, &arg, arg1.as_ref(), arg2.as_ref(), arg3.as_ref())?
+                    }
+                }
+            }
+            Expression::As {
+                expr,
+                kind,
+                convert,
+            } => {
+                let value = self.value(invocation, expr)?;
+                map(&value, &mut |scalar| cast(scalar, kind, convert))?
+            }
+            Expression::ArrayLength(expr) => {
+                let pointer = self.pointer(invocation, expr)?;
+                let region_length = self.region(invocation, pointer.region)?.len();
+                Value::from(memory::dynamic_length(module, pointer, region_length)?)
+            }
+            // These are filled in by the statements producing them.
+            Expression::Call(_)
+            | Expression::AtomicResult { .. }
+            | Expression::SubgroupBallotResult
+            | Expression::SubgroupOperationResult { .. } => {
+                return Err(InterpretError::TypeMismatch)
+            }
+        };
+        Ok(Operand::Value(value))
+    }
+}
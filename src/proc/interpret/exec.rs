@@ -0,0 +1,532 @@
+use super::{
+    eval,
+    memory::{self, Pointee, Pointer, Region},
+    InterpretError, Scalar, Texture, Value,
+};
+use crate::{
+    arena::Handle, Block, Expression, FastHashMap, Function, GlobalVariable, Module,
+    ResourceBinding, Statement, StorageClass, SwitchCase,
+};
+
+/// Result of evaluating an expression.
+#[derive(Clone, Debug, PartialEq)]
+pub(super) enum Operand {
+    Value(Value),
+    Pointer(Pointer),
+    /// An image or a sampler.
+    Resource(Handle<GlobalVariable>),
+}
+
+#[derive(Debug)]
+enum CursorKind<'a> {
+    Block,
+    Loop {
+        body: &'a Block,
+        continuing: &'a Block,
+        in_continuing: bool,
+    },
+    Switch {
+        /// Cases following the one being executed.
+        rest: &'a [SwitchCase],
+        default: &'a Block,
+        fall_through: bool,
+    },
+}
+
+/// Position in a block being executed.
+#[derive(Debug)]
+struct Cursor<'a> {
+    statements: &'a [Statement],
+    index: usize,
+    kind: CursorKind<'a>,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(block: &'a Block) -> Self {
+        Cursor {
+            statements: block,
+            index: 0,
+            kind: CursorKind::Block,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct Frame<'a> {
+    pub function: &'a Function,
+    pub arguments: Vec<Operand>,
+    /// Memory of each local variable.
+    pub locals: Vec<Vec<u8>>,
+    /// Evaluated expressions.
+    pub values: Vec<Option<Operand>>,
+    cursors: Vec<Cursor<'a>>,
+    /// Expression of the caller that receives the returned value.
+    result: Option<Handle<Expression>>,
+}
+
+impl<'a> Frame<'a> {
+    fn new(
+        module: &Module,
+        function: &'a Function,
+        arguments: Vec<Operand>,
+        result: Option<Handle<Expression>>,
+    ) -> Result<Self, InterpretError> {
+        let locals = function
+            .local_variables
+            .iter()
+            .map(|(_, local)| allocate(module, local.ty, local.init))
+            .collect::<Result<_, _>>()?;
+        Ok(Frame {
+            function,
+            arguments,
+            locals,
+            values: vec![None; function.expressions.len()],
+            cursors: vec![Cursor::new(&function.body)],
+            result,
+        })
+    }
+}
+
+/// Allocate memory for a variable, filled with its initializer or zeros.
+fn allocate(
+    module: &Module,
+    ty: Handle<crate::Type>,
+    init: Option<Handle<crate::Constant>>,
+) -> Result<Vec<u8>, InterpretError> {
+    let mut bytes = vec![0; module.types[ty].inner.span(&module.constants) as usize];
+    if let Some(init) = init {
+        let value = eval::constant_value(module, init)?;
+        memory::write(module, &mut bytes, 0, Pointee::Type(ty), &value)?;
+    }
+    Ok(bytes)
+}
+
+/// State of a single shader invocation.
+#[derive(Debug)]
+pub(super) struct Invocation<'a> {
+    pub frames: Vec<Frame<'a>>,
+    /// Memory of the private global variables.
+    private: FastHashMap<Handle<GlobalVariable>, Vec<u8>>,
+    steps: u64,
+}
+
+pub(super) enum Status {
+    /// The invocation is waiting at a control barrier.
+    Barrier,
+    Finished(Option<Value>),
+    Killed,
+}
+
+enum Next<'a> {
+    Statement(&'a Statement),
+    /// The cursors advanced without reaching a statement.
+    Advanced,
+    /// The body of the current function is over.
+    FunctionEnd,
+}
+
+impl<'a> Invocation<'a> {
+    pub fn new(
+        module: &Module,
+        function: &'a Function,
+        arguments: Vec<Value>,
+    ) -> Result<Self, InterpretError> {
+        let mut private = FastHashMap::default();
+        for (handle, var) in module.global_variables.iter() {
+            if var.class == StorageClass::Private {
+                private.insert(handle, allocate(module, var.ty, var.init)?);
+            }
+        }
+        let arguments = arguments.into_iter().map(Operand::Value).collect();
+        Ok(Invocation {
+            frames: vec![Frame::new(module, function, arguments, None)?],
+            private,
+            steps: 0,
+        })
+    }
+
+    pub fn frame(&self) -> &Frame<'a> {
+        self.frames.last().unwrap()
+    }
+
+    pub fn frame_mut(&mut self) -> &mut Frame<'a> {
+        self.frames.last_mut().unwrap()
+    }
+
+    fn next(&mut self) -> Next<'a> {
+        let frame = self.frame_mut();
+        let cursor = match frame.cursors.last_mut() {
+            Some(cursor) => cursor,
+            None => return Next::FunctionEnd,
+        };
+        let statements = cursor.statements;
+        if let Some(statement) = statements.get(cursor.index) {
+            cursor.index += 1;
+            return Next::Statement(statement);
+        }
+
+        cursor.index = 0;
+        match cursor.kind {
+            CursorKind::Block => {
+                frame.cursors.pop();
+            }
+            CursorKind::Loop {
+                body,
+                continuing,
+                ref mut in_continuing,
+            } => {
+                cursor.statements = if *in_continuing { body } else { continuing };
+                *in_continuing = !*in_continuing;
+            }
+            CursorKind::Switch {
+                ref mut rest,
+                default,
+                ref mut fall_through,
+            } => {
+                if !*fall_through {
+                    frame.cursors.pop();
+                } else if let Some((case, others)) = rest.split_first() {
+                    cursor.statements = &case.body;
+                    *rest = others;
+                    *fall_through = case.fall_through;
+                } else {
+                    cursor.statements = default;
+                    *fall_through = false;
+                }
+            }
+        }
+        Next::Advanced
+    }
+
+    /// Leave the current function, passing `value` to the caller.
+    fn finish_call(&mut self, value: Option<Value>) -> Option<Status> {
+        let frame = self.frames.pop().unwrap();
+        if self.frames.is_empty() {
+            return Some(Status::Finished(value));
+        }
+        if let (Some(result), Some(value)) = (frame.result, value) {
+            self.frame_mut().values[result.index()] = Some(Operand::Value(value));
+        }
+        None
+    }
+}
+
+/// Resources shared by all the invocations of a dispatch.
+pub(super) struct Context<'a, 'r> {
+    pub module: &'a Module,
+    pub buffers: &'r mut FastHashMap<ResourceBinding, Vec<u8>>,
+    pub textures: &'r mut FastHashMap<ResourceBinding, Texture>,
+    pub push_constants: &'r mut Vec<u8>,
+    pub workgroup: FastHashMap<Handle<GlobalVariable>, Vec<u8>>,
+    pub step_limit: u64,
+}
+
+impl<'a, 'r> Context<'a, 'r> {
+    /// Zero the memory of the workgroup variables for a new workgroup.
+    pub fn reset_workgroup(&mut self) -> Result<(), InterpretError> {
+        self.workgroup.clear();
+        for (handle, var) in self.module.global_variables.iter() {
+            if var.class == StorageClass::WorkGroup {
+                let bytes = allocate(self.module, var.ty, None)?;
+                self.workgroup.insert(handle, bytes);
+            }
+        }
+        Ok(())
+    }
+
+    fn binding(
+        &self,
+        handle: Handle<GlobalVariable>,
+    ) -> Result<&'a ResourceBinding, InterpretError> {
+        self.module.global_variables[handle]
+            .binding
+            .as_ref()
+            .ok_or(InterpretError::MissingBinding(handle))
+    }
+
+    pub fn region<'s>(
+        &'s mut self,
+        invocation: &'s mut Invocation<'a>,
+        region: Region,
+    ) -> Result<&'s mut Vec<u8>, InterpretError> {
+        let handle = match region {
+            Region::Global(handle) => handle,
+            Region::Local { frame, variable } => {
+                return Ok(&mut invocation.frames[frame].locals[variable.index()])
+            }
+        };
+        match self.module.global_variables[handle].class {
+            StorageClass::Private => Ok(invocation.private.get_mut(&handle).unwrap()),
+            StorageClass::WorkGroup => Ok(self.workgroup.get_mut(&handle).unwrap()),
+            StorageClass::PushConstant => Ok(self.push_constants),
+            StorageClass::Uniform | StorageClass::Storage => {
+                let binding = self.binding(handle)?;
+                self.buffers
+                    .get_mut(binding)
+                    .ok_or_else(|| InterpretError::MissingBuffer(binding.clone()))
+            }
+            StorageClass::Function | StorageClass::Handle => Err(InterpretError::TypeMismatch),
+        }
+    }
+
+    pub fn load(
+        &mut self,
+        invocation: &mut Invocation<'a>,
+        pointer: Pointer,
+    ) -> Result<Value, InterpretError> {
+        let module = self.module;
+        let bytes = self.region(invocation, pointer.region)?;
+        memory::read(module, bytes, pointer.offset, pointer.pointee)
+    }
+
+    pub fn store(
+        &mut self,
+        invocation: &mut Invocation<'a>,
+        pointer: Pointer,
+        value: &Value,
+    ) -> Result<(), InterpretError> {
+        let module = self.module;
+        let bytes = self.region(invocation, pointer.region)?;
+        memory::write(module, bytes, pointer.offset, pointer.pointee, value)
+    }
+
+    pub fn texture(&self, handle: Handle<GlobalVariable>) -> Result<&Texture, InterpretError> {
+        let binding = self.binding(handle)?;
+        self.textures
+            .get(binding)
+            .ok_or_else(|| InterpretError::MissingTexture(binding.clone()))
+    }
+
+    fn texture_mut(
+        &mut self,
+        handle: Handle<GlobalVariable>,
+    ) -> Result<&mut Texture, InterpretError> {
+        let binding = self.binding(handle)?;
+        self.textures
+            .get_mut(binding)
+            .ok_or_else(|| InterpretError::MissingTexture(binding.clone()))
+    }
+
+    /// Execute statements until the invocation finishes or hits a barrier.
+    pub fn run(&mut self, invocation: &mut Invocation<'a>) -> Result<Status, InterpretError> {
+        loop {
+            invocation.steps += 1;
+            if invocation.steps > self.step_limit {
+                return Err(InterpretError::StepLimitExceeded(self.step_limit));
+            }
+            let statement = match invocation.next() {
+                Next::Statement(statement) => statement,
+                Next::Advanced => continue,
+                Next::FunctionEnd => match invocation.finish_call(None) {
+                    Some(status) => return Ok(status),
+                    None => continue,
+                },
+            };
+
+            match *statement {
+                Statement::Emit(ref range) => {
+                    for handle in range.clone() {
+                        let operand = self.evaluate(invocation, handle)?;
+                        invocation.frame_mut().values[handle.index()] = Some(operand);
+                    }
+                }
+                Statement::Block(ref block) => {
+                    invocation.frame_mut().cursors.push(Cursor::new(block));
+                }
+                Statement::If {
+                    condition,
+                    ref accept,
+                    ref reject,
+                } => {
+                    let block = match self.scalar(invocation, condition)? {
+                        Scalar::Bool(true) => accept,
+                        Scalar::Bool(false) => reject,
+                        _ => return Err(InterpretError::TypeMismatch),
+                    };
+                    invocation.frame_mut().cursors.push(Cursor::new(block));
+                }
+                Statement::Switch {
+                    selector,
+                    ref cases,
+                    ref default,
+                } => {
+                    let selector = match self.scalar(invocation, selector)? {
+                        Scalar::I32(value) => value,
+                        Scalar::U32(value) => value as i32,
+                        _ => return Err(InterpretError::TypeMismatch),
+                    };
+                    let cursor = match cases.iter().position(|case| case.value == selector) {
+                        Some(index) => Cursor {
+                            statements: &cases[index].body,
+                            index: 0,
+                            kind: CursorKind::Switch {
+                                rest: &cases[index + 1..],
+                                default,
+                                fall_through: cases[index].fall_through,
+                            },
+                        },
+                        None => Cursor {
+                            statements: default,
+                            index: 0,
+                            kind: CursorKind::Switch {
+                                rest: &[],
+                                default,
+                                fall_through: false,
+                            },
+                        },
+                    };
+                    invocation.frame_mut().cursors.push(cursor);
+                }
+                Statement::Loop {
+                    ref body,
+                    ref continuing,
+                } => {
+                    invocation.frame_mut().cursors.push(Cursor {
+                        statements: body,
+                        index: 0,
+                        kind: CursorKind::Loop {
+                            body,
+                            continuing,
+                            in_continuing: false,
+                        },
+                    });
+                }
+                Statement::Break => {
+                    let cursors = &mut invocation.frame_mut().cursors;
+                    while let Some(cursor) = cursors.pop() {
+                        if let CursorKind::Loop { .. } | CursorKind::Switch { .. } = cursor.kind {
+                            break;
+                        }
+                    }
+                }
+                Statement::Continue => {
+                    let cursors = &mut invocation.frame_mut().cursors;
+                    while let Some(cursor) = cursors.last_mut() {
+                        if let CursorKind::Loop {
+                            continuing,
+                            ref mut in_continuing,
+                            ..
+                        } = cursor.kind
+                        {
+                            cursor.statements = continuing;
+                            cursor.index = 0;
+                            *in_continuing = true;
+                            break;
+                        }
+                        cursors.pop();
+                    }
+                }
+                Statement::Return { value } => {
+                    let value = match value {
+                        Some(expr) => Some(self.value(invocation, expr)?),
+                        None => None,
+                    };
+                    if let Some(status) = invocation.finish_call(value) {
+                        return Ok(status);
+                    }
+                }
+                Statement::Kill => return Ok(Status::Killed),
+                Statement::Barrier(_) => return Ok(Status::Barrier),
+                Statement::Store { pointer, value } => {
+                    let pointer = self.pointer(invocation, pointer)?;
+                    let value = self.value(invocation, value)?;
+                    self.store(invocation, pointer, &value)?;
+                }
+                Statement::ImageStore {
+                    image,
+                    coordinate,
+                    array_index,
+                    value,
+                } => {
+                    let image = self.resource(invocation, image)?;
+                    let coordinate = eval::texel_coordinate(&self.value(invocation, coordinate)?)?;
+                    let layer = match array_index {
+                        Some(expr) => eval::integer(self.scalar(invocation, expr)?)?,
+                        None => 0,
+                    };
+                    let texel = eval::texel(&self.value(invocation, value)?)?;
+                    let texture = self.texture_mut(image)?;
+                    let index = texture.index(coordinate, layer, 0).ok_or(
+                        InterpretError::TexelOutOfBounds {
+                            coordinate,
+                            layer,
+                            sample: 0,
+                        },
+                    )?;
+                    texture.texels[index] = texel;
+                }
+                Statement::Call {
+                    function,
+                    ref arguments,
+                    result,
+                } => {
+                    let arguments = arguments
+                        .iter()
+                        .map(|&arg| self.operand(invocation, arg))
+                        .collect::<Result<_, _>>()?;
+                    let function = &self.module.functions[function];
+                    let frame = Frame::new(self.module, function, arguments, result)?;
+                    invocation.frames.push(frame);
+                }
+                Statement::Atomic {
+                    pointer,
+                    ref fun,
+                    value,
+                    result,
+                } => {
+                    let pointer = self.pointer(invocation, pointer)?;
+                    let value = self.scalar(invocation, value)?;
+                    let compare = match *fun {
+                        crate::AtomicFunction::Exchange {
+                            compare: Some(compare),
+                        } => Some(self.scalar(invocation, compare)?),
+                        _ => None,
+                    };
+                    let original = match self.load(invocation, pointer)? {
+                        Value::Scalar(scalar) => scalar,
+                        Value::Composite(_) => return Err(InterpretError::TypeMismatch),
+                    };
+                    let (new, returned) = eval::atomic(fun, original, value, compare)?;
+                    self.store(invocation, pointer, &Value::Scalar(new))?;
+                    invocation.frame_mut().values[result.index()] = Some(Operand::Value(returned));
+                }
+                // Every invocation forms a subgroup of its own.
+                Statement::SubgroupBallot { result, predicate } => {
+                    let active = match predicate {
+                        Some(predicate) => match self.scalar(invocation, predicate)? {
+                            Scalar::Bool(value) => value,
+                            _ => return Err(InterpretError::TypeMismatch),
+                        },
+                        None => true,
+                    };
+                    let ballot = Value::composite(vec![active as u32, 0, 0, 0]);
+                    invocation.frame_mut().values[result.index()] = Some(Operand::Value(ballot));
+                }
+                Statement::SubgroupCollectiveOperation {
+                    op,
+                    collective_op,
+                    argument,
+                    result,
+                } => {
+                    let value = self.value(invocation, argument)?;
+                    let value = match collective_op {
+                        crate::CollectiveOperation::Reduce
+                        | crate::CollectiveOperation::InclusiveScan => value,
+                        crate::CollectiveOperation::ExclusiveScan => {
+                            eval::subgroup_identity(op, &value)?
+                        }
+                    };
+                    invocation.frame_mut().values[result.index()] = Some(Operand::Value(value));
+                }
+                Statement::SubgroupGather {
+                    mode: _,
+                    argument,
+                    result,
+                } => {
+                    let value = self.value(invocation, argument)?;
+                    invocation.frame_mut().values[result.index()] = Some(Operand::Value(value));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,350 @@
+use super::{InterpretError, Scalar, Value};
+use crate::{
+    arena::Handle, ArraySize, Bytes, Module, ScalarKind, StructMember, Type, TypeInner, VectorSize,
+};
+use std::convert::TryInto;
+
+/// Byte buffer that a pointer refers into.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) enum Region {
+    Global(Handle<crate::GlobalVariable>),
+    Local {
+        /// Index of the call frame owning the variable.
+        frame: usize,
+        variable: Handle<crate::LocalVariable>,
+    },
+}
+
+/// Type of the data behind a pointer.
+///
+/// Components of vectors and columns of matrices don't have types
+/// in the arena, so they are described in place.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) enum Pointee {
+    Type(Handle<Type>),
+    Scalar {
+        kind: ScalarKind,
+        width: Bytes,
+    },
+    Vector {
+        size: VectorSize,
+        kind: ScalarKind,
+        width: Bytes,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) struct Pointer {
+    pub region: Region,
+    pub offset: u32,
+    pub pointee: Pointee,
+}
+
+enum Shape<'a> {
+    Scalar {
+        kind: ScalarKind,
+        width: Bytes,
+    },
+    Vector {
+        size: VectorSize,
+        kind: ScalarKind,
+        width: Bytes,
+    },
+    Matrix {
+        columns: VectorSize,
+        rows: VectorSize,
+        width: Bytes,
+    },
+    Array {
+        base: Handle<Type>,
+        size: ArraySize,
+        stride: u32,
+    },
+    Struct(&'a [StructMember]),
+}
+
+fn shape(module: &Module, pointee: Pointee) -> Result<Shape<'_>, InterpretError> {
+    Ok(match pointee {
+        Pointee::Type(ty) => match module.types[ty].inner {
+            TypeInner::Scalar { kind, width } | TypeInner::Atomic { kind, width } => {
+                Shape::Scalar { kind, width }
+            }
+            TypeInner::Vector { size, kind, width } => Shape::Vector { size, kind, width },
+            TypeInner::Matrix {
+                columns,
+                rows,
+                width,
+            } => Shape::Matrix {
+                columns,
+                rows,
+                width,
+            },
+            TypeInner::Array { base, size, stride } => Shape::Array { base, size, stride },
+            TypeInner::Struct { ref members, .. } => Shape::Struct(members),
+            _ => return Err(InterpretError::Unsupported("opaque types in memory")),
+        },
+        Pointee::Scalar { kind, width } => Shape::Scalar { kind, width },
+        Pointee::Vector { size, kind, width } => Shape::Vector { size, kind, width },
+    })
+}
+
+/// Number of elements in an array starting at `offset` of a region.
+fn array_length(
+    module: &Module,
+    size: ArraySize,
+    stride: u32,
+    offset: u32,
+    region_length: usize,
+) -> Result<u32, InterpretError> {
+    match size {
+        ArraySize::Constant(handle) => module.constants[handle]
+            .to_array_length()
+            .ok_or(InterpretError::TypeMismatch),
+        ArraySize::Dynamic => {
+            Ok((region_length.saturating_sub(offset as usize) / stride.max(1) as usize) as u32)
+        }
+    }
+}
+
+fn check_index(index: i64, length: u32) -> Result<u32, InterpretError> {
+    if index >= 0 && index < length as i64 {
+        Ok(index as u32)
+    } else {
+        Err(InterpretError::IndexOutOfBounds { index, length })
+    }
+}
+
+/// Pointer to the element `index` of the composite behind `pointer`.
+pub(super) fn element(
+    module: &Module,
+    pointer: Pointer,
+    index: i64,
+    region_length: usize,
+) -> Result<Pointer, InterpretError> {
+    let (offset, pointee) = match shape(module, pointer.pointee)? {
+        Shape::Scalar { .. } => return Err(InterpretError::TypeMismatch),
+        Shape::Vector { size, kind, width } => {
+            let index = check_index(index, size as u32)?;
+            (index * width as u32, Pointee::Scalar { kind, width })
+        }
+        Shape::Matrix {
+            columns,
+            rows,
+            width,
+        } => {
+            let index = check_index(index, columns as u32)?;
+            let pointee = Pointee::Vector {
+                size: rows,
+                kind: ScalarKind::Float,
+                width,
+            };
+            (index * rows as u32 * width as u32, pointee)
+        }
+        Shape::Array { base, size, stride } => {
+            let length = array_length(module, size, stride, pointer.offset, region_length)?;
+            let index = check_index(index, length)?;
+            (index * stride, Pointee::Type(base))
+        }
+        Shape::Struct(members) => {
+            let index = check_index(index, members.len() as u32)?;
+            let member = &members[index as usize];
+            (member.offset, Pointee::Type(member.ty))
+        }
+    };
+    Ok(Pointer {
+        region: pointer.region,
+        offset: pointer.offset + offset,
+        pointee,
+    })
+}
+
+/// Number of elements in the runtime-sized array behind `pointer`.
+pub(super) fn dynamic_length(
+    module: &Module,
+    pointer: Pointer,
+    region_length: usize,
+) -> Result<u32, InterpretError> {
+    match shape(module, pointer.pointee)? {
+        Shape::Array { size, stride, .. } => {
+            array_length(module, size, stride, pointer.offset, region_length)
+        }
+        _ => Err(InterpretError::TypeMismatch),
+    }
+}
+
+fn scalar_bytes(bytes: &[u8], offset: u32, width: Bytes) -> Result<&[u8], InterpretError> {
+    let start = offset as usize;
+    bytes
+        .get(start..start + width as usize)
+        .ok_or(InterpretError::OutOfBounds {
+            offset,
+            size: width as u32,
+            length: bytes.len(),
+        })
+}
+
+fn read_scalar(
+    bytes: &[u8],
+    offset: u32,
+    kind: ScalarKind,
+    width: Bytes,
+) -> Result<Scalar, InterpretError> {
+    let chunk = scalar_bytes(bytes, offset, width)?;
+    Ok(match (kind, width) {
+        (ScalarKind::Bool, _) => Scalar::Bool(chunk.iter().any(|&byte| byte != 0)),
+        (ScalarKind::Sint, 4) => Scalar::I32(i32::from_le_bytes(chunk.try_into().unwrap())),
+        (ScalarKind::Uint, 4) => Scalar::U32(u32::from_le_bytes(chunk.try_into().unwrap())),
+        (ScalarKind::Float, 4) => Scalar::F32(f32::from_le_bytes(chunk.try_into().unwrap())),
+        (ScalarKind::Float, 8) => Scalar::F64(f64::from_le_bytes(chunk.try_into().unwrap())),
+        _ => return Err(InterpretError::Unsupported("scalars of this width")),
+    })
+}
+
+fn write_scalar(
+    bytes: &mut [u8],
+    offset: u32,
+    kind: ScalarKind,
+    width: Bytes,
+    value: Scalar,
+) -> Result<(), InterpretError> {
+    if value.kind() != kind {
+        return Err(InterpretError::TypeMismatch);
+    }
+    let start = offset as usize;
+    let length = bytes.len();
+    let chunk =
+        bytes
+            .get_mut(start..start + width as usize)
+            .ok_or(InterpretError::OutOfBounds {
+                offset,
+                size: width as u32,
+                length,
+            })?;
+    match (value, width) {
+        (Scalar::Bool(value), _) => {
+            for byte in chunk.iter_mut() {
+                *byte = 0;
+            }
+            chunk[0] = value as u8;
+        }
+        (Scalar::I32(value), 4) => chunk.copy_from_slice(&value.to_le_bytes()),
+        (Scalar::U32(value), 4) => chunk.copy_from_slice(&value.to_le_bytes()),
+        (Scalar::F32(value), 4) => chunk.copy_from_slice(&value.to_le_bytes()),
+        (Scalar::F64(value), 8) => chunk.copy_from_slice(&value.to_le_bytes()),
+        _ => return Err(InterpretError::TypeMismatch),
+    }
+    Ok(())
+}
+
+/// Read the value of type `pointee` at `offset` of `bytes`.
+pub(super) fn read(
+    module: &Module,
+    bytes: &[u8],
+    offset: u32,
+    pointee: Pointee,
+) -> Result<Value, InterpretError> {
+    let read_vector = |offset: u32, size: VectorSize, kind, width: Bytes| {
+        (0..size as u32)
+            .map(|i| read_scalar(bytes, offset + i * width as u32, kind, width).map(Value::Scalar))
+            .collect::<Result<_, _>>()
+            .map(Value::Composite)
+    };
+    Ok(match shape(module, pointee)? {
+        Shape::Scalar { kind, width } => Value::Scalar(read_scalar(bytes, offset, kind, width)?),
+        Shape::Vector { size, kind, width } => read_vector(offset, size, kind, width)?,
+        Shape::Matrix {
+            columns,
+            rows,
+            width,
+        } => Value::Composite(
+            (0..columns as u32)
+                .map(|i| {
+                    let column = offset + i * rows as u32 * width as u32;
+                    read_vector(column, rows, ScalarKind::Float, width)
+                })
+                .collect::<Result<_, _>>()?,
+        ),
+        Shape::Array { base, size, stride } => {
+            let length = array_length(module, size, stride, offset, bytes.len())?;
+            Value::Composite(
+                (0..length)
+                    .map(|i| read(module, bytes, offset + i * stride, Pointee::Type(base)))
+                    .collect::<Result<_, _>>()?,
+            )
+        }
+        Shape::Struct(members) => Value::Composite(
+            members
+                .iter()
+                .map(|member| {
+                    read(
+                        module,
+                        bytes,
+                        offset + member.offset,
+                        Pointee::Type(member.ty),
+                    )
+                })
+                .collect::<Result<_, _>>()?,
+        ),
+    })
+}
+
+/// Write `value` of type `pointee` at `offset` of `bytes`.
+pub(super) fn write(
+    module: &Module,
+    bytes: &mut [u8],
+    offset: u32,
+    pointee: Pointee,
+    value: &Value,
+) -> Result<(), InterpretError> {
+    let components = |count: u32| match *value {
+        Value::Composite(ref components) if components.len() == count as usize => Ok(components),
+        _ => Err(InterpretError::TypeMismatch),
+    };
+    match shape(module, pointee)? {
+        Shape::Scalar { kind, width } => {
+            let scalar = value.as_scalar().ok_or(InterpretError::TypeMismatch)?;
+            write_scalar(bytes, offset, kind, width, scalar)?;
+        }
+        Shape::Vector { size, kind, width } => {
+            for (i, component) in components(size as u32)?.iter().enumerate() {
+                let scalar = component.as_scalar().ok_or(InterpretError::TypeMismatch)?;
+                write_scalar(bytes, offset + i as u32 * width as u32, kind, width, scalar)?;
+            }
+        }
+        Shape::Matrix {
+            columns,
+            rows,
+            width,
+        } => {
+            let pointee = Pointee::Vector {
+                size: rows,
+                kind: ScalarKind::Float,
+                width,
+            };
+            for (i, column) in components(columns as u32)?.iter().enumerate() {
+                let column_offset = offset + i as u32 * rows as u32 * width as u32;
+                write(module, bytes, column_offset, pointee, column)?;
+            }
+        }
+        Shape::Array { base, size, stride } => {
+            let length = array_length(module, size, stride, offset, bytes.len())?;
+            for (i, element) in components(length)?.iter().enumerate() {
+                let element_offset = offset + i as u32 * stride;
+                write(module, bytes, element_offset, Pointee::Type(base), element)?;
+            }
+        }
+        Shape::Struct(members) => {
+            for (member, component) in members.iter().zip(components(members.len() as u32)?) {
+                let member_offset = offset + member.offset;
+                write(
+                    module,
+                    bytes,
+                    member_offset,
+                    Pointee::Type(member.ty),
+                    component,
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,439 @@
+//! Execution of shader entry points on the CPU.
+//!
+//! The [`Interpreter`] walks the IR of a validated module directly, which
+//! makes it possible to check what a shader computes in plain Rust tests.
+//! It favors simplicity over speed: memory is kept in little-endian byte
+//! buffers laid out as described by the types, and invocations of a
+//! workgroup are interleaved at control barriers. Every invocation forms
+//! a subgroup of its own.
+
+mod eval;
+mod exec;
+mod memory;
+#[cfg(all(test, feature = "wgsl-in"))]
+mod tests;
+
+use self::exec::{Context, Invocation, Status};
+use crate::{
+    arena::Handle, BuiltIn, Bytes, FastHashMap, GlobalVariable, Module, ResourceBinding,
+    ScalarKind, ShaderStage,
+};
+
+/// Default number of statements an invocation can execute.
+const DEFAULT_STEP_LIMIT: u64 = 1_000_000;
+
+/// A single scalar value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Scalar {
+    Bool(bool),
+    I32(i32),
+    U32(u32),
+    F32(f32),
+    F64(f64),
+}
+
+impl Scalar {
+    pub fn kind(&self) -> ScalarKind {
+        match *self {
+            Self::Bool(_) => ScalarKind::Bool,
+            Self::I32(_) => ScalarKind::Sint,
+            Self::U32(_) => ScalarKind::Uint,
+            Self::F32(_) | Self::F64(_) => ScalarKind::Float,
+        }
+    }
+
+    pub fn width(&self) -> Bytes {
+        match *self {
+            Self::Bool(_) => crate::BOOL_WIDTH,
+            Self::I32(_) | Self::U32(_) | Self::F32(_) => 4,
+            Self::F64(_) => 8,
+        }
+    }
+}
+
+/// A value computed by a shader.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Scalar(Scalar),
+    /// Components of a vector, columns of a matrix, elements of an array,
+    /// or members of a structure.
+    Composite(Vec<Value>),
+}
+
+impl Value {
+    /// Build a composite value out of anything convertible to values.
+    pub fn composite<I>(components: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<Value>,
+    {
+        Self::Composite(components.into_iter().map(Into::into).collect())
+    }
+
+    pub fn as_scalar(&self) -> Option<Scalar> {
+        match *self {
+            Self::Scalar(scalar) => Some(scalar),
+            Self::Composite(_) => None,
+        }
+    }
+
+    pub fn components(&self) -> &[Value] {
+        match *self {
+            Self::Scalar(_) => &[],
+            Self::Composite(ref components) => components,
+        }
+    }
+}
+
+macro_rules! impl_from_scalar {
+    ($($ty:ty => $variant:ident,)*) => {
+        $(
+            impl From<$ty> for Scalar {
+                fn from(value: $ty) -> Self {
+                    Self::$variant(value)
+                }
+            }
+            impl From<$ty> for Value {
+                fn from(value: $ty) -> Self {
+                    Self::Scalar(Scalar::$variant(value))
+                }
+            }
+        )*
+    };
+}
+
+impl_from_scalar! {
+    bool => Bool,
+    i32 => I32,
+    u32 => U32,
+    f32 => F32,
+    f64 => F64,
+}
+
+impl From<Scalar> for Value {
+    fn from(scalar: Scalar) -> Self {
+        Self::Scalar(scalar)
+    }
+}
+
+/// Contents of an image with a single mip level.
+///
+/// Texels are stored as four scalars each, in the order of samples, then
+/// columns, rows, depth slices and array layers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Texture {
+    /// Width, height and depth of the texture, in texels.
+    pub size: [u32; 3],
+    pub layers: u32,
+    pub samples: u32,
+    pub texels: Vec<[Scalar; 4]>,
+}
+
+impl Texture {
+    /// Create a texture with a single layer and sample, filled with `texel`.
+    pub fn new(size: [u32; 3], texel: [Scalar; 4]) -> Self {
+        Texture {
+            size,
+            layers: 1,
+            samples: 1,
+            texels: vec![texel; (size[0] * size[1] * size[2]) as usize],
+        }
+    }
+
+    fn index(&self, coordinate: [i64; 3], layer: i64, sample: i64) -> Option<usize> {
+        let mut index = 0;
+        for &(value, limit) in [
+            (layer, self.layers),
+            (coordinate[2], self.size[2]),
+            (coordinate[1], self.size[1]),
+            (coordinate[0], self.size[0]),
+            (sample, self.samples),
+        ]
+        .iter()
+        {
+            if value < 0 || value >= limit as i64 {
+                return None;
+            }
+            index = index * limit as usize + value as usize;
+        }
+        Some(index)
+    }
+
+    pub fn texel(&self, coordinate: [u32; 3], layer: u32, sample: u32) -> Option<&[Scalar; 4]> {
+        let index = self.index(
+            [
+                coordinate[0] as i64,
+                coordinate[1] as i64,
+                coordinate[2] as i64,
+            ],
+            layer as i64,
+            sample as i64,
+        )?;
+        self.texels.get(index)
+    }
+}
+
+/// Result of a fragment shader invocation.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FragmentOutput {
+    /// The fragment was discarded with [`Statement::Kill`](crate::Statement::Kill).
+    Discarded,
+    /// The value returned by the entry point, if any.
+    Returned(Option<Value>),
+}
+
+#[derive(Clone, Debug, thiserror::Error, PartialEq)]
+pub enum InterpretError {
+    #[error("entry point {0:?} is not found")]
+    MissingEntryPoint(String),
+    #[error("entry point {name:?} is a {stage:?} shader")]
+    WrongStage { name: String, stage: ShaderStage },
+    #[error("expected {expected} arguments, got {actual}")]
+    ArgumentCount { expected: usize, actual: usize },
+    #[error("built-in {0:?} is not available")]
+    UnavailableBuiltIn(BuiltIn),
+    #[error("global variable {0:?} doesn't have a binding")]
+    MissingBinding(Handle<GlobalVariable>),
+    #[error("no buffer is bound to {0:?}")]
+    MissingBuffer(ResourceBinding),
+    #[error("no texture is bound to {0:?}")]
+    MissingTexture(ResourceBinding),
+    #[error("access of {size} bytes at offset {offset} is outside of a {length} bytes region")]
+    OutOfBounds {
+        offset: u32,
+        size: u32,
+        length: usize,
+    },
+    #[error("index {index} is out of bounds of {length} elements")]
+    IndexOutOfBounds { index: i64, length: u32 },
+    #[error("texel {coordinate:?} of layer {layer}, sample {sample} is out of bounds")]
+    TexelOutOfBounds {
+        coordinate: [i64; 3],
+        layer: i64,
+        sample: i64,
+    },
+    #[error("integer division by zero")]
+    DivisionByZero,
+    #[error("invocation exceeded the limit of {0} steps")]
+    StepLimitExceeded(u64),
+    #[error("operands have unexpected types (validation error)")]
+    TypeMismatch,
+    #[error("{0} is not supported")]
+    Unsupported(&'static str),
+}
+
+/// Executes entry points of a module on the CPU.
+///
+/// Resources are looked up by their bindings: buffers back the uniform and
+/// storage variables, and textures back the images. Samplers don't need to
+/// be provided, all sampling uses the nearest texel with clamp-to-edge
+/// addressing, and depth comparisons pass if the reference is less or
+/// equal to the texel.
+#[derive(Debug)]
+pub struct Interpreter<'a> {
+    module: &'a Module,
+    buffers: FastHashMap<ResourceBinding, Vec<u8>>,
+    textures: FastHashMap<ResourceBinding, Texture>,
+    push_constants: Vec<u8>,
+    step_limit: u64,
+}
+
+impl<'a> Interpreter<'a> {
+    pub fn new(module: &'a Module) -> Self {
+        Interpreter {
+            module,
+            buffers: FastHashMap::default(),
+            textures: FastHashMap::default(),
+            push_constants: Vec::new(),
+            step_limit: DEFAULT_STEP_LIMIT,
+        }
+    }
+
+    /// Bind the contents of a uniform or storage buffer.
+    pub fn set_buffer(&mut self, binding: ResourceBinding, data: Vec<u8>) {
+        self.buffers.insert(binding, data);
+    }
+
+    pub fn buffer(&self, binding: &ResourceBinding) -> Option<&[u8]> {
+        self.buffers.get(binding).map(Vec::as_slice)
+    }
+
+    pub fn set_texture(&mut self, binding: ResourceBinding, texture: Texture) {
+        self.textures.insert(binding, texture);
+    }
+
+    pub fn texture(&self, binding: &ResourceBinding) -> Option<&Texture> {
+        self.textures.get(binding)
+    }
+
+    pub fn set_push_constants(&mut self, data: Vec<u8>) {
+        self.push_constants = data;
+    }
+
+    /// Limit the number of statements each invocation can execute,
+    /// so that runaway loops turn into errors.
+    pub fn set_step_limit(&mut self, limit: u64) {
+        self.step_limit = limit;
+    }
+
+    fn entry_point(
+        &self,
+        name: &str,
+        stage: ShaderStage,
+    ) -> Result<&'a crate::EntryPoint, InterpretError> {
+        let module = self.module;
+        let ep = module
+            .entry_points
+            .iter()
+            .find(|ep| ep.name == name)
+            .ok_or_else(|| InterpretError::MissingEntryPoint(name.to_string()))?;
+        if ep.stage != stage {
+            return Err(InterpretError::WrongStage {
+                name: name.to_string(),
+                stage: ep.stage,
+            });
+        }
+        Ok(ep)
+    }
+
+    fn context(&mut self) -> Context<'a, '_> {
+        Context {
+            module: self.module,
+            buffers: &mut self.buffers,
+            textures: &mut self.textures,
+            push_constants: &mut self.push_constants,
+            workgroup: FastHashMap::default(),
+            step_limit: self.step_limit,
+        }
+    }
+
+    /// Run a compute entry point over the given number of workgroups.
+    ///
+    /// Workgroups are executed one after another. Within a workgroup,
+    /// each invocation runs until it reaches a barrier, and all of them
+    /// are resumed once every invocation is waiting or finished.
+    pub fn dispatch(
+        &mut self,
+        entry_point: &str,
+        workgroups: [u32; 3],
+    ) -> Result<(), InterpretError> {
+        let ep = self.entry_point(entry_point, ShaderStage::Compute)?;
+        let size = ep.workgroup_size;
+        let module = self.module;
+        let mut context = self.context();
+
+        for group_z in 0..workgroups[2] {
+            for group_y in 0..workgroups[1] {
+                for group_x in 0..workgroups[0] {
+                    let group_id = [group_x, group_y, group_z];
+                    context.reset_workgroup()?;
+
+                    let mut invocations = Vec::new();
+                    for local_index in 0..size[0] * size[1] * size[2] {
+                        let local_id = [
+                            local_index % size[0],
+                            local_index / size[0] % size[1],
+                            local_index / (size[0] * size[1]),
+                        ];
+                        let builtins = ComputeBuiltIns {
+                            global_id: [
+                                group_id[0] * size[0] + local_id[0],
+                                group_id[1] * size[1] + local_id[1],
+                                group_id[2] * size[2] + local_id[2],
+                            ],
+                            local_id,
+                            local_index,
+                            group_id,
+                            group_size: size,
+                        };
+                        let arguments = ep
+                            .function
+                            .arguments
+                            .iter()
+                            .map(|arg| builtins.argument(module, arg.ty, arg.binding.as_ref()))
+                            .collect::<Result<Vec<_>, _>>()?;
+                        invocations.push(Invocation::new(module, &ep.function, arguments)?);
+                    }
+
+                    while !invocations.is_empty() {
+                        let mut index = 0;
+                        while index < invocations.len() {
+                            match context.run(&mut invocations[index])? {
+                                Status::Barrier => index += 1,
+                                Status::Finished(_) | Status::Killed => {
+                                    invocations.remove(index);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Run a fragment entry point with the values of its arguments.
+    pub fn invoke_fragment(
+        &mut self,
+        entry_point: &str,
+        arguments: &[Value],
+    ) -> Result<FragmentOutput, InterpretError> {
+        let ep = self.entry_point(entry_point, ShaderStage::Fragment)?;
+        if arguments.len() != ep.function.arguments.len() {
+            return Err(InterpretError::ArgumentCount {
+                expected: ep.function.arguments.len(),
+                actual: arguments.len(),
+            });
+        }
+        let mut invocation = Invocation::new(self.module, &ep.function, arguments.to_vec())?;
+        let mut context = self.context();
+        loop {
+            match context.run(&mut invocation)? {
+                Status::Barrier => {}
+                Status::Finished(value) => return Ok(FragmentOutput::Returned(value)),
+                Status::Killed => return Ok(FragmentOutput::Discarded),
+            }
+        }
+    }
+}
+
+struct ComputeBuiltIns {
+    global_id: [u32; 3],
+    local_id: [u32; 3],
+    local_index: u32,
+    group_id: [u32; 3],
+    group_size: [u32; 3],
+}
+
+impl ComputeBuiltIns {
+    fn argument(
+        &self,
+        module: &Module,
+        ty: Handle<crate::Type>,
+        binding: Option<&crate::Binding>,
+    ) -> Result<Value, InterpretError> {
+        match binding {
+            Some(&crate::Binding::BuiltIn(built_in)) => Ok(match built_in {
+                BuiltIn::GlobalInvocationId => Value::composite(self.global_id.iter().cloned()),
+                BuiltIn::LocalInvocationId => Value::composite(self.local_id.iter().cloned()),
+                BuiltIn::LocalInvocationIndex => self.local_index.into(),
+                BuiltIn::WorkGroupId => Value::composite(self.group_id.iter().cloned()),
+                BuiltIn::WorkGroupSize => Value::composite(self.group_size.iter().cloned()),
+                BuiltIn::SubgroupSize => 1u32.into(),
+                BuiltIn::SubgroupInvocationId => 0u32.into(),
+                _ => return Err(InterpretError::UnavailableBuiltIn(built_in)),
+            }),
+            Some(&crate::Binding::Location { .. }) => Err(InterpretError::Unsupported(
+                "location inputs of compute shaders",
+            )),
+            None => match module.types[ty].inner {
+                crate::TypeInner::Struct { ref members, .. } => members
+                    .iter()
+                    .map(|member| self.argument(module, member.ty, member.binding.as_ref()))
+                    .collect::<Result<_, _>>()
+                    .map(Value::Composite),
+                _ => Err(InterpretError::TypeMismatch),
+            },
+        }
+    }
+}
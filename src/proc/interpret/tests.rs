@@ -0,0 +1,395 @@
+use super::{FragmentOutput, InterpretError, Interpreter, Scalar, Texture, Value};
+use crate::ResourceBinding;
+
+fn parse(source: &str) -> crate::Module {
+    let module = crate::front::wgsl::parse_str(source).unwrap();
+    crate::valid::Validator::new(
+        crate::valid::ValidationFlags::all(),
+        crate::valid::Capabilities::all(),
+    )
+    .validate(&module)
+    .unwrap();
+    module
+}
+
+fn binding(binding: u32) -> ResourceBinding {
+    ResourceBinding { group: 0, binding }
+}
+
+fn to_bytes(values: &[u32]) -> Vec<u8> {
+    values
+        .iter()
+        .flat_map(|value| value.to_le_bytes().to_vec())
+        .collect()
+}
+
+fn from_bytes(bytes: &[u8]) -> Vec<u32> {
+    bytes
+        .chunks(4)
+        .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect()
+}
+
+#[test]
+fn collatz() {
+    let module = parse(include_str!("../../../tests/in/collatz.wgsl"));
+    let mut interpreter = Interpreter::new(&module);
+    interpreter.set_buffer(binding(0), to_bytes(&[1, 2, 3, 7]));
+    interpreter.dispatch("main", [4, 1, 1]).unwrap();
+    assert_eq!(
+        from_bytes(interpreter.buffer(&binding(0)).unwrap()),
+        [0, 1, 7, 16]
+    );
+}
+
+#[test]
+fn workgroup_reduction() {
+    let module = parse(
+        "
+        [[block]]
+        struct Data {
+            values: [[stride(4)]] array<u32>;
+        };
+        [[group(0), binding(0)]]
+        var<storage> data: [[access(read_write)]] Data;
+        var<workgroup> partial: array<u32, 4>;
+
+        [[stage(compute), workgroup_size(4)]]
+        fn main(
+            [[builtin(local_invocation_index)]] index: u32,
+            [[builtin(global_invocation_id)]] global_id: vec3<u32>,
+        ) {
+            partial[index] = data.values[global_id.x];
+            workgroupBarrier();
+            if (index == 0u) {
+                var sum: u32 = 0u;
+                var i: u32 = 0u;
+                loop {
+                    if (i >= 4u) {
+                        break;
+                    }
+                    sum = sum + partial[i];
+                    continuing {
+                        i = i + 1u;
+                    }
+                }
+                data.values[global_id.x] = sum;
+            }
+        }
+        ",
+    );
+    let mut interpreter = Interpreter::new(&module);
+    interpreter.set_buffer(binding(0), to_bytes(&[1, 2, 3, 4, 10, 20, 30, 40]));
+    interpreter.dispatch("main", [2, 1, 1]).unwrap();
+    assert_eq!(
+        from_bytes(interpreter.buffer(&binding(0)).unwrap()),
+        [10, 2, 3, 4, 100, 20, 30, 40]
+    );
+}
+
+#[test]
+fn control_flow() {
+    let module = parse(
+        "
+        [[block]]
+        struct Data {
+            values: [[stride(4)]] array<i32>;
+        };
+        [[group(0), binding(0)]]
+        var<storage> data: [[access(write)]] Data;
+
+        fn classify(x: i32) -> i32 {
+            var result: i32 = 0;
+            switch (x) {
+                case 0: {
+                    result = 1;
+                    fallthrough;
+                }
+                case 1: {
+                    result = result + 2;
+                    fallthrough;
+                }
+                default: {
+                    result = result * 3;
+                }
+            }
+            return result;
+        }
+
+        [[stage(compute), workgroup_size(4)]]
+        fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>) {
+            var odd_sum: i32 = 0;
+            var i: i32 = 0;
+            loop {
+                i = i + 1;
+                if (i > 5) {
+                    break;
+                }
+                if (i % 2 == 0) {
+                    continue;
+                }
+                odd_sum = odd_sum + i;
+            }
+            let m = mat2x2<f32>(vec2<f32>(2.0, 0.0), vec2<f32>(1.0, 3.0));
+            let x = i32(global_id.x);
+            data.values[global_id.x] = classify(x) * 100 + odd_sum * 10 + i32(determinant(m));
+        }
+        ",
+    );
+    let mut interpreter = Interpreter::new(&module);
+    interpreter.set_buffer(binding(0), vec![0; 16]);
+    interpreter.dispatch("main", [1, 1, 1]).unwrap();
+    assert_eq!(
+        from_bytes(interpreter.buffer(&binding(0)).unwrap()),
+        [996, 696, 96, 96]
+    );
+}
+
+#[test]
+fn atomics() {
+    let module = parse(
+        "
+        [[block]]
+        struct Counters {
+            total: atomic<u32>;
+            minimum: atomic<i32>;
+        };
+        [[group(0), binding(0)]]
+        var<storage> counters: [[access(read_write)]] Counters;
+
+        [[stage(compute), workgroup_size(8)]]
+        fn main([[builtin(local_invocation_index)]] index: u32) {
+            atomicAdd(&counters.total, index);
+            atomicMin(&counters.minimum, 3 - i32(index));
+        }
+        ",
+    );
+    let mut interpreter = Interpreter::new(&module);
+    interpreter.set_buffer(binding(0), to_bytes(&[0, 0]));
+    interpreter.dispatch("main", [3, 1, 1]).unwrap();
+    assert_eq!(
+        from_bytes(interpreter.buffer(&binding(0)).unwrap()),
+        [84, -4i32 as u32]
+    );
+}
+
+#[test]
+fn subgroups() {
+    let module = parse(
+        "
+        [[block]]
+        struct Data {
+            values: [[stride(4)]] array<u32>;
+        };
+        [[group(0), binding(0)]]
+        var<storage> data: [[access(read_write)]] Data;
+
+        [[stage(compute), workgroup_size(2)]]
+        fn main(
+            [[builtin(global_invocation_id)]] global_id: vec3<u32>,
+            [[builtin(subgroup_size)]] size: u32,
+            [[builtin(subgroup_invocation_id)]] id: u32,
+        ) {
+            let value = data.values[global_id.x];
+            let ballot = subgroupBallot(value > 1u);
+            let sum = subgroupAdd(value) + subgroupExclusiveMul(value);
+            let gathered = subgroupShuffle(value, id);
+            data.values[global_id.x] = size * 1000u + id * 100u + ballot.x * 10u + sum + gathered;
+        }
+        ",
+    );
+    let mut interpreter = Interpreter::new(&module);
+    interpreter.set_buffer(binding(0), to_bytes(&[1, 2]));
+    interpreter.dispatch("main", [1, 1, 1]).unwrap();
+    assert_eq!(
+        from_bytes(interpreter.buffer(&binding(0)).unwrap()),
+        [1003, 1015]
+    );
+}
+
+#[test]
+fn bits() {
+    let module = parse(
+        "
+        [[block]]
+        struct Data {
+            values: [[stride(4)]] array<u32>;
+        };
+        [[group(0), binding(0)]]
+        var<storage> data: [[access(write)]] Data;
+
+        [[stage(compute), workgroup_size(1)]]
+        fn main() {
+            data.values[0] = extractBits(240u, 4u, 4u);
+            data.values[1] = u32(extractBits(-16, 4u, 4u));
+            data.values[2] = insertBits(0u, 7u, 8u, 2u);
+            data.values[3] = firstTrailingBit(8u);
+            data.values[4] = u32(firstLeadingBit(-1));
+            data.values[5] = firstLeadingBit(256u);
+            data.values[6] = pack4x8unorm(vec4<f32>(1.0, 0.0, 0.5, 1.0));
+            data.values[7] = pack2x16float(vec2<f32>(1.0, -2.0));
+            let unpacked = unpack2x16snorm(2147581951u);
+            data.values[8] = u32(unpacked.x * 10.0 + 20.0);
+            data.values[9] = u32(unpacked.y * 10.0 + 20.0);
+        }
+        ",
+    );
+    let mut interpreter = Interpreter::new(&module);
+    interpreter.set_buffer(binding(0), vec![0; 40]);
+    interpreter.dispatch("main", [1, 1, 1]).unwrap();
+    assert_eq!(
+        from_bytes(interpreter.buffer(&binding(0)).unwrap()),
+        [
+            15,
+            -1i32 as u32,
+            768,
+            3,
+            -1i32 as u32,
+            8,
+            0xFF80_00FF,
+            0xC000_3C00,
+            30,
+            10
+        ]
+    );
+}
+
+#[test]
+fn fragment() {
+    let module = parse(
+        "
+        [[group(0), binding(0)]]
+        var image: texture_2d<f32>;
+        [[group(0), binding(1)]]
+        var linear: sampler;
+
+        [[stage(fragment)]]
+        fn main([[location(0)]] uv: vec2<f32>) -> [[location(0)]] vec4<f32> {
+            let color = textureSample(image, linear, uv);
+            if (uv.x < 0.0) {
+                discard;
+            }
+            let rotate = mat2x2<f32>(vec2<f32>(0.0, 1.0), vec2<f32>(-1.0, 0.0));
+            return vec4<f32>(rotate * uv, color.x + floor(2.5), length(vec2<f32>(3.0, 4.0)));
+        }
+        ",
+    );
+    let mut interpreter = Interpreter::new(&module);
+    let mut texture = Texture::new([2, 2, 1], [Scalar::F32(0.0); 4]);
+    texture.texels[3][0] = Scalar::F32(0.5);
+    interpreter.set_texture(binding(0), texture);
+
+    let uv = Value::composite(vec![0.75f32, 0.5]);
+    assert_eq!(
+        interpreter.invoke_fragment("main", &[uv]),
+        Ok(FragmentOutput::Returned(Some(Value::composite(vec![
+            -0.5f32, 0.75, 2.5, 5.0
+        ]))))
+    );
+    let uv = Value::composite(vec![-1.0f32, 0.0]);
+    assert_eq!(
+        interpreter.invoke_fragment("main", &[uv]),
+        Ok(FragmentOutput::Discarded)
+    );
+}
+
+#[test]
+fn gather() {
+    let module = parse(
+        "
+        [[group(0), binding(0)]]
+        var image: texture_2d<f32>;
+        [[group(0), binding(1)]]
+        var linear: sampler;
+
+        [[stage(fragment)]]
+        fn main([[location(0)]] uv: vec2<f32>) -> [[location(0)]] vec4<f32> {
+            return textureGather(1, image, linear, uv);
+        }
+        ",
+    );
+    let mut interpreter = Interpreter::new(&module);
+    let mut texture = Texture::new([2, 2, 1], [Scalar::F32(0.0); 4]);
+    for (index, texel) in texture.texels.iter_mut().enumerate() {
+        texel[1] = Scalar::F32(index as f32 + 1.0);
+    }
+    interpreter.set_texture(binding(0), texture);
+
+    let uv = Value::composite(vec![0.5f32, 0.5]);
+    assert_eq!(
+        interpreter.invoke_fragment("main", &[uv]),
+        Ok(FragmentOutput::Returned(Some(Value::composite(vec![
+            3.0f32, 4.0, 2.0, 1.0
+        ]))))
+    );
+}
+
+#[test]
+fn outer_product() {
+    let module = parse(
+        "
+        [[stage(fragment)]]
+        fn main([[location(0)]] uv: vec2<f32>) -> [[location(0)]] vec4<f32> {
+            let m = outerProduct(vec3<f32>(1.0, 2.0, 3.0), uv);
+            return vec4<f32>(m[1], m[0].z);
+        }
+        ",
+    );
+    let mut interpreter = Interpreter::new(&module);
+
+    let uv = Value::composite(vec![10.0f32, 100.0]);
+    assert_eq!(
+        interpreter.invoke_fragment("main", &[uv]),
+        Ok(FragmentOutput::Returned(Some(Value::composite(vec![
+            100.0f32, 200.0, 300.0, 30.0
+        ]))))
+    );
+}
+
+#[test]
+fn errors() {
+    let module = parse(
+        "
+        [[block]]
+        struct Data {
+            values: [[stride(4)]] array<u32>;
+        };
+        [[group(0), binding(0)]]
+        var<storage> data: [[access(read_write)]] Data;
+
+        [[stage(compute), workgroup_size(1)]]
+        fn main() {
+            loop {
+                let index = data.values[0];
+                data.values[index] = 0u;
+            }
+        }
+        ",
+    );
+    let mut interpreter = Interpreter::new(&module);
+    assert_eq!(
+        interpreter.dispatch("main", [1, 1, 1]),
+        Err(InterpretError::MissingBuffer(binding(0)))
+    );
+    interpreter.set_buffer(binding(0), to_bytes(&[2]));
+    assert_eq!(
+        interpreter.dispatch("main", [1, 1, 1]),
+        Err(InterpretError::IndexOutOfBounds {
+            index: 2,
+            length: 1
+        })
+    );
+    interpreter.set_buffer(binding(0), to_bytes(&[0]));
+    interpreter.set_step_limit(100);
+    assert_eq!(
+        interpreter.dispatch("main", [1, 1, 1]),
+        Err(InterpretError::StepLimitExceeded(100))
+    );
+    assert_eq!(
+        interpreter.invoke_fragment("main", &[]),
+        Err(InterpretError::WrongStage {
+            name: "main".to_string(),
+            stage: crate::ShaderStage::Compute
+        })
+    );
+}
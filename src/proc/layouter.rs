@@ -15,7 +15,7 @@ pub struct TypeLayout {
 /// Helper processor that derives the sizes of all types.
 /// It uses the default layout algorithm/table, described in
 /// <https://github.com/gpuweb/gpuweb/issues/1393>
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 #[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[cfg_attr(feature = "deserialize", derive(serde::Deserialize))]
 pub struct Layouter {
@@ -72,7 +72,7 @@ impl Layouter {
         for (ty_handle, ty) in types.iter().skip(self.layouts.len()) {
             let size = ty.inner.span(constants);
             let layout = match ty.inner {
-                Ti::Scalar { width, .. } => TypeLayout {
+                Ti::Scalar { width, .. } | Ti::Atomic { width, .. } => TypeLayout {
                     size,
                     alignment: Alignment::new(width as u32).unwrap(),
                 },
@@ -136,7 +136,7 @@ impl Layouter {
                         alignment,
                     }
                 }
-                Ti::Image { .. } | Ti::Sampler { .. } => TypeLayout {
+                Ti::Image { .. } | Ti::Sampler { .. } | Ti::BindingArray { .. } => TypeLayout {
                     size,
                     alignment: Alignment::new(1).unwrap(),
                 },
@@ -1,17 +1,34 @@
 //! Module processing functionality.
 
+mod compact;
+mod constants;
 mod index;
+mod inline;
 mod interpolator;
+mod interpret;
 mod layouter;
 mod namer;
+mod specialization;
 mod terminator;
 mod typifier;
+mod zero_init;
 
-pub use index::IndexableLength;
+pub use compact::compact;
+pub use constants::{
+    fold_constants, fold_function_constants, ConstantSolver, ConstantSolvingError,
+};
+pub use index::{
+    access_needs_check, binding_array_element, bounds_check_iter,
+    is_non_uniform_binding_array_access, BoundsCheck, GuardedIndex, IndexableLength,
+};
+pub use inline::{inline_calls, InlineOptions};
+pub use interpret::{FragmentOutput, InterpretError, Interpreter, Scalar, Texture, Value};
 pub use layouter::{Alignment, InvalidBaseType, Layouter, TypeLayout};
 pub use namer::{EntryPointIndex, NameKey, Namer};
+pub use specialization::{specialize_constants, SpecializationError};
 pub use terminator::ensure_block_returns;
 pub use typifier::{ResolveContext, ResolveError, TypeResolution};
+pub use zero_init::zero_initialize_workgroup_memory;
 
 #[derive(Clone, Debug, thiserror::Error, PartialEq)]
 pub enum ProcError {
@@ -74,6 +91,15 @@ impl super::ScalarValue {
 
 pub const POINTER_SPAN: u32 = 4;
 
+/// Round to the nearest integer, with ties going to even.
+fn round_even(value: f64) -> f64 {
+    if (value - value.trunc()).abs() == 0.5 {
+        2.0 * (value / 2.0).round()
+    } else {
+        value.round()
+    }
+}
+
 impl super::TypeInner {
     pub fn scalar_kind(&self) -> Option<super::ScalarKind> {
         match *self {
@@ -87,7 +113,7 @@ impl super::TypeInner {
 
     pub fn span(&self, constants: &super::Arena<super::Constant>) -> u32 {
         match *self {
-            Self::Scalar { kind: _, width } => width as u32,
+            Self::Scalar { kind: _, width } | Self::Atomic { kind: _, width } => width as u32,
             Self::Vector {
                 size,
                 kind: _,
@@ -115,7 +141,7 @@ impl super::TypeInner {
                 count * stride
             }
             Self::Struct { span, .. } => span,
-            Self::Image { .. } | Self::Sampler { .. } => 0,
+            Self::Image { .. } | Self::Sampler { .. } | Self::BindingArray { .. } => 0,
         }
     }
 }
@@ -178,6 +204,22 @@ impl super::MathFunction {
             // bits
             Self::CountOneBits => 1,
             Self::ReverseBits => 1,
+            Self::ExtractBits => 3,
+            Self::InsertBits => 4,
+            Self::FirstTrailingBit => 1,
+            Self::FirstLeadingBit => 1,
+            // data packing
+            Self::Pack4x8snorm => 1,
+            Self::Pack4x8unorm => 1,
+            Self::Pack2x16snorm => 1,
+            Self::Pack2x16unorm => 1,
+            Self::Pack2x16float => 1,
+            // data unpacking
+            Self::Unpack4x8snorm => 1,
+            Self::Unpack4x8unorm => 1,
+            Self::Unpack2x16snorm => 1,
+            Self::Unpack2x16unorm => 1,
+            Self::Unpack2x16float => 1,
         }
     }
 }
@@ -0,0 +1,151 @@
+//! Substitution of pipeline-overridable constants.
+
+use crate::{Bytes, ConstantInner, FastHashMap, Module, ScalarKind, ScalarValue};
+
+#[derive(Clone, Debug, thiserror::Error, PartialEq)]
+pub enum SpecializationError {
+    #[error("specialization constant {0} is not a scalar")]
+    NotScalar(u32),
+    #[error("specialization constant {id} expects a {expected:?} value, got {actual:?}")]
+    KindMismatch {
+        id: u32,
+        expected: ScalarKind,
+        actual: ScalarKind,
+    },
+    #[error("value of specialization constant {id} doesn't fit in {width} bytes")]
+    OutOfRange { id: u32, width: Bytes },
+}
+
+/// Bake the values of specialization constants into `module`.
+///
+/// Every constant with a specialization id found in `values` takes the given
+/// value, and the others keep their default one. Afterwards no constant is
+/// specializable anymore, which is what the backends without a notion of
+/// pipeline-overridable constants, like GLSL and HLSL, expect.
+///
+/// All the values are checked before any constant is changed, so the module
+/// is left untouched on error.
+pub fn specialize_constants(
+    module: &mut Module,
+    values: &FastHashMap<u32, ScalarValue>,
+) -> Result<(), SpecializationError> {
+    for (_, constant) in module.constants.iter() {
+        let id = match constant.specialization {
+            Some(id) => id,
+            None => continue,
+        };
+        let new_value = match values.get(&id) {
+            Some(&value) => value,
+            None => continue,
+        };
+        match constant.inner {
+            ConstantInner::Scalar { ref value, width } => {
+                let expected = value.scalar_kind();
+                let actual = new_value.scalar_kind();
+                if expected != actual {
+                    return Err(SpecializationError::KindMismatch {
+                        id,
+                        expected,
+                        actual,
+                    });
+                }
+                if !fits_width(new_value, width) {
+                    return Err(SpecializationError::OutOfRange { id, width });
+                }
+            }
+            ConstantInner::Composite { .. } => return Err(SpecializationError::NotScalar(id)),
+        }
+    }
+
+    for (_, constant) in module.constants.iter_mut() {
+        let id = match constant.specialization.take() {
+            Some(id) => id,
+            None => continue,
+        };
+        if let ConstantInner::Scalar { ref mut value, .. } = constant.inner {
+            if let Some(&new_value) = values.get(&id) {
+                *value = new_value;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks that an integer `value` is representable with `width` bytes.
+fn fits_width(value: ScalarValue, width: Bytes) -> bool {
+    let bits = width as u32 * 8;
+    match value {
+        ScalarValue::Sint(value) if bits < 64 => {
+            let max = (1i64 << (bits - 1)) - 1;
+            value >= -max - 1 && value <= max
+        }
+        ScalarValue::Uint(value) if bits < 64 => value >> bits == 0,
+        _ => true,
+    }
+}
+
+#[test]
+fn specialize() {
+    let mut module = Module::default();
+    let default = module.constants.append(
+        crate::Constant {
+            name: Some("default".to_string()),
+            specialization: Some(0),
+            inner: ConstantInner::Scalar {
+                width: 4,
+                value: ScalarValue::Float(1.0),
+            },
+        },
+        Default::default(),
+    );
+    let provided = module.constants.append(
+        crate::Constant {
+            name: Some("provided".to_string()),
+            specialization: Some(1),
+            inner: ConstantInner::Scalar {
+                width: 4,
+                value: ScalarValue::Uint(2),
+            },
+        },
+        Default::default(),
+    );
+
+    let mut values = FastHashMap::default();
+    values.insert(1, ScalarValue::Sint(3));
+    assert_eq!(
+        specialize_constants(&mut module, &values),
+        Err(SpecializationError::KindMismatch {
+            id: 1,
+            expected: ScalarKind::Uint,
+            actual: ScalarKind::Sint,
+        })
+    );
+
+    assert_eq!(module.constants[default].specialization, Some(0));
+    assert_eq!(module.constants[provided].specialization, Some(1));
+
+    values.insert(1, ScalarValue::Uint(1 << 32));
+    assert_eq!(
+        specialize_constants(&mut module, &values),
+        Err(SpecializationError::OutOfRange { id: 1, width: 4 })
+    );
+
+    values.insert(1, ScalarValue::Uint(3));
+    specialize_constants(&mut module, &values).unwrap();
+    for &(handle, expected) in [
+        (default, ScalarValue::Float(1.0)),
+        (provided, ScalarValue::Uint(3)),
+    ]
+    .iter()
+    {
+        let constant = &module.constants[handle];
+        assert_eq!(constant.specialization, None);
+        assert_eq!(
+            constant.inner,
+            ConstantInner::Scalar {
+                width: 4,
+                value: expected,
+            }
+        );
+    }
+}
@@ -131,7 +131,9 @@ impl<'a> ResolveContext<'a> {
                 // Arrays and matrices can only be indexed dynamically behind a
                 // pointer, but that's a validation error, not a type error, so
                 // go ahead provide a type here.
-                Ti::Array { base, .. } => TypeResolution::Handle(base),
+                Ti::Array { base, .. } | Ti::BindingArray { base, .. } => {
+                    TypeResolution::Handle(base)
+                }
                 Ti::Matrix { rows, width, .. } => TypeResolution::Value(Ti::Vector {
                     size: rows,
                     kind: crate::ScalarKind::Float,
@@ -155,7 +157,9 @@ impl<'a> ResolveContext<'a> {
                 }),
                 Ti::Pointer { base, class } => {
                     TypeResolution::Value(match types[base].inner {
-                        Ti::Array { base, .. } => Ti::Pointer { base, class },
+                        Ti::Array { base, .. } | Ti::BindingArray { base, .. } => {
+                            Ti::Pointer { base, class }
+                        }
                         Ti::Vector {
                             size: _,
                             kind,
@@ -215,7 +219,9 @@ impl<'a> ResolveContext<'a> {
                         width,
                     })
                 }
-                Ti::Array { base, .. } => TypeResolution::Handle(base),
+                Ti::Array { base, .. } | Ti::BindingArray { base, .. } => {
+                    TypeResolution::Handle(base)
+                }
                 Ti::Struct { ref members, .. } => {
                     let member = members
                         .get(index as usize)
@@ -242,7 +248,9 @@ impl<'a> ResolveContext<'a> {
                     base: ty_base,
                     class,
                 } => TypeResolution::Value(match types[ty_base].inner {
-                    Ti::Array { base, .. } => Ti::Pointer { base, class },
+                    Ti::Array { base, .. } | Ti::BindingArray { base, .. } => {
+                        Ti::Pointer { base, class }
+                    }
                     Ti::Vector { size, kind, width } => {
                         if index >= size as u32 {
                             return Err(ResolveError::OutOfBoundsIndex { expr: base, index });
@@ -350,7 +358,13 @@ impl<'a> ResolveContext<'a> {
                 })
             }
             crate::Expression::Load { pointer } => match *past(pointer).inner_with(types) {
-                Ti::Pointer { base, class: _ } => TypeResolution::Handle(base),
+                Ti::Pointer { base, class: _ } => {
+                    if let Ti::Atomic { kind, width } = types[base].inner {
+                        TypeResolution::Value(Ti::Scalar { kind, width })
+                    } else {
+                        TypeResolution::Handle(base)
+                    }
+                }
                 Ti::ValuePointer {
                     size,
                     kind,
@@ -365,6 +379,24 @@ impl<'a> ResolveContext<'a> {
                     return Err(ResolveError::InvalidPointer(pointer));
                 }
             },
+            crate::Expression::ImageSample {
+                image,
+                gather: Some(_),
+                ..
+            } => match *past(image).inner_with(types) {
+                Ti::Image { class, .. } => TypeResolution::Value(Ti::Vector {
+                    kind: match class {
+                        crate::ImageClass::Sampled { kind, multi: _ } => kind,
+                        _ => crate::ScalarKind::Float,
+                    },
+                    width: 4,
+                    size: crate::VectorSize::Quad,
+                }),
+                ref other => {
+                    log::error!("Image type {:?}", other);
+                    return Err(ResolveError::InvalidImage(image));
+                }
+            },
             crate::Expression::ImageSample { image, .. }
             | crate::Expression::ImageLoad { image, .. } => match *past(image).inner_with(types) {
                 Ti::Image { class, .. } => TypeResolution::Value(match class {
@@ -504,15 +536,32 @@ impl<'a> ResolveContext<'a> {
             },
             crate::Expression::Select { accept, .. } => past(accept).clone(),
             crate::Expression::Derivative { axis: _, expr } => past(expr).clone(),
-            crate::Expression::Relational { .. } => TypeResolution::Value(Ti::Scalar {
-                kind: crate::ScalarKind::Bool,
-                width: crate::BOOL_WIDTH,
-            }),
+            crate::Expression::Relational { fun, argument } => match fun {
+                crate::RelationalFunction::All | crate::RelationalFunction::Any => {
+                    TypeResolution::Value(Ti::Scalar {
+                        kind: crate::ScalarKind::Bool,
+                        width: crate::BOOL_WIDTH,
+                    })
+                }
+                // the float tests are done component-wise
+                _ => match *past(argument).inner_with(types) {
+                    Ti::Vector { size, .. } => TypeResolution::Value(Ti::Vector {
+                        size,
+                        kind: crate::ScalarKind::Bool,
+                        width: crate::BOOL_WIDTH,
+                    }),
+                    _ => TypeResolution::Value(Ti::Scalar {
+                        kind: crate::ScalarKind::Bool,
+                        width: crate::BOOL_WIDTH,
+                    }),
+                },
+            },
             crate::Expression::Math {
                 fun,
                 arg,
                 arg1,
                 arg2: _,
+                arg3: _,
             } => {
                 use crate::MathFunction as Mf;
                 let res_arg = past(arg);
@@ -565,7 +614,7 @@ impl<'a> ResolveContext<'a> {
                             format!("{:?}(_, None)", fun)
                         ))?;
                         match (res_arg.inner_with(types), past(arg1).inner_with(types)) {
-                            (&Ti::Vector {kind: _, size: columns,width}, &Ti::Vector{ size: rows, .. }) => TypeResolution::Value(Ti::Matrix { columns, rows, width }),
+                            (&Ti::Vector {kind: _, size: rows,width}, &Ti::Vector{ size: columns, .. }) => TypeResolution::Value(Ti::Matrix { columns, rows, width }),
                             (left, right) =>
                                 return Err(ResolveError::IncompatibleOperands(
                                     format!("{:?}({:?}, {:?})", fun, left, right)
@@ -632,7 +681,34 @@ impl<'a> ResolveContext<'a> {
                     },
                     // bits
                     Mf::CountOneBits |
-                    Mf::ReverseBits => res_arg.clone(),
+                    Mf::ReverseBits |
+                    Mf::ExtractBits |
+                    Mf::InsertBits |
+                    Mf::FirstTrailingBit |
+                    Mf::FirstLeadingBit => res_arg.clone(),
+                    // data packing
+                    Mf::Pack4x8snorm |
+                    Mf::Pack4x8unorm |
+                    Mf::Pack2x16snorm |
+                    Mf::Pack2x16unorm |
+                    Mf::Pack2x16float => TypeResolution::Value(Ti::Scalar {
+                        kind: crate::ScalarKind::Uint,
+                        width: 4,
+                    }),
+                    // data unpacking
+                    Mf::Unpack4x8snorm |
+                    Mf::Unpack4x8unorm => TypeResolution::Value(Ti::Vector {
+                        size: crate::VectorSize::Quad,
+                        kind: crate::ScalarKind::Float,
+                        width: 4,
+                    }),
+                    Mf::Unpack2x16snorm |
+                    Mf::Unpack2x16unorm |
+                    Mf::Unpack2x16float => TypeResolution::Value(Ti::Vector {
+                        size: crate::VectorSize::Bi,
+                        kind: crate::ScalarKind::Float,
+                        width: 4,
+                    }),
                 }
             }
             crate::Expression::As {
@@ -671,6 +747,25 @@ impl<'a> ResolveContext<'a> {
                 kind: crate::ScalarKind::Uint,
                 width: 4,
             }),
+            crate::Expression::AtomicResult {
+                kind,
+                width,
+                comparison,
+            } => TypeResolution::Value(if comparison {
+                Ti::Vector {
+                    size: crate::VectorSize::Bi,
+                    kind,
+                    width,
+                }
+            } else {
+                Ti::Scalar { kind, width }
+            }),
+            crate::Expression::SubgroupBallotResult => TypeResolution::Value(Ti::Vector {
+                size: crate::VectorSize::Quad,
+                kind: crate::ScalarKind::Uint,
+                width: 4,
+            }),
+            crate::Expression::SubgroupOperationResult { ty } => TypeResolution::Handle(ty),
         })
     }
 }
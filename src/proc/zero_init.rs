@@ -0,0 +1,518 @@
+//! Zero initialization of workgroup memory.
+
+use crate::{
+    arena::{Handle, Range},
+    ArraySize, Barrier, BinaryOperator, Binding, Block, BuiltIn, Constant, ConstantInner,
+    Expression, Function, FunctionArgument, LocalVariable, Module, ScalarKind, ScalarValue,
+    ShaderStage, Span, Statement, StorageClass, Type, TypeInner,
+};
+use bit_set::BitSet;
+
+/// Make every compute entry point of `module` start by zeroing the workgroup
+/// variables it uses, as WebGPU requires.
+///
+/// The stores are spread over the invocations of the workgroup by their
+/// `LocalInvocationIndex`, which is added as an argument to the entry points
+/// that don't have it yet. Arrays are split element-wise across all the
+/// invocations, and other variables are assigned to them in turn. A workgroup
+/// `Barrier` follows the stores, so the rest of the entry point sees the
+/// zeroed memory.
+///
+/// Variables with an initializer are set to it instead, and the initializer
+/// is removed, since no backend honors it for workgroup memory.
+pub fn zero_initialize_workgroup_memory(module: &mut Module) {
+    let mut initialized = BitSet::new();
+    for index in 0..module.entry_points.len() {
+        if module.entry_points[index].stage != ShaderStage::Compute {
+            continue;
+        }
+        let mut used = BitSet::new();
+        collect_workgroup_globals(module, &module.entry_points[index].function, &mut used);
+        if used.is_empty() {
+            continue;
+        }
+
+        let mut function = std::mem::take(&mut module.entry_points[index].function);
+        let [x, y, z] = module.entry_points[index].workgroup_size;
+        let mut initializer = Initializer {
+            module,
+            function: &mut function,
+            invocations: (x * y * z).max(1),
+            next_invocation: 0,
+        };
+        let mut prologue = Block::new();
+        let local_index = initializer.local_invocation_index(&mut prologue);
+        for handle in used.iter() {
+            let (handle, var) = initializer
+                .module
+                .global_variables
+                .iter()
+                .nth(handle)
+                .unwrap();
+            let (ty, init) = (var.ty, var.init);
+            let pointer = initializer.append(Expression::GlobalVariable(handle));
+            initializer.initialize(&mut prologue, pointer, ty, init, Some(local_index));
+        }
+        prologue.push(Statement::Barrier(Barrier::WORK_GROUP), Span::default());
+        prologue.append(&mut function.body);
+        function.body = prologue;
+        module.entry_points[index].function = function;
+        initialized.union_with(&used);
+    }
+
+    for (handle, var) in module.global_variables.iter_mut() {
+        if initialized.contains(handle.index()) {
+            var.init = None;
+        }
+    }
+}
+
+/// Collect the workgroup variables used by `function` and its callees.
+fn collect_workgroup_globals(module: &Module, function: &Function, used: &mut BitSet) {
+    for (_, expression) in function.expressions.iter() {
+        if let Expression::GlobalVariable(handle) = *expression {
+            if module.global_variables[handle].class == StorageClass::WorkGroup {
+                used.insert(handle.index());
+            }
+        }
+    }
+    let mut callees = Vec::new();
+    collect_callees(&function.body, &mut callees);
+    for callee in callees {
+        collect_workgroup_globals(module, &module.functions[callee], used);
+    }
+}
+
+fn collect_callees(block: &Block, callees: &mut Vec<Handle<Function>>) {
+    for statement in block.iter() {
+        match *statement {
+            Statement::Call { function, .. } => callees.push(function),
+            Statement::Block(ref block) => collect_callees(block, callees),
+            Statement::If {
+                ref accept,
+                ref reject,
+                ..
+            } => {
+                collect_callees(accept, callees);
+                collect_callees(reject, callees);
+            }
+            Statement::Switch {
+                ref cases,
+                ref default,
+                ..
+            } => {
+                for case in cases.iter() {
+                    collect_callees(&case.body, callees);
+                }
+                collect_callees(default, callees);
+            }
+            Statement::Loop {
+                ref body,
+                ref continuing,
+            } => {
+                collect_callees(body, callees);
+                collect_callees(continuing, callees);
+            }
+            _ => {}
+        }
+    }
+}
+
+struct Initializer<'a> {
+    module: &'a mut Module,
+    function: &'a mut Function,
+    /// Number of invocations in a workgroup.
+    invocations: u32,
+    /// Invocation that stores the next variable that isn't split.
+    next_invocation: u32,
+}
+
+impl Initializer<'_> {
+    fn append(&mut self, expression: Expression) -> Handle<Expression> {
+        self.function
+            .expressions
+            .append(expression, Span::default())
+    }
+
+    fn emit(&mut self, block: &mut Block, expression: Expression) -> Handle<Expression> {
+        let handle = self.append(expression);
+        block.push(
+            Statement::Emit(Range::new_from_bounds(handle, handle)),
+            Span::default(),
+        );
+        handle
+    }
+
+    fn uint_type(&mut self) -> Handle<Type> {
+        self.module.types.fetch_or_append(
+            Type {
+                name: None,
+                inner: TypeInner::Scalar {
+                    kind: ScalarKind::Uint,
+                    width: 4,
+                },
+            },
+            Span::default(),
+        )
+    }
+
+    fn uint_constant(&mut self, value: u32) -> Handle<Expression> {
+        let constant = self.module.constants.fetch_or_append(
+            Constant {
+                name: None,
+                specialization: None,
+                inner: ConstantInner::Scalar {
+                    width: 4,
+                    value: ScalarValue::Uint(value as u64),
+                },
+            },
+            Span::default(),
+        );
+        self.append(Expression::Constant(constant))
+    }
+
+    /// Find the `LocalInvocationIndex` builtin among the arguments of the
+    /// entry point, or add it.
+    fn local_invocation_index(&mut self, block: &mut Block) -> Handle<Expression> {
+        let builtin = Some(Binding::BuiltIn(BuiltIn::LocalInvocationIndex));
+        for (index, argument) in self.function.arguments.iter().enumerate() {
+            if argument.binding == builtin {
+                return self.append(Expression::FunctionArgument(index as u32));
+            }
+            if let TypeInner::Struct { ref members, .. } = self.module.types[argument.ty].inner {
+                if let Some(member) = members.iter().position(|m| m.binding == builtin) {
+                    let base = self.append(Expression::FunctionArgument(index as u32));
+                    return self.emit(
+                        block,
+                        Expression::AccessIndex {
+                            base,
+                            index: member as u32,
+                        },
+                    );
+                }
+            }
+        }
+
+        let ty = self.uint_type();
+        let index = self.function.arguments.len() as u32;
+        self.function.arguments.push(FunctionArgument {
+            name: Some("local_invocation_index".to_string()),
+            ty,
+            binding: builtin,
+        });
+        self.append(Expression::FunctionArgument(index))
+    }
+
+    /// Store the zero value of `ty`, or `init`, through `pointer`.
+    ///
+    /// If `local_index` is given, the work is spread across the workgroup,
+    /// otherwise the current invocation does all of it.
+    fn initialize(
+        &mut self,
+        block: &mut Block,
+        pointer: Handle<Expression>,
+        ty: Handle<Type>,
+        init: Option<Handle<Constant>>,
+        local_index: Option<Handle<Expression>>,
+    ) {
+        if init.is_none() {
+            match self.module.types[ty].inner {
+                TypeInner::Array {
+                    base,
+                    size: ArraySize::Constant(size),
+                    ..
+                } => {
+                    let length = self.module.constants[size].to_array_length().unwrap();
+                    self.initialize_elements(block, pointer, base, length, local_index);
+                    return;
+                }
+                // atomics can't be stored as a part of a composite value
+                TypeInner::Struct { ref members, .. } if self.has_atomics(ty) => {
+                    let members = members.iter().map(|member| member.ty).collect::<Vec<_>>();
+                    for (index, member_ty) in members.into_iter().enumerate() {
+                        let member_pointer = self.emit(
+                            block,
+                            Expression::AccessIndex {
+                                base: pointer,
+                                index: index as u32,
+                            },
+                        );
+                        self.initialize(block, member_pointer, member_ty, None, local_index);
+                    }
+                    return;
+                }
+                _ => {}
+            }
+        }
+
+        let constant = match init {
+            Some(constant) => constant,
+            None => self.zero_constant(ty),
+        };
+        let value = self.append(Expression::Constant(constant));
+        let store = Statement::Store { pointer, value };
+        match local_index {
+            Some(local_index) => {
+                let invocation = self.next_invocation;
+                self.next_invocation = (invocation + 1) % self.invocations;
+                let right = self.uint_constant(invocation);
+                let condition = self.emit(
+                    block,
+                    Expression::Binary {
+                        op: BinaryOperator::Equal,
+                        left: local_index,
+                        right,
+                    },
+                );
+                block.push(
+                    Statement::If {
+                        condition,
+                        accept: Block::from_vec(vec![store]),
+                        reject: Block::new(),
+                    },
+                    Span::default(),
+                );
+            }
+            None => block.push(store, Span::default()),
+        }
+    }
+
+    /// Loop over the elements of the array behind `pointer`, starting at
+    /// `local_index` and stepping by the workgroup size if it's given.
+    fn initialize_elements(
+        &mut self,
+        block: &mut Block,
+        pointer: Handle<Expression>,
+        base: Handle<Type>,
+        length: u32,
+        local_index: Option<Handle<Expression>>,
+    ) {
+        let (start, step) = match local_index {
+            Some(local_index) => (local_index, self.invocations),
+            None => (self.uint_constant(0), 1),
+        };
+        let ty = self.uint_type();
+        let counter = self.function.local_variables.append(
+            LocalVariable {
+                name: None,
+                ty,
+                init: None,
+            },
+            Span::default(),
+        );
+        let counter = self.append(Expression::LocalVariable(counter));
+        block.push(
+            Statement::Store {
+                pointer: counter,
+                value: start,
+            },
+            Span::default(),
+        );
+
+        let mut body = Block::new();
+        let index = self.emit(&mut body, Expression::Load { pointer: counter });
+        let right = self.uint_constant(length);
+        let condition = self.emit(
+            &mut body,
+            Expression::Binary {
+                op: BinaryOperator::GreaterEqual,
+                left: index,
+                right,
+            },
+        );
+        body.push(
+            Statement::If {
+                condition,
+                accept: Block::from_vec(vec![Statement::Break]),
+                reject: Block::new(),
+            },
+            Span::default(),
+        );
+        let element = self.emit(
+            &mut body,
+            Expression::Access {
+                base: pointer,
+                index,
+            },
+        );
+        self.initialize(&mut body, element, base, None, None);
+
+        let mut continuing = Block::new();
+        let index = self.emit(&mut continuing, Expression::Load { pointer: counter });
+        let right = self.uint_constant(step);
+        let next = self.emit(
+            &mut continuing,
+            Expression::Binary {
+                op: BinaryOperator::Add,
+                left: index,
+                right,
+            },
+        );
+        continuing.push(
+            Statement::Store {
+                pointer: counter,
+                value: next,
+            },
+            Span::default(),
+        );
+
+        block.push(Statement::Loop { body, continuing }, Span::default());
+    }
+
+    fn has_atomics(&self, ty: Handle<Type>) -> bool {
+        match self.module.types[ty].inner {
+            TypeInner::Atomic { .. } => true,
+            TypeInner::Array { base, .. } => self.has_atomics(base),
+            TypeInner::Struct { ref members, .. } => {
+                members.iter().any(|member| self.has_atomics(member.ty))
+            }
+            _ => false,
+        }
+    }
+
+    /// Return the zero value of `ty`, with atomics being plain scalars.
+    fn zero_constant(&mut self, ty: Handle<Type>) -> Handle<Constant> {
+        let inner = match self.module.types[ty].inner {
+            TypeInner::Scalar { kind, width } | TypeInner::Atomic { kind, width } => {
+                let value = match kind {
+                    ScalarKind::Sint => ScalarValue::Sint(0),
+                    ScalarKind::Uint => ScalarValue::Uint(0),
+                    ScalarKind::Float => ScalarValue::Float(0.0),
+                    ScalarKind::Bool => ScalarValue::Bool(false),
+                };
+                ConstantInner::Scalar { width, value }
+            }
+            TypeInner::Vector { size, kind, width } => {
+                let scalar = self.module.types.fetch_or_append(
+                    Type {
+                        name: None,
+                        inner: TypeInner::Scalar { kind, width },
+                    },
+                    Span::default(),
+                );
+                let component = self.zero_constant(scalar);
+                ConstantInner::Composite {
+                    ty,
+                    components: vec![component; size as usize],
+                }
+            }
+            TypeInner::Matrix {
+                columns,
+                rows,
+                width,
+            } => {
+                let column = self.module.types.fetch_or_append(
+                    Type {
+                        name: None,
+                        inner: TypeInner::Vector {
+                            size: rows,
+                            kind: ScalarKind::Float,
+                            width,
+                        },
+                    },
+                    Span::default(),
+                );
+                let component = self.zero_constant(column);
+                ConstantInner::Composite {
+                    ty,
+                    components: vec![component; columns as usize],
+                }
+            }
+            TypeInner::Array {
+                base,
+                size: ArraySize::Constant(size),
+                ..
+            } => {
+                let length = self.module.constants[size].to_array_length().unwrap();
+                let component = self.zero_constant(base);
+                ConstantInner::Composite {
+                    ty,
+                    components: vec![component; length as usize],
+                }
+            }
+            TypeInner::Struct { ref members, .. } => {
+                let members = members.iter().map(|member| member.ty).collect::<Vec<_>>();
+                let components = members
+                    .into_iter()
+                    .map(|member| self.zero_constant(member))
+                    .collect();
+                ConstantInner::Composite { ty, components }
+            }
+            ref other => unreachable!("workgroup variable of type {:?}", other),
+        };
+        self.module.constants.fetch_or_append(
+            Constant {
+                name: None,
+                specialization: None,
+                inner,
+            },
+            Span::default(),
+        )
+    }
+}
+
+#[cfg(all(test, feature = "wgsl-in"))]
+#[test]
+fn zero_initialize() {
+    let mut module = crate::front::wgsl::parse_str(
+        "
+        struct Counters {
+            total: atomic<u32>;
+            values: array<vec2<f32>, 3>;
+        };
+        var<workgroup> counters: Counters;
+        var<workgroup> matrices: array<mat2x2<f32>, 100>;
+        var<workgroup> flag: bool;
+        var<workgroup> unused: u32;
+
+        fn count() {
+            atomicAdd(&counters.total, 1u);
+        }
+
+        [[stage(compute), workgroup_size(8, 8)]]
+        fn main([[builtin(global_invocation_id)]] id: vec3<u32>) {
+            count();
+            matrices[id.x] = mat2x2<f32>(vec2<f32>(1.0), vec2<f32>(2.0));
+            flag = true;
+        }
+        ",
+    )
+    .unwrap();
+
+    zero_initialize_workgroup_memory(&mut module);
+
+    let function = &module.entry_points[0].function;
+    assert_eq!(
+        function.arguments.last().unwrap().binding,
+        Some(Binding::BuiltIn(BuiltIn::LocalInvocationIndex))
+    );
+    let barrier = function
+        .body
+        .iter()
+        .position(|statement| match *statement {
+            Statement::Barrier(_) => true,
+            _ => false,
+        })
+        .unwrap();
+    let loops = function
+        .body
+        .iter()
+        .take(barrier)
+        .filter(|statement| match **statement {
+            Statement::Loop { .. } => true,
+            _ => false,
+        });
+    // the array inside the structure and the array of matrices
+    assert_eq!(loops.count(), 2);
+    match function.body[barrier + 1] {
+        Statement::Call { .. } => {}
+        ref other => panic!("unexpected statement {:?}", other),
+    }
+
+    crate::valid::Validator::new(
+        crate::valid::ValidationFlags::all(),
+        crate::valid::Capabilities::empty(),
+    )
+    .validate(&module)
+    .unwrap();
+}
@@ -0,0 +1,321 @@
+use crate::{Arena, Handle};
+use std::{error::Error, fmt, ops::Range};
+
+/// A source code span, used for error reporting.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+#[cfg_attr(feature = "deserialize", derive(serde::Deserialize))]
+pub struct Span {
+    start: u32,
+    end: u32,
+}
+
+impl Span {
+    /// A span that doesn't point anywhere in the source.
+    pub const UNDEFINED: Self = Self { start: 0, end: 0 };
+
+    /// Creates a new `Span` from a range of byte indices.
+    ///
+    /// Note: `end` is exclusive, it doesn't belong to the `Span`.
+    pub fn new(start: u32, end: u32) -> Self {
+        Span { start, end }
+    }
+
+    /// Returns a new `Span` starting at `self` and ending at `other`.
+    pub fn until(&self, other: &Self) -> Self {
+        Span {
+            start: self.start,
+            end: other.end,
+        }
+    }
+
+    /// Modifies `self` to contain the smallest `Span` possible that
+    /// contains both `self` and `other`.
+    pub fn subsume(&mut self, other: Self) {
+        *self = if !self.is_defined() {
+            // self isn't defined so use other
+            other
+        } else if !other.is_defined() {
+            // other isn't defined so don't try to subsume
+            *self
+        } else {
+            // Both self and other are defined so calculate the span that contains them both
+            Span {
+                start: self.start.min(other.start),
+                end: self.end.max(other.end),
+            }
+        }
+    }
+
+    /// Returns the smallest `Span` possible that contains all the `Span`s
+    /// defined in the `from` iterator.
+    pub fn total_span<T: Iterator<Item = Self>>(from: T) -> Self {
+        let mut span: Self = Default::default();
+        for other in from {
+            span.subsume(other);
+        }
+        span
+    }
+
+    /// Converts `self` to a range if the span is not unknown.
+    pub fn to_range(self) -> Option<Range<usize>> {
+        if self.is_defined() {
+            Some(self.start as usize..self.end as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Checks if `self` was passed a valid range.
+    pub fn is_defined(&self) -> bool {
+        *self != Self::UNDEFINED
+    }
+}
+
+impl From<Range<usize>> for Span {
+    fn from(range: Range<usize>) -> Self {
+        Span {
+            start: range.start as u32,
+            end: range.end as u32,
+        }
+    }
+}
+
+/// A source code span together with a description of what it points at.
+pub type SpanContext = (Span, String);
+
+/// Wrapper class for [`Error`], augmenting it with a list of [`SpanContext`]s.
+#[derive(Debug, Clone)]
+pub struct WithSpan<E> {
+    inner: E,
+    #[cfg(feature = "span")]
+    spans: Vec<SpanContext>,
+}
+
+impl<E> fmt::Display for WithSpan<E>
+where
+    E: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+#[cfg(test)]
+impl<E> PartialEq for WithSpan<E>
+where
+    E: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.inner.eq(&other.inner)
+    }
+}
+
+impl<E> Error for WithSpan<E>
+where
+    E: Error,
+{
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.inner.source()
+    }
+}
+
+impl<E> WithSpan<E> {
+    /// Create a new [`WithSpan`] from an [`Error`], containing no spans.
+    pub fn new(inner: E) -> Self {
+        Self {
+            inner,
+            #[cfg(feature = "span")]
+            spans: Vec::new(),
+        }
+    }
+
+    /// Reverse of [`Self::new`], discards span information and returns an inner error.
+    pub fn into_inner(self) -> E {
+        self.inner
+    }
+
+    /// Returns a reference to the inner error.
+    pub fn as_inner(&self) -> &E {
+        &self.inner
+    }
+
+    /// Iterator over stored [`SpanContext`]s.
+    pub fn spans(&self) -> impl ExactSizeIterator<Item = &SpanContext> {
+        #[cfg(feature = "span")]
+        return self.spans.iter();
+        #[cfg(not(feature = "span"))]
+        return std::iter::empty();
+    }
+
+    /// Add a new span with description.
+    #[cfg_attr(not(feature = "span"), allow(unused_variables, unused_mut))]
+    pub fn with_span<S>(mut self, span: Span, description: S) -> Self
+    where
+        S: ToString,
+    {
+        #[cfg(feature = "span")]
+        if span.is_defined() {
+            self.spans.push((span, description.to_string()));
+        }
+        self
+    }
+
+    /// Add a [`SpanContext`].
+    pub fn with_context(self, span_context: SpanContext) -> Self {
+        let (span, description) = span_context;
+        self.with_span(span, description)
+    }
+
+    /// Add a [`Handle`] from an [`Arena`], borrowing its span information and providing
+    /// a default label.
+    pub(crate) fn with_handle<T>(self, handle: Handle<T>, arena: &Arena<T>) -> Self {
+        self.with_context(arena.get_span_context(handle))
+    }
+
+    /// Convert inner error using [`From`].
+    pub fn into_other<E2>(self) -> WithSpan<E2>
+    where
+        E2: From<E>,
+    {
+        WithSpan {
+            inner: self.inner.into(),
+            #[cfg(feature = "span")]
+            spans: self.spans,
+        }
+    }
+
+    /// Convert inner error into another type. Joins span information contained in `self`
+    /// with what is returned from `func`.
+    pub fn and_then<F, E2>(self, func: F) -> WithSpan<E2>
+    where
+        F: FnOnce(E) -> WithSpan<E2>,
+    {
+        #[cfg_attr(not(feature = "span"), allow(unused_mut))]
+        let mut res = func(self.inner);
+        #[cfg(feature = "span")]
+        res.spans.extend(self.spans);
+        res
+    }
+
+    #[cfg(feature = "span")]
+    fn diagnostic(&self) -> codespan_reporting::diagnostic::Diagnostic<()>
+    where
+        E: Error,
+    {
+        use codespan_reporting::diagnostic::{Diagnostic, Label};
+        let diagnostic = Diagnostic::error()
+            .with_message(self.inner.to_string())
+            .with_labels(
+                self.spans()
+                    .map(|&(span, ref desc)| {
+                        Label::primary((), span.to_range().unwrap()).with_message(desc.to_owned())
+                    })
+                    .collect(),
+            )
+            .with_notes({
+                let mut notes = Vec::new();
+                let mut source: &dyn Error = &self.inner;
+                while let Some(next) = Error::source(source) {
+                    notes.push(next.to_string());
+                    source = next;
+                }
+                notes
+            });
+        diagnostic
+    }
+
+    /// Emits a summary of the error to standard error stream.
+    #[cfg(feature = "span")]
+    pub fn emit_to_stderr(&self, source: &str)
+    where
+        E: Error,
+    {
+        self.emit_to_stderr_with_path(source, "wgsl")
+    }
+
+    /// Emits a summary of the error to standard error stream.
+    #[cfg(feature = "span")]
+    pub fn emit_to_stderr_with_path(&self, source: &str, path: &str)
+    where
+        E: Error,
+    {
+        use codespan_reporting::{files, term};
+        use term::termcolor::{ColorChoice, StandardStream};
+
+        let files = files::SimpleFile::new(path, source);
+        let config = term::Config::default();
+        let writer = StandardStream::stderr(ColorChoice::Auto);
+        term::emit(&mut writer.lock(), &config, &files, &self.diagnostic())
+            .expect("cannot write error");
+    }
+
+    /// Emits a summary of the error to a string.
+    #[cfg(feature = "span")]
+    pub fn emit_to_string(&self, source: &str) -> String
+    where
+        E: Error,
+    {
+        self.emit_to_string_with_path(source, "wgsl")
+    }
+
+    /// Emits a summary of the error to a string.
+    #[cfg(feature = "span")]
+    pub fn emit_to_string_with_path(&self, source: &str, path: &str) -> String
+    where
+        E: Error,
+    {
+        use codespan_reporting::{files, term};
+        use term::termcolor::NoColor;
+
+        let files = files::SimpleFile::new(path, source);
+        let config = term::Config::default();
+        let mut writer = NoColor::new(Vec::new());
+        term::emit(&mut writer, &config, &files, &self.diagnostic()).expect("cannot write error");
+        String::from_utf8(writer.into_inner()).unwrap()
+    }
+}
+
+/// Convenience trait for [`Error`] to be able to apply spans to anything.
+pub(crate) trait AddSpan: Sized {
+    type Output;
+    /// See [`WithSpan::new`].
+    fn with_span(self) -> Self::Output;
+    /// See [`WithSpan::with_span`].
+    fn with_span_static(self, span: Span, description: &'static str) -> Self::Output;
+    /// See [`WithSpan::with_handle`].
+    fn with_span_handle<T>(self, handle: Handle<T>, arena: &Arena<T>) -> Self::Output;
+}
+
+impl<E> AddSpan for E
+where
+    E: Error,
+{
+    type Output = WithSpan<Self>;
+    fn with_span(self) -> WithSpan<Self> {
+        WithSpan::new(self)
+    }
+
+    fn with_span_static(self, span: Span, description: &'static str) -> WithSpan<Self> {
+        WithSpan::new(self).with_span(span, description)
+    }
+
+    fn with_span_handle<T>(self, handle: Handle<T>, arena: &Arena<T>) -> WithSpan<Self> {
+        WithSpan::new(self).with_handle(handle, arena)
+    }
+}
+
+#[test]
+fn span_location() {
+    let mut span = Span::new(3, 6);
+    assert_eq!(span.to_range(), Some(3..6));
+    span.subsume(Span::new(1, 4));
+    assert_eq!(span, Span::new(1, 6));
+    span.subsume(Span::UNDEFINED);
+    assert_eq!(span, Span::new(1, 6));
+    assert_eq!(Span::UNDEFINED.to_range(), None);
+    assert_eq!(
+        Span::total_span(vec![Span::new(5, 7), Span::new(2, 3)].into_iter()),
+        Span::new(2, 7)
+    );
+}
@@ -0,0 +1,442 @@
+//! Operand layouts of SPIR-V instructions, shared by the assembler in
+//! `front::spv` and the disassembler in `back::spv`.
+//!
+//! Only the instructions of the core specification that matter for shaders
+//! are described. Other instructions are assumed to have a result type,
+//! a result and id operands, and any words left over after an operand
+//! layout are treated as plain literals.
+
+use num_traits::FromPrimitive;
+use spirv::Op;
+use std::fmt::Debug;
+
+/// Upper bound on the values of the enumerants we know about.
+#[cfg(feature = "spv-in")]
+const MAX_ENUMERANT: u32 = 0x1800;
+
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq)]
+pub(crate) enum EnumKind {
+    SourceLanguage,
+    ExecutionModel,
+    AddressingModel,
+    MemoryModel,
+    StorageClass,
+    Dim,
+    ImageFormat,
+    AccessQualifier,
+    Decoration,
+    BuiltIn,
+    Capability,
+    GroupOperation,
+    SamplerAddressingMode,
+    SamplerFilterMode,
+    FunctionParameterAttribute,
+    FPRoundingMode,
+    LinkageType,
+    ExecutionMode,
+    /// Instructions of the `GLSL.std.450` extended instruction set.
+    GlslStd450,
+}
+
+fn debug_name<T: Debug>(value: Option<T>) -> Option<String> {
+    value.map(|value| format!("{:?}", value))
+}
+
+impl EnumKind {
+    /// Return the name of the enumerant `value`, if it's known.
+    pub(crate) fn name(self, value: u32) -> Option<String> {
+        match self {
+            EnumKind::SourceLanguage => debug_name(spirv::SourceLanguage::from_u32(value)),
+            EnumKind::ExecutionModel => debug_name(spirv::ExecutionModel::from_u32(value)),
+            EnumKind::AddressingModel => debug_name(spirv::AddressingModel::from_u32(value)),
+            EnumKind::MemoryModel => debug_name(spirv::MemoryModel::from_u32(value)),
+            EnumKind::StorageClass => debug_name(spirv::StorageClass::from_u32(value)),
+            // `Dim1D` and friends are spelled without the prefix.
+            EnumKind::Dim => {
+                debug_name(spirv::Dim::from_u32(value)).map(|name| name[3..].to_string())
+            }
+            EnumKind::ImageFormat => debug_name(spirv::ImageFormat::from_u32(value)),
+            EnumKind::AccessQualifier => debug_name(spirv::AccessQualifier::from_u32(value)),
+            EnumKind::Decoration => debug_name(spirv::Decoration::from_u32(value)),
+            EnumKind::BuiltIn => debug_name(spirv::BuiltIn::from_u32(value)),
+            EnumKind::Capability => debug_name(spirv::Capability::from_u32(value)),
+            EnumKind::GroupOperation => debug_name(spirv::GroupOperation::from_u32(value)),
+            EnumKind::SamplerAddressingMode => {
+                debug_name(spirv::SamplerAddressingMode::from_u32(value))
+            }
+            EnumKind::SamplerFilterMode => debug_name(spirv::SamplerFilterMode::from_u32(value)),
+            EnumKind::FunctionParameterAttribute => {
+                debug_name(spirv::FunctionParameterAttribute::from_u32(value))
+            }
+            EnumKind::FPRoundingMode => debug_name(spirv::FPRoundingMode::from_u32(value)),
+            EnumKind::LinkageType => debug_name(spirv::LinkageType::from_u32(value)),
+            EnumKind::ExecutionMode => debug_name(spirv::ExecutionMode::from_u32(value)),
+            EnumKind::GlslStd450 => debug_name(spirv::GLOp::from_u32(value)),
+        }
+    }
+
+    /// Return the names and values of all the known enumerants.
+    #[cfg(feature = "spv-in")]
+    pub(crate) fn enumerants(self) -> Vec<(String, u32)> {
+        (0..MAX_ENUMERANT)
+            .filter_map(|value| self.name(value).map(|name| (name, value)))
+            .collect()
+    }
+}
+
+/// Return the names and values of all the known instructions.
+#[cfg(feature = "spv-in")]
+pub(crate) fn instructions() -> Vec<(String, u32)> {
+    (0..MAX_ENUMERANT)
+        .filter_map(|value| debug_name(Op::from_u32(value)).map(|name| (name, value)))
+        .collect()
+}
+
+/// Type of a literal number operand, derived from `OpTypeInt` and `OpTypeFloat`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum NumberType {
+    Int { signed: bool, width: u32 },
+    Float { width: u32 },
+}
+
+impl NumberType {
+    pub(crate) fn from_instruction(op: Op, operands: &[u32]) -> Option<Self> {
+        match (op, operands) {
+            (Op::TypeInt, &[width, signedness]) => Some(NumberType::Int {
+                signed: signedness != 0,
+                width,
+            }),
+            (Op::TypeFloat, &[width]) => Some(NumberType::Float { width }),
+            _ => None,
+        }
+    }
+
+    /// Return the number of words a literal of this type occupies.
+    pub(crate) fn word_count(self) -> usize {
+        match self {
+            NumberType::Int { width, .. } | NumberType::Float { width } if width > 32 => 2,
+            _ => 1,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum MaskKind {
+    FunctionControl,
+    SelectionControl,
+    LoopControl,
+    MemoryAccess,
+    ImageOperands,
+}
+
+impl MaskKind {
+    /// Return the names of the individual bits of the mask.
+    pub(crate) fn bits(self) -> &'static [(&'static str, u32)] {
+        match self {
+            MaskKind::FunctionControl => &[
+                ("Inline", 0x1),
+                ("DontInline", 0x2),
+                ("Pure", 0x4),
+                ("Const", 0x8),
+            ],
+            MaskKind::SelectionControl => &[("Flatten", 0x1), ("DontFlatten", 0x2)],
+            MaskKind::LoopControl => &[
+                ("Unroll", 0x1),
+                ("DontUnroll", 0x2),
+                ("DependencyInfinite", 0x4),
+                ("DependencyLength", 0x8),
+                ("MinIterations", 0x10),
+                ("MaxIterations", 0x20),
+                ("IterationMultiple", 0x40),
+                ("PeelCount", 0x80),
+                ("PartialCount", 0x100),
+            ],
+            MaskKind::MemoryAccess => &[
+                ("Volatile", 0x1),
+                ("Aligned", 0x2),
+                ("Nontemporal", 0x4),
+                ("MakePointerAvailable", 0x8),
+                ("MakePointerVisible", 0x10),
+                ("NonPrivatePointer", 0x20),
+            ],
+            MaskKind::ImageOperands => &[
+                ("Bias", 0x1),
+                ("Lod", 0x2),
+                ("Grad", 0x4),
+                ("ConstOffset", 0x8),
+                ("Offset", 0x10),
+                ("ConstOffsets", 0x20),
+                ("Sample", 0x40),
+                ("MinLod", 0x80),
+                ("MakeTexelAvailable", 0x100),
+                ("MakeTexelVisible", 0x200),
+                ("NonPrivateTexel", 0x400),
+                ("VolatileTexel", 0x800),
+                ("SignExtend", 0x1000),
+                ("ZeroExtend", 0x2000),
+            ],
+        }
+    }
+
+    /// Return the kind of the operands following a mask with the given value.
+    pub(crate) fn followed_by(self, value: u32) -> &'static [OperandKind] {
+        match self {
+            MaskKind::FunctionControl | MaskKind::SelectionControl => &[],
+            MaskKind::LoopControl => &[OperandKind::LiteralRest],
+            MaskKind::MemoryAccess if value & 0x2 != 0 => {
+                &[OperandKind::Literal, OperandKind::IdRest]
+            }
+            MaskKind::MemoryAccess | MaskKind::ImageOperands => &[OperandKind::IdRest],
+        }
+    }
+}
+
+/// Kind of an operand, or of all the remaining operands.
+///
+/// Operands may be missing at the end of an instruction, so all of them are
+/// effectively optional.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum OperandKind {
+    Id,
+    Literal,
+    String,
+    /// Literal number, whose width and type is given by the result type.
+    Number,
+    Enum(EnumKind),
+    Mask(MaskKind),
+    /// Decoration, followed by its own operands.
+    Decoration,
+    /// Execution mode, followed by literal operands.
+    ExecutionMode,
+    /// Instruction of the extended instruction set given by the previous id.
+    ExtInstruction,
+    /// Pairs of literals, sized as the selector, and labels of `OpSwitch`.
+    SwitchTargets,
+    IdRest,
+    LiteralRest,
+}
+
+/// Layout of a SPIR-V instruction.
+pub(crate) struct Layout {
+    pub result_type: bool,
+    pub result: bool,
+    pub operands: &'static [OperandKind],
+}
+
+fn layout(result_type: bool, result: bool, operands: &'static [OperandKind]) -> Layout {
+    Layout {
+        result_type,
+        result,
+        operands,
+    }
+}
+
+/// Return the operand layout of `op`.
+pub(crate) fn layout_of(op: Op) -> Layout {
+    use EnumKind as Ek;
+    use MaskKind as Mk;
+    use OperandKind::*;
+
+    match op {
+        Op::Nop
+        | Op::NoLine
+        | Op::FunctionEnd
+        | Op::Kill
+        | Op::Return
+        | Op::Unreachable
+        | Op::EmitVertex
+        | Op::EndPrimitive
+        | Op::BeginInvocationInterlockEXT
+        | Op::EndInvocationInterlockEXT
+        | Op::DemoteToHelperInvocationEXT => layout(false, false, &[]),
+        Op::SourceContinued | Op::SourceExtension | Op::Extension | Op::ModuleProcessed => {
+            layout(false, false, &[String])
+        }
+        Op::Source => layout(
+            false,
+            false,
+            &[Enum(Ek::SourceLanguage), Literal, Id, String],
+        ),
+        Op::Name => layout(false, false, &[Id, String]),
+        Op::MemberName => layout(false, false, &[Id, Literal, String]),
+        Op::String | Op::ExtInstImport | Op::TypeOpaque => layout(false, true, &[String]),
+        Op::Line => layout(false, false, &[Id, Literal, Literal]),
+        Op::ExtInst => layout(true, true, &[Id, ExtInstruction, IdRest]),
+        Op::MemoryModel => layout(
+            false,
+            false,
+            &[Enum(Ek::AddressingModel), Enum(Ek::MemoryModel)],
+        ),
+        Op::EntryPoint => layout(
+            false,
+            false,
+            &[Enum(Ek::ExecutionModel), Id, String, IdRest],
+        ),
+        Op::ExecutionMode => layout(false, false, &[Id, ExecutionMode]),
+        Op::Capability => layout(false, false, &[Enum(Ek::Capability)]),
+        Op::TypeVoid
+        | Op::TypeBool
+        | Op::TypeSampler
+        | Op::TypeEvent
+        | Op::TypeDeviceEvent
+        | Op::TypeReserveId
+        | Op::TypeQueue
+        | Op::TypeNamedBarrier
+        | Op::DecorationGroup
+        | Op::Label => layout(false, true, &[]),
+        Op::TypeInt => layout(false, true, &[Literal, Literal]),
+        Op::TypeFloat => layout(false, true, &[Literal]),
+        Op::TypeVector | Op::TypeMatrix => layout(false, true, &[Id, Literal]),
+        Op::TypeImage => layout(
+            false,
+            true,
+            &[
+                Id,
+                Enum(Ek::Dim),
+                Literal,
+                Literal,
+                Literal,
+                Literal,
+                Enum(Ek::ImageFormat),
+                Enum(Ek::AccessQualifier),
+            ],
+        ),
+        Op::TypeSampledImage | Op::TypeRuntimeArray => layout(false, true, &[Id]),
+        Op::TypeArray => layout(false, true, &[Id, Id]),
+        Op::TypeStruct => layout(false, true, &[IdRest]),
+        Op::TypePointer => layout(false, true, &[Enum(Ek::StorageClass), Id]),
+        Op::TypeFunction => layout(false, true, &[Id, IdRest]),
+        Op::TypePipe => layout(false, true, &[Enum(Ek::AccessQualifier)]),
+        Op::TypeForwardPointer => layout(false, false, &[Id, Enum(Ek::StorageClass)]),
+        Op::Constant | Op::SpecConstant => layout(true, true, &[Number]),
+        Op::ConstantSampler => layout(
+            true,
+            true,
+            &[
+                Enum(Ek::SamplerAddressingMode),
+                Literal,
+                Enum(Ek::SamplerFilterMode),
+            ],
+        ),
+        Op::SpecConstantOp => layout(true, true, &[Literal, IdRest]),
+        Op::Function => layout(true, true, &[Mask(Mk::FunctionControl), Id]),
+        Op::Variable => layout(true, true, &[Enum(Ek::StorageClass), Id]),
+        Op::Load => layout(true, true, &[Id, Mask(Mk::MemoryAccess)]),
+        Op::Store => layout(false, false, &[Id, Id, Mask(Mk::MemoryAccess)]),
+        Op::CopyMemory => layout(false, false, &[Id, Id, Mask(Mk::MemoryAccess)]),
+        Op::CopyMemorySized => layout(false, false, &[Id, Id, Id, Mask(Mk::MemoryAccess)]),
+        Op::ArrayLength => layout(true, true, &[Id, Literal]),
+        Op::Decorate => layout(false, false, &[Id, Decoration]),
+        Op::MemberDecorate => layout(false, false, &[Id, Literal, Decoration]),
+        Op::DecorateId => layout(false, false, &[Id, Enum(Ek::Decoration), IdRest]),
+        Op::DecorateString => layout(false, false, &[Id, Enum(Ek::Decoration), String]),
+        Op::MemberDecorateString => {
+            layout(false, false, &[Id, Literal, Enum(Ek::Decoration), String])
+        }
+        Op::GroupDecorate => layout(false, false, &[IdRest]),
+        Op::GroupMemberDecorate => layout(false, false, &[Id, IdRest]),
+        Op::VectorShuffle => layout(true, true, &[Id, Id, LiteralRest]),
+        Op::CompositeExtract => layout(true, true, &[Id, LiteralRest]),
+        Op::CompositeInsert => layout(true, true, &[Id, Id, LiteralRest]),
+        Op::ImageSampleImplicitLod
+        | Op::ImageSampleExplicitLod
+        | Op::ImageSampleProjImplicitLod
+        | Op::ImageSampleProjExplicitLod
+        | Op::ImageFetch
+        | Op::ImageRead
+        | Op::ImageSparseSampleImplicitLod
+        | Op::ImageSparseSampleExplicitLod
+        | Op::ImageSparseSampleProjImplicitLod
+        | Op::ImageSparseSampleProjExplicitLod
+        | Op::ImageSparseFetch
+        | Op::ImageSparseRead => layout(true, true, &[Id, Id, Mask(Mk::ImageOperands)]),
+        Op::ImageSampleDrefImplicitLod
+        | Op::ImageSampleDrefExplicitLod
+        | Op::ImageSampleProjDrefImplicitLod
+        | Op::ImageSampleProjDrefExplicitLod
+        | Op::ImageGather
+        | Op::ImageDrefGather
+        | Op::ImageSparseSampleDrefImplicitLod
+        | Op::ImageSparseSampleDrefExplicitLod
+        | Op::ImageSparseSampleProjDrefImplicitLod
+        | Op::ImageSparseSampleProjDrefExplicitLod
+        | Op::ImageSparseGather
+        | Op::ImageSparseDrefGather => layout(true, true, &[Id, Id, Id, Mask(Mk::ImageOperands)]),
+        Op::ImageWrite => layout(false, false, &[Id, Id, Id, Mask(Mk::ImageOperands)]),
+        Op::ControlBarrier
+        | Op::MemoryBarrier
+        | Op::AtomicStore
+        | Op::AtomicFlagClear
+        | Op::EmitStreamVertex
+        | Op::EndStreamPrimitive
+        | Op::GroupWaitEvents
+        | Op::RetainEvent
+        | Op::ReleaseEvent
+        | Op::SetUserEventStatus
+        | Op::CaptureEventProfilingInfo
+        | Op::CommitReadPipe
+        | Op::CommitWritePipe
+        | Op::GroupCommitReadPipe
+        | Op::GroupCommitWritePipe
+        | Op::MemoryNamedBarrier
+        | Op::ReturnValue
+        | Op::Branch
+        | Op::TerminateRayNV
+        | Op::IgnoreIntersectionNV
+        | Op::CooperativeMatrixStoreNV
+        | Op::SubgroupBlockWriteINTEL
+        | Op::SubgroupImageBlockWriteINTEL
+        | Op::SubgroupImageMediaBlockWriteINTEL => layout(false, false, &[IdRest]),
+        Op::LifetimeStart | Op::LifetimeStop => layout(false, false, &[Id, Literal]),
+        Op::LoopMerge => layout(false, false, &[Id, Id, Mask(Mk::LoopControl)]),
+        Op::SelectionMerge => layout(false, false, &[Id, Mask(Mk::SelectionControl)]),
+        Op::BranchConditional => layout(false, false, &[Id, Id, Id, LiteralRest]),
+        Op::Switch => layout(false, false, &[Id, Id, SwitchTargets]),
+        Op::GroupIAdd
+        | Op::GroupFAdd
+        | Op::GroupFMin
+        | Op::GroupUMin
+        | Op::GroupSMin
+        | Op::GroupFMax
+        | Op::GroupUMax
+        | Op::GroupSMax
+        | Op::GroupNonUniformBallotBitCount
+        | Op::GroupNonUniformIAdd
+        | Op::GroupNonUniformFAdd
+        | Op::GroupNonUniformIMul
+        | Op::GroupNonUniformFMul
+        | Op::GroupNonUniformSMin
+        | Op::GroupNonUniformUMin
+        | Op::GroupNonUniformFMin
+        | Op::GroupNonUniformSMax
+        | Op::GroupNonUniformUMax
+        | Op::GroupNonUniformFMax
+        | Op::GroupNonUniformBitwiseAnd
+        | Op::GroupNonUniformBitwiseOr
+        | Op::GroupNonUniformBitwiseXor
+        | Op::GroupNonUniformLogicalAnd
+        | Op::GroupNonUniformLogicalOr
+        | Op::GroupNonUniformLogicalXor
+        | Op::GroupIAddNonUniformAMD
+        | Op::GroupFAddNonUniformAMD
+        | Op::GroupFMinNonUniformAMD
+        | Op::GroupUMinNonUniformAMD
+        | Op::GroupSMinNonUniformAMD
+        | Op::GroupFMaxNonUniformAMD
+        | Op::GroupUMaxNonUniformAMD
+        | Op::GroupSMaxNonUniformAMD => layout(true, true, &[Id, Enum(Ek::GroupOperation), IdRest]),
+        _ => layout(true, true, &[IdRest]),
+    }
+}
+
+/// Return the operand kinds following the decoration `value`.
+pub(crate) fn decoration_operands(value: u32) -> &'static [OperandKind] {
+    use OperandKind::*;
+    match spirv::Decoration::from_u32(value) {
+        Some(spirv::Decoration::BuiltIn) => &[Enum(EnumKind::BuiltIn)],
+        Some(spirv::Decoration::FuncParamAttr) => &[Enum(EnumKind::FunctionParameterAttribute)],
+        Some(spirv::Decoration::FPRoundingMode) => &[Enum(EnumKind::FPRoundingMode)],
+        Some(spirv::Decoration::LinkageAttributes) => &[String, Enum(EnumKind::LinkageType)],
+        _ => &[LiteralRest],
+    }
+}
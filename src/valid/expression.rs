@@ -1,4 +1,6 @@
-use super::{compose::validate_compose, ComposeError, FunctionInfo, ShaderStages, TypeFlags};
+use super::{
+    compose::validate_compose, Capabilities, ComposeError, FunctionInfo, ShaderStages, TypeFlags,
+};
 use crate::{
     arena::{Arena, Handle},
     proc::{ProcError, ResolveError},
@@ -21,6 +23,8 @@ pub enum ExpressionError {
     IndexOutOfBounds(Handle<crate::Expression>, crate::ScalarValue),
     #[error("The expression {0:?} may only be indexed by a constant")]
     IndexMustBeConstant(Handle<crate::Expression>),
+    #[error("Indexing {0:?} with a non-uniform value requires {1:?}")]
+    NonUniformIndex(Handle<crate::Expression>, Capabilities),
     #[error("Function argument {0:?} doesn't exist")]
     FunctionArgumentDoesntExist(u32),
     #[error("Constant {0:?} doesn't exist")]
@@ -101,6 +105,14 @@ pub enum ExpressionError {
     InvalidSampleLevelBiasType(Handle<crate::Expression>),
     #[error("Sample level (gradient) of {1:?} doesn't match the image dimension {0:?}")]
     InvalidSampleLevelGradientType(crate::ImageDimension, Handle<crate::Expression>),
+    #[error("Unable to gather from an image of class {0:?}")]
+    InvalidGatherClass(crate::ImageClass),
+    #[error("Unable to gather from an image of dimension {0:?}")]
+    InvalidGatherDimension(crate::ImageDimension),
+    #[error("Gather component {0:?} is not supported for depth images")]
+    InvalidGatherComponent(crate::SwizzleComponent),
+    #[error("Gather operations can only sample the level zero")]
+    InvalidGatherLevel,
     #[error("Unable to cast")]
     InvalidCastArgument,
     #[error("Invalid argument count for {0:?}")]
@@ -128,7 +140,52 @@ impl<'a> ExpressionTypeResolver<'a> {
     }
 }
 
+/// Return the type of the global resource `expr` refers to.
+///
+/// The resource is either a global variable, or an element of a global
+/// binding array.
+pub(super) fn global_resource_type(
+    expr: Handle<crate::Expression>,
+    expressions: &Arena<crate::Expression>,
+    global_vars: &Arena<crate::GlobalVariable>,
+    types: &Arena<crate::Type>,
+) -> Option<Handle<crate::Type>> {
+    match expressions[expr] {
+        crate::Expression::GlobalVariable(var_handle) => Some(global_vars[var_handle].ty),
+        crate::Expression::Access { base, .. } | crate::Expression::AccessIndex { base, .. } => {
+            match expressions[base] {
+                crate::Expression::GlobalVariable(var_handle) => {
+                    match types[global_vars[var_handle].ty].inner {
+                        crate::TypeInner::BindingArray { base, .. } => Some(base),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
 impl super::Validator {
+    /// Return the type of the image or sampler operand `expr`.
+    fn resource_type(
+        expr: Handle<crate::Expression>,
+        function: &crate::Function,
+        module: &crate::Module,
+    ) -> Result<Handle<crate::Type>, ExpressionError> {
+        match function.expressions[expr] {
+            crate::Expression::FunctionArgument(i) => Ok(function.arguments[i as usize].ty),
+            _ => global_resource_type(
+                expr,
+                &function.expressions,
+                &module.global_variables,
+                &module.types,
+            )
+            .ok_or(ExpressionError::ExpectedGlobalVariable),
+        }
+    }
+
     pub(super) fn validate_expression(
         &self,
         root: Handle<crate::Expression>,
@@ -151,7 +208,7 @@ impl super::Validator {
                 let base_type = resolver.resolve(base)?;
                 // See the documentation for `Expression::Access`.
                 let dynamic_indexing_restricted = match *base_type {
-                    Ti::Vector { .. } => false,
+                    Ti::Vector { .. } | Ti::BindingArray { .. } => false,
                     Ti::Matrix { .. } | Ti::Array { .. } => true,
                     Ti::Pointer { .. } | Ti::ValuePointer { size: Some(_), .. } => false,
                     ref other => {
@@ -180,6 +237,26 @@ impl super::Validator {
                     return Err(ExpressionError::IndexMustBeConstant(base));
                 }
 
+                let is_binding_array = match *base_type {
+                    Ti::BindingArray { .. } => true,
+                    Ti::Pointer { base, .. } => match module.types[base].inner {
+                        Ti::BindingArray { .. } => true,
+                        _ => false,
+                    },
+                    _ => false,
+                };
+                if is_binding_array
+                    && info[index].uniformity.non_uniform_result.is_some()
+                    && !self
+                        .capabilities
+                        .contains(Capabilities::BINDING_ARRAY_NON_UNIFORM_INDEXING)
+                {
+                    return Err(ExpressionError::NonUniformIndex(
+                        base,
+                        Capabilities::BINDING_ARRAY_NON_UNIFORM_INDEXING,
+                    ));
+                }
+
                 // If we know both the length and the index, we can do the
                 // bounds check now.
                 if let crate::proc::IndexableLength::Known(known_length) =
@@ -229,6 +306,11 @@ impl super::Validator {
                             ..
                         } => module.constants[handle].to_array_length().unwrap(),
                         Ti::Array { .. } => !0, // can't statically know, but need run-time checks
+                        Ti::BindingArray {
+                            size: crate::ArraySize::Constant(handle),
+                            ..
+                        } => module.constants[handle].to_array_length().unwrap(),
+                        Ti::BindingArray { .. } => !0,
                         Ti::Pointer { base, .. } if top_level => {
                             resolve_index_limit(module, top, &module.types[base].inner, false)?
                         }
@@ -339,22 +421,11 @@ impl super::Validator {
                 offset,
                 level,
                 depth_ref,
+                gather,
             } => {
                 // check the validity of expressions
-                let image_ty = match function.expressions[image] {
-                    crate::Expression::GlobalVariable(var_handle) => {
-                        module.global_variables[var_handle].ty
-                    }
-                    crate::Expression::FunctionArgument(i) => function.arguments[i as usize].ty,
-                    _ => return Err(ExpressionError::ExpectedGlobalVariable),
-                };
-                let sampler_ty = match function.expressions[sampler] {
-                    crate::Expression::GlobalVariable(var_handle) => {
-                        module.global_variables[var_handle].ty
-                    }
-                    crate::Expression::FunctionArgument(i) => function.arguments[i as usize].ty,
-                    _ => return Err(ExpressionError::ExpectedGlobalVariable),
-                };
+                let image_ty = Self::resource_type(image, function, module)?;
+                let sampler_ty = Self::resource_type(sampler, function, module)?;
                 let comparison = match module.types[sampler_ty].inner {
                     Ti::Sampler { comparison } => comparison,
                     _ => return Err(ExpressionError::ExpectedSamplerType(sampler_ty)),
@@ -390,8 +461,21 @@ impl super::Validator {
                         kind: crate::ScalarKind::Float,
                         multi: false,
                     } => false,
+                    crate::ImageClass::Sampled {
+                        kind: crate::ScalarKind::Sint,
+                        multi: false,
+                    }
+                    | crate::ImageClass::Sampled {
+                        kind: crate::ScalarKind::Uint,
+                        multi: false,
+                    } if gather.is_some() => false,
                     crate::ImageClass::Depth => true,
-                    _ => return Err(ExpressionError::InvalidImageClass(class)),
+                    _ => {
+                        return Err(match gather {
+                            Some(_) => ExpressionError::InvalidGatherClass(class),
+                            None => ExpressionError::InvalidImageClass(class),
+                        })
+                    }
                 };
                 if comparison != depth_ref.is_some() || (comparison && !image_depth) {
                     return Err(ExpressionError::ComparisonSamplingMismatch {
@@ -401,6 +485,22 @@ impl super::Validator {
                     });
                 }
 
+                // check gather properties
+                if let Some(component) = gather {
+                    match dim {
+                        crate::ImageDimension::D2 | crate::ImageDimension::Cube => {}
+                        crate::ImageDimension::D1 | crate::ImageDimension::D3 => {
+                            return Err(ExpressionError::InvalidGatherDimension(dim))
+                        }
+                    }
+                    if image_depth && component != crate::SwizzleComponent::X {
+                        return Err(ExpressionError::InvalidGatherComponent(component));
+                    }
+                    if level != crate::SampleLevel::Zero {
+                        return Err(ExpressionError::InvalidGatherLevel);
+                    }
+                }
+
                 // check texture coordinates type
                 let num_components = match dim {
                     crate::ImageDimension::D1 => 1,
@@ -519,13 +619,7 @@ impl super::Validator {
                 array_index,
                 index,
             } => {
-                let ty = match function.expressions[image] {
-                    crate::Expression::GlobalVariable(var_handle) => {
-                        module.global_variables[var_handle].ty
-                    }
-                    crate::Expression::FunctionArgument(i) => function.arguments[i as usize].ty,
-                    _ => return Err(ExpressionError::ExpectedGlobalVariable),
-                };
+                let ty = Self::resource_type(image, function, module)?;
                 match module.types[ty].inner {
                     Ti::Image {
                         class,
@@ -574,13 +668,7 @@ impl super::Validator {
                 ShaderStages::all()
             }
             E::ImageQuery { image, query } => {
-                let ty = match function.expressions[image] {
-                    crate::Expression::GlobalVariable(var_handle) => {
-                        module.global_variables[var_handle].ty
-                    }
-                    crate::Expression::FunctionArgument(i) => function.arguments[i as usize].ty,
-                    _ => return Err(ExpressionError::ExpectedGlobalVariable),
-                };
+                let ty = Self::resource_type(image, function, module)?;
                 match module.types[ty].inner {
                     Ti::Image { class, arrayed, .. } => {
                         let can_level = match class {
@@ -856,12 +944,18 @@ impl super::Validator {
                 arg,
                 arg1,
                 arg2,
+                arg3,
             } => {
                 use crate::MathFunction as Mf;
 
                 let arg_ty = resolver.resolve(arg)?;
                 let arg1_ty = arg1.map(|expr| resolver.resolve(expr)).transpose()?;
                 let arg2_ty = arg2.map(|expr| resolver.resolve(expr)).transpose()?;
+                let arg3_ty = arg3.map(|expr| resolver.resolve(expr)).transpose()?;
+                // only `InsertBits` takes a fourth argument
+                if arg3_ty.is_some() && fun != Mf::InsertBits {
+                    return Err(ExpressionError::WrongArgumentCount(fun));
+                }
                 match fun {
                     Mf::Abs => {
                         if arg1_ty.is_some() | arg2_ty.is_some() {
@@ -996,14 +1090,47 @@ impl super::Validator {
                             } => (Some(size), width),
                             _ => return Err(ExpressionError::InvalidArgumentType(fun, 0, arg)),
                         };
-                        let good = match *arg1_ty {
-                            Ti::Pointer { base, class: _ } => module.types[base].inner == *arg_ty,
-                            Ti::ValuePointer {
+                        // `modf` writes the whole part out through a pointer to
+                        // the argument type, `frexp` writes the exponent out through
+                        // a pointer to integers, and `ldexp` takes the exponent by value.
+                        let exponent_kind = |inner: &Ti| match *inner {
+                            Ti::Scalar {
+                                kind: Sk::Sint,
+                                width: _,
+                            } => Some(None),
+                            Ti::Vector {
+                                kind: Sk::Sint,
                                 size,
-                                kind: Sk::Float,
-                                width,
-                                class: _,
-                            } => size == size0 && width == width0,
+                                width: _,
+                            } => Some(Some(size)),
+                            _ => None,
+                        };
+                        let good = match (fun, arg1_ty) {
+                            (Mf::Modf, &Ti::Pointer { base, class: _ }) => {
+                                module.types[base].inner == *arg_ty
+                            }
+                            (
+                                Mf::Modf,
+                                &Ti::ValuePointer {
+                                    size,
+                                    kind: Sk::Float,
+                                    width,
+                                    class: _,
+                                },
+                            ) => size == size0 && width == width0,
+                            (Mf::Frexp, &Ti::Pointer { base, class: _ }) => {
+                                exponent_kind(&module.types[base].inner) == Some(size0)
+                            }
+                            (
+                                Mf::Frexp,
+                                &Ti::ValuePointer {
+                                    size,
+                                    kind: Sk::Sint,
+                                    width: _,
+                                    class: _,
+                                },
+                            ) => size == size0,
+                            (Mf::Ldexp, inner) => exponent_kind(inner) == Some(size0),
                             _ => false,
                         };
                         if !good {
@@ -1014,7 +1141,36 @@ impl super::Validator {
                             ));
                         }
                     }
-                    Mf::Dot | Mf::Outer | Mf::Cross | Mf::Reflect => {
+                    Mf::Outer => {
+                        let arg1_ty = match (arg1_ty, arg2_ty) {
+                            (Some(ty1), None) => ty1,
+                            _ => return Err(ExpressionError::WrongArgumentCount(fun)),
+                        };
+                        let width = match *arg_ty {
+                            Ti::Vector {
+                                kind: Sk::Float,
+                                width,
+                                ..
+                            } => width,
+                            _ => return Err(ExpressionError::InvalidArgumentType(fun, 0, arg)),
+                        };
+                        // the vectors may differ in size, but not in width
+                        match *arg1_ty {
+                            Ti::Vector {
+                                kind: Sk::Float,
+                                width: arg1_width,
+                                ..
+                            } if arg1_width == width => {}
+                            _ => {
+                                return Err(ExpressionError::InvalidArgumentType(
+                                    fun,
+                                    1,
+                                    arg1.unwrap(),
+                                ))
+                            }
+                        }
+                    }
+                    Mf::Dot | Mf::Cross | Mf::Reflect => {
                         let arg1_ty = match (arg1_ty, arg2_ty) {
                             (Some(ty1), None) => ty1,
                             _ => return Err(ExpressionError::WrongArgumentCount(fun)),
@@ -1147,6 +1303,106 @@ impl super::Validator {
                             _ => return Err(ExpressionError::InvalidArgumentType(fun, 0, arg)),
                         }
                     }
+                    Mf::FirstTrailingBit | Mf::FirstLeadingBit => {
+                        if arg1_ty.is_some() | arg2_ty.is_some() {
+                            return Err(ExpressionError::WrongArgumentCount(fun));
+                        }
+                        match *arg_ty {
+                            Ti::Scalar {
+                                kind: Sk::Sint,
+                                width: 4,
+                            }
+                            | Ti::Scalar {
+                                kind: Sk::Uint,
+                                width: 4,
+                            }
+                            | Ti::Vector {
+                                kind: Sk::Sint,
+                                width: 4,
+                                ..
+                            }
+                            | Ti::Vector {
+                                kind: Sk::Uint,
+                                width: 4,
+                                ..
+                            } => {}
+                            _ => return Err(ExpressionError::InvalidArgumentType(fun, 0, arg)),
+                        }
+                    }
+                    Mf::ExtractBits | Mf::InsertBits => {
+                        // `InsertBits` has the inserted bits as the second argument,
+                        // then both have the offset and the count
+                        let (offset_index, offset, count) = match (fun, arg1, arg2, arg3) {
+                            (Mf::ExtractBits, Some(offset), Some(count), None) => {
+                                (1, offset, count)
+                            }
+                            (Mf::InsertBits, Some(newbits), Some(offset), Some(count)) => {
+                                if resolver.resolve(newbits)? != arg_ty {
+                                    return Err(ExpressionError::InvalidArgumentType(
+                                        fun, 1, newbits,
+                                    ));
+                                }
+                                (2, offset, count)
+                            }
+                            _ => return Err(ExpressionError::WrongArgumentCount(fun)),
+                        };
+                        match *arg_ty {
+                            Ti::Scalar { kind: Sk::Sint, .. }
+                            | Ti::Scalar { kind: Sk::Uint, .. }
+                            | Ti::Vector { kind: Sk::Sint, .. }
+                            | Ti::Vector { kind: Sk::Uint, .. } => {}
+                            _ => return Err(ExpressionError::InvalidArgumentType(fun, 0, arg)),
+                        }
+                        for &(index, expr) in
+                            [(offset_index, offset), (offset_index + 1, count)].iter()
+                        {
+                            match *resolver.resolve(expr)? {
+                                Ti::Scalar { kind: Sk::Uint, .. } => {}
+                                _ => {
+                                    return Err(ExpressionError::InvalidArgumentType(
+                                        fun, index, expr,
+                                    ))
+                                }
+                            }
+                        }
+                    }
+                    Mf::Pack4x8snorm
+                    | Mf::Pack4x8unorm
+                    | Mf::Pack2x16snorm
+                    | Mf::Pack2x16unorm
+                    | Mf::Pack2x16float => {
+                        if arg1_ty.is_some() | arg2_ty.is_some() {
+                            return Err(ExpressionError::WrongArgumentCount(fun));
+                        }
+                        let size = match fun {
+                            Mf::Pack4x8snorm | Mf::Pack4x8unorm => crate::VectorSize::Quad,
+                            _ => crate::VectorSize::Bi,
+                        };
+                        match *arg_ty {
+                            Ti::Vector {
+                                size: arg_size,
+                                kind: Sk::Float,
+                                width: 4,
+                            } if arg_size == size => {}
+                            _ => return Err(ExpressionError::InvalidArgumentType(fun, 0, arg)),
+                        }
+                    }
+                    Mf::Unpack4x8snorm
+                    | Mf::Unpack4x8unorm
+                    | Mf::Unpack2x16snorm
+                    | Mf::Unpack2x16unorm
+                    | Mf::Unpack2x16float => {
+                        if arg1_ty.is_some() | arg2_ty.is_some() {
+                            return Err(ExpressionError::WrongArgumentCount(fun));
+                        }
+                        match *arg_ty {
+                            Ti::Scalar {
+                                kind: Sk::Uint,
+                                width: 4,
+                            } => {}
+                            _ => return Err(ExpressionError::InvalidArgumentType(fun, 0, arg)),
+                        }
+                    }
                 }
                 ShaderStages::all()
             }
@@ -1190,6 +1446,10 @@ impl super::Validator {
                     return Err(ExpressionError::InvalidArrayType(expr));
                 }
             },
+            E::AtomicResult { .. } => ShaderStages::all(),
+            E::SubgroupBallotResult | E::SubgroupOperationResult { .. } => {
+                ShaderStages::COMPUTE | ShaderStages::FRAGMENT
+            }
         };
         Ok(stages)
     }
@@ -3,6 +3,7 @@ use super::{
     ExpressionError, FunctionInfo, ModuleInfo, ShaderStages, TypeFlags, ValidationFlags,
 };
 use crate::arena::{Arena, Handle};
+use crate::span::{AddSpan as _, WithSpan};
 use bit_set::BitSet;
 
 #[derive(Clone, Debug, thiserror::Error)]
@@ -34,6 +35,34 @@ pub enum CallError {
     ExpressionMismatch(Option<Handle<crate::Expression>>),
 }
 
+#[derive(Clone, Debug, thiserror::Error)]
+#[cfg_attr(test, derive(PartialEq))]
+pub enum AtomicError {
+    #[error("Pointer {0:?} to atomic is invalid.")]
+    InvalidPointer(Handle<crate::Expression>),
+    #[error("Operand {0:?} has invalid type.")]
+    InvalidOperand(Handle<crate::Expression>),
+    #[error("Result expression {0:?} has already been introduced earlier")]
+    ResultAlreadyInScope(Handle<crate::Expression>),
+    #[error("Result type for {0:?} doesn't match the statement")]
+    ResultTypeMismatch(Handle<crate::Expression>),
+}
+
+#[derive(Clone, Debug, thiserror::Error)]
+#[cfg_attr(test, derive(PartialEq))]
+pub enum SubgroupError {
+    #[error("Capability {0:?} is not supported")]
+    UnsupportedCapability(super::Capabilities),
+    #[error("Operand {0:?} has invalid type.")]
+    InvalidOperand(Handle<crate::Expression>),
+    #[error("Operation {0:?} can only be used as a reduction")]
+    InvalidCollectiveOperation(crate::SubgroupOperation),
+    #[error("Result expression {0:?} has already been introduced earlier")]
+    ResultAlreadyInScope(Handle<crate::Expression>),
+    #[error("Result type for {0:?} doesn't match the statement")]
+    ResultTypeMismatch(Handle<crate::Expression>),
+}
+
 #[derive(Clone, Debug, thiserror::Error)]
 #[cfg_attr(test, derive(PartialEq))]
 pub enum LocalVariableError {
@@ -98,6 +127,10 @@ pub enum FunctionError {
         #[source]
         error: CallError,
     },
+    #[error("Atomic operation is invalid")]
+    InvalidAtomic(#[from] AtomicError),
+    #[error("Subgroup operation is invalid")]
+    InvalidSubgroup(#[from] SubgroupError),
     #[error(
         "Required uniformity of control flow for {0:?} in {1:?} is not fulfilled because of {2:?}"
     )]
@@ -157,10 +190,10 @@ impl<'a> BlockContext<'a> {
     fn get_expression(
         &self,
         handle: Handle<crate::Expression>,
-    ) -> Result<&'a crate::Expression, FunctionError> {
+    ) -> Result<&'a crate::Expression, WithSpan<FunctionError>> {
         self.expressions
             .try_get(handle)
-            .ok_or(FunctionError::InvalidExpression(handle))
+            .ok_or_else(|| FunctionError::InvalidExpression(handle).with_span())
     }
 
     fn resolve_type_impl(
@@ -181,20 +214,24 @@ impl<'a> BlockContext<'a> {
         &self,
         handle: Handle<crate::Expression>,
         valid_expressions: &BitSet,
-    ) -> Result<&crate::TypeInner, FunctionError> {
+    ) -> Result<&crate::TypeInner, WithSpan<FunctionError>> {
         self.resolve_type_impl(handle, valid_expressions)
-            .map_err(|error| FunctionError::Expression { handle, error })
+            .map_err(|error| {
+                FunctionError::Expression { handle, error }
+                    .with_span_handle(handle, self.expressions)
+            })
     }
 
     fn resolve_pointer_type(
         &self,
         handle: Handle<crate::Expression>,
-    ) -> Result<&crate::TypeInner, FunctionError> {
+    ) -> Result<&crate::TypeInner, WithSpan<FunctionError>> {
         if handle.index() >= self.expressions.len() {
             Err(FunctionError::Expression {
                 handle,
                 error: ExpressionError::DoesntExist,
-            })
+            }
+            .with_span())
         } else {
             Ok(self.info[handle].ty.inner_with(self.types))
         }
@@ -208,27 +245,31 @@ impl super::Validator {
         arguments: &[Handle<crate::Expression>],
         result: Option<Handle<crate::Expression>>,
         context: &BlockContext,
-    ) -> Result<ShaderStages, CallError> {
+    ) -> Result<ShaderStages, WithSpan<CallError>> {
         let fun = context
             .functions
             .try_get(function)
-            .ok_or(CallError::InvalidFunction)?;
+            .ok_or_else(|| CallError::InvalidFunction.with_span())?;
         if fun.arguments.len() != arguments.len() {
             return Err(CallError::ArgumentCount {
                 required: fun.arguments.len(),
                 seen: arguments.len(),
-            });
+            }
+            .with_span());
         }
         for (index, (arg, &expr)) in fun.arguments.iter().zip(arguments).enumerate() {
             let ty = context
                 .resolve_type_impl(expr, &self.valid_expression_set)
-                .map_err(|error| CallError::Argument { index, error })?;
+                .map_err(|error| {
+                    CallError::Argument { index, error }.with_span_handle(expr, context.expressions)
+                })?;
             if ty != &context.types[arg.ty].inner {
                 return Err(CallError::ArgumentType {
                     index,
                     required: arg.ty,
                     seen_expression: expr,
-                });
+                }
+                .with_span_handle(expr, context.expressions));
             }
         }
 
@@ -236,31 +277,173 @@ impl super::Validator {
             if self.valid_expression_set.insert(expr.index()) {
                 self.valid_expression_list.push(expr);
             } else {
-                return Err(CallError::ResultAlreadyInScope(expr));
+                return Err(CallError::ResultAlreadyInScope(expr)
+                    .with_span_handle(expr, context.expressions));
             }
             match context.expressions[expr] {
                 crate::Expression::Call(callee) if fun.result.is_some() && callee == function => {}
-                _ => return Err(CallError::ExpressionMismatch(result)),
+                _ => {
+                    return Err(CallError::ExpressionMismatch(result)
+                        .with_span_handle(expr, context.expressions))
+                }
             }
         } else if fun.result.is_some() {
-            return Err(CallError::ExpressionMismatch(result));
+            return Err(CallError::ExpressionMismatch(result).with_span());
         }
 
         let callee_info = &context.prev_infos[function.index()];
         Ok(callee_info.available_stages)
     }
 
+    fn validate_atomic(
+        &mut self,
+        pointer: Handle<crate::Expression>,
+        fun: &crate::AtomicFunction,
+        value: Handle<crate::Expression>,
+        result: Handle<crate::Expression>,
+        context: &BlockContext,
+    ) -> Result<(), WithSpan<FunctionError>> {
+        let pointer_inner = context.resolve_type(pointer, &self.valid_expression_set)?;
+        let (ptr_kind, ptr_width) = match *pointer_inner {
+            crate::TypeInner::Pointer { base, .. } => match context.types[base].inner {
+                crate::TypeInner::Atomic { kind, width } => (kind, width),
+                ref other => {
+                    log::error!("Atomic pointer to type {:?}", other);
+                    return Err(AtomicError::InvalidPointer(pointer)
+                        .with_span_handle(pointer, context.expressions)
+                        .into_other());
+                }
+            },
+            ref other => {
+                log::error!("Atomic on type {:?}", other);
+                return Err(AtomicError::InvalidPointer(pointer)
+                    .with_span_handle(pointer, context.expressions)
+                    .into_other());
+            }
+        };
+
+        let value_inner = context.resolve_type(value, &self.valid_expression_set)?;
+        match *value_inner {
+            crate::TypeInner::Scalar { width, kind } if kind == ptr_kind && width == ptr_width => {}
+            ref other => {
+                log::error!("Atomic operand type {:?}", other);
+                return Err(AtomicError::InvalidOperand(value)
+                    .with_span_handle(value, context.expressions)
+                    .into_other());
+            }
+        }
+
+        if let crate::AtomicFunction::Exchange { compare: Some(cmp) } = *fun {
+            if context.resolve_type(cmp, &self.valid_expression_set)? != value_inner {
+                log::error!("Atomic exchange comparison has a different type from the value");
+                return Err(AtomicError::InvalidOperand(cmp)
+                    .with_span_handle(cmp, context.expressions)
+                    .into_other());
+            }
+        }
+
+        if self.valid_expression_set.insert(result.index()) {
+            self.valid_expression_list.push(result);
+        } else {
+            return Err(AtomicError::ResultAlreadyInScope(result)
+                .with_span_handle(result, context.expressions)
+                .into_other());
+        }
+        match *context.get_expression(result)? {
+            crate::Expression::AtomicResult {
+                kind,
+                width,
+                comparison,
+            } if kind == ptr_kind && width == ptr_width => {
+                let is_compare = match *fun {
+                    crate::AtomicFunction::Exchange { compare } => compare.is_some(),
+                    _ => false,
+                };
+                if comparison != is_compare {
+                    return Err(AtomicError::ResultTypeMismatch(result)
+                        .with_span_handle(result, context.expressions)
+                        .into_other());
+                }
+            }
+            _ => {
+                return Err(AtomicError::ResultTypeMismatch(result)
+                    .with_span_handle(result, context.expressions)
+                    .into_other())
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_subgroup_operand(
+        &self,
+        operand: Handle<crate::Expression>,
+        context: &BlockContext,
+        check: impl FnOnce(crate::ScalarKind) -> bool,
+    ) -> Result<(), WithSpan<FunctionError>> {
+        let inner = context.resolve_type(operand, &self.valid_expression_set)?;
+        let good = match *inner {
+            crate::TypeInner::Scalar { kind, .. } | crate::TypeInner::Vector { kind, .. } => {
+                check(kind)
+            }
+            _ => false,
+        };
+        if !good {
+            log::error!("Subgroup operand type {:?}", inner);
+            return Err(SubgroupError::InvalidOperand(operand)
+                .with_span_handle(operand, context.expressions)
+                .into_other());
+        }
+        Ok(())
+    }
+
+    fn validate_subgroup_result(
+        &mut self,
+        result: Handle<crate::Expression>,
+        argument: Option<Handle<crate::Expression>>,
+        context: &BlockContext,
+    ) -> Result<(), WithSpan<FunctionError>> {
+        if !self.capabilities.contains(super::Capabilities::SUBGROUP) {
+            return Err(
+                SubgroupError::UnsupportedCapability(super::Capabilities::SUBGROUP)
+                    .with_span_handle(result, context.expressions)
+                    .into_other(),
+            );
+        }
+        if self.valid_expression_set.insert(result.index()) {
+            self.valid_expression_list.push(result);
+        } else {
+            return Err(SubgroupError::ResultAlreadyInScope(result)
+                .with_span_handle(result, context.expressions)
+                .into_other());
+        }
+        let good = match (context.get_expression(result)?, argument) {
+            (&crate::Expression::SubgroupBallotResult, None) => true,
+            (&crate::Expression::SubgroupOperationResult { ty }, Some(argument)) => {
+                context.types[ty].inner
+                    == *context.resolve_type(argument, &self.valid_expression_set)?
+            }
+            _ => false,
+        };
+        if !good {
+            return Err(SubgroupError::ResultTypeMismatch(result)
+                .with_span_handle(result, context.expressions)
+                .into_other());
+        }
+        Ok(())
+    }
+
     fn validate_block_impl(
         &mut self,
-        statements: &[crate::Statement],
+        statements: &crate::Block,
         context: &BlockContext,
-    ) -> Result<ShaderStages, FunctionError> {
+    ) -> Result<ShaderStages, WithSpan<FunctionError>> {
         use crate::{Statement as S, TypeInner as Ti};
         let mut finished = false;
         let mut stages = ShaderStages::all();
-        for statement in statements {
+        for (statement, &span) in statements.span_iter() {
             if finished {
-                return Err(FunctionError::InstructionsAfterReturn);
+                return Err(FunctionError::InstructionsAfterReturn
+                    .with_span_static(span, "instructions after return"));
             }
             match *statement {
                 S::Emit(ref range) => {
@@ -268,7 +451,8 @@ impl super::Validator {
                         if self.valid_expression_set.insert(handle.index()) {
                             self.valid_expression_list.push(handle);
                         } else {
-                            return Err(FunctionError::ExpressionAlreadyInScope(handle));
+                            return Err(FunctionError::ExpressionAlreadyInScope(handle)
+                                .with_span_handle(handle, context.expressions));
                         }
                     }
                 }
@@ -285,7 +469,10 @@ impl super::Validator {
                             kind: crate::ScalarKind::Bool,
                             width: _,
                         } => {}
-                        _ => return Err(FunctionError::InvalidIfType(condition)),
+                        _ => {
+                            return Err(FunctionError::InvalidIfType(condition)
+                                .with_span_handle(condition, context.expressions))
+                        }
                     }
                     stages &= self.validate_block(accept, context)?;
                     stages &= self.validate_block(reject, context)?;
@@ -300,12 +487,16 @@ impl super::Validator {
                             kind: crate::ScalarKind::Sint,
                             width: _,
                         } => {}
-                        _ => return Err(FunctionError::InvalidSwitchType(selector)),
+                        _ => {
+                            return Err(FunctionError::InvalidSwitchType(selector)
+                                .with_span_handle(selector, context.expressions))
+                        }
                     }
                     self.select_cases.clear();
                     for case in cases {
                         if !self.select_cases.insert(case.value) {
-                            return Err(FunctionError::ConflictingSwitchCase(case.value));
+                            return Err(FunctionError::ConflictingSwitchCase(case.value)
+                                .with_span_static(span, "conflicting switch arm"));
                         }
                     }
                     let pass_through_abilities = context.abilities
@@ -343,19 +534,22 @@ impl super::Validator {
                 }
                 S::Break => {
                     if !context.abilities.contains(ControlFlowAbility::BREAK) {
-                        return Err(FunctionError::BreakOutsideOfLoopOrSwitch);
+                        return Err(FunctionError::BreakOutsideOfLoopOrSwitch
+                            .with_span_static(span, "invalid break"));
                     }
                     finished = true;
                 }
                 S::Continue => {
                     if !context.abilities.contains(ControlFlowAbility::CONTINUE) {
-                        return Err(FunctionError::ContinueOutsideOfLoop);
+                        return Err(FunctionError::ContinueOutsideOfLoop
+                            .with_span_static(span, "invalid continue"));
                     }
                     finished = true;
                 }
                 S::Return { value } => {
                     if !context.abilities.contains(ControlFlowAbility::RETURN) {
-                        return Err(FunctionError::InvalidReturnSpot);
+                        return Err(FunctionError::InvalidReturnSpot
+                            .with_span_static(span, "invalid return"));
                     }
                     let value_ty = value
                         .map(|expr| context.resolve_type(expr, &self.valid_expression_set))
@@ -367,7 +561,8 @@ impl super::Validator {
                             value_ty,
                             expected_ty
                         );
-                        return Err(FunctionError::InvalidReturnType(value));
+                        return Err(FunctionError::InvalidReturnType(value)
+                            .with_span_static(span, "invalid return"));
                     }
                     finished = true;
                 }
@@ -387,19 +582,26 @@ impl super::Validator {
                             crate::Expression::LocalVariable(_)
                             | crate::Expression::GlobalVariable(_)
                             | crate::Expression::FunctionArgument(_) => break,
-                            _ => return Err(FunctionError::InvalidStorePointer(current)),
+                            _ => {
+                                return Err(FunctionError::InvalidStorePointer(current)
+                                    .with_span_handle(pointer, context.expressions))
+                            }
                         }
                     }
 
                     let value_ty = context.resolve_type(value, &self.valid_expression_set)?;
                     match *value_ty {
                         Ti::Image { .. } | Ti::Sampler { .. } => {
-                            return Err(FunctionError::InvalidStoreValue(value));
+                            return Err(FunctionError::InvalidStoreValue(value)
+                                .with_span_handle(value, context.expressions));
                         }
                         _ => {}
                     }
                     let good = match *context.resolve_pointer_type(pointer)? {
-                        Ti::Pointer { base, class: _ } => *value_ty == context.types[base].inner,
+                        Ti::Pointer { base, class: _ } => match context.types[base].inner {
+                            Ti::Atomic { kind, width } => *value_ty == Ti::Scalar { kind, width },
+                            ref other => value_ty == other,
+                        },
                         Ti::ValuePointer {
                             size: Some(size),
                             kind,
@@ -415,7 +617,8 @@ impl super::Validator {
                         _ => false,
                     };
                     if !good {
-                        return Err(FunctionError::InvalidStoreTypes { pointer, value });
+                        return Err(FunctionError::InvalidStoreTypes { pointer, value }
+                            .with_span_static(span, "invalid store"));
                     }
                 }
                 S::ImageStore {
@@ -426,18 +629,23 @@ impl super::Validator {
                 } => {
                     //Note: this code uses a lot of `FunctionError::InvalidImageStore`,
                     // and could probably be refactored.
-                    let var = match *context.get_expression(image)? {
-                        crate::Expression::GlobalVariable(var_handle) => {
-                            &context.global_vars[var_handle]
-                        }
-                        _ => {
+                    context.get_expression(image)?;
+                    let image_ty = match super::expression::global_resource_type(
+                        image,
+                        context.expressions,
+                        context.global_vars,
+                        context.types,
+                    ) {
+                        Some(ty) => ty,
+                        None => {
                             return Err(FunctionError::InvalidImageStore(
                                 ExpressionError::ExpectedGlobalVariable,
-                            ))
+                            )
+                            .with_span_static(span, "invalid image store"))
                         }
                     };
 
-                    let value_ty = match context.types[var.ty].inner {
+                    let value_ty = match context.types[image_ty].inner {
                         Ti::Image {
                             class,
                             arrayed,
@@ -453,13 +661,15 @@ impl super::Validator {
                                         ExpressionError::InvalidImageCoordinateType(
                                             dim, coordinate,
                                         ),
-                                    ))
+                                    )
+                                    .with_span_static(span, "invalid image store"))
                                 }
                             };
                             if arrayed != array_index.is_some() {
                                 return Err(FunctionError::InvalidImageStore(
                                     ExpressionError::InvalidImageArrayIndex,
-                                ));
+                                )
+                                .with_span_static(span, "invalid image store"));
                             }
                             if let Some(expr) = array_index {
                                 match *context.resolve_type(expr, &self.valid_expression_set)? {
@@ -470,7 +680,8 @@ impl super::Validator {
                                     _ => {
                                         return Err(FunctionError::InvalidImageStore(
                                             ExpressionError::InvalidImageArrayIndexType(expr),
-                                        ))
+                                        )
+                                        .with_span_static(span, "invalid image store"))
                                     }
                                 }
                             }
@@ -483,19 +694,22 @@ impl super::Validator {
                                 _ => {
                                     return Err(FunctionError::InvalidImageStore(
                                         ExpressionError::InvalidImageClass(class),
-                                    ))
+                                    )
+                                    .with_span_static(span, "invalid image store"))
                                 }
                             }
                         }
                         _ => {
                             return Err(FunctionError::InvalidImageStore(
-                                ExpressionError::ExpectedImageType(var.ty),
-                            ))
+                                ExpressionError::ExpectedImageType(image_ty),
+                            )
+                            .with_span_static(span, "invalid image store"))
                         }
                     };
 
                     if *context.resolve_type(value, &self.valid_expression_set)? != value_ty {
-                        return Err(FunctionError::InvalidStoreValue(value));
+                        return Err(FunctionError::InvalidStoreValue(value)
+                            .with_span_handle(value, context.expressions));
                     }
                 }
                 S::Call {
@@ -504,8 +718,108 @@ impl super::Validator {
                     result,
                 } => match self.validate_call(function, arguments, result, context) {
                     Ok(callee_stages) => stages &= callee_stages,
-                    Err(error) => return Err(FunctionError::InvalidCall { function, error }),
+                    Err(error) => {
+                        return Err(error.and_then(|error| {
+                            FunctionError::InvalidCall { function, error }
+                                .with_span_static(span, "invalid function call")
+                        }))
+                    }
                 },
+                S::Atomic {
+                    pointer,
+                    ref fun,
+                    value,
+                    result,
+                } => {
+                    self.validate_atomic(pointer, fun, value, result, context)?;
+                }
+                S::SubgroupBallot { result, predicate } => {
+                    if let Some(predicate) = predicate {
+                        let inner = context.resolve_type(predicate, &self.valid_expression_set)?;
+                        if *inner
+                            != (Ti::Scalar {
+                                kind: crate::ScalarKind::Bool,
+                                width: crate::BOOL_WIDTH,
+                            })
+                        {
+                            return Err(SubgroupError::InvalidOperand(predicate)
+                                .with_span_handle(predicate, context.expressions)
+                                .into_other());
+                        }
+                    }
+                    self.validate_subgroup_result(result, None, context)?;
+                    stages &= ShaderStages::COMPUTE | ShaderStages::FRAGMENT;
+                }
+                S::SubgroupCollectiveOperation {
+                    op,
+                    collective_op,
+                    argument,
+                    result,
+                } => {
+                    use crate::{ScalarKind as Sk, SubgroupOperation as So};
+                    match op {
+                        So::All | So::Any => {
+                            if collective_op != crate::CollectiveOperation::Reduce {
+                                return Err(SubgroupError::InvalidCollectiveOperation(op)
+                                    .with_span_static(span, "subgroup scan")
+                                    .into_other());
+                            }
+                            let inner =
+                                context.resolve_type(argument, &self.valid_expression_set)?;
+                            if *inner
+                                != (Ti::Scalar {
+                                    kind: Sk::Bool,
+                                    width: crate::BOOL_WIDTH,
+                                })
+                            {
+                                return Err(SubgroupError::InvalidOperand(argument)
+                                    .with_span_handle(argument, context.expressions)
+                                    .into_other());
+                            }
+                        }
+                        So::Add | So::Mul | So::Min | So::Max => {
+                            self.validate_subgroup_operand(argument, context, |kind| {
+                                kind != Sk::Bool
+                            })?
+                        }
+                        So::And | So::Or | So::Xor => {
+                            self.validate_subgroup_operand(argument, context, |kind| {
+                                kind != Sk::Float
+                            })?
+                        }
+                    }
+                    self.validate_subgroup_result(result, Some(argument), context)?;
+                    stages &= ShaderStages::COMPUTE | ShaderStages::FRAGMENT;
+                }
+                S::SubgroupGather {
+                    ref mode,
+                    argument,
+                    result,
+                } => {
+                    match *mode {
+                        crate::GatherMode::BroadcastFirst => {}
+                        crate::GatherMode::Broadcast(index)
+                        | crate::GatherMode::Shuffle(index)
+                        | crate::GatherMode::ShuffleDown(index)
+                        | crate::GatherMode::ShuffleUp(index)
+                        | crate::GatherMode::ShuffleXor(index) => {
+                            let inner = context.resolve_type(index, &self.valid_expression_set)?;
+                            if *inner
+                                != (Ti::Scalar {
+                                    kind: crate::ScalarKind::Uint,
+                                    width: 4,
+                                })
+                            {
+                                return Err(SubgroupError::InvalidOperand(index)
+                                    .with_span_handle(index, context.expressions)
+                                    .into_other());
+                            }
+                        }
+                    }
+                    self.validate_subgroup_operand(argument, context, |_| true)?;
+                    self.validate_subgroup_result(result, Some(argument), context)?;
+                    stages &= ShaderStages::COMPUTE | ShaderStages::FRAGMENT;
+                }
             }
         }
         Ok(stages)
@@ -513,9 +827,9 @@ impl super::Validator {
 
     fn validate_block(
         &mut self,
-        statements: &[crate::Statement],
+        statements: &crate::Block,
         context: &BlockContext,
-    ) -> Result<ShaderStages, FunctionError> {
+    ) -> Result<ShaderStages, WithSpan<FunctionError>> {
         let base_expression_count = self.valid_expression_list.len();
         let stages = self.validate_block_impl(statements, context)?;
         for handle in self.valid_expression_list.drain(base_expression_count..) {
@@ -563,15 +877,18 @@ impl super::Validator {
         fun: &crate::Function,
         module: &crate::Module,
         mod_info: &ModuleInfo,
-    ) -> Result<FunctionInfo, FunctionError> {
+    ) -> Result<FunctionInfo, WithSpan<FunctionError>> {
         let mut info = mod_info.process_function(fun, module, self.flags)?;
 
         for (var_handle, var) in fun.local_variables.iter() {
             self.validate_local_var(var, &module.types, &module.constants)
-                .map_err(|error| FunctionError::LocalVariable {
-                    handle: var_handle,
-                    name: var.name.clone().unwrap_or_default(),
-                    error,
+                .map_err(|error| {
+                    FunctionError::LocalVariable {
+                        handle: var_handle,
+                        name: var.name.clone().unwrap_or_default(),
+                        error,
+                    }
+                    .with_span_handle(var_handle, &fun.local_variables)
                 })?;
         }
 
@@ -583,7 +900,8 @@ impl super::Validator {
                 return Err(FunctionError::InvalidArgumentType {
                     index,
                     name: argument.name.clone().unwrap_or_default(),
-                });
+                }
+                .with_span());
             }
         }
 
@@ -603,7 +921,10 @@ impl super::Validator {
                     &mod_info.functions,
                 ) {
                     Ok(stages) => info.available_stages &= stages,
-                    Err(error) => return Err(FunctionError::Expression { handle, error }),
+                    Err(error) => {
+                        return Err(FunctionError::Expression { handle, error }
+                            .with_span_handle(handle, &fun.expressions))
+                    }
                 }
             }
         }
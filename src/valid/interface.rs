@@ -4,6 +4,7 @@ use super::{
     ValidationFlags,
 };
 use crate::arena::{Arena, Handle};
+use crate::span::{AddSpan as _, WithSpan};
 
 use bit_set::BitSet;
 
@@ -230,6 +231,21 @@ impl VaryingContext<'_> {
                                 width,
                             },
                     ),
+                    Bi::SubgroupSize | Bi::SubgroupInvocationId => {
+                        if !self.capabilities.contains(Capabilities::SUBGROUP) {
+                            return Err(VaryingError::UnsupportedCapability(
+                                Capabilities::SUBGROUP,
+                            ));
+                        }
+                        (
+                            self.stage != St::Vertex && !self.output,
+                            *ty_inner
+                                == Ti::Scalar {
+                                    kind: Sk::Uint,
+                                    width,
+                                },
+                        )
+                    }
                 };
 
                 if !visible {
@@ -320,7 +336,12 @@ impl super::Validator {
         types: &Arena<crate::Type>,
     ) -> Result<(), GlobalVariableError> {
         log::debug!("var {:?}", var);
-        let type_info = &self.types[var.ty.index()];
+        // Binding arrays are validated by the type of their elements.
+        let (ty, is_binding_array) = match types[var.ty].inner {
+            crate::TypeInner::BindingArray { base, .. } => (base, true),
+            _ => (var.ty, false),
+        };
+        let type_info = &self.types[ty.index()];
 
         let (allowed_storage_access, required_type_flags, is_resource) = match var.class {
             crate::StorageClass::Function => return Err(GlobalVariableError::InvalidUsage),
@@ -352,7 +373,7 @@ impl super::Validator {
                 )
             }
             crate::StorageClass::Handle => {
-                let access = match types[var.ty].inner {
+                let access = match types[ty].inner {
                     crate::TypeInner::Image {
                         class: crate::ImageClass::Storage(_),
                         ..
@@ -383,6 +404,10 @@ impl super::Validator {
             }
         };
 
+        if is_binding_array && !is_resource {
+            return Err(GlobalVariableError::InvalidType);
+        }
+
         if !allowed_storage_access.contains(var.storage_access) {
             return Err(GlobalVariableError::InvalidStorageAccess {
                 seen: var.storage_access,
@@ -409,9 +434,9 @@ impl super::Validator {
         ep: &crate::EntryPoint,
         module: &crate::Module,
         mod_info: &ModuleInfo,
-    ) -> Result<FunctionInfo, EntryPointError> {
+    ) -> Result<FunctionInfo, WithSpan<EntryPointError>> {
         if ep.early_depth_test.is_some() && ep.stage != crate::ShaderStage::Fragment {
-            return Err(EntryPointError::UnexpectedEarlyDepthTest);
+            return Err(EntryPointError::UnexpectedEarlyDepthTest.with_span());
         }
         if ep.stage == crate::ShaderStage::Compute {
             if ep
@@ -419,10 +444,10 @@ impl super::Validator {
                 .iter()
                 .any(|&s| s == 0 || s > MAX_WORKGROUP_SIZE)
             {
-                return Err(EntryPointError::OutOfRangeWorkgroupSize);
+                return Err(EntryPointError::OutOfRangeWorkgroupSize.with_span());
             }
         } else if ep.workgroup_size != [0; 3] {
-            return Err(EntryPointError::UnexpectedWorkgroupSize);
+            return Err(EntryPointError::UnexpectedWorkgroupSize.with_span());
         }
 
         let stage_bit = match ep.stage {
@@ -431,10 +456,12 @@ impl super::Validator {
             crate::ShaderStage::Compute => ShaderStages::COMPUTE,
         };
 
-        let info = self.validate_function(&ep.function, module, mod_info)?;
+        let info = self
+            .validate_function(&ep.function, module, mod_info)
+            .map_err(WithSpan::into_other)?;
 
         if !info.available_stages.contains(stage_bit) {
-            return Err(EntryPointError::ForbiddenStageOperations);
+            return Err(EntryPointError::ForbiddenStageOperations.with_span());
         }
 
         self.location_mask.clear();
@@ -450,7 +477,7 @@ impl super::Validator {
                 capabilities: self.capabilities,
             };
             ctx.validate(fa.binding.as_ref())
-                .map_err(|e| EntryPointError::Argument(index as u32, e))?;
+                .map_err(|e| EntryPointError::Argument(index as u32, e).with_span())?;
             argument_built_ins = ctx.built_in_mask;
         }
 
@@ -466,7 +493,7 @@ impl super::Validator {
                 capabilities: self.capabilities,
             };
             ctx.validate(fr.binding.as_ref())
-                .map_err(EntryPointError::Result)?;
+                .map_err(|e| EntryPointError::Result(e).with_span())?;
         }
 
         for bg in self.bind_group_masks.iter_mut() {
@@ -499,7 +526,8 @@ impl super::Validator {
                     allowed_usage,
                     usage
                 );
-                return Err(EntryPointError::InvalidGlobalUsage(var_handle, usage));
+                return Err(EntryPointError::InvalidGlobalUsage(var_handle, usage)
+                    .with_span_handle(var_handle, &module.global_variables));
             }
 
             if let Some(ref bind) = var.binding {
@@ -507,7 +535,8 @@ impl super::Validator {
                     self.bind_group_masks.push(BitSet::new());
                 }
                 if !self.bind_group_masks[bind.group as usize].insert(bind.binding as usize) {
-                    return Err(EntryPointError::BindingCollision(var_handle));
+                    return Err(EntryPointError::BindingCollision(var_handle)
+                        .with_span_handle(var_handle, &module.global_variables));
                 }
             }
         }
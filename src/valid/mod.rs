@@ -3,11 +3,13 @@ mod compose;
 mod expression;
 mod function;
 mod interface;
+mod reflection;
 mod r#type;
 
 use crate::{
     arena::{Arena, Handle},
     proc::{InvalidBaseType, Layouter},
+    span::{AddSpan as _, WithSpan},
     FastHashSet,
 };
 use bit_set::BitSet;
@@ -19,9 +21,13 @@ use std::ops;
 pub use analyzer::{ExpressionInfo, FunctionInfo, GlobalUse, Uniformity, UniformityRequirements};
 pub use compose::ComposeError;
 pub use expression::ExpressionError;
-pub use function::{CallError, FunctionError, LocalVariableError};
+pub use function::{AtomicError, CallError, FunctionError, LocalVariableError, SubgroupError};
 pub use interface::{EntryPointError, GlobalVariableError, VaryingError};
 pub use r#type::{Disalignment, TypeError, TypeFlags};
+pub use reflection::{
+    BindingCount, EntryPointReflection, PushConstantRange, ResourceClass, ResourceReflection,
+    VaryingReflection,
+};
 
 bitflags::bitflags! {
     /// Validation flags.
@@ -60,6 +66,12 @@ bitflags::bitflags! {
         const FLOAT64 = 0x2;
         /// Support for `Builtin:PrimitiveIndex`.
         const PRIMITIVE_INDEX = 0x4;
+        /// Support for subgroup operations and builtins.
+        const SUBGROUP = 0x8;
+        /// Support for `TypeInner::BindingArray`.
+        const BINDING_ARRAY = 0x10;
+        /// Support for indexing binding arrays with non-uniform values.
+        const BINDING_ARRAY_NON_UNIFORM_INDEXING = 0x20;
     }
 }
 
@@ -80,6 +92,7 @@ bitflags::bitflags! {
 pub struct ModuleInfo {
     functions: Vec<FunctionInfo>,
     entry_points: Vec<FunctionInfo>,
+    layouter: Layouter,
 }
 
 impl ops::Index<Handle<crate::Function>> for ModuleInfo {
@@ -163,6 +176,7 @@ impl crate::TypeInner {
             Self::Scalar { .. }
             | Self::Vector { .. }
             | Self::Matrix { .. }
+            | Self::Atomic { .. }
             | Self::Array {
                 size: crate::ArraySize::Constant(_),
                 ..
@@ -170,7 +184,10 @@ impl crate::TypeInner {
             | Self::Pointer { .. }
             | Self::ValuePointer { .. }
             | Self::Struct { .. } => true,
-            Self::Array { .. } | Self::Image { .. } | Self::Sampler { .. } => false,
+            Self::Array { .. }
+            | Self::Image { .. }
+            | Self::Sampler { .. }
+            | Self::BindingArray { .. } => false,
         }
     }
 
@@ -252,17 +269,25 @@ impl Validator {
     }
 
     /// Check the given module to be valid.
-    pub fn validate(&mut self, module: &crate::Module) -> Result<ModuleInfo, ValidationError> {
+    pub fn validate(
+        &mut self,
+        module: &crate::Module,
+    ) -> Result<ModuleInfo, WithSpan<ValidationError>> {
         self.reset_types(module.types.len());
-        self.layouter.update(&module.types, &module.constants)?;
+        self.layouter
+            .update(&module.types, &module.constants)
+            .map_err(|error| ValidationError::from(error).with_span())?;
 
         if self.flags.contains(ValidationFlags::CONSTANTS) {
             for (handle, constant) in module.constants.iter() {
                 self.validate_constant(handle, &module.constants, &module.types)
-                    .map_err(|error| ValidationError::Constant {
-                        handle,
-                        name: constant.name.clone().unwrap_or_default(),
-                        error,
+                    .map_err(|error| {
+                        ValidationError::Constant {
+                            handle,
+                            name: constant.name.clone().unwrap_or_default(),
+                            error,
+                        }
+                        .with_span_handle(handle, &module.constants)
                     })?;
             }
         }
@@ -270,37 +295,47 @@ impl Validator {
         for (handle, ty) in module.types.iter() {
             let ty_info = self
                 .validate_type(handle, &module.types, &module.constants)
-                .map_err(|error| ValidationError::Type {
-                    handle,
-                    name: ty.name.clone().unwrap_or_default(),
-                    error,
+                .map_err(|error| {
+                    ValidationError::Type {
+                        handle,
+                        name: ty.name.clone().unwrap_or_default(),
+                        error,
+                    }
+                    .with_span_handle(handle, &module.types)
                 })?;
             self.types[handle.index()] = ty_info;
         }
 
         for (var_handle, var) in module.global_variables.iter() {
             self.validate_global_var(var, &module.types)
-                .map_err(|error| ValidationError::GlobalVariable {
-                    handle: var_handle,
-                    name: var.name.clone().unwrap_or_default(),
-                    error,
+                .map_err(|error| {
+                    ValidationError::GlobalVariable {
+                        handle: var_handle,
+                        name: var.name.clone().unwrap_or_default(),
+                        error,
+                    }
+                    .with_span_handle(var_handle, &module.global_variables)
                 })?;
         }
 
         let mut mod_info = ModuleInfo {
             functions: Vec::with_capacity(module.functions.len()),
             entry_points: Vec::with_capacity(module.entry_points.len()),
+            layouter: self.layouter.clone(),
         };
 
         for (handle, fun) in module.functions.iter() {
             match self.validate_function(fun, module, &mod_info) {
                 Ok(info) => mod_info.functions.push(info),
                 Err(error) => {
-                    return Err(ValidationError::Function {
-                        handle,
-                        name: fun.name.clone().unwrap_or_default(),
-                        error,
-                    })
+                    return Err(error.and_then(|error| {
+                        ValidationError::Function {
+                            handle,
+                            name: fun.name.clone().unwrap_or_default(),
+                            error,
+                        }
+                        .with_span_handle(handle, &module.functions)
+                    }))
                 }
             }
         }
@@ -312,17 +347,21 @@ impl Validator {
                     stage: ep.stage,
                     name: ep.name.clone(),
                     error: EntryPointError::Conflict,
-                });
+                }
+                .with_span());
             }
 
             match self.validate_entry_point(ep, module, &mod_info) {
                 Ok(info) => mod_info.entry_points.push(info),
                 Err(error) => {
-                    return Err(ValidationError::EntryPoint {
-                        stage: ep.stage,
-                        name: ep.name.clone(),
-                        error,
-                    })
+                    return Err(error.and_then(|error| {
+                        ValidationError::EntryPoint {
+                            stage: ep.stage,
+                            name: ep.name.clone(),
+                            error,
+                        }
+                        .with_span()
+                    }))
                 }
             }
         }
@@ -0,0 +1,462 @@
+//! Reflection of the entry point interfaces.
+
+use super::{GlobalUse, ModuleInfo};
+use crate::arena::Handle;
+use std::ops::Range;
+
+/// The class of a resource bound to an entry point.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+#[cfg_attr(feature = "deserialize", derive(serde::Deserialize))]
+pub enum ResourceClass {
+    /// Uniform buffer.
+    Uniform,
+    /// Storage buffer.
+    Storage,
+    /// Sampled, depth or storage image.
+    Image {
+        dim: crate::ImageDimension,
+        arrayed: bool,
+        class: crate::ImageClass,
+    },
+    /// Sampler.
+    Sampler { comparison: bool },
+}
+
+/// Number of resources bound at a binding point.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+#[cfg_attr(feature = "deserialize", derive(serde::Deserialize))]
+pub enum BindingCount {
+    /// A single resource.
+    Single,
+    /// A binding array with a fixed number of resources.
+    Array(u32),
+    /// A runtime-sized binding array.
+    RuntimeArray,
+}
+
+/// A resource used by an entry point.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+#[cfg_attr(feature = "deserialize", derive(serde::Deserialize))]
+pub struct ResourceReflection {
+    /// The global variable of the resource.
+    pub global: Handle<crate::GlobalVariable>,
+    /// Name of the global variable, if any.
+    pub name: Option<String>,
+    /// Binding point of the resource.
+    pub binding: crate::ResourceBinding,
+    /// Type of the global variable.
+    pub ty: Handle<crate::Type>,
+    /// Class of the resource, or of the elements of a binding array.
+    pub class: ResourceClass,
+    /// Number of resources in the binding.
+    pub count: BindingCount,
+    /// Access allowed to storage buffers and images, empty for the others.
+    pub access: crate::StorageAccess,
+    /// Minimal size of the bound buffer range, in bytes. For binding arrays,
+    /// this is the size of each buffer.
+    ///
+    /// For buffers ending with a runtime-sized array, this includes one
+    /// element of the array. It's `None` for images and samplers.
+    pub min_binding_size: Option<u32>,
+    /// How the entry point uses the resource.
+    pub usage: GlobalUse,
+}
+
+/// A vertex input, fragment output, or any other entry point varying.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+#[cfg_attr(feature = "deserialize", derive(serde::Deserialize))]
+pub struct VaryingReflection {
+    /// Name of the argument or structure member, if any.
+    pub name: Option<String>,
+    /// Type of the varying.
+    pub ty: Handle<crate::Type>,
+    /// Built-in or location binding, with interpolation and sampling.
+    pub binding: crate::Binding,
+}
+
+/// A push constant block used by an entry point.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+#[cfg_attr(feature = "deserialize", derive(serde::Deserialize))]
+pub struct PushConstantRange {
+    /// The global variable of the push constants.
+    pub global: Handle<crate::GlobalVariable>,
+    /// Range of the push constant data, in bytes.
+    pub range: Range<u32>,
+}
+
+/// Interface of an entry point to the pipeline.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+#[cfg_attr(feature = "deserialize", derive(serde::Deserialize))]
+pub struct EntryPointReflection {
+    /// Name of the entry point.
+    pub name: String,
+    /// Shader stage.
+    pub stage: crate::ShaderStage,
+    /// Workgroup size for compute stages.
+    pub workgroup_size: [u32; 3],
+    /// Resources used by the entry point, ordered by their binding.
+    pub resources: Vec<ResourceReflection>,
+    /// Inputs of the entry point, in the order of its arguments.
+    pub inputs: Vec<VaryingReflection>,
+    /// Outputs of the entry point.
+    pub outputs: Vec<VaryingReflection>,
+    /// Push constant blocks used by the entry point.
+    pub push_constants: Vec<PushConstantRange>,
+}
+
+impl ModuleInfo {
+    /// Reflect the interface of all the entry points of `module`.
+    ///
+    /// The module has to be the one this info was produced for.
+    pub fn reflect(&self, module: &crate::Module) -> Vec<EntryPointReflection> {
+        (0..module.entry_points.len())
+            .map(|index| self.reflect_entry_point(module, index))
+            .collect()
+    }
+
+    /// Reflect the interface of the entry point with the given `index`.
+    ///
+    /// Only the global variables used by the entry point or the functions it
+    /// calls are listed.
+    pub fn reflect_entry_point(
+        &self,
+        module: &crate::Module,
+        index: usize,
+    ) -> EntryPointReflection {
+        let ep = &module.entry_points[index];
+        let info = self.get_entry_point(index);
+
+        let mut resources = Vec::new();
+        let mut push_constants = Vec::new();
+        for (handle, var) in module.global_variables.iter() {
+            let usage = info[handle];
+            if usage.is_empty() {
+                continue;
+            }
+            let (base, count) = match module.types[var.ty].inner {
+                crate::TypeInner::BindingArray { base, size } => {
+                    let count = match size {
+                        crate::ArraySize::Constant(constant) => {
+                            match module.constants[constant].to_array_length() {
+                                Some(length) => BindingCount::Array(length),
+                                None => continue,
+                            }
+                        }
+                        crate::ArraySize::Dynamic => BindingCount::RuntimeArray,
+                    };
+                    (base, count)
+                }
+                _ => (var.ty, BindingCount::Single),
+            };
+            let size = self.layouter[base].size;
+            let (class, access, min_binding_size) = match var.class {
+                crate::StorageClass::PushConstant => {
+                    push_constants.push(PushConstantRange {
+                        global: handle,
+                        range: 0..size,
+                    });
+                    continue;
+                }
+                crate::StorageClass::Uniform => (
+                    ResourceClass::Uniform,
+                    crate::StorageAccess::empty(),
+                    Some(size),
+                ),
+                crate::StorageClass::Storage => {
+                    (ResourceClass::Storage, var.storage_access, Some(size))
+                }
+                crate::StorageClass::Handle => match module.types[base].inner {
+                    crate::TypeInner::Image {
+                        dim,
+                        arrayed,
+                        class,
+                    } => {
+                        let access = match class {
+                            crate::ImageClass::Storage(_) => var.storage_access,
+                            _ => crate::StorageAccess::empty(),
+                        };
+                        let class = ResourceClass::Image {
+                            dim,
+                            arrayed,
+                            class,
+                        };
+                        (class, access, None)
+                    }
+                    crate::TypeInner::Sampler { comparison } => (
+                        ResourceClass::Sampler { comparison },
+                        crate::StorageAccess::empty(),
+                        None,
+                    ),
+                    _ => continue,
+                },
+                _ => continue,
+            };
+            let binding = match var.binding {
+                Some(ref binding) => binding.clone(),
+                None => continue,
+            };
+            resources.push(ResourceReflection {
+                global: handle,
+                name: var.name.clone(),
+                binding,
+                ty: var.ty,
+                class,
+                count,
+                access,
+                min_binding_size,
+                usage,
+            });
+        }
+        resources.sort_by(|a, b| a.binding.cmp(&b.binding));
+
+        let mut inputs = Vec::new();
+        for arg in ep.function.arguments.iter() {
+            collect_varyings(&arg.name, arg.ty, arg.binding.as_ref(), module, &mut inputs);
+        }
+        let mut outputs = Vec::new();
+        if let Some(ref result) = ep.function.result {
+            collect_varyings(
+                &None,
+                result.ty,
+                result.binding.as_ref(),
+                module,
+                &mut outputs,
+            );
+        }
+
+        EntryPointReflection {
+            name: ep.name.clone(),
+            stage: ep.stage,
+            workgroup_size: ep.workgroup_size,
+            resources,
+            inputs,
+            outputs,
+            push_constants,
+        }
+    }
+}
+
+/// Collect the varyings of an entry point argument or result, flattening
+/// the structures without a binding.
+fn collect_varyings(
+    name: &Option<String>,
+    ty: Handle<crate::Type>,
+    binding: Option<&crate::Binding>,
+    module: &crate::Module,
+    varyings: &mut Vec<VaryingReflection>,
+) {
+    match binding {
+        Some(binding) => varyings.push(VaryingReflection {
+            name: name.clone(),
+            ty,
+            binding: binding.clone(),
+        }),
+        None => {
+            if let crate::TypeInner::Struct { ref members, .. } = module.types[ty].inner {
+                for member in members {
+                    collect_varyings(
+                        &member.name,
+                        member.ty,
+                        member.binding.as_ref(),
+                        module,
+                        varyings,
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "wgsl-in"))]
+#[test]
+fn reflect_interface() {
+    let module = crate::front::wgsl::parse_str(
+        "
+        [[block]]
+        struct Globals {
+            scale: vec4<f32>;
+        };
+        [[block]]
+        struct Particles {
+            count: u32;
+            data: [[stride(16)]] array<vec4<f32>>;
+        };
+        [[block]]
+        struct Constants {
+            index: u32;
+        };
+
+        [[group(0), binding(1)]] var<storage> particles: [[access(read)]] Particles;
+        [[group(0), binding(0)]] var<uniform> globals: Globals;
+        [[group(1), binding(0)]] var color: texture_2d<f32>;
+        [[group(1), binding(1)]] var color_sampler: sampler;
+        [[group(2), binding(0)]] var<uniform> unused: Globals;
+        var<push_constant> constants: Constants;
+
+        struct VertexOutput {
+            [[builtin(position)]] position: vec4<f32>;
+            [[location(0), interpolate(flat)]] index: u32;
+        };
+
+        [[stage(vertex)]]
+        fn vs_main([[builtin(vertex_index)]] vertex: u32, [[location(1)]] offset: vec4<f32>) -> VertexOutput {
+            let position = particles.data[vertex % particles.count] * globals.scale + offset;
+            return VertexOutput(position, constants.index);
+        }
+
+        [[stage(fragment)]]
+        fn fs_main([[location(0), interpolate(flat)]] index: u32) -> [[location(0)]] vec4<f32> {
+            return textureSample(color, color_sampler, vec2<f32>(0.5));
+        }
+        ",
+    )
+    .unwrap();
+    let info = crate::valid::Validator::new(
+        crate::valid::ValidationFlags::all(),
+        crate::valid::Capabilities::PUSH_CONSTANT,
+    )
+    .validate(&module)
+    .unwrap();
+
+    let reflection = info.reflect(&module);
+    assert_eq!(reflection.len(), 2);
+
+    let vs = &reflection[0];
+    assert_eq!(vs.stage, crate::ShaderStage::Vertex);
+    assert_eq!(
+        vs.resources
+            .iter()
+            .map(|res| (
+                res.name.as_deref(),
+                res.binding.binding,
+                res.class.clone(),
+                res.access,
+                res.min_binding_size
+            ))
+            .collect::<Vec<_>>(),
+        [
+            (
+                Some("globals"),
+                0,
+                ResourceClass::Uniform,
+                crate::StorageAccess::empty(),
+                Some(16)
+            ),
+            (
+                Some("particles"),
+                1,
+                ResourceClass::Storage,
+                crate::StorageAccess::LOAD,
+                Some(32)
+            ),
+        ]
+    );
+    assert_eq!(vs.push_constants.len(), 1);
+    assert_eq!(vs.push_constants[0].range, 0..4);
+    assert_eq!(
+        vs.inputs
+            .iter()
+            .map(|varying| varying.binding.clone())
+            .collect::<Vec<_>>(),
+        [
+            crate::Binding::BuiltIn(crate::BuiltIn::VertexIndex),
+            crate::Binding::Location {
+                location: 1,
+                interpolation: None,
+                sampling: None,
+            },
+        ]
+    );
+    assert_eq!(
+        vs.outputs
+            .iter()
+            .map(|varying| varying.name.as_deref())
+            .collect::<Vec<_>>(),
+        [Some("position"), Some("index")]
+    );
+
+    let fs = &reflection[1];
+    assert_eq!(
+        fs.resources
+            .iter()
+            .map(|res| (res.name.as_deref(), res.usage))
+            .collect::<Vec<_>>(),
+        [
+            (Some("color"), GlobalUse::READ),
+            (Some("color_sampler"), GlobalUse::READ),
+        ]
+    );
+    assert!(fs.push_constants.is_empty());
+    assert_eq!(
+        fs.outputs[0].binding,
+        crate::Binding::Location {
+            location: 0,
+            interpolation: None,
+            sampling: None,
+        }
+    );
+}
+
+#[cfg(all(test, feature = "wgsl-in"))]
+#[test]
+fn reflect_binding_arrays() {
+    let module = crate::front::wgsl::parse_str(
+        "
+        [[group(0), binding(0)]] var textures: binding_array<texture_2d<f32>, 4>;
+        [[group(0), binding(1)]] var samplers: binding_array<sampler>;
+        [[group(0), binding(2)]] var color_sampler: sampler;
+
+        [[stage(fragment)]]
+        fn main([[location(0), interpolate(flat)]] index: u32) -> [[location(0)]] vec4<f32> {
+            let color = textureSample(textures[0], color_sampler, vec2<f32>(0.5));
+            return color + textureSample(textures[1], samplers[2], vec2<f32>(0.5));
+        }
+        ",
+    )
+    .unwrap();
+    let info = crate::valid::Validator::new(
+        crate::valid::ValidationFlags::all(),
+        crate::valid::Capabilities::BINDING_ARRAY,
+    )
+    .validate(&module)
+    .unwrap();
+
+    let reflection = info.reflect_entry_point(&module, 0);
+    assert_eq!(
+        reflection
+            .resources
+            .iter()
+            .map(|res| (res.name.as_deref(), res.class.clone(), res.count))
+            .collect::<Vec<_>>(),
+        [
+            (
+                Some("textures"),
+                ResourceClass::Image {
+                    dim: crate::ImageDimension::D2,
+                    arrayed: false,
+                    class: crate::ImageClass::Sampled {
+                        kind: crate::ScalarKind::Float,
+                        multi: false,
+                    },
+                },
+                BindingCount::Array(4)
+            ),
+            (
+                Some("samplers"),
+                ResourceClass::Sampler { comparison: false },
+                BindingCount::RuntimeArray
+            ),
+            (
+                Some("color_sampler"),
+                ResourceClass::Sampler { comparison: false },
+                BindingCount::Single
+            ),
+        ]
+    );
+}
@@ -72,6 +72,8 @@ pub enum Disalignment {
 pub enum TypeError {
     #[error("The {0:?} scalar width {1} is not supported")]
     InvalidWidth(crate::ScalarKind, crate::Bytes),
+    #[error("The {0:?} scalar width {1} is not supported for an atomic")]
+    InvalidAtomicWidth(crate::ScalarKind, crate::Bytes),
     #[error("The base handle {0:?} can not be resolved")]
     UnresolvedBase(Handle<crate::Type>),
     #[error("Invalid type for pointer target {0:?}")]
@@ -103,6 +105,10 @@ pub enum TypeError {
     },
     #[error("The composite type contains a top-level structure")]
     NestedTopLevel,
+    #[error("Base type {0:?} for the binding array is invalid")]
+    InvalidBindingArrayBaseType(Handle<crate::Type>),
+    #[error("Capability {0:?} is required")]
+    MissingCapability(Capabilities),
 }
 
 // Only makes sense if `flags.contains(HOST_SHARED)`
@@ -232,6 +238,22 @@ impl super::Validator {
                     count * (width as u32),
                 )
             }
+            Ti::Atomic { kind, width } => {
+                let good = match kind {
+                    crate::ScalarKind::Bool | crate::ScalarKind::Float => false,
+                    crate::ScalarKind::Sint | crate::ScalarKind::Uint => width == 4,
+                };
+                if !good {
+                    return Err(TypeError::InvalidAtomicWidth(kind, width));
+                }
+                TypeInfo::new(
+                    TypeFlags::DATA
+                        | TypeFlags::SIZED
+                        | TypeFlags::INTERFACE
+                        | TypeFlags::HOST_SHARED,
+                    width as u32,
+                )
+            }
             Ti::Pointer { base, class: _ } => {
                 if base >= handle {
                     return Err(TypeError::UnresolvedBase(base));
@@ -468,6 +490,34 @@ impl super::Validator {
                 ti
             }
             Ti::Image { .. } | Ti::Sampler { .. } => TypeInfo::new(TypeFlags::ARGUMENT, 0),
+            Ti::BindingArray { base, size } => {
+                if !self.capabilities.contains(Capabilities::BINDING_ARRAY) {
+                    return Err(TypeError::MissingCapability(Capabilities::BINDING_ARRAY));
+                }
+                if base >= handle {
+                    return Err(TypeError::UnresolvedBase(base));
+                }
+                match types[base].inner {
+                    Ti::Image { .. } | Ti::Sampler { .. } => {}
+                    Ti::Struct {
+                        top_level: true, ..
+                    } => {}
+                    _ => return Err(TypeError::InvalidBindingArrayBaseType(base)),
+                }
+                if let crate::ArraySize::Constant(const_handle) = size {
+                    match constants
+                        .try_get(const_handle)
+                        .and_then(|constant| constant.to_array_length())
+                    {
+                        Some(0) => return Err(TypeError::NonPositiveArrayLength(const_handle)),
+                        Some(_) => {}
+                        None => return Err(TypeError::InvalidArraySizeConstant(const_handle)),
+                    }
+                }
+                // Binding arrays are only allowed as the types of resources,
+                // see `validate_global_var`.
+                TypeInfo::new(TypeFlags::empty(), 0)
+            }
         })
     }
 }
@@ -0,0 +1,31 @@
+// This snapshot tests atomic types and operations.
+
+[[block]]
+struct Counters {
+    total: atomic<u32>;
+    balance: atomic<i32>;
+};
+
+[[group(0), binding(0)]]
+var<storage> counters: [[access(read_write)]] Counters;
+
+var<workgroup> shared_min: atomic<i32>;
+
+[[stage(compute), workgroup_size(1)]]
+fn main([[builtin(local_invocation_index)]] index: u32) {
+    atomicStore(&shared_min, 100);
+    let before = atomicAdd(&counters.total, 1u);
+    atomicSub(&counters.balance, 2);
+    atomicMin(&shared_min, i32(index));
+    atomicMax(&counters.balance, -5);
+    atomicAnd(&counters.total, 255u);
+    atomicOr(&counters.total, before);
+    atomicXor(&counters.total, 3u);
+    let old = atomicExchange(&counters.total, 7u);
+    let result = atomicCompareExchangeWeak(&counters.total, old, 8u);
+    if (result.y == 1u) {
+        atomicStore(&counters.total, atomicLoad(&counters.total) + 1u);
+    }
+    let minimum = atomicLoad(&shared_min);
+    atomicAdd(&counters.balance, minimum);
+}
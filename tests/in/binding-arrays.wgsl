@@ -0,0 +1,29 @@
+[[block]]
+struct UniformIndex {
+    index: u32;
+};
+
+[[group(0), binding(0)]]
+var texture_array: binding_array<texture_2d<f32>, 5>;
+[[group(0), binding(1)]]
+var sampler_array: binding_array<sampler, 5>;
+[[group(0), binding(2)]]
+var<uniform> uni: UniformIndex;
+
+struct FragmentIn {
+    [[location(0), interpolate(flat)]] index: u32;
+};
+
+[[stage(fragment)]]
+fn main(fragment_in: FragmentIn) -> [[location(0)]] vec4<f32> {
+    let uniform_index = uni.index;
+    let non_uniform_index = fragment_in.index;
+    let uv = vec2<f32>(0.0);
+
+    var v = vec4<f32>(0.0);
+    v = v + textureSample(texture_array[0], sampler_array[0], uv);
+    v = v + textureSample(texture_array[uniform_index], sampler_array[uniform_index], uv);
+    v = v + textureSample(texture_array[non_uniform_index], sampler_array[non_uniform_index], uv);
+    v = v + textureLoad(texture_array[non_uniform_index], vec2<i32>(0), 0);
+    return v;
+}
@@ -0,0 +1,32 @@
+// Bit manipulation and data packing functions.
+
+[[stage(compute), workgroup_size(1)]]
+fn main() {
+    var i: i32 = 0;
+    var i2: vec2<i32> = vec2<i32>(0);
+    var u: u32 = 0u;
+    var u3: vec3<u32> = vec3<u32>(0u);
+    var f2: vec2<f32> = vec2<f32>(0.0);
+    var f4: vec4<f32> = vec4<f32>(0.0);
+    u = pack4x8snorm(f4);
+    u = pack4x8unorm(f4);
+    u = pack2x16snorm(f2);
+    u = pack2x16unorm(f2);
+    u = pack2x16float(f2);
+    f4 = unpack4x8snorm(u);
+    f4 = unpack4x8unorm(u);
+    f2 = unpack2x16snorm(u);
+    f2 = unpack2x16unorm(u);
+    f2 = unpack2x16float(u);
+    i = insertBits(i, i, 5u, 10u);
+    i2 = insertBits(i2, i2, 5u, 10u);
+    u3 = insertBits(u3, u3, 5u, 10u);
+    i = extractBits(i, 5u, 10u);
+    i2 = extractBits(i2, 5u, 10u);
+    u = extractBits(u, 5u, 10u);
+    u3 = extractBits(u3, 5u, 10u);
+    i = firstTrailingBit(i);
+    u3 = firstTrailingBit(u3);
+    i2 = firstLeadingBit(i2);
+    u = firstLeadingBit(u);
+}
@@ -0,0 +1,25 @@
+// Tests for `naga::back::IndexBoundsCheckPolicy` on image accesses.
+
+[[group(0), binding(1)]]
+var image_2d: texture_2d<f32>;
+[[group(0), binding(2)]]
+var image_2d_array: texture_2d_array<f32>;
+[[group(0), binding(3)]]
+var image_3d: texture_3d<f32>;
+[[group(0), binding(4)]]
+var image_aa: texture_multisampled_2d<f32>;
+[[group(0), binding(5)]]
+var image_storage_src: [[access(read)]] texture_storage_2d<rgba8uint>;
+[[group(0), binding(6)]]
+var image_storage_dst: [[access(write)]] texture_storage_2d_array<rgba8uint>;
+
+[[stage(compute), workgroup_size(1)]]
+fn main([[builtin(global_invocation_id)]] id: vec3<u32>) {
+    let coords = vec3<i32>(id);
+    let value = textureLoad(image_2d, coords.xy, coords.z)
+        + textureLoad(image_2d_array, coords.xy, coords.z, coords.x)
+        + textureLoad(image_3d, coords, coords.z)
+        + textureLoad(image_aa, coords.xy, coords.z);
+    let texel = textureLoad(image_storage_src, coords.xy) + vec4<u32>(value);
+    textureStore(image_storage_dst, coords.xy, coords.z, texel);
+}
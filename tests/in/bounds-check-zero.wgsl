@@ -5,14 +5,19 @@ struct Globals {
     a: array<f32, 10>;
     v: vec4<f32>;
     m: mat3x4<f32>;
+    d: array<f32>;
 };
 
-[[group(0), binding(0)]] var<storage> globals: Globals;
+[[group(0), binding(0)]] var<storage> globals: [[access(read_write)]] Globals;
 
 fn index_array(i: i32) -> f32 {
    return globals.a[i];
 }
 
+fn index_dynamic_array(i: i32) -> f32 {
+   return globals.d[i];
+}
+
 fn index_vector(i: i32) -> f32 {
    return globals.v[i];
 }
@@ -33,6 +38,10 @@ fn set_array(i: i32, v: f32) {
    globals.a[i] = v;
 }
 
+fn set_dynamic_array(i: i32, v: f32) {
+   globals.d[i] = v;
+}
+
 fn set_vector(i: i32, v: f32) {
    globals.v[i] = v;
 }
@@ -44,3 +53,14 @@ fn set_matrix(i: i32, v: vec4<f32>) {
 fn set_index_twice(i: i32, j: i32, v: f32) {
    globals.m[i][j] = v;
 }
+
+[[stage(compute), workgroup_size(1)]]
+fn main([[builtin(global_invocation_id)]] id: vec3<u32>) {
+   let i = i32(id.x);
+   let j = i32(id.y);
+   set_array(i, index_array(j));
+   set_dynamic_array(i, index_dynamic_array(j));
+   set_vector(i, index_vector(j) + index_vector_by_value(globals.v, j));
+   set_matrix(i, index_matrix(j));
+   set_index_twice(i, j, index_twice(j, i));
+}
@@ -3,4 +3,78 @@ fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>) {
 	//TODO: execution-only barrier?
 	storageBarrier();
 	workgroupBarrier();
+
+	var pos: i32;
+	// switch without cases
+	switch (1) {
+		default: {
+			pos = 1;
+		}
+	}
+
+	switch (pos) {
+		case 1: {
+			pos = 0;
+			break;
+		}
+		case 2: {
+			pos = 1;
+		}
+		case 3, 4: {
+			pos = 2;
+			fallthrough;
+		}
+		case 5: {
+			pos = 3;
+		}
+		default: {
+			pos = 4;
+		}
+	}
+}
+
+fn switch_default_break(i: i32) {
+	switch (i) {
+		default: {
+			break;
+		}
+	}
+}
+
+fn switch_case_break() {
+	switch(0) {
+		case 0: {
+			break;
+		}
+	}
+	return;
+}
+
+fn switch_fallthrough_default(x: i32) -> i32 {
+	var res: i32 = 0;
+	switch (x) {
+		case 0: {
+			res = 1;
+			fallthrough;
+		}
+		case 1: {
+			res = res + 2;
+			fallthrough;
+		}
+		default: {
+			res = res * 3;
+		}
+	}
+	return res;
+}
+
+fn switch_empty_fallthrough(x: i32) -> i32 {
+	switch (x) {
+		case 0: {
+			fallthrough;
+		}
+		default: {
+		}
+	}
+	return x;
 }
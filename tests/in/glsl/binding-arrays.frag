@@ -0,0 +1,14 @@
+#version 450
+#extension GL_EXT_nonuniform_qualifier : require
+
+layout(set = 0, binding = 0) uniform texture2D textures[4];
+layout(set = 0, binding = 1) uniform sampler samp;
+
+layout(location = 0) flat in uint index;
+layout(location = 1) in vec2 uv;
+layout(location = 0) out vec4 color;
+
+void main() {
+    color = texture(sampler2D(textures[0], samp), uv)
+        + texture(sampler2D(textures[nonuniformEXT(index)], samp), uv);
+}
@@ -47,6 +47,12 @@ void main() {
     mat4 outerProductOut = outerProduct(a, b);
     float distanceOut = distance(a, b);
     vec4 stepOut = step(a, b);
+    int bitfieldExtractOut = bitfieldExtract(i, 1, 2);
+    int bitfieldInsertOut = bitfieldInsert(i, i, 1, 2);
+    int findLSBOut = findLSB(uint(i));
+    int findMSBOut = findMSB(i);
+    uint packOut = packUnorm4x8(a) + packHalf2x16(a.xy);
+    vec4 unpackOut = unpackSnorm4x8(packOut);
     // TODO: support out params in wgsl output
     // vec4 modfOut = modf(a, b);
     // vec4 frexpOut = frexp(a, b);
@@ -0,0 +1,12 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform texture2D tex;
+layout(set = 0, binding = 1) uniform sampler samp;
+
+layout(location = 0) in vec2 uv;
+layout(location = 0) out vec4 color;
+
+void main() {
+    color = textureGather(sampler2D(tex, samp), uv)
+        + textureGather(sampler2D(tex, samp), uv, 2);
+}
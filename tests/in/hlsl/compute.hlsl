@@ -0,0 +1,59 @@
+// Work group reduction over a structured buffer.
+
+struct Particle
+{
+    float3 position;
+    float mass;
+};
+
+StructuredBuffer<Particle> particles : register(t0);
+RWStructuredBuffer<float> totals : register(u0);
+RWTexture2D<float4> heat_map : register(u1);
+
+groupshared float shared_mass[64];
+
+float weigh(Particle particle, out uint cell)
+{
+    cell = (uint)(particle.position.x * 8.0) & 7u;
+    return particle.mass > 0.0 ? particle.mass : 0.0;
+}
+
+[numthreads(64, 1, 1)]
+void main(uint3 global_id : SV_DispatchThreadID, uint local_index : SV_GroupIndex)
+{
+    uint count, stride;
+    particles.GetDimensions(count, stride);
+
+    uint cell = 0;
+    float mass = 0.0;
+    if (global_id.x < count) {
+        mass = weigh(particles[global_id.x], cell);
+    }
+    shared_mass[local_index] = mass;
+    GroupMemoryBarrierWithGroupSync();
+
+    [unroll]
+    for (uint offset = 32; offset > 0; offset >>= 1) {
+        if (local_index < offset) {
+            shared_mass[local_index] += shared_mass[local_index + offset];
+        }
+        GroupMemoryBarrierWithGroupSync();
+    }
+
+    switch (cell) {
+    case 0:
+        mass *= 2.0;
+        break;
+    case 1:
+    case 2:
+        mass = -mass;
+        break;
+    default:
+        break;
+    }
+
+    if (local_index == 0) {
+        totals[global_id.x / 64] = shared_mass[0];
+    }
+    heat_map[int2(cell, global_id.x % 8)] = float4(mass, 0.0, 0.0, 1.0);
+}
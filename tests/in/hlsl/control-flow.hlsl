@@ -0,0 +1,48 @@
+// Statements, operators and conversions inside helper functions.
+
+static const int c_limit = 4 * 2;
+
+int collatz(uint n)
+{
+    int steps = 0;
+    while (n != 1) {
+        if (n % 2 == 0) {
+            n /= 2;
+        } else {
+            n = 3 * n + 1;
+        }
+        steps++;
+        if (steps > c_limit) {
+            break;
+        }
+    }
+    return steps;
+}
+
+void accumulate(inout float4 sum, float3 value)
+{
+    sum.xyz += value;
+    sum.w = max(sum.w, length(value));
+}
+
+float4 reduce(float2x3 basis, bool flip)
+{
+    float4 sum = 0;
+    int i = 0;
+    do {
+        accumulate(sum, basis[i]);
+        i += 1;
+    } while (i < 2);
+    float3 axis = flip ? -basis[0] : basis[1];
+    sum.x = dot(axis, normalize(float3(1, 2, 3)));
+    return sum;
+}
+
+[shader("pixel")]
+float4 main(float4 position : SV_Position, nointerpolation uint id : ID) : SV_Target
+{
+    float2x3 basis = float2x3(1.0, 0.0, 0.0, 0.0, 1.0, 0.0);
+    float4 sum = reduce(basis, id > 3u);
+    sum.z = (float)collatz(id) + ddx(position.x);
+    return lerp(sum, float4(1, 1, 1, 1), frac(position.y));
+}
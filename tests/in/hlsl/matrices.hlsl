@@ -0,0 +1,29 @@
+// Assignments to rows and elements of matrices.
+
+struct Basis {
+    float2x3 r;
+    float scale;
+};
+
+float3x3 update(float3x3 m, int i)
+{
+    m[1][2] = 3.0;
+    m[i][0] = 2.0;
+    m[2] = float3(4.0, 5.0, 6.0);
+    m[0].yx = float2(7.0, 8.0);
+    m[2].z += 1.0;
+    m[i].zy.x = m[1][1];
+    return m;
+}
+
+[shader("pixel")]
+float4 main(float4 position : SV_Position) : SV_Target
+{
+    Basis s;
+    s.r = float2x3(1.0, 0.0, 0.0, 0.0, 1.0, 0.0);
+    s.scale = 2.0;
+    s.r[1][2] = 1.0;
+    s.r[0].zx = float2(position.x, position.y);
+    float3x3 m = update(float3x3(1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0), 1);
+    return float4(m[0] + s.r[1] * s.scale, 1.0);
+}
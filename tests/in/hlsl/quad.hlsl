@@ -0,0 +1,41 @@
+// A textured quad, with the vertex and pixel stages in one file.
+
+cbuffer Globals : register(b0)
+{
+    float4x4 transform;
+    float4 tint;
+};
+
+Texture2D<float4> color_texture : register(t0);
+SamplerState color_sampler : register(s0);
+
+struct VertexInput
+{
+    float2 position : POSITION;
+    float2 uv : TEXCOORD0;
+};
+
+struct VertexOutput
+{
+    float4 position : SV_Position;
+    float2 uv : TEXCOORD0;
+};
+
+static const float c_scale = 1.2;
+
+[shader("vertex")]
+VertexOutput vs_main(VertexInput input)
+{
+    VertexOutput output;
+    output.position = mul(transform, float4(c_scale * input.position, 0.0, 1.0));
+    output.uv = input.uv;
+    return output;
+}
+
+[shader("pixel")]
+float4 ps_main(VertexOutput input) : SV_Target0
+{
+    float4 color = color_texture.Sample(color_sampler, input.uv);
+    clip(color.a - 0.5);
+    return saturate(color * tint);
+}
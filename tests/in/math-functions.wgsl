@@ -0,0 +1,25 @@
+// Math and relational functions.
+
+[[stage(fragment)]]
+fn main([[location(0)]] v: vec4<f32>, [[location(1)]] s: f32) -> [[location(0)]] vec4<f32> {
+    let outer = outerProduct(v.xy, v.zw);
+    let outer_tall = outerProduct(v.xyz, v.zw);
+    let normal = isNormal(v);
+    let finite = isFinite(s) && !isNan(s) && !isInf(s);
+    let bits = countOneBits(u32(s)) + reverseBits(u32(s));
+    let facing = faceForward(v, v, v);
+    let det = determinant(mat2x2<f32>(v.xy, v.zw));
+    let inv = inverse(mat2x2<f32>(v.xy, v.zw));
+    let inv3 = inverse(mat3x3<f32>(v.xyz, v.yzw, v.zwx));
+    let inv4 = inverse(mat4x4<f32>(v, v.yzwx, v.zwxy, v.wxyz));
+    var whole: f32;
+    let fraction = modf(s, &whole);
+    var exponent: i32;
+    let mantissa = frexp(s, &exponent);
+    let scaled = ldexp(mantissa, exponent);
+    let refracted = refract(v.xyz, normalize(v.zyx), fraction);
+    if (all(normal) && any(normal) && finite && isNormal(s)) {
+        return outer[1].xyxy + outer_tall[1].xyzx + facing * det + vec4<f32>(inv[0], whole, scaled) + inv3[1].xyzx + inv4[2] + refracted.xyzx;
+    }
+    return vec4<f32>(f32(bits));
+}
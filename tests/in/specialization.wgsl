@@ -0,0 +1,12 @@
+// Pipeline-overridable constants.
+[[override(0)]] let has_point_light: bool = true;
+[[override(1200)]] let gain: f32 = 3.0;
+[[override(1300)]] let count: i32 = 4;
+
+[[stage(compute), workgroup_size(1)]]
+fn main() {
+    var t: f32 = gain * 2.0;
+    if (has_point_light) {
+        t = t + f32(count);
+    }
+}
@@ -0,0 +1,27 @@
+[[block]]
+struct Data {
+    values: [[stride(4)]] array<u32>;
+};
+
+[[group(0), binding(0)]]
+var<storage> data: [[access(read_write)]] Data;
+
+[[stage(compute), workgroup_size(64)]]
+fn main(
+    [[builtin(subgroup_size)]] size: u32,
+    [[builtin(subgroup_invocation_id)]] id: u32,
+) {
+    let ballot = subgroupBallot((id & 1u) == 1u);
+    let everyone = subgroupBallot();
+    let all_set = subgroupAll(id != 0u);
+    let any_set = subgroupAny(id == 0u);
+    let reduced = subgroupAdd(id) + subgroupMul(id) + subgroupMin(id) + subgroupMax(id);
+    let bits = subgroupAnd(id) | subgroupOr(id) ^ subgroupXor(id);
+    let scans = subgroupInclusiveAdd(id) + subgroupExclusiveMul(id);
+    let gathered = subgroupBroadcastFirst(id) + subgroupBroadcast(id, 4u)
+        + subgroupShuffle(id, size - 1u - id) + subgroupShuffleXor(id, 1u)
+        + subgroupShuffleUp(id, 1u) + subgroupShuffleDown(id, 1u);
+    if (all_set || any_set) {
+        data.values[id] = ballot.x + everyone.y + reduced + bits + scans + gathered;
+    }
+}
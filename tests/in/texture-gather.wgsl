@@ -0,0 +1,30 @@
+[[group(0), binding(0)]]
+var image_2d: texture_2d<f32>;
+[[group(0), binding(1)]]
+var image_2d_u32: texture_2d<u32>;
+[[group(0), binding(2)]]
+var image_2d_array: texture_2d_array<f32>;
+[[group(0), binding(3)]]
+var image_cube: texture_cube<f32>;
+[[group(0), binding(4)]]
+var image_cube_depth: texture_depth_cube;
+[[group(0), binding(5)]]
+var image_2d_depth: texture_depth_2d;
+[[group(0), binding(6)]]
+var sampler_reg: sampler;
+[[group(0), binding(7)]]
+var sampler_cmp: sampler_comparison;
+
+[[stage(fragment)]]
+fn gather() -> [[location(0)]] vec4<f32> {
+    let tc = vec2<f32>(0.5);
+    let dref = 0.5;
+    let s2d = textureGather(1, image_2d, sampler_reg, tc);
+    let s2d_offset = textureGather(3, image_2d, sampler_reg, tc, vec2<i32>(3, 1));
+    let s2d_u32 = textureGather(0, image_2d_u32, sampler_reg, tc);
+    let s2d_array = textureGather(2, image_2d_array, sampler_reg, tc, 1);
+    let s_cube = textureGather(1, image_cube, sampler_reg, vec3<f32>(0.5));
+    let s_cube_depth = textureGather(image_cube_depth, sampler_reg, vec3<f32>(0.5));
+    let s2d_depth_cmp = textureGatherCompare(image_2d_depth, sampler_cmp, tc, dref, vec2<i32>(3, 1));
+    return s2d + s2d_offset + vec4<f32>(s2d_u32) + s2d_array + s_cube + s_cube_depth + s2d_depth_cmp;
+}
@@ -0,0 +1,18 @@
+struct Histogram {
+    total: atomic<u32>;
+    bins: array<atomic<u32>, 4>;
+};
+
+var<workgroup> histogram: Histogram;
+var<workgroup> samples: array<vec2<f32>, 128>;
+var<workgroup> scale: f32;
+
+[[stage(compute), workgroup_size(64)]]
+fn main([[builtin(local_invocation_id)]] local_id: vec3<u32>) {
+    samples[local_id.x] = vec2<f32>(f32(local_id.x));
+    if (local_id.x == 0u) {
+        scale = 2.0;
+    }
+    atomicAdd(&histogram.total, 1u);
+    atomicAdd(&histogram.bins[local_id.x % 4u], 1u);
+}
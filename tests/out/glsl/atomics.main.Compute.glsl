@@ -0,0 +1,36 @@
+#version 310 es
+
+precision highp float;
+
+layout(local_size_x = 1, local_size_y = 1, local_size_z = 1) in;
+
+buffer Counters_block_0 {
+    uint total;
+    int balance;
+} _group_0_binding_0;
+
+shared int shared_min;
+
+
+void main() {
+    uint index = gl_LocalInvocationIndex;
+    shared_min = 100;
+    uint _expr6 = atomicAdd(_group_0_binding_0.total, 1u);
+    int _expr9 = atomicAdd(_group_0_binding_0.balance, -2);
+    int _expr11 = atomicMin(shared_min, int(index));
+    int _expr14 = atomicMax(_group_0_binding_0.balance, -5);
+    uint _expr17 = atomicAnd(_group_0_binding_0.total, 255u);
+    uint _expr19 = atomicOr(_group_0_binding_0.total, _expr6);
+    uint _expr22 = atomicXor(_group_0_binding_0.total, 3u);
+    uint _expr25 = atomicExchange(_group_0_binding_0.total, 7u);
+    uint _expr28_old = atomicCompSwap(_group_0_binding_0.total, _expr25, 8u);
+    uvec2 _expr28 = uvec2(_expr28_old, uint(_expr28_old == _expr25));
+    if ((_expr28.y == 1u)) {
+        uint _expr34 = _group_0_binding_0.total;
+        _group_0_binding_0.total = (_expr34 + 1u);
+    }
+    int minimum = shared_min;
+    int _expr39 = atomicAdd(_group_0_binding_0.balance, minimum);
+    return;
+}
+
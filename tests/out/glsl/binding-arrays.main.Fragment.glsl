@@ -0,0 +1,39 @@
+#version 450 core
+#extension GL_EXT_nonuniform_qualifier : require
+struct FragmentIn {
+    uint index;
+};
+
+uniform highp sampler2D _group_0_binding_0[5];
+
+uniform UniformIndex_block_0 {
+    uint index;
+} _group_0_binding_2;
+
+layout(location = 0) flat in uint _vs2fs_location0;
+layout(location = 0) out vec4 _fs2p_location0;
+
+void main() {
+    FragmentIn fragment_in = FragmentIn(_vs2fs_location0);
+    vec4 v;
+    uint uniform_index = _group_0_binding_2.index;
+    uint non_uniform_index = fragment_in.index;
+    vec2 uv = vec2(0.0);
+    v = vec4(0.0);
+    vec4 _expr12 = v;
+    vec4 _expr17 = texture(_group_0_binding_0[0], vec2(uv));
+    v = (_expr12 + _expr17);
+    vec4 _expr19 = v;
+    vec4 _expr22 = texture(_group_0_binding_0[uniform_index], vec2(uv));
+    v = (_expr19 + _expr22);
+    vec4 _expr24 = v;
+    vec4 _expr27 = texture(_group_0_binding_0[nonuniformEXT(non_uniform_index)], vec2(uv));
+    v = (_expr24 + _expr27);
+    vec4 _expr29 = v;
+    vec4 _expr34 = texelFetch(_group_0_binding_0[nonuniformEXT(non_uniform_index)], ivec2(0), 0);
+    v = (_expr29 + _expr34);
+    vec4 _expr36 = v;
+    _fs2p_location0 = _expr36;
+    return;
+}
+
@@ -0,0 +1,64 @@
+#version 450 core
+#extension GL_ARB_compute_shader : require
+layout(local_size_x = 1, local_size_y = 1, local_size_z = 1) in;
+
+
+void main() {
+    int i = 0;
+    ivec2 i2_;
+    uint u = 0u;
+    uvec3 u3_;
+    vec2 f2_;
+    vec4 f4_;
+    i2_ = ivec2(0);
+    u3_ = uvec3(0u);
+    f2_ = vec2(0.0);
+    f4_ = vec4(0.0);
+    vec4 _expr16 = f4_;
+    u = packSnorm4x8(_expr16);
+    vec4 _expr18 = f4_;
+    u = packUnorm4x8(_expr18);
+    vec2 _expr20 = f2_;
+    u = packSnorm2x16(_expr20);
+    vec2 _expr22 = f2_;
+    u = packUnorm2x16(_expr22);
+    vec2 _expr24 = f2_;
+    u = packHalf2x16(_expr24);
+    uint _expr26 = u;
+    f4_ = unpackSnorm4x8(_expr26);
+    uint _expr28 = u;
+    f4_ = unpackUnorm4x8(_expr28);
+    uint _expr30 = u;
+    f2_ = unpackSnorm2x16(_expr30);
+    uint _expr32 = u;
+    f2_ = unpackUnorm2x16(_expr32);
+    uint _expr34 = u;
+    f2_ = unpackHalf2x16(_expr34);
+    int _expr36 = i;
+    int _expr37 = i;
+    i = bitfieldInsert(_expr36, _expr37, int(5u), int(10u));
+    ivec2 _expr41 = i2_;
+    ivec2 _expr42 = i2_;
+    i2_ = bitfieldInsert(_expr41, _expr42, int(5u), int(10u));
+    uvec3 _expr46 = u3_;
+    uvec3 _expr47 = u3_;
+    u3_ = bitfieldInsert(_expr46, _expr47, int(5u), int(10u));
+    int _expr51 = i;
+    i = bitfieldExtract(_expr51, int(5u), int(10u));
+    ivec2 _expr55 = i2_;
+    i2_ = bitfieldExtract(_expr55, int(5u), int(10u));
+    uint _expr59 = u;
+    u = bitfieldExtract(_expr59, int(5u), int(10u));
+    uvec3 _expr63 = u3_;
+    u3_ = bitfieldExtract(_expr63, int(5u), int(10u));
+    int _expr67 = i;
+    i = findLSB(_expr67);
+    uvec3 _expr69 = u3_;
+    u3_ = uvec3(findLSB(_expr69));
+    ivec2 _expr71 = i2_;
+    i2_ = findMSB(_expr71);
+    uint _expr73 = u;
+    u = uint(findMSB(_expr73));
+    return;
+}
+
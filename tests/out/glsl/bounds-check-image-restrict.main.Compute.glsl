@@ -0,0 +1,33 @@
+#version 310 es
+
+precision highp float;
+
+layout(local_size_x = 1, local_size_y = 1, local_size_z = 1) in;
+
+uniform highp sampler2D _group_0_binding_1;
+
+uniform highp sampler2DArray _group_0_binding_2;
+
+uniform highp sampler3D _group_0_binding_3;
+
+uniform highp sampler2DMS _group_0_binding_4;
+
+layout(rgba8ui) readonly uniform highp uimage2D _group_0_binding_5;
+
+layout(rgba8ui) writeonly uniform highp uimage2DArray _group_0_binding_6;
+
+
+void main() {
+    uvec3 id = gl_GlobalInvocationID;
+    ivec3 coords = ivec3(id);
+    vec4 _expr10 = texelFetch(_group_0_binding_1, clamp(coords.xy, ivec2(0), textureSize(_group_0_binding_1, clamp(coords.z, 0, textureQueryLevels(_group_0_binding_1) - 1)) - 1), clamp(coords.z, 0, textureQueryLevels(_group_0_binding_1) - 1));
+    vec4 _expr14 = texelFetch(_group_0_binding_2, clamp(ivec3(coords.xy, coords.z), ivec3(0), textureSize(_group_0_binding_2, clamp(coords.x, 0, textureQueryLevels(_group_0_binding_2) - 1)) - 1), clamp(coords.x, 0, textureQueryLevels(_group_0_binding_2) - 1));
+    vec4 _expr17 = texelFetch(_group_0_binding_3, clamp(coords, ivec3(0), textureSize(_group_0_binding_3, clamp(coords.z, 0, textureQueryLevels(_group_0_binding_3) - 1)) - 1), clamp(coords.z, 0, textureQueryLevels(_group_0_binding_3) - 1));
+    vec4 _expr21 = texelFetch(_group_0_binding_4, clamp(coords.xy, ivec2(0), textureSize(_group_0_binding_4) - 1), clamp(coords.z, 0, textureSamples(_group_0_binding_4) - 1));
+    vec4 value = (((_expr10 + _expr14) + _expr17) + _expr21);
+    uvec4 _expr24 = imageLoad(_group_0_binding_5, clamp(coords.xy, ivec2(0), imageSize(_group_0_binding_5) - 1));
+    uvec4 texel = (_expr24 + uvec4(value));
+    imageStore(_group_0_binding_6, clamp(ivec3(coords.xy, coords.z), ivec3(0), imageSize(_group_0_binding_6) - 1), texel);
+    return;
+}
+
@@ -0,0 +1,35 @@
+#version 310 es
+
+precision highp float;
+
+layout(local_size_x = 1, local_size_y = 1, local_size_z = 1) in;
+
+uniform highp sampler2D _group_0_binding_1;
+
+uniform highp sampler2DArray _group_0_binding_2;
+
+uniform highp sampler3D _group_0_binding_3;
+
+uniform highp sampler2DMS _group_0_binding_4;
+
+layout(rgba8ui) readonly uniform highp uimage2D _group_0_binding_5;
+
+layout(rgba8ui) writeonly uniform highp uimage2DArray _group_0_binding_6;
+
+
+void main() {
+    uvec3 id = gl_GlobalInvocationID;
+    ivec3 coords = ivec3(id);
+    vec4 _expr10 = (uint(coords.z) < uint(textureQueryLevels(_group_0_binding_1)) && all(lessThan(uvec2(coords.xy), uvec2(textureSize(_group_0_binding_1, coords.z)))) ? texelFetch(_group_0_binding_1, coords.xy, coords.z) : vec4(0));
+    vec4 _expr14 = (uint(coords.x) < uint(textureQueryLevels(_group_0_binding_2)) && all(lessThan(uvec3(ivec3(coords.xy, coords.z)), uvec3(textureSize(_group_0_binding_2, coords.x)))) ? texelFetch(_group_0_binding_2, ivec3(coords.xy, coords.z), coords.x) : vec4(0));
+    vec4 _expr17 = (uint(coords.z) < uint(textureQueryLevels(_group_0_binding_3)) && all(lessThan(uvec3(coords), uvec3(textureSize(_group_0_binding_3, coords.z)))) ? texelFetch(_group_0_binding_3, coords, coords.z) : vec4(0));
+    vec4 _expr21 = (uint(coords.z) < uint(textureSamples(_group_0_binding_4)) && all(lessThan(uvec2(coords.xy), uvec2(textureSize(_group_0_binding_4)))) ? texelFetch(_group_0_binding_4, coords.xy, coords.z) : vec4(0));
+    vec4 value = (((_expr10 + _expr14) + _expr17) + _expr21);
+    uvec4 _expr24 = (all(lessThan(uvec2(coords.xy), uvec2(imageSize(_group_0_binding_5)))) ? imageLoad(_group_0_binding_5, coords.xy) : uvec4(0));
+    uvec4 texel = (_expr24 + uvec4(value));
+    if (all(lessThan(uvec3(ivec3(coords.xy, coords.z)), uvec3(imageSize(_group_0_binding_6))))) {
+        imageStore(_group_0_binding_6, ivec3(coords.xy, coords.z), texel);
+    }
+    return;
+}
+
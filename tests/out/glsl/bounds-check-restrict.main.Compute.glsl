@@ -0,0 +1,87 @@
+#version 310 es
+
+precision highp float;
+
+layout(local_size_x = 1, local_size_y = 1, local_size_z = 1) in;
+
+buffer Globals_block_0 {
+    float a[10];
+    vec4 v;
+    mat3x4 m;
+    float d[];
+} _group_0_binding_0;
+
+
+float index_array(int i) {
+    float _expr4 = _group_0_binding_0.a[min(uint(i), 9u)];
+    return _expr4;
+}
+
+float index_dynamic_array(int i1) {
+    float _expr4 = _group_0_binding_0.d[min(uint(i1), uint(_group_0_binding_0.d.length()) - 1u)];
+    return _expr4;
+}
+
+float index_vector(int i2) {
+    vec4 _expr3 = _group_0_binding_0.v;
+    return _expr3[min(uint(i2), 3u)];
+}
+
+float index_vector_by_value(vec4 v, int i3) {
+    return v[min(uint(i3), 3u)];
+}
+
+vec4 index_matrix(int i4) {
+    vec4 _expr4 = _group_0_binding_0.m[min(uint(i4), 2u)];
+    return _expr4;
+}
+
+float index_twice(int i5, int j) {
+    vec4 _expr5 = _group_0_binding_0.m[min(uint(i5), 2u)];
+    return _expr5[min(uint(j), 3u)];
+}
+
+void set_array(int i6, float v1) {
+    _group_0_binding_0.a[min(uint(i6), 9u)] = v1;
+    return;
+}
+
+void set_dynamic_array(int i7, float v2) {
+    _group_0_binding_0.d[min(uint(i7), uint(_group_0_binding_0.d.length()) - 1u)] = v2;
+    return;
+}
+
+void set_vector(int i8, float v3) {
+    _group_0_binding_0.v[min(uint(i8), 3u)] = v3;
+    return;
+}
+
+void set_matrix(int i9, vec4 v4) {
+    _group_0_binding_0.m[min(uint(i9), 2u)] = v4;
+    return;
+}
+
+void set_index_twice(int i10, int j1, float v5) {
+    _group_0_binding_0.m[min(uint(i10), 2u)][min(uint(j1), 3u)] = v5;
+    return;
+}
+
+void main() {
+    uvec3 id = gl_GlobalInvocationID;
+    int i11 = int(id.x);
+    int j2 = int(id.y);
+    float _expr6 = index_array(j2);
+    set_array(i11, _expr6);
+    float _expr7 = index_dynamic_array(j2);
+    set_dynamic_array(i11, _expr7);
+    float _expr8 = index_vector(j2);
+    vec4 _expr10 = _group_0_binding_0.v;
+    float _expr11 = index_vector_by_value(_expr10, j2);
+    set_vector(i11, (_expr8 + _expr11));
+    vec4 _expr13 = index_matrix(j2);
+    set_matrix(i11, _expr13);
+    float _expr14 = index_twice(j2, i11);
+    set_index_twice(i11, j2, _expr14);
+    return;
+}
+
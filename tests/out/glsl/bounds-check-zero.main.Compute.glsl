@@ -0,0 +1,97 @@
+#version 310 es
+
+precision highp float;
+
+layout(local_size_x = 1, local_size_y = 1, local_size_z = 1) in;
+
+buffer Globals_block_0 {
+    float a[10];
+    vec4 v;
+    mat3x4 m;
+    float d[];
+} _group_0_binding_0;
+
+
+float index_array(int i) {
+    float _expr4 = (uint(i) < 10u ? _group_0_binding_0.a[i] : float(0));
+    return _expr4;
+}
+
+float index_dynamic_array(int i1) {
+    float _expr4 = (uint(i1) < uint(_group_0_binding_0.d.length()) ? _group_0_binding_0.d[i1] : float(0));
+    return _expr4;
+}
+
+float index_vector(int i2) {
+    vec4 _expr3 = _group_0_binding_0.v;
+    return (uint(i2) < 4u ? _expr3[i2] : float(0));
+}
+
+float index_vector_by_value(vec4 v, int i3) {
+    return (uint(i3) < 4u ? v[i3] : float(0));
+}
+
+vec4 index_matrix(int i4) {
+    vec4 _expr4 = (uint(i4) < 3u ? _group_0_binding_0.m[i4] : vec4(0));
+    return _expr4;
+}
+
+float index_twice(int i5, int j) {
+    vec4 _expr5 = (uint(i5) < 3u ? _group_0_binding_0.m[i5] : vec4(0));
+    return (uint(j) < 4u ? _expr5[j] : float(0));
+}
+
+void set_array(int i6, float v1) {
+    if (uint(i6) < 10u) {
+        _group_0_binding_0.a[i6] = v1;
+    }
+    return;
+}
+
+void set_dynamic_array(int i7, float v2) {
+    if (uint(i7) < uint(_group_0_binding_0.d.length())) {
+        _group_0_binding_0.d[i7] = v2;
+    }
+    return;
+}
+
+void set_vector(int i8, float v3) {
+    if (uint(i8) < 4u) {
+        _group_0_binding_0.v[i8] = v3;
+    }
+    return;
+}
+
+void set_matrix(int i9, vec4 v4) {
+    if (uint(i9) < 3u) {
+        _group_0_binding_0.m[i9] = v4;
+    }
+    return;
+}
+
+void set_index_twice(int i10, int j1, float v5) {
+    if (uint(j1) < 4u && uint(i10) < 3u) {
+        _group_0_binding_0.m[i10][j1] = v5;
+    }
+    return;
+}
+
+void main() {
+    uvec3 id = gl_GlobalInvocationID;
+    int i11 = int(id.x);
+    int j2 = int(id.y);
+    float _expr6 = index_array(j2);
+    set_array(i11, _expr6);
+    float _expr7 = index_dynamic_array(j2);
+    set_dynamic_array(i11, _expr7);
+    float _expr8 = index_vector(j2);
+    vec4 _expr10 = _group_0_binding_0.v;
+    float _expr11 = index_vector_by_value(_expr10, j2);
+    set_vector(i11, (_expr8 + _expr11));
+    vec4 _expr13 = index_matrix(j2);
+    set_matrix(i11, _expr13);
+    float _expr14 = index_twice(j2, i11);
+    set_index_twice(i11, j2, _expr14);
+    return;
+}
+
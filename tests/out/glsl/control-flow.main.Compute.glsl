@@ -5,10 +5,73 @@ precision highp float;
 layout(local_size_x = 1, local_size_y = 1, local_size_z = 1) in;
 
 
+void switch_default_break(int i) {
+    switch(i) {
+        default:
+            break;
+    }
+}
+
+void switch_case_break() {
+    switch(0) {
+        case 0:
+            break;
+            break;
+    }
+    return;
+}
+
+int switch_fallthrough_default(int x) {
+    int res = 0;
+    switch(x) {
+        case 0:
+            res = 1;
+        case 1:
+            int _expr4 = res;
+            res = (_expr4 + 2);
+        default:
+            int _expr7 = res;
+            res = (_expr7 * 3);
+    }
+    int _expr10 = res;
+    return _expr10;
+}
+
+int switch_empty_fallthrough(int x1) {
+    switch(x1) {
+    }
+    return x1;
+}
+
 void main() {
     uvec3 global_id = gl_GlobalInvocationID;
+    int pos;
     groupMemoryBarrier();
     groupMemoryBarrier();
-    return;
+    switch(1) {
+        default:
+            pos = 1;
+    }
+    int _expr4 = pos;
+    switch(_expr4) {
+        case 1:
+            pos = 0;
+            break;
+            break;
+        case 2:
+            pos = 1;
+            return;
+            break;
+        case 3:
+        case 4:
+            pos = 2;
+        case 5:
+            pos = 3;
+            return;
+            break;
+        default:
+            pos = 4;
+            return;
+    }
 }
 
@@ -0,0 +1,37 @@
+#version 310 es
+
+precision highp float;
+
+layout(location = 0) smooth in vec4 _vs2fs_location0;
+layout(location = 1) smooth in float _vs2fs_location1;
+layout(location = 0) out vec4 _fs2p_location0;
+
+void main() {
+    vec4 v = _vs2fs_location0;
+    float s = _vs2fs_location1;
+    float whole;
+    int exponent;
+    mat2x2 outer = outerProduct(v.xy, v.zw);
+    mat2x3 outer_tall = outerProduct(v.xyz, v.zw);
+    bvec4 normal = !isnan(v);
+    bool finite = ((!isinf(s) && (! isnan(s))) && (! isinf(s)));
+    uint bits = (bitCount(uint(s)) + bitfieldReverse(uint(s)));
+    vec4 facing = faceforward(v, v, v);
+    float det = determinant(mat2x2(v.xy, v.zw));
+    mat2x2 inv = inverse(mat2x2(v.xy, v.zw));
+    mat3x3 inv3_ = inverse(mat3x3(v.xyz, v.yzw, v.zwx));
+    mat4x4 inv4_ = inverse(mat4x4(v, v.yzwx, v.zwxy, v.wxyz));
+    float fraction = modf(s, whole);
+    float mantissa = frexp(s, exponent);
+    int _expr44 = exponent;
+    float scaled = ldexp(mantissa, _expr44);
+    vec3 refracted = refract(v.xyz, normalize(v.zyx), fraction);
+    if ((((all(normal) && any(normal)) && finite) && !isnan(s))) {
+        float _expr67 = whole;
+        _fs2p_location0 = ((((((outer[1].xyxy + outer_tall[1].xyzx) + (facing * det)) + vec4(inv[0], _expr67, scaled)) + inv3_[1].xyzx) + inv4_[2]) + refracted.xyzx);
+        return;
+    }
+    _fs2p_location0 = vec4(float(bits));
+    return;
+}
+
@@ -0,0 +1,19 @@
+#version 310 es
+
+precision highp float;
+
+layout(local_size_x = 1, local_size_y = 1, local_size_z = 1) in;
+
+
+void main() {
+    float t;
+    t = (3.0 * 2.0);
+    if (true) {
+        float _expr6 = t;
+        t = (_expr6 + float(4));
+        return;
+    } else {
+        return;
+    }
+}
+
@@ -0,0 +1,51 @@
+#version 310 es
+#extension GL_KHR_shader_subgroup_basic : require
+#extension GL_KHR_shader_subgroup_vote : require
+#extension GL_KHR_shader_subgroup_arithmetic : require
+#extension GL_KHR_shader_subgroup_ballot : require
+#extension GL_KHR_shader_subgroup_shuffle : require
+#extension GL_KHR_shader_subgroup_shuffle_relative : require
+
+precision highp float;
+
+layout(local_size_x = 64, local_size_y = 1, local_size_z = 1) in;
+
+buffer Data_block_0 {
+    uint values[];
+} _group_0_binding_0;
+
+
+void main() {
+    uint size = gl_SubgroupSize;
+    uint id = gl_SubgroupInvocationID;
+    uvec4 _expr7 = subgroupBallot(((id & 1u) == 1u));
+    uvec4 _expr8 = subgroupBallot(true);
+    bool _expr11 = subgroupAll((id != 0u));
+    bool _expr14 = subgroupAny((id == 0u));
+    uint _expr15 = subgroupAdd(id);
+    uint _expr16 = subgroupMul(id);
+    uint _expr18 = subgroupMin(id);
+    uint _expr20 = subgroupMax(id);
+    uint reduced = (((_expr15 + _expr16) + _expr18) + _expr20);
+    uint _expr22 = subgroupAnd(id);
+    uint _expr23 = subgroupOr(id);
+    uint _expr24 = subgroupXor(id);
+    uint bits = (_expr22 | (_expr23 ^ _expr24));
+    uint _expr27 = subgroupInclusiveAdd(id);
+    uint _expr28 = subgroupExclusiveMul(id);
+    uint scans = (_expr27 + _expr28);
+    uint _expr30 = subgroupBroadcastFirst(id);
+    uint _expr32 = subgroupBroadcast(id, 4u);
+    uint _expr37 = subgroupShuffle(id, ((size - 1u) - id));
+    uint _expr40 = subgroupShuffleXor(id, 1u);
+    uint _expr43 = subgroupShuffleUp(id, 1u);
+    uint _expr46 = subgroupShuffleDown(id, 1u);
+    uint gathered = (((((_expr30 + _expr32) + _expr37) + _expr40) + _expr43) + _expr46);
+    if ((_expr11 || _expr14)) {
+        _group_0_binding_0.values[id] = (((((_expr7.x + _expr8.y) + reduced) + bits) + scans) + gathered);
+        return;
+    } else {
+        return;
+    }
+}
+
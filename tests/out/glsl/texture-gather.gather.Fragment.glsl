@@ -0,0 +1,28 @@
+#version 450 core
+uniform highp sampler2D _group_0_binding_0;
+
+uniform highp usampler2D _group_0_binding_1;
+
+uniform highp sampler2DArray _group_0_binding_2;
+
+uniform highp samplerCube _group_0_binding_3;
+
+uniform highp samplerCubeShadow _group_0_binding_4;
+
+uniform highp sampler2DShadow _group_0_binding_5;
+
+layout(location = 0) out vec4 _fs2p_location0;
+
+void main() {
+    vec2 tc = vec2(0.5);
+    vec4 s2d = textureGather(_group_0_binding_0, tc, 1);
+    vec4 s2d_offset = textureGatherOffset(_group_0_binding_0, tc, ivec2(3, 1), 3);
+    uvec4 s2d_u32_ = textureGather(_group_0_binding_1, tc);
+    vec4 s2d_array = textureGather(_group_0_binding_2, vec3(tc, 1), 2);
+    vec4 s_cube = textureGather(_group_0_binding_3, vec3(0.5), 1);
+    vec4 s_cube_depth = textureGather(_group_0_binding_4, vec3(0.5));
+    vec4 s2d_depth_cmp = textureGatherOffset(_group_0_binding_5, tc, 0.5, ivec2(3, 1));
+    _fs2p_location0 = ((((((s2d + s2d_offset) + vec4(s2d_u32_)) + s2d_array) + s_cube) + s_cube_depth) + s2d_depth_cmp);
+    return;
+}
+
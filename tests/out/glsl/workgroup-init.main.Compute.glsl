@@ -0,0 +1,62 @@
+#version 310 es
+
+precision highp float;
+
+layout(local_size_x = 64, local_size_y = 1, local_size_z = 1) in;
+
+shared Histogram histogram;
+
+shared vec2 samples[128];
+
+shared float scale;
+
+
+void main() {
+    uvec3 local_id = gl_LocalInvocationID;
+    uint local_invocation_index = gl_LocalInvocationIndex;
+    uint local;
+    uint local1;
+    if ((local_invocation_index == 0u)) {
+        histogram.total = 0u;
+    }
+    local = local_invocation_index;
+    bool loop_init = true;
+    while(true) {
+        if (!loop_init) {
+        uint _expr36 = local;
+        local = (_expr36 + 64u);
+        }
+        loop_init = false;
+        uint _expr31 = local;
+        if ((_expr31 >= 4u)) {
+            break;
+        }
+        histogram.bins[_expr31] = 0u;
+    }
+    local1 = local_invocation_index;
+    bool loop_init1 = true;
+    while(true) {
+        if (!loop_init1) {
+        uint _expr46 = local1;
+        local1 = (_expr46 + 64u);
+        }
+        loop_init1 = false;
+        uint _expr41 = local1;
+        if ((_expr41 >= 128u)) {
+            break;
+        }
+        samples[_expr41] = vec2(0.0, 0.0);
+    }
+    if ((local_invocation_index == 1u)) {
+        scale = 0.0;
+    }
+    groupMemoryBarrier();
+    samples[local_id.x] = vec2(float(local_id.x));
+    if ((local_id.x == 0u)) {
+        scale = 2.0;
+    }
+    uint _expr15 = atomicAdd(histogram.total, 1u);
+    uint _expr22 = atomicAdd(histogram.bins[(local_id.x % 4u)], 1u);
+    return;
+}
+
@@ -0,0 +1,33 @@
+struct Bar {
+    float4x4 matrix1;
+    int data[1];
+};
+
+RWBar bar : register(u0);
+
+struct VertexInput_foo {
+    uint vi1 : SV_VertexID;
+};
+
+uint NagaArrayLength_bar()
+{
+    uint size;
+    bar.GetDimensions(size);
+    return (size - 64u) / 4u;
+}
+
+float4 foo(VertexInput_foo vertexinput_foo) : SV_Position
+{
+    float foo1 = 0.0;
+    int c = (int[5])0;
+
+    float baz = foo1;
+    foo1 = 1.0;
+    float4 _expr9 = bar.matrix1[3];
+    float b = _expr9.x;
+    int a = bar.data[(NagaArrayLength_bar() - 2u)];
+    c = int(a, int(b), 3, 4, 5);
+    c[(vertexinput_foo.vi1 + 1u)] = 42;
+    int value = c[vertexinput_foo.vi1];
+    return float4(int4(value.xxxx));
+}
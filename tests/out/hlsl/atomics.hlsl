@@ -0,0 +1,44 @@
+struct Counters {
+    uint total;
+    int balance;
+};
+
+RWCounters counters : register(u0);
+groupshared int shared_min;
+
+struct ComputeInput_main {
+    uint index1 : SV_GroupIndex;
+};
+
+[numthreads(1, 1, 1)]
+void main(ComputeInput_main computeinput_main)
+{
+    shared_min = 100;
+    uint _e6;
+    InterlockedAdd(counters.total, 1u, _e6);
+    int _e9;
+    InterlockedAdd(counters.balance, -2, _e9);
+    int _e11;
+    InterlockedMin(shared_min, int(computeinput_main.index1), _e11);
+    int _e14;
+    InterlockedMax(counters.balance, -5, _e14);
+    uint _e17;
+    InterlockedAnd(counters.total, 255u, _e17);
+    uint _e19;
+    InterlockedOr(counters.total, _e6, _e19);
+    uint _e22;
+    InterlockedXor(counters.total, 3u, _e22);
+    uint _e25;
+    InterlockedExchange(counters.total, 7u, _e25);
+    uint _e28_old;
+    InterlockedCompareExchange(counters.total, _e25, 8u, _e28_old);
+    uint2 _e28 = uint2(_e28_old, uint(_e28_old == _e25));
+    if ((_e28.y == 1u)) {
+        uint _expr34 = counters.total;
+        counters.total = (_expr34 + 1u);
+    }
+    int minimum = shared_min;
+    int _e39;
+    InterlockedAdd(counters.balance, minimum, _e39);
+    return;
+}
@@ -0,0 +1,39 @@
+struct UniformIndex {
+    uint index;
+};
+
+struct FragmentIn {
+    nointerpolation uint index : LOC0;
+};
+
+Texture2D<float4> texture_array[5] : register(t0, space0);
+SamplerState sampler_array[5] : register(s1, space0);
+cbuffer uni : register(b2, space0) { UniformIndex uni; }
+
+struct FragmentInput_main {
+    FragmentIn fragment_in1;
+};
+
+float4 main(FragmentInput_main fragmentinput_main) : SV_Target0
+{
+    float4 v = (float4)0;
+
+    uint uniform_index = uni.index;
+    uint non_uniform_index = fragmentinput_main.fragment_in1.index;
+    float2 uv = float2(0.0.xx);
+    v = float4(0.0.xxxx);
+    float4 _expr12 = v;
+    float4 _expr17 = texture_array[0].Sample(sampler_array[0], uv);
+    v = (_expr12 + _expr17);
+    float4 _expr19 = v;
+    float4 _expr22 = texture_array[uniform_index].Sample(sampler_array[uniform_index], uv);
+    v = (_expr19 + _expr22);
+    float4 _expr24 = v;
+    float4 _expr27 = texture_array[NonUniformResourceIndex(non_uniform_index)].Sample(sampler_array[NonUniformResourceIndex(non_uniform_index)], uv);
+    v = (_expr24 + _expr27);
+    float4 _expr29 = v;
+    float4 _expr34 = texture_array[NonUniformResourceIndex(non_uniform_index)].Load(int3(int2(0.xx), 0));
+    v = (_expr29 + _expr34);
+    float4 _expr36 = v;
+    return _expr36;
+}
@@ -0,0 +1,169 @@
+uint NagaPack4x8snorm(float4 x)
+{
+    uint4 v = uint4(int4(round(clamp(x, -1.0, 1.0) * 127.0)) & 0xFF);
+    return v.x | (v.y << 8) | (v.z << 16) | (v.w << 24);
+}
+
+uint NagaPack4x8unorm(float4 x)
+{
+    uint4 v = uint4(round(saturate(x) * 255.0));
+    return v.x | (v.y << 8) | (v.z << 16) | (v.w << 24);
+}
+
+uint NagaPack2x16snorm(float2 x)
+{
+    uint2 v = uint2(int2(round(clamp(x, -1.0, 1.0) * 32767.0)) & 0xFFFF);
+    return v.x | (v.y << 16);
+}
+
+uint NagaPack2x16unorm(float2 x)
+{
+    uint2 v = uint2(round(saturate(x) * 65535.0));
+    return v.x | (v.y << 16);
+}
+
+uint NagaPack2x16float(float2 x)
+{
+    return f32tof16(x.x) | (f32tof16(x.y) << 16);
+}
+
+float4 NagaUnpack4x8snorm(uint x)
+{
+    int4 v = int4(x << 24, x << 16, x << 8, x) >> 24;
+    return clamp(float4(v) / 127.0, -1.0, 1.0);
+}
+
+float4 NagaUnpack4x8unorm(uint x)
+{
+    return float4(x & 0xFF, (x >> 8) & 0xFF, (x >> 16) & 0xFF, x >> 24) / 255.0;
+}
+
+float2 NagaUnpack2x16snorm(uint x)
+{
+    int2 v = int2(x << 16, x) >> 16;
+    return clamp(float2(v) / 32767.0, -1.0, 1.0);
+}
+
+float2 NagaUnpack2x16unorm(uint x)
+{
+    return float2(x & 0xFFFF, x >> 16) / 65535.0;
+}
+
+float2 NagaUnpack2x16float(uint x)
+{
+    return float2(f16tof32(x), f16tof32(x >> 16));
+}
+
+int NagaInsertBitsInt(int e, int newbits, uint offset, uint count)
+{
+    uint o = min(offset, 32u);
+    uint c = min(count, 32u - o);
+    uint mask = (0xFFFFFFFFu >> (32u - c)) << o;
+    return c == 0u ? e : ((e & ~mask) | ((newbits << o) & mask));
+}
+
+int2 NagaInsertBitsInt2(int2 e, int2 newbits, uint offset, uint count)
+{
+    uint o = min(offset, 32u);
+    uint c = min(count, 32u - o);
+    uint mask = (0xFFFFFFFFu >> (32u - c)) << o;
+    return c == 0u ? e : ((e & ~mask) | ((newbits << o) & mask));
+}
+
+uint3 NagaInsertBitsUint3(uint3 e, uint3 newbits, uint offset, uint count)
+{
+    uint o = min(offset, 32u);
+    uint c = min(count, 32u - o);
+    uint mask = (0xFFFFFFFFu >> (32u - c)) << o;
+    return c == 0u ? e : ((e & ~mask) | ((newbits << o) & mask));
+}
+
+int NagaExtractBitsInt(int e, uint offset, uint count)
+{
+    uint o = min(offset, 32u);
+    uint c = min(count, 32u - o);
+    return c == 0u ? 0 : ((e << (32u - c - o)) >> (32u - c));
+}
+
+int2 NagaExtractBitsInt2(int2 e, uint offset, uint count)
+{
+    uint o = min(offset, 32u);
+    uint c = min(count, 32u - o);
+    return c == 0u ? 0 : ((e << (32u - c - o)) >> (32u - c));
+}
+
+uint NagaExtractBitsUint(uint e, uint offset, uint count)
+{
+    uint o = min(offset, 32u);
+    uint c = min(count, 32u - o);
+    return c == 0u ? 0 : ((e << (32u - c - o)) >> (32u - c));
+}
+
+uint3 NagaExtractBitsUint3(uint3 e, uint offset, uint count)
+{
+    uint o = min(offset, 32u);
+    uint c = min(count, 32u - o);
+    return c == 0u ? 0 : ((e << (32u - c - o)) >> (32u - c));
+}
+
+[numthreads(1, 1, 1)]
+void main()
+{
+    int i = 0;
+    int2 i2_ = (int2)0;
+    uint u = 0u;
+    uint3 u3_ = (uint3)0;
+    float2 f2_ = (float2)0;
+    float4 f4_ = (float4)0;
+
+    i2_ = int2(0.xx);
+    u3_ = uint3(0u.xxx);
+    f2_ = float2(0.0.xx);
+    f4_ = float4(0.0.xxxx);
+    float4 _expr16 = f4_;
+    u = NagaPack4x8snorm(_expr16);
+    float4 _expr18 = f4_;
+    u = NagaPack4x8unorm(_expr18);
+    float2 _expr20 = f2_;
+    u = NagaPack2x16snorm(_expr20);
+    float2 _expr22 = f2_;
+    u = NagaPack2x16unorm(_expr22);
+    float2 _expr24 = f2_;
+    u = NagaPack2x16float(_expr24);
+    uint _expr26 = u;
+    f4_ = NagaUnpack4x8snorm(_expr26);
+    uint _expr28 = u;
+    f4_ = NagaUnpack4x8unorm(_expr28);
+    uint _expr30 = u;
+    f2_ = NagaUnpack2x16snorm(_expr30);
+    uint _expr32 = u;
+    f2_ = NagaUnpack2x16unorm(_expr32);
+    uint _expr34 = u;
+    f2_ = NagaUnpack2x16float(_expr34);
+    int _expr36 = i;
+    int _expr37 = i;
+    i = NagaInsertBitsInt(_expr36, _expr37, 5u, 10u);
+    int2 _expr41 = i2_;
+    int2 _expr42 = i2_;
+    i2_ = NagaInsertBitsInt2(_expr41, _expr42, 5u, 10u);
+    uint3 _expr46 = u3_;
+    uint3 _expr47 = u3_;
+    u3_ = NagaInsertBitsUint3(_expr46, _expr47, 5u, 10u);
+    int _expr51 = i;
+    i = NagaExtractBitsInt(_expr51, 5u, 10u);
+    int2 _expr55 = i2_;
+    i2_ = NagaExtractBitsInt2(_expr55, 5u, 10u);
+    uint _expr59 = u;
+    u = NagaExtractBitsUint(_expr59, 5u, 10u);
+    uint3 _expr63 = u3_;
+    u3_ = NagaExtractBitsUint3(_expr63, 5u, 10u);
+    int _expr67 = i;
+    i = asint(firstbitlow(_expr67));
+    uint3 _expr69 = u3_;
+    u3_ = firstbitlow(_expr69);
+    int2 _expr71 = i2_;
+    i2_ = asint(firstbithigh(_expr71));
+    uint _expr73 = u;
+    u = firstbithigh(_expr73);
+    return;
+}
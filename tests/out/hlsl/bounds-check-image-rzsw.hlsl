@@ -0,0 +1,111 @@
+Texture2D<float4> image_2d : register(t1);
+Texture2DArray<float4> image_2d_array : register(t2);
+Texture3D<float4> image_3d : register(t3);
+Texture2DMS<float4> image_aa : register(t4);
+Texture2D<uint4> image_storage_src : register(t5);
+RWTexture2DArray<uint4> image_storage_dst : register(u6);
+
+struct ComputeInput_main {
+    uint3 id1 : SV_DispatchThreadID;
+};
+
+int2 NagaDimensions2D(Texture2D<uint4> tex)
+{
+    uint4 ret;
+    tex.GetDimensions(ret.x, ret.y);
+    return ret.xy;
+}
+
+int2 NagaDimensions2DArray(Texture2DArray<uint4> tex)
+{
+    uint4 ret;
+    tex.GetDimensions(ret.x, ret.y, ret.z);
+    return ret.xy;
+}
+
+int NagaNumLayers2DArray(Texture2DArray<uint4> tex)
+{
+    uint4 ret;
+    tex.GetDimensions(ret.x, ret.y, ret.z);
+    return ret.z;
+}
+
+int2 NagaMipDimensions2D(Texture2D<float4> tex, uint MipLevel)
+{
+    uint4 ret;
+    tex.GetDimensions(MipLevel, ret.x, ret.y, ret.z);
+    return ret.xy;
+}
+
+int NagaNumLevels2D(Texture2D<float4> tex)
+{
+    uint4 ret;
+    tex.GetDimensions(0, ret.x, ret.y, ret.z);
+    return ret.z;
+}
+
+int2 NagaMipDimensions2DArray(Texture2DArray<float4> tex, uint MipLevel)
+{
+    uint4 ret;
+    tex.GetDimensions(MipLevel, ret.x, ret.y, ret.z, ret.w);
+    return ret.xy;
+}
+
+int NagaNumLevels2DArray(Texture2DArray<float4> tex)
+{
+    uint4 ret;
+    tex.GetDimensions(0, ret.x, ret.y, ret.z, ret.w);
+    return ret.w;
+}
+
+int NagaNumLayers2DArray(Texture2DArray<float4> tex)
+{
+    uint4 ret;
+    tex.GetDimensions(0, ret.x, ret.y, ret.z, ret.w);
+    return ret.w;
+}
+
+int3 NagaMipDimensions3D(Texture3D<float4> tex, uint MipLevel)
+{
+    uint4 ret;
+    tex.GetDimensions(MipLevel, ret.x, ret.y, ret.z, ret.w);
+    return ret.xyz;
+}
+
+int NagaNumLevels3D(Texture3D<float4> tex)
+{
+    uint4 ret;
+    tex.GetDimensions(0, ret.x, ret.y, ret.z, ret.w);
+    return ret.w;
+}
+
+int2 NagaMSDimensions2D(Texture2DMS<float4> tex)
+{
+    uint4 ret;
+    tex.GetDimensions(ret.x, ret.y, ret.z);
+    return ret.xy;
+}
+
+int NagaMSNumSamples2D(Texture2DMS<float4> tex)
+{
+    uint4 ret;
+    tex.GetDimensions(ret.x, ret.y, ret.z);
+    return ret.z;
+}
+
+[numthreads(1, 1, 1)]
+void main(ComputeInput_main computeinput_main)
+{
+    int3 coords = int3(computeinput_main.id1);
+    float4 _expr10 = (uint(coords.z) < uint(NagaNumLevels2D(image_2d)) && all(uint2(coords.xy) < uint2(NagaMipDimensions2D(image_2d, coords.z))) ? image_2d.Load(int3(coords.xy, coords.z)) : (float4)0);
+    float4 _expr14 = (uint(coords.x) < uint(NagaNumLevels2DArray(image_2d_array)) && uint(coords.z) < uint(NagaNumLayers2DArray(image_2d_array)) && all(uint2(coords.xy) < uint2(NagaMipDimensions2DArray(image_2d_array, coords.x))) ? image_2d_array.Load(int4(coords.xy, coords.z, coords.x)) : (float4)0);
+    float4 _expr17 = (uint(coords.z) < uint(NagaNumLevels3D(image_3d)) && all(uint3(coords) < uint3(NagaMipDimensions3D(image_3d, coords.z))) ? image_3d.Load(int4(coords, coords.z)) : (float4)0);
+    float4 _expr21 = (uint(coords.z) < uint(NagaMSNumSamples2D(image_aa)) && all(uint2(coords.xy) < uint2(NagaMSDimensions2D(image_aa))) ? image_aa.Load(coords.xy, coords.z) : (float4)0);
+    float4 value = (((_expr10 + _expr14) + _expr17) + _expr21);
+    uint4 _expr24 = (all(uint2(coords.xy) < uint2(NagaDimensions2D(image_storage_src))) ? image_storage_src.Load(int3(coords.xy, 0)) : (uint4)0);
+    uint4 texel = (_expr24 + uint4(value));
+    if (uint(coords.z) < uint(NagaNumLayers2DArray(image_storage_dst)) && all(uint2(coords.xy) < uint2(NagaDimensions2DArray(image_storage_dst)))) {
+        image_storage_dst[int3(coords.xy, coords.z)] = texel;
+    }
+    return;
+}
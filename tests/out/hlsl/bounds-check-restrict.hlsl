@@ -0,0 +1,104 @@
+struct Globals {
+    float a[10];
+    float4 v;
+    float3x4 m;
+    float d[1];
+};
+
+RWGlobals globals : register(u0);
+
+struct ComputeInput_main {
+    uint3 id1 : SV_DispatchThreadID;
+};
+
+float index_array(int i)
+{
+    float _expr4 = globals.a[min(uint(i), 9u)];
+    return _expr4;
+}
+
+uint NagaArrayLength_globals()
+{
+    uint size;
+    globals.GetDimensions(size);
+    return (size - 112u) / 4u;
+}
+
+float index_dynamic_array(int i1)
+{
+    float _expr4 = globals.d[min(uint(i1), NagaArrayLength_globals() - 1u)];
+    return _expr4;
+}
+
+float index_vector(int i2)
+{
+    float4 _expr3 = globals.v;
+    return _expr3[min(uint(i2), 3u)];
+}
+
+float index_vector_by_value(float4 v, int i3)
+{
+    return v[min(uint(i3), 3u)];
+}
+
+float4 index_matrix(int i4)
+{
+    float4 _expr4 = globals.m[min(uint(i4), 2u)];
+    return _expr4;
+}
+
+float index_twice(int i5, int j)
+{
+    float4 _expr5 = globals.m[min(uint(i5), 2u)];
+    return _expr5[min(uint(j), 3u)];
+}
+
+void set_array(int i6, float v1)
+{
+    globals.a[min(uint(i6), 9u)] = v1;
+    return;
+}
+
+void set_dynamic_array(int i7, float v2)
+{
+    globals.d[min(uint(i7), NagaArrayLength_globals() - 1u)] = v2;
+    return;
+}
+
+void set_vector(int i8, float v3)
+{
+    globals.v[min(uint(i8), 3u)] = v3;
+    return;
+}
+
+void set_matrix(int i9, float4 v4)
+{
+    globals.m[min(uint(i9), 2u)] = v4;
+    return;
+}
+
+void set_index_twice(int i10, int j1, float v5)
+{
+    globals.m[min(uint(i10), 2u)][min(uint(j1), 3u)] = v5;
+    return;
+}
+
+[numthreads(1, 1, 1)]
+void main(ComputeInput_main computeinput_main)
+{
+    int i11 = int(computeinput_main.id1.x);
+    int j2 = int(computeinput_main.id1.y);
+    const float _e6 = index_array(j2);
+    set_array(i11, _e6);
+    const float _e7 = index_dynamic_array(j2);
+    set_dynamic_array(i11, _e7);
+    const float _e8 = index_vector(j2);
+    float4 _expr10 = globals.v;
+    const float _e11 = index_vector_by_value(_expr10, j2);
+    set_vector(i11, (_e8 + _e11));
+    const float4 _e13 = index_matrix(j2);
+    set_matrix(i11, _e13);
+    const float _e14 = index_twice(j2, i11);
+    set_index_twice(i11, j2, _e14);
+    return;
+}
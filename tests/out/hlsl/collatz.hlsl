@@ -0,0 +1,44 @@
+struct PrimeIndices {
+    uint data[1];
+};
+
+RWPrimeIndices v_indices : register(u0);
+
+struct ComputeInput_main {
+    uint3 global_id1 : SV_DispatchThreadID;
+};
+
+uint collatz_iterations(uint n_base)
+{
+    uint n = (uint)0;
+    uint i = 0u;
+
+    n = n_base;
+    while(true) {
+        uint _expr5 = n;
+        if ((_expr5 <= 1u)) {
+            break;
+        }
+        uint _expr8 = n;
+        if (((_expr8 % 2u) == 0u)) {
+            uint _expr13 = n;
+            n = (_expr13 / 2u);
+        } else {
+            uint _expr17 = n;
+            n = (mul(3u, _expr17) + 1u);
+        }
+        uint _expr21 = i;
+        i = (_expr21 + 1u);
+    }
+    uint _expr24 = i;
+    return _expr24;
+}
+
+[numthreads(1, 1, 1)]
+void main(ComputeInput_main computeinput_main)
+{
+    uint _expr8 = v_indices.data[computeinput_main.global_id1.x];
+    const uint _e9 = collatz_iterations(_expr8);
+    v_indices.data[computeinput_main.global_id1.x] = _e9;
+    return;
+}
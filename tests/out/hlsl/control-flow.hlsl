@@ -2,10 +2,114 @@ struct ComputeInput_main {
     uint3 global_id1 : SV_DispatchThreadID;
 };
 
+void switch_default_break(int i)
+{
+    switch(i) {
+        default: {
+            break;
+        }
+    }
+}
+
+void switch_case_break()
+{
+    switch(0) {
+        case 0: {
+            break;
+        }
+    }
+    return;
+}
+
+int switch_fallthrough_default(int x)
+{
+    int res = 0;
+
+    switch(x) {
+        case 0: {
+            {
+                res = 1;
+            }
+            {
+                int _expr4 = res;
+                res = (_expr4 + 2);
+            }
+            {
+                int _expr7 = res;
+                res = mul(_expr7, 3);
+            }
+            break;
+        }
+        case 1: {
+            {
+                int _expr4 = res;
+                res = (_expr4 + 2);
+            }
+            {
+                int _expr7 = res;
+                res = mul(_expr7, 3);
+            }
+            break;
+        }
+        default: {
+            int _expr7 = res;
+            res = mul(_expr7, 3);
+        }
+    }
+    int _expr10 = res;
+    return _expr10;
+}
+
+int switch_empty_fallthrough(int x1)
+{
+    switch(x1) {
+        case 0:
+        default: {
+            break;
+        }
+    }
+    return x1;
+}
+
 [numthreads(1, 1, 1)]
 void main(ComputeInput_main computeinput_main)
 {
+    int pos = (int)0;
+
     DeviceMemoryBarrierWithGroupSync();
     GroupMemoryBarrierWithGroupSync();
-    return;
+    switch(1) {
+        default: {
+            pos = 1;
+        }
+    }
+    int _expr4 = pos;
+    switch(_expr4) {
+        case 1: {
+            pos = 0;
+            break;
+        }
+        case 2: {
+            pos = 1;
+            return;
+        }
+        case 3:
+        case 4: {
+            {
+                pos = 2;
+            }
+            {
+                pos = 3;
+                return;
+            }
+        }
+        case 5: {
+            pos = 3;
+            return;
+        }
+        default: {
+            pos = 4;
+            return;
+        }
+    }
 }
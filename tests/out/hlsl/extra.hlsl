@@ -0,0 +1,24 @@
+struct PushConstants {
+    uint index;
+    double2 double1;
+};
+
+struct FragmentIn {
+    linear float4 color : LOC0;
+    uint primitive_index : SV_PrimitiveID;
+};
+
+cbuffer pc : register(b0, space0) { PushConstants pc; }
+
+struct FragmentInput_main {
+    FragmentIn in2;
+};
+
+float4 main(FragmentInput_main fragmentinput_main) : SV_Target0
+{
+    if (((fragmentinput_main.in2.primitive_index % 2u) == 0u)) {
+        return fragmentinput_main.in2.color;
+    } else {
+        return float4((float3(1.0.xxx) - fragmentinput_main.in2.color.xyz), fragmentinput_main.in2.color.w);
+    }
+}
@@ -0,0 +1,98 @@
+struct FragmentInput_main {
+    float4 v1 : LOC0;
+    float s1 : LOC1;
+};
+
+float2x2 NagaOuter2x2(float2 a, float2 b)
+{
+    return float2x2(a * b.x, a * b.y);
+}
+
+float2x3 NagaOuter2x3(float3 a, float2 b)
+{
+    return float2x3(a * b.x, a * b.y);
+}
+
+bool4 NagaIsNormal4(float4 x)
+{
+    return isfinite(x) && abs(x) >= 1.17549435e-38;
+}
+
+float2x2 NagaInverse2x2(float2x2 m)
+{
+    return float2x2(m[1][1], -m[0][1], -m[1][0], m[0][0]) / determinant(m);
+}
+
+float3x3 NagaInverse3x3(float3x3 m)
+{
+    float3x3 adj = transpose(float3x3(cross(m[1], m[2]), cross(m[2], m[0]), cross(m[0], m[1])));
+    return adj / dot(m[0], cross(m[1], m[2]));
+}
+
+float4x4 NagaInverse4x4(float4x4 m)
+{
+    float s0 = m[0][0] * m[1][1] - m[1][0] * m[0][1];
+    float s1 = m[0][0] * m[1][2] - m[1][0] * m[0][2];
+    float s2 = m[0][0] * m[1][3] - m[1][0] * m[0][3];
+    float s3 = m[0][1] * m[1][2] - m[1][1] * m[0][2];
+    float s4 = m[0][1] * m[1][3] - m[1][1] * m[0][3];
+    float s5 = m[0][2] * m[1][3] - m[1][2] * m[0][3];
+    float c0 = m[2][0] * m[3][1] - m[3][0] * m[2][1];
+    float c1 = m[2][0] * m[3][2] - m[3][0] * m[2][2];
+    float c2 = m[2][0] * m[3][3] - m[3][0] * m[2][3];
+    float c3 = m[2][1] * m[3][2] - m[3][1] * m[2][2];
+    float c4 = m[2][1] * m[3][3] - m[3][1] * m[2][3];
+    float c5 = m[2][2] * m[3][3] - m[3][2] * m[2][3];
+    float det = s0 * c5 - s1 * c4 + s2 * c3 + s3 * c2 - s4 * c1 + s5 * c0;
+    float4x4 adj = float4x4(
+        m[1][1] * c5 - m[1][2] * c4 + m[1][3] * c3,
+        -m[0][1] * c5 + m[0][2] * c4 - m[0][3] * c3,
+        m[3][1] * s5 - m[3][2] * s4 + m[3][3] * s3,
+        -m[2][1] * s5 + m[2][2] * s4 - m[2][3] * s3,
+        -m[1][0] * c5 + m[1][2] * c2 - m[1][3] * c1,
+        m[0][0] * c5 - m[0][2] * c2 + m[0][3] * c1,
+        -m[3][0] * s5 + m[3][2] * s2 - m[3][3] * s1,
+        m[2][0] * s5 - m[2][2] * s2 + m[2][3] * s1,
+        m[1][0] * c4 - m[1][1] * c2 + m[1][3] * c0,
+        -m[0][0] * c4 + m[0][1] * c2 - m[0][3] * c0,
+        m[3][0] * s4 - m[3][1] * s2 + m[3][3] * s0,
+        -m[2][0] * s4 + m[2][1] * s2 - m[2][3] * s0,
+        -m[1][0] * c3 + m[1][1] * c1 - m[1][2] * c0,
+        m[0][0] * c3 - m[0][1] * c1 + m[0][2] * c0,
+        -m[3][0] * s3 + m[3][1] * s1 - m[3][2] * s0,
+        m[2][0] * s3 - m[2][1] * s1 + m[2][2] * s0
+    );
+    return adj / det;
+}
+
+bool NagaIsNormal(float x)
+{
+    return isfinite(x) && abs(x) >= 1.17549435e-38;
+}
+
+float4 main(FragmentInput_main fragmentinput_main) : SV_Target0
+{
+    float whole = (float)0;
+    int exponent = (int)0;
+
+    float2x2 outer = NagaOuter2x2(fragmentinput_main.v1.xy, fragmentinput_main.v1.zw);
+    float2x3 outer_tall = NagaOuter2x3(fragmentinput_main.v1.xyz, fragmentinput_main.v1.zw);
+    bool4 normal = NagaIsNormal4(fragmentinput_main.v1);
+    bool finite = ((isfinite(fragmentinput_main.s1) && (!isnan(fragmentinput_main.s1))) && (!isinf(fragmentinput_main.s1)));
+    uint bits = (countbits(uint(fragmentinput_main.s1)) + reversebits(uint(fragmentinput_main.s1)));
+    float4 facing = faceforward(fragmentinput_main.v1, fragmentinput_main.v1, fragmentinput_main.v1);
+    float det = determinant(float2x2(fragmentinput_main.v1.xy, fragmentinput_main.v1.zw));
+    float2x2 inv = NagaInverse2x2(float2x2(fragmentinput_main.v1.xy, fragmentinput_main.v1.zw));
+    float3x3 inv3_ = NagaInverse3x3(float3x3(fragmentinput_main.v1.xyz, fragmentinput_main.v1.yzw, fragmentinput_main.v1.zwx));
+    float4x4 inv4_ = NagaInverse4x4(float4x4(fragmentinput_main.v1, fragmentinput_main.v1.yzwx, fragmentinput_main.v1.zwxy, fragmentinput_main.v1.wxyz));
+    float fraction = modf(fragmentinput_main.s1, whole);
+    float mantissa = frexp(fragmentinput_main.s1, exponent);
+    int _expr44 = exponent;
+    float scaled = ldexp(mantissa, _expr44);
+    float3 refracted = refract(fragmentinput_main.v1.xyz, normalize(fragmentinput_main.v1.zyx), fraction);
+    if ((((all(normal) && any(normal)) && finite) && NagaIsNormal(fragmentinput_main.s1))) {
+        float _expr67 = whole;
+        return ((((((outer[1].xyxy + outer_tall[1].xyzx) + mul(facing, det)) + float4(inv[0], _expr67, scaled)) + inv3_[1].xyzx) + inv4_[2]) + refracted.xyzx);
+    }
+    return float4(float(bits).xxxx);
+}
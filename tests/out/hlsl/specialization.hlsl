@@ -0,0 +1,20 @@
+static const bool has_point_light = true;
+
+static const float gain = 3.0;
+
+static const int count = 4;
+
+[numthreads(1, 1, 1)]
+void main()
+{
+    float t = (float)0;
+
+    t = mul(gain, 2.0);
+    if (has_point_light) {
+        float _expr6 = t;
+        t = (_expr6 + float(count));
+        return;
+    } else {
+        return;
+    }
+}
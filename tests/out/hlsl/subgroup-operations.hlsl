@@ -0,0 +1,42 @@
+struct Data {
+    uint values[1];
+};
+
+RWData data : register(u0, space0);
+
+struct ComputeInput_main {
+};
+
+[numthreads(64, 1, 1)]
+void main(ComputeInput_main computeinput_main)
+{
+    uint4 _e7 = WaveActiveBallot(((WaveGetLaneIndex() & 1u) == 1u));
+    uint4 _e8 = WaveActiveBallot(true);
+    bool _e11 = WaveActiveAllTrue((WaveGetLaneIndex() != 0u));
+    bool _e14 = WaveActiveAnyTrue((WaveGetLaneIndex() == 0u));
+    uint _e15 = WaveActiveSum(WaveGetLaneIndex());
+    uint _e16 = WaveActiveProduct(WaveGetLaneIndex());
+    uint _e18 = WaveActiveMin(WaveGetLaneIndex());
+    uint _e20 = WaveActiveMax(WaveGetLaneIndex());
+    uint reduced = (((_e15 + _e16) + _e18) + _e20);
+    uint _e22 = WaveActiveBitAnd(WaveGetLaneIndex());
+    uint _e23 = WaveActiveBitOr(WaveGetLaneIndex());
+    uint _e24 = WaveActiveBitXor(WaveGetLaneIndex());
+    uint bits = (_e22 | (_e23 ^ _e24));
+    uint _e27 = WavePrefixSum(WaveGetLaneIndex()) + WaveGetLaneIndex();
+    uint _e28 = WavePrefixProduct(WaveGetLaneIndex());
+    uint scans = (_e27 + _e28);
+    uint _e30 = WaveReadLaneFirst(WaveGetLaneIndex());
+    uint _e32 = WaveReadLaneAt(WaveGetLaneIndex(), 4u);
+    uint _e37 = WaveReadLaneAt(WaveGetLaneIndex(), ((WaveGetLaneCount() - 1u) - WaveGetLaneIndex()));
+    uint _e40 = WaveReadLaneAt(WaveGetLaneIndex(), WaveGetLaneIndex() ^ 1u);
+    uint _e43 = WaveReadLaneAt(WaveGetLaneIndex(), WaveGetLaneIndex() - 1u);
+    uint _e46 = WaveReadLaneAt(WaveGetLaneIndex(), WaveGetLaneIndex() + 1u);
+    uint gathered = (((((_e30 + _e32) + _e37) + _e40) + _e43) + _e46);
+    if ((_e11 || _e14)) {
+        data.values[WaveGetLaneIndex()] = (((((_e7.x + _e8.y) + reduced) + bits) + scans) + gathered);
+        return;
+    } else {
+        return;
+    }
+}
@@ -0,0 +1,21 @@
+Texture2D<float4> image_2d : register(t0);
+Texture2D<uint4> image_2d_u32_ : register(t1);
+Texture2DArray<float4> image_2d_array : register(t2);
+TextureCube<float4> image_cube : register(t3);
+TextureCube image_cube_depth : register(t4);
+Texture2D image_2d_depth : register(t5);
+SamplerState sampler_reg : register(s6);
+SamplerComparisonState sampler_cmp : register(s7);
+
+float4 gather() : SV_Target0
+{
+    float2 tc = float2(0.5.xx);
+    float4 s2d = image_2d.GatherGreen(sampler_reg, tc);
+    float4 s2d_offset = image_2d.GatherAlpha(sampler_reg, tc, int2(3, 1));
+    uint4 s2d_u32_ = image_2d_u32_.GatherRed(sampler_reg, tc);
+    float4 s2d_array = image_2d_array.GatherBlue(sampler_reg, float3(tc, 1));
+    float4 s_cube = image_cube.GatherGreen(sampler_reg, float3(0.5.xxx));
+    float4 s_cube_depth = image_cube_depth.GatherRed(sampler_reg, float3(0.5.xxx));
+    float4 s2d_depth_cmp = image_2d_depth.GatherCmp(sampler_cmp, tc, 0.5, int2(3, 1));
+    return ((((((s2d + s2d_offset) + float4(s2d_u32_)) + s2d_array) + s_cube) + s_cube_depth) + s2d_depth_cmp);
+}
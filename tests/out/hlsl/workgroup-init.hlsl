@@ -0,0 +1,57 @@
+struct Histogram {
+    uint total;
+    uint bins[4];
+};
+
+groupshared Histogram histogram;
+groupshared float2 samples[128];
+groupshared float scale;
+
+struct ComputeInput_main {
+    uint3 local_id1 : SV_GroupThreadID;
+    uint local_invocation_index1 : SV_GroupIndex;
+};
+
+[numthreads(64, 1, 1)]
+void main(ComputeInput_main computeinput_main)
+{
+    uint local = (uint)0;
+    uint local1 = (uint)0;
+
+    if ((computeinput_main.local_invocation_index1 == 0u)) {
+        histogram.total = 0u;
+    }
+    local = computeinput_main.local_invocation_index1;
+    while(true) {
+        uint _expr31 = local;
+        if ((_expr31 >= 4u)) {
+            break;
+        }
+        histogram.bins[_expr31] = 0u;
+        uint _expr36 = local;
+        local = (_expr36 + 64u);
+    }
+    local1 = computeinput_main.local_invocation_index1;
+    while(true) {
+        uint _expr41 = local1;
+        if ((_expr41 >= 128u)) {
+            break;
+        }
+        samples[_expr41] = float2(0.0, 0.0);
+        uint _expr46 = local1;
+        local1 = (_expr46 + 64u);
+    }
+    if ((computeinput_main.local_invocation_index1 == 1u)) {
+        scale = 0.0;
+    }
+    GroupMemoryBarrierWithGroupSync();
+    samples[computeinput_main.local_id1.x] = float2(float(computeinput_main.local_id1.x).xx);
+    if ((computeinput_main.local_id1.x == 0u)) {
+        scale = 2.0;
+    }
+    uint _e15;
+    InterlockedAdd(histogram.total, 1u, _e15);
+    uint _e22;
+    InterlockedAdd(histogram.bins[(computeinput_main.local_id1.x % 4u)], 1u, _e22);
+    return;
+}
@@ -0,0 +1,30 @@
+[[block]]
+struct Counters {
+    total: atomic<u32>;
+    balance: atomic<i32>;
+};
+
+[[group(0), binding(0)]]
+var<storage> counters: [[access(read_write)]] Counters;
+var<workgroup> shared_min: atomic<i32>;
+
+[[stage(compute), workgroup_size(1, 1, 1)]]
+fn main([[builtin(local_invocation_index)]] index: u32) {
+    atomicStore(&shared_min, 100);
+    let _e6: u32 = atomicAdd(&counters.total, 1u);
+    let _e9: i32 = atomicSub(&counters.balance, 2);
+    let _e11: i32 = atomicMin(&shared_min, i32(index));
+    let _e14: i32 = atomicMax(&counters.balance, -5);
+    let _e17: u32 = atomicAnd(&counters.total, 255u);
+    let _e19: u32 = atomicOr(&counters.total, _e6);
+    let _e22: u32 = atomicXor(&counters.total, 3u);
+    let _e25: u32 = atomicExchange(&counters.total, 7u);
+    let _e28: vec2<u32> = atomicCompareExchangeWeak(&counters.total, _e25, 8u);
+    if ((_e28.y == 1u)) {
+        let _e34: u32 = atomicLoad(&counters.total);
+        atomicStore(&counters.total, (_e34 + 1u));
+    }
+    let minimum: i32 = atomicLoad(&shared_min);
+    let _e39: i32 = atomicAdd(&counters.balance, minimum);
+    return;
+}
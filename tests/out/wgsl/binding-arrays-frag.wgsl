@@ -0,0 +1,30 @@
+struct FragmentOutput {
+    [[location(0)]] color: vec4<f32>;
+};
+
+[[group(0), binding(0)]]
+var textures: binding_array<texture_2d<f32>,4>;
+[[group(0), binding(1)]]
+var samp: sampler;
+var<private> index1: u32;
+var<private> uv1: vec2<f32>;
+var<private> color: vec4<f32>;
+
+fn main1() {
+    let _e7: vec2<f32> = uv1;
+    let _e8: vec4<f32> = textureSample(textures[0], samp, _e7);
+    let _e9: u32 = index1;
+    let _e11: vec2<f32> = uv1;
+    let _e12: vec4<f32> = textureSample(textures[_e9], samp, _e11);
+    color = (_e8 + _e12);
+    return;
+}
+
+[[stage(fragment)]]
+fn main([[location(0)]] index: u32, [[location(1)]] uv: vec2<f32>) -> FragmentOutput {
+    index1 = index;
+    uv1 = uv;
+    main1();
+    let _e5: vec4<f32> = color;
+    return FragmentOutput(_e5);
+}
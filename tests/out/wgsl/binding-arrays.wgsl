@@ -0,0 +1,39 @@
+[[block]]
+struct UniformIndex {
+    index: u32;
+};
+
+struct FragmentIn {
+    [[location(0)]] index: u32;
+};
+
+[[group(0), binding(0)]]
+var texture_array: binding_array<texture_2d<f32>,5>;
+[[group(0), binding(1)]]
+var sampler_array: binding_array<sampler,5>;
+[[group(0), binding(2)]]
+var<uniform> uni: UniformIndex;
+
+[[stage(fragment)]]
+fn main(fragment_in: FragmentIn) -> [[location(0)]] vec4<f32> {
+    var v: vec4<f32>;
+
+    let uniform_index: u32 = uni.index;
+    let non_uniform_index: u32 = fragment_in.index;
+    let uv: vec2<f32> = vec2<f32>(0.0);
+    v = vec4<f32>(0.0);
+    let _e12: vec4<f32> = v;
+    let _e17: vec4<f32> = textureSample(texture_array[0], sampler_array[0], uv);
+    v = (_e12 + _e17);
+    let _e19: vec4<f32> = v;
+    let _e22: vec4<f32> = textureSample(texture_array[uniform_index], sampler_array[uniform_index], uv);
+    v = (_e19 + _e22);
+    let _e24: vec4<f32> = v;
+    let _e27: vec4<f32> = textureSample(texture_array[non_uniform_index], sampler_array[non_uniform_index], uv);
+    v = (_e24 + _e27);
+    let _e29: vec4<f32> = v;
+    let _e34: vec4<f32> = textureLoad(texture_array[non_uniform_index], vec2<i32>(0), 0);
+    v = (_e29 + _e34);
+    let _e36: vec4<f32> = v;
+    return _e36;
+}
@@ -0,0 +1,60 @@
+[[stage(compute), workgroup_size(1, 1, 1)]]
+fn main() {
+    var i: i32 = 0;
+    var i2_: vec2<i32>;
+    var u: u32 = 0u;
+    var u3_: vec3<u32>;
+    var f2_: vec2<f32>;
+    var f4_: vec4<f32>;
+
+    i2_ = vec2<i32>(0);
+    u3_ = vec3<u32>(0u);
+    f2_ = vec2<f32>(0.0);
+    f4_ = vec4<f32>(0.0);
+    let _e16: vec4<f32> = f4_;
+    u = pack4x8snorm(_e16);
+    let _e18: vec4<f32> = f4_;
+    u = pack4x8unorm(_e18);
+    let _e20: vec2<f32> = f2_;
+    u = pack2x16snorm(_e20);
+    let _e22: vec2<f32> = f2_;
+    u = pack2x16unorm(_e22);
+    let _e24: vec2<f32> = f2_;
+    u = pack2x16float(_e24);
+    let _e26: u32 = u;
+    f4_ = unpack4x8snorm(_e26);
+    let _e28: u32 = u;
+    f4_ = unpack4x8unorm(_e28);
+    let _e30: u32 = u;
+    f2_ = unpack2x16snorm(_e30);
+    let _e32: u32 = u;
+    f2_ = unpack2x16unorm(_e32);
+    let _e34: u32 = u;
+    f2_ = unpack2x16float(_e34);
+    let _e36: i32 = i;
+    let _e37: i32 = i;
+    i = insertBits(_e36, _e37, 5u, 10u);
+    let _e41: vec2<i32> = i2_;
+    let _e42: vec2<i32> = i2_;
+    i2_ = insertBits(_e41, _e42, 5u, 10u);
+    let _e46: vec3<u32> = u3_;
+    let _e47: vec3<u32> = u3_;
+    u3_ = insertBits(_e46, _e47, 5u, 10u);
+    let _e51: i32 = i;
+    i = extractBits(_e51, 5u, 10u);
+    let _e55: vec2<i32> = i2_;
+    i2_ = extractBits(_e55, 5u, 10u);
+    let _e59: u32 = u;
+    u = extractBits(_e59, 5u, 10u);
+    let _e63: vec3<u32> = u3_;
+    u3_ = extractBits(_e63, 5u, 10u);
+    let _e67: i32 = i;
+    i = firstTrailingBit(_e67);
+    let _e69: vec3<u32> = u3_;
+    u3_ = firstTrailingBit(_e69);
+    let _e71: vec2<i32> = i2_;
+    i2_ = firstLeadingBit(_e71);
+    let _e73: u32 = u;
+    u = firstLeadingBit(_e73);
+    return;
+}
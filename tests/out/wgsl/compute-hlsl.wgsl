@@ -0,0 +1,91 @@
+struct Particle {
+    position: vec3<f32>;
+    mass: f32;
+};
+
+[[block]]
+struct type3 {
+    data: [[stride(16)]] array<Particle>;
+};
+
+[[block]]
+struct type5 {
+    data: [[stride(4)]] array<f32>;
+};
+
+[[group(0), binding(96)]]
+var<storage> particles: [[access(read)]] type3;
+[[group(0), binding(32)]]
+var<storage> totals: [[access(read_write)]] type5;
+[[group(0), binding(33)]]
+var heat_map: [[access(read_write)]] texture_storage_2d<rgba32float>;
+var<workgroup> shared_mass: array<f32,64u>;
+
+fn weigh(particle: Particle, cell1: u32) -> f32 {
+    cell1 = (u32((particle.position.x * 8.0)) & 7u);
+    return select(0.0, particle.mass, (particle.mass > 0.0));
+}
+
+[[stage(compute), workgroup_size(64, 1, 1)]]
+fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>, [[builtin(local_invocation_index)]] local_index: u32) {
+    var count: u32;
+    var stride: u32;
+    var cell: u32 = 0u;
+    var mass: f32 = 0.0;
+    var local: u32;
+    var offset: u32 = 32u;
+
+    count = arrayLength(&particles.data);
+    stride = 16u;
+    let _e14: u32 = count;
+    if ((global_id.x < _e14)) {
+        let _e20: Particle = particles.data[global_id.x];
+        let _e22: f32 = weigh(_e20, local);
+        let _e23: u32 = local;
+        cell = _e23;
+        mass = _e22;
+    }
+    let _e26: f32 = mass;
+    shared_mass[local_index] = _e26;
+    workgroupBarrier();
+    loop {
+        let _e31: u32 = offset;
+        if ((_e31 > 0u)) {
+        } else {
+            break;
+        }
+        let _e38: u32 = offset;
+        if ((local_index < _e38)) {
+            let _e43: u32 = offset;
+            let _e46: f32 = shared_mass[(local_index + _e43)];
+            let _e47: f32 = shared_mass[local_index];
+            shared_mass[local_index] = (_e47 + _e46);
+        }
+        workgroupBarrier();
+        continuing {
+            let _e35: u32 = offset;
+            offset = (_e35 >> 1u);
+        }
+    }
+    let _e49: u32 = cell;
+    switch(i32(_e49)) {
+        case 0: {
+            let _e52: f32 = mass;
+            mass = (_e52 * 2.0);
+        }
+        case 1, 2: {
+            let _e54: f32 = mass;
+            mass = -(_e54);
+        }
+    }
+    if ((local_index == 0u)) {
+        let _e69: f32 = shared_mass[0];
+        totals.data[(global_id.x / 64u)] = _e69;
+    }
+    let _e75: u32 = cell;
+    let _e78: vec2<i32> = vec2<i32>(i32(_e75), i32((global_id.x % 8u)));
+    let _e79: vec4<f32> = textureLoad(heat_map, _e78);
+    let _e83: f32 = mass;
+    textureStore(heat_map, _e78, vec4<f32>(_e83, 0.0, 0.0, 1.0));
+    return;
+}
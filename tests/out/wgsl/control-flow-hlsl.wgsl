@@ -0,0 +1,85 @@
+let c_limit: i32 = 8;
+
+fn collatz(n: u32) -> i32 {
+    var n1: u32;
+    var steps: i32 = 0;
+
+    n1 = n;
+    loop {
+        let _e5: u32 = n1;
+        if ((_e5 != 1u)) {
+        } else {
+            break;
+        }
+        let _e9: u32 = n1;
+        if (((_e9 % 2u) == 0u)) {
+            let _e16: u32 = n1;
+            n1 = (_e16 / 2u);
+        } else {
+            let _e20: u32 = n1;
+            n1 = ((3u * _e20) + 1u);
+        }
+        let _e26: i32 = steps;
+        steps = (_e26 + 1);
+        let _e30: i32 = steps;
+        if ((_e30 > c_limit)) {
+            break;
+        }
+    }
+    let _e32: i32 = steps;
+    return _e32;
+}
+
+fn accumulate(sum1: vec4<f32>, value: vec3<f32>) {
+    let _e2: vec4<f32> = sum1;
+    let _e4: vec3<f32> = (_e2.xyz + value);
+    sum1.x = _e4.x;
+    sum1.y = _e4.y;
+    sum1.z = _e4.z;
+    let _e14: f32 = sum1.w;
+    sum1.w = max(_e14, length(value));
+    return;
+}
+
+fn reduce(basis1: mat3x2<f32>, flip: bool) -> vec4<f32> {
+    var sum2: vec4<f32> = vec4<f32>(0.0, 0.0, 0.0, 0.0);
+    var i: i32 = 0;
+    var local: vec4<f32>;
+    var axis: vec3<f32>;
+
+    loop {
+        let _e8: i32 = i;
+        let _e17: vec4<f32> = sum2;
+        local = _e17;
+        accumulate(local, vec3<f32>(basis1[0][_e8], basis1[1][_e8], basis1[2][_e8]));
+        let _e18: vec4<f32> = local;
+        sum2 = _e18;
+        let _e20: i32 = i;
+        i = (_e20 + 1);
+        let _e23: i32 = i;
+        if ((_e23 < 2)) {
+        } else {
+            break;
+        }
+    }
+    axis = select(vec3<f32>(basis1[0].y, basis1[1].y, basis1[2].y), -(vec3<f32>(basis1[0].x, basis1[1].x, basis1[2].x)), flip);
+    let _e53: vec3<f32> = axis;
+    sum2.x = dot(_e53, normalize(vec3<f32>(1.0, 2.0, 3.0)));
+    let _e55: vec4<f32> = sum2;
+    return _e55;
+}
+
+[[stage(fragment)]]
+fn main([[builtin(position)]] position: vec4<f32>, [[location(0)]] id: u32) -> [[location(0)]] vec4<f32> {
+    var basis: mat3x2<f32> = mat3x2<f32>(vec2<f32>(1.0, 0.0), vec2<f32>(0.0, 1.0), vec2<f32>(0.0, 0.0));
+    var sum: vec4<f32>;
+
+    let _e16: mat3x2<f32> = basis;
+    let _e17: vec4<f32> = reduce(_e16, (id > 3u));
+    sum = _e17;
+    let _e19: i32 = collatz(id);
+    let _e22: f32 = dpdx(position.x);
+    sum.z = (f32(_e19) + _e22);
+    let _e35: vec4<f32> = sum;
+    return mix(_e35, vec4<f32>(1.0, 1.0, 1.0, 1.0), vec4<f32>(fract(position.y)));
+}
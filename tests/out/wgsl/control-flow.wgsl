@@ -1,6 +1,85 @@
+fn switch_default_break(i: i32) {
+    switch(i) {
+        default: {
+            break;
+        }
+    }
+}
+
+fn switch_case_break() {
+    switch(0) {
+        case 0: {
+            break;
+        }
+    }
+    return;
+}
+
+fn switch_fallthrough_default(x: i32) -> i32 {
+    var res: i32 = 0;
+
+    switch(x) {
+        case 0: {
+            res = 1;
+            fallthrough;
+        }
+        case 1: {
+            let _e4: i32 = res;
+            res = (_e4 + 2);
+            fallthrough;
+        }
+        default: {
+            let _e7: i32 = res;
+            res = (_e7 * 3);
+        }
+    }
+    let _e10: i32 = res;
+    return _e10;
+}
+
+fn switch_empty_fallthrough(x1: i32) -> i32 {
+    switch(x1) {
+        case 0: {
+            fallthrough;
+        }
+        default: {
+        }
+    }
+    return x1;
+}
+
 [[stage(compute), workgroup_size(1, 1, 1)]]
 fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>) {
+    var pos: i32;
+
     storageBarrier();
     workgroupBarrier();
-    return;
+    switch(1) {
+        default: {
+            pos = 1;
+        }
+    }
+    let _e4: i32 = pos;
+    switch(_e4) {
+        case 1: {
+            pos = 0;
+            break;
+        }
+        case 2: {
+            pos = 1;
+            return;
+        }
+        case 3, 4: {
+            pos = 2;
+            fallthrough;
+        }
+        case 5: {
+            pos = 3;
+            return;
+        }
+        default: {
+            pos = 4;
+            return;
+        }
+    }
 }
@@ -42,6 +42,12 @@ fn main1() {
     var outerProductOut: mat4x4<f32>;
     var distanceOut: f32;
     var stepOut: vec4<f32>;
+    var bitfieldExtractOut: i32;
+    var bitfieldInsertOut: i32;
+    var findLSBOut: i32;
+    var findMSBOut: i32;
+    var packOut: u32;
+    var unpackOut: vec4<f32>;
 
     let _e6: vec4<f32> = a;
     let _e7: vec4<f32> = b;
@@ -137,6 +143,20 @@ fn main1() {
     let _e145: vec4<f32> = a;
     let _e146: vec4<f32> = b;
     stepOut = step(_e145, _e146);
+    let _e149: i32 = i;
+    bitfieldExtractOut = extractBits(_e149, u32(1), u32(2));
+    let _e156: i32 = i;
+    let _e157: i32 = i;
+    bitfieldInsertOut = insertBits(_e156, _e157, u32(1), u32(2));
+    let _e164: i32 = i;
+    findLSBOut = i32(firstTrailingBit(u32(_e164)));
+    let _e169: i32 = i;
+    findMSBOut = firstLeadingBit(_e169);
+    let _e172: vec4<f32> = a;
+    let _e174: vec4<f32> = a;
+    packOut = (pack4x8unorm(_e172) + pack2x16float(_e174.xy));
+    let _e179: u32 = packOut;
+    unpackOut = unpack4x8snorm(_e179);
     return;
 }
 
@@ -0,0 +1,26 @@
+[[stage(fragment)]]
+fn main([[location(0)]] v: vec4<f32>, [[location(1)]] s: f32) -> [[location(0)]] vec4<f32> {
+    var whole: f32;
+    var exponent: i32;
+
+    let outer: mat2x2<f32> = outerProduct(v.xy, v.zw);
+    let outer_tall: mat2x3<f32> = outerProduct(v.xyz, v.zw);
+    let normal: vec4<bool> = isNormal(v);
+    let finite: bool = ((isFinite(s) && !(isNan(s))) && !(isInf(s)));
+    let bits: u32 = (countOneBits(u32(s)) + reverseBits(u32(s)));
+    let facing: vec4<f32> = faceForward(v, v, v);
+    let det: f32 = determinant(mat2x2<f32>(v.xy, v.zw));
+    let inv: mat2x2<f32> = inverse(mat2x2<f32>(v.xy, v.zw));
+    let inv3_: mat3x3<f32> = inverse(mat3x3<f32>(v.xyz, v.yzw, v.zwx));
+    let inv4_: mat4x4<f32> = inverse(mat4x4<f32>(v, v.yzwx, v.zwxy, v.wxyz));
+    let fraction: f32 = modf(s, &whole);
+    let mantissa: f32 = frexp(s, &exponent);
+    let _e44: i32 = exponent;
+    let scaled: f32 = ldexp(mantissa, _e44);
+    let refracted: vec3<f32> = refract(v.xyz, normalize(v.zyx), fraction);
+    if ((((all(normal) && any(normal)) && finite) && isNormal(s))) {
+        let _e67: f32 = whole;
+        return ((((((outer[1].xyxy + outer_tall[1].xyzx) + (facing * det)) + vec4<f32>(inv[0], _e67, scaled)) + inv3_[1].xyzx) + inv4_[2]) + refracted.xyzx);
+    }
+    return vec4<f32>(f32(bits));
+}
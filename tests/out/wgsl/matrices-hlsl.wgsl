@@ -0,0 +1,53 @@
+struct Basis {
+    r: mat3x2<f32>;
+    scale: f32;
+};
+
+fn update(m1: mat3x3<f32>, i: i32) -> mat3x3<f32> {
+    var m2: mat3x3<f32>;
+
+    m2 = m1;
+    let _e4: mat3x3<f32> = m2;
+    m2[2][1] = 3.0;
+    let _e16: mat3x3<f32> = m2;
+    m2[0][i] = 2.0;
+    let _e29: mat3x3<f32> = m2;
+    let _e40: vec3<f32> = vec3<f32>(4.0, 5.0, 6.0);
+    m2[0][2] = _e40.x;
+    m2[1][2] = _e40.y;
+    m2[2][2] = _e40.z;
+    let _e51: mat3x3<f32> = m2;
+    let _e62: vec2<f32> = vec2<f32>(7.0, 8.0);
+    m2[1][0] = _e62.x;
+    m2[0][0] = _e62.y;
+    let _e70: mat3x3<f32> = m2;
+    let _e81: f32 = m2[2][2];
+    m2[2][2] = (_e81 + 1.0);
+    let _e83: mat3x3<f32> = m2;
+    let _e95: mat3x3<f32> = m2;
+    let _e106: f32 = m2[1][1];
+    m2[2][i] = _e106;
+    let _e107: mat3x3<f32> = m2;
+    return _e107;
+}
+
+[[stage(fragment)]]
+fn main([[builtin(position)]] position: vec4<f32>) -> [[location(0)]] vec4<f32> {
+    var s: Basis;
+    var m: mat3x3<f32>;
+
+    s.r = mat3x2<f32>(vec2<f32>(1.0, 0.0), vec2<f32>(0.0, 1.0), vec2<f32>(0.0, 0.0));
+    s.scale = 2.0;
+    let _e17: mat3x2<f32> = s.r;
+    s.r[2][1] = 1.0;
+    let _e31: mat3x2<f32> = s.r;
+    let _e42: vec2<f32> = vec2<f32>(position.x, position.y);
+    s.r[2][0] = _e42.x;
+    s.r[0][0] = _e42.y;
+    let _e64: mat3x3<f32> = update(mat3x3<f32>(vec3<f32>(1.0, 0.0, 0.0), vec3<f32>(0.0, 1.0, 0.0), vec3<f32>(0.0, 0.0, 1.0)), 1);
+    m = _e64;
+    let _e66: mat3x3<f32> = m;
+    let _e76: mat3x2<f32> = s.r;
+    let _e85: f32 = s.scale;
+    return vec4<f32>((vec3<f32>(_e66[0].x, _e66[1].x, _e66[2].x) + (vec3<f32>(_e76[0].y, _e76[1].y, _e76[2].y) * vec3<f32>(_e85))), 1.0);
+}
@@ -0,0 +1,50 @@
+[[block]]
+struct Globals {
+    transform: mat4x4<f32>;
+    tint: vec4<f32>;
+};
+
+struct VertexInput {
+    [[location(0)]] position: vec2<f32>;
+    [[location(1)]] uv: vec2<f32>;
+};
+
+struct VertexOutput {
+    [[builtin(position)]] position: vec4<f32>;
+    [[location(1)]] uv: vec2<f32>;
+};
+
+let c_scale: f32 = 1.2;
+
+[[group(0), binding(0)]]
+var<uniform> Globals1: Globals;
+[[group(0), binding(96)]]
+var color_texture: texture_2d<f32>;
+[[group(0), binding(16)]]
+var color_sampler: sampler;
+
+[[stage(vertex)]]
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+
+    let _e12: mat4x4<f32> = Globals1.transform;
+    output.position = (_e12 * vec4<f32>((vec2<f32>(c_scale) * input.position), 0.0, 1.0));
+    output.uv = input.uv;
+    let _e16: VertexOutput = output;
+    return _e16;
+}
+
+[[stage(fragment)]]
+fn ps_main(input1: VertexOutput) -> [[location(0)]] vec4<f32> {
+    var color: vec4<f32>;
+
+    let _e5: vec4<f32> = textureSample(color_texture, color_sampler, input1.uv);
+    color = _e5;
+    let _e8: f32 = color.w;
+    if (((_e8 - 0.5) < 0.0)) {
+        discard;
+    }
+    let _e14: vec4<f32> = color;
+    let _e15: vec4<f32> = Globals1.tint;
+    return clamp((_e14 * _e15), vec4<f32>(0.0), vec4<f32>(1.0));
+}
@@ -0,0 +1,19 @@
+[[override(0)]] let has_point_light: bool = true;
+
+[[override(1200)]] let gain: f32 = 3.0;
+
+[[override(1300)]] let count: i32 = 4;
+
+[[stage(compute), workgroup_size(1, 1, 1)]]
+fn main() {
+    var t: f32;
+
+    t = (gain * 2.0);
+    if (has_point_light) {
+        let _e6: f32 = t;
+        t = (_e6 + f32(count));
+        return;
+    } else {
+        return;
+    }
+}
@@ -0,0 +1,92 @@
+[[block]]
+struct Data {
+    values: [[stride(4)]] array<u32>;
+};
+
+[[group(0), binding(0)]]
+var<storage> global: [[access(read_write)]] Data;
+var<private> gl_SubgroupInvocationID: u32;
+var<private> gl_SubgroupSize: u32;
+
+fn main1() {
+    var id: u32;
+    var ballot: vec4<u32>;
+    var all_set: bool;
+    var any_set: bool;
+    var sum: u32;
+    var bits: u32;
+    var scans: u32;
+    var gathered: u32;
+
+    let _e3: u32 = gl_SubgroupInvocationID;
+    id = _e3;
+    let _e5: u32 = id;
+    let _e10: vec4<u32> = subgroupBallot(((_e5 & 1u) == 1u));
+    ballot = _e10;
+    let _e12: u32 = id;
+    let _e15: bool = subgroupAll((_e12 != 0u));
+    all_set = _e15;
+    let _e17: u32 = id;
+    let _e20: bool = subgroupAny((_e17 == 0u));
+    any_set = _e20;
+    let _e22: u32 = id;
+    let _e23: u32 = subgroupAdd(_e22);
+    let _e24: u32 = id;
+    let _e25: u32 = subgroupMul(_e24);
+    let _e27: u32 = id;
+    let _e28: u32 = subgroupMin(_e27);
+    let _e30: u32 = id;
+    let _e31: u32 = subgroupMax(_e30);
+    sum = (((_e23 + _e25) + _e28) + _e31);
+    let _e34: u32 = id;
+    let _e35: u32 = subgroupAnd(_e34);
+    let _e36: u32 = id;
+    let _e37: u32 = subgroupOr(_e36);
+    let _e38: u32 = id;
+    let _e39: u32 = subgroupXor(_e38);
+    bits = (_e35 | (_e37 ^ _e39));
+    let _e43: u32 = id;
+    let _e44: u32 = subgroupInclusiveAdd(_e43);
+    let _e45: u32 = id;
+    let _e46: u32 = subgroupExclusiveMul(_e45);
+    scans = (_e44 + _e46);
+    let _e50: u32 = id;
+    let _e51: u32 = subgroupBroadcastFirst(_e50);
+    let _e52: u32 = id;
+    let _e54: u32 = subgroupBroadcast(_e52, 4u);
+    let _e56: u32 = id;
+    let _e57: u32 = gl_SubgroupSize;
+    let _e60: u32 = id;
+    let _e62: u32 = subgroupShuffle(_e56, ((_e57 - 1u) - _e60));
+    let _e64: u32 = id;
+    let _e66: u32 = subgroupShuffleXor(_e64, 1u);
+    let _e68: u32 = id;
+    let _e70: u32 = subgroupShuffleUp(_e68, 1u);
+    let _e72: u32 = id;
+    let _e74: u32 = subgroupShuffleDown(_e72, 1u);
+    gathered = (((((_e51 + _e54) + _e62) + _e66) + _e70) + _e74);
+    let _e77: bool = all_set;
+    let _e78: bool = any_set;
+    if ((_e77 || _e78)) {
+        {
+            let _e80: u32 = id;
+            let _e82: vec4<u32> = ballot;
+            let _e84: u32 = sum;
+            let _e86: u32 = bits;
+            let _e88: u32 = scans;
+            let _e90: u32 = gathered;
+            global.values[_e80] = ((((_e82.x + _e84) + _e86) + _e88) + _e90);
+            return;
+        }
+    } else {
+        return;
+    }
+}
+
+[[stage(compute), workgroup_size(64, 1, 1)]]
+fn main([[builtin(subgroup_invocation_id)]] param: u32, [[builtin(subgroup_size)]] param1: u32) {
+    gl_SubgroupInvocationID = param;
+    gl_SubgroupSize = param1;
+    main1();
+    return;
+}
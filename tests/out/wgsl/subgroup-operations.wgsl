@@ -0,0 +1,40 @@
+[[block]]
+struct Data {
+    values: [[stride(4)]] array<u32>;
+};
+
+[[group(0), binding(0)]]
+var<storage> data: [[access(read_write)]] Data;
+
+[[stage(compute), workgroup_size(64, 1, 1)]]
+fn main([[builtin(subgroup_size)]] size: u32, [[builtin(subgroup_invocation_id)]] id: u32) {
+    let _e7: vec4<u32> = subgroupBallot(((id & 1u) == 1u));
+    let _e8: vec4<u32> = subgroupBallot();
+    let _e11: bool = subgroupAll((id != 0u));
+    let _e14: bool = subgroupAny((id == 0u));
+    let _e15: u32 = subgroupAdd(id);
+    let _e16: u32 = subgroupMul(id);
+    let _e18: u32 = subgroupMin(id);
+    let _e20: u32 = subgroupMax(id);
+    let reduced: u32 = (((_e15 + _e16) + _e18) + _e20);
+    let _e22: u32 = subgroupAnd(id);
+    let _e23: u32 = subgroupOr(id);
+    let _e24: u32 = subgroupXor(id);
+    let bits: u32 = (_e22 | (_e23 ^ _e24));
+    let _e27: u32 = subgroupInclusiveAdd(id);
+    let _e28: u32 = subgroupExclusiveMul(id);
+    let scans: u32 = (_e27 + _e28);
+    let _e30: u32 = subgroupBroadcastFirst(id);
+    let _e32: u32 = subgroupBroadcast(id, 4u);
+    let _e37: u32 = subgroupShuffle(id, ((size - 1u) - id));
+    let _e40: u32 = subgroupShuffleXor(id, 1u);
+    let _e43: u32 = subgroupShuffleUp(id, 1u);
+    let _e46: u32 = subgroupShuffleDown(id, 1u);
+    let gathered: u32 = (((((_e30 + _e32) + _e37) + _e40) + _e43) + _e46);
+    if ((_e11 || _e14)) {
+        data.values[id] = (((((_e7.x + _e8.y) + reduced) + bits) + scans) + gathered);
+        return;
+    } else {
+        return;
+    }
+}
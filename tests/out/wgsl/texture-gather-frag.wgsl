@@ -0,0 +1,27 @@
+struct FragmentOutput {
+    [[location(0)]] color: vec4<f32>;
+};
+
+[[group(0), binding(0)]]
+var tex: texture_2d<f32>;
+[[group(0), binding(1)]]
+var samp: sampler;
+var<private> uv1: vec2<f32>;
+var<private> color: vec4<f32>;
+
+fn main1() {
+    let _e4: vec2<f32> = uv1;
+    let _e5: vec4<f32> = textureGather(0, tex, samp, _e4);
+    let _e6: vec2<f32> = uv1;
+    let _e8: vec4<f32> = textureGather(2, tex, samp, _e6);
+    color = (_e5 + _e8);
+    return;
+}
+
+[[stage(fragment)]]
+fn main([[location(0)]] uv: vec2<f32>) -> FragmentOutput {
+    uv1 = uv;
+    main1();
+    let _e3: vec4<f32> = color;
+    return FragmentOutput(_e3);
+}
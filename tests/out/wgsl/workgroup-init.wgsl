@@ -0,0 +1,53 @@
+struct Histogram {
+    total: atomic<u32>;
+    bins: [[stride(4)]] array<atomic<u32>,4>;
+};
+
+var<workgroup> histogram: Histogram;
+var<workgroup> samples: array<vec2<f32>,128>;
+var<workgroup> scale: f32;
+
+[[stage(compute), workgroup_size(64, 1, 1)]]
+fn main([[builtin(local_invocation_id)]] local_id: vec3<u32>, [[builtin(local_invocation_index)]] local_invocation_index: u32) {
+    var local: u32;
+    var local1: u32;
+
+    if ((local_invocation_index == 0u)) {
+        atomicStore(&histogram.total, 0u);
+    }
+    local = local_invocation_index;
+    loop {
+        let _e31: u32 = local;
+        if ((_e31 >= 4u)) {
+            break;
+        }
+        atomicStore(&histogram.bins[_e31], 0u);
+        continuing {
+            let _e36: u32 = local;
+            local = (_e36 + 64u);
+        }
+    }
+    local1 = local_invocation_index;
+    loop {
+        let _e41: u32 = local1;
+        if ((_e41 >= 128u)) {
+            break;
+        }
+        samples[_e41] = vec2<f32>(0.0, 0.0);
+        continuing {
+            let _e46: u32 = local1;
+            local1 = (_e46 + 64u);
+        }
+    }
+    if ((local_invocation_index == 1u)) {
+        scale = 0.0;
+    }
+    workgroupBarrier();
+    samples[local_id.x] = vec2<f32>(f32(local_id.x));
+    if ((local_id.x == 0u)) {
+        scale = 2.0;
+    }
+    let _e15: u32 = atomicAdd(&histogram.total, 1u);
+    let _e22: u32 = atomicAdd(&histogram.bins[(local_id.x % 4u)], 1u);
+    return;
+}
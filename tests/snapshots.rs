@@ -23,6 +23,8 @@ bitflags::bitflags! {
 struct Parameters {
     #[serde(default)]
     god_mode: bool,
+    #[serde(default)]
+    zero_initialize_workgroup_memory: bool,
 
     // We can only deserialize `IndexBoundsCheckPolicy` values if `deserialize`
     // feature was enabled, but features should not affect snapshot contents, so
@@ -72,8 +74,21 @@ struct Parameters {
     hlsl_custom: bool,
 }
 
+impl Parameters {
+    #[allow(dead_code)]
+    fn index_bounds_check_policy(&self) -> naga::back::IndexBoundsCheckPolicy {
+        if self.bounds_check_restrict {
+            naga::back::IndexBoundsCheckPolicy::Restrict
+        } else if self.bounds_check_read_zero_skip_write {
+            naga::back::IndexBoundsCheckPolicy::ReadZeroSkipWrite
+        } else {
+            naga::back::IndexBoundsCheckPolicy::UndefinedBehavior
+        }
+    }
+}
+
 #[allow(dead_code, unused_variables)]
-fn check_targets(module: &naga::Module, name: &str, targets: Targets) {
+fn check_targets(module: &mut naga::Module, name: &str, targets: Targets) {
     let root = env!("CARGO_MANIFEST_DIR");
     let params = match fs::read_to_string(format!("{}/{}/{}.param.ron", root, BASE_DIR_IN, name)) {
         Ok(string) => ron::de::from_str(&string).expect("Couldn't find param file"),
@@ -82,6 +97,9 @@ fn check_targets(module: &naga::Module, name: &str, targets: Targets) {
     if params.bounds_check_restrict && params.bounds_check_read_zero_skip_write {
         panic!("select only one bounds check policy");
     }
+    if params.zero_initialize_workgroup_memory {
+        naga::proc::zero_initialize_workgroup_memory(module);
+    }
     let capabilities = if params.god_mode {
         naga::valid::Capabilities::all()
     } else {
@@ -174,22 +192,23 @@ fn write_output_spv(
         } else {
             Some(params.spv_capabilities.clone())
         },
-        index_bounds_check_policy: if params.bounds_check_restrict {
-            naga::back::IndexBoundsCheckPolicy::Restrict
-        } else if params.bounds_check_read_zero_skip_write {
-            naga::back::IndexBoundsCheckPolicy::ReadZeroSkipWrite
-        } else {
-            naga::back::IndexBoundsCheckPolicy::UndefinedBehavior
-        },
+        index_bounds_check_policy: params.index_bounds_check_policy(),
         ..spv::Options::default()
     };
 
     let spv = spv::write_vec(module, info, &options).unwrap();
 
-    let dis = rspirv::dr::load_words(spv)
+    let dis = rspirv::dr::load_words(&spv)
         .expect("Produced invalid SPIR-V")
         .disassemble();
 
+    // the disassembly assembles back into the same module, save for the generator
+    #[cfg(feature = "spv-in")]
+    {
+        let assembled = naga::front::spv::assemble(&dis).unwrap();
+        assert_eq!(assembled[3..], spv[3..]);
+    }
+
     fs::write(destination.join(format!("spv/{}.spvasm", file_name)), dis).unwrap();
 }
 
@@ -214,6 +233,10 @@ fn write_output_msl(
     } else {
         &default_options
     };
+    let options = &msl::Options {
+        index_bounds_check_policy: params.index_bounds_check_policy(),
+        ..options.clone()
+    };
 
     let pipeline_options = msl::PipelineOptions {
         allow_point_size: true,
@@ -253,6 +276,10 @@ fn write_output_glsl(
     } else {
         &default_options
     };
+    let options = &glsl::Options {
+        index_bounds_check_policy: params.index_bounds_check_policy(),
+        ..options.clone()
+    };
 
     let pipeline_options = glsl::PipelineOptions {
         shader_stage: stage,
@@ -293,6 +320,10 @@ fn write_output_hlsl(
     } else {
         &default_options
     };
+    let options = &hlsl::Options {
+        index_bounds_check_policy: params.index_bounds_check_policy(),
+        ..options.clone()
+    };
 
     let mut buffer = String::new();
     let mut writer = hlsl::Writer::new(&mut buffer, options);
@@ -368,7 +399,7 @@ fn convert_wgsl() {
         ),
         (
             "boids",
-            Targets::SPIRV | Targets::METAL | Targets::GLSL | Targets::WGSL,
+            Targets::SPIRV | Targets::METAL | Targets::GLSL | Targets::HLSL | Targets::WGSL,
         ),
         (
             "skybox",
@@ -376,7 +407,12 @@ fn convert_wgsl() {
         ),
         (
             "collatz",
-            Targets::SPIRV | Targets::METAL | Targets::IR | Targets::ANALYSIS | Targets::WGSL,
+            Targets::SPIRV
+                | Targets::METAL
+                | Targets::IR
+                | Targets::ANALYSIS
+                | Targets::HLSL
+                | Targets::WGSL,
         ),
         (
             "shadow",
@@ -386,7 +422,10 @@ fn convert_wgsl() {
             "image",
             Targets::SPIRV | Targets::METAL | Targets::HLSL | Targets::WGSL,
         ),
-        ("extra", Targets::SPIRV | Targets::METAL | Targets::WGSL),
+        (
+            "extra",
+            Targets::SPIRV | Targets::METAL | Targets::HLSL | Targets::WGSL,
+        ),
         (
             "operators",
             Targets::SPIRV | Targets::METAL | Targets::GLSL | Targets::HLSL | Targets::WGSL,
@@ -395,7 +434,10 @@ fn convert_wgsl() {
             "interpolate",
             Targets::SPIRV | Targets::METAL | Targets::GLSL | Targets::HLSL | Targets::WGSL,
         ),
-        ("access", Targets::SPIRV | Targets::METAL | Targets::WGSL),
+        (
+            "access",
+            Targets::SPIRV | Targets::METAL | Targets::HLSL | Targets::WGSL,
+        ),
         (
             "control-flow",
             Targets::SPIRV | Targets::METAL | Targets::GLSL | Targets::HLSL | Targets::WGSL,
@@ -413,10 +455,57 @@ fn convert_wgsl() {
             "globals",
             Targets::SPIRV | Targets::METAL | Targets::GLSL | Targets::HLSL | Targets::WGSL,
         ),
-        ("bounds-check-zero", Targets::SPIRV),
+        (
+            "bounds-check-zero",
+            Targets::SPIRV | Targets::METAL | Targets::GLSL | Targets::HLSL,
+        ),
+        (
+            "bounds-check-restrict",
+            Targets::SPIRV | Targets::METAL | Targets::GLSL | Targets::HLSL,
+        ),
+        (
+            "bounds-check-image-restrict",
+            Targets::METAL | Targets::GLSL | Targets::HLSL,
+        ),
+        (
+            "bounds-check-image-rzsw",
+            Targets::METAL | Targets::GLSL | Targets::HLSL,
+        ),
         (
             "texture-arg",
-            Targets::SPIRV | Targets::METAL | Targets::GLSL | Targets::WGSL,
+            Targets::SPIRV | Targets::METAL | Targets::GLSL | Targets::HLSL | Targets::WGSL,
+        ),
+        (
+            "atomics",
+            Targets::SPIRV | Targets::METAL | Targets::GLSL | Targets::HLSL | Targets::WGSL,
+        ),
+        (
+            "specialization",
+            Targets::SPIRV | Targets::METAL | Targets::GLSL | Targets::HLSL | Targets::WGSL,
+        ),
+        (
+            "math-functions",
+            Targets::METAL | Targets::GLSL | Targets::HLSL | Targets::WGSL,
+        ),
+        (
+            "subgroup-operations",
+            Targets::SPIRV | Targets::METAL | Targets::GLSL | Targets::HLSL | Targets::WGSL,
+        ),
+        (
+            "binding-arrays",
+            Targets::SPIRV | Targets::METAL | Targets::GLSL | Targets::HLSL | Targets::WGSL,
+        ),
+        (
+            "texture-gather",
+            Targets::SPIRV | Targets::METAL | Targets::GLSL | Targets::HLSL | Targets::WGSL,
+        ),
+        (
+            "bits",
+            Targets::SPIRV | Targets::METAL | Targets::GLSL | Targets::HLSL | Targets::WGSL,
+        ),
+        (
+            "workgroup-init",
+            Targets::SPIRV | Targets::METAL | Targets::GLSL | Targets::HLSL | Targets::WGSL,
         ),
     ];
 
@@ -426,7 +515,7 @@ fn convert_wgsl() {
         let file = fs::read_to_string(format!("{}/{}/{}.wgsl", root, BASE_DIR_IN, name))
             .expect("Couldn't find wgsl file");
         match naga::front::wgsl::parse_str(&file) {
-            Ok(module) => check_targets(&module, name, targets),
+            Ok(mut module) => check_targets(&mut module, name, targets),
             Err(e) => panic!("{}", e),
         }
     }
@@ -437,7 +526,7 @@ fn convert_spv(name: &str, adjust_coordinate_space: bool, targets: Targets) {
     let _ = env_logger::try_init();
 
     let root = env!("CARGO_MANIFEST_DIR");
-    let module = naga::front::spv::parse_u8_slice(
+    let mut module = naga::front::spv::parse_u8_slice(
         &fs::read(format!("{}/{}/spv/{}.spv", root, BASE_DIR_IN, name))
             .expect("Couldn't find spv file"),
         &naga::front::spv::Options {
@@ -447,7 +536,7 @@ fn convert_spv(name: &str, adjust_coordinate_space: bool, targets: Targets) {
         },
     )
     .unwrap();
-    check_targets(&module, name, targets);
+    check_targets(&mut module, name, targets);
     naga::valid::Validator::new(
         naga::valid::ValidationFlags::all(),
         naga::valid::Capabilities::empty(),
@@ -556,3 +645,36 @@ fn convert_glsl_folder() {
         }
     }
 }
+
+#[cfg(feature = "hlsl-in")]
+#[test]
+fn convert_hlsl_folder() {
+    let _ = env_logger::try_init();
+
+    let root = env!("CARGO_MANIFEST_DIR");
+
+    for entry in std::fs::read_dir(format!("{}/{}/hlsl", root, BASE_DIR_IN)).unwrap() {
+        let entry = entry.unwrap();
+        let file_name = entry.file_name().into_string().unwrap();
+        println!("Processing {}", file_name);
+
+        let source = fs::read_to_string(entry.path()).expect("Couldn't find hlsl file");
+        let module = match naga::front::hlsl::parse_str(&source, &Default::default()) {
+            Ok(module) => module,
+            Err(e) => panic!("{}", e.emit_to_string(&source)),
+        };
+
+        let info = naga::valid::Validator::new(
+            naga::valid::ValidationFlags::all(),
+            naga::valid::Capabilities::all(),
+        )
+        .validate(&module)
+        .unwrap();
+
+        #[cfg(feature = "wgsl-out")]
+        {
+            let dest = PathBuf::from(root).join(BASE_DIR_OUT);
+            write_output_wgsl(&module, &info, &dest, &file_name.replace(".", "-"));
+        }
+    }
+}
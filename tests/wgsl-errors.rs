@@ -5,8 +5,12 @@ fn check(input: &str, snapshot: &str) {
     let output = naga::front::wgsl::parse_str(input)
         .expect_err("expected parser error")
         .emit_to_string(input);
+    compare_snapshot(&output, snapshot);
+}
+
+fn compare_snapshot(output: &str, snapshot: &str) {
     if output != snapshot {
-        for diff in diff::lines(&output, snapshot) {
+        for diff in diff::lines(output, snapshot) {
             match diff {
                 diff::Result::Left(l) => println!("-{}", l),
                 diff::Result::Both(l, _) => println!(" {}", l),
@@ -241,6 +245,22 @@ fn unknown_attribute() {
     );
 }
 
+#[test]
+fn non_scalar_override() {
+    check(
+        r#"
+            [[override(0)]] let v: vec2<f32> = vec2<f32>(1.0, 2.0);
+        "#,
+        r#"error: overridable constant `v` must be a scalar
+  ┌─ wgsl:2:33
+  │
+2 │             [[override(0)]] let v: vec2<f32> = vec2<f32>(1.0, 2.0);
+  │                                 ^ definition of `v`
+
+"#,
+    );
+}
+
 #[test]
 fn unknown_built_in() {
     check(
@@ -511,6 +531,28 @@ fn local_var_missing_type() {
     );
 }
 
+#[test]
+fn invalid_gather_component() {
+    check(
+        r#"
+            [[group(0), binding(0)]] var t: texture_2d<f32>;
+            [[group(0), binding(1)]] var s: sampler;
+            fn main() -> vec4<f32> {
+                return textureGather(4, t, s, vec2<f32>(0.5));
+            }
+        "#,
+        r###"error: invalid gather component `4`
+  ┌─ wgsl:5:38
+  │
+5 │                 return textureGather(4, t, s, vec2<f32>(0.5));
+  │                                      ^ invalid component
+  │
+  = note: valid components are 0, 1, 2 and 3
+
+"###,
+    );
+}
+
 macro_rules! check_validation_error {
     // We want to support an optional guard expression after the pattern, so
     // that we can check values we can't match against, like strings.
@@ -560,6 +602,7 @@ fn validation_error(source: &str) -> Result<naga::valid::ModuleInfo, naga::valid
         naga::valid::Capabilities::empty(),
     )
     .validate(&module)
+    .map_err(|e| e.into_inner())
 }
 
 #[test]
@@ -820,3 +863,191 @@ fn invalid_local_vars() {
         if local_var_name == "not_okay"
     }
 }
+
+#[test]
+fn missing_subgroup_capability() {
+    check_validation_error! {
+        "
+        fn main(value: u32) -> u32 {
+            return subgroupAdd(value);
+        }
+        ":
+        Err(naga::valid::ValidationError::Function {
+            error: naga::valid::FunctionError::InvalidSubgroup(
+                naga::valid::SubgroupError::UnsupportedCapability(_),
+            ),
+            ..
+        })
+    }
+}
+
+#[test]
+fn invalid_gather() {
+    check_validation_error! {
+        r#"
+            [[group(0), binding(0)]] var t: texture_3d<f32>;
+            [[group(0), binding(1)]] var s: sampler;
+            fn main() -> vec4<f32> {
+                return textureGather(0, t, s, vec3<f32>(0.5));
+            }
+        "#:
+        Err(naga::valid::ValidationError::Function {
+            error: naga::valid::FunctionError::Expression {
+                error: naga::valid::ExpressionError::InvalidGatherDimension(_),
+                ..
+            },
+            ..
+        })
+    }
+
+    check_validation_error! {
+        r#"
+            [[group(0), binding(0)]] var t: texture_depth_2d;
+            [[group(0), binding(1)]] var s: sampler;
+            fn main() -> vec4<f32> {
+                return textureGather(1, t, s, vec2<f32>(0.5));
+            }
+        "#:
+        Err(naga::valid::ValidationError::Function {
+            error: naga::valid::FunctionError::Expression {
+                error: naga::valid::ExpressionError::InvalidGatherComponent(_),
+                ..
+            },
+            ..
+        })
+    }
+}
+
+#[test]
+fn invalid_bit_functions() {
+    check_validation_error! {
+        r#"
+            fn main() -> f32 {
+                return extractBits(1.0, 0u, 4u);
+            }
+        "#:
+        Err(naga::valid::ValidationError::Function {
+            error: naga::valid::FunctionError::Expression {
+                error: naga::valid::ExpressionError::InvalidArgumentType(
+                    naga::MathFunction::ExtractBits,
+                    0,
+                    _,
+                ),
+                ..
+            },
+            ..
+        })
+    }
+
+    check_validation_error! {
+        r#"
+            fn main() -> u32 {
+                return insertBits(1u, 2u, 0, 4u);
+            }
+        "#:
+        Err(naga::valid::ValidationError::Function {
+            error: naga::valid::FunctionError::Expression {
+                error: naga::valid::ExpressionError::InvalidArgumentType(
+                    naga::MathFunction::InsertBits,
+                    2,
+                    _,
+                ),
+                ..
+            },
+            ..
+        })
+    }
+
+    check_validation_error! {
+        r#"
+            fn main() -> u32 {
+                return pack4x8unorm(vec2<f32>(0.5));
+            }
+        "#:
+        Err(naga::valid::ValidationError::Function {
+            error: naga::valid::FunctionError::Expression {
+                error: naga::valid::ExpressionError::InvalidArgumentType(
+                    naga::MathFunction::Pack4x8unorm,
+                    0,
+                    _,
+                ),
+                ..
+            },
+            ..
+        })
+    }
+}
+
+#[test]
+fn missing_binding_array_capability() {
+    check_validation_error! {
+        "type Textures = binding_array<texture_2d<f32>, 4>;",
+        "type Samplers = binding_array<sampler>;":
+        Err(naga::valid::ValidationError::Type {
+            error: naga::valid::TypeError::MissingCapability(naga::valid::Capabilities::BINDING_ARRAY),
+            ..
+        })
+    }
+}
+
+#[cfg(feature = "span")]
+fn check_validation_spans(input: &str, snapshot: &str) {
+    let module = naga::front::wgsl::parse_str(input).unwrap();
+    let output = naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::empty(),
+    )
+    .validate(&module)
+    .expect_err("expected validation error")
+    .emit_to_string(input);
+    compare_snapshot(&output, snapshot);
+}
+
+#[cfg(feature = "span")]
+#[test]
+fn validation_error_spans() {
+    check_validation_spans(
+        r###"fn main() -> f32 {
+    let a = array<f32, 3>(0., 1., 2.);
+    return a[3];
+}
+"###,
+        r###"error: Function [1] 'main' is invalid
+  ┌─ wgsl:1:1
+  │  
+1 │ ╭ fn main() -> f32 {
+2 │ │     let a = array<f32, 3>(0., 1., 2.);
+3 │ │     return a[3];
+  │ │            ^^^^ Expression [6]
+4 │ │ }
+  │ ╰─^ Function [1]
+  │  
+  = Expression [6] is invalid
+  = Accessing index Uint(3) is out of [4] bounds
+
+"###,
+    );
+
+    check_validation_spans(
+        r###"fn main() {
+    if (1.0) {
+        return;
+    }
+}
+"###,
+        r###"error: Function [1] 'main' is invalid
+  ┌─ wgsl:1:1
+  │  
+1 │ ╭ fn main() {
+2 │ │     if (1.0) {
+  │ │         ^^^ Expression [1]
+3 │ │         return;
+4 │ │     }
+5 │ │ }
+  │ ╰─^ Function [1]
+  │  
+  = The `if` condition [1] is not a boolean scalar
+
+"###,
+    );
+}